@@ -0,0 +1,86 @@
+// 下载指定版本的 Deno, 作为 Tauri sidecar 放到 src-tauri/binaries 下
+// 文件名需带目标三元组后缀, 例如 deno-x86_64-pc-windows-msvc.exe
+// 用法: node scripts/fetch-deno.mjs [版本], 已存在时跳过
+import { execSync } from "node:child_process";
+import { chmodSync, existsSync, mkdirSync, writeFileSync } from "node:fs";
+import { dirname, join } from "node:path";
+import { fileURLToPath } from "node:url";
+import { inflateRawSync } from "node:zlib";
+
+const version = process.argv[2] ?? process.env.DENO_VERSION ?? "2.1.4";
+const root = join(dirname(fileURLToPath(import.meta.url)), "..");
+
+function fail(message) {
+  console.error(`错误：${message}`);
+  process.exit(1);
+}
+
+let triple;
+try {
+  triple = execSync("rustc -Vv", { encoding: "utf8" }).match(/^host: (\S+)$/m)?.[1];
+} catch {
+  // 下面统一报错
+}
+if (!triple) {
+  fail("无法获取 rustc 目标三元组，请先安装 Rust。");
+}
+
+const ext = triple.includes("windows") ? ".exe" : "";
+const binDir = join(root, "src-tauri", "binaries");
+const target = join(binDir, `deno-${triple}${ext}`);
+
+if (existsSync(target)) {
+  console.log(`已存在：${target}`);
+  process.exit(0);
+}
+
+// 按中央目录取出 zip 中的文件, 只支持未压缩与 deflate
+function extract(zip, name) {
+  const end = zip.lastIndexOf(Buffer.from([0x50, 0x4b, 0x05, 0x06]));
+  if (end < 0) {
+    return null;
+  }
+  const count = zip.readUInt16LE(end + 10);
+  let offset = zip.readUInt32LE(end + 16);
+  for (let i = 0; i < count; i++) {
+    const method = zip.readUInt16LE(offset + 10);
+    const size = zip.readUInt32LE(offset + 20);
+    const nameLength = zip.readUInt16LE(offset + 28);
+    const extraLength = zip.readUInt16LE(offset + 30);
+    const commentLength = zip.readUInt16LE(offset + 32);
+    const local = zip.readUInt32LE(offset + 42);
+    const entry = zip.toString("utf8", offset + 46, offset + 46 + nameLength);
+    if (entry === name) {
+      const start = local + 30 + zip.readUInt16LE(local + 26) + zip.readUInt16LE(local + 28);
+      const data = zip.subarray(start, start + size);
+      return method === 0 ? data : inflateRawSync(data);
+    }
+    offset += 46 + nameLength + extraLength + commentLength;
+  }
+  return null;
+}
+
+const url = `https://github.com/denoland/deno/releases/download/v${version}/deno-${triple}.zip`;
+const manual = `也可以手动下载 ${url}，解压后保存为 ${target}`;
+
+console.log(`下载 Deno v${version} (${triple})...`);
+let response;
+try {
+  response = await fetch(url);
+} catch (err) {
+  fail(`下载失败：${err.message}\n${manual}`);
+}
+if (!response.ok) {
+  fail(`下载失败 (HTTP ${response.status})\n${manual}`);
+}
+
+const binary = extract(Buffer.from(await response.arrayBuffer()), `deno${ext}`);
+if (!binary) {
+  fail(`压缩包中没有找到 deno${ext}\n${manual}`);
+}
+
+mkdirSync(binDir, { recursive: true });
+writeFileSync(target, binary);
+chmodSync(target, 0o755);
+
+console.log(`已保存到：${target}`);
@@ -1,3 +1,19 @@
+use std::path::Path;
+
 fn main() {
+    // Deno sidecar 不在仓库中, 缺失时提示获取方式, 而不是 tauri-build 的路径错误
+    let target = std::env::var("TARGET").unwrap();
+    let ext = if target.contains("windows") {
+        ".exe"
+    } else {
+        ""
+    };
+    let sidecar = format!("binaries/deno-{}{}", target, ext);
+    if !Path::new(&sidecar).exists() {
+        panic!(
+            "缺少 src-tauri/{}, 请在项目根目录运行 npm run fetch-deno 下载 Deno",
+            sidecar
+        );
+    }
     tauri_build::build()
-}
\ No newline at end of file
+}
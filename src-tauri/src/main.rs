@@ -1,22 +1,46 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use ghostie::plugins::{deno, knowledge};
-use ghostie::utils;
-use tauri::{
-    menu::{Menu, MenuItem},
-    tray::TrayIconBuilder,
-    Manager,
+use ghostie::plugins::{
+    agent, approval, artifacts, background, backup, batch, bots, builtin, bundle, cache, catalog,
+    chat_export, chat_import, chats, clipboard, db, deno, directory, embeddings, env, grants,
+    harness, history, i18n, ingest, install, kb_sync, knowledge, local, logs, mcp, mcp_server,
+    meta, notification, ocr, openapi, palette, profile, providers, proxy, quick_capture, registry,
+    reload, replay, runtime, schedule, screen, search, secrets, server, service, shell, signature,
+    stats, stt, sync, templates, trace, tray, trigger, tts, usage, validate, versions, wasm, web,
+    web_search, workflow,
 };
+use ghostie::utils;
+use tauri::Manager;
+use tauri_plugin_deep_link::DeepLinkExt;
 use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
 
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let mcp = args.get(1).map(String::as_str) == Some("mcp");
+    // 修改数据目录后在重启时迁移数据, 此时还没有打开任何文件
+    if !mcp {
+        utils::data_dir::apply_pending();
+    }
+    trace::init();
+    // ghostie mcp --token <令牌>: 作为 MCP 服务在标准输入输出上运行, 不启动窗口
+    if mcp {
+        let token = args
+            .iter()
+            .position(|arg| arg == "--token")
+            .and_then(|index| args.get(index + 1).cloned())
+            .or_else(|| std::env::var("GHOSTIE_MCP_TOKEN").ok())
+            .unwrap_or_default();
+        mcp_server::serve_stdio(token).await;
+        return;
+    }
     let app = tauri::Builder::default()
         .plugin(tauri_plugin_global_shortcut::Builder::default().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_updater::Builder::default().build())
         .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_autostart::init(
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             Some(vec!["--flag1", "--flag2"]),
@@ -40,6 +64,13 @@ async fn main() {
                 .build(),
         )
         .manage(knowledge::KnowledgeState::new())
+        // 拖放插件脚本到窗口上时导入
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                local::handle_drop(window.app_handle().clone(), paths.clone());
+            }
+            tray::handle_window_event(window, event);
+        })
         .setup(|app| {
             // 仅在桌面平台启用自动更新功能
             #[cfg(desktop)]
@@ -55,65 +86,86 @@ async fn main() {
                 Some(vec!["--flag1", "--flag2"]),
             ));
 
+            // 先完成数据库与设置的迁移, 之后启动的后台任务都读取升级后的数据
+            // 迁移失败时继续启动, 错误与回滚记录在 migration_report 中
+            if let Err(err) = db::init() {
+                tracing::error!(error = %err, "打开数据库失败");
+            }
+            let _ = utils::settings::get();
+            // 启动声明了自动运行的插件后台服务
+            tauri::async_runtime::spawn(service::start_autostart());
+            // 提醒与通知按钮需要发送事件
+            notification::init(app.handle().clone());
+            // 启动定时任务循环
+            tauri::async_runtime::spawn(schedule::run_scheduler());
+            // 恢复文件变化触发器
+            tauri::async_runtime::spawn(trigger::start_all());
+            // 监听知识库目录并定期扫描
+            tauri::async_runtime::spawn(kb_sync::start_all(app.handle().clone()));
+            // 将明文保存的环境变量迁移到系统密钥链
+            tauri::async_runtime::spawn_blocking(|| {
+                let _ = secrets::migrate();
+            });
+            // 监听插件源码的外部修改
+            let _ = reload::start(app.handle().clone());
+            // 检查插件仓库中的更新
+            tauri::async_runtime::spawn(registry::notify_updates(app.handle().clone()));
+            // 启用时启动本地 HTTP 服务
+            tauri::async_runtime::spawn(async {
+                let _ = server::apply_settings().await;
+            });
+
+            // 处理 echo:// 链接, 开发模式下需要手动注册协议
+            #[cfg(all(debug_assertions, any(target_os = "linux", windows)))]
+            let _ = app.deep_link().register_all();
+            let handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for link in event.urls() {
+                    install::handle_deep_link(handle.clone(), link);
+                }
+            });
+
             // 注册快捷键
             let _ = app.handle();
             let shortcut = Shortcut::new(Some(Modifiers::ALT), Code::Space);
             app.global_shortcut().register(shortcut)?;
+            // 快速提问窗口的快捷键
+            quick_capture::start(app.handle());
 
-            // 创建托盘图标
-            let menu = Menu::with_items(
-                app,
-                &[
-                    &MenuItem::with_id(app, "show", "显示", true, None::<&str>)?,
-                    &MenuItem::with_id(app, "hide", "隐藏", true, None::<&str>)?,
-                    &MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?,
-                ],
-            )?;
-
-            TrayIconBuilder::new()
-                .icon(app.default_window_icon().unwrap().clone())
-                .menu(&menu)
-                .on_menu_event(move |app, event| {
-                    if let Some(window) = app.get_webview_window("main") {
-                        match event.id().as_ref() {
-                            "quit" => {
-                                app.exit(0);
-                            }
-                            "show" => {
-                                let _ = window.show();
-                                let _ = window.set_focus();
-                            }
-                            "hide" => {
-                                let _ = window.hide();
-                            }
-                            _ => {}
-                        }
-                    }
-                })
-                .on_tray_icon_event(|tray, event| {
-                    if let Some(window) = tray.app_handle().get_webview_window("main") {
-                        match event {
-                            tauri::tray::TrayIconEvent::Click { .. } => {
-                                let _ = window.show();
-                            }
-                            _ => {}
-                        }
-                    }
-                })
-                .build(app)?;
+            // 创建托盘图标, 菜单包含最近对话与固定的工具
+            tray::create(app.handle())?;
+            // 开启时监听剪贴板内容
+            clipboard::start(app.handle().clone());
+            // 恢复上次打开的独立窗口
+            utils::window::restore_windows(app.handle());
+            // 开启时按间隔同步配置与插件
+            sync::start(app.handle().clone());
             Ok(())
         })
         // 启用系统对话框插件
         .invoke_handler(tauri::generate_handler![
+            utils::data_dir::data_dir_get,
+            utils::data_dir::data_dir_set,
             utils::file::open_files_path,
             utils::file::open_file,
             utils::file::save_file,
             utils::file::read_file_text,
+            utils::migrate::migration_report,
             utils::window::open_window,
             utils::window::hide_window,
             utils::window::open_config_dir,
+            utils::window::window_open,
+            utils::window::window_states,
+            utils::workspace::workspace_create,
+            utils::workspace::workspace_list,
+            utils::workspace::workspace_switch,
             utils::update::check_update,
             utils::update::install_update,
+            utils::update::update_channel_get,
+            utils::update::update_channel_set,
+            utils::update::update_check,
+            utils::update::update_download,
+            utils::update::update_install,
             knowledge::get_aliyun_api_key,
             knowledge::save_aliyun_api_key,
             knowledge::upload_knowledge_file,
@@ -121,32 +173,212 @@ async fn main() {
             knowledge::delete_knowledge,
             knowledge::search_knowledge,
             deno::plugin_import,
+            deno::plugin_duplicate,
+            env::plugin_env_list,
+            env::plugin_env_save,
+            meta::plugin_set_meta,
+            templates::plugin_create,
+            directory::plugin_import_dir,
+            local::plugin_import_file,
+            directory::plugin_read_file,
+            install::plugin_import_url,
+            install::plugin_import_git,
+            install::plugin_check_update,
+            install::plugin_update_from_source,
+            registry::registry_refresh,
+            registry::registry_search,
+            registry::registry_install,
+            registry::plugins_check_updates,
+            registry::registry_configure,
+            bundle::plugin_export,
+            bundle::plugin_import_bundle,
+            openapi::plugin_import_openapi,
+            openapi::plugin_import_ai_plugin,
+            versions::plugin_versions,
+            versions::plugin_version_content,
+            versions::plugin_rollback,
+            signature::signature_trusted_keys,
+            signature::signature_trust_key,
+            signature::signature_untrust_key,
             deno::plugins_list,
             deno::plugin_get,
             deno::plugin_remove,
             deno::plugin_execute,
+            deno::plugin_explain,
+            approval::execution_pending,
+            approval::execution_approve,
+            approval::execution_reject,
+            grants::plugin_grants,
+            grants::plugin_grant_revoke,
+            batch::plugin_execute_many,
+            artifacts::artifacts_clear,
             deno::plugin_update,
+            validate::plugin_validate,
+            harness::plugin_test,
+            deno::plugin_set_enabled,
+            deno::plugin_set_tags,
+            deno::plugins_search,
+            catalog::tools_export_schema,
+            providers::provider_list,
+            providers::provider_save,
+            providers::provider_delete,
+            providers::chat_stream,
+            providers::chat_cancel,
+            providers::ollama_models,
+            providers::image_generate,
+            quick_capture::quick_capture_binding,
+            quick_capture::quick_capture_set_binding,
+            quick_capture::quick_capture_submit,
+            tray::tray_settings,
+            tray::tray_configure,
+            tray::tray_refresh,
+            clipboard::clipboard_watch_settings,
+            clipboard::clipboard_watch_configure,
+            notification::notify,
+            notification::reminder_create,
+            notification::reminder_list,
+            notification::reminder_delete,
+            background::autostart_get,
+            background::autostart_set,
+            background::background_settings,
+            background::background_set,
+            palette::palette_query,
+            palette::palette_record,
+            palette::palette_refresh,
+            backup::backup_create,
+            backup::backup_restore,
+            sync::sync_settings,
+            sync::sync_configure,
+            sync::sync_now,
+            sync::sync_status,
+            sync::sync_resolve,
+            proxy::proxy_settings,
+            proxy::proxy_configure,
+            proxy::proxy_test,
+            providers::local::local_model_load,
+            providers::local::local_model_unload,
+            providers::local::local_model_current,
+            providers::tokens::tokens_count,
+            providers::tokens::tokens_trim,
+            chats::chat_create,
+            chats::chat_append,
+            chats::chat_list,
+            chats::chat_messages,
+            chats::chat_rename,
+            chats::chat_archive,
+            chats::chat_delete,
+            chat_export::chat_export,
+            chat_export::chat_export_all,
+            chat_import::chat_import,
+            bots::bot_list,
+            bots::bot_get,
+            bots::bot_save,
+            bots::bot_delete,
+            bots::bot_export,
+            bots::bot_import,
+            agent::agent_run,
+            agent::agent_cancel,
+            workflow::workflow_save,
+            workflow::workflow_list,
+            workflow::workflow_delete,
+            workflow::workflow_run,
+            workflow::workflow_cancel,
+            workflow::workflow_status,
+            usage::usage_report,
+            usage::usage_export,
+            usage::usage_price_list,
+            usage::usage_price_set,
+            usage::usage_price_delete,
+            embeddings::embeddings_create,
+            embeddings::collection_create,
+            embeddings::collection_list,
+            embeddings::collection_delete,
+            embeddings::documents_add,
+            embeddings::documents_remove,
+            embeddings::similar_search,
+            ingest::document_ingest,
+            kb_sync::kb_source_add,
+            kb_sync::kb_source_list,
+            kb_sync::kb_source_remove,
+            kb_sync::kb_sync_now,
+            ocr::ocr,
+            screen::screen_capture,
+            stt::transcribe,
+            stt::stt_models_list,
+            stt::stt_model_download,
+            stt::stt_model_delete,
+            tts::tts_speak,
+            tts::tts_stop,
+            tts::tts_voices,
+            web::web_fetch,
+            web_search::web_search,
+            web_search::web_search_settings,
+            web_search::web_search_configure,
+            builtin::builtin_tools_list,
+            search::search,
+            search::search_reindex,
+            deno::plugins_list_page,
+            i18n::plugins_set_locale,
+            deno::plugin_content,
+            deno::plugin_cache_deps,
+            deno::plugin_update_lock,
+            cache::plugin_cache_clear,
+            wasm::plugin_import_wasm,
+            shell::plugin_save_shell,
+            mcp::mcp_server_add,
+            mcp::mcp_server_remove,
+            mcp::mcp_server_list,
+            mcp::mcp_tools,
+            mcp_server::mcp_clients_list,
+            mcp_server::mcp_client_add,
+            mcp_server::mcp_client_set_plugins,
+            mcp_server::mcp_client_remove,
+            mcp_server::mcp_client_config,
             deno::env_list,
             deno::env_save,
+            env::env_set,
+            env::env_delete,
+            env::env_rename,
+            env::env_export,
+            profile::profile_list,
+            profile::profile_save,
+            profile::profile_delete,
+            profile::profile_activate,
+            logs::plugin_logs,
+            service::plugin_service_start,
+            service::plugin_service_stop,
+            service::plugin_service_status,
+            schedule::schedule_create,
+            schedule::schedule_list,
+            schedule::schedule_delete,
+            schedule::schedule_history,
+            trigger::trigger_create,
+            trigger::trigger_list,
+            trigger::trigger_delete,
+            server::server_status,
+            server::server_configure,
+            server::server_rotate_token,
+            server::server_set_cors,
+            history::execution_history,
+            history::execution_history_clear,
+            replay::execution_replay,
+            stats::plugin_stats,
+            stats::plugin_stats_reset,
+            trace::logs_level,
+            trace::logs_set_level,
+            trace::logs_query,
+            trace::logs_export,
+            runtime::runtime_info,
+            runtime::runtime_install,
+            runtime::runtime_refresh,
+            runtime::runtime_set_path,
+            runtime::runtime_set_offline,
+            runtime::runtime_limits_get,
+            runtime::runtime_limits_set,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
 
-    app.run(|app, event| {
-        if let tauri::RunEvent::WindowEvent {
-            label,
-            event: tauri::WindowEvent::CloseRequested { api, .. },
-            ..
-        } = event
-        {
-            if label == "main" {
-                if let Some(window) = app.get_webview_window("main") {
-                    if window.is_visible().unwrap_or(false) {
-                        api.prevent_close();
-                        let _ = window.hide();
-                    }
-                }
-            }
-        }
-    });
+    // 后台模式下关闭窗口后继续运行
+    app.run(|app, event| background::handle_run_event(app, &event));
 }
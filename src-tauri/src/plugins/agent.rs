@@ -0,0 +1,357 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+use super::approval;
+use super::bots::{self, Bot};
+use super::builtin;
+use super::catalog::{self, ExposedTool, SchemaFormat};
+use super::chats;
+use super::db::with_db;
+use super::deno::{PluginError, Result};
+use super::providers::{self, ChatMessage, ChatRequest, Sink, ToolCall, Usage};
+use crate::utils::gen::generate_id;
+
+// 默认的最大轮数, 每轮为一次模型请求
+const DEFAULT_MAX_STEPS: u32 = 8;
+// 回传给模型的工具结果的最大字符数
+const MAX_TOOL_OUTPUT: usize = 16000;
+
+/// 一次代理运行的参数
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AgentRequest {
+    /// 用于取消与匹配事件, 为空时自动生成
+    pub run_id: Option<String>,
+    /// 读取并写入的会话, 为空时只使用 messages
+    pub conversation_id: Option<String>,
+    /// 使用的助手, 提供系统提示词、模型与插件
+    pub bot: Option<String>,
+    /// 覆盖助手的模型服务与模型
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    /// 未指定助手时可调用的插件, 为空时不提供工具
+    pub plugins: Option<Vec<String>>,
+    /// 未指定助手时可调用的内置工具, 如 web_fetch
+    pub tools: Option<Vec<String>>,
+    /// 会话之外的历史消息
+    pub messages: Vec<ChatMessage>,
+    /// 本轮的用户输入
+    pub input: Option<String>,
+    pub max_steps: Option<u32>,
+    /// 需要确认的工具是否弹窗询问, 为 false 时直接拒绝, 用于无人值守的运行
+    pub interactive: Option<bool>,
+}
+
+/// 运行过程中的事件, 以 agent://event 发送
+#[derive(Debug, Serialize, Clone)]
+pub struct AgentEvent {
+    pub run_id: String,
+    #[serde(flatten)]
+    pub kind: AgentEventKind,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentEventKind {
+    /// 模型输出的一段文本
+    Delta {
+        step: u32,
+        content: String,
+    },
+    ToolStarted {
+        step: u32,
+        call_id: String,
+        name: String,
+        plugin_id: Option<String>,
+        tool: Option<String>,
+        arguments: String,
+    },
+    ToolFinished {
+        step: u32,
+        call_id: String,
+        success: bool,
+        output: String,
+        execution_id: Option<String>,
+    },
+    Finished {
+        content: String,
+        steps: u32,
+    },
+}
+
+/// 运行结果
+#[derive(Debug, Serialize, Clone)]
+pub struct AgentResult {
+    pub run_id: String,
+    /// 最终回复
+    pub content: String,
+    pub steps: u32,
+    /// 本次运行新增的消息, 包含工具调用与结果
+    pub messages: Vec<ChatMessage>,
+    pub usage: Usage,
+    /// stop、length、tool_calls 或 max_steps
+    pub finish_reason: Option<String>,
+}
+
+pub(crate) type Emit = Arc<dyn Fn(AgentEvent) + Send + Sync>;
+
+// 进行中的运行, 发送信号后取消
+static CANCELS: Lazy<Mutex<HashMap<String, oneshot::Sender<()>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn truncate(text: String) -> String {
+    if text.chars().count() <= MAX_TOOL_OUTPUT {
+        return text;
+    }
+    let mut truncated: String = text.chars().take(MAX_TOOL_OUTPUT).collect();
+    truncated.push_str("\n...(结果过长, 已截断)");
+    truncated
+}
+
+// 确定模型服务与模型, 请求中的设置优先于助手
+fn resolve(request: &AgentRequest, bot: Option<&Bot>) -> Result<(String, String)> {
+    let provider = request
+        .provider
+        .clone()
+        .or_else(|| bot.and_then(|b| b.provider.clone()))
+        .ok_or_else(|| PluginError::Plugin("未指定模型服务".to_string()))?;
+    let model = request
+        .model
+        .clone()
+        .or_else(|| bot.and_then(|b| b.model.clone()))
+        .ok_or_else(|| PluginError::Plugin("未指定模型".to_string()))?;
+    Ok((provider, model))
+}
+
+// 执行一次工具调用, 返回是否成功、写回模型的文本与执行 id
+async fn dispatch(
+    call: &ToolCall,
+    exposed: Option<&ExposedTool>,
+    builtins: &[String],
+    interactive: bool,
+) -> (bool, String, Option<String>) {
+    let args: Value = match serde_json::from_str(&call.function.arguments) {
+        Ok(args) => args,
+        Err(err) => return (false, format!("参数不是有效的 JSON: {}", err), None),
+    };
+    let Some(exposed) = exposed else {
+        if builtins.contains(&call.function.name) {
+            return match builtin::call(&call.function.name, &args).await {
+                Ok(value) => (true, truncate(value.to_string()), None),
+                Err(err) => (false, format!("执行失败: {}", err), None),
+            };
+        }
+        return (false, format!("未知工具: {}", call.function.name), None);
+    };
+    let result =
+        approval::execute_confirmed(&exposed.plugin_id, &exposed.tool, args, "助手", interactive)
+            .await;
+    match result {
+        Ok(result) => {
+            let text = match result.result {
+                Value::String(text) => text,
+                value => value.to_string(),
+            };
+            (true, truncate(text), Some(result.execution_id))
+        }
+        Err(err) => (false, format!("执行失败: {}", err), None),
+    }
+}
+
+fn persist(conversation_id: Option<&str>, message: &ChatMessage, execution_id: Option<&str>) {
+    if let Some(id) = conversation_id {
+        if let Err(err) = chats::append_message(id, message, execution_id) {
+            tracing::warn!(conversation = id, error = %err, "保存消息失败");
+        }
+    }
+}
+
+/// 驱动模型与工具的循环, 直到模型给出不含工具调用的回复或达到最大轮数
+pub(crate) async fn run(request: AgentRequest, emit: Emit) -> Result<AgentResult> {
+    let run_id = request.run_id.clone().unwrap_or_else(generate_id);
+    let bot = request.bot.as_deref().map(bots::find_bot).transpose()?;
+    let (provider, model) = resolve(&request, bot.as_ref())?;
+    let interactive = request.interactive.unwrap_or(true);
+    let conversation_id = request.conversation_id.as_deref();
+
+    let mut messages = Vec::new();
+    if let Some(bot) = bot.as_ref().filter(|b| !b.system_prompt.trim().is_empty()) {
+        messages.push(ChatMessage {
+            role: "system".to_string(),
+            content: bot.system_prompt.clone(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        });
+    }
+    if let Some(id) = conversation_id {
+        let history = with_db(|conn| chats::load_messages(conn, id))?;
+        messages.extend(history.into_iter().map(|m| m.message));
+    }
+    messages.extend(request.messages.iter().cloned());
+    let mut added = Vec::new();
+    if let Some(input) = request.input.as_ref().filter(|i| !i.trim().is_empty()) {
+        let message = ChatMessage {
+            role: "user".to_string(),
+            content: input.clone(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        };
+        persist(conversation_id, &message, None);
+        messages.push(message.clone());
+        added.push(message);
+    }
+
+    let plugins = match &bot {
+        Some(bot) => Some(bot.plugins.clone()),
+        None => request.plugins.clone(),
+    }
+    .filter(|p| !p.is_empty());
+    let builtins = match &bot {
+        Some(bot) => bot.tools.clone(),
+        None => request.tools.clone().unwrap_or_default(),
+    };
+    let (mut tools, mapping) = match plugins {
+        Some(plugins) => {
+            let export =
+                catalog::tools_export_schema(SchemaFormat::Openai, Some(plugins), None).await?;
+            let tools = export.tools.as_array().cloned().unwrap_or_default();
+            (tools, export.mapping)
+        }
+        None => (Vec::new(), Vec::new()),
+    };
+    tools.extend(builtin::schemas(&builtins));
+
+    let max_steps = request.max_steps.unwrap_or(DEFAULT_MAX_STEPS).max(1);
+    let mut usage = Usage::default();
+    let mut step = 0;
+    let mut finish_reason = None;
+    let mut content = String::new();
+    while step < max_steps {
+        step += 1;
+        let sink: Sink = {
+            let emit = emit.clone();
+            let run_id = run_id.clone();
+            Arc::new(move |text: &str| {
+                emit(AgentEvent {
+                    run_id: run_id.clone(),
+                    kind: AgentEventKind::Delta {
+                        step,
+                        content: text.to_string(),
+                    },
+                })
+            })
+        };
+        let chat = ChatRequest {
+            provider: provider.clone(),
+            model: model.clone(),
+            messages: messages.clone(),
+            tools: tools.clone(),
+            temperature: bot.as_ref().and_then(|b| b.temperature),
+            max_tokens: bot.as_ref().and_then(|b| b.max_tokens),
+            request_id: None,
+            bot: request.bot.clone(),
+        };
+        let response = providers::chat(&chat, sink).await?;
+        if let Some(u) = &response.usage {
+            usage.prompt_tokens += u.prompt_tokens;
+            usage.completion_tokens += u.completion_tokens;
+        }
+        finish_reason = response.finish_reason.clone();
+        let reply = response.message;
+        persist(conversation_id, &reply, None);
+        messages.push(reply.clone());
+        added.push(reply.clone());
+        content = reply.content.clone();
+        if reply.tool_calls.is_empty() {
+            break;
+        }
+
+        for call in &reply.tool_calls {
+            let exposed = mapping.iter().find(|t| t.name == call.function.name);
+            emit(AgentEvent {
+                run_id: run_id.clone(),
+                kind: AgentEventKind::ToolStarted {
+                    step,
+                    call_id: call.id.clone(),
+                    name: call.function.name.clone(),
+                    plugin_id: exposed.map(|t| t.plugin_id.clone()),
+                    tool: exposed.map(|t| t.tool.clone()),
+                    arguments: call.function.arguments.clone(),
+                },
+            });
+            let (success, output, execution_id) =
+                dispatch(call, exposed, &builtins, interactive).await;
+            emit(AgentEvent {
+                run_id: run_id.clone(),
+                kind: AgentEventKind::ToolFinished {
+                    step,
+                    call_id: call.id.clone(),
+                    success,
+                    output: output.clone(),
+                    execution_id: execution_id.clone(),
+                },
+            });
+            let message = ChatMessage {
+                role: "tool".to_string(),
+                content: output,
+                tool_calls: Vec::new(),
+                tool_call_id: Some(call.id.clone()),
+            };
+            persist(conversation_id, &message, execution_id.as_deref());
+            messages.push(message.clone());
+            added.push(message);
+        }
+        if step == max_steps {
+            finish_reason = Some("max_steps".to_string());
+        }
+    }
+
+    emit(AgentEvent {
+        run_id: run_id.clone(),
+        kind: AgentEventKind::Finished {
+            content: content.clone(),
+            steps: step,
+        },
+    });
+    tracing::info!(run = %run_id, steps = step, "代理运行结束");
+    Ok(AgentResult {
+        run_id,
+        content,
+        steps: step,
+        messages: added,
+        usage,
+        finish_reason,
+    })
+}
+
+/// 运行代理, 过程以 agent://event 事件推送
+#[tauri::command]
+pub async fn agent_run(app: AppHandle, mut request: AgentRequest) -> Result<AgentResult> {
+    let run_id = request.run_id.clone().unwrap_or_else(generate_id);
+    request.run_id = Some(run_id.clone());
+    let (cancel, cancelled) = oneshot::channel();
+    CANCELS.lock().unwrap().insert(run_id.clone(), cancel);
+    let emit: Emit = Arc::new(move |event| {
+        let _ = app.emit("agent://event", event);
+    });
+    let result = tokio::select! {
+        result = run(request, emit) => result,
+        _ = cancelled => Err(PluginError::Plugin("运行已取消".to_string())),
+    };
+    CANCELS.lock().unwrap().remove(&run_id);
+    result
+}
+
+/// 取消进行中的代理运行
+#[tauri::command]
+pub async fn agent_cancel(run_id: String) -> Result<()> {
+    if let Some(cancel) = CANCELS.lock().unwrap().remove(&run_id) {
+        let _ = cancel.send(());
+    }
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+
+use super::deno::{
+    execute_tool, find_plugin, plugin_execute, read_content, ExecutionResult, Plugin, PluginError,
+    PluginRuntime, Result,
+};
+use super::directory;
+use super::host;
+use super::profile;
+use super::redact;
+use crate::utils::gen::generate_id;
+
+// 确认请求的有效期 (毫秒), 过期后需重新发起
+const APPROVAL_TTL_MS: i64 = 5 * 60 * 1000;
+
+// 可执行命令或删除文件的接口, 源码中出现时工具需要确认后才能执行
+const DANGEROUS_APIS: [&str; 12] = [
+    "Deno.Command",
+    "Deno.run(",
+    "child_process",
+    "execSync",
+    "subprocess",
+    "os.system",
+    "os.remove",
+    "os.unlink",
+    "shutil.rmtree",
+    "fs.rm",
+    "fs.unlink",
+    "rmSync",
+];
+
+/// 等待确认的执行请求
+#[derive(Debug, Serialize, Clone)]
+pub struct PendingApproval {
+    pub id: String,
+    pub plugin_id: String,
+    pub tool: String,
+    /// 已替换敏感信息的参数, 仅用于展示
+    pub args: Value,
+    pub profile: Option<String>,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+struct Pending {
+    info: PendingApproval,
+    args: Value,
+}
+
+static PENDING: Lazy<Mutex<HashMap<String, Pending>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 源码中使用的危险接口
+pub(crate) fn detect(content: &str) -> Vec<String> {
+    DANGEROUS_APIS
+        .iter()
+        .filter(|api| content.contains(*api))
+        .map(|api| api.trim_end_matches('(').to_string())
+        .collect()
+}
+
+/// 插件源码中使用的危险接口, 目录插件同时检查目录中的源码文件
+pub(crate) fn detect_plugin(id: &str, content: &str) -> Result<Vec<String>> {
+    let mut apis = detect(content);
+    let root = directory::plugin_dir(id);
+    for file in directory::list_files(id)? {
+        // 非文本文件跳过
+        let Ok(source) = fs::read_to_string(root.join(file)) else {
+            continue;
+        };
+        for api in detect(&source) {
+            if !apis.contains(&api) {
+                apis.push(api);
+            }
+        }
+    }
+    Ok(apis)
+}
+
+/// 工具是否需要确认: 声明了 dangerous、Shell 插件或源码中使用了危险接口
+pub(crate) fn requires_approval(plugin: &Plugin, tool: &str) -> bool {
+    plugin.runtime == PluginRuntime::Shell
+        || !plugin.dangerous_apis.is_empty()
+        || plugin.tools.iter().any(|t| t.name == tool && t.dangerous)
+}
+
+// 删除过期的请求
+fn prune(pending: &mut HashMap<String, Pending>) {
+    let now = chrono::Utc::now().timestamp_millis();
+    pending.retain(|_, p| p.info.expires_at > now);
+}
+
+/// 插件的工具是否需要确认
+pub(crate) async fn needs_approval(id: &str, tool: &str) -> Result<bool> {
+    let mut plugin = find_plugin(id).await?;
+    // 早于检测功能安装的插件没有检测结果, 按当前源码重新检测
+    if plugin.dangerous_apis.is_empty() {
+        plugin.dangerous_apis = detect_plugin(id, &read_content(&plugin)?)?;
+    }
+    Ok(requires_approval(&plugin, tool))
+}
+
+/// 需要确认时登记请求并返回 ApprovalRequired, 否则直接通过
+pub(crate) async fn check(id: &str, tool: &str, args: &Value) -> Result<()> {
+    if !needs_approval(id, tool).await? {
+        return Ok(());
+    }
+    let now = chrono::Utc::now().timestamp_millis();
+    let info = PendingApproval {
+        id: generate_id(),
+        plugin_id: id.to_string(),
+        tool: tool.to_string(),
+        args: redact::scrub_value(args),
+        profile: profile::current(),
+        created_at: now,
+        expires_at: now + APPROVAL_TTL_MS,
+    };
+    let mut pending = PENDING.lock().unwrap();
+    prune(&mut pending);
+    pending.insert(
+        info.id.clone(),
+        Pending {
+            info: info.clone(),
+            args: args.clone(),
+        },
+    );
+    tracing::info!(plugin = id, tool, approval = %info.id, "执行等待确认");
+    Err(PluginError::ApprovalRequired {
+        approval_id: info.id,
+        plugin_id: info.plugin_id,
+        tool: info.tool,
+        expires_at: info.expires_at,
+    })
+}
+
+// 弹窗询问用户是否允许执行
+async fn ask(requester: &str, plugin_id: &str, tool: &str) -> bool {
+    let message = format!(
+        "{}请求执行插件 {} 的工具 {}, 该工具可能执行命令或删除文件。\n\n是否允许?",
+        requester, plugin_id, tool
+    );
+    host::dialog(Some("执行确认".to_string()), message, true).await
+}
+
+/// 不经过确认队列的执行 (如重放) 在需要确认时直接弹窗询问, 用户拒绝时返回错误
+///
+/// `id` 为实际执行的插件, `label` 为对话框中显示的插件 id
+pub(crate) async fn confirm(id: &str, label: &str, tool: &str, requester: &str) -> Result<()> {
+    if needs_approval(id, tool).await? && !ask(requester, label, tool).await {
+        tracing::info!(plugin = label, tool, "用户拒绝了执行");
+        return Err(PluginError::Plugin("用户拒绝了执行".to_string()));
+    }
+    Ok(())
+}
+
+/// 执行工具, 需要确认时弹窗询问用户
+///
+/// `requester` 为发起执行的一方, 显示在对话框中; `interactive` 为 false 时直接拒绝,
+/// 用于定时任务、文件触发等无人值守的执行
+pub(crate) async fn execute_confirmed(
+    plugin_id: &str,
+    tool: &str,
+    args: Value,
+    requester: &str,
+    interactive: bool,
+) -> Result<ExecutionResult> {
+    match plugin_execute(plugin_id.to_string(), tool.to_string(), args, None, None).await {
+        Err(PluginError::ApprovalRequired { approval_id, .. }) => {
+            if !interactive {
+                let _ = execution_reject(approval_id).await;
+                return Err(PluginError::Plugin(format!(
+                    "工具 {} 需要确认, {}不能自动执行",
+                    tool, requester
+                )));
+            }
+            if !ask(requester, plugin_id, tool).await {
+                let _ = execution_reject(approval_id).await;
+                return Err(PluginError::Plugin("用户拒绝了执行".to_string()));
+            }
+            execution_approve(approval_id).await
+        }
+        result => result,
+    }
+}
+
+/// 列出等待确认的执行请求
+#[tauri::command]
+pub async fn execution_pending() -> Result<Vec<PendingApproval>> {
+    let mut pending = PENDING.lock().unwrap();
+    prune(&mut pending);
+    let mut list: Vec<PendingApproval> = pending.values().map(|p| p.info.clone()).collect();
+    list.sort_by_key(|p| p.created_at);
+    Ok(list)
+}
+
+fn take(id: &str) -> Result<Pending> {
+    let mut pending = PENDING.lock().unwrap();
+    prune(&mut pending);
+    pending
+        .remove(id)
+        .ok_or_else(|| PluginError::Plugin("确认请求不存在或已过期".to_string()))
+}
+
+/// 确认并执行请求, 每个请求只能确认一次
+#[tauri::command]
+pub async fn execution_approve(id: String) -> Result<ExecutionResult> {
+    let pending = take(&id)?;
+    let info = pending.info;
+    tracing::info!(plugin = %info.plugin_id, tool = %info.tool, approval = %id, "执行已确认");
+    profile::scope(
+        info.profile,
+        execute_tool(&info.plugin_id, &info.tool, &pending.args, &[]),
+    )
+    .await?
+}
+
+/// 拒绝请求
+#[tauri::command]
+pub async fn execution_reject(id: String) -> Result<()> {
+    take(&id)?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::deno::{plugins_dir, EnvVar, Result};
+
+/// 插件写入产物文件的目录, 通过环境变量传给插件进程
+pub(crate) const ARTIFACTS_DIR_ENV: &str = "ECHO_ARTIFACTS_DIR";
+
+/// 工具生成的文件
+#[derive(Debug, Serialize, Clone)]
+pub struct Artifact {
+    pub name: String,
+    pub path: String,
+    pub mime: String,
+    pub size: u64,
+}
+
+/// 为一次执行创建产物目录
+pub(crate) fn create_dir(execution_id: &str) -> Result<PathBuf> {
+    let dir = plugins_dir().join("artifacts").join(execution_id);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+pub(crate) fn env_var(dir: &Path) -> EnvVar {
+    EnvVar {
+        key: ARTIFACTS_DIR_ENV.to_string(),
+        value: dir.to_string_lossy().to_string(),
+    }
+}
+
+fn walk(dir: &Path, root: &Path, artifacts: &mut Vec<Artifact>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(&path, root, artifacts)?;
+            continue;
+        }
+        artifacts.push(Artifact {
+            name: path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/"),
+            mime: mime_guess::from_path(&path)
+                .first_or_octet_stream()
+                .to_string(),
+            size: fs::metadata(&path)?.len(),
+            path: path.to_string_lossy().to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// 收集执行期间生成的文件, 没有产物时删除目录
+pub(crate) fn collect(dir: &Path) -> Result<Vec<Artifact>> {
+    let mut artifacts = Vec::new();
+    if dir.exists() {
+        walk(dir, dir, &mut artifacts)?;
+        if artifacts.is_empty() {
+            fs::remove_dir_all(dir)?;
+        }
+    }
+    artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(artifacts)
+}
+
+/// 删除一次执行的产物, 不传 id 时清空全部产物
+#[tauri::command]
+pub async fn artifacts_clear(execution_id: Option<String>) -> Result<()> {
+    let dir = match execution_id {
+        Some(id) => plugins_dir().join("artifacts").join(id),
+        None => plugins_dir().join("artifacts"),
+    };
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
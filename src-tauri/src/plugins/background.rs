@@ -0,0 +1,80 @@
+use tauri::{AppHandle, Manager, RunEvent, WindowEvent};
+use tauri_plugin_autostart::ManagerExt;
+
+use super::deno::{PluginError, Result};
+use crate::utils::settings::{self, BackgroundSettings};
+use crate::utils::update;
+
+const MAIN_WINDOW: &str = "main";
+
+fn autostart_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("设置开机启动失败: {}", message))
+}
+
+// 后台模式或关闭到托盘时, 关闭窗口不退出应用
+fn keep_running() -> bool {
+    let settings = settings::get();
+    settings.background.enabled || settings.tray.close_to_tray
+}
+
+/// 处理窗口关闭与退出请求
+///
+/// 后台模式下关闭主窗口只隐藏窗口, 定时任务、本地 HTTP 服务与后台插件继续运行,
+/// 只有托盘菜单中的退出会结束进程; 关闭后台模式时关闭主窗口即退出应用
+pub fn handle_run_event(app: &AppHandle, event: &RunEvent) {
+    match event {
+        RunEvent::WindowEvent {
+            label,
+            event: WindowEvent::CloseRequested { api, .. },
+            ..
+        } if label == MAIN_WINDOW => {
+            if !keep_running() {
+                app.exit(0);
+                return;
+            }
+            if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+                if window.is_visible().unwrap_or(false) {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
+            }
+        }
+        // 所有窗口关闭时 code 为 None, 主动调用 exit 时不拦截
+        RunEvent::ExitRequested {
+            code: None, api, ..
+        } if keep_running() => api.prevent_exit(),
+        // 已下载的更新在退出时安装
+        RunEvent::Exit => update::install_pending(),
+        _ => {}
+    }
+}
+
+/// 是否已设置开机启动
+#[tauri::command]
+pub async fn autostart_get(app: AppHandle) -> Result<bool> {
+    app.autolaunch().is_enabled().map_err(autostart_error)
+}
+
+/// 开启或关闭开机启动, 使用系统的登录项、注册表或 XDG autostart
+#[tauri::command]
+pub async fn autostart_set(app: AppHandle, enabled: bool) -> Result<bool> {
+    let launcher = app.autolaunch();
+    if enabled {
+        launcher.enable().map_err(autostart_error)?;
+    } else {
+        launcher.disable().map_err(autostart_error)?;
+    }
+    launcher.is_enabled().map_err(autostart_error)
+}
+
+#[tauri::command]
+pub async fn background_settings() -> Result<BackgroundSettings> {
+    Ok(settings::get().background)
+}
+
+/// 开启或关闭后台模式
+#[tauri::command]
+pub async fn background_set(enabled: bool) -> Result<BackgroundSettings> {
+    let updated = settings::update(|s| s.background.enabled = enabled)?;
+    Ok(updated.background)
+}
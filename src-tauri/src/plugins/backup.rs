@@ -0,0 +1,789 @@
+use chrono::Utc;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use super::db::with_db;
+use super::deno::{invalidate_plugin_list, plugins_dir, PluginError, Result};
+use super::secrets;
+use crate::utils::settings::{self, Settings};
+
+// 备份格式版本, 不兼容的修改时递增
+const BACKUP_VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+const SETTINGS_FILE: &str = "settings.toml";
+const SECRETS_FILE: &str = "secrets.json";
+const FILES_PREFIX: &str = "files";
+const TABLES_PREFIX: &str = "tables";
+
+// 插件目录中不备份的内容: 数据库按表导出, 其余为缓存或可重新生成的文件
+const EXCLUDED: &[&str] = &[
+    "echo.db",
+    "echo.db-wal",
+    "echo.db-shm",
+    "artifacts",
+    "cache",
+    "exports",
+    "logs",
+    "speech",
+];
+
+// 保存密钥明文的列, 排除密钥时清空
+const SECRET_COLUMNS: &[(&str, &str)] = &[
+    ("env_vars", "value"),
+    ("plugin_env", "value"),
+    ("env_profile_vars", "value"),
+];
+
+/// 备份的内容分类
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BackupSection {
+    /// 插件、插件文件与插件数据
+    Plugins,
+    /// 全局环境变量与环境配置
+    Env,
+    /// 应用设置、模型服务与工作流
+    Settings,
+    /// 助手
+    Bots,
+    /// 定时任务、提醒与触发器
+    Schedules,
+    /// 对话记录与附件
+    Chats,
+}
+
+// 按外键依赖排列, 还原时依次写入
+const ALL_SECTIONS: [BackupSection; 6] = [
+    BackupSection::Plugins,
+    BackupSection::Env,
+    BackupSection::Settings,
+    BackupSection::Bots,
+    BackupSection::Schedules,
+    BackupSection::Chats,
+];
+
+impl BackupSection {
+    fn tables(self) -> &'static [&'static str] {
+        match self {
+            BackupSection::Plugins => &["plugins", "tools", "plugin_env", "mcp_servers"],
+            BackupSection::Env => &["env_vars", "env_profiles", "env_profile_vars"],
+            BackupSection::Settings => &["providers", "workflows"],
+            BackupSection::Bots => &["bots"],
+            BackupSection::Schedules => &["schedules", "reminders"],
+            BackupSection::Chats => &[
+                "conversations",
+                "chat_messages",
+                "chat_tool_calls",
+                "chat_attachments",
+            ],
+        }
+    }
+}
+
+/// 创建备份的选项
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct BackupOptions {
+    /// 备份的内容, 默认全部
+    pub sections: Option<Vec<BackupSection>>,
+    /// 不备份环境变量的值, 默认备份, 此时备份文件中包含密钥明文
+    pub exclude_secrets: bool,
+}
+
+/// 备份文件的清单
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub created_at: i64,
+    /// 备份时的插件目录, 还原时用于改写附件路径
+    pub plugins_dir: String,
+    pub sections: Vec<BackupSection>,
+    /// 是否包含环境变量的值
+    pub secrets: bool,
+    /// 各表的记录数
+    pub tables: BTreeMap<String, usize>,
+    pub files: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackupInfo {
+    pub path: String,
+    pub size: u64,
+    #[serde(flatten)]
+    pub manifest: BackupManifest,
+}
+
+/// 还原时已有数据的处理方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreStrategy {
+    /// 只添加本机没有的内容, 冲突时保留本机数据
+    #[default]
+    Merge,
+    /// 冲突时以备份为准
+    Overwrite,
+}
+
+/// 单个分类的还原结果, 对话按会话计数
+#[derive(Debug, Serialize)]
+pub struct SectionReport {
+    pub section: BackupSection,
+    pub created: usize,
+    pub overwritten: usize,
+    /// 内容相同或因冲突保留本机数据的项
+    pub skipped: usize,
+    /// 与本机数据不同的项, 如 bots:<id>、files:<路径>
+    pub conflicts: Vec<String>,
+}
+
+impl SectionReport {
+    fn new(section: BackupSection) -> Self {
+        SectionReport {
+            section,
+            created: 0,
+            overwritten: 0,
+            skipped: 0,
+            conflicts: Vec::new(),
+        }
+    }
+
+    // 记录一项, 返回是否需要写入
+    fn record(&mut self, key: String, exists: bool, strategy: RestoreStrategy) -> bool {
+        if !exists {
+            self.created += 1;
+            return true;
+        }
+        self.conflicts.push(key);
+        match strategy {
+            RestoreStrategy::Merge => {
+                self.skipped += 1;
+                false
+            }
+            RestoreStrategy::Overwrite => {
+                self.overwritten += 1;
+                true
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestoreReport {
+    pub dry_run: bool,
+    pub strategy: RestoreStrategy,
+    pub manifest: BackupManifest,
+    pub sections: Vec<SectionReport>,
+}
+
+/// 备份中的环境变量值, plugin_id 为空时是全局变量
+#[derive(Debug, Serialize, Deserialize)]
+struct SecretEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    plugin_id: Option<String>,
+    key: String,
+    value: String,
+}
+
+type Row = Map<String, Value>;
+
+fn zip_error(err: zip::result::ZipError) -> PluginError {
+    PluginError::Plugin(format!("备份文件读写失败: {}", err))
+}
+
+// 插件目录中的文件所属的分类, None 表示不备份
+fn section_of(relative: &Path) -> Option<BackupSection> {
+    let first = relative.components().next()?.as_os_str().to_string_lossy();
+    if EXCLUDED.contains(&first.as_ref()) || first.starts_with("temp_") {
+        return None;
+    }
+    Some(match first.as_ref() {
+        "attachments" => BackupSection::Chats,
+        "triggers.json" => BackupSection::Schedules,
+        _ => BackupSection::Plugins,
+    })
+}
+
+// 当前表结构的列与主键, 备份中多出的列被忽略
+struct TableInfo {
+    columns: Vec<String>,
+    key: Vec<String>,
+}
+
+fn table_info(conn: &Connection, table: &str) -> Result<TableInfo> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(1)?, row.get::<_, i64>(5)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let mut key: Vec<(i64, String)> = rows
+        .iter()
+        .filter(|(_, pk)| *pk > 0)
+        .map(|(name, pk)| (*pk, name.clone()))
+        .collect();
+    key.sort();
+    Ok(TableInfo {
+        columns: rows.into_iter().map(|(name, _)| name).collect(),
+        key: key.into_iter().map(|(_, name)| name).collect(),
+    })
+}
+
+fn dump(conn: &Connection, table: &str, include_secrets: bool) -> Result<Vec<Row>> {
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {}", table))?;
+    let names: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let mut rows = stmt.query([])?;
+    let mut result = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut map = Row::new();
+        for (i, name) in names.iter().enumerate() {
+            let value = if !include_secrets && SECRET_COLUMNS.contains(&(table, name.as_str())) {
+                Value::String(String::new())
+            } else {
+                match row.get_ref(i)? {
+                    ValueRef::Integer(v) => Value::from(v),
+                    ValueRef::Real(v) => serde_json::Number::from_f64(v)
+                        .map(Value::Number)
+                        .unwrap_or(Value::Null),
+                    ValueRef::Text(v) => Value::String(String::from_utf8_lossy(v).into_owned()),
+                    // 备份的表中没有二进制列
+                    ValueRef::Null | ValueRef::Blob(_) => Value::Null,
+                }
+            };
+            map.insert(name.clone(), value);
+        }
+        result.push(map);
+    }
+    Ok(result)
+}
+
+fn to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(v) => SqlValue::Integer(i64::from(*v)),
+        Value::Number(v) => v
+            .as_i64()
+            .map(SqlValue::Integer)
+            .unwrap_or_else(|| SqlValue::Real(v.as_f64().unwrap_or_default())),
+        Value::String(v) => SqlValue::Text(v.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+// 只保留当前表结构中存在的列
+fn row_values(info: &TableInfo, row: &Row) -> Vec<(String, SqlValue)> {
+    info.columns
+        .iter()
+        .filter_map(|column| row.get(column).map(|v| (column.clone(), to_sql(v))))
+        .collect()
+}
+
+fn key_of(info: &TableInfo, row: &Row) -> String {
+    info.key
+        .iter()
+        .map(|column| match row.get(column) {
+            Some(Value::String(v)) => v.clone(),
+            Some(v) => v.to_string(),
+            None => String::new(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// 查找主键相同的记录, 不存在时返回 None, 否则返回内容是否相同
+fn compare(
+    conn: &Connection,
+    table: &str,
+    info: &TableInfo,
+    values: &[(String, SqlValue)],
+) -> Result<Option<bool>> {
+    let lookup = |column: &String| {
+        values
+            .iter()
+            .find(|(name, _)| name == column)
+            .map(|(_, v)| v)
+    };
+    let Some(keys) = info.key.iter().map(lookup).collect::<Option<Vec<_>>>() else {
+        return Ok(None);
+    };
+    let columns: Vec<&str> = values.iter().map(|(name, _)| name.as_str()).collect();
+    let condition: Vec<String> = info
+        .key
+        .iter()
+        .enumerate()
+        .map(|(i, column)| format!("{} = ?{}", column, i + 1))
+        .collect();
+    let sql = format!(
+        "SELECT {} FROM {} WHERE {}",
+        columns.join(", "),
+        table,
+        condition.join(" AND ")
+    );
+    let existing = conn
+        .query_row(&sql, params_from_iter(keys), |row| {
+            (0..columns.len())
+                .map(|i| row.get::<_, SqlValue>(i))
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })
+        .optional()?;
+    Ok(existing.map(|existing| existing.iter().eq(values.iter().map(|(_, v)| v))))
+}
+
+// 按主键插入或更新, 没有主键时直接插入
+fn upsert(
+    conn: &Connection,
+    table: &str,
+    key: &[String],
+    values: &[(String, SqlValue)],
+) -> Result<()> {
+    let columns: Vec<&str> = values.iter().map(|(name, _)| name.as_str()).collect();
+    let placeholders: Vec<String> = (1..=values.len()).map(|i| format!("?{}", i)).collect();
+    let updates: Vec<String> = columns
+        .iter()
+        .filter(|column| !key.iter().any(|k| k == *column))
+        .map(|column| format!("{0} = excluded.{0}", column))
+        .collect();
+    let conflict = match (key.is_empty(), updates.is_empty()) {
+        (true, _) => String::new(),
+        (false, true) => format!(" ON CONFLICT({}) DO NOTHING", key.join(", ")),
+        (false, false) => format!(
+            " ON CONFLICT({}) DO UPDATE SET {}",
+            key.join(", "),
+            updates.join(", ")
+        ),
+    };
+    conn.execute(
+        &format!(
+            "INSERT INTO {} ({}) VALUES ({}){}",
+            table,
+            columns.join(", "),
+            placeholders.join(", "),
+            conflict
+        ),
+        params_from_iter(values.iter().map(|(_, v)| v)),
+    )?;
+    Ok(())
+}
+
+// 还原一张表, 返回写入的记录的主键
+fn restore_table(
+    conn: &Connection,
+    table: &str,
+    rows: &[Row],
+    strategy: RestoreStrategy,
+    report: &mut SectionReport,
+) -> Result<HashSet<String>> {
+    let info = table_info(conn, table)?;
+    let mut written = HashSet::new();
+    for row in rows {
+        let values = row_values(&info, row);
+        let key = key_of(&info, row);
+        let write = match compare(conn, table, &info, &values)? {
+            None => report.record(format!("{}:{}", table, key), false, strategy),
+            Some(true) => {
+                report.skipped += 1;
+                false
+            }
+            Some(false) => report.record(format!("{}:{}", table, key), true, strategy),
+        };
+        if write {
+            upsert(conn, table, &info.key, &values)?;
+            written.insert(key);
+        }
+    }
+    Ok(written)
+}
+
+// 将备份机器上的附件路径改到本机的插件目录
+fn rebase(path: &str, old_root: &str) -> String {
+    match path.strip_prefix(old_root) {
+        Some(rest) => rest
+            .split(['/', '\\'])
+            .filter(|part| !part.is_empty())
+            .fold(plugins_dir(), |path, part| path.join(part))
+            .to_string_lossy()
+            .to_string(),
+        None => path.to_string(),
+    }
+}
+
+fn rows<'a>(tables: &'a HashMap<String, Vec<Row>>, table: &str) -> &'a [Row] {
+    tables.get(table).map(Vec::as_slice).unwrap_or_default()
+}
+
+// 对话以会话为单位还原, 消息 id 由本机数据库重新分配
+fn restore_chats(
+    conn: &Connection,
+    tables: &HashMap<String, Vec<Row>>,
+    strategy: RestoreStrategy,
+    old_root: &str,
+    report: &mut SectionReport,
+) -> Result<()> {
+    let restored = restore_table(
+        conn,
+        "conversations",
+        rows(tables, "conversations"),
+        strategy,
+        report,
+    )?;
+    for id in &restored {
+        conn.execute("DELETE FROM chat_messages WHERE conversation_id = ?1", [id])?;
+    }
+
+    let info = table_info(conn, "chat_messages")?;
+    let mut ids = HashMap::new();
+    for row in rows(tables, "chat_messages") {
+        let conversation = row.get("conversation_id").and_then(Value::as_str);
+        if !conversation.is_some_and(|id| restored.contains(id)) {
+            continue;
+        }
+        let values: Vec<_> = row_values(&info, row)
+            .into_iter()
+            .filter(|(name, _)| name != "id")
+            .collect();
+        upsert(conn, "chat_messages", &[], &values)?;
+        if let Some(old) = row.get("id").and_then(Value::as_i64) {
+            ids.insert(old, conn.last_insert_rowid());
+        }
+    }
+
+    for table in ["chat_tool_calls", "chat_attachments"] {
+        let info = table_info(conn, table)?;
+        for row in rows(tables, table) {
+            let Some(&message) = row
+                .get("message_id")
+                .and_then(Value::as_i64)
+                .and_then(|id| ids.get(&id))
+            else {
+                continue;
+            };
+            let mut row = row.clone();
+            row.insert("message_id".to_string(), Value::from(message));
+            if let Some(Value::String(path)) = row.get_mut("path") {
+                *path = rebase(path, old_root);
+            }
+            upsert(conn, table, &info.key, &row_values(&info, &row))?;
+        }
+    }
+    Ok(())
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Result<Option<Vec<u8>>> {
+    match archive.by_name(name) {
+        Ok(mut file) => {
+            let mut bytes = Vec::new();
+            file.read_to_end(&mut bytes)?;
+            Ok(Some(bytes))
+        }
+        Err(zip::result::ZipError::FileNotFound) => Ok(None),
+        Err(err) => Err(zip_error(err)),
+    }
+}
+
+fn write_archive(
+    path: &Path,
+    sections: &[BackupSection],
+    include_secrets: bool,
+) -> Result<BackupManifest> {
+    let tables: Vec<(&str, Vec<Row>)> = with_db(|conn| {
+        sections
+            .iter()
+            .flat_map(|section| section.tables())
+            .map(|table| Ok((*table, dump(conn, table, include_secrets)?)))
+            .collect()
+    })?;
+
+    // 环境变量的值保存在系统密钥链中, 需要逐个读取
+    let mut entries = Vec::new();
+    if include_secrets {
+        let stored: Vec<(Option<String>, String, String)> = with_db(|conn| {
+            let mut rows = Vec::new();
+            if sections.contains(&BackupSection::Env) {
+                let mut stmt = conn.prepare("SELECT key, value FROM env_vars")?;
+                for row in stmt.query_map([], |row| Ok((None, row.get(0)?, row.get(1)?)))? {
+                    rows.push(row?);
+                }
+            }
+            if sections.contains(&BackupSection::Plugins) {
+                let mut stmt = conn.prepare("SELECT plugin_id, key, value FROM plugin_env")?;
+                for row in
+                    stmt.query_map([], |row| Ok((Some(row.get(0)?), row.get(1)?, row.get(2)?)))?
+                {
+                    rows.push(row?);
+                }
+            }
+            Ok(rows)
+        })?;
+        for (plugin_id, key, stored) in stored {
+            let value = secrets::resolve(plugin_id.as_deref(), &key, stored)?;
+            if !value.is_empty() {
+                entries.push(SecretEntry {
+                    plugin_id,
+                    key,
+                    value,
+                });
+            }
+        }
+    }
+
+    let mut zip = ZipWriter::new(File::create(path)?);
+    let options = FileOptions::default();
+    let mut counts = BTreeMap::new();
+    for (table, rows) in &tables {
+        zip.start_file(format!("{}/{}.json", TABLES_PREFIX, table), options)
+            .map_err(zip_error)?;
+        zip.write_all(&serde_json::to_vec(rows)?)?;
+        counts.insert(table.to_string(), rows.len());
+    }
+    if sections.contains(&BackupSection::Settings) {
+        zip.start_file(SETTINGS_FILE, options).map_err(zip_error)?;
+        zip.write_all(toml::to_string(&settings::get())?.as_bytes())?;
+    }
+    if !entries.is_empty() {
+        zip.start_file(SECRETS_FILE, options).map_err(zip_error)?;
+        zip.write_all(&serde_json::to_vec(&entries)?)?;
+    }
+
+    // 备份文件本身可能位于插件目录中
+    let root = fs::canonicalize(&plugins_dir())?;
+    let output = fs::canonicalize(path)?;
+    let mut files = 0;
+    let walker = WalkDir::new(&root)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|entry| {
+            let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+            section_of(relative).is_some_and(|section| sections.contains(&section))
+        });
+    for entry in walker {
+        let entry = entry.map_err(|e| PluginError::Io(e.to_string()))?;
+        if !entry.file_type().is_file() || entry.path() == output {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+        let name = format!(
+            "{}/{}",
+            FILES_PREFIX,
+            relative.to_string_lossy().replace('\\', "/")
+        );
+        zip.start_file(name, options).map_err(zip_error)?;
+        std::io::copy(&mut File::open(entry.path())?, &mut zip)?;
+        files += 1;
+    }
+
+    let manifest = BackupManifest {
+        version: BACKUP_VERSION,
+        created_at: Utc::now().timestamp_millis(),
+        plugins_dir: plugins_dir().to_string_lossy().to_string(),
+        sections: sections.to_vec(),
+        secrets: !entries.is_empty(),
+        tables: counts,
+        files,
+    };
+    zip.start_file(MANIFEST, options).map_err(zip_error)?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+    zip.finish().map_err(zip_error)?;
+    Ok(manifest)
+}
+
+/// 将插件、环境变量、设置、助手、定时任务与对话记录备份为一个 zip 文件
+///
+/// # 参数
+/// * `path` - 备份文件的保存路径
+/// * `options` - 备份的内容, 以及是否排除环境变量的值
+#[tauri::command]
+pub async fn backup_create(path: String, options: Option<BackupOptions>) -> Result<BackupInfo> {
+    let options = options.unwrap_or_default();
+    let sections: Vec<BackupSection> = match options.sections {
+        Some(selected) if !selected.is_empty() => ALL_SECTIONS
+            .into_iter()
+            .filter(|section| selected.contains(section))
+            .collect(),
+        _ => ALL_SECTIONS.to_vec(),
+    };
+    let path = PathBuf::from(path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let manifest = match write_archive(&path, &sections, !options.exclude_secrets) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            let _ = fs::remove_file(&path);
+            return Err(err);
+        }
+    };
+    Ok(BackupInfo {
+        path: path.to_string_lossy().to_string(),
+        size: fs::metadata(&path)?.len(),
+        manifest,
+    })
+}
+
+/// 从备份文件还原
+///
+/// # 参数
+/// * `strategy` - merge 只添加本机没有的内容, overwrite 以备份为准, 默认 merge
+/// * `dry_run` - 只返回还原报告与冲突, 不修改任何数据
+///
+/// 部分设置 (如快捷键、剪贴板监听) 在重启应用后生效
+#[tauri::command]
+pub async fn backup_restore(
+    path: String,
+    strategy: Option<RestoreStrategy>,
+    dry_run: Option<bool>,
+) -> Result<RestoreReport> {
+    let strategy = strategy.unwrap_or_default();
+    let dry_run = dry_run.unwrap_or(false);
+    let mut archive = ZipArchive::new(File::open(&path)?).map_err(zip_error)?;
+    let manifest: BackupManifest = serde_json::from_slice(
+        &read_entry(&mut archive, MANIFEST)?
+            .ok_or_else(|| PluginError::Plugin("备份文件缺少清单".to_string()))?,
+    )?;
+    if manifest.version > BACKUP_VERSION {
+        return Err(PluginError::Plugin(format!(
+            "不支持的备份版本: {}",
+            manifest.version
+        )));
+    }
+
+    let sections: Vec<BackupSection> = ALL_SECTIONS
+        .into_iter()
+        .filter(|section| manifest.sections.contains(section))
+        .collect();
+    let mut tables = HashMap::new();
+    for table in sections.iter().flat_map(|section| section.tables()) {
+        if let Some(bytes) = read_entry(&mut archive, &format!("{}/{}.json", TABLES_PREFIX, table))?
+        {
+            tables.insert(
+                table.to_string(),
+                serde_json::from_slice::<Vec<Row>>(&bytes)?,
+            );
+        }
+    }
+    let mut reports: Vec<SectionReport> = sections.iter().map(|s| SectionReport::new(*s)).collect();
+    let report_index = |section: BackupSection| sections.iter().position(|s| *s == section);
+
+    // 数据库在同一事务中还原, 试运行时回滚
+    with_db(|conn| {
+        let tx = conn.transaction()?;
+        for (section, report) in sections.iter().zip(reports.iter_mut()) {
+            if *section == BackupSection::Chats {
+                restore_chats(&tx, &tables, strategy, &manifest.plugins_dir, report)?;
+                continue;
+            }
+            for table in section.tables() {
+                if let Some(rows) = tables.get(*table) {
+                    restore_table(&tx, table, rows, strategy, report)?;
+                }
+            }
+        }
+        if !dry_run {
+            tx.commit()?;
+        }
+        Ok(())
+    })?;
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).map_err(zip_error)?;
+        let Some(relative) = file
+            .enclosed_name()
+            .and_then(|name| name.strip_prefix(FILES_PREFIX).ok())
+            .map(Path::to_path_buf)
+        else {
+            continue;
+        };
+        let Some(index) = section_of(&relative).and_then(report_index) else {
+            continue;
+        };
+        if file.is_dir() {
+            continue;
+        }
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        let target = plugins_dir().join(&relative);
+        let key = format!("files:{}", relative.to_string_lossy().replace('\\', "/"));
+        let report = &mut reports[index];
+        let write = match fs::read(&target) {
+            Ok(existing) if existing == content => {
+                report.skipped += 1;
+                false
+            }
+            Ok(_) => report.record(key, true, strategy),
+            Err(_) => report.record(key, false, strategy),
+        };
+        if write && !dry_run {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, content)?;
+        }
+    }
+
+    if let (Some(index), Some(bytes)) = (
+        report_index(BackupSection::Settings),
+        read_entry(&mut archive, SETTINGS_FILE)?,
+    ) {
+        let restored: Settings = toml::from_str(&String::from_utf8_lossy(&bytes))?;
+        let current = toml::to_string(&settings::get())?;
+        let incoming = toml::to_string(&restored)?;
+        let report = &mut reports[index];
+        // 从未修改过设置时视为新增
+        let write = if current == incoming {
+            report.skipped += 1;
+            false
+        } else {
+            let exists = current != toml::to_string(&Settings::default())?;
+            report.record(SETTINGS_FILE.to_string(), exists, strategy)
+        };
+        if write && !dry_run {
+            settings::update(|s| *s = restored)?;
+        }
+    }
+
+    if let Some(bytes) = read_entry(&mut archive, SECRETS_FILE)? {
+        let entries: Vec<SecretEntry> = serde_json::from_slice(&bytes)?;
+        for entry in entries {
+            let section = match entry.plugin_id {
+                Some(_) => BackupSection::Plugins,
+                None => BackupSection::Env,
+            };
+            let Some(index) = report_index(section) else {
+                continue;
+            };
+            let scope = entry.plugin_id.as_deref();
+            let current = secrets::get(scope, &entry.key)?;
+            let report = &mut reports[index];
+            let write = if current == entry.value {
+                report.skipped += 1;
+                false
+            } else {
+                let key = match scope {
+                    Some(id) => format!("secrets:{}/{}", id, entry.key),
+                    None => format!("secrets:{}", entry.key),
+                };
+                report.record(key, !current.is_empty(), strategy)
+            };
+            if write && !dry_run {
+                secrets::set(scope, &entry.key, &entry.value)?;
+            }
+        }
+    }
+
+    if !dry_run {
+        invalidate_plugin_list().await;
+    }
+    Ok(RestoreReport {
+        dry_run,
+        strategy,
+        manifest,
+        sections: reports,
+    })
+}
@@ -0,0 +1,59 @@
+use futures_util::future::join_all;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Semaphore;
+
+use super::approval;
+use super::deno::{execute_tool, ExecutionResult, PluginError, Result};
+
+// 同时运行的插件进程上限, 所有批量调用共享
+const MAX_PARALLEL_CALLS: usize = 4;
+
+static EXECUTION_SLOTS: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(MAX_PARALLEL_CALLS));
+
+/// 批量执行中的一次工具调用
+#[derive(Debug, Deserialize, Clone)]
+pub struct ToolCall {
+    pub id: String,
+    pub tool: String,
+    #[serde(default)]
+    pub args: Value,
+}
+
+/// 单次调用的结果, result 与 error 只有一个存在
+#[derive(Debug, Serialize)]
+pub struct ToolCallResult {
+    pub result: Option<ExecutionResult>,
+    pub error: Option<PluginError>,
+}
+
+/// 并发执行多个工具调用, 结果按传入顺序返回
+///
+/// 需要确认的调用不执行, 该项的 error 为 ApprovalRequired, 确认后通过 execution_approve 执行
+#[tauri::command]
+pub async fn plugin_execute_many(calls: Vec<ToolCall>) -> Result<Vec<ToolCallResult>> {
+    let tasks = calls.iter().map(|call| async move {
+        approval::check(&call.id, &call.tool, &call.args).await?;
+        let _permit = EXECUTION_SLOTS
+            .acquire()
+            .await
+            .map_err(|e| PluginError::Plugin(e.to_string()))?;
+        execute_tool(&call.id, &call.tool, &call.args, &[]).await
+    });
+
+    Ok(join_all(tasks)
+        .await
+        .into_iter()
+        .map(|result| match result {
+            Ok(result) => ToolCallResult {
+                result: Some(result),
+                error: None,
+            },
+            Err(error) => ToolCallResult {
+                result: None,
+                error: Some(error),
+            },
+        })
+        .collect())
+}
@@ -0,0 +1,238 @@
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+
+use super::builtin;
+use super::db::with_db;
+use super::deno::{load_plugin_list, PluginError, Result};
+use super::knowledge;
+use super::providers::{self, local};
+use crate::utils::gen::generate_id;
+
+// 分享文件的格式标识与版本
+const BOT_FORMAT: &str = "ghostie.bot";
+const BOT_VERSION: u32 = 1;
+
+/// 助手定义
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Bot {
+    /// 新建时留空
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub avatar: Option<String>,
+    #[serde(default)]
+    pub system_prompt: String,
+    /// 模型服务 id, 为空时使用对话时选择的服务
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// 可调用的插件 id
+    #[serde(default)]
+    pub plugins: Vec<String>,
+    /// 可调用的内置工具
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// 引用的知识库 id
+    #[serde(default)]
+    pub knowledge: Vec<String>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+/// 分享文件的内容
+#[derive(Debug, Serialize, Deserialize)]
+struct BotFile {
+    format: String,
+    version: u32,
+    bot: Bot,
+}
+
+/// 导入结果, 本机不存在的引用会被移除
+#[derive(Debug, Serialize)]
+pub struct BotImport {
+    pub bot: Bot,
+    pub warnings: Vec<String>,
+}
+
+fn invalid(message: String) -> PluginError {
+    PluginError::Plugin(message)
+}
+
+// 检查引用的服务、插件与知识库是否存在, 返回不存在的项
+async fn missing_references(bot: &Bot) -> Result<Vec<String>> {
+    let mut missing = Vec::new();
+    if let Some(provider) = bot.provider.as_deref().filter(|p| *p != local::PROVIDER_ID) {
+        if providers::find_provider(provider).is_err() {
+            missing.push(format!("模型服务 {}", provider));
+        }
+    }
+    let plugins = load_plugin_list().await?;
+    for id in bot.plugins.iter().filter(|id| !plugins.contains_key(*id)) {
+        missing.push(format!("插件 {}", id));
+    }
+    for id in bot.knowledge.iter().filter(|id| !knowledge::exists(id)) {
+        missing.push(format!("知识库 {}", id));
+    }
+    Ok(missing)
+}
+
+fn normalize(bot: &mut Bot) -> Result<()> {
+    bot.name = bot.name.trim().to_string();
+    if bot.name.is_empty() {
+        return Err(invalid("助手名称不能为空".to_string()));
+    }
+    if let Some(temperature) = bot.temperature {
+        if !(0.0..=2.0).contains(&temperature) {
+            return Err(invalid(format!("温度应在 0 到 2 之间: {}", temperature)));
+        }
+    }
+    bot.provider = bot.provider.take().filter(|p| !p.trim().is_empty());
+    bot.model = bot.model.take().filter(|m| !m.trim().is_empty());
+    let mut seen = HashSet::new();
+    bot.plugins.retain(|id| seen.insert(id.clone()));
+    let mut seen = HashSet::new();
+    bot.tools
+        .retain(|name| builtin::exists(name) && seen.insert(name.clone()));
+    let mut seen = HashSet::new();
+    bot.knowledge.retain(|id| seen.insert(id.clone()));
+    Ok(())
+}
+
+pub(crate) fn write_bot(bot: &Bot) -> Result<()> {
+    let data = serde_json::to_string(bot)?;
+    with_db(|conn| {
+        conn.execute(
+            "INSERT INTO bots (id, data) VALUES (?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET data = excluded.data",
+            [&bot.id, &data],
+        )?;
+        Ok(())
+    })
+}
+
+/// 按 id 读取助手
+pub(crate) fn find_bot(id: &str) -> Result<Bot> {
+    let data: Option<String> = with_db(|conn| {
+        Ok(conn
+            .query_row("SELECT data FROM bots WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    })?;
+    let data = data.ok_or_else(|| invalid(format!("助手不存在: {}", id)))?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+#[tauri::command]
+pub async fn bot_list() -> Result<Vec<Bot>> {
+    let rows: Vec<String> = with_db(|conn| {
+        let mut stmt = conn.prepare("SELECT data FROM bots")?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })?;
+    let mut bots = rows
+        .iter()
+        .map(|data| Ok(serde_json::from_str(data)?))
+        .collect::<Result<Vec<Bot>>>()?;
+    bots.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    Ok(bots)
+}
+
+#[tauri::command]
+pub async fn bot_get(id: String) -> Result<Bot> {
+    find_bot(&id)
+}
+
+/// 新建或更新助手, id 为空时新建
+///
+/// 保存前检查引用的模型服务、插件与知识库均存在
+#[tauri::command]
+pub async fn bot_save(mut bot: Bot) -> Result<Bot> {
+    normalize(&mut bot)?;
+    let missing = missing_references(&bot).await?;
+    if !missing.is_empty() {
+        return Err(invalid(format!("以下引用不存在: {}", missing.join(", "))));
+    }
+    let now = chrono::Utc::now().timestamp_millis();
+    if bot.id.is_empty() {
+        bot.id = generate_id();
+        bot.created_at = now;
+    } else if let Ok(existing) = find_bot(&bot.id) {
+        bot.created_at = existing.created_at;
+    }
+    bot.updated_at = now;
+    write_bot(&bot)?;
+    Ok(bot)
+}
+
+#[tauri::command]
+pub async fn bot_delete(id: String) -> Result<()> {
+    with_db(|conn| {
+        conn.execute("DELETE FROM bots WHERE id = ?1", [&id])?;
+        Ok(())
+    })
+}
+
+/// 导出为可分享的文件
+#[tauri::command]
+pub async fn bot_export(id: String, path: String) -> Result<()> {
+    let mut bot = find_bot(&id)?;
+    // 服务 id 只在本机有效
+    bot.provider = None;
+    let file = BotFile {
+        format: BOT_FORMAT.to_string(),
+        version: BOT_VERSION,
+        bot,
+    };
+    fs::write(path, serde_json::to_string_pretty(&file)?)?;
+    Ok(())
+}
+
+/// 从分享文件导入为新的助手
+#[tauri::command]
+pub async fn bot_import(path: String) -> Result<BotImport> {
+    let content = fs::read_to_string(&path)?;
+    let file: BotFile =
+        serde_json::from_str(&content).map_err(|e| invalid(format!("无效的助手文件: {}", e)))?;
+    if file.format != BOT_FORMAT {
+        return Err(invalid("无效的助手文件".to_string()));
+    }
+    if file.version > BOT_VERSION {
+        return Err(invalid("助手文件的版本过新, 请先升级应用".to_string()));
+    }
+    let mut bot = file.bot;
+    normalize(&mut bot)?;
+
+    let warnings = missing_references(&bot).await?;
+    let plugins = load_plugin_list().await?;
+    bot.plugins.retain(|id| plugins.contains_key(id));
+    bot.knowledge.retain(|id| knowledge::exists(id));
+    if bot
+        .provider
+        .as_deref()
+        .is_some_and(|p| p != local::PROVIDER_ID && providers::find_provider(p).is_err())
+    {
+        bot.provider = None;
+    }
+
+    let now = chrono::Utc::now().timestamp_millis();
+    bot.id = generate_id();
+    bot.created_at = now;
+    bot.updated_at = now;
+    write_bot(&bot)?;
+    Ok(BotImport { bot, warnings })
+}
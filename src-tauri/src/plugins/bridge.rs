@@ -0,0 +1,156 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::pin::Pin;
+
+use super::approval;
+use super::deno::{execute_tool, find_plugin, ExecutionResult, PluginError, Result};
+use super::host::{self, HostPermission};
+use super::storage;
+
+// 插件间调用的最大嵌套层数
+const MAX_INVOKE_DEPTH: usize = 5;
+
+/// 插件进程发起宿主调用时, stdout 中请求行的前缀
+pub(crate) const RPC_PREFIX: &str = "@@echo-rpc@@";
+
+// 插件发起的宿主调用
+#[derive(Deserialize)]
+struct BridgeRequest {
+    id: u64,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// 处理插件进程的宿主调用, 每次执行对应一个实例
+pub(crate) struct Bridge {
+    plugin_id: String,
+    /// 调用链, 依次为 "插件id/工具名", 最后一项为当前工具
+    chain: Vec<String>,
+}
+
+fn param<'a>(params: &'a Value, name: &str) -> Result<&'a str> {
+    params[name]
+        .as_str()
+        .ok_or_else(|| PluginError::Plugin(format!("缺少参数: {}", name)))
+}
+
+impl Bridge {
+    pub(crate) fn new(plugin_id: &str, tool: &str, parents: &[String]) -> Self {
+        let mut chain = parents.to_vec();
+        chain.push(format!("{}/{}", plugin_id, tool));
+        Self {
+            plugin_id: plugin_id.to_string(),
+            chain,
+        }
+    }
+
+    // 调用其他插件的工具, 返回其执行结果
+    async fn invoke(&self, params: &Value) -> Result<Value> {
+        let target = param(params, "plugin")?;
+        let tool = param(params, "tool")?;
+        let frame = format!("{}/{}", target, tool);
+        if self.chain.contains(&frame) {
+            return Err(PluginError::Plugin(format!(
+                "检测到循环调用: {} -> {}",
+                self.chain.join(" -> "),
+                frame
+            )));
+        }
+        if self.chain.len() >= MAX_INVOKE_DEPTH {
+            return Err(PluginError::Plugin(format!(
+                "插件调用层数超过上限 ({})",
+                MAX_INVOKE_DEPTH
+            )));
+        }
+
+        let args = match &params["args"] {
+            Value::Null => json!({}),
+            args => args.clone(),
+        };
+        // 被调用的工具需要确认时同样等待用户确认, 避免绕过确认
+        approval::check(target, tool, &args).await?;
+        // 插件间调用会递归回到执行流程, 需要装箱
+        let future: Pin<Box<dyn Future<Output = Result<ExecutionResult>> + Send + '_>> =
+            Box::pin(execute_tool(target, tool, &args, &self.chain));
+        Ok(future.await?.result)
+    }
+
+    // 检查插件是否声明了对应的宿主能力
+    async fn require(&self, permission: HostPermission) -> Result<()> {
+        if find_plugin(&self.plugin_id)
+            .await?
+            .permissions
+            .contains(&permission)
+        {
+            return Ok(());
+        }
+        Err(PluginError::Plugin(format!(
+            "插件未声明权限: {}",
+            serde_json::to_value(permission)?
+                .as_str()
+                .unwrap_or_default()
+        )))
+    }
+
+    /// 处理一行请求, 返回写回插件进程的响应
+    pub(crate) async fn respond(&self, request: &str) -> String {
+        let request: BridgeRequest = match serde_json::from_str(request) {
+            Ok(request) => request,
+            Err(err) => {
+                return json!({ "id": null, "error": format!("无效的宿主调用: {}", err) })
+                    .to_string()
+            }
+        };
+        match self.handle(&request.method, &request.params).await {
+            Ok(result) => json!({ "id": request.id, "result": result }),
+            Err(err) => json!({ "id": request.id, "error": err.to_string() }),
+        }
+        .to_string()
+    }
+
+    async fn handle(&self, method: &str, params: &Value) -> Result<Value> {
+        let id = &self.plugin_id;
+        match method {
+            "storage.get" => storage::get(id, param(params, "key")?).await,
+            "storage.set" => {
+                storage::set(id, param(params, "key")?, params["value"].clone()).await?;
+                Ok(Value::Null)
+            }
+            "storage.delete" => Ok(Value::Bool(
+                storage::delete(id, param(params, "key")?).await?,
+            )),
+            "storage.keys" => Ok(json!(storage::keys(id).await?)),
+            "invoke" => self.invoke(params).await,
+            "clipboard.read" => {
+                self.require(HostPermission::Clipboard).await?;
+                Ok(Value::String(host::clipboard_read().await?))
+            }
+            "clipboard.write" => {
+                self.require(HostPermission::Clipboard).await?;
+                host::clipboard_write(param(params, "text")?.to_string()).await?;
+                Ok(Value::Null)
+            }
+            "notification.show" => {
+                self.require(HostPermission::Notification).await?;
+                let body = params["body"].as_str().unwrap_or_default().to_string();
+                host::notify(param(params, "title")?.to_string(), body).await?;
+                Ok(Value::Null)
+            }
+            "open.url" => {
+                self.require(HostPermission::Open).await?;
+                host::open_url(param(params, "url")?)?;
+                Ok(Value::Null)
+            }
+            "dialog.message" | "dialog.confirm" => {
+                self.require(HostPermission::Dialog).await?;
+                let title = params["title"].as_str().map(|t| t.to_string());
+                let message = param(params, "message")?.to_string();
+                let confirmed = host::dialog(title, message, method == "dialog.confirm").await;
+                Ok(Value::Bool(confirmed))
+            }
+            _ => Err(PluginError::Plugin(format!("未知的宿主方法: {}", method))),
+        }
+    }
+}
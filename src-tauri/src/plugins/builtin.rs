@@ -0,0 +1,132 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+
+use super::deno::{PluginError, Result};
+use super::ocr;
+use super::web::{self, FetchOptions};
+use super::web_search;
+
+/// 内置工具, 无需安装插件即可提供给助手调用
+#[derive(Debug, Serialize, Clone)]
+pub struct BuiltinTool {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub parameters: Value,
+}
+
+/// 全部内置工具的定义
+pub(crate) fn tools() -> Vec<BuiltinTool> {
+    vec![
+        BuiltinTool {
+            name: "web_fetch",
+            description: "下载网页并提取正文, 返回标题与 Markdown 格式的内容",
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "url": { "type": "string", "description": "http 或 https 地址" },
+                    "full_page": {
+                        "type": "boolean",
+                        "description": "为 true 时返回整个页面而不只是正文"
+                    }
+                },
+                "required": ["url"]
+            }),
+        },
+        BuiltinTool {
+            name: "web_search",
+            description: "搜索网页, 返回结果的标题、地址与摘要",
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "搜索内容" },
+                    "count": { "type": "integer", "description": "返回的结果数, 默认 10" }
+                },
+                "required": ["query"]
+            }),
+        },
+        BuiltinTool {
+            name: "ocr",
+            description: "识别图片或截图中的文字",
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "图片文件路径" },
+                    "lang": {
+                        "type": "string",
+                        "description": "tesseract 语言代码, 如 chi_sim+eng"
+                    }
+                },
+                "required": ["path"]
+            }),
+        },
+    ]
+}
+
+pub(crate) fn exists(name: &str) -> bool {
+    tools().iter().any(|t| t.name == name)
+}
+
+/// 生成 OpenAI 函数调用格式的定义, 只包含给出的工具
+pub(crate) fn schemas(names: &[String]) -> Vec<Value> {
+    tools()
+        .into_iter()
+        .filter(|t| names.iter().any(|n| n == t.name))
+        .map(|t| {
+            json!({
+                "type": "function",
+                "function": {
+                    "name": t.name,
+                    "description": t.description,
+                    "parameters": t.parameters,
+                }
+            })
+        })
+        .collect()
+}
+
+fn required_str<'a>(args: &'a Value, key: &str) -> Result<&'a str> {
+    args[key]
+        .as_str()
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| PluginError::Plugin(format!("缺少参数: {}", key)))
+}
+
+/// 执行内置工具
+pub(crate) async fn call(name: &str, args: &Value) -> Result<Value> {
+    match name {
+        "web_fetch" => {
+            let options = FetchOptions {
+                full_page: args["full_page"].as_bool().unwrap_or(false),
+                ..Default::default()
+            };
+            let page = web::fetch(required_str(args, "url")?, &options).await?;
+            Ok(json!({
+                "url": page.url,
+                "title": page.title,
+                "text": page.text,
+            }))
+        }
+        "web_search" => {
+            let count = args["count"].as_u64().unwrap_or(10) as usize;
+            let results = web_search::search(required_str(args, "query")?, None, count).await?;
+            Ok(serde_json::to_value(results)?)
+        }
+        "ocr" => {
+            let path = PathBuf::from(required_str(args, "path")?);
+            let lang = args["lang"].as_str().map(str::to_string);
+            let result =
+                tokio::task::spawn_blocking(move || ocr::recognize(&path, lang.as_deref()))
+                    .await
+                    .map_err(|e| PluginError::Plugin(e.to_string()))??;
+            Ok(Value::String(result.text))
+        }
+        _ => Err(PluginError::Plugin(format!("未知的内置工具: {}", name))),
+    }
+}
+
+/// 列出内置工具, 可在助手中启用
+#[tauri::command]
+pub async fn builtin_tools_list() -> Result<Vec<BuiltinTool>> {
+    Ok(tools())
+}
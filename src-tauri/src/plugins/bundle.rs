@@ -0,0 +1,293 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use super::deno::{
+    find_plugin, load_env_vars, load_plugin_list, plugins_dir, process_plugin_content,
+    register_plugin, Plugin, PluginError, PluginRuntime, Result,
+};
+use super::directory;
+use super::signature;
+use super::wasm;
+use crate::utils::gen::generate_id;
+
+// 包格式版本, 不兼容的修改时递增
+const BUNDLE_VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+const LOCK_FILE: &str = "deno.lock";
+// 目录插件的文件在包中的前缀
+const FILES_PREFIX: &str = "files/";
+
+/// 插件包的清单
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleManifest {
+    version: u32,
+    plugin: Plugin,
+    /// 源码文件在包中的名称, Shell 插件没有源码
+    source: Option<String>,
+    /// 插件用到的环境变量名, 不包含值
+    env_keys: Vec<String>,
+}
+
+/// 导入时插件 id 已存在的处理方式
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// 报错并保留现有插件
+    #[default]
+    Error,
+    /// 覆盖现有插件
+    Replace,
+    /// 以新 id 导入一份副本
+    Copy,
+}
+
+/// 导入插件包的结果
+#[derive(Debug, Serialize)]
+pub struct BundleImport {
+    pub plugin: Plugin,
+    /// 本机尚未配置的环境变量
+    pub missing_env: Vec<String>,
+}
+
+// 插件源码文件的扩展名
+fn source_ext(runtime: PluginRuntime) -> Option<&'static str> {
+    match runtime {
+        PluginRuntime::Deno | PluginRuntime::Node => Some("ts"),
+        PluginRuntime::Python => Some("py"),
+        PluginRuntime::Wasm => Some("wasm"),
+        PluginRuntime::Shell | PluginRuntime::Mcp => None,
+    }
+}
+
+fn lock_path(id: &str) -> PathBuf {
+    plugins_dir().join(format!("{}.lock", id))
+}
+
+fn zip_error(err: zip::result::ZipError) -> PluginError {
+    PluginError::Plugin(format!("插件包读写失败: {}", err))
+}
+
+/// 将插件导出为 zip 包, 返回包的路径
+#[tauri::command]
+pub async fn plugin_export(id: String, path: Option<String>) -> Result<String> {
+    let mut plugin = find_plugin(&id).await?;
+    // 权限决定与依赖缓存属于本机, 不随插件包分享
+    plugin.grants.clear();
+    plugin.deps_cached_at = None;
+    let source = source_ext(plugin.runtime).map(|ext| format!("plugin.{}", ext));
+    let content = match source_ext(plugin.runtime) {
+        Some(ext) => Some(fs::read(plugins_dir().join(format!("{}.{}", id, ext)))?),
+        None => None,
+    };
+
+    // 只记录源码或命令模板中出现的环境变量名
+    let text = match &content {
+        Some(bytes) => String::from_utf8_lossy(bytes).to_string(),
+        None => serde_json::to_string(&plugin.tools)?,
+    };
+    let env_keys = load_env_vars()
+        .await?
+        .into_iter()
+        .map(|var| var.key)
+        .filter(|key| plugin.env.contains(key) || text.contains(key.as_str()))
+        .collect();
+
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let dir = plugins_dir().join("exports");
+            fs::create_dir_all(&dir)?;
+            dir.join(format!("{}.zip", id))
+        }
+    };
+    let mut zip = ZipWriter::new(File::create(&path)?);
+    let options = FileOptions::default();
+    let manifest = BundleManifest {
+        version: BUNDLE_VERSION,
+        plugin,
+        source: source.clone(),
+        env_keys,
+    };
+    zip.start_file(MANIFEST, options).map_err(zip_error)?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+    if let (Some(name), Some(content)) = (source, content) {
+        zip.start_file(name, options).map_err(zip_error)?;
+        zip.write_all(&content)?;
+    }
+    if let Ok(lock) = fs::read(lock_path(&id)) {
+        zip.start_file(LOCK_FILE, options).map_err(zip_error)?;
+        zip.write_all(&lock)?;
+    }
+    let dir = directory::plugin_dir(&id);
+    for file in directory::list_files(&id)? {
+        let name = format!(
+            "{}{}",
+            FILES_PREFIX,
+            file.to_string_lossy().replace('\\', "/")
+        );
+        zip.start_file(name, options).map_err(zip_error)?;
+        zip.write_all(&fs::read(dir.join(file))?)?;
+    }
+    zip.finish().map_err(zip_error)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// 包中的 id 会用作文件名与目录名, 只允许单级名称
+fn check_id(id: &str) -> Result<()> {
+    let mut components = Path::new(id).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) if !id.contains(['/', '\\']) => Ok(()),
+        _ => Err(PluginError::Plugin(format!("插件包中的 id 无效: {}", id))),
+    }
+}
+
+// 没有源码的插件只保留包中声明的信息, 权限决定、签名、哈希等本机状态不沿用
+fn declared_only(plugin: Plugin, id: String) -> Plugin {
+    Plugin {
+        id,
+        name: plugin.name,
+        description: plugin.description,
+        translations: plugin.translations,
+        version: plugin.version,
+        changelog: plugin.changelog,
+        tags: plugin.tags,
+        category: plugin.category,
+        runtime: plugin.runtime,
+        permissions: plugin.permissions,
+        env: plugin.env,
+        service: plugin.service,
+        tests: plugin.tests,
+        tools: plugin.tools,
+        ..Default::default()
+    }
+}
+
+/// 从 zip 包导入插件, 保留包中的锁文件与目录文件
+///
+/// 元数据按包中的源码重新读取, 不沿用包中记录的权限决定、签名与哈希;
+/// 签名在写入任何文件之前校验, 目录文件解压完成后才替换现有目录
+#[tauri::command]
+pub async fn plugin_import_bundle(
+    path: String,
+    on_conflict: Option<ConflictStrategy>,
+    allow_unsigned: Option<bool>,
+) -> Result<BundleImport> {
+    let mut archive = ZipArchive::new(File::open(&path)?).map_err(zip_error)?;
+    let read_entry = |archive: &mut ZipArchive<File>, name: &str| -> Result<Option<Vec<u8>>> {
+        match archive.by_name(name) {
+            Ok(mut file) => {
+                let mut bytes = Vec::new();
+                file.read_to_end(&mut bytes)?;
+                Ok(Some(bytes))
+            }
+            Err(zip::result::ZipError::FileNotFound) => Ok(None),
+            Err(err) => Err(zip_error(err)),
+        }
+    };
+
+    let manifest: BundleManifest = serde_json::from_slice(
+        &read_entry(&mut archive, MANIFEST)?
+            .ok_or_else(|| PluginError::Plugin("插件包缺少清单".to_string()))?,
+    )?;
+    if manifest.version > BUNDLE_VERSION {
+        return Err(PluginError::Plugin(format!(
+            "不支持的插件包版本: {}",
+            manifest.version
+        )));
+    }
+
+    let declared = manifest.plugin;
+    check_id(&declared.id)?;
+    let original_id = declared.id.clone();
+    let exists = load_plugin_list().await?.contains_key(&original_id);
+    let id = match on_conflict.unwrap_or_default() {
+        ConflictStrategy::Error if exists => {
+            return Err(PluginError::Plugin(format!("插件已存在: {}", original_id)));
+        }
+        ConflictStrategy::Copy if exists => generate_id(),
+        _ => original_id.clone(),
+    };
+
+    let ext = source_ext(declared.runtime);
+    let mut source = match (&manifest.source, ext) {
+        (Some(name), Some(_)) => Some(
+            read_entry(&mut archive, name)?
+                .ok_or_else(|| PluginError::Plugin(format!("插件包缺少源码: {}", name)))?,
+        ),
+        _ => None,
+    };
+    let names: Vec<String> = archive
+        .file_names()
+        .filter(|name| name.starts_with(FILES_PREFIX))
+        .map(|name| name.to_string())
+        .collect();
+    // 以新 id 导入目录插件时同步修改入口引用的目录
+    if id != original_id && !names.is_empty() {
+        if let Some(content) = source.as_mut() {
+            *content =
+                directory::retarget_shim(&String::from_utf8_lossy(content), &original_id, &id)
+                    .into_bytes();
+        }
+    }
+
+    // 包中记录的签名状态不可信, 写入任何文件之前重新校验源码
+    let text = source
+        .as_ref()
+        .map(|content| String::from_utf8_lossy(content).to_string())
+        .unwrap_or_else(|| serde_json::to_string(&declared.tools).unwrap_or_default());
+    let info = signature::check(&text, None, allow_unsigned.unwrap_or(false))?;
+
+    // 目录文件先解压到临时目录, 全部成功后再替换现有目录
+    if !names.is_empty() {
+        let staged = plugins_dir().join(format!("temp_import_{}", generate_id()));
+        let mut extract = || -> Result<()> {
+            for name in &names {
+                let relative = directory::check_relative(&name[FILES_PREFIX.len()..])?;
+                let dest = staged.join(relative);
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(dest, read_entry(&mut archive, name)?.unwrap_or_default())?;
+            }
+            Ok(())
+        };
+        if let Err(err) = extract() {
+            let _ = fs::remove_dir_all(&staged);
+            return Err(err);
+        }
+        let dir = directory::plugin_dir(&id);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::rename(&staged, &dir)?;
+    }
+    match read_entry(&mut archive, LOCK_FILE)? {
+        Some(lock) => fs::write(lock_path(&id), lock)?,
+        None if lock_path(&id).exists() => fs::remove_file(lock_path(&id))?,
+        None => {}
+    }
+
+    let mut plugin = match (source, ext) {
+        (Some(module), Some("wasm")) => wasm::load(&id, &module).await?,
+        (Some(_), Some(_)) => process_plugin_content(id, text).await?,
+        _ => declared_only(declared, id),
+    };
+    plugin.signature = Some(info);
+
+    let configured: Vec<String> = load_env_vars().await?.into_iter().map(|v| v.key).collect();
+    let missing_env = manifest
+        .env_keys
+        .into_iter()
+        .filter(|key| !configured.contains(key))
+        .collect();
+    let plugin = register_plugin(plugin).await?;
+    super::service::reload(&plugin.id).await?;
+    Ok(BundleImport {
+        plugin,
+        missing_env,
+    })
+}
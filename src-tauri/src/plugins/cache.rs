@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+
+use super::deno::{plugins_dir, Result};
+
+/// 工具结果缓存策略, 如 { ttl: 300 }
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CachePolicy {
+    /// 缓存有效期 (秒)
+    pub ttl: u64,
+}
+
+// 缓存条目
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    expires_at: i64,
+    result: Value,
+}
+
+fn cache_dir(id: &str) -> PathBuf {
+    plugins_dir().join("cache").join(id)
+}
+
+// 以工具名与参数的哈希作为文件名
+fn entry_path(id: &str, tool: &str, args: &Value) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(tool.as_bytes());
+    hasher.update([0]);
+    hasher.update(args.to_string().as_bytes());
+    cache_dir(id).join(format!("{:x}.json", hasher.finalize()))
+}
+
+/// 读取未过期的缓存结果
+pub(crate) fn get(id: &str, tool: &str, args: &Value) -> Option<Value> {
+    let path = entry_path(id, tool, args);
+    let entry: CacheEntry = serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+    if entry.expires_at <= chrono::Utc::now().timestamp() {
+        let _ = fs::remove_file(path);
+        return None;
+    }
+    Some(entry.result)
+}
+
+/// 写入缓存结果
+pub(crate) fn put(
+    id: &str,
+    tool: &str,
+    args: &Value,
+    policy: &CachePolicy,
+    result: &Value,
+) -> Result<()> {
+    fs::create_dir_all(cache_dir(id))?;
+    let entry = CacheEntry {
+        expires_at: chrono::Utc::now().timestamp() + policy.ttl as i64,
+        result: result.clone(),
+    };
+    fs::write(entry_path(id, tool, args), serde_json::to_string(&entry)?)?;
+    Ok(())
+}
+
+/// 删除插件的缓存, 不传 id 时清空全部缓存
+pub(crate) fn clear(id: Option<&str>) -> Result<()> {
+    let dir = match id {
+        Some(id) => cache_dir(id),
+        None => plugins_dir().join("cache"),
+    };
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plugin_cache_clear(id: Option<String>) -> Result<()> {
+    clear(id.as_deref())
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+
+use super::deno::{load_plugin_list, Plugin, Result};
+use super::i18n;
+use super::meta;
+
+// 函数名称的长度上限, OpenAI 与 MCP 均为 64
+const MAX_TOOL_NAME: usize = 64;
+
+/// 对外暴露的一个工具
+#[derive(Debug, Serialize, Clone)]
+pub struct ExposedTool {
+    /// 对外的工具名称, 格式为 插件名__工具名
+    pub name: String,
+    pub plugin_id: String,
+    pub tool: String,
+}
+
+/// 导出格式
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum SchemaFormat {
+    /// OpenAI 函数调用的 tools 数组
+    Openai,
+    /// 以工具名为键的 JSON Schema 集合
+    JsonSchema,
+}
+
+/// 导出的工具定义
+#[derive(Debug, Serialize)]
+pub struct SchemaExport {
+    /// 可直接传给模型的定义
+    pub tools: Value,
+    /// 工具名对应的插件与工具, 用于调用 plugin_execute
+    pub mapping: Vec<ExposedTool>,
+}
+
+// 只保留字母、数字、下划线与连字符
+fn sanitize(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    name.trim_matches('_').to_string()
+}
+
+/// 为全部已安装的工具生成对外名称, 插件名无法使用或重名时改用插件 id
+///
+/// 名称只由脚本声明的插件名与工具名决定, 不受显示语言与用户修改的名称影响
+pub(crate) fn exposed_tools(plugins: &HashMap<String, Plugin>) -> Vec<ExposedTool> {
+    let mut plugins: Vec<&Plugin> = plugins.values().collect();
+    plugins.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut prefixes: HashMap<String, usize> = HashMap::new();
+    for plugin in &plugins {
+        *prefixes.entry(sanitize(&plugin.name)).or_default() += 1;
+    }
+    let mut used = HashSet::new();
+    let mut tools = Vec::new();
+    for plugin in plugins {
+        let prefix = Some(sanitize(&plugin.name))
+            .filter(|p| !p.is_empty() && prefixes[p] == 1)
+            .unwrap_or_else(|| sanitize(&plugin.id));
+        for tool in &plugin.tools {
+            let mut name = format!("{}__{}", prefix, sanitize(&tool.name));
+            name.truncate(MAX_TOOL_NAME);
+            if !used.insert(name.clone()) {
+                continue;
+            }
+            tools.push(ExposedTool {
+                name,
+                plugin_id: plugin.id.clone(),
+                tool: tool.name.clone(),
+            });
+        }
+    }
+    tools
+}
+
+// 参数 schema, 未声明时为空对象
+fn parameters(schema: Option<&Value>) -> Value {
+    match schema {
+        Some(schema) if schema.is_object() => schema.clone(),
+        _ => json!({ "type": "object", "properties": {} }),
+    }
+}
+
+/// 导出已启用插件的工具定义
+///
+/// # 参数
+/// * `format` - openai 或 json-schema
+/// * `plugins` - 只导出这些插件, 为空时导出全部
+/// * `locale` - 描述使用的语言, 默认为应用设置的语言
+#[tauri::command]
+pub async fn tools_export_schema(
+    format: SchemaFormat,
+    plugins: Option<Vec<String>>,
+    locale: Option<String>,
+) -> Result<SchemaExport> {
+    let installed = load_plugin_list().await?;
+    let locale = i18n::current_locale(locale);
+    let mapping: Vec<ExposedTool> = exposed_tools(&installed)
+        .into_iter()
+        .filter(|t| installed[&t.plugin_id].enabled)
+        .filter(|t| {
+            plugins
+                .as_ref()
+                .map_or(true, |ids| ids.contains(&t.plugin_id))
+        })
+        .collect();
+
+    let mut displayed: HashMap<String, Plugin> = HashMap::new();
+    let mut definitions = Vec::new();
+    for exposed in &mapping {
+        let plugin = displayed
+            .entry(exposed.plugin_id.clone())
+            .or_insert_with(|| {
+                let mut plugin = installed[&exposed.plugin_id].clone();
+                meta::display(&mut plugin, locale.as_deref());
+                plugin
+            });
+        let Some(tool) = plugin.tools.iter().find(|t| t.name == exposed.tool) else {
+            continue;
+        };
+        let description = format!("[{}] {}", plugin.name, tool.description);
+        definitions.push((exposed, description, parameters(tool.parameters.as_ref())));
+    }
+
+    let tools = match format {
+        SchemaFormat::Openai => Value::Array(
+            definitions
+                .into_iter()
+                .map(|(exposed, description, parameters)| {
+                    json!({
+                        "type": "function",
+                        "function": {
+                            "name": exposed.name,
+                            "description": description,
+                            "parameters": parameters,
+                        },
+                    })
+                })
+                .collect(),
+        ),
+        SchemaFormat::JsonSchema => {
+            let mut defs = Map::new();
+            for (exposed, description, mut parameters) in definitions {
+                if let Some(schema) = parameters.as_object_mut() {
+                    schema.insert("title".to_string(), json!(exposed.name));
+                    schema.insert("description".to_string(), json!(description));
+                }
+                defs.insert(exposed.name.clone(), parameters);
+            }
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "$defs": defs,
+            })
+        }
+    };
+    Ok(SchemaExport { tools, mapping })
+}
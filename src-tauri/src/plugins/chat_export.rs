@@ -0,0 +1,325 @@
+use chrono::TimeZone;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::chats::{self, Conversation, StoredMessage};
+use super::db::with_db;
+use super::deno::Result;
+
+/// 导出文件的格式版本, 导入时据此检查兼容性
+pub(crate) const EXPORT_VERSION: u32 = 1;
+pub(crate) const EXPORT_FORMAT: &str = "ghostie.conversation";
+
+/// 导出格式
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+    Html,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+/// JSON 导出的内容
+#[derive(Debug, Serialize)]
+pub struct ConversationExport<'a> {
+    pub format: &'static str,
+    pub version: u32,
+    pub conversation: &'a Conversation,
+    pub messages: &'a [StoredMessage],
+}
+
+const HTML_STYLE: &str = r#"
+body { max-width: 820px; margin: 2rem auto; padding: 0 1rem; color: #1f2328;
+  font: 15px/1.6 -apple-system, "Segoe UI", "PingFang SC", "Microsoft YaHei", sans-serif; }
+header { border-bottom: 1px solid #d0d7de; margin-bottom: 1.5rem; }
+header p { color: #656d76; font-size: 13px; }
+.message { border: 1px solid #d0d7de; border-radius: 8px; padding: .75rem 1rem; margin: 1rem 0; }
+.message.user { background: #f6f8fa; }
+.message.tool { background: #fbfaf4; }
+.role { font-weight: 600; font-size: 13px; color: #656d76; }
+.time { float: right; font-size: 12px; color: #8c959f; }
+.content { white-space: pre-wrap; word-wrap: break-word; }
+pre { background: #f6f8fa; border-radius: 6px; padding: .5rem .75rem; overflow-x: auto;
+  font: 12px/1.5 ui-monospace, SFMono-Regular, Menlo, monospace; }
+.call { margin-top: .5rem; font-size: 13px; }
+.attachments { font-size: 13px; }
+"#;
+
+fn format_time(millis: i64) -> String {
+    chrono::Local
+        .timestamp_millis_opt(millis)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+fn role_name(role: &str) -> &str {
+    match role {
+        "system" => "系统",
+        "user" => "用户",
+        "assistant" => "助手",
+        "tool" => "工具",
+        other => other,
+    }
+}
+
+fn title(conversation: &Conversation) -> &str {
+    if conversation.title.is_empty() {
+        "未命名会话"
+    } else {
+        &conversation.title
+    }
+}
+
+// 模型与时间等元信息
+fn summary(conversation: &Conversation) -> String {
+    let mut parts = Vec::new();
+    if let Some(model) = &conversation.model {
+        match &conversation.provider {
+            Some(provider) => parts.push(format!("模型: {} / {}", provider, model)),
+            None => parts.push(format!("模型: {}", model)),
+        }
+    }
+    parts.push(format!("创建于 {}", format_time(conversation.created_at)));
+    parts.push(format!("{} 条消息", conversation.message_count));
+    parts.join(" · ")
+}
+
+// 参数尽量格式化为缩进的 JSON
+fn pretty_arguments(arguments: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(arguments)
+        .ok()
+        .and_then(|value| serde_json::to_string_pretty(&value).ok())
+        .unwrap_or_else(|| arguments.to_string())
+}
+
+// 代码块的围栏比内容中最长的反引号序列多一个
+fn fence(content: &str) -> String {
+    let longest = content.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn render_markdown(conversation: &Conversation, messages: &[StoredMessage]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", title(conversation));
+    let _ = writeln!(out, "> {}\n", summary(conversation));
+    for stored in messages {
+        let message = &stored.message;
+        let _ = writeln!(
+            out,
+            "## {} · {}\n",
+            role_name(&message.role),
+            format_time(stored.created_at)
+        );
+        if message.role == "tool" {
+            let fence = fence(&message.content);
+            let _ = writeln!(
+                out,
+                "工具结果 `{}`\n\n{}\n{}\n{}\n",
+                message.tool_call_id.as_deref().unwrap_or_default(),
+                fence,
+                message.content,
+                fence
+            );
+        } else if !message.content.is_empty() {
+            let _ = writeln!(out, "{}\n", message.content);
+        }
+        for call in &message.tool_calls {
+            let arguments = pretty_arguments(&call.function.arguments);
+            let fence = fence(&arguments);
+            let _ = writeln!(
+                out,
+                "调用工具 `{}` (`{}`)\n\n{}json\n{}\n{}\n",
+                call.function.name, call.id, fence, arguments, fence
+            );
+        }
+        for attachment in &stored.attachments {
+            let _ = writeln!(out, "- 附件: [{}](<{}>)", attachment.name, attachment.path);
+        }
+        if !stored.attachments.is_empty() {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_html(conversation: &Conversation, messages: &[StoredMessage]) -> String {
+    let title = escape_html(title(conversation));
+    let mut body = String::new();
+    for stored in messages {
+        let message = &stored.message;
+        let _ = write!(
+            body,
+            r#"<section class="message {}"><div class="role">{}<span class="time">{}</span></div>"#,
+            escape_html(&message.role),
+            role_name(&message.role),
+            format_time(stored.created_at)
+        );
+        if message.role == "tool" {
+            let _ = write!(
+                body,
+                r#"<div class="call">工具结果 <code>{}</code></div><pre>{}</pre>"#,
+                escape_html(message.tool_call_id.as_deref().unwrap_or_default()),
+                escape_html(&message.content)
+            );
+        } else if !message.content.is_empty() {
+            let _ = write!(
+                body,
+                r#"<div class="content">{}</div>"#,
+                escape_html(&message.content)
+            );
+        }
+        for call in &message.tool_calls {
+            let _ = write!(
+                body,
+                r#"<div class="call">调用工具 <code>{}</code></div><pre>{}</pre>"#,
+                escape_html(&call.function.name),
+                escape_html(&pretty_arguments(&call.function.arguments))
+            );
+        }
+        if !stored.attachments.is_empty() {
+            body.push_str(r#"<ul class="attachments">"#);
+            for attachment in &stored.attachments {
+                let _ = write!(
+                    body,
+                    r#"<li>附件: <a href="file://{}">{}</a></li>"#,
+                    escape_html(&attachment.path),
+                    escape_html(&attachment.name)
+                );
+            }
+            body.push_str("</ul>");
+        }
+        body.push_str("</section>\n");
+    }
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>{style}</style>\n</head>\n<body>\n\
+         <header><h1>{title}</h1><p>{summary}</p></header>\n{body}</body>\n</html>\n",
+        title = title,
+        style = HTML_STYLE,
+        summary = escape_html(&summary(conversation)),
+        body = body
+    )
+}
+
+fn render(
+    format: ExportFormat,
+    conversation: &Conversation,
+    messages: &[StoredMessage],
+) -> Result<String> {
+    Ok(match format {
+        ExportFormat::Markdown => render_markdown(conversation, messages),
+        ExportFormat::Html => render_html(conversation, messages),
+        ExportFormat::Json => serde_json::to_string_pretty(&ConversationExport {
+            format: EXPORT_FORMAT,
+            version: EXPORT_VERSION,
+            conversation,
+            messages,
+        })?,
+    })
+}
+
+fn load(conversation_id: &str) -> Result<(Conversation, Vec<StoredMessage>)> {
+    with_db(|conn| {
+        let conversation = chats::find_conversation(conn, conversation_id)?;
+        let messages = chats::load_messages(conn, conversation_id)?;
+        Ok((conversation, messages))
+    })
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+// 导出文件名: 标题加 id, 去掉文件名中不允许的字符
+fn file_name(conversation: &Conversation, format: ExportFormat) -> String {
+    let title: String = title(conversation)
+        .chars()
+        .map(|c| {
+            if c.is_control() || "/\\:*?\"<>|".contains(c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .take(60)
+        .collect();
+    format!(
+        "{}-{}.{}",
+        title.trim(),
+        conversation.id,
+        format.extension()
+    )
+}
+
+/// 导出一个会话
+///
+/// # 参数
+/// * `format` - markdown、json 或 html
+/// * `path` - 目标文件路径
+#[tauri::command]
+pub async fn chat_export(
+    conversation_id: String,
+    format: ExportFormat,
+    path: String,
+) -> Result<String> {
+    let (conversation, messages) = load(&conversation_id)?;
+    let content = render(format, &conversation, &messages)?;
+    let path = PathBuf::from(path);
+    write_file(&path, &content)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// 导出全部会话 (含已归档) 到目录, 每个会话一个文件, 返回写入的文件
+#[tauri::command]
+pub async fn chat_export_all(format: ExportFormat, dir: String) -> Result<Vec<String>> {
+    let ids: Vec<String> = with_db(|conn| {
+        let mut stmt = conn.prepare("SELECT id FROM conversations ORDER BY created_at")?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(ids)
+    })?;
+    let dir = PathBuf::from(dir);
+    fs::create_dir_all(&dir)?;
+    let mut files = Vec::new();
+    for id in ids {
+        let (conversation, messages) = load(&id)?;
+        let path = dir.join(file_name(&conversation, format));
+        write_file(&path, &render(format, &conversation, &messages)?)?;
+        files.push(path.to_string_lossy().to_string());
+    }
+    tracing::info!(count = files.len(), dir = %dir.display(), "会话已导出");
+    Ok(files)
+}
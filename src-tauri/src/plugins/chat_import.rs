@@ -0,0 +1,363 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use super::chat_export::{EXPORT_FORMAT, EXPORT_VERSION};
+use super::chats;
+use super::db::with_db;
+use super::deno::{PluginError, Result};
+use super::providers::{ChatMessage, ToolCall};
+
+/// 导入来源
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportSource {
+    /// ChatGPT 导出的 conversations.json 或压缩包
+    Chatgpt,
+    /// Claude 导出的 conversations.json 或压缩包
+    Claude,
+    /// 本应用导出的 JSON
+    Ghostie,
+}
+
+/// 导入结果
+#[derive(Debug, Serialize, Default)]
+pub struct ImportReport {
+    pub imported: usize,
+    /// 已导入过或没有消息的会话
+    pub skipped: usize,
+    pub messages: usize,
+}
+
+// 解析出的会话, id 由来源的会话 id 生成, 重复导入时跳过
+struct Imported {
+    id: String,
+    title: String,
+    model: Option<String>,
+    created_at: i64,
+    updated_at: i64,
+    messages: Vec<(ChatMessage, i64)>,
+}
+
+fn import_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("导入会话失败: {}", message))
+}
+
+// 读取 JSON 文件, 压缩包中读取 conversations.json
+fn read_json(path: &Path) -> Result<Value> {
+    let is_zip = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+    let content = if is_zip {
+        let file = fs::File::open(path)?;
+        let mut archive = zip::ZipArchive::new(file).map_err(import_error)?;
+        let mut entry = archive
+            .by_name("conversations.json")
+            .map_err(|_| import_error("压缩包中没有 conversations.json"))?;
+        let mut content = String::new();
+        entry.read_to_string(&mut content)?;
+        content
+    } else {
+        fs::read_to_string(path)?
+    };
+    Ok(serde_json::from_str(&content)?)
+}
+
+// 秒级浮点时间戳
+fn seconds(value: &Value) -> Option<i64> {
+    value.as_f64().map(|secs| (secs * 1000.0) as i64)
+}
+
+fn iso_time(value: &Value) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(value.as_str()?)
+        .ok()
+        .map(|time| time.timestamp_millis())
+}
+
+fn message(role: &str, content: String) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content,
+        tool_calls: Vec::new(),
+        tool_call_id: None,
+    }
+}
+
+// ChatGPT 的消息内容, 只保留文本部分
+fn chatgpt_content(content: &Value) -> String {
+    match content["content_type"].as_str().unwrap_or_default() {
+        "text" | "multimodal_text" => content["parts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "code" | "execution_output" | "tether_quote" => {
+            content["text"].as_str().unwrap_or_default().to_string()
+        }
+        _ => String::new(),
+    }
+}
+
+// ChatGPT 的消息是一棵树, 从 current_node 回溯到根得到当前显示的分支
+fn parse_chatgpt(conversation: &Value) -> Option<Imported> {
+    let mapping = conversation["mapping"].as_object()?;
+    let mut node = conversation["current_node"]
+        .as_str()
+        .map(str::to_string)
+        .or_else(|| {
+            // 没有 current_node 时沿第一个子节点走到叶子
+            let mut id = mapping
+                .iter()
+                .find(|(_, n)| n["parent"].is_null())
+                .map(|(id, _)| id.clone())?;
+            while let Some(child) = mapping.get(&id).and_then(|n| n["children"][0].as_str()) {
+                id = child.to_string();
+            }
+            Some(id)
+        });
+    let mut path = Vec::new();
+    while let Some(id) = node {
+        let Some(entry) = mapping.get(&id) else {
+            break;
+        };
+        path.push(entry);
+        node = entry["parent"].as_str().map(str::to_string);
+    }
+    path.reverse();
+
+    let created_at = seconds(&conversation["create_time"]).unwrap_or(0);
+    let mut model = None;
+    let mut messages = Vec::new();
+    for entry in path {
+        let item = &entry["message"];
+        if item.is_null() || item["metadata"]["is_visually_hidden_from_conversation"] == true {
+            continue;
+        }
+        let role = match item["author"]["role"].as_str().unwrap_or_default() {
+            "user" => "user",
+            "assistant" => "assistant",
+            "system" => "system",
+            "tool" => "tool",
+            _ => continue,
+        };
+        let content = chatgpt_content(&item["content"]);
+        if content.trim().is_empty() {
+            continue;
+        }
+        if let Some(slug) = item["metadata"]["model_slug"].as_str() {
+            model = Some(slug.to_string());
+        }
+        let time = seconds(&item["create_time"]).unwrap_or(created_at);
+        messages.push((message(role, content), time));
+    }
+    let id = conversation["conversation_id"]
+        .as_str()
+        .or_else(|| conversation["id"].as_str())?;
+    Some(Imported {
+        id: format!("chatgpt-{}", id),
+        title: conversation["title"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        model,
+        created_at,
+        updated_at: seconds(&conversation["update_time"]).unwrap_or(created_at),
+        messages,
+    })
+}
+
+// Claude 的消息文本与附件中提取的内容
+fn claude_content(item: &Value) -> String {
+    let mut text = item["content"]
+        .as_array()
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter(|b| b["type"] == "text")
+                .filter_map(|b| b["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .filter(|text| !text.is_empty())
+        .unwrap_or_else(|| item["text"].as_str().unwrap_or_default().to_string());
+    for attachment in item["attachments"].as_array().into_iter().flatten() {
+        if let Some(extracted) = attachment["extracted_content"].as_str() {
+            text.push_str(&format!(
+                "\n\n[{}]\n{}",
+                attachment["file_name"].as_str().unwrap_or_default(),
+                extracted
+            ));
+        }
+    }
+    text
+}
+
+// 有 parent_message_uuid 时从最新的消息回溯出当前分支, 否则按原顺序
+fn parse_claude(conversation: &Value) -> Option<Imported> {
+    let items = conversation["chat_messages"].as_array()?;
+    let by_id: HashMap<&str, &Value> = items
+        .iter()
+        .filter_map(|item| Some((item["uuid"].as_str()?, item)))
+        .collect();
+    let branched = items
+        .iter()
+        .any(|item| item["parent_message_uuid"].is_string());
+    let ordered: Vec<&Value> = if branched {
+        let latest = items
+            .iter()
+            .max_by_key(|item| iso_time(&item["created_at"]))?;
+        let mut path = vec![latest];
+        let mut current = latest;
+        while let Some(&parent) = current["parent_message_uuid"]
+            .as_str()
+            .and_then(|id| by_id.get(id))
+        {
+            // 数据损坏形成环时停止
+            if path.len() > items.len() {
+                break;
+            }
+            path.push(parent);
+            current = parent;
+        }
+        path.reverse();
+        path
+    } else {
+        items.iter().collect()
+    };
+
+    let created_at = iso_time(&conversation["created_at"]).unwrap_or(0);
+    let messages = ordered
+        .into_iter()
+        .filter_map(|item| {
+            let role = match item["sender"].as_str()? {
+                "human" => "user",
+                "assistant" => "assistant",
+                _ => return None,
+            };
+            let content = claude_content(item);
+            let time = iso_time(&item["created_at"]).unwrap_or(created_at);
+            (!content.trim().is_empty()).then(|| (message(role, content), time))
+        })
+        .collect();
+    Some(Imported {
+        id: format!("claude-{}", conversation["uuid"].as_str()?),
+        title: conversation["name"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        model: conversation["model"].as_str().map(str::to_string),
+        created_at,
+        updated_at: iso_time(&conversation["updated_at"]).unwrap_or(created_at),
+        messages,
+    })
+}
+
+// 本应用导出的 JSON, 保留工具调用
+fn parse_ghostie(export: &Value) -> Result<Imported> {
+    if export["format"] != EXPORT_FORMAT {
+        return Err(import_error("不是本应用导出的会话文件"));
+    }
+    if export["version"].as_u64().unwrap_or(0) > EXPORT_VERSION as u64 {
+        return Err(import_error("导出文件的版本过新, 请先升级应用"));
+    }
+    let conversation = &export["conversation"];
+    let messages = export["messages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|item| {
+            let message = ChatMessage {
+                role: item["role"].as_str().unwrap_or("user").to_string(),
+                content: item["content"].as_str().unwrap_or_default().to_string(),
+                tool_calls: serde_json::from_value::<Vec<ToolCall>>(item["tool_calls"].clone())
+                    .unwrap_or_default(),
+                tool_call_id: item["tool_call_id"].as_str().map(str::to_string),
+            };
+            (message, item["created_at"].as_i64().unwrap_or(0))
+        })
+        .collect();
+    Ok(Imported {
+        id: conversation["id"]
+            .as_str()
+            .ok_or_else(|| import_error("缺少会话 id"))?
+            .to_string(),
+        title: conversation["title"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        model: conversation["model"].as_str().map(str::to_string),
+        created_at: conversation["created_at"].as_i64().unwrap_or(0),
+        updated_at: conversation["updated_at"].as_i64().unwrap_or(0),
+        messages,
+    })
+}
+
+/// 导入其他应用导出的会话
+///
+/// # 参数
+/// * `path` - conversations.json、导出的压缩包或本应用导出的 JSON
+/// * `source` - chatgpt、claude 或 ghostie
+///
+/// ChatGPT 与 Claude 的会话只导入当前显示的分支
+#[tauri::command]
+pub async fn chat_import(path: String, source: ImportSource) -> Result<ImportReport> {
+    let data = read_json(Path::new(&path))?;
+    let mut report = ImportReport::default();
+    let conversations: Vec<Imported> = match source {
+        ImportSource::Ghostie => vec![parse_ghostie(&data)?],
+        ImportSource::Chatgpt | ImportSource::Claude => {
+            let items = data
+                .as_array()
+                .ok_or_else(|| import_error("文件内容应为会话数组"))?;
+            let parse = match source {
+                ImportSource::Chatgpt => parse_chatgpt,
+                _ => parse_claude,
+            };
+            let parsed: Vec<Imported> = items.iter().filter_map(parse).collect();
+            report.skipped += items.len() - parsed.len();
+            parsed
+        }
+    };
+
+    with_db(|conn| {
+        let tx = conn.transaction()?;
+        for conversation in conversations {
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM conversations WHERE id = ?1)",
+                [&conversation.id],
+                |row| row.get(0),
+            )?;
+            if exists || conversation.messages.is_empty() {
+                report.skipped += 1;
+                continue;
+            }
+            chats::insert_conversation(
+                &tx,
+                &conversation.id,
+                &conversation.title,
+                None,
+                conversation.model.as_deref(),
+                conversation.created_at,
+                conversation.updated_at,
+            )?;
+            for (message, time) in &conversation.messages {
+                chats::insert_message(&tx, &conversation.id, message, None, *time)?;
+            }
+            report.imported += 1;
+            report.messages += conversation.messages.len();
+        }
+        tx.commit()?;
+        Ok(())
+    })?;
+    tracing::info!(
+        imported = report.imported,
+        skipped = report.skipped,
+        "会话导入完成"
+    );
+    Ok(report)
+}
@@ -0,0 +1,550 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::db::with_db;
+use super::deno::{plugins_dir, PluginError, Result};
+use super::history::HistoryPage;
+use super::providers::{ChatMessage, FunctionCall, ToolCall};
+use crate::utils::gen::generate_id;
+
+// 默认每页条数
+const DEFAULT_PAGE_SIZE: usize = 50;
+// 未设置标题时使用首条用户消息的前若干个字符
+const TITLE_CHARS: usize = 40;
+
+/// 会话
+#[derive(Debug, Serialize, Clone)]
+pub struct Conversation {
+    pub id: String,
+    pub title: String,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub archived: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub message_count: usize,
+}
+
+/// 消息的附件, 文件复制到数据目录中保存
+#[derive(Debug, Serialize, Clone)]
+pub struct Attachment {
+    pub id: String,
+    pub name: String,
+    pub mime: String,
+    pub size: u64,
+    pub path: String,
+}
+
+/// 工具调用记录, 通过 execution_id 关联执行日志
+#[derive(Debug, Serialize, Clone)]
+pub struct ToolCallRecord {
+    pub call_id: String,
+    pub name: String,
+    pub arguments: String,
+    pub execution_id: Option<String>,
+    pub result: Option<String>,
+}
+
+/// 已保存的消息
+#[derive(Debug, Serialize, Clone)]
+pub struct StoredMessage {
+    pub id: i64,
+    pub conversation_id: String,
+    #[serde(flatten)]
+    pub message: ChatMessage,
+    pub created_at: i64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_records: Vec<ToolCallRecord>,
+}
+
+/// 追加的消息
+#[derive(Debug, Deserialize)]
+pub struct NewMessage {
+    #[serde(flatten)]
+    pub message: ChatMessage,
+    /// 附件文件路径
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    /// tool 消息对应的执行 id
+    #[serde(default)]
+    pub execution_id: Option<String>,
+}
+
+/// 会话筛选条件
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct ChatFilter {
+    /// 为空时只列出未归档的会话
+    pub archived: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConversationPage {
+    pub total: usize,
+    pub conversations: Vec<Conversation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MessagePage {
+    pub total: usize,
+    pub messages: Vec<StoredMessage>,
+}
+
+fn attachments_dir(conversation_id: &str) -> PathBuf {
+    plugins_dir().join("attachments").join(conversation_id)
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+const CONVERSATION_COLUMNS: &str = "c.id, c.title, c.provider, c.model, c.archived, c.created_at, \
+     c.updated_at, (SELECT COUNT(*) FROM chat_messages m WHERE m.conversation_id = c.id)";
+
+fn read_conversation(row: &rusqlite::Row) -> rusqlite::Result<Conversation> {
+    Ok(Conversation {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        provider: row.get(2)?,
+        model: row.get(3)?,
+        archived: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+        message_count: row.get::<_, i64>(7)? as usize,
+    })
+}
+
+pub(crate) fn find_conversation(conn: &Connection, id: &str) -> Result<Conversation> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM conversations c WHERE c.id = ?1",
+            CONVERSATION_COLUMNS
+        ),
+        [id],
+        read_conversation,
+    )
+    .optional()?
+    .ok_or_else(|| PluginError::Plugin(format!("会话不存在: {}", id)))
+}
+
+// 读取一组消息的工具调用与附件
+fn load_details(conn: &Connection, messages: &mut [StoredMessage]) -> Result<()> {
+    let index: HashMap<i64, usize> = messages
+        .iter()
+        .enumerate()
+        .map(|(i, m)| (m.id, i))
+        .collect();
+    let Some((first, last)) = messages
+        .first()
+        .zip(messages.last())
+        .map(|(f, l)| (f.id, l.id))
+    else {
+        return Ok(());
+    };
+    let conversation_id = messages[0].conversation_id.clone();
+
+    let mut stmt = conn.prepare(
+        "SELECT t.message_id, t.call_id, t.name, t.arguments, t.execution_id, t.result
+         FROM chat_tool_calls t JOIN chat_messages m ON m.id = t.message_id
+         WHERE m.conversation_id = ?1 AND t.message_id BETWEEN ?2 AND ?3
+         ORDER BY t.rowid",
+    )?;
+    let rows = stmt.query_map(params![conversation_id, first, last], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            ToolCallRecord {
+                call_id: row.get(1)?,
+                name: row.get(2)?,
+                arguments: row.get(3)?,
+                execution_id: row.get(4)?,
+                result: row.get(5)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (message_id, record) = row?;
+        if let Some(&i) = index.get(&message_id) {
+            messages[i].message.tool_calls.push(ToolCall {
+                id: record.call_id.clone(),
+                function: FunctionCall {
+                    name: record.name.clone(),
+                    arguments: record.arguments.clone(),
+                },
+                ..Default::default()
+            });
+            messages[i].tool_records.push(record);
+        }
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT a.message_id, a.id, a.name, a.mime, a.size, a.path
+         FROM chat_attachments a JOIN chat_messages m ON m.id = a.message_id
+         WHERE m.conversation_id = ?1 AND a.message_id BETWEEN ?2 AND ?3",
+    )?;
+    let rows = stmt.query_map(params![conversation_id, first, last], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            Attachment {
+                id: row.get(1)?,
+                name: row.get(2)?,
+                mime: row.get(3)?,
+                size: row.get::<_, i64>(4)? as u64,
+                path: row.get(5)?,
+            },
+        ))
+    })?;
+    for row in rows {
+        let (message_id, attachment) = row?;
+        if let Some(&i) = index.get(&message_id) {
+            messages[i].attachments.push(attachment);
+        }
+    }
+    Ok(())
+}
+
+fn read_message(row: &rusqlite::Row) -> rusqlite::Result<StoredMessage> {
+    Ok(StoredMessage {
+        id: row.get(0)?,
+        conversation_id: row.get(1)?,
+        message: ChatMessage {
+            role: row.get(2)?,
+            content: row.get(3)?,
+            tool_calls: Vec::new(),
+            tool_call_id: row.get(4)?,
+        },
+        created_at: row.get(5)?,
+        attachments: Vec::new(),
+        tool_records: Vec::new(),
+    })
+}
+
+/// 读取会话的全部消息, 按时间顺序
+pub(crate) fn load_messages(
+    conn: &Connection,
+    conversation_id: &str,
+) -> Result<Vec<StoredMessage>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, conversation_id, role, content, tool_call_id, created_at
+         FROM chat_messages WHERE conversation_id = ?1 ORDER BY id",
+    )?;
+    let mut messages = stmt
+        .query_map([conversation_id], read_message)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    load_details(conn, &mut messages)?;
+    Ok(messages)
+}
+
+// 复制附件到会话目录
+fn copy_attachment(conversation_id: &str, source: &str) -> Result<Attachment> {
+    let source = PathBuf::from(source);
+    let name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| PluginError::Plugin(format!("无效的附件路径: {}", source.display())))?;
+    let id = generate_id();
+    let dir = attachments_dir(conversation_id);
+    fs::create_dir_all(&dir)?;
+    let target = dir.join(format!("{}-{}", id, name));
+    let size = fs::copy(&source, &target)?;
+    Ok(Attachment {
+        id,
+        mime: mime_guess::from_path(&source)
+            .first_or_octet_stream()
+            .to_string(),
+        name,
+        size,
+        path: target.to_string_lossy().to_string(),
+    })
+}
+
+/// 写入会话, 导入时使用原始的时间
+pub(crate) fn insert_conversation(
+    conn: &Connection,
+    id: &str,
+    title: &str,
+    provider: Option<&str>,
+    model: Option<&str>,
+    created_at: i64,
+    updated_at: i64,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO conversations (id, title, provider, model, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![id, title.trim(), provider, model, created_at, updated_at],
+    )?;
+    Ok(())
+}
+
+/// 写入一条消息及其工具调用, tool 消息的结果写回对应的调用, 返回消息 id
+pub(crate) fn insert_message(
+    conn: &Connection,
+    conversation_id: &str,
+    message: &ChatMessage,
+    execution_id: Option<&str>,
+    created_at: i64,
+) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO chat_messages (conversation_id, role, content, tool_call_id, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            conversation_id,
+            message.role,
+            message.content,
+            message.tool_call_id,
+            created_at
+        ],
+    )?;
+    let message_id = conn.last_insert_rowid();
+    for call in &message.tool_calls {
+        conn.execute(
+            "INSERT OR REPLACE INTO chat_tool_calls (message_id, call_id, name, arguments)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                message_id,
+                call.id,
+                call.function.name,
+                call.function.arguments
+            ],
+        )?;
+    }
+    if let Some(call_id) = message
+        .tool_call_id
+        .as_ref()
+        .filter(|_| message.role == "tool")
+    {
+        conn.execute(
+            "UPDATE chat_tool_calls SET result = ?1, execution_id = COALESCE(?2, execution_id)
+             WHERE call_id = ?3 AND message_id IN
+                 (SELECT id FROM chat_messages WHERE conversation_id = ?4)",
+            params![message.content, execution_id, call_id, conversation_id],
+        )?;
+    }
+    Ok(message_id)
+}
+
+/// 追加一条消息并更新会话时间, 用于后台生成的消息
+pub(crate) fn append_message(
+    conversation_id: &str,
+    message: &ChatMessage,
+    execution_id: Option<&str>,
+) -> Result<i64> {
+    let time = now();
+    with_db(|conn| {
+        let tx = conn.transaction()?;
+        let id = insert_message(&tx, conversation_id, message, execution_id, time)?;
+        tx.execute(
+            "UPDATE conversations SET updated_at = ?1 WHERE id = ?2",
+            params![time, conversation_id],
+        )?;
+        tx.commit()?;
+        Ok(id)
+    })
+}
+
+/// 新建会话
+#[tauri::command]
+pub async fn chat_create(
+    title: Option<String>,
+    provider: Option<String>,
+    model: Option<String>,
+) -> Result<Conversation> {
+    let id = generate_id();
+    let time = now();
+    with_db(|conn| {
+        insert_conversation(
+            conn,
+            &id,
+            &title.unwrap_or_default(),
+            provider.as_deref(),
+            model.as_deref(),
+            time,
+            time,
+        )?;
+        find_conversation(conn, &id)
+    })
+}
+
+/// 向会话追加一条消息
+///
+/// assistant 消息的工具调用单独记录, tool 消息会把结果与执行 id 写回对应的调用
+#[tauri::command]
+pub async fn chat_append(conversation_id: String, message: NewMessage) -> Result<StoredMessage> {
+    // 先复制附件, 避免在持有数据库锁时读写文件
+    let attachments = message
+        .attachments
+        .iter()
+        .map(|path| copy_attachment(&conversation_id, path))
+        .collect::<Result<Vec<_>>>()?;
+    let time = now();
+    let NewMessage {
+        message,
+        execution_id,
+        ..
+    } = message;
+
+    with_db(|conn| {
+        let conversation = find_conversation(conn, &conversation_id)?;
+        let tx = conn.transaction()?;
+        let message_id = insert_message(
+            &tx,
+            &conversation_id,
+            &message,
+            execution_id.as_deref(),
+            time,
+        )?;
+        for attachment in &attachments {
+            tx.execute(
+                "INSERT INTO chat_attachments (id, message_id, name, mime, size, path)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    attachment.id,
+                    message_id,
+                    attachment.name,
+                    attachment.mime,
+                    attachment.size as i64,
+                    attachment.path
+                ],
+            )?;
+        }
+        // 首条用户消息作为默认标题
+        if conversation.title.is_empty() && message.role == "user" {
+            let title: String = message.content.trim().chars().take(TITLE_CHARS).collect();
+            tx.execute(
+                "UPDATE conversations SET title = ?1 WHERE id = ?2",
+                params![title, conversation_id],
+            )?;
+        }
+        tx.execute(
+            "UPDATE conversations SET updated_at = ?1 WHERE id = ?2",
+            params![time, conversation_id],
+        )?;
+        tx.commit()?;
+
+        let tool_records = message
+            .tool_calls
+            .iter()
+            .map(|call| ToolCallRecord {
+                call_id: call.id.clone(),
+                name: call.function.name.clone(),
+                arguments: call.function.arguments.clone(),
+                execution_id: None,
+                result: None,
+            })
+            .collect();
+        Ok(StoredMessage {
+            id: message_id,
+            conversation_id: conversation_id.clone(),
+            message,
+            created_at: time,
+            attachments,
+            tool_records,
+        })
+    })
+}
+
+/// 列出会话, 最近更新的在前
+#[tauri::command]
+pub async fn chat_list(
+    filter: Option<ChatFilter>,
+    page: Option<HistoryPage>,
+) -> Result<ConversationPage> {
+    let archived = filter.unwrap_or_default().archived.unwrap_or(false);
+    let page = page.unwrap_or_default();
+    let page_size = page.page_size.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+    with_db(|conn| {
+        let total: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM conversations WHERE archived = ?1",
+            [archived],
+            |row| row.get(0),
+        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM conversations c WHERE c.archived = ?1
+             ORDER BY c.updated_at DESC LIMIT {} OFFSET {}",
+            CONVERSATION_COLUMNS,
+            page_size,
+            page.page * page_size
+        ))?;
+        let conversations = stmt
+            .query_map([archived], read_conversation)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(ConversationPage {
+            total: total as usize,
+            conversations,
+        })
+    })
+}
+
+/// 分页读取会话消息, 按时间顺序
+#[tauri::command]
+pub async fn chat_messages(
+    conversation_id: String,
+    page: Option<HistoryPage>,
+) -> Result<MessagePage> {
+    let page = page.unwrap_or_default();
+    let page_size = page.page_size.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+    with_db(|conn| {
+        let total: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM chat_messages WHERE conversation_id = ?1",
+            [&conversation_id],
+            |row| row.get(0),
+        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, conversation_id, role, content, tool_call_id, created_at
+             FROM chat_messages WHERE conversation_id = ?1 ORDER BY id LIMIT {} OFFSET {}",
+            page_size,
+            page.page * page_size
+        ))?;
+        let mut messages = stmt
+            .query_map([&conversation_id], read_message)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        load_details(conn, &mut messages)?;
+        Ok(MessagePage {
+            total: total as usize,
+            messages,
+        })
+    })
+}
+
+/// 修改会话标题
+#[tauri::command]
+pub async fn chat_rename(id: String, title: String) -> Result<Conversation> {
+    with_db(|conn| {
+        conn.execute(
+            "UPDATE conversations SET title = ?1 WHERE id = ?2",
+            params![title.trim(), id],
+        )?;
+        find_conversation(conn, &id)
+    })
+}
+
+/// 归档或取消归档会话
+#[tauri::command]
+pub async fn chat_archive(id: String, archived: bool) -> Result<Conversation> {
+    with_db(|conn| {
+        conn.execute(
+            "UPDATE conversations SET archived = ?1 WHERE id = ?2",
+            params![archived, id],
+        )?;
+        find_conversation(conn, &id)
+    })
+}
+
+/// 删除会话及其附件
+#[tauri::command]
+pub async fn chat_delete(id: String) -> Result<()> {
+    let deleted =
+        with_db(|conn| Ok(conn.execute("DELETE FROM conversations WHERE id = ?1", [&id])?))?;
+    if deleted == 0 {
+        return Err(PluginError::Plugin(format!("会话不存在: {}", id)));
+    }
+    let dir = attachments_dir(&id);
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
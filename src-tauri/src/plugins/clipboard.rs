@@ -0,0 +1,223 @@
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use super::deno::{find_plugin, PluginError, Result};
+use super::{approval, host, logs, redact};
+use crate::utils::settings::{self, ClipboardMatch, ClipboardRule, ClipboardSettings};
+
+const DEFAULT_ARG_NAME: &str = "text";
+const DEFAULT_MIN_LENGTH: usize = 500;
+const MIN_INTERVAL_MS: u64 = 200;
+
+// 每次修改设置后递增, 旧的监听循环发现不一致时退出
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 剪贴板内容匹配规则时发送的事件
+#[derive(Debug, Serialize, Clone)]
+pub struct ClipboardHit {
+    pub rule: String,
+    pub kind: ClipboardMatch,
+    pub text: String,
+    /// 是否已执行绑定的工具
+    pub triggered: bool,
+}
+
+struct CompiledRule {
+    rule: ClipboardRule,
+    regex: Option<Regex>,
+}
+
+fn clipboard_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("剪贴板监听失败: {}", message))
+}
+
+fn compile(rule: &ClipboardRule) -> Result<CompiledRule> {
+    let regex = match (rule.kind, rule.pattern.as_deref()) {
+        (ClipboardMatch::Regex, Some(pattern)) => Some(
+            Regex::new(pattern)
+                .map_err(|e| clipboard_error(format!("规则 {} 的正则无效: {}", rule.name, e)))?,
+        ),
+        (ClipboardMatch::Regex, None) => {
+            return Err(clipboard_error(format!("规则 {} 缺少 pattern", rule.name)))
+        }
+        _ => None,
+    };
+    Ok(CompiledRule {
+        rule: rule.clone(),
+        regex,
+    })
+}
+
+fn is_url(text: &str) -> bool {
+    let text = text.trim();
+    !text.contains(char::is_whitespace)
+        && url::Url::parse(text).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+// Markdown 代码块, 或多数行带有缩进或代码符号的多行文本
+fn is_code(text: &str) -> bool {
+    if text.contains("```") {
+        return true;
+    }
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    if lines.len() < 3 {
+        return false;
+    }
+    let code_like = lines
+        .iter()
+        .filter(|line| {
+            let trimmed = line.trim_end();
+            line.starts_with("    ")
+                || line.starts_with('\t')
+                || trimmed.ends_with([';', '{', '}', ')', ':'])
+        })
+        .count();
+    code_like * 2 >= lines.len()
+}
+
+fn matches(rule: &CompiledRule, text: &str) -> bool {
+    match rule.rule.kind {
+        ClipboardMatch::Url => is_url(text),
+        ClipboardMatch::Code => is_code(text),
+        ClipboardMatch::Length => {
+            text.chars().count() >= rule.rule.min_length.unwrap_or(DEFAULT_MIN_LENGTH)
+        }
+        ClipboardMatch::Regex => rule.regex.as_ref().is_some_and(|r| r.is_match(text)),
+    }
+}
+
+// 执行规则绑定的工具, 未绑定时返回 false
+async fn trigger(rule: &ClipboardRule, text: &str) -> bool {
+    let (Some(plugin_id), Some(tool)) = (&rule.plugin_id, &rule.tool) else {
+        return false;
+    };
+    let mut args = serde_json::Map::new();
+    args.insert(
+        rule.arg_name
+            .clone()
+            .unwrap_or_else(|| DEFAULT_ARG_NAME.to_string()),
+        Value::String(text.to_string()),
+    );
+    // 无人值守, 需要确认的工具直接拒绝
+    let result =
+        approval::execute_confirmed(plugin_id, tool, Value::Object(args), "剪贴板规则", false)
+            .await;
+    if let Err(err) = result {
+        tracing::warn!(plugin = %plugin_id, error = %err, "剪贴板触发执行失败");
+        let _ = logs::append_logs(
+            plugin_id,
+            Some(tool),
+            &format!("剪贴板触发执行失败 ({}): {}", rule.name, err),
+        );
+    }
+    true
+}
+
+// 只比较哈希, 不保留剪贴板原文
+fn digest(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+async fn handle(app: &AppHandle, config: &ClipboardSettings, rules: &[CompiledRule], text: &str) {
+    if text.trim().is_empty() || text.chars().count() > config.max_length {
+        return;
+    }
+    if config.ignore_secrets && redact::scrub(text) != text {
+        return;
+    }
+    for rule in rules.iter().filter(|rule| matches(rule, text)) {
+        let triggered = trigger(&rule.rule, text).await;
+        let _ = app.emit(
+            "clipboard://match",
+            ClipboardHit {
+                rule: rule.rule.name.clone(),
+                kind: rule.rule.kind,
+                text: text.to_string(),
+                triggered,
+            },
+        );
+    }
+}
+
+async fn watch(app: AppHandle, generation: u64, config: ClipboardSettings) {
+    let rules: Vec<CompiledRule> = config
+        .rules
+        .iter()
+        .filter_map(|rule| match compile(rule) {
+            Ok(rule) => Some(rule),
+            Err(err) => {
+                tracing::warn!(error = %err, "忽略无效的剪贴板规则");
+                None
+            }
+        })
+        .collect();
+    let interval = Duration::from_millis(config.interval_ms.max(MIN_INTERVAL_MS));
+    // 开始监听时已有的内容不触发
+    let mut last = host::clipboard_read().await.ok().map(|text| digest(&text));
+    while GENERATION.load(Ordering::SeqCst) == generation {
+        tokio::time::sleep(interval).await;
+        let Ok(text) = host::clipboard_read().await else {
+            continue;
+        };
+        let current = digest(&text);
+        if last == Some(current) {
+            continue;
+        }
+        last = Some(current);
+        handle(&app, &config, &rules, &text).await;
+    }
+}
+
+/// 按设置启动或停止剪贴板监听
+pub fn start(app: AppHandle) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let config = settings::get().clipboard;
+    if config.enabled {
+        tauri::async_runtime::spawn(watch(app, generation, config));
+    }
+}
+
+#[tauri::command]
+pub async fn clipboard_watch_settings() -> Result<ClipboardSettings> {
+    Ok(settings::get().clipboard)
+}
+
+/// 修改剪贴板监听设置, 保存后按新设置重新监听
+///
+/// # 参数
+/// * `config` - 规则绑定 plugin_id 与 tool 时直接执行工具, 否则只发送 clipboard://match 事件
+#[tauri::command]
+pub async fn clipboard_watch_configure(
+    app: AppHandle,
+    config: ClipboardSettings,
+) -> Result<ClipboardSettings> {
+    for rule in &config.rules {
+        compile(rule)?;
+        match (&rule.plugin_id, &rule.tool) {
+            (Some(plugin_id), Some(tool)) => {
+                let plugin = find_plugin(plugin_id).await?;
+                if !plugin.tools.iter().any(|t| &t.name == tool) {
+                    return Err(PluginError::Plugin(format!("未知函数: {}", tool)));
+                }
+            }
+            (None, None) => {}
+            _ => {
+                return Err(clipboard_error(format!(
+                    "规则 {} 需要同时指定 plugin_id 与 tool",
+                    rule.name
+                )))
+            }
+        }
+    }
+    let updated = settings::update(|s| s.clipboard = config)?;
+    start(app);
+    Ok(updated.clipboard)
+}
@@ -0,0 +1,514 @@
+use once_cell::sync::Lazy;
+use rusqlite::{Connection, Transaction};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::deno::{self, plugins_dir, EnvVar, Plugin, PluginError, Result};
+use super::env;
+use super::history::{self, ExecutionRecord};
+use super::schedule::{self, Schedule, ScheduleRun};
+use super::search;
+use super::stats;
+use crate::utils::migrate::{self, Step};
+
+// 数据库结构版本, 修改表结构时递增并在 MIGRATIONS 中补充升级步骤
+const SCHEMA_VERSION: i64 = 6;
+
+// 旧版本数据库的升级步骤, 环境变量与 list.toml 等旧文件在第一步导入
+const MIGRATIONS: &[Step] = &[
+    Step {
+        version: 1,
+        description: "导入旧版本的 list.toml、.env、执行历史与定时任务",
+    },
+    Step {
+        version: 2,
+        description: "执行历史增加环境配置列",
+    },
+    Step {
+        version: 3,
+        description: "执行历史增加执行 id 列",
+    },
+    Step {
+        version: 4,
+        description: "生成插件调用统计",
+    },
+    Step {
+        version: 5,
+        description: "重建全文搜索索引",
+    },
+    Step {
+        version: 6,
+        description: "环境变量保留 .env 中的注释与空行",
+    },
+];
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS plugins (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    runtime TEXT NOT NULL,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS tools (
+    plugin_id TEXT NOT NULL REFERENCES plugins(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    PRIMARY KEY (plugin_id, name)
+);
+CREATE TABLE IF NOT EXISTS env_vars (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    -- 变量前的注释与空行
+    comment TEXT NOT NULL DEFAULT ''
+);
+-- 最后一个变量之后的注释与空行, 只有一行
+CREATE TABLE IF NOT EXISTS env_footer (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    lines TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS plugin_env (
+    plugin_id TEXT NOT NULL REFERENCES plugins(id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (plugin_id, key)
+);
+CREATE TABLE IF NOT EXISTS env_profiles (
+    name TEXT PRIMARY KEY,
+    created_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS env_profile_vars (
+    profile TEXT NOT NULL REFERENCES env_profiles(name) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (profile, key)
+);
+CREATE TABLE IF NOT EXISTS execution_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    time INTEGER NOT NULL,
+    plugin_id TEXT NOT NULL,
+    tool TEXT NOT NULL,
+    args TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    attempts INTEGER NOT NULL,
+    success INTEGER NOT NULL,
+    output TEXT NOT NULL,
+    profile TEXT,
+    execution_id TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_history_time ON execution_history(time);
+CREATE INDEX IF NOT EXISTS idx_history_plugin ON execution_history(plugin_id, tool);
+CREATE TABLE IF NOT EXISTS tool_stats (
+    plugin_id TEXT NOT NULL,
+    tool TEXT NOT NULL,
+    invocations INTEGER NOT NULL,
+    failures INTEGER NOT NULL,
+    total_duration_ms INTEGER NOT NULL,
+    last_used INTEGER,
+    last_success INTEGER,
+    PRIMARY KEY (plugin_id, tool)
+);
+CREATE TABLE IF NOT EXISTS execution_snapshots (
+    execution_id TEXT PRIMARY KEY,
+    plugin_id TEXT NOT NULL,
+    tool TEXT NOT NULL,
+    version TEXT,
+    args TEXT NOT NULL,
+    env TEXT NOT NULL,
+    profile TEXT,
+    time INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_snapshots_time ON execution_snapshots(time);
+CREATE TABLE IF NOT EXISTS mcp_servers (
+    plugin_id TEXT PRIMARY KEY REFERENCES plugins(id) ON DELETE CASCADE,
+    config TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS providers (
+    id TEXT PRIMARY KEY,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS bots (
+    id TEXT PRIMARY KEY,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS llm_usage (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    time INTEGER NOT NULL,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    bot TEXT,
+    prompt_tokens INTEGER NOT NULL,
+    completion_tokens INTEGER NOT NULL,
+    cost REAL NOT NULL,
+    duration_ms INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_llm_usage_time ON llm_usage(time);
+CREATE TABLE IF NOT EXISTS model_prices (
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    prompt_price REAL NOT NULL,
+    completion_price REAL NOT NULL,
+    PRIMARY KEY (provider, model)
+);
+CREATE TABLE IF NOT EXISTS vector_collections (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    dimension INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS vector_documents (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    collection_id TEXT NOT NULL REFERENCES vector_collections(id) ON DELETE CASCADE,
+    source TEXT,
+    content TEXT NOT NULL,
+    metadata TEXT NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_vector_documents ON vector_documents(collection_id, source);
+CREATE TABLE IF NOT EXISTS kb_sources (
+    id TEXT PRIMARY KEY,
+    collection_id TEXT NOT NULL REFERENCES vector_collections(id) ON DELETE CASCADE,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS kb_files (
+    source_id TEXT NOT NULL REFERENCES kb_sources(id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+    modified INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+    chunks INTEGER NOT NULL,
+    PRIMARY KEY (source_id, path)
+);
+CREATE TABLE IF NOT EXISTS workflows (
+    id TEXT PRIMARY KEY,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS workflow_runs (
+    id TEXT PRIMARY KEY,
+    workflow_id TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_workflow_runs ON workflow_runs(workflow_id, started_at);
+CREATE TABLE IF NOT EXISTS schedules (
+    id TEXT PRIMARY KEY,
+    plugin_id TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS schedule_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    schedule_id TEXT NOT NULL,
+    time INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_schedule_runs ON schedule_runs(schedule_id, time);
+CREATE TABLE IF NOT EXISTS reminders (
+    id TEXT PRIMARY KEY,
+    at INTEGER NOT NULL,
+    fired INTEGER NOT NULL DEFAULT 0,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_reminders ON reminders(fired, at);
+CREATE TABLE IF NOT EXISTS palette_usage (
+    id TEXT PRIMARY KEY,
+    count INTEGER NOT NULL,
+    last_used INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS conversations (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    provider TEXT,
+    model TEXT,
+    archived INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_conversations_updated ON conversations(archived, updated_at);
+CREATE TABLE IF NOT EXISTS chat_messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    role TEXT NOT NULL,
+    content TEXT NOT NULL,
+    tool_call_id TEXT,
+    created_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_chat_messages ON chat_messages(conversation_id, id);
+CREATE TABLE IF NOT EXISTS chat_tool_calls (
+    message_id INTEGER NOT NULL REFERENCES chat_messages(id) ON DELETE CASCADE,
+    call_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    arguments TEXT NOT NULL,
+    execution_id TEXT,
+    result TEXT,
+    PRIMARY KEY (message_id, call_id)
+);
+CREATE TABLE IF NOT EXISTS chat_attachments (
+    id TEXT PRIMARY KEY,
+    message_id INTEGER NOT NULL REFERENCES chat_messages(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    mime TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    path TEXT NOT NULL
+);
+CREATE VIRTUAL TABLE IF NOT EXISTS search_messages USING fts5(body, tokenize = 'trigram');
+CREATE VIRTUAL TABLE IF NOT EXISTS search_conversations USING fts5(title, tokenize = 'trigram');
+CREATE VIRTUAL TABLE IF NOT EXISTS search_plugins
+    USING fts5(name, description, tokenize = 'trigram');
+CREATE VIRTUAL TABLE IF NOT EXISTS search_tools
+    USING fts5(name, description, tokenize = 'trigram');
+CREATE VIRTUAL TABLE IF NOT EXISTS search_results USING fts5(body, tokenize = 'trigram');
+CREATE TRIGGER IF NOT EXISTS search_messages_insert AFTER INSERT ON chat_messages BEGIN
+    INSERT INTO search_messages (rowid, body) VALUES (new.id, new.content);
+END;
+CREATE TRIGGER IF NOT EXISTS search_messages_delete AFTER DELETE ON chat_messages BEGIN
+    DELETE FROM search_messages WHERE rowid = old.id;
+END;
+CREATE TRIGGER IF NOT EXISTS search_conversations_insert AFTER INSERT ON conversations BEGIN
+    INSERT INTO search_conversations (rowid, title) VALUES (new.rowid, new.title);
+END;
+CREATE TRIGGER IF NOT EXISTS search_conversations_update AFTER UPDATE OF title ON conversations
+BEGIN
+    UPDATE search_conversations SET title = new.title WHERE rowid = new.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS search_conversations_delete AFTER DELETE ON conversations BEGIN
+    DELETE FROM search_conversations WHERE rowid = old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS search_plugins_insert AFTER INSERT ON plugins BEGIN
+    INSERT INTO search_plugins (rowid, name, description)
+        VALUES (new.rowid, new.name, COALESCE(new.description, ''));
+END;
+CREATE TRIGGER IF NOT EXISTS search_plugins_update AFTER UPDATE OF name, description ON plugins
+BEGIN
+    UPDATE search_plugins SET name = new.name, description = COALESCE(new.description, '')
+        WHERE rowid = new.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS search_plugins_delete AFTER DELETE ON plugins BEGIN
+    DELETE FROM search_plugins WHERE rowid = old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS search_tools_insert AFTER INSERT ON tools BEGIN
+    INSERT INTO search_tools (rowid, name, description)
+        VALUES (new.rowid, new.name, new.description);
+END;
+CREATE TRIGGER IF NOT EXISTS search_tools_delete AFTER DELETE ON tools BEGIN
+    DELETE FROM search_tools WHERE rowid = old.rowid;
+END;
+CREATE TRIGGER IF NOT EXISTS search_results_insert AFTER INSERT ON execution_history BEGIN
+    INSERT INTO search_results (rowid, body) VALUES (new.id, new.output);
+END;
+CREATE TRIGGER IF NOT EXISTS search_results_delete AFTER DELETE ON execution_history BEGIN
+    DELETE FROM search_results WHERE rowid = old.id;
+END;
+"#;
+
+// 打开或迁移失败时保留错误, 之后的数据库操作都返回该错误, 应用仍可启动并展示迁移报告
+static DB: Lazy<std::result::Result<Mutex<Connection>, String>> = Lazy::new(|| {
+    open().map(Mutex::new).map_err(|err| match err {
+        PluginError::Database(message) => message,
+        err => err.to_string(),
+    })
+});
+
+impl From<rusqlite::Error> for PluginError {
+    fn from(err: rusqlite::Error) -> Self {
+        PluginError::Database(err.to_string())
+    }
+}
+
+fn open() -> Result<Connection> {
+    // 注册 sqlite-vec, 之后打开的连接都可以使用 vec0 虚拟表
+    unsafe {
+        rusqlite::ffi::sqlite3_auto_extension(Some(std::mem::transmute::<
+            *const (),
+            unsafe extern "C" fn(),
+        >(
+            sqlite_vec::sqlite3_vec_init as *const ()
+        )));
+    }
+    let path = plugins_dir().join("echo.db");
+    // 全新安装且没有旧版本文件时直接使用最新结构
+    let fresh = !path.exists() && legacy_files().iter().all(|p| !p.exists());
+    let mut conn = Connection::open(path)?;
+    conn.execute_batch(
+        "PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;",
+    )?;
+    conn.execute_batch(SCHEMA)?;
+    if fresh {
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    } else {
+        migrate(&mut conn)?;
+    }
+    Ok(conn)
+}
+
+/// 打开数据库并完成迁移, 启动时调用以便尽早生成迁移报告, 失败时返回错误而不是中止启动
+pub fn init() -> Result<()> {
+    with_db(|_| Ok(()))
+}
+
+/// 在数据库连接上执行操作
+pub(crate) fn with_db<T>(f: impl FnOnce(&mut Connection) -> Result<T>) -> Result<T> {
+    let db = DB
+        .as_ref()
+        .map_err(|err| PluginError::Database(err.clone()))?;
+    let mut conn = db.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(&mut conn)
+}
+
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version >= SCHEMA_VERSION {
+        return Ok(());
+    }
+    // 版本 0 的数据来自旧版本文件, 导入后会重命名保留, 不需要另做备份
+    let backup = if version > 0 {
+        let path = migrate::backup_path("echo.db", &format!("v{}", version))
+            .map_err(PluginError::Plugin)?;
+        conn.execute("VACUUM INTO ?1", [path.to_string_lossy().to_string()])?;
+        Some(path.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    let tx = conn.transaction()?;
+    let mut report = migrate::run("database", version, MIGRATIONS, |step| {
+        apply_migration(&tx, step)
+    });
+    report.backup = backup;
+    if let Some(err) = report.error.clone() {
+        // 回滚后数据库保持原来的版本
+        drop(tx);
+        migrate::record(report);
+        return Err(PluginError::Database(format!("数据库迁移失败: {}", err)));
+    }
+    tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    tx.commit()?;
+    migrate::record(report);
+    rename_legacy();
+    Ok(())
+}
+
+// 执行指定版本的升级步骤, SCHEMA 已按最新结构建表, 增加列前先检查是否已存在
+fn apply_migration(tx: &Transaction, version: i64) -> Result<()> {
+    match version {
+        1 => import_legacy(tx),
+        2 => add_column(tx, "execution_history", "profile", "TEXT"),
+        3 => add_column(tx, "execution_history", "execution_id", "TEXT"),
+        4 => stats::backfill(tx),
+        5 => search::rebuild(tx),
+        6 => {
+            add_column(tx, "env_vars", "comment", "TEXT NOT NULL DEFAULT ''")?;
+            restore_env_comments(tx)
+        }
+        _ => Err(PluginError::Database(format!(
+            "未知的数据库版本: {}",
+            version
+        ))),
+    }
+}
+
+fn add_column(tx: &Transaction, table: &str, column: &str, kind: &str) -> Result<()> {
+    let exists = tx
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .iter()
+        .any(|name| name == column);
+    if !exists {
+        tx.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {};",
+            table, column, kind
+        ))?;
+    }
+    Ok(())
+}
+
+// 旧版本使用的文件, 迁移后重命名保留
+fn legacy_files() -> Vec<std::path::PathBuf> {
+    vec![
+        plugins_dir().join("list.toml"),
+        plugins_dir().join(".env"),
+        plugins_dir().join("history.jsonl"),
+        plugins_dir().join("schedules.json"),
+        plugins_dir().join("schedules"),
+    ]
+}
+
+fn rename_legacy() {
+    for path in legacy_files().into_iter().filter(|p| p.exists()) {
+        let mut migrated = path.clone().into_os_string();
+        migrated.push(".migrated");
+        let _ = fs::rename(&path, migrated);
+    }
+}
+
+// 读取旧的 list.toml, 文件损坏时尝试 .bak 备份
+fn legacy_plugins() -> HashMap<String, Plugin> {
+    let path = plugins_dir().join("list.toml");
+    [path.clone(), path.with_extension("bak")]
+        .iter()
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .find_map(|content| toml::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn legacy_env() -> Vec<EnvVar> {
+    let content = fs::read_to_string(plugins_dir().join(".env")).unwrap_or_default();
+    env::parse_dotenv(&content)
+        .0
+        .into_iter()
+        .map(|(var, _)| var)
+        .collect()
+}
+
+// 早期版本导入 .env 时丢弃了注释, 按保留的旧文件恢复, 尚未导入时读取原文件
+fn restore_env_comments(tx: &Transaction) -> Result<()> {
+    let content = [".env", ".env.migrated"]
+        .iter()
+        .find_map(|name| fs::read_to_string(plugins_dir().join(name)).ok());
+    match content {
+        Some(content) => env::restore_comments(tx, &content),
+        None => Ok(()),
+    }
+}
+
+fn read_jsonl<T: serde::de::DeserializeOwned>(path: &Path) -> Vec<T> {
+    fs::read_to_string(path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+// 一次性导入旧版本的 TOML/JSON 文件, 无法解析的记录直接跳过
+fn import_legacy(tx: &Transaction) -> Result<()> {
+    for plugin in legacy_plugins().values() {
+        deno::insert_plugin(tx, plugin)?;
+    }
+    deno::insert_env_rows(tx, &legacy_env())?;
+    for record in read_jsonl::<ExecutionRecord>(&plugins_dir().join("history.jsonl")) {
+        history::insert_record(tx, &record)?;
+    }
+
+    let schedules: Vec<Schedule> = fs::read_to_string(plugins_dir().join("schedules.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    for schedule in &schedules {
+        schedule::insert_schedule(tx, schedule)?;
+        let runs_path = plugins_dir()
+            .join("schedules")
+            .join(format!("{}.jsonl", schedule.id));
+        for run in read_jsonl::<ScheduleRun>(&runs_path) {
+            schedule::insert_run(tx, &schedule.id, &run)?;
+        }
+    }
+    Ok(())
+}
@@ -1,16 +1,54 @@
 use once_cell::sync::Lazy;
+use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 use tokio::sync::Mutex;
 use toml;
 
-use crate::utils::file::get_config_dir;
+use super::approval;
+use super::artifacts::{self, Artifact};
+use super::bridge::Bridge;
+use super::cache::{self, CachePolicy};
+use super::db::with_db;
+use super::directory::{self, FileNode};
+use super::duplicate::{self, ContentHash};
+use super::env;
+use super::grants::{self, PermissionGrant};
+use super::harness::TestCase;
+use super::history;
+use super::host::HostPermission;
+use super::i18n::{self, Translation};
+use super::install::PluginSource;
+use super::logs;
+use super::mcp;
+use super::meta::{self, MetaOverrides};
+use super::node;
+use super::profile;
+use super::python;
+use super::rate_limit::{self, RateLimit};
+use super::redact;
+use super::reload;
+use super::replay;
+use super::retry::RetryPolicy;
+use super::runtime::{deno, DenoTask, TaskPlan};
+use super::schedule;
+use super::schema;
+use super::secrets;
+use super::service::{self, ServiceConfig};
+use super::shell;
+use super::signature::{self, SignatureInfo, SignatureStatus};
+use super::stats;
+use super::storage;
+use super::trigger;
+use super::versions;
+use super::wasm;
 use crate::utils::gen::generate_id;
+// 插件目录随当前工作区变化, 每次使用时重新解析
+pub(crate) use crate::utils::workspace::plugins_dir;
 
 // 定义错误类型
 #[derive(Error, Debug, Serialize)]
@@ -23,6 +61,31 @@ pub enum PluginError {
     Toml(String),
     #[error("插件错误: {0}")]
     Plugin(String),
+    #[error("调用过于频繁, 请在 {retry_after} 秒后重试")]
+    RateLimited { retry_after: u64 },
+    #[error("参数校验失败: {}", .0.join("; "))]
+    InvalidArgs(Vec<String>),
+    #[error("插件已禁用: {0}")]
+    Disabled(String),
+    #[error("数据库错误: {0}")]
+    Database(String),
+    #[error("已安装相同的插件: {name}")]
+    Duplicate {
+        id: String,
+        name: String,
+        /// 内容完全一致, 否则仅忽略空白与签名后一致
+        identical: bool,
+    },
+    /// 未通过签名校验, 需要用户确认后以 allow_unsigned 重新导入
+    #[error("{}", signature::rejection(.status))]
+    Unsigned { status: SignatureStatus },
+    #[error("执行需要确认: {plugin_id}/{tool}")]
+    ApprovalRequired {
+        approval_id: String,
+        plugin_id: String,
+        tool: String,
+        expires_at: i64,
+    },
 }
 
 impl From<std::io::Error> for PluginError {
@@ -61,7 +124,25 @@ impl<'a> From<&'a str> for PluginError {
     }
 }
 
-type Result<T> = std::result::Result<T, PluginError>;
+pub(crate) type Result<T> = std::result::Result<T, PluginError>;
+
+// 插件运行时类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginRuntime {
+    #[default]
+    Deno,
+    Wasm,
+    Python,
+    Node,
+    Shell,
+    /// 外部 MCP 服务, 工具由服务提供
+    Mcp,
+}
+
+fn default_enabled() -> bool {
+    true
+}
 
 // 插件信息结构
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -69,22 +150,157 @@ pub struct Plugin {
     pub id: String,
     pub name: String,
     pub description: Option<String>,
+    /// 各语言的名称与描述, name 与 description 为默认文本
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub translations: BTreeMap<String, Translation>,
+    /// 用户修改的显示信息
+    #[serde(default, skip_serializing_if = "MetaOverrides::is_empty")]
+    pub overrides: MetaOverrides,
+    /// 禁用的插件保留在列表中, 但不能执行
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 插件声明的版本号
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// 当前版本的更新说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changelog: Option<String>,
+    /// 标签与分类, 可在元数据中声明或手动设置
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub runtime: PluginRuntime,
+    /// 依赖最近一次成功缓存的时间
+    #[serde(default)]
+    pub deps_cached_at: Option<i64>,
+    /// 插件声明的宿主能力
+    #[serde(default)]
+    pub permissions: Vec<HostPermission>,
+    /// 插件声明需要的全局环境变量, 未声明的不会注入
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<String>,
+    /// 后台服务配置, 仅 Deno 插件支持
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<ServiceConfig>,
+    /// 从链接或仓库安装时记录的来源, 用于检查更新
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PluginSource>,
+    /// 签名校验结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SignatureInfo>,
+    /// 插件声明的测试用例
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tests: Vec<TestCase>,
+    /// 源码哈希, WASM 与 Shell 插件没有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<ContentHash>,
+    /// 用户对 Deno 权限请求的决定
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grants: Vec<PermissionGrant>,
+    /// 源码中使用的可执行命令或删除文件的接口, 非空时全部工具都需要确认
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dangerous_apis: Vec<String>,
     pub tools: Vec<Tool>,
 }
 
+impl Default for Plugin {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            description: None,
+            translations: BTreeMap::new(),
+            overrides: MetaOverrides::default(),
+            enabled: true,
+            version: None,
+            changelog: None,
+            tags: Vec::new(),
+            category: None,
+            runtime: PluginRuntime::default(),
+            deps_cached_at: None,
+            permissions: Vec::new(),
+            env: Vec::new(),
+            service: None,
+            source: None,
+            signature: None,
+            tests: Vec::new(),
+            content_hash: None,
+            grants: Vec::new(),
+            dangerous_apis: Vec::new(),
+            tools: Vec::new(),
+        }
+    }
+}
+
 // 工具信息结构
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Tool {
     pub name: String,
     pub description: String,
+    /// Shell 插件的命令模板, 参数以 {{name}} 引用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Shell 插件的工作目录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// 调用频率限制
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+    /// 失败重试策略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// 跳过参数的 JSON Schema 校验
+    #[serde(default)]
+    pub skip_validation: bool,
+    /// 结果缓存策略, 仅用于幂等的工具
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache: Option<CachePolicy>,
+    /// 会删除文件或执行命令, 由代理调用时需要用户确认
+    #[serde(default)]
+    pub dangerous: bool,
     pub parameters: Option<Value>,
 }
 
+/// 工具执行结果
+#[derive(Debug, Serialize, Clone)]
+pub struct ExecutionResult {
+    pub execution_id: String,
+    pub result: Value,
+    /// 实际尝试次数, 命中缓存时为 0
+    pub attempts: u32,
+    pub duration_ms: u64,
+    /// 是否来自缓存
+    pub cached: bool,
+    /// 工具生成的文件
+    pub artifacts: Vec<Artifact>,
+    /// 仅生成执行计划, 此时 result 为 ExecutionPlan
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+/// 执行计划, 描述将要运行的命令而不实际执行
+#[derive(Debug, Serialize)]
+pub struct ExecutionPlan {
+    pub plugin_id: String,
+    pub tool: String,
+    pub runtime: PluginRuntime,
+    /// 将要运行的命令与启动脚本, 目前只有 Deno 插件提供
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<TaskPlan>,
+    /// 注入的环境变量名, 不包含值
+    pub env_keys: Vec<String>,
+}
+
 // 在文件开头的其他结构体定义附近添加
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PluginWithContent {
     pub info: Plugin,
     pub content: String,
+    /// 目录插件的文件树
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files: Option<Vec<FileNode>>,
 }
 
 // 在其他结构体定义附近添加
@@ -94,136 +310,248 @@ pub struct EnvVar {
     pub value: String,
 }
 
-// 使用 Lazy 静态变量缓存插件目录和 Deno 运行时配置
-static PLUGINS_DIR: Lazy<PathBuf> = Lazy::new(|| {
-    let mut config_dir = get_config_dir().expect("无法获取配置目录");
-    config_dir.push("plugins");
-    fs::create_dir_all(&config_dir).expect("无法创建插件目录");
-    config_dir
-});
+// 单次执行中最多询问的权限数
+const MAX_PERMISSION_PROMPTS: usize = 10;
 
-/// Deno 运行时
-static DENO_RUNTIME: Lazy<DenoRuntime> =
-    Lazy::new(|| DenoRuntime::new().expect("无法初始化 Deno 运行时"));
+/// 插件数据目录对应的环境变量
+pub(crate) const DATA_DIR_ENV: &str = "ECHO_PLUGIN_DATA_DIR";
 
 // 缓存插件列表
 static PLUGIN_CACHE: Lazy<Mutex<Option<HashMap<String, Plugin>>>> = Lazy::new(|| Mutex::new(None));
 
-// Deno 运行时封装
-struct DenoRuntime {
-    is_installed: bool,
-    base_args: Vec<String>,
-}
-
-// 运行时实现
-impl DenoRuntime {
-    // 运行时初始化
-    fn new() -> std::io::Result<Self> {
-        let is_installed = Command::new("deno").arg("--version").output().is_ok();
-        Ok(Self {
-            is_installed,
-            base_args: vec![
-                "run".to_string(),
-                "--no-check".to_string(),
-                "--allow-read".to_string(),
-                "--allow-write".to_string(),
-                "--allow-net".to_string(),
-                "--allow-env".to_string(),
-                "--allow-run".to_string(),
-            ],
-        })
+/// 读取全局环境变量, 值从系统密钥链中取出
+pub(crate) async fn load_env_vars() -> Result<Vec<EnvVar>> {
+    with_db(|conn| {
+        let mut stmt = conn.prepare("SELECT key, value FROM env_vars ORDER BY position")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.into_iter()
+            .map(|(key, stored)| {
+                let value = secrets::resolve(None, &key, stored)?;
+                Ok(EnvVar { key, value })
+            })
+            .collect()
+    })
+}
+
+/// 覆盖保存全部环境变量, 值写入系统密钥链, 数据库只保留变量名
+pub(crate) fn insert_env_vars(conn: &Connection, vars: &[EnvVar]) -> Result<()> {
+    let mut stmt = conn.prepare("SELECT key FROM env_vars")?;
+    let existing = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for key in existing
+        .iter()
+        .filter(|key| !vars.iter().any(|v| &v.key == *key))
+    {
+        secrets::delete(None, key)?;
+    }
+    for var in vars {
+        secrets::set(None, &var.key, &var.value)?;
     }
+    let names: Vec<EnvVar> = vars
+        .iter()
+        .map(|var| EnvVar {
+            key: var.key.clone(),
+            value: String::new(),
+        })
+        .collect();
+    insert_env_rows(conn, &names)
+}
 
-    // 执行插件
-    async fn execute(&self, script: &str, env_vars: &[EnvVar]) -> std::io::Result<String> {
-        if !self.is_installed {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "Deno 未安装，请先安装 Deno: https://deno.land/#installation",
-            ));
-        }
+/// 按原样写入环境变量行, 保留的变量沿用原有注释, 旧版本导入的明文值在启动时迁移到密钥链
+pub(crate) fn insert_env_rows(conn: &Connection, vars: &[EnvVar]) -> Result<()> {
+    let comments = env::comments(conn)?;
+    conn.execute("DELETE FROM env_vars", [])?;
+    for (position, var) in vars.iter().enumerate() {
+        conn.execute(
+            "INSERT OR REPLACE INTO env_vars (key, value, position, comment)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                var.key,
+                var.value,
+                position as i64,
+                comments.get(&var.key).map(String::as_str).unwrap_or("")
+            ],
+        )?;
+    }
+    Ok(())
+}
 
-        // 临时文件
-        let temp_file = PLUGINS_DIR.join("temp.ts");
-        fs::write(&temp_file, script)?;
-        // cmd
-        let mut cmd = Command::new("deno");
-        cmd.args(&self.base_args).arg(&temp_file);
+/// 写入插件及其工具, 完整信息以 JSON 保存在 data 列
+pub(crate) fn insert_plugin(conn: &Connection, plugin: &Plugin) -> Result<()> {
+    let runtime = serde_json::to_value(plugin.runtime)?;
+    conn.execute(
+        "INSERT INTO plugins (id, name, description, runtime, enabled, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, description = excluded.description,
+             runtime = excluded.runtime, enabled = excluded.enabled, data = excluded.data",
+        params![
+            plugin.id,
+            plugin.name,
+            plugin.description,
+            runtime.as_str(),
+            plugin.enabled,
+            serde_json::to_string(plugin)?
+        ],
+    )?;
+    conn.execute("DELETE FROM tools WHERE plugin_id = ?1", [&plugin.id])?;
+    for tool in &plugin.tools {
+        conn.execute(
+            "INSERT OR REPLACE INTO tools (plugin_id, name, description) VALUES (?1, ?2, ?3)",
+            params![plugin.id, tool.name, tool.description],
+        )?;
+    }
+    Ok(())
+}
 
-        for var in env_vars {
-            cmd.env(&var.key, &var.value);
+fn read_plugin_list() -> Result<HashMap<String, Plugin>> {
+    with_db(|conn| {
+        let mut stmt = conn.prepare("SELECT data FROM plugins")?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut plugins = HashMap::new();
+        for data in rows {
+            let plugin: Plugin = serde_json::from_str(&data)?;
+            plugins.insert(plugin.id.clone(), plugin);
         }
+        Ok(plugins)
+    })
+}
 
-        let output = cmd.output()?;
-        fs::remove_file(temp_file)?;
-
-        if output.status.success() {
-            Ok(String::from_utf8_lossy(&output.stdout).to_string())
-        } else {
-            Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                String::from_utf8_lossy(&output.stderr).to_string(),
-            ))
-        }
+// 在缓存的插件列表上执行只读操作, 避免复制整个列表
+async fn with_plugin_list<T>(f: impl FnOnce(&HashMap<String, Plugin>) -> T) -> Result<T> {
+    let mut cache = PLUGIN_CACHE.lock().await;
+    if cache.is_none() {
+        *cache = Some(read_plugin_list()?);
     }
+    Ok(f(cache.as_ref().unwrap()))
 }
 
-async fn load_env_vars() -> Result<Vec<EnvVar>> {
-    let path = PLUGINS_DIR.join(".env");
-    if !path.exists() {
-        return Ok(Vec::new());
-    }
+pub(crate) async fn load_plugin_list() -> Result<HashMap<String, Plugin>> {
+    with_plugin_list(|plugins| plugins.clone()).await
+}
 
-    let content = fs::read_to_string(path)?;
-    Ok(content
-        .lines()
-        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
-        .filter_map(|line| {
-            let parts: Vec<&str> = line.splitn(2, '=').collect();
-            if parts.len() == 2 {
-                Some(EnvVar {
-                    key: parts[0].trim().to_string(),
-                    value: parts[1].trim().to_string(),
-                })
-            } else {
-                None
-            }
-        })
-        .collect())
+/// 清空插件列表缓存, 直接修改数据库后调用
+pub(crate) async fn invalidate_plugin_list() {
+    *PLUGIN_CACHE.lock().await = None;
 }
 
-async fn load_plugin_list() -> Result<HashMap<String, Plugin>> {
+// 写入单个插件并同步缓存
+async fn store_plugin(plugin: &Plugin) -> Result<()> {
     let mut cache = PLUGIN_CACHE.lock().await;
-    if let Some(ref cached) = *cache {
-        return Ok(cached.clone());
+    with_db(|conn| {
+        let tx = conn.transaction()?;
+        insert_plugin(&tx, plugin)?;
+        tx.commit()?;
+        Ok(())
+    })?;
+    if let Some(plugins) = cache.as_mut() {
+        plugins.insert(plugin.id.clone(), plugin.clone());
     }
+    Ok(())
+}
 
-    let path = PLUGINS_DIR.join("list.toml");
-    if !path.exists() {
-        let empty_map = HashMap::new();
-        *cache = Some(empty_map.clone());
-        return Ok(empty_map);
+// 删除插件记录, 工具随之级联删除
+async fn delete_plugin(id: &str) -> Result<()> {
+    let mut cache = PLUGIN_CACHE.lock().await;
+    with_db(|conn| {
+        conn.execute("DELETE FROM plugins WHERE id = ?1", [id])?;
+        Ok(())
+    })?;
+    if let Some(plugins) = cache.as_mut() {
+        plugins.remove(id);
     }
+    Ok(())
+}
 
-    let content = fs::read_to_string(path)?;
-    let plugins: HashMap<String, Plugin> = toml::from_str(&content)?;
-    *cache = Some(plugins.clone());
-    Ok(plugins)
+/// 插件独立的数据目录, 不存在时创建
+pub(crate) fn data_dir(id: &str) -> Result<PathBuf> {
+    let dir = plugins_dir().join("data").join(id);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
 }
 
-async fn save_plugin_list(plugins: &HashMap<String, Plugin>) -> Result<()> {
-    let content = toml::to_string(plugins)?;
-    fs::write(PLUGINS_DIR.join("list.toml"), content)?;
+// 插件锁文件路径
+fn lock_path(id: &str) -> PathBuf {
+    plugins_dir().join(format!("{}.lock", id))
+}
 
-    let mut cache = PLUGIN_CACHE.lock().await;
-    *cache = Some(plugins.clone());
+// 重新生成插件锁文件, 同时缓存依赖
+async fn write_lock(id: &str) -> Result<()> {
+    let lock_file = lock_path(id);
+    if lock_file.exists() {
+        fs::remove_file(&lock_file)?;
+    }
+
+    let plugin_file = plugins_dir().join(format!("{}.ts", id));
+    let output = deno().cache(&plugin_file, Some(&lock_file)).await?;
+    let _ = logs::append_logs(id, None, &output.stderr);
+    output.into_stdout()?;
     Ok(())
 }
 
-// 处理插件内容
-async fn process_plugin_content(id: String, content: String) -> Result<Plugin> {
-    let plugin_file = PLUGINS_DIR.join(format!("{}.ts", id));
-    fs::write(&plugin_file, &content)?;
+// 已存在的锁文件
+fn existing_lock(id: &str) -> Option<PathBuf> {
+    Some(lock_path(id)).filter(|path| path.exists())
+}
+
+// 处理插件内容, 根据内容选择运行时
+pub(crate) async fn process_plugin_content(id: String, content: String) -> Result<Plugin> {
+    let mut plugin = if python::is_python(&content) {
+        python::load(&id, &content).await?
+    } else {
+        load_deno_plugin(&id, &content).await?
+    };
+
+    // 运行时变化时清理旧的源码文件
+    let stale = match plugin.runtime {
+        PluginRuntime::Python => vec![plugins_dir().join(format!("{}.ts", id)), lock_path(&id)],
+        _ => vec![python::source_path(&id)],
+    };
+    for path in stale.into_iter().filter(|p| p.exists()) {
+        fs::remove_file(path)?;
+    }
+
+    // 编辑源码时保留启用状态、安装来源、显示信息与用户的签名确认, 元数据未声明标签时保留原有标签
+    let mut overridden = false;
+    if let Some(existing) = load_plugin_list().await?.remove(&id) {
+        plugin.enabled = existing.enabled;
+        plugin.overrides = existing.overrides;
+        plugin.grants = existing.grants;
+        if plugin.tags.is_empty() {
+            plugin.tags = existing.tags;
+        }
+        if plugin.category.is_none() {
+            plugin.category = existing.category;
+        }
+        plugin.source = existing.source;
+        overridden = existing.signature.is_some_and(|s| s.overridden);
+    }
+    plugin.signature = Some(SignatureInfo {
+        status: signature::verify(&content, None),
+        overridden,
+    });
+    plugin.content_hash = Some(duplicate::hash(&content));
+    plugin.dangerous_apis = approval::detect_plugin(&id, &content)?;
+    versions::snapshot(&plugin, &content)?;
+    register_plugin(plugin).await
+}
+
+// 写入 JS/TS 插件并读取元数据, 未安装 Deno 时回退到 Node.js
+async fn load_deno_plugin(id: &str, content: &str) -> Result<Plugin> {
+    let plugin_file = plugins_dir().join(format!("{}.ts", id));
+    reload::remember(id, content);
+    fs::write(&plugin_file, content)?;
+
+    if !deno().is_installed() && node::is_available() {
+        let plugin_info = node::describe(id).await?;
+        return parse_plugin_info(id, PluginRuntime::Node, &plugin_info);
+    }
+
+    write_lock(id).await?;
 
     let script = format!(
         r#"
@@ -237,21 +565,73 @@ async fn process_plugin_content(id: String, content: String) -> Result<Plugin> {
                 if (value.parameters) {{
                    res.parameters = value.parameters;
                 }}
+                if (value.rateLimit) {{
+                   res.rateLimit = value.rateLimit;
+                }}
+                if (value.retry) {{
+                   res.retry = value.retry;
+                }}
+                if (value.skipValidation) {{
+                   res.skipValidation = true;
+                }}
+                if (value.cache) {{
+                   res.cache = value.cache;
+                }}
+                if (value.dangerous) {{
+                   res.dangerous = true;
+                }}
                 return res;
             }});
-        console.log(JSON.stringify({{
+        await __echoOutput({{
             name: plugin.default.name || "undefined",
             description: plugin.default.description || "",
+            version: plugin.default.version || null,
+            changelog: plugin.default.changelog || null,
+            tags: plugin.default.tags || [],
+            category: plugin.default.category || null,
+            tests: plugin.default.tests || [],
+            runtime: plugin.default.runtime || "deno",
+            permissions: plugin.default.permissions || [],
+            env: plugin.default.env || [],
+            service: plugin.default.service
+                ? {{ autostart: !!plugin.default.service.autostart }}
+                : null,
             tools
-        }}));
+        }});
         "#,
         plugin_path = plugin_file.to_string_lossy().replace('\\', "/")
     );
 
-    let env_vars = load_env_vars().await?;
-    let output = DENO_RUNTIME.execute(&script, &env_vars).await?;
-    let plugin_info: Value = serde_json::from_str(&output)?;
+    let task = DenoTask {
+        script,
+        env_vars: env::scoped(id)?,
+        lock_file: existing_lock(id),
+        data_dir: Some(data_dir(id)?),
+        artifacts_dir: None,
+        bridge: None,
+        grants: Vec::new(),
+    };
+    let output = deno().execute(&task).await?;
+    let _ = logs::append_logs(id, None, &output.stderr);
+    let plugin_info: Value = serde_json::from_str(&output.into_stdout()?)?;
 
+    // 插件可通过 runtime: "node" 声明使用 Node.js 执行
+    let runtime = match plugin_info["runtime"].as_str() {
+        Some("node") => PluginRuntime::Node,
+        _ => PluginRuntime::Deno,
+    };
+    let mut plugin = parse_plugin_info(id, runtime, &plugin_info)?;
+    // 生成锁文件时已缓存依赖
+    plugin.deps_cached_at = Some(chrono::Utc::now().timestamp());
+    Ok(plugin)
+}
+
+// 从运行时输出的元数据构建插件信息
+pub(crate) fn parse_plugin_info(
+    id: &str,
+    runtime: PluginRuntime,
+    plugin_info: &Value,
+) -> Result<Plugin> {
     let tools = plugin_info["tools"]
         .as_array()
         .ok_or_else(|| PluginError::Plugin("tools 字段无效".to_string()))?
@@ -265,93 +645,679 @@ async fn process_plugin_content(id: String, content: String) -> Result<Plugin> {
                 .as_str()
                 .ok_or_else(|| PluginError::Plugin("tool description 字段无效".to_string()))?
                 .to_string();
+            let rate_limit = match tool.get("rateLimit").or_else(|| tool.get("rate_limit")) {
+                Some(value) => Some(
+                    serde_json::from_value::<RateLimit>(value.clone()).map_err(|e| {
+                        PluginError::Plugin(format!("tool rateLimit 字段无效: {}", e))
+                    })?,
+                ),
+                None => None,
+            };
+            let cache = match tool.get("cache") {
+                Some(value) => Some(
+                    serde_json::from_value::<CachePolicy>(value.clone())
+                        .map_err(|e| PluginError::Plugin(format!("tool cache 字段无效: {}", e)))?,
+                ),
+                None => None,
+            };
+            let retry = match tool.get("retry") {
+                Some(value) => Some(
+                    serde_json::from_value::<RetryPolicy>(value.clone())
+                        .map_err(|e| PluginError::Plugin(format!("tool retry 字段无效: {}", e)))?,
+                ),
+                None => None,
+            };
             Ok(Tool {
                 name,
                 description,
+                rate_limit,
+                retry,
+                cache,
+                skip_validation: tool
+                    .get("skipValidation")
+                    .or_else(|| tool.get("skip_validation"))
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+                dangerous: tool["dangerous"].as_bool().unwrap_or(false),
                 parameters: if tool.get("parameters").is_some() {
                     Some(tool["parameters"].clone())
                 } else {
                     None
                 },
+                ..Default::default()
             })
         })
         .collect::<Result<Vec<_>>>()?;
+    // name 与 description 可以是字符串或 { 语言: 文本 }
+    let (name, description, translations) =
+        i18n::parse(&plugin_info["name"], &plugin_info["description"])?;
 
-    let plugin = Plugin {
-        id: id.clone(),
-        name: plugin_info["name"]
-            .as_str()
-            .ok_or_else(|| PluginError::Plugin("name 字段无效".to_string()))?
-            .to_string(),
-        description: plugin_info["description"].as_str().map(|s| s.to_string()),
+    Ok(Plugin {
+        id: id.to_string(),
+        name,
+        description,
+        translations,
+        version: plugin_info["version"].as_str().map(|s| s.to_string()),
+        changelog: plugin_info["changelog"].as_str().map(|s| s.to_string()),
+        tags: plugin_info["tags"]
+            .as_array()
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|t| t.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        category: plugin_info["category"].as_str().map(|s| s.to_string()),
+        tests: match plugin_info.get("tests") {
+            Some(value) if !value.is_null() => serde_json::from_value(value.clone())
+                .map_err(|e| PluginError::Plugin(format!("tests 字段无效: {}", e)))?,
+            _ => Vec::new(),
+        },
+        runtime,
+        env: match plugin_info.get("env") {
+            Some(value) if !value.is_null() => serde_json::from_value(value.clone())
+                .map_err(|e| PluginError::Plugin(format!("env 字段无效: {}", e)))?,
+            _ => Vec::new(),
+        },
+        permissions: match plugin_info.get("permissions") {
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|e| PluginError::Plugin(format!("permissions 字段无效: {}", e)))?,
+            None => Vec::new(),
+        },
+        service: match plugin_info.get("service") {
+            Some(value) if !value.is_null() => Some(
+                serde_json::from_value(value.clone())
+                    .map_err(|e| PluginError::Plugin(format!("service 字段无效: {}", e)))?,
+            ),
+            _ => None,
+        },
         tools,
-    };
-
-    let mut plugins = load_plugin_list().await?;
-    plugins.insert(id, plugin.clone());
-    save_plugin_list(&plugins).await?;
+        ..Default::default()
+    })
+}
 
+// 写入插件列表
+pub(crate) async fn register_plugin(plugin: Plugin) -> Result<Plugin> {
+    store_plugin(&plugin).await?;
+    // 插件更新后旧的缓存结果不再可靠
+    cache::clear(Some(&plugin.id))?;
     Ok(plugin)
 }
 
+// 查找已注册的插件
+pub(crate) async fn find_plugin(id: &str) -> Result<Plugin> {
+    with_plugin_list(|plugins| plugins.get(id).cloned())
+        .await?
+        .ok_or_else(|| PluginError::Plugin(format!("插件不存在: {}", id)))
+}
+
+/// 导入插件, 已安装相同内容时返回 Duplicate 错误, force 为 true 时仍作为新插件导入
 #[tauri::command]
-pub async fn plugin_import(content: String) -> Result<Plugin> {
+pub async fn plugin_import(
+    content: String,
+    signature: Option<String>,
+    allow_unsigned: Option<bool>,
+    force: Option<bool>,
+) -> Result<Plugin> {
+    let info = signature::check(
+        &content,
+        signature.as_deref(),
+        allow_unsigned.unwrap_or(false),
+    )?;
+    if !force.unwrap_or(false) {
+        duplicate::check(&content).await?;
+    }
     let id = generate_id();
-    process_plugin_content(id, content).await
+    let mut plugin = process_plugin_content(id, content).await?;
+    plugin.signature = Some(info);
+    register_plugin(plugin).await
 }
 
+/// 列出插件, 名称与描述按 locale 或应用设置的语言返回
 #[tauri::command]
-pub async fn plugins_list() -> Result<HashMap<String, Plugin>> {
-    load_plugin_list().await
+pub async fn plugins_list(locale: Option<String>) -> Result<HashMap<String, Plugin>> {
+    let locale = i18n::current_locale(locale);
+    let mut plugins = load_plugin_list().await?;
+    for plugin in plugins.values_mut() {
+        meta::display(plugin, locale.as_deref());
+    }
+    Ok(plugins)
 }
 
+/// 设置插件的标签与分类
 #[tauri::command]
-pub async fn plugin_get(id: String) -> Result<Option<PluginWithContent>> {
-    let plugins = load_plugin_list().await?;
-
-    Ok(if let Some(plugin) = plugins.get(&id) {
-        let plugin_file = PLUGINS_DIR.join(format!("{}.ts", id));
-        let content = fs::read_to_string(plugin_file)?;
-        Some(PluginWithContent {
-            info: plugin.clone(),
-            content,
+pub async fn plugin_set_tags(
+    id: String,
+    tags: Vec<String>,
+    category: Option<String>,
+) -> Result<Plugin> {
+    let mut plugin = find_plugin(&id).await?;
+    plugin.tags = Vec::new();
+    for tag in tags.iter().map(|tag| tag.trim()) {
+        if !tag.is_empty() && !plugin.tags.iter().any(|t| t == tag) {
+            plugin.tags.push(tag.to_string());
+        }
+    }
+    plugin.category = category.filter(|c| !c.trim().is_empty());
+    register_plugin(plugin).await
+}
+
+/// 按名称、描述、工具名与标签搜索插件, tags 中的标签需全部匹配
+#[tauri::command]
+pub async fn plugins_search(
+    query: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<Vec<Plugin>> {
+    let query = query.unwrap_or_default().trim().to_lowercase();
+    let tags: Vec<String> = tags
+        .unwrap_or_default()
+        .into_iter()
+        .map(|tag| tag.to_lowercase())
+        .collect();
+    let mut plugins: Vec<Plugin> = load_plugin_list()
+        .await?
+        .into_values()
+        .filter(|plugin| {
+            tags.iter()
+                .all(|tag| plugin.tags.iter().any(|t| t.to_lowercase() == *tag))
         })
-    } else {
-        None
+        .filter(|plugin| {
+            let contains = |text: &str| text.to_lowercase().contains(&query);
+            query.is_empty()
+                || contains(&plugin.name)
+                || plugin.description.as_deref().is_some_and(contains)
+                || plugin.overrides.name.as_deref().is_some_and(contains)
+                || plugin
+                    .overrides
+                    .description
+                    .as_deref()
+                    .is_some_and(contains)
+                || plugin.translations.values().any(|t| {
+                    t.name.as_deref().is_some_and(contains)
+                        || t.description.as_deref().is_some_and(contains)
+                })
+                || plugin.category.as_deref().is_some_and(contains)
+                || plugin.tags.iter().any(|t| contains(t))
+                || plugin.tools.iter().any(|t| contains(&t.name))
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    Ok(plugins)
+}
+
+/// 插件列表中的摘要信息, 不包含工具定义
+#[derive(Debug, Serialize, Clone)]
+pub struct PluginSummary {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    pub version: Option<String>,
+    pub enabled: bool,
+    pub runtime: PluginRuntime,
+    pub tags: Vec<String>,
+    pub category: Option<String>,
+    pub tool_count: usize,
+}
+
+/// 分页列表的排序方式
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginSort {
+    #[default]
+    Name,
+    NameDesc,
+    Runtime,
+    ToolCount,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PluginPage {
+    pub total: usize,
+    pub items: Vec<PluginSummary>,
+}
+
+// 读取插件源码, WASM 与 Shell 插件没有可编辑的源码
+pub(crate) fn read_content(plugin: &Plugin) -> Result<String> {
+    Ok(match plugin.runtime {
+        PluginRuntime::Deno | PluginRuntime::Node => {
+            fs::read_to_string(plugins_dir().join(format!("{}.ts", plugin.id)))?
+        }
+        PluginRuntime::Python => fs::read_to_string(python::source_path(&plugin.id))?,
+        PluginRuntime::Wasm | PluginRuntime::Shell | PluginRuntime::Mcp => String::new(),
     })
 }
 
+/// 分页返回插件摘要
+#[tauri::command]
+pub async fn plugins_list_page(
+    offset: Option<usize>,
+    limit: Option<usize>,
+    sort: Option<PluginSort>,
+    locale: Option<String>,
+) -> Result<PluginPage> {
+    let locale = i18n::current_locale(locale);
+    let mut summaries: Vec<PluginSummary> = with_plugin_list(|plugins| {
+        plugins
+            .values()
+            .map(|plugin| {
+                let mut plugin = plugin.clone();
+                meta::display(&mut plugin, locale.as_deref());
+                plugin
+            })
+            .map(|plugin| PluginSummary {
+                id: plugin.id,
+                name: plugin.name,
+                description: plugin.description,
+                icon: plugin.overrides.icon,
+                version: plugin.version,
+                enabled: plugin.enabled,
+                runtime: plugin.runtime,
+                tags: plugin.tags,
+                category: plugin.category,
+                tool_count: plugin.tools.len(),
+            })
+            .collect()
+    })
+    .await?;
+
+    let by_name = |a: &PluginSummary, b: &PluginSummary| {
+        a.name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then_with(|| a.id.cmp(&b.id))
+    };
+    match sort.unwrap_or_default() {
+        PluginSort::Name => summaries.sort_by(by_name),
+        PluginSort::NameDesc => summaries.sort_by(|a, b| by_name(b, a)),
+        PluginSort::Runtime => summaries.sort_by(|a, b| {
+            format!("{:?}", a.runtime)
+                .cmp(&format!("{:?}", b.runtime))
+                .then_with(|| by_name(a, b))
+        }),
+        PluginSort::ToolCount => {
+            summaries.sort_by(|a, b| b.tool_count.cmp(&a.tool_count).then_with(|| by_name(a, b)))
+        }
+    }
+
+    Ok(PluginPage {
+        total: summaries.len(),
+        items: summaries
+            .into_iter()
+            .skip(offset.unwrap_or(0))
+            .take(limit.unwrap_or(usize::MAX))
+            .collect(),
+    })
+}
+
+/// 单独读取插件源码
+#[tauri::command]
+pub async fn plugin_content(id: String) -> Result<String> {
+    read_content(&find_plugin(&id).await?)
+}
+
+#[tauri::command]
+pub async fn plugin_get(id: String) -> Result<Option<PluginWithContent>> {
+    let Some(plugin) = with_plugin_list(|plugins| plugins.get(&id).cloned()).await? else {
+        return Ok(None);
+    };
+    Ok(Some(PluginWithContent {
+        content: read_content(&plugin)?,
+        files: directory::file_tree(&id)?,
+        info: plugin,
+    }))
+}
+
 #[tauri::command]
 pub async fn plugin_remove(id: String) -> Result<()> {
-    let mut plugins = load_plugin_list().await?;
-    plugins.remove(&id);
-    save_plugin_list(&plugins).await?;
+    env::remove(&id)?;
+    delete_plugin(&id).await?;
 
-    let plugin_path = PLUGINS_DIR.join(format!("{}.ts", id));
-    if plugin_path.exists() {
-        fs::remove_file(plugin_path)?;
+    for ext in ["ts", "py", "wasm"] {
+        let plugin_path = plugins_dir().join(format!("{}.{}", id, ext));
+        if plugin_path.exists() {
+            fs::remove_file(plugin_path)?;
+        }
+    }
+    if let Some(lock_file) = existing_lock(&id) {
+        fs::remove_file(lock_file)?;
+    }
+    let dir = directory::plugin_dir(&id);
+    if dir.is_dir() {
+        fs::remove_dir_all(dir)?;
+    }
+    let data = plugins_dir().join("data").join(&id);
+    if data.exists() {
+        fs::remove_dir_all(data)?;
     }
+    logs::remove_logs(&id)?;
+    rate_limit::reset(&id);
+    service::stop(&id);
+    schedule::remove_for_plugin(&id).await?;
+    trigger::remove_for_plugin(&id).await?;
+    cache::clear(Some(&id))?;
+    storage::remove(&id).await?;
+    versions::remove(&id)?;
+    stats::remove(&id)?;
+    mcp::disconnect(&id).await;
 
     Ok(())
 }
 
+/// 以新 id 复制插件的源码、元数据与存储, 不保留安装来源
+#[tauri::command]
+pub async fn plugin_duplicate(id: String, new_name: Option<String>) -> Result<Plugin> {
+    let original = find_plugin(&id).await?;
+    let mut plugin = original.clone();
+    plugin.id = generate_id();
+    // 副本名称记为用户修改, 更新源码后仍然保留
+    let mut display = original;
+    meta::display(&mut display, None);
+    plugin.overrides.name = Some(match new_name.filter(|name| !name.trim().is_empty()) {
+        Some(name) => name.trim().to_string(),
+        None => format!("{} 副本", display.name),
+    });
+    plugin.source = None;
+
+    let is_dir = directory::copy(&id, &plugin.id)?;
+    for ext in ["ts", "py", "wasm"] {
+        let path = plugins_dir().join(format!("{}.{}", id, ext));
+        if !path.exists() {
+            continue;
+        }
+        let dest = plugins_dir().join(format!("{}.{}", plugin.id, ext));
+        if ext == "wasm" {
+            fs::copy(path, dest)?;
+            continue;
+        }
+        let mut content = fs::read_to_string(path)?;
+        if is_dir {
+            content = directory::retarget_shim(&content, &id, &plugin.id);
+        }
+        reload::remember(&plugin.id, &content);
+        fs::write(dest, &content)?;
+        versions::snapshot(&plugin, &content)?;
+    }
+    if let Some(lock_file) = existing_lock(&id) {
+        fs::copy(lock_file, lock_path(&plugin.id))?;
+    }
+    storage::copy(&id, &plugin.id).await?;
+    let plugin = register_plugin(plugin).await?;
+    // 插件记录写入后才能复制其环境变量
+    env::copy(&id, &plugin.id)?;
+    Ok(plugin)
+}
+
 /// 执行指定插件的工具函数
 ///
 /// # 参数
 /// * `id` - 插件的唯一标识符
 /// * `tool` - 要执行的工具函数名称
 /// * `args` - 传递给工具函数的参数，使用JSON Value格式
+/// * `profile` - 本次执行使用的环境配置，未指定时使用设置中启用的配置
+/// * `dry_run` - 为 true 时不执行, 返回生成的脚本、Deno 参数与注入的环境变量名
 ///
 /// # 返回值
-/// * `Result<Value>` - 成功时返回工具函数的执行结果，失败时返回错误信息
+/// * `Result<ExecutionResult>` - 成功时返回工具函数的执行结果与尝试次数，失败时返回错误信息
 ///
 /// # 错误
+/// * 工具需要确认时返回 `PluginError::ApprovalRequired`, 确认后通过 `execution_approve` 执行
 /// * 当插件文件不存在时返回 `PluginError::Plugin`
 /// * 当JSON解析失败时返回 `PluginError::Json`
 #[tauri::command]
-pub async fn plugin_execute(id: String, tool: String, args: Value) -> Result<Value> {
+pub async fn plugin_execute(
+    id: String,
+    tool: String,
+    args: Value,
+    profile: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<ExecutionResult> {
+    if dry_run.unwrap_or(false) {
+        let plan = profile::scope(profile, explain(&id, &tool, &args)).await??;
+        return Ok(ExecutionResult {
+            execution_id: generate_id(),
+            result: serde_json::to_value(plan)?,
+            attempts: 0,
+            duration_ms: 0,
+            cached: false,
+            artifacts: Vec::new(),
+            dry_run: true,
+        });
+    }
+    profile::scope(profile, async {
+        approval::check(&id, &tool, &args).await?;
+        execute_tool(&id, &tool, &args, &[]).await
+    })
+    .await?
+}
+
+/// 生成执行计划, 不启动插件进程
+#[tauri::command]
+pub async fn plugin_explain(
+    id: String,
+    tool: String,
+    args: Value,
+    profile: Option<String>,
+) -> Result<ExecutionPlan> {
+    profile::scope(profile, explain(&id, &tool, &args)).await?
+}
+
+async fn explain(id: &str, tool: &str, args: &Value) -> Result<ExecutionPlan> {
+    let plugin = find_plugin(id).await?;
+    let target = plugin
+        .tools
+        .iter()
+        .find(|t| t.name == tool)
+        .ok_or_else(|| PluginError::Plugin(format!("未知函数: {}", tool)))?;
+    schema::validate_args(target, args)?;
+    // 产物目录只用于生成参数, 不会创建
+    let artifacts_dir = plugins_dir().join("artifacts").join("<execution_id>");
+    let (command, env_keys) = match plugin.runtime {
+        PluginRuntime::Deno => {
+            let task = deno_task(id, tool, args, &artifacts_dir, &[]).await?;
+            (Some(deno().plan(&task)), deno().env_keys(&task))
+        }
+        _ => {
+            let mut keys: Vec<String> = env::for_plugin(id)
+                .await?
+                .into_iter()
+                .map(|var| var.key)
+                .collect();
+            keys.push(artifacts::ARTIFACTS_DIR_ENV.to_string());
+            (None, keys)
+        }
+    };
+    Ok(ExecutionPlan {
+        plugin_id: id.to_string(),
+        tool: tool.to_string(),
+        runtime: plugin.runtime,
+        command,
+        env_keys,
+    })
+}
+
+/// 执行工具, 按重试策略重试并记录调用历史
+///
+/// `chain` 为发起调用的插件工具链, 由插件间调用传入, 用于检测循环调用
+#[tracing::instrument(
+    name = "execute",
+    skip(args, chain),
+    fields(plugin = id, execution_id = tracing::field::Empty)
+)]
+pub(crate) async fn execute_tool(
+    id: &str,
+    tool: &str,
+    args: &Value,
+    chain: &[String],
+) -> Result<ExecutionResult> {
+    let started = std::time::Instant::now();
+    let plugin = find_plugin(id).await?;
+    let target = resolve_tool(&plugin, tool).await?;
+    let execution_id = generate_id();
+    tracing::Span::current().record("execution_id", execution_id.as_str());
+    let _ = replay::save(&execution_id, &plugin, tool, args).await;
+    let retry = target.retry.as_ref();
+    let cache_policy = target.cache.as_ref();
+    let mut produced = Vec::new();
+
+    let (result, attempts, cached) = if let Err(err) = schema::validate_args(&target, args) {
+        // 参数不合法时不启动插件进程
+        (Err(err), 0, false)
+    } else if let Some(value) = cache_policy.and_then(|_| cache::get(id, tool, args)) {
+        (Ok(value), 0, true)
+    } else {
+        let dir = artifacts::create_dir(&execution_id)?;
+        let (result, attempts) = run_with_retry(id, tool, args, &dir, chain, retry).await;
+        if let (Ok(value), Some(policy)) = (&result, cache_policy) {
+            let _ = cache::put(id, tool, args, policy, value);
+        }
+        produced = artifacts::collect(&dir)?;
+        (result, attempts, false)
+    };
+
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let result = result.map_err(redact::scrub_error);
+    match result {
+        Ok(_) => tracing::info!(duration_ms, attempts, cached, "执行完成"),
+        Err(ref err) => tracing::warn!(duration_ms, attempts, error = %err, "执行失败"),
+    }
+    let _ = history::record(
+        &execution_id,
+        id,
+        tool,
+        args,
+        duration_ms,
+        attempts,
+        &result,
+    )
+    .await;
+    Ok(ExecutionResult {
+        execution_id,
+        result: result?,
+        attempts,
+        duration_ms,
+        cached,
+        artifacts: produced,
+        dry_run: false,
+    })
+}
+
+/// 执行前检查插件已启用且声明了该工具, 返回工具信息
+pub(crate) async fn resolve_tool(plugin: &Plugin, tool: &str) -> Result<Tool> {
+    if !plugin.enabled {
+        return Err(PluginError::Disabled(plugin.id.clone()));
+    }
+    let unknown = || PluginError::Plugin(format!("未知函数: {}", tool));
+    match plugin.tools.iter().find(|t| t.name == tool) {
+        Some(target) => Ok(target.clone()),
+        // MCP 服务的工具列表可能尚未同步, 同步后再查找一次
+        None if plugin.runtime == PluginRuntime::Mcp => {
+            let _ = mcp::mcp_tools(plugin.id.clone()).await;
+            find_plugin(&plugin.id)
+                .await?
+                .tools
+                .into_iter()
+                .find(|t| t.name == tool)
+                .ok_or_else(unknown)
+        }
+        None => Err(unknown()),
+    }
+}
+
+// 按重试策略执行, 返回最终结果与尝试次数
+async fn run_with_retry(
+    id: &str,
+    tool: &str,
+    args: &Value,
+    artifacts_dir: &Path,
+    chain: &[String],
+    policy: Option<&RetryPolicy>,
+) -> (Result<Value>, u32) {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let result = run_tool(id, tool, args, artifacts_dir, chain).await;
+        match (&result, policy) {
+            (Err(err), Some(policy))
+                if attempts < policy.max_attempts() && policy.should_retry(err) =>
+            {
+                tracing::warn!(attempts, error = %err, "执行失败, 准备重试");
+                let _ = logs::append_logs(
+                    id,
+                    Some(tool),
+                    &format!("第 {} 次执行失败, 准备重试: {}", attempts, err),
+                );
+                tokio::time::sleep(policy.delay(attempts)).await;
+            }
+            _ => return (result, attempts),
+        }
+    }
+}
+
+// 按插件运行时分发执行
+pub(crate) async fn run_tool(
+    id: &str,
+    tool: &str,
+    args: &Value,
+    artifacts_dir: &Path,
+    chain: &[String],
+) -> Result<Value> {
+    let plugin = find_plugin(id).await?;
+    if let Some(limit) = plugin
+        .tools
+        .iter()
+        .find(|t| t.name == tool)
+        .and_then(|t| t.rate_limit.as_ref())
+    {
+        rate_limit::acquire(id, tool, limit)?;
+    }
+    match plugin.runtime {
+        PluginRuntime::Deno => execute_deno(id, tool, args, artifacts_dir, chain).await,
+        PluginRuntime::Wasm => wasm::execute(id, tool, args, artifacts_dir).await,
+        PluginRuntime::Python => python::execute(id, tool, args, artifacts_dir).await,
+        PluginRuntime::Node => node::execute(id, tool, args, artifacts_dir).await,
+        PluginRuntime::Shell => shell::execute(&plugin, tool, args, artifacts_dir).await,
+        PluginRuntime::Mcp => mcp::execute(id, tool, args).await,
+    }
+}
+
+// 通过 Deno 执行工具
+async fn execute_deno(
+    id: &str,
+    tool: &str,
+    args: &Value,
+    artifacts_dir: &Path,
+    chain: &[String],
+) -> Result<Value> {
+    // 被拒绝的访问经用户允许后重新执行, 拒绝前已产生的副作用会重复发生
+    let mut prompts = 0;
+    loop {
+        let task = deno_task(id, tool, args, artifacts_dir, chain).await?;
+        let output = deno().execute(&task).await?;
+        let _ = logs::append_logs(id, Some(tool), &output.stderr);
+        if !output.success && prompts < MAX_PERMISSION_PROMPTS {
+            if let Some(request) = grants::parse_denial(&output.stderr) {
+                if grants::ask(id, &request).await? {
+                    prompts += 1;
+                    continue;
+                }
+            }
+        }
+        return serde_json::from_str(&output.into_stdout()?)
+            .map_err(|e| PluginError::Json(e.to_string()));
+    }
+}
+
+// 生成调用工具的脚本并加载环境变量
+async fn deno_task(
+    id: &str,
+    tool: &str,
+    args: &Value,
+    artifacts_dir: &Path,
+    chain: &[String],
+) -> Result<DenoTask> {
     /* 插件文件 */
-    let plugin_file = PLUGINS_DIR.join(format!("{}.ts", id));
+    let plugin_file = plugins_dir().join(format!("{}.ts", id));
     /* 如果插件不存在则返回插件文件不存在的错误. */
     if !plugin_file.exists() {
         return Err(PluginError::Plugin(format!("插件文件不存在: {}", id)));
@@ -361,31 +1327,90 @@ pub async fn plugin_execute(id: String, tool: String, args: Value) -> Result<Val
     let script = format!(
         r#"
         const plugin = await import('file://{plugin_path}');
-        const targetFunction = plugin.default.tools['{tool}'];
+        const targetFunction = plugin.default.tools[{tool}];
         if (!targetFunction) {{
-            throw new Error('未知函数: {tool}');
+            throw new Error('未知函数: ' + {tool});
         }}
         const result = await targetFunction.handler({args});
-        console.log(JSON.stringify(result));
+        await __echoOutput(result);
         "#,
         plugin_path = plugin_file.to_string_lossy().replace('\\', "/"),
-        tool = tool,
-        args = serde_json::to_string(&args)?
+        tool = serde_json::to_string(tool)?,
+        args = serde_json::to_string(args)?
     );
 
     /* 环境变量加载 */
-    let env_vars = load_env_vars().await?;
-    let output = DENO_RUNTIME.execute(&script, &env_vars).await?;
-    serde_json::from_str(&output).map_err(|e| PluginError::Json(e.to_string()))
+    let mut env_vars = env::for_plugin(id).await?;
+    env_vars.push(artifacts::env_var(artifacts_dir));
+    Ok(DenoTask {
+        script,
+        env_vars,
+        lock_file: existing_lock(id),
+        data_dir: Some(data_dir(id)?),
+        artifacts_dir: Some(artifacts_dir.to_path_buf()),
+        bridge: Some(Bridge::new(id, tool, chain)),
+        grants: find_plugin(id).await?.grants,
+    })
+}
+
+/// 启用或禁用插件, 禁用时停止其后台服务
+#[tauri::command]
+pub async fn plugin_set_enabled(id: String, enabled: bool) -> Result<Plugin> {
+    let mut plugin = find_plugin(&id).await?;
+    plugin.enabled = enabled;
+    let plugin = register_plugin(plugin).await?;
+    if !enabled {
+        service::stop(&id);
+        mcp::disconnect(&id).await;
+    } else if plugin.service.as_ref().is_some_and(|s| s.autostart) {
+        service::start(&id).await?;
+    }
+    Ok(plugin)
 }
 
 #[tauri::command]
 pub async fn plugin_update(id: String, content: String) -> Result<Plugin> {
-    let plugins = load_plugin_list().await?;
-    if !plugins.contains_key(&id) {
-        return Err(PluginError::Plugin(format!("插件不存在: {}", id)));
+    let plugin = find_plugin(&id).await?;
+    if matches!(
+        plugin.runtime,
+        PluginRuntime::Wasm | PluginRuntime::Shell | PluginRuntime::Mcp
+    ) {
+        return Err(PluginError::Plugin(format!("插件不支持编辑源码: {}", id)));
     }
-    process_plugin_content(id, content).await
+    // 保留覆盖前的版本, 便于回滚
+    versions::snapshot_current(&plugin)?;
+    let plugin = process_plugin_content(id, content).await?;
+    service::reload(&plugin.id).await?;
+    Ok(plugin)
+}
+
+/// 预先缓存插件的远程依赖, 供离线模式使用
+#[tauri::command]
+pub async fn plugin_cache_deps(id: String) -> Result<Plugin> {
+    find_plugin(&id).await?;
+    let plugin_file = plugins_dir().join(format!("{}.ts", id));
+    let output = deno()
+        .cache(&plugin_file, existing_lock(&id).as_deref())
+        .await?;
+    let _ = logs::append_logs(&id, None, &output.stderr);
+    output.into_stdout()?;
+    mark_deps_cached(&id).await
+}
+
+// 记录依赖缓存时间
+async fn mark_deps_cached(id: &str) -> Result<Plugin> {
+    let mut plugin = find_plugin(id).await?;
+    plugin.deps_cached_at = Some(chrono::Utc::now().timestamp());
+    store_plugin(&plugin).await?;
+    Ok(plugin)
+}
+
+/// 主动刷新插件的依赖锁文件
+#[tauri::command]
+pub async fn plugin_update_lock(id: String) -> Result<Plugin> {
+    find_plugin(&id).await?;
+    write_lock(&id).await?;
+    mark_deps_cached(&id).await
 }
 
 #[tauri::command]
@@ -395,12 +1420,11 @@ pub async fn env_list() -> Result<Vec<EnvVar>> {
 
 #[tauri::command]
 pub async fn env_save(vars: Vec<EnvVar>) -> Result<()> {
-    let content = vars
-        .iter()
-        .map(|var| format!("{}={}", var.key, var.value))
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    fs::write(PLUGINS_DIR.join(".env"), content)?;
-    Ok(())
+    env::check_vars(&vars)?;
+    with_db(|conn| {
+        let tx = conn.transaction()?;
+        insert_env_vars(&tx, &vars)?;
+        tx.commit()?;
+        Ok(())
+    })
 }
@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use super::deno::{plugins_dir, process_plugin_content, Plugin, PluginError, Result};
+use super::signature;
+use crate::utils::gen::generate_id;
+
+// 未指定入口时依次查找的文件
+const ENTRY_CANDIDATES: [&str; 6] = [
+    "mod.ts",
+    "main.ts",
+    "index.ts",
+    "plugin.ts",
+    "main.js",
+    "index.js",
+];
+// 复制时跳过的文件与目录
+const SKIPPED_NAMES: [&str; 3] = [".git", "node_modules", ".DS_Store"];
+// 目录插件的文件数量与总大小上限
+const MAX_FILES: usize = 1000;
+const MAX_TOTAL_BYTES: u64 = 20 * 1024 * 1024;
+
+/// 插件目录中的文件或子目录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FileNode {
+    pub name: String,
+    /// 相对插件目录的路径, 使用 / 分隔
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<FileNode>,
+}
+
+/// 目录插件的文件目录
+pub(crate) fn plugin_dir(id: &str) -> PathBuf {
+    plugins_dir().join(id)
+}
+
+/// 检查相对路径, 不允许跳出插件目录
+pub(crate) fn check_relative(path: &str) -> Result<&Path> {
+    let relative = Path::new(path);
+    if path.is_empty()
+        || relative.is_absolute()
+        || relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return Err(PluginError::Plugin(format!("无效的文件路径: {}", path)));
+    }
+    Ok(relative)
+}
+
+// 统计待复制的文件, 超出限制时报错
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<PathBuf>, total: &mut u64) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if SKIPPED_NAMES.iter().any(|skipped| name == *skipped) {
+            continue;
+        }
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(root, &path, files, total)?;
+        } else if file_type.is_file() {
+            *total += entry.metadata()?.len();
+            files.push(path.strip_prefix(root).unwrap_or(&path).to_path_buf());
+            if files.len() > MAX_FILES || *total > MAX_TOTAL_BYTES {
+                return Err(PluginError::Plugin("插件目录过大".to_string()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 插件目录中的全部文件, 返回相对路径
+pub(crate) fn list_files(id: &str) -> Result<Vec<PathBuf>> {
+    let root = plugin_dir(id);
+    let mut files = Vec::new();
+    if root.is_dir() {
+        collect_files(&root, &root, &mut files, &mut 0)?;
+    }
+    Ok(files)
+}
+
+fn build_tree(root: &Path, dir: &Path) -> Result<Vec<FileNode>> {
+    let mut nodes = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        nodes.push(FileNode {
+            name: entry.file_name().to_string_lossy().to_string(),
+            path: relative.to_string_lossy().replace('\\', "/"),
+            is_dir: metadata.is_dir(),
+            size: if metadata.is_dir() { 0 } else { metadata.len() },
+            children: if metadata.is_dir() {
+                build_tree(root, &path)?
+            } else {
+                Vec::new()
+            },
+        });
+    }
+    // 目录在前, 同类按名称排序
+    nodes.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    Ok(nodes)
+}
+
+/// 目录插件的文件树, 单文件插件返回 None
+pub(crate) fn file_tree(id: &str) -> Result<Option<Vec<FileNode>>> {
+    let root = plugin_dir(id);
+    if !root.is_dir() {
+        return Ok(None);
+    }
+    build_tree(&root, &root).map(Some)
+}
+
+// 单文件入口, 只重新导出目录中的入口模块, 其余流程与单文件插件一致
+/// 复制目录插件的文件, 返回源插件是否为目录插件
+pub(crate) fn copy(from: &str, to: &str) -> Result<bool> {
+    let source = plugin_dir(from);
+    if !source.is_dir() {
+        return Ok(false);
+    }
+    let target = plugin_dir(to);
+    for file in list_files(from)? {
+        let dest = target.join(&file);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(source.join(&file), dest)?;
+    }
+    Ok(true)
+}
+
+/// 入口文件引用的目录改为新 id
+pub(crate) fn retarget_shim(content: &str, from: &str, to: &str) -> String {
+    content.replace(&format!("./{}/", from), &format!("./{}/", to))
+}
+
+fn entry_shim(id: &str, entry: &str) -> String {
+    format!(
+        "export {{ default }} from \"./{}/{}\";\n",
+        id,
+        entry.replace('\\', "/")
+    )
+}
+
+/// 导入包含多个模块与资源的插件目录
+#[tauri::command]
+pub async fn plugin_import_dir(
+    path: String,
+    entry: Option<String>,
+    allow_unsigned: Option<bool>,
+) -> Result<Plugin> {
+    let source = PathBuf::from(&path);
+    if !source.is_dir() {
+        return Err(PluginError::Plugin(format!("目录不存在: {}", path)));
+    }
+    let entry = match entry {
+        Some(entry) => check_relative(&entry)?.to_string_lossy().to_string(),
+        None => ENTRY_CANDIDATES
+            .iter()
+            .find(|candidate| source.join(candidate).is_file())
+            .map(|candidate| candidate.to_string())
+            .ok_or_else(|| PluginError::Plugin("未找到插件入口文件".to_string()))?,
+    };
+    let entry_content = fs::read_to_string(source.join(&entry))
+        .map_err(|e| PluginError::Plugin(format!("无法读取入口文件 {}: {}", entry, e)))?;
+    let info = signature::check(&entry_content, None, allow_unsigned.unwrap_or(false))?;
+
+    let mut files = Vec::new();
+    collect_files(&source, &source, &mut files, &mut 0)?;
+    let id = generate_id();
+    let target = plugin_dir(&id);
+    for file in &files {
+        let dest = target.join(file);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(source.join(file), dest)?;
+    }
+
+    let result = process_plugin_content(id.clone(), entry_shim(&id, &entry)).await;
+    let mut plugin = match result {
+        Ok(plugin) => plugin,
+        Err(err) => {
+            let _ = fs::remove_dir_all(&target);
+            let _ = fs::remove_file(plugins_dir().join(format!("{}.ts", id)));
+            return Err(err);
+        }
+    };
+    plugin.signature = Some(info);
+    super::deno::register_plugin(plugin).await
+}
+
+/// 读取目录插件中的文件
+#[tauri::command]
+pub async fn plugin_read_file(id: String, path: String) -> Result<String> {
+    let file = plugin_dir(&id).join(check_relative(&path)?);
+    Ok(fs::read_to_string(file)?)
+}
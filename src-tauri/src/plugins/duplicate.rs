@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use super::deno::{load_plugin_list, PluginError, Result};
+use super::install::digest;
+use super::signature;
+
+/// 插件源码的哈希, 用于导入时的重复检测
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ContentHash {
+    /// 源码的 SHA-256
+    pub sha256: String,
+    /// 忽略签名、空行、行尾空白与换行符差异后的 SHA-256
+    pub normalized: String,
+}
+
+// 近似相同的内容归一化为同一文本
+fn normalize(content: &str) -> String {
+    signature::strip(content)
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub(crate) fn hash(content: &str) -> ContentHash {
+    ContentHash {
+        sha256: digest(content),
+        normalized: digest(&normalize(content)),
+    }
+}
+
+/// 已安装相同或近似相同内容时返回 Duplicate 错误, 由调用方选择更新已有插件
+pub(crate) async fn check(content: &str) -> Result<()> {
+    let hash = hash(content);
+    let existing = load_plugin_list().await?.into_values().find_map(|plugin| {
+        let existing = plugin.content_hash.as_ref()?;
+        if existing.sha256 == hash.sha256 {
+            Some((plugin, true))
+        } else if existing.normalized == hash.normalized {
+            Some((plugin, false))
+        } else {
+            None
+        }
+    });
+    match existing {
+        Some((plugin, identical)) => Err(PluginError::Duplicate {
+            id: plugin.id,
+            name: plugin.name,
+            identical,
+        }),
+        None => Ok(()),
+    }
+}
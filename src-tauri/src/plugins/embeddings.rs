@@ -0,0 +1,345 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::db::with_db;
+use super::deno::{PluginError, Result};
+use super::providers;
+use crate::utils::gen::generate_id;
+
+// 每次请求向量化的文本条数
+const EMBED_BATCH: usize = 64;
+// 默认返回的结果数
+const DEFAULT_K: usize = 5;
+
+/// 向量集合, 同一集合中的向量由同一模型生成
+#[derive(Debug, Serialize, Clone)]
+pub struct Collection {
+    pub id: String,
+    pub name: String,
+    /// 生成向量的模型服务与模型, 查询时使用同一模型
+    pub provider: String,
+    pub model: String,
+    pub dimension: usize,
+    pub document_count: usize,
+    pub created_at: i64,
+}
+
+/// 待写入的文本片段
+#[derive(Debug, Deserialize, Clone)]
+pub struct DocumentChunk {
+    pub content: String,
+    /// 来源, 如文件路径, 可按来源删除
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub metadata: Value,
+}
+
+/// 相似度查询的结果
+#[derive(Debug, Serialize, Clone)]
+pub struct SimilarHit {
+    pub id: i64,
+    pub content: String,
+    pub source: Option<String>,
+    pub metadata: Value,
+    /// 余弦相似度, 越大越相似
+    pub score: f32,
+}
+
+fn invalid(message: String) -> PluginError {
+    PluginError::Plugin(message)
+}
+
+// 集合 id 只含字母与数字, 可直接用作表名
+fn vector_table(collection_id: &str) -> String {
+    format!("vec_{}", collection_id)
+}
+
+// sqlite-vec 接受 JSON 数组形式的向量
+fn vector_json(vector: &[f32]) -> Result<String> {
+    Ok(serde_json::to_string(vector)?)
+}
+
+fn read_collection(row: &rusqlite::Row) -> rusqlite::Result<Collection> {
+    Ok(Collection {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        provider: row.get(2)?,
+        model: row.get(3)?,
+        dimension: row.get::<_, i64>(4)? as usize,
+        document_count: row.get::<_, i64>(5)? as usize,
+        created_at: row.get(6)?,
+    })
+}
+
+const COLLECTION_COLUMNS: &str = "c.id, c.name, c.provider, c.model, c.dimension,
+    (SELECT COUNT(*) FROM vector_documents d WHERE d.collection_id = c.id), c.created_at";
+
+/// 按 id 或名称查找集合
+pub(crate) fn find_collection(conn: &Connection, collection: &str) -> Result<Collection> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM vector_collections c WHERE c.id = ?1 OR c.name = ?1",
+            COLLECTION_COLUMNS
+        ),
+        [collection],
+        read_collection,
+    )
+    .optional()?
+    .ok_or_else(|| invalid(format!("向量集合不存在: {}", collection)))
+}
+
+/// 分批向量化, 结果与输入一一对应
+pub(crate) async fn embed_all(
+    provider: &str,
+    model: &str,
+    texts: &[String],
+) -> Result<Vec<Vec<f32>>> {
+    let mut vectors = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(EMBED_BATCH) {
+        vectors.extend(providers::embed(provider, model, batch).await?);
+    }
+    Ok(vectors)
+}
+
+/// 写入已向量化的片段, 返回片段 id
+pub(crate) fn insert_documents(
+    conn: &mut Connection,
+    collection: &Collection,
+    chunks: &[DocumentChunk],
+    vectors: &[Vec<f32>],
+) -> Result<Vec<i64>> {
+    if let Some(vector) = vectors.iter().find(|v| v.len() != collection.dimension) {
+        return Err(invalid(format!(
+            "向量维度不一致: 集合为 {}, 模型返回 {}",
+            collection.dimension,
+            vector.len()
+        )));
+    }
+    let now = chrono::Utc::now().timestamp_millis();
+    let tx = conn.transaction()?;
+    let mut ids = Vec::with_capacity(chunks.len());
+    for (chunk, vector) in chunks.iter().zip(vectors) {
+        tx.execute(
+            "INSERT INTO vector_documents (collection_id, source, content, metadata, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                collection.id,
+                chunk.source,
+                chunk.content,
+                serde_json::to_string(&chunk.metadata)?,
+                now
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.execute(
+            &format!(
+                "INSERT INTO {} (rowid, embedding) VALUES (?1, ?2)",
+                vector_table(&collection.id)
+            ),
+            params![id, vector_json(vector)?],
+        )?;
+        ids.push(id);
+    }
+    tx.commit()?;
+    Ok(ids)
+}
+
+/// 删除来源的全部片段, 返回删除的条数
+pub(crate) fn remove_source(
+    conn: &mut Connection,
+    collection_id: &str,
+    source: &str,
+) -> Result<usize> {
+    let tx = conn.transaction()?;
+    tx.execute(
+        &format!(
+            "DELETE FROM {} WHERE rowid IN
+                 (SELECT id FROM vector_documents WHERE collection_id = ?1 AND source = ?2)",
+            vector_table(collection_id)
+        ),
+        [collection_id, source],
+    )?;
+    let removed = tx.execute(
+        "DELETE FROM vector_documents WHERE collection_id = ?1 AND source = ?2",
+        [collection_id, source],
+    )?;
+    tx.commit()?;
+    Ok(removed)
+}
+
+/// 查询与文本最相似的片段
+pub(crate) async fn search(collection: &str, query: &str, k: usize) -> Result<Vec<SimilarHit>> {
+    let collection = with_db(|conn| find_collection(conn, collection))?;
+    let vector = providers::embed(
+        &collection.provider,
+        &collection.model,
+        &[query.to_string()],
+    )
+    .await?
+    .pop()
+    .unwrap_or_default();
+    let vector = vector_json(&vector)?;
+    with_db(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT d.id, d.content, d.source, d.metadata, v.distance
+             FROM {} v JOIN vector_documents d ON d.id = v.rowid
+             WHERE v.embedding MATCH ?1 AND k = ?2 ORDER BY v.distance",
+            vector_table(&collection.id)
+        ))?;
+        let rows = stmt
+            .query_map(params![vector, k as i64], |row| {
+                let metadata: String = row.get(3)?;
+                Ok(SimilarHit {
+                    id: row.get(0)?,
+                    content: row.get(1)?,
+                    source: row.get(2)?,
+                    metadata: serde_json::from_str(&metadata).unwrap_or(Value::Null),
+                    score: 1.0 - row.get::<_, f64>(4)? as f32,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })
+}
+
+/// 计算文本的向量
+///
+/// # 参数
+/// * `provider` - 模型服务 id, 为 `local` 时使用已加载的本地模型
+#[tauri::command]
+pub async fn embeddings_create(
+    provider: String,
+    model: String,
+    input: Vec<String>,
+) -> Result<Vec<Vec<f32>>> {
+    embed_all(&provider, &model, &input).await
+}
+
+/// 新建向量集合, 向量维度由模型的输出确定
+#[tauri::command]
+pub async fn collection_create(
+    name: String,
+    provider: String,
+    model: String,
+) -> Result<Collection> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(invalid("集合名称不能为空".to_string()));
+    }
+    let exists = with_db(|conn| {
+        Ok(conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM vector_collections WHERE name = ?1)",
+            [&name],
+            |row| row.get::<_, bool>(0),
+        )?)
+    })?;
+    if exists {
+        return Err(invalid(format!("集合已存在: {}", name)));
+    }
+    let probe = providers::embed(&provider, &model, &["dimension".to_string()]).await?;
+    let dimension = probe.first().map_or(0, Vec::len);
+    if dimension == 0 {
+        return Err(invalid("模型没有返回向量".to_string()));
+    }
+
+    let collection = Collection {
+        id: generate_id(),
+        name,
+        provider,
+        model,
+        dimension,
+        document_count: 0,
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+    with_db(|conn| {
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO vector_collections (id, name, provider, model, dimension, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                collection.id,
+                collection.name,
+                collection.provider,
+                collection.model,
+                collection.dimension as i64,
+                collection.created_at
+            ],
+        )?;
+        tx.execute_batch(&format!(
+            "CREATE VIRTUAL TABLE {} USING vec0(embedding float[{}] distance_metric=cosine);",
+            vector_table(&collection.id),
+            collection.dimension
+        ))?;
+        tx.commit()?;
+        Ok(())
+    })?;
+    tracing::info!(collection = %collection.name, dimension, "向量集合已创建");
+    Ok(collection)
+}
+
+#[tauri::command]
+pub async fn collection_list() -> Result<Vec<Collection>> {
+    with_db(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM vector_collections c ORDER BY c.created_at",
+            COLLECTION_COLUMNS
+        ))?;
+        let rows = stmt
+            .query_map([], read_collection)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })
+}
+
+/// 删除集合及其全部片段
+#[tauri::command]
+pub async fn collection_delete(collection: String) -> Result<()> {
+    with_db(|conn| {
+        let collection = find_collection(conn, &collection)?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM vector_collections WHERE id = ?1",
+            [&collection.id],
+        )?;
+        tx.execute_batch(&format!(
+            "DROP TABLE IF EXISTS {};",
+            vector_table(&collection.id)
+        ))?;
+        tx.commit()?;
+        Ok(())
+    })
+}
+
+/// 向量化并写入片段, 返回片段 id
+#[tauri::command]
+pub async fn documents_add(collection: String, chunks: Vec<DocumentChunk>) -> Result<Vec<i64>> {
+    let collection = with_db(|conn| find_collection(conn, &collection))?;
+    let chunks: Vec<DocumentChunk> = chunks
+        .into_iter()
+        .filter(|c| !c.content.trim().is_empty())
+        .collect();
+    let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+    let vectors = embed_all(&collection.provider, &collection.model, &texts).await?;
+    with_db(|conn| insert_documents(conn, &collection, &chunks, &vectors))
+}
+
+/// 删除来源的全部片段
+#[tauri::command]
+pub async fn documents_remove(collection: String, source: String) -> Result<usize> {
+    with_db(|conn| {
+        let collection = find_collection(conn, &collection)?;
+        remove_source(conn, &collection.id, &source)
+    })
+}
+
+/// 查询与文本最相似的 k 个片段
+#[tauri::command]
+pub async fn similar_search(
+    collection: String,
+    query: String,
+    k: Option<usize>,
+) -> Result<Vec<SimilarHit>> {
+    search(&collection, &query, k.unwrap_or(DEFAULT_K).max(1)).await
+}
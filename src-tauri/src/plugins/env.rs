@@ -0,0 +1,339 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::HashMap;
+
+use super::db::with_db;
+use super::deno::{find_plugin, load_env_vars, EnvVar, PluginError, Result};
+use super::profile;
+use super::secrets;
+
+/// 校验变量名: 以字母或下划线开头, 只包含字母、数字与下划线
+pub(crate) fn check_key(key: &str) -> Result<()> {
+    let mut chars = key.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(PluginError::Plugin(format!("无效的变量名: {}", key)))
+    }
+}
+
+/// 校验一组变量, 不允许重复的变量名
+pub(crate) fn check_vars(vars: &[EnvVar]) -> Result<()> {
+    for (index, var) in vars.iter().enumerate() {
+        check_key(&var.key)?;
+        if vars[..index].iter().any(|v| v.key == var.key) {
+            return Err(PluginError::Plugin(format!("变量名重复: {}", var.key)));
+        }
+    }
+    Ok(())
+}
+
+// 全局变量在数据库中保存的值, 不存在时返回 None
+fn stored_value(conn: &Connection, key: &str) -> Result<Option<String>> {
+    let mut stmt = conn.prepare("SELECT value FROM env_vars WHERE key = ?1")?;
+    let mut rows = stmt.query_map([key], |row| row.get::<_, String>(0))?;
+    Ok(rows.next().transpose()?)
+}
+
+/// 解析 .env 内容, 每个变量附带前面的注释、空行等非变量行, 另外返回最后一个变量之后的行
+pub(crate) fn parse_dotenv(content: &str) -> (Vec<(EnvVar, String)>, String) {
+    let mut vars = Vec::new();
+    let mut pending = String::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        match line.split_once('=') {
+            Some((key, value)) if !trimmed.is_empty() && !trimmed.starts_with('#') => {
+                let var = EnvVar {
+                    key: key.trim().to_string(),
+                    value: value.trim().to_string(),
+                };
+                vars.push((var, std::mem::take(&mut pending)));
+            }
+            _ => {
+                pending.push_str(line);
+                pending.push('\n');
+            }
+        }
+    }
+    (vars, pending)
+}
+
+/// 全局变量前的注释与空行
+pub(crate) fn comments(conn: &Connection) -> Result<HashMap<String, String>> {
+    let mut stmt = conn.prepare("SELECT key, comment FROM env_vars")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(rows)
+}
+
+fn footer(conn: &Connection) -> Result<String> {
+    Ok(conn
+        .query_row("SELECT lines FROM env_footer WHERE id = 0", [], |row| {
+            row.get(0)
+        })
+        .optional()?
+        .unwrap_or_default())
+}
+
+fn set_footer(conn: &Connection, lines: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO env_footer (id, lines) VALUES (0, ?1)",
+        [lines],
+    )?;
+    Ok(())
+}
+
+/// 按 .env 内容恢复注释与空行, 只更新已存在的变量
+pub(crate) fn restore_comments(conn: &Connection, content: &str) -> Result<()> {
+    let (vars, trailing) = parse_dotenv(content);
+    for (var, comment) in vars {
+        conn.execute(
+            "UPDATE env_vars SET comment = ?1 WHERE key = ?2",
+            params![comment, var.key],
+        )?;
+    }
+    set_footer(conn, &trailing)
+}
+
+/// 全局变量之外的一组变量
+#[derive(Clone, Copy)]
+pub(crate) enum Scope<'a> {
+    /// 插件自己的变量
+    Plugin(&'a str),
+    /// 环境配置中的变量
+    Profile(&'a str),
+}
+
+impl Scope<'_> {
+    // 所在的表与归属列
+    fn table(&self) -> (&'static str, &'static str) {
+        match self {
+            Scope::Plugin(_) => ("plugin_env", "plugin_id"),
+            Scope::Profile(_) => ("env_profile_vars", "profile"),
+        }
+    }
+
+    fn owner(&self) -> &str {
+        match self {
+            Scope::Plugin(id) | Scope::Profile(id) => id,
+        }
+    }
+
+    // 密钥链中的账户前缀, 插件使用 id, 配置加上 profile: 前缀
+    fn secret_scope(&self) -> String {
+        match self {
+            Scope::Plugin(id) => id.to_string(),
+            Scope::Profile(name) => format!("profile:{}", name),
+        }
+    }
+}
+
+pub(crate) fn read_vars(conn: &Connection, scope: Scope) -> Result<Vec<EnvVar>> {
+    let (table, column) = scope.table();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT key, value FROM {} WHERE {} = ?1 ORDER BY position",
+        table, column
+    ))?;
+    let rows = stmt
+        .query_map([scope.owner()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let secret_scope = scope.secret_scope();
+    rows.into_iter()
+        .map(|(key, stored)| {
+            let value = secrets::resolve(Some(&secret_scope), &key, stored)?;
+            Ok(EnvVar { key, value })
+        })
+        .collect()
+}
+
+/// 覆盖保存, 值写入系统密钥链, 数据库只保留变量名
+pub(crate) fn write_vars(conn: &mut Connection, scope: Scope, vars: &[EnvVar]) -> Result<()> {
+    let secret_scope = scope.secret_scope();
+    for old in read_vars(conn, scope)? {
+        if !vars.iter().any(|var| var.key == old.key) {
+            secrets::delete(Some(&secret_scope), &old.key)?;
+        }
+    }
+    for var in vars {
+        secrets::set(Some(&secret_scope), &var.key, &var.value)?;
+    }
+    let (table, column) = scope.table();
+    let tx = conn.transaction()?;
+    tx.execute(
+        &format!("DELETE FROM {} WHERE {} = ?1", table, column),
+        [scope.owner()],
+    )?;
+    for (position, var) in vars.iter().enumerate() {
+        tx.execute(
+            &format!(
+                "INSERT OR REPLACE INTO {} ({}, key, value, position) VALUES (?1, ?2, ?3, ?4)",
+                table, column
+            ),
+            params![scope.owner(), var.key, "", position as i64],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// 插件自己的环境变量
+pub(crate) fn scoped(id: &str) -> Result<Vec<EnvVar>> {
+    with_db(|conn| read_vars(conn, Scope::Plugin(id)))
+}
+
+/// 执行插件时注入的环境变量: 插件声明需要的全局变量, 依次以当前环境配置与插件自己的变量覆盖
+pub(crate) async fn for_plugin(id: &str) -> Result<Vec<EnvVar>> {
+    let plugin = find_plugin(id).await?;
+    let mut globals = load_env_vars().await?;
+    if let Some(name) = profile::current() {
+        let overrides = with_db(|conn| read_vars(conn, Scope::Profile(&name)))?;
+        globals.retain(|var| !overrides.iter().any(|o| o.key == var.key));
+        globals.extend(overrides);
+    }
+    let scoped = scoped(id)?;
+    let mut vars: Vec<EnvVar> = globals
+        .into_iter()
+        .filter(|var| plugin.env.contains(&var.key))
+        .filter(|var| !scoped.iter().any(|s| s.key == var.key))
+        .collect();
+    vars.extend(scoped);
+    Ok(vars)
+}
+
+/// 复制插件自己的环境变量
+pub(crate) fn copy(from: &str, to: &str) -> Result<()> {
+    with_db(|conn| {
+        let vars = read_vars(conn, Scope::Plugin(from))?;
+        write_vars(conn, Scope::Plugin(to), &vars)
+    })
+}
+
+/// 删除插件自己的环境变量及其密钥
+pub(crate) fn remove(id: &str) -> Result<()> {
+    with_db(|conn| write_vars(conn, Scope::Plugin(id), &[]))
+}
+
+#[tauri::command]
+pub async fn plugin_env_list(id: String) -> Result<Vec<EnvVar>> {
+    find_plugin(&id).await?;
+    scoped(&id)
+}
+
+/// 覆盖保存插件自己的环境变量
+#[tauri::command]
+pub async fn plugin_env_save(id: String, vars: Vec<EnvVar>) -> Result<()> {
+    find_plugin(&id).await?;
+    check_vars(&vars)?;
+    with_db(|conn| write_vars(conn, Scope::Plugin(&id), &vars))
+}
+
+/// 设置单个全局变量, 已存在时保持原有顺序与注释, 否则追加到末尾
+#[tauri::command]
+pub async fn env_set(key: String, value: String) -> Result<()> {
+    check_key(&key)?;
+    with_db(|conn| {
+        secrets::set(None, &key, &value)?;
+        if stored_value(conn, &key)?.is_some() {
+            conn.execute("UPDATE env_vars SET value = '' WHERE key = ?1", [&key])?;
+        } else {
+            conn.execute(
+                "INSERT INTO env_vars (key, value, position)
+                 VALUES (?1, '', (SELECT COALESCE(MAX(position), -1) + 1 FROM env_vars))",
+                [&key],
+            )?;
+        }
+        Ok(())
+    })
+}
+
+/// 删除单个全局变量, 返回变量是否存在
+///
+/// 变量前的注释与空行移到下一个变量之前, 如分组标题, 没有下一个变量时移到末尾
+#[tauri::command]
+pub async fn env_delete(key: String) -> Result<bool> {
+    with_db(|conn| {
+        let removed: Option<(String, i64)> = conn
+            .query_row(
+                "SELECT comment, position FROM env_vars WHERE key = ?1",
+                [&key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((comment, position)) = removed else {
+            return Ok(false);
+        };
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM env_vars WHERE key = ?1", [&key])?;
+        if !comment.is_empty() {
+            let next: Option<String> = tx
+                .query_row(
+                    "SELECT key FROM env_vars WHERE position > ?1 ORDER BY position LIMIT 1",
+                    [position],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            match next {
+                Some(next) => {
+                    tx.execute(
+                        "UPDATE env_vars SET comment = ?1 || comment WHERE key = ?2",
+                        params![comment, next],
+                    )?;
+                }
+                None => {
+                    let trailing = footer(&tx)?;
+                    set_footer(&tx, &format!("{}{}", comment, trailing))?;
+                }
+            }
+        }
+        tx.commit()?;
+        secrets::delete(None, &key)?;
+        Ok(true)
+    })
+}
+
+/// 导出全局变量为 .env 格式, 保留导入时的注释与空行
+#[tauri::command]
+pub async fn env_export() -> Result<String> {
+    let vars = load_env_vars().await?;
+    with_db(|conn| {
+        let comments = comments(conn)?;
+        let mut content = String::new();
+        for var in vars {
+            if let Some(comment) = comments.get(&var.key) {
+                content.push_str(comment);
+            }
+            content.push_str(&format!("{}={}\n", var.key, var.value));
+        }
+        content.push_str(&footer(conn)?);
+        Ok(content)
+    })
+}
+
+/// 重命名全局变量, 保持值、顺序与注释不变
+#[tauri::command]
+pub async fn env_rename(old: String, new: String) -> Result<()> {
+    check_key(&new)?;
+    if old == new {
+        return Ok(());
+    }
+    with_db(|conn| {
+        let stored = stored_value(conn, &old)?
+            .ok_or_else(|| PluginError::Plugin(format!("变量不存在: {}", old)))?;
+        if stored_value(conn, &new)?.is_some() {
+            return Err(PluginError::Plugin(format!("变量已存在: {}", new)));
+        }
+        let value = secrets::resolve(None, &old, stored)?;
+        secrets::set(None, &new, &value)?;
+        conn.execute(
+            "UPDATE env_vars SET key = ?1, value = '' WHERE key = ?2",
+            params![new, old],
+        )?;
+        secrets::delete(None, &old)
+    })
+}
@@ -0,0 +1,134 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::deno::{find_plugin, register_plugin, PluginError, Result};
+use super::host;
+
+// Deno 拒绝访问时的错误信息, 如: NotCapable: Requires net access to "example.com:443"
+static DENIAL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"Requires (\w+) access(?: to "([^"]+)")?"#).unwrap());
+
+// 可以授予的 Deno 权限
+const KINDS: [&str; 8] = ["read", "write", "net", "env", "run", "sys", "ffi", "import"];
+
+/// 用户对一次权限请求的决定, 保存在插件记录中, 之后的执行直接应用
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PermissionGrant {
+    /// 权限类型, 如 net、read、run
+    pub kind: String,
+    /// 访问目标, 为空表示该类型的全部目标
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub allowed: bool,
+    pub time: i64,
+}
+
+/// 一次被拒绝的访问
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PermissionRequest {
+    pub kind: String,
+    pub target: Option<String>,
+}
+
+/// 从插件进程的错误输出中解析被拒绝的访问
+pub(crate) fn parse_denial(stderr: &str) -> Option<PermissionRequest> {
+    let captures = DENIAL.captures(stderr)?;
+    let kind = captures[1].to_string();
+    KINDS.contains(&kind.as_str()).then(|| PermissionRequest {
+        kind,
+        target: captures.get(2).map(|m| m.as_str().to_string()),
+    })
+}
+
+/// 合并默认权限与用户允许的权限, 生成 Deno 参数
+///
+/// 同一类型的目标合并为一个参数, 任一目标为空时授予该类型的全部目标
+pub(crate) fn flags(defaults: &[(&str, String)], grants: &[PermissionGrant]) -> Vec<String> {
+    let mut allow: BTreeMap<String, Option<Vec<String>>> = BTreeMap::new();
+    let granted = grants
+        .iter()
+        .filter(|g| g.allowed)
+        .map(|g| (g.kind.as_str(), g.target.clone()));
+    let defaults = defaults
+        .iter()
+        .map(|(kind, target)| (*kind, Some(target.clone())));
+    for (kind, target) in defaults.chain(granted) {
+        let entry = allow
+            .entry(kind.to_string())
+            .or_insert_with(|| Some(Vec::new()));
+        match (entry.as_mut(), target) {
+            (Some(targets), Some(target)) => {
+                if !targets.contains(&target) {
+                    targets.push(target);
+                }
+            }
+            _ => *entry = None,
+        }
+    }
+    allow
+        .into_iter()
+        .map(|(kind, targets)| match targets {
+            Some(targets) => format!("--allow-{}={}", kind, targets.join(",")),
+            None => format!("--allow-{}", kind),
+        })
+        .collect()
+}
+
+/// 询问用户是否允许插件的访问请求并记住决定, 已有决定时不再询问
+///
+/// 返回 true 表示已允许, 调用方可以重新执行
+pub(crate) async fn ask(id: &str, request: &PermissionRequest) -> Result<bool> {
+    let mut plugin = find_plugin(id).await?;
+    if plugin
+        .grants
+        .iter()
+        .any(|g| g.kind == request.kind && (g.target.is_none() || g.target == request.target))
+    {
+        // 已拒绝, 或已允许但仍被拒绝, 重新执行也不会成功
+        return Ok(false);
+    }
+    let target = request.target.as_deref().unwrap_or("全部");
+    let message = format!(
+        "插件「{}」请求 {} 权限: {}\n\n是否允许? 选择会被记住, 可在插件设置中撤销。",
+        plugin.name, request.kind, target
+    );
+    let allowed = host::dialog(Some("插件权限请求".to_string()), message, true).await;
+    tracing::info!(plugin = id, kind = %request.kind, target, allowed, "权限请求已处理");
+    plugin.grants.push(PermissionGrant {
+        kind: request.kind.clone(),
+        target: request.target.clone(),
+        allowed,
+        time: chrono::Utc::now().timestamp_millis(),
+    });
+    register_plugin(plugin).await?;
+    Ok(allowed)
+}
+
+/// 列出插件已保存的权限决定
+#[tauri::command]
+pub async fn plugin_grants(id: String) -> Result<Vec<PermissionGrant>> {
+    Ok(find_plugin(&id).await?.grants)
+}
+
+/// 撤销权限决定, 未指定类型时清空全部, 下次执行时重新询问
+#[tauri::command]
+pub async fn plugin_grant_revoke(
+    id: String,
+    kind: Option<String>,
+    target: Option<String>,
+) -> Result<Vec<PermissionGrant>> {
+    let mut plugin = find_plugin(&id).await?;
+    let before = plugin.grants.len();
+    match kind {
+        Some(kind) => plugin
+            .grants
+            .retain(|g| !(g.kind == kind && g.target == target)),
+        None => plugin.grants.clear(),
+    }
+    if plugin.grants.len() == before {
+        return Err(PluginError::Plugin("没有对应的权限决定".to_string()));
+    }
+    Ok(register_plugin(plugin).await?.grants)
+}
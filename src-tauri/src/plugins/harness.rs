@@ -0,0 +1,155 @@
+use jsonschema::JSONSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+
+use super::artifacts;
+use super::deno::{find_plugin, run_tool, Result};
+use super::logs;
+use super::schema;
+use crate::utils::gen::generate_id;
+
+// 读取测试期间日志时的最大条数
+const MAX_CAPTURED_LOGS: usize = 200;
+
+/// 插件声明的测试用例
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TestCase {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub tool: String,
+    #[serde(default)]
+    pub args: Value,
+    /// 结果需满足的 JSON Schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect: Option<Value>,
+    /// 结果需与之完全相等
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<Value>,
+    /// 期望工具执行失败
+    #[serde(
+        default,
+        alias = "expectError",
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub expect_error: bool,
+}
+
+/// 单个用例的执行结果
+#[derive(Debug, Serialize)]
+pub struct TestResult {
+    pub name: String,
+    pub tool: String,
+    pub passed: bool,
+    pub duration_ms: u64,
+    pub output: Option<Value>,
+    pub error: Option<String>,
+    /// 失败原因
+    pub failures: Vec<String>,
+    /// 执行期间插件输出的日志
+    pub logs: Vec<String>,
+}
+
+/// 插件测试报告
+#[derive(Debug, Serialize)]
+pub struct TestReport {
+    pub plugin_id: String,
+    pub passed: usize,
+    pub failed: usize,
+    pub results: Vec<TestResult>,
+}
+
+// 对比结果与期望, 返回不满足的条件
+fn check(case: &TestCase, result: &Result<Value>) -> Vec<String> {
+    let mut failures = Vec::new();
+    match result {
+        Err(err) if !case.expect_error => failures.push(format!("执行失败: {}", err)),
+        Err(_) => {}
+        Ok(_) if case.expect_error => failures.push("期望执行失败, 实际成功".to_string()),
+        Ok(value) => {
+            if let Some(expected) = &case.equals {
+                if expected != value {
+                    failures.push(format!("结果不相等, 期望 {}", expected));
+                }
+            }
+            if let Some(schema) = &case.expect {
+                match JSONSchema::compile(schema) {
+                    Ok(compiled) => {
+                        if let Err(errors) = compiled.validate(value) {
+                            failures.extend(errors.map(|err| {
+                                let path = err.instance_path.to_string();
+                                if path.is_empty() {
+                                    err.to_string()
+                                } else {
+                                    format!("{}: {}", path, err)
+                                }
+                            }));
+                        }
+                    }
+                    Err(err) => failures.push(format!("expect schema 无效: {}", err)),
+                }
+            }
+        }
+    }
+    failures
+}
+
+async fn run_case(id: &str, index: usize, case: &TestCase) -> Result<TestResult> {
+    let plugin = find_plugin(id).await?;
+    let started_at = chrono::Utc::now().timestamp_millis();
+    let started = std::time::Instant::now();
+    let execution_id = generate_id();
+    let dir = artifacts::create_dir(&execution_id)?;
+
+    let result = match plugin.tools.iter().find(|t| t.name == case.tool) {
+        Some(tool) => match schema::validate_args(tool, &case.args) {
+            Ok(()) => run_tool(id, &case.tool, &case.args, &dir, &[]).await,
+            Err(err) => Err(err),
+        },
+        None => Err(format!("未知函数: {}", case.tool).into()),
+    };
+    let duration_ms = started.elapsed().as_millis() as u64;
+    // 测试产生的文件不保留
+    let _ = fs::remove_dir_all(&dir);
+
+    let logs = logs::read_logs(id, MAX_CAPTURED_LOGS)?
+        .into_iter()
+        .filter(|log| log.time >= started_at && log.tool.as_deref() == Some(case.tool.as_str()))
+        .map(|log| log.message)
+        .collect();
+    let failures = check(case, &result);
+    let (output, error) = match result {
+        Ok(value) => (Some(value), None),
+        Err(err) => (None, Some(err.to_string())),
+    };
+    Ok(TestResult {
+        name: case
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("{} #{}", case.tool, index + 1)),
+        tool: case.tool.clone(),
+        passed: failures.is_empty(),
+        duration_ms,
+        output,
+        error,
+        failures,
+        logs,
+    })
+}
+
+/// 依次执行插件声明的测试用例, 不写入调用历史与缓存
+#[tauri::command]
+pub async fn plugin_test(id: String) -> Result<TestReport> {
+    let plugin = find_plugin(&id).await?;
+    let mut results = Vec::new();
+    for (index, case) in plugin.tests.iter().enumerate() {
+        results.push(run_case(&id, index, case).await?);
+    }
+    let passed = results.iter().filter(|r| r.passed).count();
+    Ok(TestReport {
+        plugin_id: id,
+        passed,
+        failed: results.len() - passed,
+        results,
+    })
+}
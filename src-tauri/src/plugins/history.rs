@@ -0,0 +1,237 @@
+use rusqlite::{params, Connection, ToSql};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::db::with_db;
+use super::deno::Result;
+use super::profile;
+use super::redact;
+use super::replay;
+use super::stats;
+
+// 结果保留的最大字符数
+const MAX_RESULT_CHARS: usize = 2000;
+// 默认每页条数
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// 一次工具调用的记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExecutionRecord {
+    /// 执行 id, 可用于重放
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_id: Option<String>,
+    pub time: i64,
+    pub plugin_id: String,
+    pub tool: String,
+    pub args: Value,
+    pub duration_ms: u64,
+    /// 尝试次数, 包含重试
+    #[serde(default)]
+    pub attempts: u32,
+    pub success: bool,
+    /// 截断后的结果或错误信息
+    pub output: String,
+    /// 执行时生效的环境配置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+}
+
+/// 历史记录筛选条件
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct HistoryFilter {
+    pub plugin_id: Option<String>,
+    pub tool: Option<String>,
+    pub success: Option<bool>,
+    /// 起止时间 (毫秒时间戳)
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+/// 分页参数, page 从 0 开始
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct HistoryPage {
+    pub page: usize,
+    pub page_size: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryResult {
+    pub total: usize,
+    pub records: Vec<ExecutionRecord>,
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_RESULT_CHARS {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(MAX_RESULT_CHARS).collect();
+    truncated.push_str("...");
+    truncated
+}
+
+/// 结果是否成功及保存到记录中的文本
+pub(crate) fn summarize(result: &Result<Value>) -> (bool, String) {
+    let (success, output) = match result {
+        Ok(value) => (true, value.to_string()),
+        Err(err) => (false, err.to_string()),
+    };
+    (success, truncate(&redact::scrub(&output)))
+}
+
+/// 追加一条调用记录
+pub(crate) async fn record(
+    execution_id: &str,
+    plugin_id: &str,
+    tool: &str,
+    args: &Value,
+    duration_ms: u64,
+    attempts: u32,
+    result: &Result<Value>,
+) -> Result<()> {
+    let (success, output) = summarize(result);
+    let entry = ExecutionRecord {
+        execution_id: Some(execution_id.to_string()),
+        time: chrono::Utc::now().timestamp_millis(),
+        plugin_id: plugin_id.to_string(),
+        tool: tool.to_string(),
+        args: redact::scrub_value(args),
+        duration_ms,
+        attempts,
+        success,
+        output,
+        profile: profile::current(),
+    };
+
+    with_db(|conn| {
+        insert_record(conn, &entry)?;
+        stats::record(conn, &entry)
+    })
+}
+
+/// 写入一条调用记录
+pub(crate) fn insert_record(conn: &Connection, record: &ExecutionRecord) -> Result<()> {
+    conn.execute(
+        "INSERT INTO execution_history
+             (time, plugin_id, tool, args, duration_ms, attempts, success, output, profile,
+              execution_id)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            record.time,
+            record.plugin_id,
+            record.tool,
+            serde_json::to_string(&record.args)?,
+            record.duration_ms as i64,
+            record.attempts,
+            record.success,
+            record.output,
+            record.profile,
+            record.execution_id
+        ],
+    )?;
+    Ok(())
+}
+
+const RECORD_COLUMNS: &str =
+    "time, plugin_id, tool, args, duration_ms, attempts, success, output, profile, execution_id";
+
+fn read_record(row: &rusqlite::Row) -> rusqlite::Result<ExecutionRecord> {
+    let args: String = row.get(3)?;
+    Ok(ExecutionRecord {
+        time: row.get(0)?,
+        plugin_id: row.get(1)?,
+        tool: row.get(2)?,
+        args: serde_json::from_str(&args).unwrap_or(Value::Null),
+        duration_ms: row.get::<_, i64>(4)? as u64,
+        attempts: row.get(5)?,
+        success: row.get(6)?,
+        output: row.get(7)?,
+        profile: row.get(8)?,
+        execution_id: row.get(9)?,
+    })
+}
+
+/// 按执行 id 查找记录
+pub(crate) fn find(execution_id: &str) -> Result<Option<ExecutionRecord>> {
+    with_db(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM execution_history WHERE execution_id = ?1",
+            RECORD_COLUMNS
+        ))?;
+        let mut rows = stmt.query_map([execution_id], read_record)?;
+        Ok(rows.next().transpose()?)
+    })
+}
+
+/// 按条件查询调用记录, 最新的在前
+#[tauri::command]
+pub async fn execution_history(
+    filter: Option<HistoryFilter>,
+    page: Option<HistoryPage>,
+) -> Result<HistoryResult> {
+    let filter = filter.unwrap_or_default();
+    let page = page.unwrap_or_default();
+    let page_size = page.page_size.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+
+    let mut conditions = Vec::new();
+    let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+    if let Some(id) = filter.plugin_id {
+        conditions.push("plugin_id = ?");
+        values.push(Box::new(id));
+    }
+    if let Some(tool) = filter.tool {
+        conditions.push("tool = ?");
+        values.push(Box::new(tool));
+    }
+    if let Some(success) = filter.success {
+        conditions.push("success = ?");
+        values.push(Box::new(success));
+    }
+    if let Some(since) = filter.since {
+        conditions.push("time >= ?");
+        values.push(Box::new(since));
+    }
+    if let Some(until) = filter.until {
+        conditions.push("time <= ?");
+        values.push(Box::new(until));
+    }
+    let clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    with_db(|conn| {
+        let params: Vec<&dyn ToSql> = values.iter().map(|v| v.as_ref()).collect();
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM execution_history {}", clause),
+            params.as_slice(),
+            |row| row.get(0),
+        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM execution_history {} ORDER BY time DESC, id DESC LIMIT {} OFFSET {}",
+            RECORD_COLUMNS,
+            clause,
+            page_size,
+            page.page * page_size
+        ))?;
+        let records = stmt
+            .query_map(params.as_slice(), read_record)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(HistoryResult {
+            total: total as usize,
+            records,
+        })
+    })
+}
+
+/// 清空调用记录
+#[tauri::command]
+pub async fn execution_history_clear() -> Result<()> {
+    with_db(|conn| {
+        conn.execute("DELETE FROM execution_history", [])?;
+        Ok(())
+    })?;
+    replay::clear()
+}
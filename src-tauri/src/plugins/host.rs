@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+use super::deno::{PluginError, Result};
+
+/// 插件可申请的宿主能力, 在插件元数据的 permissions 中声明
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HostPermission {
+    Clipboard,
+    Notification,
+    Open,
+    Dialog,
+}
+
+fn host_error(err: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("宿主调用失败: {}", err))
+}
+
+/// 读取剪贴板文本
+pub(crate) async fn clipboard_read() -> Result<String> {
+    tokio::task::spawn_blocking(|| {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_text())
+            .map_err(host_error)
+    })
+    .await
+    .map_err(host_error)?
+}
+
+/// 写入剪贴板文本
+pub(crate) async fn clipboard_write(text: String) -> Result<()> {
+    tokio::task::spawn_blocking(move || {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text))
+            .map_err(host_error)
+    })
+    .await
+    .map_err(host_error)?
+}
+
+/// 显示系统通知
+pub(crate) async fn notify(title: String, body: String) -> Result<()> {
+    tokio::task::spawn_blocking(move || {
+        notify_rust::Notification::new()
+            .summary(&title)
+            .body(&body)
+            .show()
+            .map(|_| ())
+            .map_err(host_error)
+    })
+    .await
+    .map_err(host_error)?
+}
+
+/// 用默认浏览器打开链接, 只允许 http、https 与 mailto
+pub(crate) fn open_url(url: &str) -> Result<()> {
+    let parsed =
+        url::Url::parse(url).map_err(|e| PluginError::Plugin(format!("无效的链接: {}", e)))?;
+    if !matches!(parsed.scheme(), "http" | "https" | "mailto") {
+        return Err(PluginError::Plugin(format!(
+            "不支持的链接协议: {}",
+            parsed.scheme()
+        )));
+    }
+    open::that(parsed.as_str()).map_err(host_error)
+}
+
+/// 显示消息对话框, confirm 为 true 时带取消按钮并返回用户是否确认
+pub(crate) async fn dialog(title: Option<String>, message: String, confirm: bool) -> bool {
+    let buttons = if confirm {
+        rfd::MessageButtons::OkCancel
+    } else {
+        rfd::MessageButtons::Ok
+    };
+    rfd::AsyncMessageDialog::new()
+        .set_title(title.as_deref().unwrap_or("Ghostie"))
+        .set_description(&message)
+        .set_buttons(buttons)
+        .show()
+        .await
+}
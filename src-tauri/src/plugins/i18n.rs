@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::deno::{Plugin, PluginError, Result};
+use crate::utils::settings;
+
+/// 某个语言下的插件名称与描述
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct Translation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+// 元数据中的文本, 可以是字符串或 { 语言: 文本 }
+enum Text {
+    Plain(String),
+    Localized(BTreeMap<String, String>),
+}
+
+fn parse_text(field: &str, value: &Value) -> Result<Option<Text>> {
+    match value {
+        Value::Null => Ok(None),
+        Value::String(text) => Ok(Some(Text::Plain(text.clone()))),
+        Value::Object(map) => {
+            let mut texts = BTreeMap::new();
+            for (locale, text) in map {
+                let text = text
+                    .as_str()
+                    .ok_or_else(|| PluginError::Plugin(format!("{} 字段无效", field)))?;
+                texts.insert(normalize(locale), text.to_string());
+            }
+            Ok(Some(Text::Localized(texts)))
+        }
+        _ => Err(PluginError::Plugin(format!("{} 字段无效", field))),
+    }
+}
+
+// 统一为小写并使用 "-" 分隔, 如 zh_CN -> zh-cn
+fn normalize(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_lowercase()
+}
+
+// 未指定语言时使用的文本: default, en, 其次按语言代码排序的第一个
+fn fallback(texts: &BTreeMap<String, String>) -> Option<String> {
+    texts
+        .get("default")
+        .or_else(|| texts.get("en"))
+        .or_else(|| texts.values().next())
+        .cloned()
+}
+
+/// 解析元数据中的 name 与 description, 返回默认文本与各语言的翻译
+pub(crate) fn parse(
+    name: &Value,
+    description: &Value,
+) -> Result<(String, Option<String>, BTreeMap<String, Translation>)> {
+    let mut translations: BTreeMap<String, Translation> = BTreeMap::new();
+    let mut default_text = |text: Option<Text>, set: fn(&mut Translation, String)| match text {
+        Some(Text::Plain(text)) => Some(text),
+        Some(Text::Localized(texts)) => {
+            let default = fallback(&texts);
+            for (locale, text) in texts {
+                set(translations.entry(locale).or_default(), text);
+            }
+            default
+        }
+        None => None,
+    };
+    let name = default_text(parse_text("name", name)?, |t, text| t.name = Some(text))
+        .ok_or_else(|| PluginError::Plugin("name 字段无效".to_string()))?;
+    let description = default_text(parse_text("description", description)?, |t, text| {
+        t.description = Some(text)
+    });
+    Ok((name, description, translations))
+}
+
+// 按 zh-cn -> zh 的顺序查找翻译
+fn lookup<'a>(
+    translations: &'a BTreeMap<String, Translation>,
+    locale: &str,
+    get: impl Fn(&'a Translation) -> Option<&'a String>,
+) -> Option<&'a String> {
+    let locale = normalize(locale);
+    let language = locale.split('-').next().unwrap_or_default();
+    translations
+        .get(&locale)
+        .and_then(&get)
+        .or_else(|| translations.get(language).and_then(&get))
+}
+
+/// 当前使用的语言, 未传入时读取应用设置
+pub(crate) fn current_locale(locale: Option<String>) -> Option<String> {
+    locale
+        .or_else(|| settings::get().locale)
+        .filter(|locale| !locale.trim().is_empty())
+}
+
+/// 将插件的名称与描述替换为指定语言的文本
+pub(crate) fn localize(plugin: &mut Plugin, locale: &str) {
+    if let Some(name) = lookup(&plugin.translations, locale, |t| t.name.as_ref()) {
+        plugin.name = name.clone();
+    }
+    if let Some(description) = lookup(&plugin.translations, locale, |t| t.description.as_ref()) {
+        plugin.description = Some(description.clone());
+    }
+}
+
+/// 设置插件名称与描述使用的语言, 为空时使用插件的默认文本
+#[tauri::command]
+pub async fn plugins_set_locale(locale: Option<String>) -> Result<Option<String>> {
+    let locale = locale.filter(|locale| !locale.trim().is_empty());
+    Ok(settings::update(|s| s.locale = locale)?.locale)
+}
@@ -0,0 +1,323 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+
+use super::db::with_db;
+use super::deno::{PluginError, Result};
+use super::embeddings::{self, Collection, DocumentChunk};
+use super::ocr;
+use crate::utils::document::{read_docx, read_pdf_pages};
+
+// 单个文件的大小上限
+const MAX_FILE_SIZE: u64 = 50 * 1024 * 1024;
+// 片段的默认长度与重叠 (字符数)
+const DEFAULT_CHUNK_SIZE: usize = 800;
+const DEFAULT_OVERLAP: usize = 100;
+
+/// 支持的文件扩展名, 图片通过文字识别提取
+pub(crate) const EXTENSIONS: [&str; 15] = [
+    "pdf", "docx", "md", "markdown", "html", "htm", "txt", "text", "png", "jpg", "jpeg", "bmp",
+    "tif", "tiff", "webp",
+];
+
+static HTML_IGNORED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(?is)<script\b.*?</script>|<style\b.*?</style>|",
+        r"<noscript\b.*?</noscript>|<head\b.*?</head>|<!--.*?-->"
+    ))
+    .unwrap()
+});
+static HTML_BLOCK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)<(br|/p|/div|/li|/tr|/h[1-6]|/section|/article|/blockquote|/pre)\b[^>]*>")
+        .unwrap()
+});
+static HTML_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+static BLANK_LINES: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n[ \t]*(\n[ \t]*)+").unwrap());
+
+/// 分块参数
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub struct ChunkingOptions {
+    /// 每个片段的最大字符数
+    pub chunk_size: usize,
+    /// 相邻片段重叠的字符数
+    pub overlap: usize,
+}
+
+impl Default for ChunkingOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            overlap: DEFAULT_OVERLAP,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestStatus {
+    Extracting,
+    Embedding,
+    Done,
+    Failed,
+}
+
+/// 单个文件的进度, 以 ingest://progress 事件发送
+#[derive(Debug, Serialize, Clone)]
+pub struct IngestProgress {
+    pub path: String,
+    /// 当前文件的序号与文件总数
+    pub index: usize,
+    pub total: usize,
+    pub status: IngestStatus,
+    pub chunks: usize,
+    pub error: Option<String>,
+}
+
+/// 单个文件的导入结果
+#[derive(Debug, Serialize, Clone)]
+pub struct IngestedFile {
+    pub path: String,
+    pub chunks: usize,
+    pub error: Option<String>,
+}
+
+// 提取出的一段文本, PDF 按页提取
+struct Section {
+    page: Option<usize>,
+    text: String,
+}
+
+fn ingest_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("提取文本失败: {}", message))
+}
+
+// 非 UTF-8 的文本按检测出的编码解码
+fn read_text(path: &Path) -> Result<String> {
+    let bytes = fs::read(path)?;
+    if let Ok(text) = String::from_utf8(bytes.clone()) {
+        return Ok(text);
+    }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(&bytes, true);
+    let (text, _, _) = detector.guess(None, true).decode(&bytes);
+    Ok(text.into_owned())
+}
+
+/// 解码常见的 HTML 实体
+pub(crate) fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+fn html_to_text(html: &str) -> String {
+    let text = HTML_IGNORED.replace_all(html, "");
+    let text = HTML_BLOCK.replace_all(&text, "\n");
+    let text = HTML_TAG.replace_all(&text, "");
+    let text = decode_entities(&text);
+    BLANK_LINES.replace_all(&text, "\n\n").trim().to_string()
+}
+
+fn extract(path: &Path) -> Result<Vec<Section>> {
+    let size = fs::metadata(path)?.len();
+    if size > MAX_FILE_SIZE {
+        return Err(ingest_error(format!(
+            "文件超过 {}MB",
+            MAX_FILE_SIZE / 1024 / 1024
+        )));
+    }
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    if !EXTENSIONS.contains(&extension.as_str()) {
+        return Err(ingest_error(format!("不支持的文件类型: {}", extension)));
+    }
+    let display = path.to_string_lossy();
+    let whole = |text: String| vec![Section { page: None, text }];
+    let pages = |pages: Vec<String>| -> Vec<Section> {
+        pages
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| Section {
+                page: Some(i + 1),
+                text,
+            })
+            .collect()
+    };
+    Ok(match extension.as_str() {
+        "pdf" => {
+            let text = read_pdf_pages(&display).map_err(ingest_error)?;
+            // 扫描版 PDF 没有文本层, 改为逐页识别
+            if text.iter().all(|page| page.trim().is_empty()) {
+                pages(ocr::recognize_pdf(path, None)?)
+            } else {
+                pages(text)
+            }
+        }
+        "docx" => whole(read_docx(&display).map_err(ingest_error)?),
+        "html" | "htm" => whole(html_to_text(&read_text(path)?)),
+        ext if ocr::IMAGE_EXTENSIONS.contains(&ext) => whole(ocr::recognize(path, None)?.text),
+        _ => whole(read_text(path)?),
+    })
+}
+
+// 片段尽量在段落或句子处结束, 在片段后半部分中找最后一个分隔符
+fn split_point(chars: &[char], start: usize, end: usize) -> usize {
+    if end >= chars.len() {
+        return chars.len();
+    }
+    let half = start + (end - start) / 2;
+    let groups: [&[char]; 3] = [
+        &['\n'],
+        &['。', '！', '？', '.', '!', '?'],
+        &['，', ',', ';', '；', ' '],
+    ];
+    for separators in groups {
+        if let Some(at) = (half..end).rev().find(|&i| separators.contains(&chars[i])) {
+            return at + 1;
+        }
+    }
+    end
+}
+
+/// 按长度切分文本, 相邻片段保留重叠部分
+pub(crate) fn chunk_text(text: &str, options: ChunkingOptions) -> Vec<String> {
+    let chunk_size = options.chunk_size.max(1);
+    let overlap = options.overlap.min(chunk_size / 2);
+    let chars: Vec<char> = text.chars().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let end = split_point(&chars, start, (start + chunk_size).min(chars.len()));
+        let chunk: String = chars[start..end].iter().collect();
+        if !chunk.trim().is_empty() {
+            chunks.push(chunk.trim().to_string());
+        }
+        if end >= chars.len() {
+            break;
+        }
+        start = end.saturating_sub(overlap).max(start + 1);
+    }
+    chunks
+}
+
+/// 提取并切分文件, 附带来源信息
+pub(crate) fn prepare(path: &Path, options: ChunkingOptions) -> Result<Vec<DocumentChunk>> {
+    let source = path.to_string_lossy().to_string();
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut chunks = Vec::new();
+    for section in extract(path)? {
+        for text in chunk_text(&section.text, options) {
+            chunks.push(DocumentChunk {
+                content: text,
+                source: Some(source.clone()),
+                metadata: json!({
+                    "file": source,
+                    "name": name,
+                    "page": section.page,
+                    "chunk": chunks.len(),
+                }),
+            });
+        }
+    }
+    Ok(chunks)
+}
+
+/// 导入一个文件, 替换该文件之前导入的片段, 返回片段数
+pub(crate) async fn ingest_file(
+    collection: &Collection,
+    chunks: Vec<DocumentChunk>,
+    source: &str,
+) -> Result<usize> {
+    let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+    let vectors = embeddings::embed_all(&collection.provider, &collection.model, &texts).await?;
+    with_db(|conn| {
+        embeddings::remove_source(conn, &collection.id, source)?;
+        embeddings::insert_documents(conn, collection, &chunks, &vectors)
+    })?;
+    Ok(chunks.len())
+}
+
+/// 提取文件文本并写入向量集合, 每个文件的进度以 ingest://progress 事件推送
+///
+/// # 参数
+/// * `paths` - PDF、DOCX、Markdown、HTML、纯文本或图片文件, 扫描件与图片通过文字识别提取
+/// * `collection` - 集合 id 或名称
+/// * `chunking` - 分块参数, 默认每段 800 字符、重叠 100 字符
+///
+/// 单个文件失败不影响其他文件, 错误记录在结果中
+#[tauri::command]
+pub async fn document_ingest(
+    app: AppHandle,
+    paths: Vec<String>,
+    collection: String,
+    chunking: Option<ChunkingOptions>,
+) -> Result<Vec<IngestedFile>> {
+    let collection = with_db(|conn| embeddings::find_collection(conn, &collection))?;
+    let options = chunking.unwrap_or_default();
+    let total = paths.len();
+    let emit =
+        |path: &str, index: usize, status: IngestStatus, chunks: usize, error: Option<String>| {
+            let _ = app.emit(
+                "ingest://progress",
+                IngestProgress {
+                    path: path.to_string(),
+                    index,
+                    total,
+                    status,
+                    chunks,
+                    error,
+                },
+            );
+        };
+
+    let mut results = Vec::with_capacity(total);
+    for (index, path) in paths.into_iter().enumerate() {
+        emit(&path, index, IngestStatus::Extracting, 0, None);
+        let file = Path::new(&path).to_path_buf();
+        let prepared = match tokio::task::spawn_blocking(move || prepare(&file, options)).await {
+            Ok(prepared) => prepared,
+            Err(err) => Err(ingest_error(err)),
+        };
+        let result = match prepared {
+            Ok(chunks) => {
+                emit(&path, index, IngestStatus::Embedding, chunks.len(), None);
+                ingest_file(&collection, chunks, &path).await
+            }
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(chunks) => {
+                emit(&path, index, IngestStatus::Done, chunks, None);
+                results.push(IngestedFile {
+                    path,
+                    chunks,
+                    error: None,
+                });
+            }
+            Err(err) => {
+                tracing::warn!(path = %path, error = %err, "文件导入失败");
+                emit(&path, index, IngestStatus::Failed, 0, Some(err.to_string()));
+                results.push(IngestedFile {
+                    path,
+                    chunks: 0,
+                    error: Some(err.to_string()),
+                });
+            }
+        }
+    }
+    tracing::info!(collection = %collection.name, files = total, "文档导入完成");
+    Ok(results)
+}
@@ -0,0 +1,389 @@
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, Emitter};
+
+use super::deno::{
+    find_plugin, process_plugin_content, register_plugin, Plugin, PluginError, Result,
+};
+use super::proxy;
+use super::runtime::run_limited;
+use super::signature::{self, SignatureInfo, SignatureStatus};
+use crate::utils::gen::generate_id;
+
+// 插件脚本的大小上限
+pub(crate) const MAX_SCRIPT_BYTES: usize = 1024 * 1024;
+// 允许的响应类型, 未声明类型时也接受
+const SCRIPT_CONTENT_TYPES: [&str; 6] = [
+    "text/",
+    "application/javascript",
+    "application/typescript",
+    "application/x-typescript",
+    "application/octet-stream",
+    "application/x-python",
+];
+
+/// 插件的安装来源
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PluginSource {
+    Url {
+        url: String,
+        sha256: String,
+    },
+    Git {
+        repo: String,
+        #[serde(rename = "ref")]
+        git_ref: Option<String>,
+        path: String,
+        /// 安装时的提交
+        commit: String,
+        sha256: String,
+    },
+    /// 通过 MCP 服务添加, transport 为连接方式
+    Mcp {
+        transport: String,
+    },
+}
+
+impl PluginSource {
+    fn sha256(&self) -> &str {
+        match self {
+            PluginSource::Url { sha256, .. } | PluginSource::Git { sha256, .. } => sha256,
+            PluginSource::Mcp { .. } => "",
+        }
+    }
+}
+
+/// 更新检查结果
+#[derive(Debug, Serialize)]
+pub struct UpdateCheck {
+    pub has_update: bool,
+    /// 当前安装内容的摘要
+    pub current: String,
+    /// 来源最新内容的摘要
+    pub latest: String,
+}
+
+pub(crate) fn digest(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// 下载插件脚本, 限制大小与响应类型
+pub(crate) async fn download_script(url: &str) -> Result<String> {
+    let parsed =
+        url::Url::parse(url).map_err(|e| PluginError::Plugin(format!("无效的链接: {}", e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(PluginError::Plugin(format!(
+            "不支持的链接协议: {}",
+            parsed.scheme()
+        )));
+    }
+
+    let response = proxy::client()
+        .get(parsed)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| PluginError::Plugin(format!("下载插件失败: {}", e)))?;
+    if let Some(content_type) = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    {
+        if !SCRIPT_CONTENT_TYPES
+            .iter()
+            .any(|allowed| content_type.starts_with(allowed))
+        {
+            return Err(PluginError::Plugin(format!(
+                "不支持的内容类型: {}",
+                content_type
+            )));
+        }
+    }
+    if response
+        .content_length()
+        .is_some_and(|len| len as usize > MAX_SCRIPT_BYTES)
+    {
+        return Err(PluginError::Plugin("插件文件过大".to_string()));
+    }
+
+    let mut bytes = Vec::new();
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| PluginError::Plugin(format!("下载插件失败: {}", e)))?;
+        bytes.extend_from_slice(&chunk);
+        if bytes.len() > MAX_SCRIPT_BYTES {
+            return Err(PluginError::Plugin("插件文件过大".to_string()));
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| PluginError::Plugin("插件文件不是 UTF-8 文本".to_string()))
+}
+
+// 不执行脚本, 从源码中粗略读取 name 等字段用于确认
+fn static_field(content: &str, field: &str) -> Option<String> {
+    let start = content.find(&format!("{}:", field))? + field.len() + 1;
+    let rest = content[start..].trim_start();
+    let quote = rest
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '"' | '\'' | '`'))?;
+    let end = rest[1..].find(quote)?;
+    Some(rest[1..end + 1].to_string())
+}
+
+// 展示来源与基本信息, 由用户确认是否安装
+async fn confirm_install(url: &str, content: &str, signature: &SignatureStatus) -> bool {
+    let name = static_field(content, "name").unwrap_or_else(|| "未知".to_string());
+    let description = static_field(content, "description").unwrap_or_default();
+    let signature = match signature {
+        SignatureStatus::Verified { publisher } => format!("已签名 ({})", publisher),
+        SignatureStatus::Unsigned => "未签名".to_string(),
+        SignatureStatus::Invalid => "签名无效".to_string(),
+    };
+    let message = format!(
+        "是否安装来自以下地址的插件?\n\n{}\n\n名称: {}\n描述: {}\n签名: {}\n大小: {} 字节\nSHA-256: {}",
+        url,
+        name,
+        description,
+        signature,
+        content.len(),
+        digest(content)
+    );
+    rfd::AsyncMessageDialog::new()
+        .set_title("安装插件")
+        .set_description(&message)
+        .set_buttons(rfd::MessageButtons::OkCancel)
+        .show()
+        .await
+}
+
+// 处理 echo://plugin/install?url=...
+async fn install_from_link(link: &url::Url) -> Result<Option<Plugin>> {
+    if link.host_str() != Some("plugin") || link.path() != "/install" {
+        return Err(PluginError::Plugin(format!("不支持的链接: {}", link)));
+    }
+    let url = link
+        .query_pairs()
+        .find(|(key, _)| key == "url")
+        .map(|(_, value)| value.to_string())
+        .ok_or_else(|| PluginError::Plugin("链接缺少 url 参数".to_string()))?;
+
+    let detached = link
+        .query_pairs()
+        .find(|(key, _)| key == "signature")
+        .map(|(_, value)| value.to_string());
+
+    let content = download_script(&url).await?;
+    let status = signature::verify(&content, detached.as_deref());
+    if !confirm_install(&url, &content, &status).await {
+        return Ok(None);
+    }
+    // 用户已在对话框中看到签名状态并确认
+    let info = signature::check(&content, detached.as_deref(), true)?;
+    let source = PluginSource::Url {
+        sha256: digest(&content),
+        url,
+    };
+    install(generate_id(), content, source, info)
+        .await
+        .map(Some)
+}
+
+// 检查仓库地址与引用, 避免被 git 当作命令行选项
+fn check_git_arg(value: &str, name: &str) -> Result<()> {
+    if value.is_empty() || value.starts_with('-') {
+        return Err(PluginError::Plugin(format!("无效的 {}: {}", name, value)));
+    }
+    Ok(())
+}
+
+// 浅克隆仓库并读取指定文件, 返回内容与提交
+async fn fetch_git(repo: &str, git_ref: Option<&str>, path: &str) -> Result<(String, String)> {
+    check_git_arg(repo, "仓库地址")?;
+    if let Some(git_ref) = git_ref {
+        check_git_arg(git_ref, "引用")?;
+    }
+    let relative = Path::new(path);
+    if relative.is_absolute()
+        || relative
+            .components()
+            .any(|c| matches!(c, std::path::Component::ParentDir))
+    {
+        return Err(PluginError::Plugin(format!("无效的文件路径: {}", path)));
+    }
+
+    let dir = std::env::temp_dir().join(format!("echo_git_{}", generate_id()));
+    let result = async {
+        let mut clone = tokio::process::Command::new("git");
+        clone.args(["clone", "--depth", "1"]);
+        if let Some(git_ref) = git_ref {
+            clone.args(["--branch", git_ref]);
+        }
+        clone.arg("--").arg(repo).arg(&dir);
+        run_limited(clone, None).await?.into_stdout()?;
+
+        let mut rev = tokio::process::Command::new("git");
+        rev.arg("-C").arg(&dir).args(["rev-parse", "HEAD"]);
+        let commit = run_limited(rev, None)
+            .await?
+            .into_stdout()?
+            .trim()
+            .to_string();
+
+        let file = dir.join(relative);
+        if fs::metadata(&file)?.len() as usize > MAX_SCRIPT_BYTES {
+            return Err(PluginError::Plugin("插件文件过大".to_string()));
+        }
+        let content = fs::read_to_string(&file)?;
+        Ok((content, commit))
+    }
+    .await;
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+// 从来源重新获取插件内容
+async fn fetch_source(source: &PluginSource) -> Result<(String, PluginSource)> {
+    match source {
+        PluginSource::Url { url, .. } => {
+            let content = download_script(url).await?;
+            let source = PluginSource::Url {
+                url: url.clone(),
+                sha256: digest(&content),
+            };
+            Ok((content, source))
+        }
+        PluginSource::Git {
+            repo,
+            git_ref,
+            path,
+            ..
+        } => {
+            let (content, commit) = fetch_git(repo, git_ref.as_deref(), path).await?;
+            let source = PluginSource::Git {
+                repo: repo.clone(),
+                git_ref: git_ref.clone(),
+                path: path.clone(),
+                commit,
+                sha256: digest(&content),
+            };
+            Ok((content, source))
+        }
+        PluginSource::Mcp { .. } => Err(PluginError::Plugin(
+            "MCP 服务的工具由服务提供, 请刷新工具列表".to_string(),
+        )),
+    }
+}
+
+// 走与导入相同的流程, 并记录来源与签名校验结果
+pub(crate) async fn install(
+    id: String,
+    content: String,
+    source: PluginSource,
+    signature: SignatureInfo,
+) -> Result<Plugin> {
+    let mut plugin = process_plugin_content(id, content).await?;
+    plugin.source = Some(source);
+    plugin.signature = Some(signature);
+    register_plugin(plugin).await
+}
+
+/// 从链接导入插件, signature 为可选的独立签名
+#[tauri::command]
+pub async fn plugin_import_url(
+    url: String,
+    signature: Option<String>,
+    allow_unsigned: Option<bool>,
+) -> Result<Plugin> {
+    let source = PluginSource::Url {
+        url,
+        sha256: String::new(),
+    };
+    let (content, source) = fetch_source(&source).await?;
+    let info = signature::check(
+        &content,
+        signature.as_deref(),
+        allow_unsigned.unwrap_or(false),
+    )?;
+    install(generate_id(), content, source, info).await
+}
+
+/// 从 git 仓库导入插件, path 为仓库内的插件文件
+#[tauri::command]
+pub async fn plugin_import_git(
+    repo: String,
+    git_ref: Option<String>,
+    path: String,
+    signature: Option<String>,
+    allow_unsigned: Option<bool>,
+) -> Result<Plugin> {
+    let source = PluginSource::Git {
+        repo,
+        git_ref,
+        path,
+        commit: String::new(),
+        sha256: String::new(),
+    };
+    let (content, source) = fetch_source(&source).await?;
+    let info = signature::check(
+        &content,
+        signature.as_deref(),
+        allow_unsigned.unwrap_or(false),
+    )?;
+    install(generate_id(), content, source, info).await
+}
+
+fn installed_source(plugin: &Plugin) -> Result<&PluginSource> {
+    plugin
+        .source
+        .as_ref()
+        .ok_or_else(|| PluginError::Plugin(format!("插件没有记录安装来源: {}", plugin.id)))
+}
+
+/// 对比来源的最新内容, 检查插件是否有更新
+#[tauri::command]
+pub async fn plugin_check_update(id: String) -> Result<UpdateCheck> {
+    let plugin = find_plugin(&id).await?;
+    let source = installed_source(&plugin)?;
+    let (_, latest) = fetch_source(source).await?;
+    Ok(UpdateCheck {
+        has_update: source.sha256() != latest.sha256(),
+        current: source.sha256().to_string(),
+        latest: latest.sha256().to_string(),
+    })
+}
+
+/// 从安装来源更新插件
+///
+/// # 参数
+/// * `allow_unsigned` - 允许安装未通过签名校验的新版本, 之前已确认过的插件无需再次确认
+#[tauri::command]
+pub async fn plugin_update_from_source(id: String, allow_unsigned: Option<bool>) -> Result<Plugin> {
+    let plugin = find_plugin(&id).await?;
+    let (content, source) = fetch_source(installed_source(&plugin)?).await?;
+    // 之前确认过的插件更新后仍需重新校验, 用户的确认继续有效
+    let overridden =
+        allow_unsigned.unwrap_or(false) || plugin.signature.is_some_and(|s| s.overridden);
+    let info = signature::check(&content, None, overridden)?;
+    let plugin = install(id, content, source, info).await?;
+    super::service::reload(&plugin.id).await?;
+    Ok(plugin)
+}
+
+/// 处理系统传入的 echo:// 链接, 结果通过事件通知前端
+pub fn handle_deep_link(app: AppHandle, link: url::Url) {
+    tauri::async_runtime::spawn(async move {
+        match install_from_link(&link).await {
+            Ok(Some(plugin)) => {
+                let _ = app.emit("plugins://installed", plugin);
+            }
+            Ok(None) => {}
+            Err(err) => {
+                let _ = app.emit("plugins://install-failed", err.to_string());
+            }
+        }
+    });
+}
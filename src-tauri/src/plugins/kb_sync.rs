@@ -0,0 +1,557 @@
+use globset::{Glob, GlobMatcher};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::{Lazy, OnceCell};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc;
+use walkdir::WalkDir;
+
+use super::db::with_db;
+use super::deno::{PluginError, Result};
+use super::embeddings::{self, Collection};
+use super::ingest::{self, ChunkingOptions, EXTENSIONS};
+use crate::utils::gen::generate_id;
+
+// 文件变化后等待静默的时间
+const DEBOUNCE: Duration = Duration::from_secs(2);
+// 默认的定期扫描间隔 (秒)
+const DEFAULT_INTERVAL_SECS: u64 = 600;
+// 检查是否需要定期扫描的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 知识库目录, 目录中的文档同步到向量集合
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KbSource {
+    pub id: String,
+    pub collection_id: String,
+    /// 同步的目录
+    pub path: String,
+    /// 文件匹配规则, 相对目录, 如 "**/*.md"
+    pub pattern: String,
+    pub recursive: bool,
+    /// 定期全量扫描的间隔, 为 0 时只依赖文件监听
+    pub interval_secs: u64,
+    pub chunking: ChunkingOptions,
+    pub created_at: i64,
+}
+
+/// 添加知识库目录的参数
+#[derive(Debug, Deserialize)]
+pub struct KbSourceOptions {
+    pub path: String,
+    /// 集合 id 或名称
+    pub collection: String,
+    pub pattern: Option<String>,
+    pub recursive: Option<bool>,
+    pub interval_secs: Option<u64>,
+    pub chunking: Option<ChunkingOptions>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState {
+    #[default]
+    Idle,
+    Syncing,
+    Failed,
+}
+
+/// 同步状态, 变化时以 kb://sync 事件发送
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct SyncStatus {
+    pub source_id: String,
+    pub state: SyncState,
+    pub last_sync: Option<i64>,
+    /// 上次同步新增、更新、移除与失败的文件数
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub failed: usize,
+    /// 已索引的文件数
+    pub files: usize,
+    pub error: Option<String>,
+}
+
+/// 知识库目录及其同步状态
+#[derive(Debug, Serialize)]
+pub struct KbSourceInfo {
+    #[serde(flatten)]
+    pub source: KbSource,
+    pub watching: bool,
+    pub status: SyncStatus,
+}
+
+// 已索引文件的记录
+struct IndexedFile {
+    modified: i64,
+    hash: String,
+}
+
+static APP: OnceCell<AppHandle> = OnceCell::new();
+// 正在运行的监听器, 移除后停止监听
+static WATCHERS: Lazy<Mutex<HashMap<String, RecommendedWatcher>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static STATUS: Lazy<Mutex<HashMap<String, SyncStatus>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+// 正在同步的目录, 值表示同步期间是否又有变化, 需要再同步一次
+static RUNNING: Lazy<Mutex<HashMap<String, bool>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn sync_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("知识库同步失败: {}", message))
+}
+
+fn compile_pattern(pattern: &str) -> Result<GlobMatcher> {
+    Glob::new(pattern)
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| PluginError::Plugin(format!("无效的匹配规则: {}", e)))
+}
+
+// 扩展名受支持且匹配规则的文件
+fn included(matcher: &GlobMatcher, root: &Path, path: &Path) -> bool {
+    let supported = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .is_some_and(|e| EXTENSIONS.contains(&e.as_str()));
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    supported
+        && (matcher.is_match(relative)
+            || path.file_name().is_some_and(|name| matcher.is_match(name)))
+}
+
+fn read_source(id: &str) -> Result<KbSource> {
+    with_db(|conn| {
+        let data: String = conn
+            .query_row("SELECT data FROM kb_sources WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .map_err(|_| PluginError::Plugin(format!("知识库目录不存在: {}", id)))?;
+        Ok(serde_json::from_str(&data)?)
+    })
+}
+
+fn read_sources() -> Result<Vec<KbSource>> {
+    with_db(|conn| {
+        let mut stmt = conn.prepare("SELECT data FROM kb_sources")?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows
+            .iter()
+            .filter_map(|data| serde_json::from_str(data).ok())
+            .collect())
+    })
+}
+
+fn status_of(id: &str) -> SyncStatus {
+    STATUS
+        .lock()
+        .unwrap()
+        .get(id)
+        .cloned()
+        .unwrap_or_else(|| SyncStatus {
+            source_id: id.to_string(),
+            ..Default::default()
+        })
+}
+
+fn set_status(status: SyncStatus) {
+    if let Some(app) = APP.get() {
+        let _ = app.emit("kb://sync", &status);
+    }
+    STATUS
+        .lock()
+        .unwrap()
+        .insert(status.source_id.clone(), status);
+}
+
+// 列出目录中需要索引的文件及其修改时间
+fn scan(source: &KbSource) -> Result<Vec<(PathBuf, i64)>> {
+    let root = PathBuf::from(&source.path);
+    if !root.is_dir() {
+        return Err(sync_error(format!("目录不存在: {}", source.path)));
+    }
+    let matcher = compile_pattern(&source.pattern)?;
+    let depth = if source.recursive { usize::MAX } else { 1 };
+    Ok(WalkDir::new(&root)
+        .max_depth(depth)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && included(&matcher, &root, entry.path()))
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            let modified = chrono::DateTime::<chrono::Utc>::from(modified).timestamp_millis();
+            Some((entry.into_path(), modified))
+        })
+        .collect())
+}
+
+fn file_hash(path: &Path) -> Result<String> {
+    Ok(format!("{:x}", Sha256::digest(fs::read(path)?)))
+}
+
+fn save_file(source_id: &str, path: &str, modified: i64, hash: &str, chunks: usize) -> Result<()> {
+    with_db(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO kb_files (source_id, path, modified, hash, chunks)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![source_id, path, modified, hash, chunks as i64],
+        )?;
+        Ok(())
+    })
+}
+
+// 重新导入内容变化的文件, 返回是否重新导入
+async fn sync_file(
+    source: &KbSource,
+    collection: &Collection,
+    path: PathBuf,
+    modified: i64,
+    previous: Option<&IndexedFile>,
+) -> Result<bool> {
+    let key = path.to_string_lossy().to_string();
+    let hashed = path.clone();
+    let hash = tokio::task::spawn_blocking(move || file_hash(&hashed))
+        .await
+        .map_err(sync_error)??;
+    // 只有修改时间变化时不重新导入
+    if previous.is_some_and(|p| p.hash == hash) {
+        save_modified(&source.id, &key, modified)?;
+        return Ok(false);
+    }
+    let options = source.chunking;
+    let chunks = tokio::task::spawn_blocking(move || ingest::prepare(&path, options))
+        .await
+        .map_err(sync_error)??;
+    let count = ingest::ingest_file(collection, chunks, &key).await?;
+    save_file(&source.id, &key, modified, &hash, count)?;
+    Ok(true)
+}
+
+// 比较目录与已索引的文件, 只重新导入新增或内容变化的文件
+async fn sync_once(
+    source: &KbSource,
+    collection: &Collection,
+    status: &mut SyncStatus,
+) -> Result<()> {
+    let mut indexed: HashMap<String, IndexedFile> = with_db(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT path, modified, hash FROM kb_files WHERE source_id = ?1")?;
+        let rows = stmt
+            .query_map([&source.id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    IndexedFile {
+                        modified: row.get(1)?,
+                        hash: row.get(2)?,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+        Ok(rows)
+    })?;
+
+    let scanned = {
+        let source = source.clone();
+        tokio::task::spawn_blocking(move || scan(&source))
+            .await
+            .map_err(sync_error)??
+    };
+    let mut files = 0;
+    for (path, modified) in scanned {
+        let key = path.to_string_lossy().to_string();
+        let previous = indexed.remove(&key);
+        if previous.as_ref().is_some_and(|p| p.modified == modified) {
+            files += 1;
+            continue;
+        }
+        match sync_file(source, collection, path, modified, previous.as_ref()).await {
+            Ok(changed) => {
+                files += 1;
+                match (changed, previous.is_some()) {
+                    (false, _) => {}
+                    (true, true) => status.updated += 1,
+                    (true, false) => status.added += 1,
+                }
+            }
+            Err(err) => {
+                tracing::warn!(path = %key, error = %err, "知识库文件同步失败");
+                status.failed += 1;
+            }
+        }
+    }
+
+    // 剩下的记录对应已删除的文件
+    for path in indexed.into_keys() {
+        with_db(|conn| {
+            embeddings::remove_source(conn, &collection.id, &path)?;
+            conn.execute(
+                "DELETE FROM kb_files WHERE source_id = ?1 AND path = ?2",
+                [&source.id, &path],
+            )?;
+            Ok(())
+        })?;
+        status.removed += 1;
+    }
+    status.files = files;
+    Ok(())
+}
+
+fn save_modified(source_id: &str, path: &str, modified: i64) -> Result<()> {
+    with_db(|conn| {
+        conn.execute(
+            "UPDATE kb_files SET modified = ?3 WHERE source_id = ?1 AND path = ?2",
+            params![source_id, path, modified],
+        )?;
+        Ok(())
+    })
+}
+
+/// 同步知识库目录, 同步进行中时在结束后再同步一次
+pub(crate) async fn sync(id: &str) -> Result<SyncStatus> {
+    {
+        let mut running = RUNNING.lock().unwrap();
+        if let Some(again) = running.get_mut(id) {
+            *again = true;
+            return Ok(status_of(id));
+        }
+        running.insert(id.to_string(), false);
+    }
+    loop {
+        let result = run_sync(id).await;
+        let mut running = RUNNING.lock().unwrap();
+        if running.get(id) == Some(&true) && result.is_ok() {
+            running.insert(id.to_string(), false);
+            continue;
+        }
+        running.remove(id);
+        return result;
+    }
+}
+
+async fn run_sync(id: &str) -> Result<SyncStatus> {
+    let previous = status_of(id);
+    set_status(SyncStatus {
+        state: SyncState::Syncing,
+        ..previous.clone()
+    });
+    let mut status = SyncStatus {
+        source_id: id.to_string(),
+        ..Default::default()
+    };
+    let result = async {
+        let source = read_source(id)?;
+        let collection = with_db(|conn| embeddings::find_collection(conn, &source.collection_id))?;
+        sync_once(&source, &collection, &mut status).await
+    }
+    .await;
+    status.last_sync = Some(chrono::Utc::now().timestamp_millis());
+    match &result {
+        Ok(()) => {
+            tracing::info!(
+                source = %id,
+                added = status.added,
+                updated = status.updated,
+                removed = status.removed,
+                failed = status.failed,
+                "知识库同步完成"
+            );
+        }
+        Err(err) => {
+            tracing::warn!(source = %id, error = %err, "知识库同步失败");
+            status.state = SyncState::Failed;
+            status.error = Some(err.to_string());
+            status.files = previous.files;
+        }
+    }
+    set_status(status.clone());
+    result.map(|_| status)
+}
+
+// 收集事件, 在静默后同步一次
+async fn debounce(id: String, mut events: mpsc::UnboundedReceiver<()>) {
+    while events.recv().await.is_some() {
+        loop {
+            match tokio::time::timeout(DEBOUNCE, events.recv()).await {
+                Ok(Some(())) => {}
+                Ok(None) => return,
+                Err(_) => break,
+            }
+        }
+        let _ = sync(&id).await;
+    }
+}
+
+// 监听目录, 文件新增、修改、删除时触发同步
+fn watch(source: &KbSource) -> Result<()> {
+    let root = PathBuf::from(&source.path);
+    if !root.is_dir() {
+        return Err(sync_error(format!("目录不存在: {}", source.path)));
+    }
+    let matcher = compile_pattern(&source.pattern)?;
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    let watch_root = root.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        if !matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) {
+            return;
+        }
+        if event
+            .paths
+            .iter()
+            .any(|path| included(&matcher, &watch_root, path))
+        {
+            let _ = tx.send(());
+        }
+    })
+    .map_err(|e| PluginError::Plugin(format!("无法创建文件监听: {}", e)))?;
+    let mode = if source.recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher
+        .watch(&root, mode)
+        .map_err(|e| PluginError::Plugin(format!("无法监听目录: {}", e)))?;
+
+    tokio::spawn(debounce(source.id.clone(), rx));
+    WATCHERS.lock().unwrap().insert(source.id.clone(), watcher);
+    Ok(())
+}
+
+fn unwatch(id: &str) {
+    WATCHERS.lock().unwrap().remove(id);
+}
+
+// 到达间隔的目录做一次全量扫描, 补上监听遗漏的变化
+async fn run_periodic() {
+    let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        ticker.tick().await;
+        let now = chrono::Utc::now().timestamp_millis();
+        for source in read_sources().unwrap_or_default() {
+            if source.interval_secs == 0 {
+                continue;
+            }
+            let due = match status_of(&source.id).last_sync {
+                Some(last) => now - last >= (source.interval_secs * 1000) as i64,
+                None => true,
+            };
+            if due {
+                let _ = sync(&source.id).await;
+            }
+        }
+    }
+}
+
+/// 启动所有知识库目录的监听与定期扫描, 应用启动时调用
+///
+/// 启动时先同步一次, 导入应用关闭期间的变化
+pub async fn start_all(app: AppHandle) {
+    let _ = APP.set(app);
+    for source in read_sources().unwrap_or_default() {
+        if let Err(err) = watch(&source) {
+            tracing::warn!(source = %source.id, error = %err, "知识库目录监听启动失败");
+        }
+    }
+    run_periodic().await;
+}
+
+/// 添加知识库目录并开始首次同步
+#[tauri::command]
+pub async fn kb_source_add(options: KbSourceOptions) -> Result<KbSource> {
+    let collection = with_db(|conn| embeddings::find_collection(conn, &options.collection))?;
+    let path = PathBuf::from(&options.path);
+    if !path.is_dir() {
+        return Err(sync_error(format!("目录不存在: {}", options.path)));
+    }
+    let pattern = options.pattern.unwrap_or_else(|| "**/*".to_string());
+    compile_pattern(&pattern)?;
+
+    let source = KbSource {
+        id: generate_id(),
+        collection_id: collection.id,
+        path: path.to_string_lossy().to_string(),
+        pattern,
+        recursive: options.recursive.unwrap_or(true),
+        interval_secs: options.interval_secs.unwrap_or(DEFAULT_INTERVAL_SECS),
+        chunking: options.chunking.unwrap_or_default(),
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+    with_db(|conn| {
+        conn.execute(
+            "INSERT INTO kb_sources (id, collection_id, data) VALUES (?1, ?2, ?3)",
+            params![
+                source.id,
+                source.collection_id,
+                serde_json::to_string(&source)?
+            ],
+        )?;
+        Ok(())
+    })?;
+    watch(&source)?;
+    let id = source.id.clone();
+    tokio::spawn(async move {
+        let _ = sync(&id).await;
+    });
+    Ok(source)
+}
+
+#[tauri::command]
+pub async fn kb_source_list() -> Result<Vec<KbSourceInfo>> {
+    let sources = read_sources()?;
+    let watchers = WATCHERS.lock().unwrap();
+    Ok(sources
+        .into_iter()
+        .map(|source| KbSourceInfo {
+            watching: watchers.contains_key(&source.id),
+            status: status_of(&source.id),
+            source,
+        })
+        .collect())
+}
+
+/// 移除知识库目录
+///
+/// # 参数
+/// * `purge` - 是否同时从集合中删除该目录导入的片段, 默认删除
+#[tauri::command]
+pub async fn kb_source_remove(id: String, purge: Option<bool>) -> Result<()> {
+    unwatch(&id);
+    let source = read_source(&id)?;
+    with_db(|conn| {
+        if purge.unwrap_or(true) {
+            let paths = {
+                let mut stmt = conn.prepare("SELECT path FROM kb_files WHERE source_id = ?1")?;
+                let rows = stmt
+                    .query_map([&id], |row| row.get::<_, String>(0))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows
+            };
+            for path in paths {
+                embeddings::remove_source(conn, &source.collection_id, &path)?;
+            }
+        }
+        conn.execute("DELETE FROM kb_sources WHERE id = ?1", [&id])?;
+        Ok(())
+    })?;
+    STATUS.lock().unwrap().remove(&id);
+    Ok(())
+}
+
+/// 立即同步知识库目录, 返回同步结果
+#[tauri::command]
+pub async fn kb_sync_now(source: String) -> Result<SyncStatus> {
+    sync(&source).await
+}
@@ -4,8 +4,9 @@ const CHUNK_SIZE: usize = 300;
 const EMBEDDING_DIMENSION: usize = 1024;
 const KNOWLEDGE_VERSION: &str = "1.0.0";
 
-use crate::utils::file::get_config_dir;
+use super::proxy;
 use crate::utils::gen::generate_id;
+use crate::utils::workspace;
 use reqwest;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -67,7 +68,7 @@ pub struct KnowledgeState {
 impl KnowledgeState {
     pub fn new() -> Self {
         // 确保知识库目录存在
-        if let Some(mut config_dir) = get_config_dir() {
+        if let Some(mut config_dir) = workspace::dir() {
             config_dir.push("knowledge");
             if !config_dir.exists() {
                 let _ = fs::create_dir_all(&config_dir);
@@ -81,7 +82,7 @@ impl KnowledgeState {
     }
 
     fn get_knowledge_dir() -> Option<PathBuf> {
-        let mut path = get_config_dir()?;
+        let mut path = workspace::dir()?;
         path.push("knowledge");
         Some(path)
     }
@@ -136,7 +137,7 @@ impl KnowledgeState {
 }
 
 fn get_api_key_path() -> Option<PathBuf> {
-    let mut path = get_config_dir()?;
+    let mut path = workspace::dir()?;
     path.push("aliyun_api_key.txt");
     Some(path)
 }
@@ -220,7 +221,7 @@ pub fn save_aliyun_api_key(key: String) -> Result<(), String> {
     let api_key_path = get_api_key_path().ok_or("无法获取 API Key 文件路径")?;
 
     // 确保配置目录存在
-    if let Some(config_dir) = get_config_dir() {
+    if let Some(config_dir) = workspace::dir() {
         if !config_dir.exists() {
             fs::create_dir_all(&config_dir).map_err(|e| format!("创建配置目录失败: {}", e))?;
         }
@@ -238,7 +239,7 @@ async fn text_to_embedding(text: &str) -> Result<Vec<f32>, String> {
         return Err("API Key 未配置".to_string());
     }
 
-    let client = reqwest::Client::new();
+    let client = proxy::client();
     let response = client
         .post("https://dashscope.aliyuncs.com/compatible-mode/v1/embeddings")
         .header("Authorization", format!("Bearer {}", api_key))
@@ -263,8 +264,6 @@ async fn text_to_embedding(text: &str) -> Result<Vec<f32>, String> {
         .await
         .map_err(|e| format!("解析响应失败: {}", e))?;
 
-    println!("response_json: {:?}", response_json);
-
     // 从响应中提取 embedding 向量
     let embedding = response_json["data"][0]["embedding"]
         .as_array()
@@ -365,13 +364,13 @@ pub async fn upload_knowledge_file(
         // 文本分块
         let text_chunks = split_text_into_chunks(&content, CHUNK_SIZE);
 
-        println!("text_chunks: {:?}", text_chunks);
+        tracing::debug!(file = %file_path, chunks = text_chunks.len(), "文本已分块");
 
         // 为每个分块生成向量
         let mut chunks = Vec::new();
         for (i, chunk_text) in text_chunks.iter().enumerate() {
             let embedding = text_to_embedding(&chunk_text).await?;
-            println!("embedding: {:?}", embedding);
+            tracing::trace!(chunk = i, dimension = embedding.len(), "已生成向量");
             // 验证向量维度
             if embedding.len() != EMBEDDING_DIMENSION {
                 return Err(format!(
@@ -437,6 +436,11 @@ pub async fn upload_knowledge_file(
     Ok(knowledge)
 }
 
+/// 知识库是否存在
+pub(crate) fn exists(id: &str) -> bool {
+    KnowledgeState::get_knowledge_file_path(id).is_some_and(|path| path.exists())
+}
+
 #[tauri::command]
 pub fn get_knowledge_list(state: State<KnowledgeState>) -> Vec<Knowledge> {
     state.items.lock().unwrap().clone()
@@ -0,0 +1,145 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use super::bundle;
+use super::deno::{plugin_import, Plugin, PluginError, Result};
+use super::directory;
+use super::host;
+use super::install::MAX_SCRIPT_BYTES;
+use super::signature;
+
+// 可直接导入的脚本扩展名
+const SCRIPT_EXTENSIONS: [&str; 5] = ["ts", "js", "mjs", "mts", "py"];
+
+/// 单个文件的导入结果, 成功与失败互斥
+#[derive(Debug, Serialize)]
+pub struct FileImport {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<Plugin>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<PluginError>,
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default()
+}
+
+// 读取并检查脚本文件
+fn read_script(path: &Path) -> Result<String> {
+    if !SCRIPT_EXTENSIONS.contains(&extension(path).as_str()) {
+        return Err(PluginError::Plugin(format!(
+            "不支持的文件类型: {}",
+            path.display()
+        )));
+    }
+    if fs::metadata(path)?.len() as usize > MAX_SCRIPT_BYTES {
+        return Err(PluginError::Plugin(format!(
+            "插件文件超过 {}KB",
+            MAX_SCRIPT_BYTES / 1024
+        )));
+    }
+    let content = String::from_utf8(fs::read(path)?)
+        .map_err(|_| PluginError::Plugin("插件文件不是 UTF-8 文本".to_string()))?;
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(PluginError::Plugin("插件文件为空".to_string()));
+    }
+    Ok(content.to_string())
+}
+
+// 按路径类型导入: 目录、插件包或单个脚本
+async fn import_path(path: &Path, allow_unsigned: bool) -> Result<Plugin> {
+    let display = path.to_string_lossy().to_string();
+    if path.is_dir() {
+        return directory::plugin_import_dir(display, None, Some(allow_unsigned)).await;
+    }
+    if extension(path) == "zip" {
+        return Ok(
+            bundle::plugin_import_bundle(display, None, Some(allow_unsigned))
+                .await?
+                .plugin,
+        );
+    }
+    let content = read_script(path)?;
+    plugin_import(content, None, Some(allow_unsigned), None).await
+}
+
+async fn import_paths(paths: Vec<PathBuf>, allow_unsigned: bool) -> Vec<FileImport> {
+    let mut results = Vec::new();
+    for path in paths {
+        let result = import_path(&path, allow_unsigned).await;
+        let (plugin, error) = match result {
+            Ok(plugin) => (Some(plugin), None),
+            Err(err) => (None, Some(err)),
+        };
+        results.push(FileImport {
+            path: path.to_string_lossy().to_string(),
+            plugin,
+            error,
+        });
+    }
+    results
+}
+
+/// 从本地文件导入插件, 支持多选, 单个文件失败不影响其余文件
+#[tauri::command]
+pub async fn plugin_import_file(
+    paths: Vec<String>,
+    allow_unsigned: Option<bool>,
+) -> Result<Vec<FileImport>> {
+    if paths.is_empty() {
+        return Err(PluginError::Plugin("未选择文件".to_string()));
+    }
+    let paths = paths.into_iter().map(PathBuf::from).collect();
+    Ok(import_paths(paths, allow_unsigned.unwrap_or(false)).await)
+}
+
+/// 处理拖放到窗口上的文件, 逐个导入并通知前端, 未签名的插件弹窗确认
+/// 只处理脚本与插件包, 其余文件留给页面自身的拖放逻辑
+pub fn handle_drop(app: AppHandle, paths: Vec<PathBuf>) {
+    let paths: Vec<PathBuf> = paths
+        .into_iter()
+        .filter(|path| {
+            let ext = extension(path);
+            path.is_file() && (ext == "zip" || SCRIPT_EXTENSIONS.contains(&ext.as_str()))
+        })
+        .collect();
+    if paths.is_empty() {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        for path in paths {
+            let display = path.to_string_lossy().to_string();
+            let result = match import_path(&path, false).await {
+                // 未通过签名校验时逐个询问, 用户确认后再导入
+                Err(PluginError::Unsigned { status }) => {
+                    let message = format!(
+                        "{}\n\n{}。是否仍然安装?",
+                        display,
+                        signature::rejection(&status)
+                    );
+                    if host::dialog(Some("安装确认".to_string()), message, true).await {
+                        import_path(&path, true).await
+                    } else {
+                        continue;
+                    }
+                }
+                result => result,
+            };
+            match result {
+                Ok(plugin) => {
+                    let _ = app.emit("plugins://installed", plugin);
+                }
+                Err(err) => {
+                    let _ = app.emit("plugins://install-failed", format!("{}: {}", display, err));
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use super::deno::{plugins_dir, Result};
+use super::redact;
+
+// 每个插件保留的最大日志条数
+const MAX_LOG_LINES: usize = 500;
+// 默认返回的日志条数
+const DEFAULT_LOG_LIMIT: usize = 100;
+
+// 插件日志条目
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PluginLog {
+    pub time: i64,
+    pub tool: Option<String>,
+    pub message: String,
+}
+
+fn log_file(id: &str) -> PathBuf {
+    let dir = plugins_dir().join("logs");
+    let _ = fs::create_dir_all(&dir);
+    dir.join(format!("{}.log", id))
+}
+
+/// 追加插件的控制台输出, 超出上限时只保留最近的记录
+pub(crate) fn append_logs(id: &str, tool: Option<&str>, output: &str) -> Result<()> {
+    let time = chrono::Utc::now().timestamp_millis();
+    // 插件输出中可能包含密钥, 写入前替换
+    let output = redact::scrub(output);
+    let entries: Vec<PluginLog> = output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| PluginLog {
+            time,
+            tool: tool.map(|t| t.to_string()),
+            message: line.to_string(),
+        })
+        .collect();
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let path = log_file(id);
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    for entry in &entries {
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    }
+    drop(file);
+
+    // 裁剪旧日志
+    let content = fs::read_to_string(&path)?;
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.len() > MAX_LOG_LINES {
+        let mut kept = lines[lines.len() - MAX_LOG_LINES..].join("\n");
+        kept.push('\n');
+        fs::write(&path, kept)?;
+    }
+    Ok(())
+}
+
+/// 读取插件最近的日志
+pub(crate) fn read_logs(id: &str, limit: usize) -> Result<Vec<PluginLog>> {
+    let path = log_file(id);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    let entries: Vec<PluginLog> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let start = entries.len().saturating_sub(limit);
+    Ok(entries[start..].to_vec())
+}
+
+/// 删除插件的日志文件
+pub(crate) fn remove_logs(id: &str) -> Result<()> {
+    let path = log_file(id);
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn plugin_logs(id: String, limit: Option<usize>) -> Result<Vec<PluginLog>> {
+    read_logs(&id, limit.unwrap_or(DEFAULT_LOG_LIMIT))
+}
@@ -0,0 +1,622 @@
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin};
+use tokio::sync::oneshot;
+
+use super::db::with_db;
+use super::deno::{
+    find_plugin, load_plugin_list, plugin_remove, register_plugin, EnvVar, Plugin, PluginError,
+    PluginRuntime, Result, Tool,
+};
+use super::env;
+use super::install::PluginSource;
+use super::proxy;
+use crate::utils::gen::generate_id;
+
+// 客户端支持的协议版本
+const PROTOCOL_VERSION: &str = "2024-11-05";
+// 单个请求的超时时间
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// MCP 服务的连接方式
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum McpTransport {
+    /// 启动本地进程, 通过标准输入输出通信
+    Stdio {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        env: BTreeMap<String, String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cwd: Option<String>,
+    },
+    /// 连接远程服务, 通过 SSE 接收消息, 通过 POST 发送消息
+    Sse {
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+    },
+}
+
+impl McpTransport {
+    fn kind(&self) -> &'static str {
+        match self {
+            McpTransport::Stdio { .. } => "stdio",
+            McpTransport::Sse { .. } => "sse",
+        }
+    }
+
+    // 配置中以 ${KEY} 引用的环境变量
+    fn referenced_env(&self) -> Vec<String> {
+        let texts: Vec<&String> = match self {
+            McpTransport::Stdio { args, env, .. } => args.iter().chain(env.values()).collect(),
+            McpTransport::Sse { url, headers } => {
+                std::iter::once(url).chain(headers.values()).collect()
+            }
+        };
+        let mut keys = Vec::new();
+        for text in texts {
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("${") {
+                let Some(end) = rest[start..].find('}') else {
+                    break;
+                };
+                let key = rest[start + 2..start + end].to_string();
+                if !key.is_empty() && !keys.contains(&key) {
+                    keys.push(key);
+                }
+                rest = &rest[start + end + 1..];
+            }
+        }
+        keys
+    }
+}
+
+/// 已添加的 MCP 服务
+#[derive(Debug, Serialize)]
+pub struct McpServer {
+    /// 同时也是对应插件的 id
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub transport: McpTransport,
+    pub tools: usize,
+    pub connected: bool,
+}
+
+/// MCP 服务提供的工具
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct McpTool {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default, rename = "inputSchema")]
+    pub input_schema: Option<Value>,
+    #[serde(default)]
+    pub annotations: Option<Value>,
+}
+
+/// MCP 服务提供的资源
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct McpResource {
+    pub uri: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default, rename = "mimeType")]
+    pub mime_type: Option<String>,
+}
+
+/// 服务的工具与资源列表
+#[derive(Debug, Serialize)]
+pub struct McpCatalog {
+    pub tools: Vec<McpTool>,
+    pub resources: Vec<McpResource>,
+}
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<Value>>>>>;
+
+// 发送消息的通道
+enum Sender {
+    Stdio {
+        stdin: tokio::sync::Mutex<ChildStdin>,
+        // 客户端释放时终止进程
+        _child: Child,
+    },
+    Sse {
+        http: reqwest::Client,
+        endpoint: String,
+        headers: reqwest::header::HeaderMap,
+    },
+}
+
+impl Sender {
+    async fn send(&self, message: &Value) -> Result<()> {
+        match self {
+            Sender::Stdio { stdin, .. } => {
+                let mut stdin = stdin.lock().await;
+                stdin.write_all(format!("{}\n", message).as_bytes()).await?;
+                stdin.flush().await?;
+            }
+            Sender::Sse {
+                http,
+                endpoint,
+                headers,
+            } => {
+                http.post(endpoint)
+                    .headers(headers.clone())
+                    .json(message)
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .map_err(mcp_error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// 一个已初始化的 MCP 连接
+struct McpClient {
+    sender: Sender,
+    pending: Pending,
+    next_id: AtomicU64,
+    closed: Arc<AtomicBool>,
+}
+
+// 已建立的连接, 按插件 id 复用
+static CLIENTS: Lazy<tokio::sync::Mutex<HashMap<String, Arc<McpClient>>>> =
+    Lazy::new(|| tokio::sync::Mutex::new(HashMap::new()));
+
+fn mcp_error(err: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("MCP 服务错误: {}", err))
+}
+
+// 以插件的环境变量替换 ${KEY}
+fn expand(text: &str, vars: &[EnvVar]) -> String {
+    let mut text = text.to_string();
+    for var in vars {
+        text = text.replace(&format!("${{{}}}", var.key), &var.value);
+    }
+    text
+}
+
+// 处理服务发来的一条消息: 响应交给等待的请求, 服务端请求只回应 ping
+fn dispatch(message: Value, pending: &Pending) -> Option<Value> {
+    let id = message.get("id").cloned();
+    if message.get("method").is_some() {
+        let id = id?;
+        let reply = if message["method"] == "ping" {
+            json!({ "jsonrpc": "2.0", "id": id, "result": {} })
+        } else {
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": "Method not found" }
+            })
+        };
+        return Some(reply);
+    }
+    let sender = pending.lock().unwrap().remove(&id?.as_u64()?)?;
+    let result = match message.get("error") {
+        Some(error) => Err(mcp_error(error["message"].as_str().unwrap_or("未知错误"))),
+        None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+    };
+    let _ = sender.send(result);
+    None
+}
+
+// 连接断开时结束全部等待中的请求
+fn close(pending: &Pending, closed: &AtomicBool) {
+    closed.store(true, Ordering::SeqCst);
+    for (_, sender) in pending.lock().unwrap().drain() {
+        let _ = sender.send(Err(mcp_error("连接已断开")));
+    }
+}
+
+// 解析 SSE 事件块, 返回事件名与数据
+fn parse_event(block: &str) -> (String, String) {
+    let mut event = "message".to_string();
+    let mut data = Vec::new();
+    for line in block.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            event = value.trim().to_string();
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push(value.strip_prefix(' ').unwrap_or(value));
+        }
+    }
+    (event, data.join("\n"))
+}
+
+impl McpClient {
+    async fn connect(id: &str, transport: &McpTransport) -> Result<Arc<McpClient>> {
+        let vars = env::for_plugin(id).await?;
+        let proxy_vars = proxy::env_vars_for(id).await;
+        let pending: Pending = Arc::new(Mutex::new(HashMap::new()));
+        let closed = Arc::new(AtomicBool::new(false));
+        // 服务端请求的回应通过该通道写回
+        let (reply_tx, mut reply_rx) = tokio::sync::mpsc::unbounded_channel::<Value>();
+
+        let sender = match transport {
+            McpTransport::Stdio {
+                command,
+                args,
+                env,
+                cwd,
+            } => {
+                let mut cmd = tokio::process::Command::new(command);
+                cmd.args(args.iter().map(|arg| expand(arg, &vars)))
+                    .envs(proxy_vars)
+                    .envs(vars.iter().map(|var| (&var.key, &var.value)))
+                    .envs(env.iter().map(|(k, v)| (k, expand(v, &vars))))
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .kill_on_drop(true);
+                if let Some(cwd) = cwd {
+                    cmd.current_dir(cwd);
+                }
+                let mut child = cmd
+                    .spawn()
+                    .map_err(|e| mcp_error(format!("无法启动 {}: {}", command, e)))?;
+                let stdin = child
+                    .stdin
+                    .take()
+                    .ok_or_else(|| mcp_error("无法打开标准输入"))?;
+                let stdout = child
+                    .stdout
+                    .take()
+                    .ok_or_else(|| mcp_error("无法打开标准输出"))?;
+                let stderr = child
+                    .stderr
+                    .take()
+                    .ok_or_else(|| mcp_error("无法打开错误输出"))?;
+
+                let (pending_r, closed_r, log_id) =
+                    (pending.clone(), closed.clone(), id.to_string());
+                tokio::spawn(async move {
+                    let mut lines = BufReader::new(stdout).lines();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                            continue;
+                        };
+                        if let Some(reply) = dispatch(message, &pending_r) {
+                            let _ = reply_tx.send(reply);
+                        }
+                    }
+                    tracing::info!(plugin = %log_id, "MCP 服务已退出");
+                    close(&pending_r, &closed_r);
+                });
+                let log_id = id.to_string();
+                tokio::spawn(async move {
+                    let mut lines = BufReader::new(stderr).lines();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let _ = super::logs::append_logs(&log_id, None, &line);
+                    }
+                });
+                Sender::Stdio {
+                    stdin: tokio::sync::Mutex::new(stdin),
+                    _child: child,
+                }
+            }
+            McpTransport::Sse { url, headers } => {
+                let url = expand(url, &vars);
+                let mut header_map = reqwest::header::HeaderMap::new();
+                for (key, value) in headers {
+                    let name = reqwest::header::HeaderName::from_bytes(key.as_bytes())
+                        .map_err(mcp_error)?;
+                    let value = reqwest::header::HeaderValue::from_str(&expand(value, &vars))
+                        .map_err(mcp_error)?;
+                    header_map.insert(name, value);
+                }
+                let http = proxy::client();
+                let response = http
+                    .get(&url)
+                    .headers(header_map.clone())
+                    .header(reqwest::header::ACCEPT, "text/event-stream")
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .map_err(mcp_error)?;
+
+                // 第一个 endpoint 事件给出发送消息的地址
+                let (endpoint_tx, endpoint_rx) = oneshot::channel::<String>();
+                let (pending_r, closed_r) = (pending.clone(), closed.clone());
+                tokio::spawn(async move {
+                    let mut endpoint_tx = Some(endpoint_tx);
+                    let mut stream = response.bytes_stream();
+                    let mut buffer = String::new();
+                    while let Some(Ok(chunk)) = stream.next().await {
+                        buffer.push_str(&String::from_utf8_lossy(&chunk).replace("\r\n", "\n"));
+                        while let Some(index) = buffer.find("\n\n") {
+                            let block = buffer[..index].to_string();
+                            buffer.drain(..index + 2);
+                            let (event, data) = parse_event(&block);
+                            if event == "endpoint" {
+                                if let Some(tx) = endpoint_tx.take() {
+                                    let _ = tx.send(data);
+                                }
+                                continue;
+                            }
+                            let Ok(message) = serde_json::from_str::<Value>(&data) else {
+                                continue;
+                            };
+                            if let Some(reply) = dispatch(message, &pending_r) {
+                                let _ = reply_tx.send(reply);
+                            }
+                        }
+                    }
+                    close(&pending_r, &closed_r);
+                });
+                let endpoint = tokio::time::timeout(REQUEST_TIMEOUT, endpoint_rx)
+                    .await
+                    .map_err(|_| mcp_error("等待 endpoint 事件超时"))?
+                    .map_err(|_| mcp_error("连接已断开"))?;
+                let endpoint = url::Url::parse(&url)
+                    .and_then(|base| base.join(&endpoint))
+                    .map_err(mcp_error)?;
+                Sender::Sse {
+                    http,
+                    endpoint: endpoint.to_string(),
+                    headers: header_map,
+                }
+            }
+        };
+
+        let client = Arc::new(McpClient {
+            sender,
+            pending,
+            next_id: AtomicU64::new(1),
+            closed,
+        });
+        let writer = Arc::downgrade(&client);
+        tokio::spawn(async move {
+            while let Some(reply) = reply_rx.recv().await {
+                let Some(client) = writer.upgrade() else {
+                    break;
+                };
+                let _ = client.sender.send(&reply).await;
+            }
+        });
+
+        client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": { "name": "ghostie", "version": env!("CARGO_PKG_VERSION") },
+                }),
+            )
+            .await?;
+        client
+            .sender
+            .send(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+            .await?;
+        Ok(client)
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(mcp_error("连接已断开"));
+        }
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(err) = self.sender.send(&message).await {
+            self.pending.lock().unwrap().remove(&id);
+            return Err(err);
+        }
+        match tokio::time::timeout(REQUEST_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(mcp_error("连接已断开")),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&id);
+                Err(mcp_error(format!("{} 请求超时", method)))
+            }
+        }
+    }
+}
+
+fn read_transport(id: &str) -> Result<Option<McpTransport>> {
+    let config: Option<String> = with_db(|conn| {
+        Ok(conn
+            .query_row(
+                "SELECT config FROM mcp_servers WHERE plugin_id = ?1",
+                [id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    })?;
+    config
+        .map(|config| serde_json::from_str(&config).map_err(PluginError::from))
+        .transpose()
+}
+
+// 复用已有连接, 连接已断开时重新连接
+async fn client(id: &str) -> Result<Arc<McpClient>> {
+    let mut clients = CLIENTS.lock().await;
+    if let Some(client) = clients.get(id) {
+        if !client.closed.load(Ordering::SeqCst) {
+            return Ok(client.clone());
+        }
+    }
+    let transport = read_transport(id)?
+        .ok_or_else(|| PluginError::Plugin(format!("MCP 服务不存在: {}", id)))?;
+    let client = McpClient::connect(id, &transport).await?;
+    clients.insert(id.to_string(), client.clone());
+    Ok(client)
+}
+
+/// 断开连接, 本地进程随之终止
+pub(crate) async fn disconnect(id: &str) {
+    CLIENTS.lock().await.remove(id);
+}
+
+// 按 MCP 的工具注解判断是否为危险工具, 未声明时按协议默认值视为可能有破坏性
+fn is_dangerous(tool: &McpTool) -> bool {
+    let hint = |key: &str| {
+        tool.annotations
+            .as_ref()
+            .and_then(|a| a.get(key))
+            .and_then(Value::as_bool)
+    };
+    !hint("readOnlyHint").unwrap_or(false) && hint("destructiveHint").unwrap_or(true)
+}
+
+async fn fetch_catalog(id: &str) -> Result<McpCatalog> {
+    let client = client(id).await?;
+    let tools = client.request("tools/list", json!({})).await?;
+    let tools: Vec<McpTool> = serde_json::from_value(tools["tools"].clone())?;
+    // 资源是可选能力, 不支持时返回空列表
+    let resources = match client.request("resources/list", json!({})).await {
+        Ok(value) => serde_json::from_value(value["resources"].clone()).unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    Ok(McpCatalog { tools, resources })
+}
+
+// 将服务的工具同步到插件记录中
+async fn sync_tools(id: &str) -> Result<McpCatalog> {
+    let catalog = fetch_catalog(id).await?;
+    let mut plugin = find_plugin(id).await?;
+    plugin.tools = catalog
+        .tools
+        .iter()
+        .map(|tool| Tool {
+            name: tool.name.clone(),
+            description: tool.description.clone().unwrap_or_default(),
+            parameters: tool.input_schema.clone(),
+            dangerous: is_dangerous(tool),
+            ..Default::default()
+        })
+        .collect();
+    register_plugin(plugin).await?;
+    Ok(catalog)
+}
+
+/// 调用 MCP 服务的工具, 结果只有一段文本时返回文本, 否则返回内容列表
+pub(crate) async fn execute(id: &str, tool: &str, args: &Value) -> Result<Value> {
+    let result = client(id)
+        .await?
+        .request("tools/call", json!({ "name": tool, "arguments": args }))
+        .await?;
+    let content = result["content"].as_array().cloned().unwrap_or_default();
+    let text: Vec<&str> = content
+        .iter()
+        .filter(|item| item["type"] == "text")
+        .filter_map(|item| item["text"].as_str())
+        .collect();
+    if result["isError"].as_bool().unwrap_or(false) {
+        return Err(PluginError::Plugin(text.join("\n")));
+    }
+    if let Some(structured) = result.get("structuredContent") {
+        return Ok(structured.clone());
+    }
+    Ok(match (text.as_slice(), content.len()) {
+        ([text], 1) => Value::String(text.to_string()),
+        _ => Value::Array(content),
+    })
+}
+
+/// 添加 MCP 服务, 连接成功后作为插件注册, 工具通过 plugin_execute 调用
+///
+/// 配置中可以用 ${KEY} 引用环境变量, 变量值保存在系统密钥链中
+#[tauri::command]
+pub async fn mcp_server_add(name: String, transport: McpTransport) -> Result<Plugin> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(PluginError::Plugin("MCP 服务名称不能为空".to_string()));
+    }
+    let plugin = Plugin {
+        id: generate_id(),
+        name,
+        runtime: PluginRuntime::Mcp,
+        env: transport.referenced_env(),
+        source: Some(PluginSource::Mcp {
+            transport: transport.kind().to_string(),
+        }),
+        ..Default::default()
+    };
+    let id = plugin.id.clone();
+    register_plugin(plugin).await?;
+    let saved = with_db(|conn| {
+        conn.execute(
+            "INSERT INTO mcp_servers (plugin_id, config, created_at) VALUES (?1, ?2, ?3)",
+            params![
+                id,
+                serde_json::to_string(&transport)?,
+                chrono::Utc::now().timestamp_millis()
+            ],
+        )?;
+        Ok(())
+    });
+    // 连接失败时不保留插件记录
+    if let Err(err) = match saved {
+        Ok(()) => sync_tools(&id).await.map(|_| ()),
+        Err(err) => Err(err),
+    } {
+        let _ = plugin_remove(id).await;
+        return Err(err);
+    }
+    tracing::info!(plugin = %id, transport = transport.kind(), "已添加 MCP 服务");
+    find_plugin(&id).await
+}
+
+/// 删除 MCP 服务及对应的插件
+#[tauri::command]
+pub async fn mcp_server_remove(id: String) -> Result<()> {
+    if read_transport(&id)?.is_none() {
+        return Err(PluginError::Plugin(format!("MCP 服务不存在: {}", id)));
+    }
+    plugin_remove(id).await
+}
+
+#[tauri::command]
+pub async fn mcp_server_list() -> Result<Vec<McpServer>> {
+    let rows: Vec<(String, String)> = with_db(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT plugin_id, config FROM mcp_servers ORDER BY created_at")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })?;
+    let plugins = load_plugin_list().await?;
+    let clients = CLIENTS.lock().await;
+    let mut servers = Vec::new();
+    for (id, config) in rows {
+        let Some(plugin) = plugins.get(&id) else {
+            continue;
+        };
+        servers.push(McpServer {
+            name: plugin.name.clone(),
+            enabled: plugin.enabled,
+            transport: serde_json::from_str(&config)?,
+            tools: plugin.tools.len(),
+            connected: clients
+                .get(&id)
+                .is_some_and(|c| !c.closed.load(Ordering::SeqCst)),
+            id,
+        });
+    }
+    Ok(servers)
+}
+
+/// 获取服务当前的工具与资源, 同时更新插件记录中的工具列表
+#[tauri::command]
+pub async fn mcp_tools(server: String) -> Result<McpCatalog> {
+    sync_tools(&server).await
+}
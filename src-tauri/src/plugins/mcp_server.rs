@@ -0,0 +1,418 @@
+use axum::body::Bytes;
+use axum::extract::Query;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+use super::approval;
+use super::catalog::{exposed_tools, ExposedTool};
+use super::deno::{execute_tool, load_plugin_list, Plugin, PluginError, Result};
+use super::host;
+use super::server::generate_token;
+use crate::utils::gen::generate_id;
+use crate::utils::settings::{self, McpClientAccess};
+
+// 支持的协议版本, 客户端请求其他版本时使用第一个
+const PROTOCOL_VERSIONS: [&str; 2] = ["2025-03-26", "2024-11-05"];
+
+// SSE 连接, 响应通过事件流发回
+struct Session {
+    client_id: String,
+    sender: mpsc::UnboundedSender<Value>,
+}
+
+static SESSIONS: Lazy<Mutex<HashMap<String, Session>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn rpc_result(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn rpc_error(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message.into() } })
+}
+
+// 客户端允许调用的工具, 不包含已禁用的插件
+async fn allowed_tools(
+    client: &McpClientAccess,
+) -> Result<(HashMap<String, Plugin>, Vec<ExposedTool>)> {
+    let plugins = load_plugin_list().await?;
+    let tools = exposed_tools(&plugins)
+        .into_iter()
+        .filter(|t| client.plugins.contains(&t.plugin_id) && plugins[&t.plugin_id].enabled)
+        .collect();
+    Ok((plugins, tools))
+}
+
+async fn list_tools(client: &McpClientAccess) -> Result<Value> {
+    let (plugins, exposed) = allowed_tools(client).await?;
+    let tools: Vec<Value> = exposed
+        .into_iter()
+        .filter_map(|exposed| {
+            let plugin = plugins.get(&exposed.plugin_id)?;
+            let tool = plugin.tools.iter().find(|t| t.name == exposed.tool)?;
+            Some(json!({
+                "name": exposed.name,
+                "description": format!("[{}] {}", plugin.name, tool.description),
+                "inputSchema": tool
+                    .parameters
+                    .clone()
+                    .unwrap_or_else(|| json!({ "type": "object" })),
+                "annotations": { "destructiveHint": tool.dangerous },
+            }))
+        })
+        .collect();
+    Ok(json!({ "tools": tools }))
+}
+
+fn tool_result(text: String, is_error: bool) -> Value {
+    json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+}
+
+// 执行工具, 插件错误作为工具结果返回, 以便调用方的模型看到原因
+async fn call_tool(client: &McpClientAccess, params: &Value) -> Result<Value> {
+    let name = params["name"].as_str().unwrap_or_default();
+    let args = params
+        .get("arguments")
+        .cloned()
+        .unwrap_or_else(|| json!({}));
+    let (plugins, exposed) = allowed_tools(client).await?;
+    let exposed = exposed
+        .into_iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| PluginError::Plugin(format!("未知工具: {}", name)))?;
+
+    // 需要确认的工具在本机弹窗确认, 外部应用无法代为确认
+    if approval::needs_approval(&exposed.plugin_id, &exposed.tool).await? {
+        let plugin_name = plugins
+            .get(&exposed.plugin_id)
+            .map_or("", |p| p.name.as_str());
+        let message = format!(
+            "「{}」请求执行插件「{}」的工具 {}, 该工具可能执行命令或删除文件。\n\n是否允许?",
+            client.name, plugin_name, exposed.tool
+        );
+        if !host::dialog(Some("外部应用请求执行工具".to_string()), message, true).await {
+            return Ok(tool_result("用户拒绝了执行".to_string(), true));
+        }
+    }
+
+    tracing::info!(
+        client = %client.name,
+        plugin = %exposed.plugin_id,
+        tool = %exposed.tool,
+        "MCP 客户端调用工具"
+    );
+    Ok(
+        match execute_tool(&exposed.plugin_id, &exposed.tool, &args, &[]).await {
+            Ok(result) => {
+                let text = match result.result {
+                    Value::String(text) => text,
+                    value => value.to_string(),
+                };
+                tool_result(text, false)
+            }
+            Err(err) => tool_result(err.to_string(), true),
+        },
+    )
+}
+
+/// 处理一条 JSON-RPC 消息, 通知与响应没有回复
+async fn handle(client: &McpClientAccess, message: Value) -> Option<Value> {
+    let method = message.get("method")?.as_str()?.to_string();
+    let Some(id) = message.get("id").cloned() else {
+        // 通知, 如 notifications/initialized
+        return None;
+    };
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    let result = match method.as_str() {
+        "initialize" => {
+            let requested = params["protocolVersion"].as_str().unwrap_or_default();
+            let version = PROTOCOL_VERSIONS
+                .into_iter()
+                .find(|v| *v == requested)
+                .unwrap_or(PROTOCOL_VERSIONS[0]);
+            Ok(json!({
+                "protocolVersion": version,
+                "capabilities": { "tools": { "listChanged": false } },
+                "serverInfo": { "name": "ghostie", "version": env!("CARGO_PKG_VERSION") },
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "tools/list" => list_tools(client).await,
+        "tools/call" => call_tool(client, &params).await,
+        _ => return Some(rpc_error(id, -32601, format!("不支持的方法: {}", method))),
+    };
+    Some(match result {
+        Ok(result) => rpc_result(id, result),
+        Err(err) => rpc_error(id, -32603, err.to_string()),
+    })
+}
+
+// 按 Bearer 令牌查找客户端
+fn authorize(headers: &HeaderMap) -> Option<McpClientAccess> {
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))?
+        .trim();
+    settings::get()
+        .server
+        .mcp_clients
+        .into_iter()
+        .find(|client| client.token == provided)
+}
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(json!({ "message": "令牌无效" })),
+    )
+        .into_response()
+}
+
+fn parse_message(body: &[u8]) -> std::result::Result<Value, Response> {
+    serde_json::from_slice(body)
+        .map_err(|e| Json(rpc_error(Value::Null, -32700, e.to_string())).into_response())
+}
+
+// POST /mcp, 请求体为一条 JSON-RPC 消息, 响应直接返回
+async fn message_handler(headers: HeaderMap, body: Bytes) -> Response {
+    let Some(client) = authorize(&headers) else {
+        return unauthorized();
+    };
+    let message = match parse_message(&body) {
+        Ok(message) => message,
+        Err(response) => return response,
+    };
+    match handle(&client, message).await {
+        Some(reply) => Json(reply).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
+
+// 事件流结束时移除连接
+struct SessionGuard {
+    id: String,
+    receiver: mpsc::UnboundedReceiver<Value>,
+}
+
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        SESSIONS.lock().unwrap().remove(&self.id);
+    }
+}
+
+// GET /mcp/sse, 建立事件流, 首个 endpoint 事件给出发送消息的地址
+async fn sse_handler(headers: HeaderMap) -> Response {
+    let Some(client) = authorize(&headers) else {
+        return unauthorized();
+    };
+    let id = generate_id();
+    let (sender, receiver) = mpsc::unbounded_channel();
+    SESSIONS.lock().unwrap().insert(
+        id.clone(),
+        Session {
+            client_id: client.id,
+            sender,
+        },
+    );
+    let endpoint = Event::default()
+        .event("endpoint")
+        .data(format!("/mcp/messages?session_id={}", id));
+    let guard = SessionGuard { id, receiver };
+    let messages = futures::stream::unfold(guard, |mut guard| async move {
+        let message = guard.receiver.recv().await?;
+        let event = Event::default().event("message").data(message.to_string());
+        Some((Ok::<_, Infallible>(event), guard))
+    });
+    let stream = futures::StreamExt::chain(
+        futures::stream::once(async { Ok::<_, Infallible>(endpoint) }),
+        messages,
+    );
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+#[derive(Deserialize)]
+struct SessionQuery {
+    session_id: String,
+}
+
+// POST /mcp/messages?session_id=..., 响应通过对应的事件流发回
+async fn session_handler(
+    headers: HeaderMap,
+    Query(query): Query<SessionQuery>,
+    body: Bytes,
+) -> Response {
+    let Some(client) = authorize(&headers) else {
+        return unauthorized();
+    };
+    let sender = SESSIONS
+        .lock()
+        .unwrap()
+        .get(&query.session_id)
+        .filter(|session| session.client_id == client.id)
+        .map(|session| session.sender.clone());
+    let Some(sender) = sender else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "message": "连接不存在" })),
+        )
+            .into_response();
+    };
+    let message = match parse_message(&body) {
+        Ok(message) => message,
+        Err(response) => return response,
+    };
+    tokio::spawn(async move {
+        if let Some(reply) = handle(&client, message).await {
+            let _ = sender.send(reply);
+        }
+    });
+    StatusCode::ACCEPTED.into_response()
+}
+
+/// MCP 服务的路由, 挂在本地 HTTP 服务上
+pub(crate) fn routes() -> Router {
+    Router::new()
+        .route("/mcp", post(message_handler))
+        .route("/mcp/sse", get(sse_handler))
+        .route("/mcp/messages", post(session_handler))
+}
+
+/// `ghostie mcp` 子命令: 在标准输入输出上提供 MCP 服务
+///
+/// 消息转发到正在运行的应用的本地 HTTP 服务, 因此需要先启用本地服务
+pub async fn serve_stdio(token: String) {
+    let url = format!("http://127.0.0.1:{}/mcp", settings::get().server.port);
+    let http = reqwest::Client::new();
+    let stdout = Arc::new(tokio::sync::Mutex::new(tokio::io::stdout()));
+    let mut tasks = tokio::task::JoinSet::new();
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (http, url, token, stdout) = (http.clone(), url.clone(), token.clone(), stdout.clone());
+        tasks.spawn(async move {
+            let Some(reply) = forward(&http, &url, &token, &line).await else {
+                return;
+            };
+            let mut stdout = stdout.lock().await;
+            let _ = stdout.write_all(format!("{}\n", reply).as_bytes()).await;
+            let _ = stdout.flush().await;
+        });
+    }
+    // 标准输入关闭后等待进行中的请求完成
+    while tasks.join_next().await.is_some() {}
+}
+
+async fn forward(http: &reqwest::Client, url: &str, token: &str, line: &str) -> Option<Value> {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(err) => return Some(rpc_error(Value::Null, -32700, err.to_string())),
+    };
+    let id = message.get("id").cloned();
+    let response = http
+        .post(url)
+        .bearer_auth(token)
+        .json(&message)
+        .send()
+        .await;
+    match response {
+        Ok(response) if response.status() == reqwest::StatusCode::ACCEPTED => None,
+        Ok(response) if response.status().is_success() => response.json().await.ok(),
+        Ok(response) => {
+            id.map(|id| rpc_error(id, -32000, format!("请求被拒绝: {}", response.status())))
+        }
+        Err(_) => id.map(|id| {
+            rpc_error(
+                id,
+                -32000,
+                "无法连接应用, 请确认应用正在运行并已启用本地服务",
+            )
+        }),
+    }
+}
+
+fn find_client(id: &str) -> Result<McpClientAccess> {
+    settings::get()
+        .server
+        .mcp_clients
+        .into_iter()
+        .find(|client| client.id == id)
+        .ok_or_else(|| PluginError::Plugin(format!("MCP 客户端不存在: {}", id)))
+}
+
+#[tauri::command]
+pub async fn mcp_clients_list() -> Result<Vec<McpClientAccess>> {
+    Ok(settings::get().server.mcp_clients)
+}
+
+/// 添加 MCP 客户端并生成令牌, plugins 为允许调用的插件 id
+#[tauri::command]
+pub async fn mcp_client_add(name: String, plugins: Vec<String>) -> Result<McpClientAccess> {
+    let client = McpClientAccess {
+        id: generate_id(),
+        name: name.trim().to_string(),
+        token: generate_token(),
+        plugins,
+    };
+    if client.name.is_empty() {
+        return Err(PluginError::Plugin("客户端名称不能为空".to_string()));
+    }
+    settings::update(|s| s.server.mcp_clients.push(client.clone()))?;
+    Ok(client)
+}
+
+/// 修改客户端允许调用的插件, 立即生效
+#[tauri::command]
+pub async fn mcp_client_set_plugins(id: String, plugins: Vec<String>) -> Result<McpClientAccess> {
+    find_client(&id)?;
+    settings::update(|s| {
+        if let Some(client) = s.server.mcp_clients.iter_mut().find(|c| c.id == id) {
+            client.plugins = plugins;
+        }
+    })?;
+    find_client(&id)
+}
+
+/// 删除客户端, 其令牌立即失效
+#[tauri::command]
+pub async fn mcp_client_remove(id: String) -> Result<()> {
+    find_client(&id)?;
+    settings::update(|s| s.server.mcp_clients.retain(|c| c.id != id))?;
+    SESSIONS
+        .lock()
+        .unwrap()
+        .retain(|_, session| session.client_id != id);
+    Ok(())
+}
+
+/// 生成客户端的配置片段, 可直接填入 Claude Desktop 等应用的 mcpServers
+#[tauri::command]
+pub async fn mcp_client_config(id: String) -> Result<Value> {
+    let client = find_client(&id)?;
+    let exe = std::env::current_exe()?;
+    let port = settings::get().server.port;
+    Ok(json!({
+        "stdio": {
+            "command": exe.to_string_lossy(),
+            "args": ["mcp", "--token", client.token],
+        },
+        "sse": {
+            "url": format!("http://127.0.0.1:{}/mcp/sse", port),
+            "headers": { "Authorization": format!("Bearer {}", client.token) },
+        },
+    }))
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use super::deno::{find_plugin, register_plugin, Plugin, Result};
+use super::i18n;
+
+/// 用户修改的显示信息, 优先于脚本声明的元数据, 更新源码时保留
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct MetaOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 图标, emoji 或图片地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+impl MetaOverrides {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// 转换为展示用的插件信息: 按语言选择文本, 再应用用户的修改
+pub(crate) fn display(plugin: &mut Plugin, locale: Option<&str>) {
+    if let Some(locale) = locale {
+        i18n::localize(plugin, locale);
+    }
+    if let Some(ref name) = plugin.overrides.name {
+        plugin.name = name.clone();
+    }
+    if let Some(ref description) = plugin.overrides.description {
+        plugin.description = Some(description.clone());
+    }
+}
+
+// 空字符串表示清除对应的修改
+fn normalize(value: String) -> Option<String> {
+    Some(value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// 修改插件的显示名称、描述与图标, 未传入的字段保持不变, 传入空字符串时恢复脚本声明的值
+#[tauri::command]
+pub async fn plugin_set_meta(
+    id: String,
+    name: Option<String>,
+    description: Option<String>,
+    icon: Option<String>,
+) -> Result<Plugin> {
+    let mut plugin = find_plugin(&id).await?;
+    if let Some(name) = name {
+        plugin.overrides.name = normalize(name);
+    }
+    if let Some(description) = description {
+        plugin.overrides.description = normalize(description);
+    }
+    if let Some(icon) = icon {
+        plugin.overrides.icon = normalize(icon);
+    }
+    let mut plugin = register_plugin(plugin).await?;
+    display(&mut plugin, i18n::current_locale(None).as_deref());
+    Ok(plugin)
+}
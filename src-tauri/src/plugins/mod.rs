@@ -1,2 +1,78 @@
+pub mod agent;
+pub mod approval;
+pub mod artifacts;
+pub mod background;
+pub mod backup;
+pub mod batch;
+pub mod bots;
+pub mod bridge;
+pub mod builtin;
+pub mod bundle;
+pub mod cache;
+pub mod catalog;
+pub mod chat_export;
+pub mod chat_import;
+pub mod chats;
+pub mod clipboard;
+pub mod db;
 pub mod deno;
+pub mod directory;
+pub mod duplicate;
+pub mod embeddings;
+pub mod env;
+pub mod grants;
+pub mod harness;
+pub mod history;
+pub mod host;
+pub mod i18n;
+pub mod ingest;
+pub mod install;
+pub mod kb_sync;
 pub mod knowledge;
+pub mod local;
+pub mod logs;
+pub mod mcp;
+pub mod mcp_server;
+pub mod meta;
+pub mod node;
+pub mod notification;
+pub mod ocr;
+pub mod openapi;
+pub mod palette;
+pub mod profile;
+pub mod providers;
+pub mod proxy;
+pub mod python;
+pub mod quick_capture;
+pub mod rate_limit;
+pub mod redact;
+pub mod registry;
+pub mod reload;
+pub mod replay;
+pub mod retry;
+pub mod runtime;
+pub mod schedule;
+pub mod schema;
+pub mod screen;
+pub mod search;
+pub mod secrets;
+pub mod server;
+pub mod service;
+pub mod shell;
+pub mod signature;
+pub mod stats;
+pub mod storage;
+pub mod stt;
+pub mod sync;
+pub mod templates;
+pub mod trace;
+pub mod tray;
+pub mod trigger;
+pub mod tts;
+pub mod usage;
+pub mod validate;
+pub mod versions;
+pub mod wasm;
+pub mod web;
+pub mod web_search;
+pub mod workflow;
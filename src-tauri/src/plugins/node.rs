@@ -0,0 +1,184 @@
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::RwLock;
+
+use super::artifacts;
+use super::deno::{data_dir, plugins_dir, EnvVar, PluginError, Result, DATA_DIR_ENV};
+use super::env;
+use super::logs;
+use super::proxy;
+use super::runtime::{run_limited, RunOutput};
+use crate::utils::settings;
+
+// 与 Deno 引导脚本相同的约定, 插件默认导出 { name, description, tools }
+const BOOTSTRAP: &str = r#"
+import { pathToFileURL } from "node:url";
+
+console.log = console.info = console.debug = (...args) => console.error(...args);
+
+const plugin = await import(pathToFileURL(process.argv[2]).href);
+const mode = process.argv[3];
+let result;
+
+if (mode === "describe") {
+    const tools = Object.entries(plugin.default.tools || {}).map(([key, value]) => {
+        const res = { name: key || "undefined", description: value.description || "" };
+        if (value.parameters) {
+            res.parameters = value.parameters;
+        }
+        if (value.rateLimit) {
+            res.rateLimit = value.rateLimit;
+        }
+        if (value.retry) {
+            res.retry = value.retry;
+        }
+        if (value.skipValidation) {
+            res.skipValidation = true;
+        }
+        if (value.cache) {
+            res.cache = value.cache;
+        }
+        if (value.dangerous) {
+            res.dangerous = true;
+        }
+        return res;
+    });
+    result = {
+        name: plugin.default.name || "undefined",
+        description: plugin.default.description || "",
+        version: plugin.default.version || null,
+        changelog: plugin.default.changelog || null,
+        tags: plugin.default.tags || [],
+        category: plugin.default.category || null,
+        tests: plugin.default.tests || [],
+        env: plugin.default.env || [],
+        runtime: plugin.default.runtime || "node",
+        tools,
+    };
+} else {
+    const targetFunction = (plugin.default.tools || {})[process.argv[4]];
+    if (!targetFunction) {
+        throw new Error("未知函数: " + process.argv[4]);
+    }
+    const chunks = [];
+    for await (const chunk of process.stdin) {
+        chunks.push(chunk);
+    }
+    const args = JSON.parse(Buffer.concat(chunks).toString() || "{}");
+    result = await targetFunction.handler(args);
+}
+
+process.stdout.write(JSON.stringify(result ?? null));
+"#;
+
+// Node.js 版本
+#[derive(Clone, Copy)]
+struct NodeVersion {
+    major: u32,
+    minor: u32,
+}
+
+impl NodeVersion {
+    // 22.6 起支持直接运行 TypeScript
+    fn strips_types(&self) -> bool {
+        self.major > 22 || (self.major == 22 && self.minor >= 6)
+    }
+}
+
+static NODE: Lazy<RwLock<Option<NodeVersion>>> = Lazy::new(|| RwLock::new(detect()));
+
+// 读取版本号, 如 "v22.11.0"
+fn detect() -> Option<NodeVersion> {
+    let output = Command::new("node").arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let mut parts = version.trim_start_matches('v').split('.');
+    Some(NodeVersion {
+        major: parts.next()?.parse().ok()?,
+        minor: parts.next()?.parse().ok()?,
+    })
+}
+
+/// 重新检测 Node.js
+pub(crate) fn refresh() {
+    *NODE.write().unwrap() = detect();
+}
+
+/// Node.js 是否可用
+pub(crate) fn is_available() -> bool {
+    NODE.read().unwrap().is_some()
+}
+
+fn source_path(id: &str) -> PathBuf {
+    plugins_dir().join(format!("{}.ts", id))
+}
+
+async fn run(
+    id: &str,
+    plugin_file: &Path,
+    args: &[&str],
+    input: &str,
+    env_vars: &[EnvVar],
+) -> Result<RunOutput> {
+    let version = NODE
+        .read()
+        .unwrap()
+        .ok_or("未找到 Node.js, 请先安装 Node.js")?;
+
+    let bootstrap = plugins_dir().join(format!("temp_{}.mjs", crate::utils::gen::generate_id()));
+    fs::write(&bootstrap, BOOTSTRAP)?;
+
+    let mut cmd = tokio::process::Command::new("node");
+    if version.strips_types() {
+        cmd.args(["--experimental-strip-types", "--no-warnings"]);
+    }
+    if let Some(mb) = settings::get().runtime.limits.memory_mb {
+        cmd.arg(format!("--max-old-space-size={}", mb));
+    }
+    cmd.arg(&bootstrap).arg(plugin_file).args(args);
+    // 以插件数据目录作为工作目录
+    let dir = data_dir(id)?;
+    cmd.current_dir(&dir)
+        .env(DATA_DIR_ENV, &dir)
+        .envs(proxy::env_vars_for(id).await);
+    for var in env_vars {
+        cmd.env(&var.key, &var.value);
+    }
+
+    let output = run_limited(cmd, Some(input.to_string())).await;
+    let _ = fs::remove_file(&bootstrap);
+    output
+}
+
+/// 通过 Node.js 读取插件元数据
+pub(crate) async fn describe(id: &str) -> Result<Value> {
+    let env_vars = env::scoped(id)?;
+    let output = run(id, &source_path(id), &["describe"], "", &env_vars).await?;
+    let _ = logs::append_logs(id, None, &output.stderr);
+    Ok(serde_json::from_str(&output.into_stdout()?)?)
+}
+
+/// 通过 Node.js 执行工具
+pub(crate) async fn execute(
+    id: &str,
+    tool: &str,
+    args: &Value,
+    artifacts_dir: &Path,
+) -> Result<Value> {
+    let plugin_file = source_path(id);
+    if !plugin_file.exists() {
+        return Err(PluginError::Plugin(format!("插件文件不存在: {}", id)));
+    }
+
+    let mut env_vars = env::for_plugin(id).await?;
+    env_vars.push(artifacts::env_var(artifacts_dir));
+    let input = serde_json::to_string(args)?;
+    let output = run(id, &plugin_file, &["call", tool], &input, &env_vars).await?;
+    let _ = logs::append_logs(id, Some(tool), &output.stderr);
+    serde_json::from_str(&output.into_stdout()?).map_err(|e| PluginError::Json(e.to_string()))
+}
@@ -0,0 +1,261 @@
+use chrono::{DateTime, Utc};
+use once_cell::sync::OnceCell;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use super::approval;
+use super::db::with_db;
+use super::deno::{find_plugin, PluginError, Result};
+use super::logs;
+use crate::utils::gen::generate_id;
+
+// 用于发送通知按钮与提醒事件
+static APP: OnceCell<AppHandle> = OnceCell::new();
+
+/// 通知上的按钮, 目前只有 Linux 支持
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotificationAction {
+    pub id: String,
+    pub label: String,
+}
+
+/// 点击通知按钮时以 notification://action 事件发送
+#[derive(Debug, Serialize, Clone)]
+pub struct NotificationResponse {
+    pub notification: String,
+    pub action: String,
+}
+
+/// 到时提醒, 可附带到时执行的工具
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Reminder {
+    pub id: String,
+    /// 提醒时间 (毫秒时间戳)
+    pub at: i64,
+    pub message: String,
+    pub title: Option<String>,
+    pub plugin_id: Option<String>,
+    pub tool: Option<String>,
+    #[serde(default)]
+    pub args: Value,
+    pub created_at: i64,
+    pub fired_at: Option<i64>,
+    /// 执行工具失败时的错误
+    pub error: Option<String>,
+}
+
+/// 创建提醒时绑定的工具
+#[derive(Debug, Deserialize)]
+pub struct ReminderTool {
+    pub plugin_id: String,
+    pub tool: String,
+    pub args: Option<Value>,
+}
+
+fn notify_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("通知失败: {}", message))
+}
+
+/// 保存应用句柄, 应用启动时调用
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+// 显示系统通知, 有按钮时在后台等待用户点击
+fn show(id: &str, title: &str, body: &str, actions: &[NotificationAction]) -> Result<()> {
+    let mut notification = notify_rust::Notification::new();
+    notification.summary(title).body(body);
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        if !actions.is_empty() {
+            for action in actions {
+                notification.action(&action.id, &action.label);
+            }
+            let handle = notification.show().map_err(notify_error)?;
+            let id = id.to_string();
+            std::thread::spawn(move || {
+                handle.wait_for_action(|action| {
+                    if action == "__closed" {
+                        return;
+                    }
+                    if let Some(app) = APP.get() {
+                        let _ = app.emit(
+                            "notification://action",
+                            NotificationResponse {
+                                notification: id,
+                                action: action.to_string(),
+                            },
+                        );
+                    }
+                });
+            });
+            return Ok(());
+        }
+    }
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    let _ = (id, actions);
+    notification.show().map(|_| ()).map_err(notify_error)
+}
+
+fn read_reminder(data: String) -> rusqlite::Result<Reminder> {
+    serde_json::from_str(&data).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn save_reminder(reminder: &Reminder) -> Result<()> {
+    with_db(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO reminders (id, at, fired, data) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                reminder.id,
+                reminder.at,
+                reminder.fired_at.is_some(),
+                serde_json::to_string(reminder)?
+            ],
+        )?;
+        Ok(())
+    })
+}
+
+// 提醒并执行绑定的工具
+async fn fire(mut reminder: Reminder) {
+    let title = reminder.title.clone().unwrap_or_else(|| "提醒".to_string());
+    if let Err(err) = show(&reminder.id, &title, &reminder.message, &[]) {
+        tracing::warn!(reminder = %reminder.id, error = %err, "显示提醒失败");
+    }
+    if let (Some(plugin_id), Some(tool)) = (&reminder.plugin_id, &reminder.tool) {
+        // 无人值守, 需要确认的工具直接拒绝
+        let result =
+            approval::execute_confirmed(plugin_id, tool, reminder.args.clone(), "提醒", false)
+                .await;
+        if let Err(err) = result {
+            tracing::warn!(plugin = %plugin_id, error = %err, "提醒执行工具失败");
+            let _ = logs::append_logs(
+                plugin_id,
+                Some(tool),
+                &format!("提醒执行失败 ({}): {}", reminder.message, err),
+            );
+            reminder.error = Some(err.to_string());
+        }
+    }
+    if let Some(app) = APP.get() {
+        let _ = app.emit("reminder://fired", &reminder);
+    }
+    if let Err(err) = save_reminder(&reminder) {
+        tracing::warn!(reminder = %reminder.id, error = %err, "保存提醒状态失败");
+    }
+}
+
+/// 触发到期的提醒, 由定时任务循环调用
+///
+/// 应用关闭期间到期的提醒在启动后补发
+pub(crate) async fn fire_due() {
+    let now = Utc::now().timestamp_millis();
+    let due: Result<Vec<Reminder>> = with_db(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT data FROM reminders WHERE fired = 0 AND at <= ?1 ORDER BY at")?;
+        let rows = stmt
+            .query_map([now], |row| read_reminder(row.get(0)?))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    });
+    for mut reminder in due.unwrap_or_default() {
+        // 先标记为已触发, 避免工具执行期间重复触发
+        reminder.fired_at = Some(now);
+        if save_reminder(&reminder).is_ok() {
+            tokio::spawn(fire(reminder));
+        }
+    }
+}
+
+/// 显示系统通知
+///
+/// # 参数
+/// * `actions` - 通知上的按钮, 点击后发送 notification://action 事件, 目前只有 Linux 支持
+///
+/// # 返回值
+/// * 通知 id, 与事件中的 notification 对应
+#[tauri::command]
+pub async fn notify(
+    title: String,
+    body: String,
+    actions: Option<Vec<NotificationAction>>,
+) -> Result<String> {
+    let id = generate_id();
+    let notification = id.clone();
+    tokio::task::spawn_blocking(move || {
+        show(&notification, &title, &body, &actions.unwrap_or_default())
+    })
+    .await
+    .map_err(notify_error)??;
+    Ok(id)
+}
+
+/// 创建提醒, 到时显示通知并执行绑定的工具
+///
+/// # 参数
+/// * `at` - RFC 3339 格式的时间, 如 2024-01-01T09:00:00+08:00
+#[tauri::command]
+pub async fn reminder_create(
+    at: DateTime<Utc>,
+    message: String,
+    title: Option<String>,
+    tool: Option<ReminderTool>,
+) -> Result<Reminder> {
+    if message.trim().is_empty() {
+        return Err(PluginError::Plugin("提醒内容不能为空".to_string()));
+    }
+    if let Some(tool) = &tool {
+        let plugin = find_plugin(&tool.plugin_id).await?;
+        if !plugin.tools.iter().any(|t| t.name == tool.tool) {
+            return Err(PluginError::Plugin(format!("未知函数: {}", tool.tool)));
+        }
+    }
+    let (plugin_id, tool, args) = match tool {
+        Some(tool) => (
+            Some(tool.plugin_id),
+            Some(tool.tool),
+            tool.args.unwrap_or_else(|| serde_json::json!({})),
+        ),
+        None => (None, None, Value::Null),
+    };
+    let reminder = Reminder {
+        id: generate_id(),
+        at: at.timestamp_millis(),
+        message,
+        title,
+        plugin_id,
+        tool,
+        args,
+        created_at: Utc::now().timestamp_millis(),
+        fired_at: None,
+        error: None,
+    };
+    save_reminder(&reminder)?;
+    Ok(reminder)
+}
+
+/// 列出提醒, 默认只返回未触发的
+#[tauri::command]
+pub async fn reminder_list(include_fired: Option<bool>) -> Result<Vec<Reminder>> {
+    let include_fired = include_fired.unwrap_or(false);
+    with_db(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT data FROM reminders WHERE fired = 0 OR ?1 ORDER BY at")?;
+        let rows = stmt
+            .query_map([include_fired], |row| read_reminder(row.get(0)?))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })
+}
+
+#[tauri::command]
+pub async fn reminder_delete(id: String) -> Result<()> {
+    with_db(|conn| {
+        conn.execute("DELETE FROM reminders WHERE id = ?1", [&id])?;
+        Ok(())
+    })
+}
@@ -0,0 +1,245 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::deno::{PluginError, Result};
+use crate::utils::gen::generate_id;
+
+// 默认识别简体中文与英文
+const DEFAULT_LANG: &str = "chi_sim+eng";
+// 扫描版 PDF 渲染为图片的分辨率
+const PDF_DPI: &str = "200";
+
+/// 支持识别的图片扩展名
+pub(crate) const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "bmp", "tif", "tiff", "webp"];
+
+static TESSERACT: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    let candidates = [
+        "tesseract",
+        "/opt/homebrew/bin/tesseract",
+        "/usr/local/bin/tesseract",
+        r"C:\Program Files\Tesseract-OCR\tesseract.exe",
+    ];
+    candidates.into_iter().map(PathBuf::from).find(|p| {
+        Command::new(p)
+            .arg("--version")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    })
+});
+
+/// 待识别的图片, 文件路径或 base64 编码的内容
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum OcrImage {
+    Path { path: String },
+    Bytes { base64: String },
+}
+
+/// 识别区域在图片中的位置 (像素)
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct BoundingBox {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl BoundingBox {
+    fn union(self, other: BoundingBox) -> BoundingBox {
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        BoundingBox {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OcrWord {
+    pub text: String,
+    pub bbox: BoundingBox,
+    /// 置信度, 0 到 100
+    pub confidence: f32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OcrLine {
+    pub text: String,
+    pub bbox: BoundingBox,
+    pub words: Vec<OcrWord>,
+}
+
+/// 识别结果
+#[derive(Debug, Serialize, Clone)]
+pub struct OcrResult {
+    /// 全部文本, 段落之间以空行分隔
+    pub text: String,
+    pub lines: Vec<OcrLine>,
+}
+
+fn ocr_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("文字识别失败: {}", message))
+}
+
+fn tesseract() -> Result<&'static PathBuf> {
+    TESSERACT
+        .as_ref()
+        .ok_or_else(|| ocr_error("未找到 tesseract, 请先安装 Tesseract OCR"))
+}
+
+// 中日韩文字之间不加空格
+fn joins_without_space(lang: &str) -> bool {
+    ["chi", "jpn", "kor"].iter().any(|l| lang.contains(l))
+}
+
+// 解析 tesseract 的 TSV 输出, 按 block、段落与行合并单词
+fn parse_tsv(tsv: &str, lang: &str) -> OcrResult {
+    let separator = if joins_without_space(lang) { "" } else { " " };
+    let mut lines: Vec<((u32, u32, u32), OcrLine)> = Vec::new();
+    for row in tsv.lines().skip(1) {
+        let columns: Vec<&str> = row.split('\t').collect();
+        if columns.len() < 12 || columns[0] != "5" {
+            continue;
+        }
+        let text = columns[11].trim();
+        if text.is_empty() {
+            continue;
+        }
+        let number = |i: usize| columns[i].parse::<u32>().unwrap_or(0);
+        let key = (number(2), number(3), number(4));
+        let word = OcrWord {
+            text: text.to_string(),
+            bbox: BoundingBox {
+                x: number(6),
+                y: number(7),
+                width: number(8),
+                height: number(9),
+            },
+            confidence: columns[10].parse().unwrap_or(0.0),
+        };
+        match lines.last_mut().filter(|(k, _)| *k == key) {
+            Some((_, line)) => {
+                line.text.push_str(separator);
+                line.text.push_str(&word.text);
+                line.bbox = line.bbox.union(word.bbox);
+                line.words.push(word);
+            }
+            None => lines.push((
+                key,
+                OcrLine {
+                    text: word.text.clone(),
+                    bbox: word.bbox,
+                    words: vec![word],
+                },
+            )),
+        }
+    }
+
+    let mut text = String::new();
+    let mut previous: Option<(u32, u32)> = None;
+    for ((block, paragraph, _), line) in &lines {
+        if let Some(previous) = previous {
+            text.push_str(if previous == (*block, *paragraph) {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        text.push_str(&line.text);
+        previous = Some((*block, *paragraph));
+    }
+    OcrResult {
+        text,
+        lines: lines.into_iter().map(|(_, line)| line).collect(),
+    }
+}
+
+/// 识别图片中的文字
+///
+/// `lang` 为 tesseract 的语言代码, 多个语言以 + 连接, 如 chi_sim+eng
+pub(crate) fn recognize(path: &Path, lang: Option<&str>) -> Result<OcrResult> {
+    let lang = lang
+        .filter(|l| !l.trim().is_empty())
+        .unwrap_or(DEFAULT_LANG);
+    let output = Command::new(tesseract()?)
+        .arg(path)
+        .arg("stdout")
+        .args(["-l", lang, "tsv"])
+        .output()
+        .map_err(ocr_error)?;
+    if !output.status.success() {
+        return Err(ocr_error(String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(parse_tsv(&String::from_utf8_lossy(&output.stdout), lang))
+}
+
+// 渲染到临时目录后逐页识别
+fn recognize_pages(path: &Path, dir: &Path, lang: Option<&str>) -> Result<Vec<String>> {
+    let output = Command::new("pdftoppm")
+        .args(["-r", PDF_DPI, "-png"])
+        .arg(path)
+        .arg(dir.join("page"))
+        .output()
+        .map_err(|e| ocr_error(format!("无法运行 pdftoppm: {}", e)))?;
+    if !output.status.success() {
+        return Err(ocr_error(String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    // 输出文件名为 page-1.png、page-01.png 等, 按页码排序
+    let mut pages: Vec<(u32, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_string_lossy().to_string();
+            let number = stem.rsplit('-').next()?.parse().ok()?;
+            Some((number, path))
+        })
+        .collect();
+    pages.sort();
+    pages
+        .iter()
+        .map(|(_, page)| recognize(page, lang).map(|r| r.text))
+        .collect()
+}
+
+/// 将扫描版 PDF 逐页渲染后识别, 需要 poppler 提供的 pdftoppm
+pub(crate) fn recognize_pdf(path: &Path, lang: Option<&str>) -> Result<Vec<String>> {
+    let dir = std::env::temp_dir().join(format!("ghostie-ocr-{}", generate_id()));
+    fs::create_dir_all(&dir)?;
+    let result = recognize_pages(path, &dir, lang);
+    let _ = fs::remove_dir_all(&dir);
+    result
+}
+
+/// 识别图片中的文字, 返回文本及每行、每个单词的位置
+///
+/// # 参数
+/// * `image` - `{ "path": "..." }` 或 `{ "base64": "..." }`
+/// * `lang` - tesseract 语言代码, 默认 chi_sim+eng
+#[tauri::command]
+pub async fn ocr(image: OcrImage, lang: Option<String>) -> Result<OcrResult> {
+    tokio::task::spawn_blocking(move || match image {
+        OcrImage::Path { path } => recognize(Path::new(&path), lang.as_deref()),
+        OcrImage::Bytes { base64 } => {
+            let data = base64.rsplit(',').next().unwrap_or_default();
+            let bytes = STANDARD
+                .decode(data.trim())
+                .map_err(|e| ocr_error(format!("无效的图片数据: {}", e)))?;
+            let path = std::env::temp_dir().join(format!("ghostie-ocr-{}.png", generate_id()));
+            fs::write(&path, bytes)?;
+            let result = recognize(&path, lang.as_deref());
+            let _ = fs::remove_file(&path);
+            result
+        }
+    })
+    .await
+    .map_err(ocr_error)?
+}
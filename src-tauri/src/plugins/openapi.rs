@@ -0,0 +1,606 @@
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+use super::deno::{
+    process_plugin_content, register_plugin, PluginError, PluginWithContent, Result,
+};
+use super::grants::PermissionGrant;
+use super::proxy;
+use crate::utils::gen::generate_id;
+
+// 生成工具的 HTTP 方法
+const METHODS: [&str; 7] = ["get", "put", "post", "delete", "patch", "head", "options"];
+// 展开 $ref 的最大深度, 超过后以空 schema 代替, 避免循环引用
+const MAX_REF_DEPTH: usize = 16;
+
+// 请求逻辑, 占位符在生成时替换
+const RUNTIME: &str = r#"const baseUrl = Deno.env.get(__BASE_URL_ENV__) ?? __BASE_URL__;
+
+type Operation = {
+  method: string;
+  path: string;
+  params: { name: string; in: string; key: string }[];
+  body?: string;
+};
+
+// 已配置环境变量的认证方式都会应用
+const auth: Record<string, string>[] = __AUTH__;
+
+function applyAuth(headers: Headers, query: URLSearchParams, cookies: string[]) {
+  for (const scheme of auth) {
+    if (scheme.type === "basic") {
+      const username = Deno.env.get(scheme.username);
+      const password = Deno.env.get(scheme.password) ?? "";
+      if (username) headers.set("Authorization", `Basic ${btoa(`${username}:${password}`)}`);
+      continue;
+    }
+    const value = Deno.env.get(scheme.env);
+    if (!value) continue;
+    if (scheme.type === "bearer") headers.set("Authorization", `Bearer ${value}`);
+    else if (scheme.in === "header") headers.set(scheme.name, value);
+    else if (scheme.in === "query") query.set(scheme.name, value);
+    else if (scheme.in === "cookie") cookies.push(`${scheme.name}=${encodeURIComponent(value)}`);
+  }
+}
+
+async function call(op: Operation, args: Record<string, unknown>) {
+  let path = op.path;
+  const query = new URLSearchParams();
+  const headers = new Headers();
+  const cookies: string[] = [];
+  for (const param of op.params) {
+    const value = args[param.key];
+    if (value === undefined || value === null) continue;
+    const text = typeof value === "object" ? JSON.stringify(value) : String(value);
+    if (param.in === "path") {
+      path = path.replace(`{${param.name}}`, encodeURIComponent(text));
+    } else if (param.in === "query") {
+      if (Array.isArray(value)) value.forEach((v) => query.append(param.name, String(v)));
+      else query.set(param.name, text);
+    } else if (param.in === "header") {
+      headers.set(param.name, text);
+    } else if (param.in === "cookie") {
+      cookies.push(`${param.name}=${encodeURIComponent(text)}`);
+    }
+  }
+  applyAuth(headers, query, cookies);
+  if (cookies.length) headers.set("Cookie", cookies.join("; "));
+
+  let body: BodyInit | undefined;
+  if (op.body && args.body !== undefined) {
+    if (op.body === "application/x-www-form-urlencoded") {
+      body = new URLSearchParams(args.body as Record<string, string>);
+    } else {
+      headers.set("Content-Type", op.body);
+      body = op.body.includes("json") ? JSON.stringify(args.body) : String(args.body);
+    }
+  }
+  const search = query.toString();
+  const url = `${baseUrl.replace(/\/$/, "")}${path}${search ? `?${search}` : ""}`;
+  const response = await fetch(url, { method: op.method, headers, body });
+  const text = await response.text();
+  if (!response.ok) {
+    throw new Error(`请求失败: ${response.status} ${text}`);
+  }
+  try {
+    return JSON.parse(text);
+  } catch {
+    return text;
+  }
+}
+"#;
+
+/// 导入选项
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct OpenApiImportOptions {
+    /// 插件名称, 默认使用文档标题
+    pub name: Option<String>,
+    /// 要生成的操作, 按 operationId 或工具名匹配, 为空时生成全部
+    pub operations: Vec<String>,
+    /// 服务地址, 默认使用文档中的第一个 servers
+    pub base_url: Option<String>,
+    /// 环境变量名前缀, 默认由插件名生成
+    pub env_prefix: Option<String>,
+}
+
+// 一个要生成的工具
+struct GeneratedTool {
+    name: String,
+    description: String,
+    parameters: Value,
+    operation: Value,
+    dangerous: bool,
+}
+
+// 读取文档, 支持链接、JSON 与 YAML
+async fn load_spec(spec: &str) -> Result<(Value, Option<url::Url>)> {
+    let spec = spec.trim();
+    let (content, source) = if spec.starts_with("http://") || spec.starts_with("https://") {
+        let url =
+            url::Url::parse(spec).map_err(|e| PluginError::Plugin(format!("无效的链接: {}", e)))?;
+        let content = proxy::client()
+            .get(url.clone())
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| PluginError::Plugin(format!("下载 OpenAPI 文档失败: {}", e)))?
+            .text()
+            .await
+            .map_err(|e| PluginError::Plugin(format!("下载 OpenAPI 文档失败: {}", e)))?;
+        (content, Some(url))
+    } else {
+        (spec.to_string(), None)
+    };
+    let doc: Value = match serde_json::from_str(&content) {
+        Ok(doc) => doc,
+        Err(_) => serde_yaml::from_str(&content)
+            .map_err(|e| PluginError::Plugin(format!("无法解析 OpenAPI 文档: {}", e)))?,
+    };
+    if !doc["openapi"].as_str().is_some_and(|v| v.starts_with("3.")) {
+        return Err(PluginError::Plugin("仅支持 OpenAPI 3.x 文档".to_string()));
+    }
+    Ok((doc, source))
+}
+
+// 展开文档内的 $ref
+fn resolve(doc: &Value, value: &Value, depth: usize) -> Value {
+    if depth > MAX_REF_DEPTH {
+        return json!({});
+    }
+    match value {
+        Value::Object(map) => {
+            if let Some(reference) = map.get("$ref").and_then(Value::as_str) {
+                let target = reference
+                    .strip_prefix('#')
+                    .and_then(|pointer| doc.pointer(pointer))
+                    .cloned()
+                    .unwrap_or_else(|| json!({}));
+                return resolve(doc, &target, depth + 1);
+            }
+            Value::Object(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), resolve(doc, v, depth)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|v| resolve(doc, v, depth)).collect()),
+        _ => value.clone(),
+    }
+}
+
+// 转换为 snake_case 标识符
+fn identifier(text: &str) -> String {
+    let mut out = String::new();
+    let mut prev_lower = false;
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && prev_lower {
+                out.push('_');
+            }
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+            out.push(c.to_ascii_lowercase());
+        } else {
+            if !out.ends_with('_') {
+                out.push('_');
+            }
+            prev_lower = false;
+        }
+    }
+    let out = out.trim_matches('_').to_string();
+    if out.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("op_{}", out)
+    } else {
+        out
+    }
+}
+
+fn env_name(prefix: &str, name: &str) -> String {
+    format!("{}_{}", prefix, identifier(name).to_uppercase())
+}
+
+// 服务地址, 代入 server 变量的默认值, 相对地址按文档链接解析
+fn base_url(doc: &Value, source: Option<&url::Url>) -> String {
+    let Some(server) = doc["servers"].get(0) else {
+        return source
+            .map(|url| url.origin().ascii_serialization())
+            .unwrap_or_default();
+    };
+    let mut url = server["url"].as_str().unwrap_or_default().to_string();
+    if let Some(vars) = server["variables"].as_object() {
+        for (key, var) in vars {
+            let default = var["default"].as_str().unwrap_or_default();
+            url = url.replace(&format!("{{{}}}", key), default);
+        }
+    }
+    match source {
+        Some(source) if !url.contains("://") => {
+            source.join(&url).map(|u| u.to_string()).unwrap_or(url)
+        }
+        _ => url,
+    }
+}
+
+// 认证方式与对应的环境变量
+fn auth_schemes(doc: &Value, prefix: &str) -> (Vec<Value>, Vec<String>) {
+    let mut schemes = Vec::new();
+    let mut env = Vec::new();
+    let Some(defined) = doc["components"]["securitySchemes"].as_object() else {
+        return (schemes, env);
+    };
+    let single = defined.len() == 1;
+    for (key, scheme) in defined {
+        let scheme = resolve(doc, scheme, 0);
+        let var = |suffix: &str| {
+            if single {
+                env_name(prefix, suffix)
+            } else {
+                env_name(prefix, &format!("{}_{}", key, suffix))
+            }
+        };
+        match (
+            scheme["type"].as_str().unwrap_or_default(),
+            scheme["scheme"].as_str().map(str::to_lowercase).as_deref(),
+        ) {
+            ("http", Some("basic")) => {
+                let (username, password) = (var("username"), var("password"));
+                schemes
+                    .push(json!({ "type": "basic", "username": username, "password": password }));
+                env.extend([username, password]);
+            }
+            ("http", _) | ("oauth2", _) | ("openIdConnect", _) => {
+                let name = var("token");
+                schemes.push(json!({ "type": "bearer", "env": name }));
+                env.push(name);
+            }
+            ("apiKey", _) => {
+                let name = var("api_key");
+                schemes.push(json!({
+                    "type": "apiKey",
+                    "in": scheme["in"].as_str().unwrap_or("header"),
+                    "name": scheme["name"].as_str().unwrap_or("X-API-Key"),
+                    "env": name,
+                }));
+                env.push(name);
+            }
+            _ => {}
+        }
+    }
+    (schemes, env)
+}
+
+// 由一个操作生成工具的参数 schema 与请求描述
+fn build_tool(
+    doc: &Value,
+    path: &str,
+    method: &str,
+    path_item: &Value,
+    operation: &Value,
+    name: String,
+) -> GeneratedTool {
+    // 操作级参数覆盖路径级的同名参数
+    let mut params: Vec<Value> = Vec::new();
+    for param in path_item["parameters"]
+        .as_array()
+        .into_iter()
+        .chain(operation["parameters"].as_array())
+        .flatten()
+    {
+        let param = resolve(doc, param, 0);
+        params.retain(|p| !(p["name"] == param["name"] && p["in"] == param["in"]));
+        params.push(param);
+    }
+
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    let mut mapping = Vec::new();
+    for param in &params {
+        let (Some(param_name), Some(location)) = (param["name"].as_str(), param["in"].as_str())
+        else {
+            continue;
+        };
+        // 不同位置的参数重名时加上位置后缀
+        let mut key = param_name.to_string();
+        if properties.contains_key(&key) {
+            key = format!("{}_{}", param_name, location);
+        }
+        let mut schema = param
+            .get("schema")
+            .cloned()
+            .unwrap_or_else(|| json!({ "type": "string" }));
+        if let (Some(schema), Some(description)) =
+            (schema.as_object_mut(), param["description"].as_str())
+        {
+            schema
+                .entry("description")
+                .or_insert_with(|| json!(description));
+        }
+        if location == "path" || param["required"].as_bool().unwrap_or(false) {
+            required.push(json!(key));
+        }
+        properties.insert(key.clone(), schema);
+        mapping.push(json!({ "name": param_name, "in": location, "key": key }));
+    }
+
+    let mut body_type = None;
+    let request_body = resolve(doc, &operation["requestBody"], 0);
+    if let Some(content) = request_body["content"].as_object() {
+        // 优先使用 JSON
+        let chosen = content
+            .iter()
+            .find(|(kind, _)| kind.contains("json"))
+            .or_else(|| content.iter().next());
+        if let Some((kind, media)) = chosen {
+            let mut schema = media.get("schema").cloned().unwrap_or_else(|| json!({}));
+            if !kind.contains("json") && kind != "application/x-www-form-urlencoded" {
+                schema = json!({ "type": "string" });
+            }
+            if let (Some(schema), Some(description)) =
+                (schema.as_object_mut(), request_body["description"].as_str())
+            {
+                schema
+                    .entry("description")
+                    .or_insert_with(|| json!(description));
+            }
+            properties.insert("body".to_string(), schema);
+            if request_body["required"].as_bool().unwrap_or(false) {
+                required.push(json!("body"));
+            }
+            body_type = Some(kind.clone());
+        }
+    }
+
+    let description = [
+        operation["summary"].as_str(),
+        operation["description"].as_str(),
+    ]
+    .into_iter()
+    .flatten()
+    .map(str::trim)
+    .filter(|s| !s.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n");
+    let mut op = json!({
+        "method": method.to_uppercase(),
+        "path": path,
+        "params": mapping,
+    });
+    if let Some(kind) = body_type {
+        op["body"] = json!(kind);
+    }
+    GeneratedTool {
+        name,
+        description: if description.is_empty() {
+            format!("{} {}", method.to_uppercase(), path)
+        } else {
+            description
+        },
+        parameters: json!({ "type": "object", "properties": properties, "required": required }),
+        operation: op,
+        dangerous: method == "delete",
+    }
+}
+
+fn collect_tools(doc: &Value, selected: &[String]) -> Result<Vec<GeneratedTool>> {
+    let mut tools = Vec::new();
+    let mut used = HashSet::new();
+    let Some(paths) = doc["paths"].as_object() else {
+        return Err(PluginError::Plugin("文档中没有 paths".to_string()));
+    };
+    for (path, path_item) in paths {
+        let path_item = resolve(doc, path_item, 0);
+        for method in METHODS {
+            let Some(operation) = path_item.get(method) else {
+                continue;
+            };
+            let operation_id = operation["operationId"].as_str();
+            // operationId 无法转换为标识符时由方法与路径生成
+            let mut name = Some(operation_id.map(identifier).unwrap_or_default())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| identifier(&format!("{} {}", method, path)));
+            if !selected.is_empty()
+                && !selected
+                    .iter()
+                    .any(|s| Some(s.as_str()) == operation_id || *s == name)
+            {
+                continue;
+            }
+            while !used.insert(name.clone()) {
+                name = format!("{}_{}", name, method);
+            }
+            tools.push(build_tool(doc, path, method, &path_item, operation, name));
+        }
+    }
+    if tools.is_empty() {
+        return Err(PluginError::Plugin("没有可生成的操作".to_string()));
+    }
+    Ok(tools)
+}
+
+// 缩进多行文本, 首行除外
+fn indent(text: &str, spaces: usize) -> String {
+    text.lines()
+        .collect::<Vec<_>>()
+        .join(&format!("\n{}", " ".repeat(spaces)))
+}
+
+fn render(
+    name: &str,
+    description: &str,
+    base_url: &str,
+    base_env: &str,
+    auth: &[Value],
+    env: &[String],
+    tools: &[GeneratedTool],
+) -> Result<String> {
+    let runtime = RUNTIME
+        .replace("__BASE_URL_ENV__", &serde_json::to_string(base_env)?)
+        .replace("__BASE_URL__", &serde_json::to_string(base_url)?)
+        .replace("__AUTH__", &serde_json::to_string(auth)?);
+    let mut source = format!(
+        "// 由 OpenAPI 文档生成, 环境变量 {} 可覆盖服务地址\n{}\nexport default {{\n  name: {},\n  description: {},\n  env: {},\n  tools: {{\n",
+        base_env,
+        runtime,
+        serde_json::to_string(name)?,
+        serde_json::to_string(description)?,
+        serde_json::to_string(env)?,
+    );
+    for tool in tools {
+        source.push_str(&format!(
+            "    {}: {{\n      description: {},\n      parameters: {},\n{}      handler: (args: Record<string, unknown>) =>\n        call({}, args),\n    }},\n",
+            tool.name,
+            serde_json::to_string(&tool.description)?,
+            indent(&serde_json::to_string_pretty(&tool.parameters)?, 6),
+            if tool.dangerous { "      dangerous: true,\n" } else { "" },
+            serde_json::to_string(&tool.operation)?,
+        ));
+    }
+    source.push_str("  },\n};\n");
+    Ok(source)
+}
+
+// ChatGPT 插件清单中声明的认证方式, 文档未声明 securitySchemes 时使用
+fn manifest_auth(manifest: &Value, prefix: &str) -> (Vec<Value>, Vec<String>) {
+    let auth = &manifest["auth"];
+    match auth["type"].as_str().unwrap_or("none") {
+        "user_http" | "service_http" if auth["authorization_type"] == "basic" => {
+            let (username, password) = (env_name(prefix, "username"), env_name(prefix, "password"));
+            (
+                vec![json!({ "type": "basic", "username": username, "password": password })],
+                vec![username, password],
+            )
+        }
+        "user_http" | "service_http" | "oauth" => {
+            let name = env_name(prefix, "token");
+            (vec![json!({ "type": "bearer", "env": name })], vec![name])
+        }
+        _ => (Vec::new(), Vec::new()),
+    }
+}
+
+// 由文档生成插件并注册, manifest 为 ChatGPT 插件清单
+async fn generate(
+    doc: &Value,
+    source: Option<&url::Url>,
+    options: OpenApiImportOptions,
+    manifest: Option<&Value>,
+) -> Result<PluginWithContent> {
+    let name = options
+        .name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .or_else(|| manifest.and_then(|m| m["name_for_human"].as_str().map(str::to_string)))
+        .or_else(|| doc["info"]["title"].as_str().map(str::to_string))
+        .unwrap_or_else(|| "OpenAPI".to_string());
+    let prefix = options
+        .env_prefix
+        .map(|p| identifier(&p))
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| identifier(&name))
+        .to_uppercase();
+    let prefix = if prefix.is_empty() {
+        "API".to_string()
+    } else {
+        prefix
+    };
+    let base = options.base_url.unwrap_or_else(|| base_url(doc, source));
+    let base_env = env_name(&prefix, "base_url");
+    let (mut auth, mut env) = auth_schemes(doc, &prefix);
+    if auth.is_empty() {
+        if let Some(manifest) = manifest {
+            (auth, env) = manifest_auth(manifest, &prefix);
+        }
+    }
+    env.insert(0, base_env.clone());
+    let tools = collect_tools(doc, &options.operations)?;
+    // 清单中给模型的描述更适合作为插件描述
+    let description = manifest
+        .and_then(|m| m["description_for_model"].as_str())
+        .or_else(|| doc["info"]["description"].as_str())
+        .or_else(|| doc["info"]["title"].as_str())
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    let content = render(&name, &description, &base, &base_env, &auth, &env, &tools)?;
+    let mut info = process_plugin_content(generate_id(), content.clone()).await?;
+    // 插件只会访问文档中的服务, 不再单独询问
+    if let Some(host) = url::Url::parse(&base).ok().and_then(|url| {
+        Some(format!(
+            "{}:{}",
+            url.host_str()?,
+            url.port_or_known_default()?
+        ))
+    }) {
+        info.grants.push(PermissionGrant {
+            kind: "net".to_string(),
+            target: Some(host),
+            allowed: true,
+            time: chrono::Utc::now().timestamp_millis(),
+        });
+        info = register_plugin(info).await?;
+    }
+    tracing::info!(plugin = %info.id, tools = tools.len(), "已由 OpenAPI 文档生成插件");
+    Ok(PluginWithContent {
+        info,
+        content,
+        files: None,
+    })
+}
+
+/// 由 OpenAPI 3.x 文档生成 Deno 插件, 每个操作对应一个工具
+///
+/// 认证信息通过环境变量配置, DELETE 操作标记为需要确认, 并预先允许访问服务地址
+#[tauri::command]
+pub async fn plugin_import_openapi(
+    spec: String,
+    options: Option<OpenApiImportOptions>,
+) -> Result<PluginWithContent> {
+    let (doc, source) = load_spec(&spec).await?;
+    generate(&doc, source.as_ref(), options.unwrap_or_default(), None).await
+}
+
+/// 由 ChatGPT 插件清单 (ai-plugin.json) 生成插件, 按清单中的地址读取 OpenAPI 文档
+#[tauri::command]
+pub async fn plugin_import_ai_plugin(
+    url: String,
+    options: Option<OpenApiImportOptions>,
+) -> Result<PluginWithContent> {
+    let manifest_url = url::Url::parse(url.trim())
+        .map_err(|e| PluginError::Plugin(format!("无效的链接: {}", e)))?;
+    let manifest: Value = proxy::client()
+        .get(manifest_url.clone())
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| PluginError::Plugin(format!("下载插件清单失败: {}", e)))?
+        .json()
+        .await
+        .map_err(|e| PluginError::Plugin(format!("插件清单无效: {}", e)))?;
+    if manifest["api"]["type"]
+        .as_str()
+        .is_some_and(|t| t != "openapi")
+    {
+        return Err(PluginError::Plugin(format!(
+            "不支持的接口类型: {}",
+            manifest["api"]["type"]
+        )));
+    }
+    let api_url = manifest["api"]["url"]
+        .as_str()
+        .ok_or_else(|| PluginError::Plugin("插件清单中没有 api.url".to_string()))?;
+    // 接口地址可以是相对清单的路径
+    let api_url = manifest_url
+        .join(api_url)
+        .map_err(|e| PluginError::Plugin(format!("无效的接口地址: {}", e)))?;
+    let (doc, source) = load_spec(api_url.as_str()).await?;
+    let source = source.unwrap_or(api_url);
+    generate(
+        &doc,
+        Some(&source),
+        options.unwrap_or_default(),
+        Some(&manifest),
+    )
+    .await
+}
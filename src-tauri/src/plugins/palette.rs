@@ -0,0 +1,310 @@
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use super::bots;
+use super::db::with_db;
+use super::deno::{load_plugin_list, Result};
+use super::{i18n, meta};
+
+const DEFAULT_LIMIT: usize = 20;
+const RECENT_CONVERSATIONS: usize = 200;
+// 超过该时间的索引在下次查询时重建
+const INDEX_TTL: Duration = Duration::from_secs(30);
+const DAY_MS: f64 = 86_400_000.0;
+
+// 设置页面: (页面 id, 标题, 搜索用的别名)
+const SETTINGS_PAGES: &[(&str, &str, &str)] = &[
+    ("general", "通用设置", "general 语言 locale"),
+    ("providers", "模型服务", "providers models api key"),
+    ("plugins", "插件设置", "plugins 插件"),
+    ("shortcuts", "快捷键", "shortcuts hotkey 快捷键"),
+    ("tray", "托盘与后台", "tray background autostart 开机启动"),
+    ("clipboard", "剪贴板监听", "clipboard 剪贴板"),
+    ("proxy", "网络代理", "proxy network 代理"),
+    ("schedules", "定时任务", "schedules cron 定时"),
+    ("update", "检查更新", "update 更新"),
+    ("about", "关于", "about 关于"),
+];
+
+/// 命令面板中的条目类型
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PaletteKind {
+    Plugin,
+    Tool,
+    Conversation,
+    Bot,
+    Setting,
+}
+
+/// 命令面板条目, 选中后由前端按 kind 与 target 跳转或执行
+#[derive(Debug, Serialize, Clone)]
+pub struct PaletteEntry {
+    /// 唯一 id, 如 tool:plugin/tool, 用于记录使用次数
+    pub id: String,
+    pub kind: PaletteKind,
+    pub title: String,
+    pub subtitle: Option<String>,
+    /// 插件、对话、助手或设置页面的 id
+    pub target: String,
+    /// 工具所属的函数名
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    #[serde(skip_serializing)]
+    keywords: String,
+}
+
+/// 查询结果, 按分数从高到低排列
+#[derive(Debug, Serialize, Clone)]
+pub struct PaletteMatch {
+    #[serde(flatten)]
+    pub entry: PaletteEntry,
+    pub score: f64,
+}
+
+struct Index {
+    entries: Vec<PaletteEntry>,
+    built_at: Instant,
+}
+
+static INDEX: Lazy<RwLock<Option<Index>>> = Lazy::new(|| RwLock::new(None));
+
+fn entry(
+    id: String,
+    kind: PaletteKind,
+    title: String,
+    subtitle: Option<String>,
+    target: String,
+) -> PaletteEntry {
+    PaletteEntry {
+        id,
+        kind,
+        title,
+        subtitle,
+        target,
+        tool: None,
+        keywords: String::new(),
+    }
+}
+
+async fn build() -> Result<Vec<PaletteEntry>> {
+    let locale = i18n::current_locale(None);
+    let mut entries = Vec::new();
+
+    for mut plugin in load_plugin_list().await?.into_values() {
+        if !plugin.enabled {
+            continue;
+        }
+        meta::display(&mut plugin, locale.as_deref());
+        for tool in &plugin.tools {
+            let mut item = entry(
+                format!("tool:{}/{}", plugin.id, tool.name),
+                PaletteKind::Tool,
+                tool.name.clone(),
+                Some(plugin.name.clone()),
+                plugin.id.clone(),
+            );
+            item.tool = Some(tool.name.clone());
+            item.keywords = tool.description.clone();
+            entries.push(item);
+        }
+        let mut item = entry(
+            format!("plugin:{}", plugin.id),
+            PaletteKind::Plugin,
+            plugin.name.clone(),
+            plugin.description.clone(),
+            plugin.id.clone(),
+        );
+        item.keywords = plugin.id.clone();
+        entries.push(item);
+    }
+
+    let conversations: Vec<(String, String)> = with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, title FROM conversations WHERE archived = 0 ORDER BY updated_at DESC LIMIT ?1",
+        )?;
+        let rows = stmt
+            .query_map([RECENT_CONVERSATIONS as i64], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })?;
+    entries.extend(conversations.into_iter().map(|(id, title)| {
+        entry(
+            format!("conversation:{}", id),
+            PaletteKind::Conversation,
+            title,
+            None,
+            id,
+        )
+    }));
+
+    entries.extend(bots::bot_list().await?.into_iter().map(|bot| {
+        entry(
+            format!("bot:{}", bot.id),
+            PaletteKind::Bot,
+            bot.name,
+            Some(bot.description).filter(|d| !d.is_empty()),
+            bot.id,
+        )
+    }));
+
+    entries.extend(SETTINGS_PAGES.iter().map(|(id, title, keywords)| {
+        let mut item = entry(
+            format!("setting:{}", id),
+            PaletteKind::Setting,
+            title.to_string(),
+            None,
+            id.to_string(),
+        );
+        item.keywords = keywords.to_string();
+        item
+    }));
+
+    Ok(entries)
+}
+
+async fn entries() -> Result<Vec<PaletteEntry>> {
+    let cached = INDEX
+        .read()
+        .unwrap()
+        .as_ref()
+        .filter(|index| index.built_at.elapsed() < INDEX_TTL)
+        .map(|index| index.entries.clone());
+    if let Some(entries) = cached {
+        return Ok(entries);
+    }
+    let entries = build().await?;
+    *INDEX.write().unwrap() = Some(Index {
+        entries: entries.clone(),
+        built_at: Instant::now(),
+    });
+    Ok(entries)
+}
+
+// 子序列模糊匹配, 开头、词首与连续命中加分, 不匹配时返回 None
+fn fuzzy(query: &[char], text: &str) -> Option<f64> {
+    let chars: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0.0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for &c in query {
+        let found = (position..chars.len()).find(|&i| chars[i] == c)?;
+        score += 1.0;
+        if found == 0 {
+            score += 3.0;
+        } else if !chars[found - 1].is_alphanumeric() {
+            score += 2.0;
+        }
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 1.5;
+        }
+        // 跳过的字符越多分数越低
+        score -= (found - position) as f64 * 0.05;
+        previous = Some(found);
+        position = found + 1;
+    }
+    // 完全相同或前缀相同时额外加分
+    if chars.len() == query.len() {
+        score += 5.0;
+    } else if chars.starts_with(query) {
+        score += 2.0;
+    }
+    Some(score)
+}
+
+fn score(query: &[char], entry: &PaletteEntry) -> Option<f64> {
+    let title = fuzzy(query, &entry.title);
+    let others = [entry.subtitle.as_deref(), Some(entry.keywords.as_str())]
+        .into_iter()
+        .flatten()
+        .filter_map(|text| fuzzy(query, text))
+        .fold(None, |best: Option<f64>, s| {
+            Some(best.map_or(s, |b| b.max(s)))
+        });
+    // 副标题与关键词命中的权重低于标题
+    match (title, others) {
+        (Some(t), Some(o)) => Some(t.max(o * 0.6)),
+        (Some(t), None) => Some(t),
+        (None, Some(o)) => Some(o * 0.6),
+        (None, None) => None,
+    }
+}
+
+fn usage() -> Result<HashMap<String, (i64, i64)>> {
+    with_db(|conn| {
+        let mut stmt = conn.prepare("SELECT id, count, last_used FROM palette_usage")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+            .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+        Ok(rows)
+    })
+}
+
+// 使用次数取对数, 最近使用的条目按天数衰减加分
+fn usage_boost(count: i64, last_used: i64, now: i64) -> f64 {
+    let days = (now - last_used).max(0) as f64 / DAY_MS;
+    (1.0 + count as f64).ln() * 2.0 + 3.0 / (1.0 + days)
+}
+
+/// 查询命令面板, 在插件、工具、对话、助手与设置页面中模糊匹配
+///
+/// # 参数
+/// * `text` - 查询文本, 为空时按使用频率返回常用条目
+/// * `limit` - 返回数量, 默认 20
+#[tauri::command]
+pub async fn palette_query(text: String, limit: Option<usize>) -> Result<Vec<PaletteMatch>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    let query: Vec<char> = text
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let usage = usage()?;
+    let now = Utc::now().timestamp_millis();
+
+    let mut matches: Vec<PaletteMatch> = entries()
+        .await?
+        .into_iter()
+        .filter_map(|entry| {
+            let boost = usage
+                .get(&entry.id)
+                .map(|&(count, last_used)| usage_boost(count, last_used, now));
+            let score = if query.is_empty() {
+                boost?
+            } else {
+                score(&query, &entry)? + boost.unwrap_or(0.0)
+            };
+            Some(PaletteMatch { entry, score })
+        })
+        .collect();
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    matches.truncate(limit);
+    Ok(matches)
+}
+
+/// 记录选中的条目, 用于按使用频率排序
+#[tauri::command]
+pub async fn palette_record(id: String) -> Result<()> {
+    with_db(|conn| {
+        conn.execute(
+            "INSERT INTO palette_usage (id, count, last_used) VALUES (?1, 1, ?2)
+             ON CONFLICT(id) DO UPDATE SET count = count + 1, last_used = excluded.last_used",
+            params![id, Utc::now().timestamp_millis()],
+        )?;
+        Ok(())
+    })
+}
+
+/// 立即重建索引, 插件、对话或助手变化后可调用
+#[tauri::command]
+pub async fn palette_refresh() -> Result<usize> {
+    *INDEX.write().unwrap() = None;
+    Ok(entries().await?.len())
+}
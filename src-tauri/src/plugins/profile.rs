@@ -0,0 +1,122 @@
+use rusqlite::params;
+use serde::Serialize;
+use std::future::Future;
+
+use super::db::with_db;
+use super::deno::{EnvVar, PluginError, Result};
+use super::env::{self, Scope};
+use crate::utils::settings;
+
+tokio::task_local! {
+    // 单次执行指定的环境配置, 优先于设置中启用的配置
+    static OVERRIDE: Option<String>;
+}
+
+/// 环境配置, 如 dev / staging / prod, 其中的变量覆盖同名的全局变量
+#[derive(Debug, Serialize)]
+pub struct EnvProfile {
+    pub name: String,
+    pub active: bool,
+    pub vars: Vec<EnvVar>,
+}
+
+fn exists(name: &str) -> Result<bool> {
+    with_db(|conn| {
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM env_profiles WHERE name = ?1",
+            [name],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    })
+}
+
+fn check_exists(name: &str) -> Result<()> {
+    if exists(name)? {
+        Ok(())
+    } else {
+        Err(PluginError::Plugin(format!("环境配置不存在: {}", name)))
+    }
+}
+
+/// 当前生效的环境配置
+pub(crate) fn current() -> Option<String> {
+    OVERRIDE
+        .try_with(|name| name.clone())
+        .ok()
+        .flatten()
+        .or_else(|| settings::get().profile)
+}
+
+/// 以指定的环境配置执行, 为 None 时使用设置中启用的配置
+pub(crate) async fn scope<F: Future>(name: Option<String>, f: F) -> Result<F::Output> {
+    match name {
+        Some(name) => {
+            check_exists(&name)?;
+            Ok(OVERRIDE.scope(Some(name), f).await)
+        }
+        None => Ok(f.await),
+    }
+}
+
+#[tauri::command]
+pub async fn profile_list() -> Result<Vec<EnvProfile>> {
+    let active = settings::get().profile;
+    with_db(|conn| {
+        let conn: &rusqlite::Connection = conn;
+        let mut stmt = conn.prepare("SELECT name FROM env_profiles ORDER BY name")?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        names
+            .into_iter()
+            .map(|name| {
+                Ok(EnvProfile {
+                    active: active.as_deref() == Some(name.as_str()),
+                    vars: env::read_vars(conn, Scope::Profile(&name))?,
+                    name,
+                })
+            })
+            .collect()
+    })
+}
+
+/// 创建或覆盖保存环境配置
+#[tauri::command]
+pub async fn profile_save(name: String, vars: Vec<EnvVar>) -> Result<()> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(PluginError::Plugin("环境配置名称不能为空".to_string()));
+    }
+    env::check_vars(&vars)?;
+    with_db(|conn| {
+        conn.execute(
+            "INSERT OR IGNORE INTO env_profiles (name, created_at) VALUES (?1, ?2)",
+            params![name, chrono::Utc::now().timestamp_millis()],
+        )?;
+        env::write_vars(conn, Scope::Profile(&name), &vars)
+    })
+}
+
+#[tauri::command]
+pub async fn profile_delete(name: String) -> Result<()> {
+    check_exists(&name)?;
+    with_db(|conn| {
+        env::write_vars(conn, Scope::Profile(&name), &[])?;
+        conn.execute("DELETE FROM env_profiles WHERE name = ?1", [&name])?;
+        Ok(())
+    })?;
+    if settings::get().profile.as_deref() == Some(name.as_str()) {
+        settings::update(|s| s.profile = None)?;
+    }
+    Ok(())
+}
+
+/// 启用环境配置, 为空时只使用全局变量
+#[tauri::command]
+pub async fn profile_activate(name: Option<String>) -> Result<Option<String>> {
+    if let Some(ref name) = name {
+        check_exists(name)?;
+    }
+    Ok(settings::update(|s| s.profile = name)?.profile)
+}
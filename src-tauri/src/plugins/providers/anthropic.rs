@@ -0,0 +1,199 @@
+use serde_json::{json, Value};
+
+use super::{
+    client, functions, parse_arguments, send, ChatMessage, ChatRequest, ChatResponse, FunctionCall,
+    ProviderConfig, Sink, StreamReader, ToolCall, Usage,
+};
+use crate::plugins::deno::{PluginError, Result};
+
+const API_VERSION: &str = "2023-06-01";
+// Anthropic 要求必须指定输出上限
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+// 转换为 Anthropic 的消息格式, system 单独传递, 相邻的同角色消息合并
+fn convert(messages: &[ChatMessage]) -> (String, Vec<Value>) {
+    let mut system = Vec::new();
+    let mut converted: Vec<(String, Vec<Value>)> = Vec::new();
+    for message in messages {
+        let (role, blocks) = match message.role.as_str() {
+            "system" => {
+                system.push(message.content.clone());
+                continue;
+            }
+            "tool" => (
+                "user",
+                vec![json!({
+                    "type": "tool_result",
+                    "tool_use_id": message.tool_call_id.clone().unwrap_or_default(),
+                    "content": message.content,
+                })],
+            ),
+            "assistant" => {
+                let mut blocks = Vec::new();
+                if !message.content.is_empty() {
+                    blocks.push(json!({ "type": "text", "text": message.content }));
+                }
+                for call in &message.tool_calls {
+                    blocks.push(json!({
+                        "type": "tool_use",
+                        "id": call.id,
+                        "name": call.function.name,
+                        "input": parse_arguments(&call.function.arguments),
+                    }));
+                }
+                ("assistant", blocks)
+            }
+            _ => (
+                "user",
+                vec![json!({ "type": "text", "text": message.content })],
+            ),
+        };
+        match converted.last_mut() {
+            Some((last, content)) if last == role => content.extend(blocks),
+            _ => converted.push((role.to_string(), blocks)),
+        }
+    }
+    let messages = converted
+        .into_iter()
+        .map(|(role, content)| json!({ "role": role, "content": content }))
+        .collect();
+    (system.join("\n\n"), messages)
+}
+
+fn finish_reason(reason: &str) -> String {
+    match reason {
+        "end_turn" | "stop_sequence" => "stop",
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Anthropic Messages 接口的流式对话
+pub(super) async fn chat(
+    config: &ProviderConfig,
+    key: &str,
+    request: &ChatRequest,
+    sink: &Sink,
+) -> Result<ChatResponse> {
+    let url = format!("{}/v1/messages", config.base_url);
+    let (system, messages) = convert(&request.messages);
+    let mut body = json!({
+        "model": request.model,
+        "messages": messages,
+        "max_tokens": request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+        "stream": true,
+    });
+    if !system.is_empty() {
+        body["system"] = json!(system);
+    }
+    if !request.tools.is_empty() {
+        body["tools"] = functions(&request.tools)
+            .map(|(name, description, parameters)| {
+                json!({
+                    "name": name,
+                    "description": description,
+                    "input_schema": parameters,
+                })
+            })
+            .collect();
+    }
+    if let Some(temperature) = request.temperature {
+        body["temperature"] = json!(temperature);
+    }
+    let response = send(|| {
+        client()
+            .post(&url)
+            .header("x-api-key", key)
+            .header("anthropic-version", API_VERSION)
+            .json(&body)
+    })
+    .await?;
+
+    let mut events = StreamReader::new(response);
+    let mut content = String::new();
+    let mut tool_calls: Vec<ToolCall> = Vec::new();
+    // 内容块序号对应的工具调用
+    let mut blocks: Vec<Option<usize>> = Vec::new();
+    let mut finish = None;
+    let mut usage = Usage::default();
+    while let Some(event) = events.next_event().await? {
+        if event.data.is_empty() {
+            continue;
+        }
+        let data: Value = serde_json::from_str(&event.data)?;
+        match data["type"].as_str().unwrap_or_default() {
+            "message_start" => {
+                let value = &data["message"]["usage"];
+                usage.prompt_tokens = value["input_tokens"].as_u64().unwrap_or(0);
+                usage.completion_tokens = value["output_tokens"].as_u64().unwrap_or(0);
+            }
+            "content_block_start" => {
+                let index = data["index"].as_u64().unwrap_or(0) as usize;
+                while blocks.len() <= index {
+                    blocks.push(None);
+                }
+                let block = &data["content_block"];
+                if block["type"] == "tool_use" {
+                    blocks[index] = Some(tool_calls.len());
+                    tool_calls.push(ToolCall {
+                        id: block["id"].as_str().unwrap_or_default().to_string(),
+                        function: FunctionCall {
+                            name: block["name"].as_str().unwrap_or_default().to_string(),
+                            arguments: String::new(),
+                        },
+                        ..Default::default()
+                    });
+                }
+            }
+            "content_block_delta" => {
+                let index = data["index"].as_u64().unwrap_or(0) as usize;
+                let delta = &data["delta"];
+                if let Some(text) = delta["text"].as_str().filter(|t| !t.is_empty()) {
+                    content.push_str(text);
+                    sink(text);
+                }
+                if let Some(partial) = delta["partial_json"].as_str() {
+                    if let Some(Some(call)) = blocks.get(index) {
+                        tool_calls[*call].function.arguments.push_str(partial);
+                    }
+                }
+            }
+            "message_delta" => {
+                if let Some(reason) = data["delta"]["stop_reason"].as_str() {
+                    finish = Some(finish_reason(reason));
+                }
+                if let Some(tokens) = data["usage"]["output_tokens"].as_u64() {
+                    usage.completion_tokens = tokens;
+                }
+            }
+            "error" => {
+                return Err(PluginError::Plugin(format!(
+                    "模型服务错误: {}",
+                    data["error"]["message"].as_str().unwrap_or_default()
+                )));
+            }
+            "message_stop" => break,
+            _ => {}
+        }
+    }
+    // 无参数的工具调用不会返回 partial_json
+    for call in &mut tool_calls {
+        if call.function.arguments.is_empty() {
+            call.function.arguments = "{}".to_string();
+        }
+    }
+
+    Ok(ChatResponse {
+        request_id: String::new(),
+        message: ChatMessage {
+            role: "assistant".to_string(),
+            content,
+            tool_calls,
+            tool_call_id: None,
+        },
+        finish_reason: finish,
+        usage: Some(usage),
+    })
+}
@@ -0,0 +1,239 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::{
+    client, functions, parse_arguments, send, ChatMessage, ChatRequest, ChatResponse, FunctionCall,
+    ProviderConfig, Sink, StreamReader, ToolCall, Usage,
+};
+use crate::plugins::deno::{PluginError, Result};
+
+// Gemini 只支持 OpenAPI schema 的子集, 去掉不支持的字段
+fn clean_schema(schema: &mut Value) {
+    match schema {
+        Value::Object(map) => {
+            map.remove("$schema");
+            map.remove("additionalProperties");
+            map.values_mut().for_each(clean_schema);
+        }
+        Value::Array(items) => items.iter_mut().for_each(clean_schema),
+        _ => {}
+    }
+}
+
+// 转换为 Gemini 的 contents, tool 消息按调用 id 找回函数名
+fn convert(messages: &[ChatMessage]) -> (String, Vec<Value>) {
+    let mut system = Vec::new();
+    let mut names: HashMap<&str, &str> = HashMap::new();
+    let mut contents: Vec<(&str, Vec<Value>)> = Vec::new();
+    for message in messages {
+        let (role, parts) = match message.role.as_str() {
+            "system" => {
+                system.push(message.content.clone());
+                continue;
+            }
+            "tool" => {
+                let id = message.tool_call_id.as_deref().unwrap_or_default();
+                let name = names.get(id).copied().unwrap_or(id);
+                (
+                    "user",
+                    vec![json!({
+                        "functionResponse": {
+                            "name": name,
+                            "response": { "content": message.content },
+                        },
+                    })],
+                )
+            }
+            "assistant" => {
+                let mut parts = Vec::new();
+                if !message.content.is_empty() {
+                    parts.push(json!({ "text": message.content }));
+                }
+                for call in &message.tool_calls {
+                    names.insert(&call.id, &call.function.name);
+                    parts.push(json!({
+                        "functionCall": {
+                            "name": call.function.name,
+                            "args": parse_arguments(&call.function.arguments),
+                        },
+                    }));
+                }
+                ("model", parts)
+            }
+            _ => ("user", vec![json!({ "text": message.content })]),
+        };
+        match contents.last_mut() {
+            Some((last, content)) if *last == role => content.extend(parts),
+            _ => contents.push((role, parts)),
+        }
+    }
+    let contents = contents
+        .into_iter()
+        .map(|(role, parts)| json!({ "role": role, "parts": parts }))
+        .collect();
+    (system.join("\n\n"), contents)
+}
+
+fn finish_reason(reason: &str) -> String {
+    match reason {
+        "STOP" => "stop",
+        "MAX_TOKENS" => "length",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Gemini generateContent 接口的流式对话
+pub(super) async fn chat(
+    config: &ProviderConfig,
+    key: &str,
+    request: &ChatRequest,
+    sink: &Sink,
+) -> Result<ChatResponse> {
+    let url = format!(
+        "{}/v1beta/models/{}:streamGenerateContent?alt=sse",
+        config.base_url, request.model
+    );
+    let (system, contents) = convert(&request.messages);
+    let mut body = json!({ "contents": contents });
+    if !system.is_empty() {
+        body["systemInstruction"] = json!({ "parts": [{ "text": system }] });
+    }
+    if !request.tools.is_empty() {
+        let declarations: Vec<Value> = functions(&request.tools)
+            .map(|(name, description, mut parameters)| {
+                clean_schema(&mut parameters);
+                json!({
+                    "name": name,
+                    "description": description,
+                    "parameters": parameters,
+                })
+            })
+            .collect();
+        body["tools"] = json!([{ "functionDeclarations": declarations }]);
+    }
+    let mut generation = json!({});
+    if let Some(temperature) = request.temperature {
+        generation["temperature"] = json!(temperature);
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        generation["maxOutputTokens"] = json!(max_tokens);
+    }
+    body["generationConfig"] = generation;
+    let response = send(|| {
+        client()
+            .post(&url)
+            .header("x-goog-api-key", key)
+            .json(&body)
+    })
+    .await?;
+
+    let mut events = StreamReader::new(response);
+    let mut content = String::new();
+    let mut tool_calls: Vec<ToolCall> = Vec::new();
+    let mut finish = None;
+    let mut usage = None;
+    while let Some(event) = events.next_event().await? {
+        if event.data.is_empty() {
+            continue;
+        }
+        let chunk: Value = serde_json::from_str(&event.data)?;
+        if let Some(error) = chunk.get("error") {
+            return Err(PluginError::Plugin(format!(
+                "模型服务错误: {}",
+                error["message"].as_str().unwrap_or_default()
+            )));
+        }
+        if let Some(value) = chunk.get("usageMetadata") {
+            usage = Some(Usage {
+                prompt_tokens: value["promptTokenCount"].as_u64().unwrap_or(0),
+                completion_tokens: value["candidatesTokenCount"].as_u64().unwrap_or(0),
+            });
+        }
+        let Some(candidate) = chunk["candidates"].get(0) else {
+            continue;
+        };
+        for part in candidate["content"]["parts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            if let Some(text) = part["text"].as_str().filter(|t| !t.is_empty()) {
+                content.push_str(text);
+                sink(text);
+            }
+            // Gemini 的函数调用没有 id, 按顺序生成
+            if let Some(call) = part.get("functionCall") {
+                tool_calls.push(ToolCall {
+                    id: format!("call_{}", tool_calls.len()),
+                    function: FunctionCall {
+                        name: call["name"].as_str().unwrap_or_default().to_string(),
+                        arguments: call
+                            .get("args")
+                            .map_or_else(|| "{}".to_string(), Value::to_string),
+                    },
+                    ..Default::default()
+                });
+            }
+        }
+        if let Some(reason) = candidate["finishReason"].as_str() {
+            finish = Some(finish_reason(reason));
+        }
+    }
+    if !tool_calls.is_empty() {
+        finish = Some("tool_calls".to_string());
+    }
+
+    Ok(ChatResponse {
+        request_id: String::new(),
+        message: ChatMessage {
+            role: "assistant".to_string(),
+            content,
+            tool_calls,
+            tool_call_id: None,
+        },
+        finish_reason: finish,
+        usage,
+    })
+}
+
+/// Gemini 的批量向量化, 接口不返回用量
+pub(super) async fn embed(
+    config: &ProviderConfig,
+    key: &str,
+    model: &str,
+    input: &[String],
+) -> Result<(Vec<Vec<f32>>, Usage)> {
+    let url = format!(
+        "{}/v1beta/models/{}:batchEmbedContents",
+        config.base_url, model
+    );
+    let requests: Vec<Value> = input
+        .iter()
+        .map(|text| {
+            json!({
+                "model": format!("models/{}", model),
+                "content": { "parts": [{ "text": text }] },
+            })
+        })
+        .collect();
+    let body = json!({ "requests": requests });
+    let response = send(|| {
+        client()
+            .post(&url)
+            .header("x-goog-api-key", key)
+            .json(&body)
+    })
+    .await?;
+    let value: Value = response
+        .json()
+        .await
+        .map_err(|e| PluginError::Plugin(format!("解析向量失败: {}", e)))?;
+    let vectors = value["embeddings"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|item| serde_json::from_value(item["values"].clone()).unwrap_or_default())
+        .collect();
+    Ok((vectors, Usage::default()))
+}
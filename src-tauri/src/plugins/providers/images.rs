@@ -0,0 +1,413 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::{client, send, ProviderConfig};
+use crate::plugins::artifacts::Artifact;
+use crate::plugins::deno::{PluginError, Result};
+use crate::utils::gen::generate_id;
+
+const DEFAULT_SIZE: &str = "1024x1024";
+const DEFAULT_STEPS: u32 = 20;
+const MAX_COUNT: u32 = 8;
+// ComfyUI 任务的轮询间隔与最长等待时间
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const COMFYUI_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// 图片生成接口
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageBackend {
+    /// OpenAI Images 及兼容接口
+    Openai,
+    /// Stable Diffusion WebUI (A1111) 的 /sdapi/v1/txt2img
+    A1111,
+    /// ComfyUI 的 /prompt 工作流接口
+    Comfyui,
+}
+
+/// 图片生成参数
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ImageOptions {
+    /// 为空时按模型服务类型选择, OpenAI 兼容服务使用 openai
+    pub backend: Option<ImageBackend>,
+    /// OpenAI 的模型名, 或 Stable Diffusion 的 checkpoint 文件名
+    pub model: Option<String>,
+    /// 宽x高, 默认 1024x1024
+    pub size: Option<String>,
+    /// 生成的张数, 默认 1
+    pub count: Option<u32>,
+    pub negative_prompt: Option<String>,
+    pub steps: Option<u32>,
+    pub seed: Option<i64>,
+    /// OpenAI 的图片质量, 如 standard、hd
+    pub quality: Option<String>,
+    /// ComfyUI API 格式的工作流, 字符串 {{prompt}}、{{negative_prompt}}、{{seed}}、{{width}}、{{height}}、{{steps}} 会被替换
+    pub workflow: Option<Value>,
+}
+
+/// 生成的图片, 保存在产物目录中
+#[derive(Debug, Serialize, Clone)]
+pub struct GeneratedImage {
+    #[serde(flatten)]
+    pub artifact: Artifact,
+    /// 模型改写后的提示词
+    pub revised_prompt: Option<String>,
+    pub seed: Option<i64>,
+}
+
+/// 图片生成结果
+#[derive(Debug, Serialize, Clone)]
+pub struct ImageResult {
+    /// 产物目录的 id, 可用 artifacts_clear 删除
+    pub id: String,
+    pub provider: String,
+    pub backend: ImageBackend,
+    pub model: Option<String>,
+    pub prompt: String,
+    pub images: Vec<GeneratedImage>,
+}
+
+// 接口返回的原始图片
+struct RawImage {
+    data: Vec<u8>,
+    revised_prompt: Option<String>,
+    seed: Option<i64>,
+}
+
+fn image_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("图片生成失败: {}", message))
+}
+
+fn parse_size(size: &str) -> Result<(u32, u32)> {
+    size.split_once(['x', 'X', '*'])
+        .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)))
+        .filter(|(w, h)| *w > 0 && *h > 0)
+        .ok_or_else(|| image_error(format!("无效的图片尺寸: {}", size)))
+}
+
+fn decode_base64(data: &str) -> Result<Vec<u8>> {
+    let data = data.rsplit(',').next().unwrap_or_default();
+    STANDARD
+        .decode(data.trim())
+        .map_err(|e| image_error(format!("无效的图片数据: {}", e)))
+}
+
+async fn download(url: &str) -> Result<Vec<u8>> {
+    let response = send(|| client().get(url)).await?;
+    let bytes = response.bytes().await.map_err(image_error)?;
+    Ok(bytes.to_vec())
+}
+
+async fn openai(
+    config: &ProviderConfig,
+    key: &str,
+    prompt: &str,
+    options: &ImageOptions,
+    count: u32,
+) -> Result<Vec<RawImage>> {
+    let url = format!("{}/images/generations", config.base_url);
+    let model = options.model.as_deref().unwrap_or("dall-e-3");
+    let mut body = json!({
+        "model": model,
+        "prompt": prompt,
+        "n": count,
+        "size": options.size.as_deref().unwrap_or(DEFAULT_SIZE),
+    });
+    if let Some(quality) = &options.quality {
+        body["quality"] = json!(quality);
+    }
+    // gpt-image 系列只返回 base64, 不接受 response_format
+    if model.starts_with("dall-e") {
+        body["response_format"] = json!("b64_json");
+    }
+    let response = send(|| {
+        let builder = client().post(&url).json(&body);
+        if key.is_empty() {
+            builder
+        } else {
+            builder.bearer_auth(key)
+        }
+    })
+    .await?;
+    let value: Value = response.json().await.map_err(image_error)?;
+    let mut images = Vec::new();
+    for item in value["data"].as_array().into_iter().flatten() {
+        let data = match (item["b64_json"].as_str(), item["url"].as_str()) {
+            (Some(data), _) => decode_base64(data)?,
+            (None, Some(url)) => download(url).await?,
+            (None, None) => continue,
+        };
+        images.push(RawImage {
+            data,
+            revised_prompt: item["revised_prompt"].as_str().map(str::to_string),
+            seed: None,
+        });
+    }
+    Ok(images)
+}
+
+async fn a1111(
+    config: &ProviderConfig,
+    key: &str,
+    prompt: &str,
+    options: &ImageOptions,
+    count: u32,
+) -> Result<Vec<RawImage>> {
+    let url = format!("{}/sdapi/v1/txt2img", config.base_url.trim_end_matches('/'));
+    let (width, height) = parse_size(options.size.as_deref().unwrap_or(DEFAULT_SIZE))?;
+    let mut body = json!({
+        "prompt": prompt,
+        "negative_prompt": options.negative_prompt.as_deref().unwrap_or_default(),
+        "steps": options.steps.unwrap_or(DEFAULT_STEPS),
+        "width": width,
+        "height": height,
+        "seed": options.seed.unwrap_or(-1),
+        "batch_size": count,
+    });
+    if let Some(model) = &options.model {
+        body["override_settings"] = json!({ "sd_model_checkpoint": model });
+    }
+    let response = send(|| {
+        let builder = client().post(&url).json(&body);
+        if key.is_empty() {
+            builder
+        } else {
+            builder.bearer_auth(key)
+        }
+    })
+    .await?;
+    let value: Value = response.json().await.map_err(image_error)?;
+    // info 是 JSON 字符串, 其中 all_seeds 为每张图片的种子
+    let seeds: Vec<i64> = value["info"]
+        .as_str()
+        .and_then(|info| serde_json::from_str::<Value>(info).ok())
+        .and_then(|info| serde_json::from_value(info["all_seeds"].clone()).ok())
+        .unwrap_or_default();
+    value["images"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .enumerate()
+        .map(|(i, data)| {
+            Ok(RawImage {
+                data: decode_base64(data)?,
+                revised_prompt: None,
+                seed: seeds.get(i).copied(),
+            })
+        })
+        .collect()
+}
+
+// 未提供工作流时使用的文生图流程
+fn default_workflow(model: &str, count: u32) -> Value {
+    json!({
+        "1": {
+            "class_type": "CheckpointLoaderSimple",
+            "inputs": { "ckpt_name": model }
+        },
+        "2": {
+            "class_type": "CLIPTextEncode",
+            "inputs": { "text": "{{prompt}}", "clip": ["1", 1] }
+        },
+        "3": {
+            "class_type": "CLIPTextEncode",
+            "inputs": { "text": "{{negative_prompt}}", "clip": ["1", 1] }
+        },
+        "4": {
+            "class_type": "EmptyLatentImage",
+            "inputs": { "width": "{{width}}", "height": "{{height}}", "batch_size": count }
+        },
+        "5": {
+            "class_type": "KSampler",
+            "inputs": {
+                "model": ["1", 0],
+                "positive": ["2", 0],
+                "negative": ["3", 0],
+                "latent_image": ["4", 0],
+                "seed": "{{seed}}",
+                "steps": "{{steps}}",
+                "cfg": 7,
+                "sampler_name": "euler",
+                "scheduler": "normal",
+                "denoise": 1
+            }
+        },
+        "6": {
+            "class_type": "VAEDecode",
+            "inputs": { "samples": ["5", 0], "vae": ["1", 2] }
+        },
+        "7": {
+            "class_type": "SaveImage",
+            "inputs": { "images": ["6", 0], "filename_prefix": "ghostie" }
+        }
+    })
+}
+
+// 将工作流中的占位字符串替换为实际参数
+fn fill_workflow(value: &mut Value, params: &[(&str, Value)]) {
+    match value {
+        Value::String(text) => {
+            if let Some((_, param)) = params
+                .iter()
+                .find(|(name, _)| text.as_str() == format!("{{{{{}}}}}", name))
+            {
+                *value = param.clone();
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| fill_workflow(v, params)),
+        Value::Object(map) => map.values_mut().for_each(|v| fill_workflow(v, params)),
+        _ => {}
+    }
+}
+
+async fn comfyui(
+    config: &ProviderConfig,
+    prompt: &str,
+    options: &ImageOptions,
+    count: u32,
+) -> Result<Vec<RawImage>> {
+    let base = config.base_url.trim_end_matches('/');
+    let (width, height) = parse_size(options.size.as_deref().unwrap_or(DEFAULT_SIZE))?;
+    // ComfyUI 不接受负数种子, 未指定时随机生成
+    let seed = options
+        .seed
+        .filter(|s| *s >= 0)
+        .unwrap_or_else(|| i64::from(rand::random::<u32>()));
+    let mut workflow = match &options.workflow {
+        Some(workflow) => workflow.clone(),
+        None => {
+            let model = options
+                .model
+                .as_deref()
+                .ok_or_else(|| image_error("ComfyUI 需要指定 checkpoint 模型或工作流"))?;
+            default_workflow(model, count)
+        }
+    };
+    fill_workflow(
+        &mut workflow,
+        &[
+            ("prompt", json!(prompt)),
+            (
+                "negative_prompt",
+                json!(options.negative_prompt.as_deref().unwrap_or_default()),
+            ),
+            ("seed", json!(seed)),
+            ("width", json!(width)),
+            ("height", json!(height)),
+            ("steps", json!(options.steps.unwrap_or(DEFAULT_STEPS))),
+        ],
+    );
+
+    let url = format!("{}/prompt", base);
+    let body = json!({ "prompt": workflow, "client_id": generate_id() });
+    let response = send(|| client().post(&url).json(&body)).await?;
+    let value: Value = response.json().await.map_err(image_error)?;
+    let prompt_id = value["prompt_id"]
+        .as_str()
+        .ok_or_else(|| image_error(format!("ComfyUI 未返回任务 id: {}", value)))?
+        .to_string();
+
+    // 任务完成后才会出现在 history 中
+    let history_url = format!("{}/history/{}", base, prompt_id);
+    let start = Instant::now();
+    let outputs = loop {
+        if start.elapsed() > COMFYUI_TIMEOUT {
+            return Err(image_error("等待 ComfyUI 超时"));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let response = send(|| client().get(&history_url)).await?;
+        let history: Value = response.json().await.map_err(image_error)?;
+        let entry = &history[&prompt_id];
+        if entry.is_null() {
+            continue;
+        }
+        if entry["status"]["status_str"].as_str() == Some("error") {
+            return Err(image_error("ComfyUI 执行工作流出错"));
+        }
+        break entry["outputs"].clone();
+    };
+
+    let mut images = Vec::new();
+    for output in outputs.as_object().into_iter().flat_map(|o| o.values()) {
+        for image in output["images"].as_array().into_iter().flatten() {
+            // temp 为预览图, 只取保存的结果
+            if image["type"].as_str() == Some("temp") {
+                continue;
+            }
+            let view = format!("{}/view", base);
+            let query = [
+                ("filename", image["filename"].as_str().unwrap_or_default()),
+                ("subfolder", image["subfolder"].as_str().unwrap_or_default()),
+                ("type", image["type"].as_str().unwrap_or("output")),
+            ];
+            let response = send(|| client().get(&view).query(&query)).await?;
+            let bytes = response.bytes().await.map_err(image_error)?;
+            images.push(RawImage {
+                data: bytes.to_vec(),
+                revised_prompt: None,
+                seed: Some(seed),
+            });
+        }
+    }
+    Ok(images)
+}
+
+// 按文件头判断扩展名, 无法识别时使用 png
+fn extension(data: &[u8]) -> &'static str {
+    if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        "jpg"
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        "webp"
+    } else {
+        "png"
+    }
+}
+
+fn save(dir: &Path, index: usize, raw: RawImage) -> Result<GeneratedImage> {
+    let ext = extension(&raw.data);
+    let name = format!("image-{}.{}", index + 1, ext);
+    let path = dir.join(&name);
+    fs::write(&path, &raw.data)?;
+    Ok(GeneratedImage {
+        artifact: Artifact {
+            name,
+            path: path.to_string_lossy().to_string(),
+            mime: mime_guess::from_ext(ext)
+                .first_or_octet_stream()
+                .to_string(),
+            size: raw.data.len() as u64,
+        },
+        revised_prompt: raw.revised_prompt,
+        seed: raw.seed,
+    })
+}
+
+/// 调用图片接口并将结果保存到 dir
+pub(super) async fn generate(
+    config: &ProviderConfig,
+    key: &str,
+    backend: ImageBackend,
+    prompt: &str,
+    options: &ImageOptions,
+    dir: &Path,
+) -> Result<Vec<GeneratedImage>> {
+    let count = options.count.unwrap_or(1).clamp(1, MAX_COUNT);
+    let raw = match backend {
+        ImageBackend::Openai => openai(config, key, prompt, options, count).await?,
+        ImageBackend::A1111 => a1111(config, key, prompt, options, count).await?,
+        ImageBackend::Comfyui => comfyui(config, prompt, options, count).await?,
+    };
+    if raw.is_empty() {
+        return Err(image_error("接口没有返回图片"));
+    }
+    raw.into_iter()
+        .enumerate()
+        .map(|(i, image)| save(dir, i, image))
+        .collect()
+}
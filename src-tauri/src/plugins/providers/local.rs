@@ -0,0 +1,364 @@
+use serde::{Deserialize, Serialize};
+
+use super::{ChatRequest, ChatResponse, Sink, Usage};
+use crate::plugins::deno::{PluginError, Result};
+
+/// 本地模型在对话请求中使用的服务 id
+pub const PROVIDER_ID: &str = "local";
+
+/// 加载参数, 未设置的项使用 llama.cpp 的默认值
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LocalModelParams {
+    /// 上下文长度
+    #[serde(default)]
+    pub n_ctx: Option<u32>,
+    /// 放到 GPU 上的层数
+    #[serde(default)]
+    pub n_gpu_layers: Option<u32>,
+    /// 推理线程数
+    #[serde(default)]
+    pub threads: Option<i32>,
+}
+
+/// 已加载的本地模型
+#[derive(Debug, Serialize, Clone)]
+pub struct LocalModelInfo {
+    pub path: String,
+    /// 文件名, 作为对话请求中的模型名
+    pub name: String,
+    pub params: LocalModelParams,
+}
+
+#[cfg(feature = "local-llm")]
+mod engine {
+    use llama_cpp_2::context::params::LlamaContextParams;
+    use llama_cpp_2::llama_backend::LlamaBackend;
+    use llama_cpp_2::llama_batch::LlamaBatch;
+    use llama_cpp_2::model::params::LlamaModelParams;
+    use llama_cpp_2::model::{AddBos, LlamaChatMessage, LlamaModel, Special};
+    use llama_cpp_2::sampling::LlamaSampler;
+    use once_cell::sync::{Lazy, OnceCell};
+    use std::num::NonZeroU32;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::mpsc;
+
+    use super::LocalModelInfo;
+    use crate::plugins::deno::{PluginError, Result};
+    use crate::plugins::providers::{ChatMessage, ChatRequest, ChatResponse, Sink, Usage};
+
+    // 未指定时的最大输出长度
+    const DEFAULT_MAX_TOKENS: u32 = 1024;
+    const BATCH_SIZE: usize = 512;
+
+    struct Loaded {
+        info: LocalModelInfo,
+        model: LlamaModel,
+    }
+
+    static BACKEND: OnceCell<LlamaBackend> = OnceCell::new();
+    static MODEL: Lazy<Mutex<Option<Arc<Loaded>>>> = Lazy::new(|| Mutex::new(None));
+
+    fn llama_error(err: impl std::fmt::Display) -> PluginError {
+        PluginError::Plugin(format!("本地模型出错: {}", err))
+    }
+
+    fn backend() -> Result<&'static LlamaBackend> {
+        BACKEND.get_or_try_init(|| LlamaBackend::init().map_err(llama_error))
+    }
+
+    pub fn load(info: LocalModelInfo) -> Result<()> {
+        let mut params = LlamaModelParams::default();
+        if let Some(layers) = info.params.n_gpu_layers {
+            params = params.with_n_gpu_layers(layers);
+        }
+        let model =
+            LlamaModel::load_from_file(backend()?, &info.path, &params).map_err(llama_error)?;
+        *MODEL.lock().unwrap() = Some(Arc::new(Loaded { info, model }));
+        Ok(())
+    }
+
+    pub fn unload() -> bool {
+        MODEL.lock().unwrap().take().is_some()
+    }
+
+    pub fn current() -> Option<LocalModelInfo> {
+        MODEL.lock().unwrap().as_ref().map(|m| m.info.clone())
+    }
+
+    // 使用模型自带的对话模板, 没有时按角色逐行拼接
+    fn prompt(model: &LlamaModel, messages: &[ChatMessage]) -> String {
+        let templated = model.get_chat_template().ok().and_then(|template| {
+            let messages = messages
+                .iter()
+                .map(|m| LlamaChatMessage::new(m.role.clone(), m.content.clone()))
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .ok()?;
+            model.apply_chat_template(&template, &messages, true).ok()
+        });
+        templated.unwrap_or_else(|| {
+            let mut prompt: String = messages
+                .iter()
+                .map(|m| format!("{}: {}\n", m.role, m.content))
+                .collect();
+            prompt.push_str("assistant: ");
+            prompt
+        })
+    }
+
+    // 在阻塞线程中生成, 接收端关闭后停止
+    fn generate(
+        loaded: &Loaded,
+        request: &ChatRequest,
+        tx: mpsc::Sender<String>,
+    ) -> Result<(Usage, String)> {
+        let model = &loaded.model;
+        let params = &loaded.info.params;
+        let mut context_params =
+            LlamaContextParams::default().with_n_ctx(params.n_ctx.and_then(NonZeroU32::new));
+        if let Some(threads) = params.threads {
+            context_params = context_params
+                .with_n_threads(threads)
+                .with_n_threads_batch(threads);
+        }
+        let mut ctx = model
+            .new_context(backend()?, context_params)
+            .map_err(llama_error)?;
+
+        let tokens = model
+            .str_to_token(&prompt(model, &request.messages), AddBos::Always)
+            .map_err(llama_error)?;
+        let n_ctx = ctx.n_ctx() as usize;
+        if tokens.len() >= n_ctx {
+            return Err(PluginError::Plugin(format!(
+                "输入过长: {} 个 token, 上下文长度为 {}",
+                tokens.len(),
+                n_ctx
+            )));
+        }
+        let mut batch = LlamaBatch::new(BATCH_SIZE.max(tokens.len()), 1);
+        let last = tokens.len() as i32 - 1;
+        for (position, token) in (0_i32..).zip(tokens.iter()) {
+            batch
+                .add(*token, position, &[0], position == last)
+                .map_err(llama_error)?;
+        }
+        ctx.decode(&mut batch).map_err(llama_error)?;
+
+        let mut sampler = LlamaSampler::chain_simple([
+            LlamaSampler::temp(request.temperature.unwrap_or(0.8)),
+            LlamaSampler::dist(rand::random()),
+        ]);
+        let max_tokens = request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS) as usize;
+        let mut position = batch.n_tokens();
+        let mut generated = 0;
+        // 单个 token 可能只是多字节字符的一部分
+        let mut pending: Vec<u8> = Vec::new();
+        let mut finish = "length";
+        while generated < max_tokens && (position as usize) < n_ctx {
+            let token = sampler.sample(&ctx, batch.n_tokens() - 1);
+            sampler.accept(token);
+            if model.is_eog_token(token) {
+                finish = "stop";
+                break;
+            }
+            generated += 1;
+            pending.extend(
+                model
+                    .token_to_bytes(token, Special::Tokenize)
+                    .map_err(llama_error)?,
+            );
+            let valid = match std::str::from_utf8(&pending) {
+                Ok(text) => text.len(),
+                Err(err) if err.error_len().is_none() => err.valid_up_to(),
+                Err(_) => pending.len(),
+            };
+            if valid > 0 {
+                let text = String::from_utf8_lossy(&pending[..valid]).into_owned();
+                pending.drain(..valid);
+                if tx.blocking_send(text).is_err() {
+                    finish = "cancelled";
+                    break;
+                }
+            }
+            batch.clear();
+            batch
+                .add(token, position, &[0], true)
+                .map_err(llama_error)?;
+            position += 1;
+            ctx.decode(&mut batch).map_err(llama_error)?;
+        }
+        let usage = Usage {
+            prompt_tokens: tokens.len() as u64,
+            completion_tokens: generated as u64,
+        };
+        Ok((usage, finish.to_string()))
+    }
+
+    // 逐条计算向量, 每条之后清空缓存
+    fn embed_blocking(loaded: &Loaded, input: &[String]) -> Result<(Vec<Vec<f32>>, Usage)> {
+        let model = &loaded.model;
+        let params = &loaded.info.params;
+        let mut context_params = LlamaContextParams::default()
+            .with_n_ctx(params.n_ctx.and_then(NonZeroU32::new))
+            .with_embeddings(true);
+        if let Some(threads) = params.threads {
+            context_params = context_params
+                .with_n_threads(threads)
+                .with_n_threads_batch(threads);
+        }
+        let mut ctx = model
+            .new_context(backend()?, context_params)
+            .map_err(llama_error)?;
+        let mut vectors = Vec::with_capacity(input.len());
+        let mut prompt_tokens = 0;
+        for text in input {
+            let tokens = model
+                .str_to_token(text, AddBos::Always)
+                .map_err(llama_error)?;
+            let tokens = &tokens[..tokens.len().min(ctx.n_ctx() as usize)];
+            prompt_tokens += tokens.len() as u64;
+            let mut batch = LlamaBatch::new(tokens.len().max(1), 1);
+            for (position, token) in (0_i32..).zip(tokens.iter()) {
+                batch
+                    .add(*token, position, &[0], true)
+                    .map_err(llama_error)?;
+            }
+            ctx.clear_kv_cache();
+            ctx.decode(&mut batch).map_err(llama_error)?;
+            vectors.push(ctx.embeddings_seq_ith(0).map_err(llama_error)?.to_vec());
+        }
+        let usage = Usage {
+            prompt_tokens,
+            completion_tokens: 0,
+        };
+        Ok((vectors, usage))
+    }
+
+    pub async fn embed(input: &[String]) -> Result<(Vec<Vec<f32>>, Usage)> {
+        let loaded = MODEL
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| PluginError::Plugin("未加载本地模型".to_string()))?;
+        let input = input.to_vec();
+        tokio::task::spawn_blocking(move || embed_blocking(&loaded, &input))
+            .await
+            .map_err(|e| PluginError::Plugin(format!("本地模型线程异常: {}", e)))?
+    }
+
+    pub async fn chat(request: &ChatRequest, sink: &Sink) -> Result<ChatResponse> {
+        let loaded = MODEL
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| PluginError::Plugin("未加载本地模型".to_string()))?;
+        if !request.tools.is_empty() {
+            tracing::warn!("本地模型不支持工具调用, 已忽略工具定义");
+        }
+        let (tx, mut rx) = mpsc::channel(64);
+        let request = request.clone();
+        let task = tokio::task::spawn_blocking(move || generate(&loaded, &request, tx));
+
+        let mut content = String::new();
+        while let Some(text) = rx.recv().await {
+            sink(&text);
+            content.push_str(&text);
+        }
+        let (usage, finish) = task
+            .await
+            .map_err(|e| PluginError::Plugin(format!("本地模型线程异常: {}", e)))??;
+        Ok(ChatResponse {
+            request_id: String::new(),
+            message: ChatMessage {
+                role: "assistant".to_string(),
+                content,
+                tool_calls: Vec::new(),
+                tool_call_id: None,
+            },
+            finish_reason: Some(finish),
+            usage: Some(usage),
+        })
+    }
+}
+
+#[cfg(not(feature = "local-llm"))]
+mod engine {
+    use super::LocalModelInfo;
+    use crate::plugins::deno::{PluginError, Result};
+    use crate::plugins::providers::{ChatRequest, ChatResponse, Sink, Usage};
+
+    fn disabled() -> PluginError {
+        PluginError::Plugin("当前版本未启用本地模型, 请使用 local-llm 特性构建".to_string())
+    }
+
+    pub fn load(_info: LocalModelInfo) -> Result<()> {
+        Err(disabled())
+    }
+
+    pub fn unload() -> bool {
+        false
+    }
+
+    pub fn current() -> Option<LocalModelInfo> {
+        None
+    }
+
+    pub async fn chat(_request: &ChatRequest, _sink: &Sink) -> Result<ChatResponse> {
+        Err(disabled())
+    }
+
+    pub async fn embed(_input: &[String]) -> Result<(Vec<Vec<f32>>, Usage)> {
+        Err(disabled())
+    }
+}
+
+/// 使用已加载的本地模型对话
+pub(super) async fn chat(request: &ChatRequest, sink: &Sink) -> Result<ChatResponse> {
+    engine::chat(request, sink).await
+}
+
+/// 使用已加载的本地模型计算向量
+pub(super) async fn embed(input: &[String]) -> Result<(Vec<Vec<f32>>, Usage)> {
+    engine::embed(input).await
+}
+
+/// 加载 GGUF 模型, 替换已加载的模型
+///
+/// 加载完成后以服务 id `local` 调用 chat_stream
+#[tauri::command]
+pub async fn local_model_load(
+    path: String,
+    params: Option<LocalModelParams>,
+) -> Result<LocalModelInfo> {
+    let file = std::path::Path::new(&path);
+    if !file.is_file() {
+        return Err(PluginError::Plugin(format!("模型文件不存在: {}", path)));
+    }
+    let info = LocalModelInfo {
+        name: file
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path,
+        params: params.unwrap_or_default(),
+    };
+    let loaded = info.clone();
+    // 加载大模型耗时较长, 放到阻塞线程中
+    tokio::task::spawn_blocking(move || engine::load(loaded))
+        .await
+        .map_err(|e| PluginError::Plugin(format!("加载本地模型失败: {}", e)))??;
+    tracing::info!(path = %info.path, "本地模型已加载");
+    Ok(info)
+}
+
+/// 卸载本地模型并释放内存, 返回之前是否已加载
+#[tauri::command]
+pub async fn local_model_unload() -> Result<bool> {
+    Ok(engine::unload())
+}
+
+/// 当前加载的本地模型
+#[tauri::command]
+pub async fn local_model_current() -> Result<Option<LocalModelInfo>> {
+    Ok(engine::current())
+}
@@ -0,0 +1,667 @@
+mod anthropic;
+mod gemini;
+mod images;
+pub mod local;
+mod ollama;
+mod openai;
+pub mod tokens;
+
+pub use images::{GeneratedImage, ImageBackend, ImageOptions, ImageResult};
+pub use ollama::OllamaModel;
+
+use once_cell::sync::Lazy;
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+use super::artifacts;
+use super::db::with_db;
+use super::deno::{PluginError, Result};
+use super::proxy;
+use super::secrets;
+use super::usage;
+use crate::utils::gen::generate_id;
+
+// 请求失败时的最大尝试次数, 仅重试连接错误、429 与 5xx
+const MAX_ATTEMPTS: u32 = 3;
+// 重试等待的上限
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+// 两次收到数据的最长间隔, 超过后视为超时
+const IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+// API Key 在密钥链中的变量名
+const API_KEY: &str = "api_key";
+
+/// 模型服务的接口类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// OpenAI 及兼容 OpenAI 接口的服务
+    Openai,
+    Anthropic,
+    Gemini,
+    /// 本地运行的 Ollama
+    Ollama,
+}
+
+impl ProviderKind {
+    fn default_base_url(self) -> &'static str {
+        match self {
+            ProviderKind::Openai => "https://api.openai.com/v1",
+            ProviderKind::Anthropic => "https://api.anthropic.com",
+            ProviderKind::Gemini => "https://generativelanguage.googleapis.com",
+            ProviderKind::Ollama => ollama::DEFAULT_BASE_URL,
+        }
+    }
+}
+
+/// 模型服务配置, API Key 保存在系统密钥链中
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProviderConfig {
+    /// 新建时留空
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub kind: ProviderKind,
+    pub base_url: String,
+    /// 常用的模型
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 是否已设置 API Key, 只在读取时返回
+    #[serde(default, skip_deserializing)]
+    pub has_key: bool,
+}
+
+/// 模型请求的工具调用, 与 OpenAI 的格式一致
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type", default = "function_kind")]
+    pub kind: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FunctionCall {
+    pub name: String,
+    /// JSON 文本
+    pub arguments: String,
+}
+
+fn function_kind() -> String {
+    "function".to_string()
+}
+
+impl Default for ToolCall {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            kind: function_kind(),
+            function: FunctionCall::default(),
+        }
+    }
+}
+
+/// 对话消息, 各服务的请求与响应都转换为这一格式
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatMessage {
+    /// system、user、assistant 或 tool
+    pub role: String,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// tool 消息对应的调用 id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+/// 对话请求
+#[derive(Debug, Deserialize, Clone)]
+pub struct ChatRequest {
+    /// 模型服务 id
+    pub provider: String,
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    /// OpenAI tools 格式的工具定义
+    #[serde(default)]
+    pub tools: Vec<Value>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// 用于取消请求与匹配流式事件, 为空时自动生成
+    #[serde(default)]
+    pub request_id: Option<String>,
+    /// 发起请求的助手, 用于用量统计
+    #[serde(default)]
+    pub bot: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// 对话结果
+#[derive(Debug, Serialize, Clone)]
+pub struct ChatResponse {
+    pub request_id: String,
+    pub message: ChatMessage,
+    /// stop、length 或 tool_calls
+    pub finish_reason: Option<String>,
+    pub usage: Option<Usage>,
+}
+
+/// 流式输出的一段文本, 以 chat://delta 事件发送
+#[derive(Debug, Serialize, Clone)]
+pub struct ChatDelta {
+    pub request_id: String,
+    pub content: String,
+}
+
+/// 接收流式输出的文本
+pub(crate) type Sink = Arc<dyn Fn(&str) + Send + Sync>;
+
+// 进行中的请求, 发送信号后取消
+static CANCELS: Lazy<Mutex<HashMap<String, oneshot::Sender<()>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static CLIENT: Lazy<RwLock<reqwest::Client>> = Lazy::new(|| RwLock::new(build_client()));
+
+fn build_client() -> reqwest::Client {
+    proxy::apply(reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT))
+        .build()
+        .unwrap_or_default()
+}
+
+pub(crate) fn client() -> reqwest::Client {
+    CLIENT.read().unwrap().clone()
+}
+
+/// 代理设置修改后重建客户端
+pub(crate) fn reset_client() {
+    *CLIENT.write().unwrap() = build_client();
+}
+
+// 从错误响应中取出说明, 无法解析时返回原文
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|value| {
+            value["error"]["message"]
+                .as_str()
+                .or_else(|| value["error"].as_str())
+                .or_else(|| value["message"].as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| body.chars().take(500).collect())
+}
+
+/// 发送请求, 连接失败、429 与 5xx 时按 Retry-After 或指数退避重试
+pub(crate) async fn send(build: impl Fn() -> reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let retry_after = match build().send().await {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => {
+                let status = response.status();
+                let retry_after = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.parse().ok())
+                    .map(Duration::from_secs);
+                let body = response.text().await.unwrap_or_default();
+                let retryable =
+                    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+                if !retryable || attempt >= MAX_ATTEMPTS {
+                    return Err(PluginError::Plugin(format!(
+                        "模型服务返回 {}: {}",
+                        status,
+                        error_message(&body)
+                    )));
+                }
+                retry_after
+            }
+            Err(err) if (err.is_connect() || err.is_timeout()) && attempt < MAX_ATTEMPTS => None,
+            Err(err) => {
+                return Err(PluginError::Plugin(format!("请求模型服务失败: {}", err)));
+            }
+        };
+        let delay = retry_after
+            .unwrap_or_else(|| Duration::from_secs(1 << (attempt - 1)))
+            .min(MAX_BACKOFF);
+        tracing::warn!(
+            attempt,
+            delay_ms = delay.as_millis() as u64,
+            "模型请求失败, 稍后重试"
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// 一个 SSE 事件
+pub(crate) struct SseEvent {
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// 逐段读取流式响应, 按字节缓冲以免拆开多字节字符
+pub(crate) struct StreamReader {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl StreamReader {
+    pub fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+            done: false,
+        }
+    }
+
+    fn parse(block: &[u8]) -> SseEvent {
+        let block = String::from_utf8_lossy(block);
+        let mut event = None;
+        let mut data = Vec::new();
+        for line in block.lines() {
+            if let Some(value) = line.strip_prefix("event:") {
+                event = Some(value.trim().to_string());
+            } else if let Some(value) = line.strip_prefix("data:") {
+                data.push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+        }
+        SseEvent {
+            event,
+            data: data.join("\n"),
+        }
+    }
+
+    // 读取到下一个分隔符, 响应结束时返回剩余内容
+    async fn next_block(&mut self, delimiter: &[u8]) -> Result<Option<Vec<u8>>> {
+        loop {
+            if let Some(index) = self
+                .buffer
+                .windows(delimiter.len())
+                .position(|w| w == delimiter)
+            {
+                let mut block: Vec<u8> = self.buffer.drain(..index + delimiter.len()).collect();
+                block.truncate(index);
+                return Ok(Some(block));
+            }
+            if self.done {
+                if self.buffer.iter().all(u8::is_ascii_whitespace) {
+                    return Ok(None);
+                }
+                return Ok(Some(std::mem::take(&mut self.buffer)));
+            }
+            match tokio::time::timeout(IDLE_TIMEOUT, self.response.chunk()).await {
+                Err(_) => return Err(PluginError::Plugin("模型响应超时".to_string())),
+                Ok(Err(err)) => {
+                    return Err(PluginError::Plugin(format!("读取模型响应失败: {}", err)));
+                }
+                Ok(Ok(None)) => self.done = true,
+                Ok(Ok(Some(chunk))) => self
+                    .buffer
+                    .extend(chunk.iter().copied().filter(|b| *b != b'\r')),
+            }
+        }
+    }
+
+    /// 下一个 SSE 事件
+    pub async fn next_event(&mut self) -> Result<Option<SseEvent>> {
+        while let Some(block) = self.next_block(b"\n\n").await? {
+            let event = Self::parse(&block);
+            if !event.data.is_empty() || event.event.is_some() {
+                return Ok(Some(event));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 下一个非空行, 用于逐行输出 JSON 的接口
+    pub async fn next_line(&mut self) -> Result<Option<String>> {
+        while let Some(line) = self.next_block(b"\n").await? {
+            let line = String::from_utf8_lossy(&line).trim().to_string();
+            if !line.is_empty() {
+                return Ok(Some(line));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// 由 OpenAI tools 格式取出函数定义: 名称、描述与参数 schema
+pub(crate) fn functions(tools: &[Value]) -> impl Iterator<Item = (&str, &str, Value)> {
+    tools.iter().filter_map(|tool| {
+        let function = tool.get("function")?;
+        Some((
+            function["name"].as_str()?,
+            function["description"].as_str().unwrap_or_default(),
+            function
+                .get("parameters")
+                .cloned()
+                .unwrap_or_else(|| serde_json::json!({ "type": "object", "properties": {} })),
+        ))
+    })
+}
+
+/// 解析工具调用参数, 无法解析时为空对象
+pub(crate) fn parse_arguments(arguments: &str) -> Value {
+    serde_json::from_str(arguments).unwrap_or_else(|_| serde_json::json!({}))
+}
+
+// 密钥链中的作用域, 与插件变量区分
+fn key_scope(id: &str) -> String {
+    format!("providers/{}", id)
+}
+
+/// 读取模型服务的 API Key, 未设置时返回空字符串
+pub(crate) fn api_key(id: &str) -> Result<String> {
+    secrets::get(Some(&key_scope(id)), API_KEY)
+}
+
+fn read_provider(data: String) -> Result<ProviderConfig> {
+    let mut config: ProviderConfig = serde_json::from_str(&data)?;
+    config.has_key = !secrets::get(Some(&key_scope(&config.id)), API_KEY)?.is_empty();
+    Ok(config)
+}
+
+pub(crate) fn find_provider(id: &str) -> Result<ProviderConfig> {
+    let data: Option<String> = with_db(|conn| {
+        Ok(conn
+            .query_row("SELECT data FROM providers WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    })?;
+    let data = data.ok_or_else(|| PluginError::Plugin(format!("模型服务不存在: {}", id)))?;
+    read_provider(data)
+}
+
+/// 按服务类型调用模型, 流式文本交给 sink
+pub(crate) async fn chat(request: &ChatRequest, sink: Sink) -> Result<ChatResponse> {
+    let start = Instant::now();
+    let (provider, result) = if request.provider == local::PROVIDER_ID {
+        (
+            local::PROVIDER_ID.to_string(),
+            local::chat(request, &sink).await,
+        )
+    } else {
+        let config = find_provider(&request.provider)?;
+        let key = secrets::get(Some(&key_scope(&config.id)), API_KEY)?;
+        let result = match config.kind {
+            ProviderKind::Openai => openai::chat(&config, &key, request, &sink).await,
+            ProviderKind::Anthropic => anthropic::chat(&config, &key, request, &sink).await,
+            ProviderKind::Gemini => gemini::chat(&config, &key, request, &sink).await,
+            ProviderKind::Ollama => ollama::chat(&config, request, &sink).await,
+        };
+        (config.name, result)
+    };
+    let duration_ms = start.elapsed().as_millis() as u64;
+    match &result {
+        Ok(response) => {
+            let usage = response.usage.clone().unwrap_or_default();
+            tracing::info!(
+                provider = %provider,
+                model = %request.model,
+                duration_ms,
+                prompt_tokens = usage.prompt_tokens,
+                completion_tokens = usage.completion_tokens,
+                "模型请求完成"
+            );
+            if let Err(err) = usage::record(
+                &request.provider,
+                &request.model,
+                request.bot.as_deref(),
+                &usage,
+                duration_ms,
+            ) {
+                tracing::warn!(error = %err, "记录模型用量失败");
+            }
+        }
+        Err(err) => {
+            tracing::warn!(
+                provider = %provider,
+                model = %request.model,
+                duration_ms,
+                error = %err,
+                "模型请求失败"
+            );
+        }
+    }
+    result
+}
+
+/// 计算文本的向量, 结果与输入一一对应
+pub(crate) async fn embed(provider: &str, model: &str, input: &[String]) -> Result<Vec<Vec<f32>>> {
+    let start = Instant::now();
+    let (vectors, usage) = if provider == local::PROVIDER_ID {
+        local::embed(input).await?
+    } else {
+        let config = find_provider(provider)?;
+        let key = secrets::get(Some(&key_scope(&config.id)), API_KEY)?;
+        match config.kind {
+            ProviderKind::Openai => openai::embed(&config, &key, model, input).await?,
+            ProviderKind::Gemini => gemini::embed(&config, &key, model, input).await?,
+            ProviderKind::Ollama => ollama::embed(&config, model, input).await?,
+            ProviderKind::Anthropic => {
+                return Err(PluginError::Plugin(format!(
+                    "模型服务 {} 不支持向量化",
+                    config.name
+                )))
+            }
+        }
+    };
+    if vectors.len() != input.len() {
+        return Err(PluginError::Plugin(format!(
+            "向量数量与输入不一致: {} / {}",
+            vectors.len(),
+            input.len()
+        )));
+    }
+    let duration_ms = start.elapsed().as_millis() as u64;
+    if let Err(err) = usage::record(provider, model, None, &usage, duration_ms) {
+        tracing::warn!(error = %err, "记录模型用量失败");
+    }
+    Ok(vectors)
+}
+
+/// 语音合成的参数
+#[derive(Debug, Clone)]
+pub(crate) struct SpeechRequest<'a> {
+    pub model: &'a str,
+    pub voice: &'a str,
+    pub text: &'a str,
+    /// mp3 或 wav
+    pub format: &'a str,
+    pub speed: f32,
+}
+
+/// 合成语音, 返回音频内容
+pub(crate) async fn speech(provider: &str, request: &SpeechRequest<'_>) -> Result<Vec<u8>> {
+    let config = find_provider(provider)?;
+    let key = secrets::get(Some(&key_scope(&config.id)), API_KEY)?;
+    match config.kind {
+        ProviderKind::Openai => openai::speech(&config, &key, request).await,
+        _ => Err(PluginError::Plugin(format!(
+            "模型服务 {} 不支持语音合成",
+            config.name
+        ))),
+    }
+}
+
+/// 生成图片, 结果保存到产物目录
+///
+/// # 参数
+/// * `provider` - 模型服务 id, A1111 与 ComfyUI 使用其地址与密钥
+/// * `options` - 未指定 backend 时, OpenAI 兼容服务使用 OpenAI Images 接口
+#[tauri::command]
+pub async fn image_generate(
+    prompt: String,
+    provider: String,
+    options: Option<ImageOptions>,
+) -> Result<ImageResult> {
+    if prompt.trim().is_empty() {
+        return Err(PluginError::Plugin("提示词不能为空".to_string()));
+    }
+    let options = options.unwrap_or_default();
+    let config = find_provider(&provider)?;
+    let backend = match (options.backend, config.kind) {
+        (Some(backend), _) => backend,
+        (None, ProviderKind::Openai) => ImageBackend::Openai,
+        (None, _) => {
+            return Err(PluginError::Plugin(format!(
+                "模型服务 {} 不支持图片生成",
+                config.name
+            )))
+        }
+    };
+    let key = secrets::get(Some(&key_scope(&config.id)), API_KEY)?;
+    let id = generate_id();
+    let dir = artifacts::create_dir(&id)?;
+    let start = Instant::now();
+    let result = images::generate(&config, &key, backend, &prompt, &options, &dir).await;
+    let duration_ms = start.elapsed().as_millis() as u64;
+    let images = match result {
+        Ok(images) => images,
+        Err(err) => {
+            let _ = std::fs::remove_dir_all(&dir);
+            tracing::warn!(provider = %config.name, duration_ms, error = %err, "图片生成失败");
+            return Err(err);
+        }
+    };
+    tracing::info!(
+        provider = %config.name,
+        duration_ms,
+        images = images.len(),
+        "图片生成完成"
+    );
+    Ok(ImageResult {
+        id,
+        provider,
+        backend,
+        model: options.model,
+        prompt,
+        images,
+    })
+}
+
+#[tauri::command]
+pub async fn provider_list() -> Result<Vec<ProviderConfig>> {
+    let rows: Vec<String> = with_db(|conn| {
+        let mut stmt = conn.prepare("SELECT data FROM providers")?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })?;
+    let mut providers = rows
+        .into_iter()
+        .map(read_provider)
+        .collect::<Result<Vec<_>>>()?;
+    providers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(providers)
+}
+
+/// 新建或修改模型服务, api_key 为空时保留原有的 Key, 为空字符串时删除
+#[tauri::command]
+pub async fn provider_save(
+    mut config: ProviderConfig,
+    api_key: Option<String>,
+) -> Result<ProviderConfig> {
+    config.name = config.name.trim().to_string();
+    config.base_url = config.base_url.trim().trim_end_matches('/').to_string();
+    if config.base_url.is_empty() {
+        config.base_url = config.kind.default_base_url().to_string();
+    }
+    if config.name.is_empty() {
+        return Err(PluginError::Plugin("模型服务名称不能为空".to_string()));
+    }
+    url::Url::parse(&config.base_url)
+        .map_err(|e| PluginError::Plugin(format!("无效的服务地址: {}", e)))?;
+    if config.id.is_empty() {
+        config.id = generate_id();
+    }
+    let data = serde_json::to_string(&config)?;
+    with_db(|conn| {
+        conn.execute(
+            "INSERT INTO providers (id, data) VALUES (?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET data = excluded.data",
+            [&config.id, &data],
+        )?;
+        Ok(())
+    })?;
+    match api_key.as_deref().map(str::trim) {
+        Some("") => secrets::delete(Some(&key_scope(&config.id)), API_KEY)?,
+        Some(key) => secrets::set(Some(&key_scope(&config.id)), API_KEY, key)?,
+        None => {}
+    }
+    find_provider(&config.id)
+}
+
+#[tauri::command]
+pub async fn provider_delete(id: String) -> Result<()> {
+    secrets::delete(Some(&key_scope(&id)), API_KEY)?;
+    with_db(|conn| {
+        conn.execute("DELETE FROM providers WHERE id = ?1", [&id])?;
+        Ok(())
+    })
+}
+
+/// 流式对话, 文本以 chat://delta 事件发送, 返回完整的回复与工具调用
+#[tauri::command]
+pub async fn chat_stream(app: AppHandle, request: ChatRequest) -> Result<ChatResponse> {
+    let request_id = request.request_id.clone().unwrap_or_else(generate_id);
+    let (cancel, cancelled) = oneshot::channel();
+    CANCELS.lock().unwrap().insert(request_id.clone(), cancel);
+    let sink: Sink = {
+        let request_id = request_id.clone();
+        Arc::new(move |content: &str| {
+            let _ = app.emit(
+                "chat://delta",
+                ChatDelta {
+                    request_id: request_id.clone(),
+                    content: content.to_string(),
+                },
+            );
+        })
+    };
+    let result = tokio::select! {
+        result = chat(&request, sink) => result,
+        _ = cancelled => Err(PluginError::Plugin("请求已取消".to_string())),
+    };
+    CANCELS.lock().unwrap().remove(&request_id);
+    let mut response = result?;
+    response.request_id = request_id;
+    Ok(response)
+}
+
+/// 列出 Ollama 已下载的模型, 未指定服务时使用本机默认地址
+#[tauri::command]
+pub async fn ollama_models(provider: Option<String>) -> Result<Vec<OllamaModel>> {
+    let base_url = match provider {
+        Some(id) => find_provider(&id)?.base_url,
+        None => ollama::DEFAULT_BASE_URL.to_string(),
+    };
+    ollama::models(&base_url).await
+}
+
+/// 取消进行中的对话请求
+#[tauri::command]
+pub async fn chat_cancel(request_id: String) -> Result<()> {
+    if let Some(cancel) = CANCELS.lock().unwrap().remove(&request_id) {
+        let _ = cancel.send(());
+    }
+    Ok(())
+}
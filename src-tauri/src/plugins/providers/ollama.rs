@@ -0,0 +1,173 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use super::{
+    client, parse_arguments, send, ChatMessage, ChatRequest, ChatResponse, FunctionCall,
+    ProviderConfig, Sink, StreamReader, ToolCall, Usage,
+};
+use crate::plugins::deno::{PluginError, Result};
+
+pub(super) const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+/// Ollama 本地已下载的模型
+#[derive(Debug, Serialize, Clone)]
+pub struct OllamaModel {
+    pub name: String,
+    /// 文件大小, 单位为字节
+    pub size: u64,
+    pub modified_at: String,
+    /// 参数规模, 如 8B
+    pub parameter_size: Option<String>,
+}
+
+// Ollama 的工具调用参数为对象而非 JSON 文本
+fn convert(messages: &[ChatMessage]) -> Vec<Value> {
+    messages
+        .iter()
+        .map(|message| {
+            let mut value = json!({ "role": message.role, "content": message.content });
+            if !message.tool_calls.is_empty() {
+                value["tool_calls"] = message
+                    .tool_calls
+                    .iter()
+                    .map(|call| {
+                        json!({
+                            "function": {
+                                "name": call.function.name,
+                                "arguments": parse_arguments(&call.function.arguments),
+                            },
+                        })
+                    })
+                    .collect();
+            }
+            value
+        })
+        .collect()
+}
+
+/// Ollama chat 接口的流式对话, 响应为逐行 JSON
+pub(super) async fn chat(
+    config: &ProviderConfig,
+    request: &ChatRequest,
+    sink: &Sink,
+) -> Result<ChatResponse> {
+    let url = format!("{}/api/chat", config.base_url);
+    let mut body = json!({
+        "model": request.model,
+        "messages": convert(&request.messages),
+        "stream": true,
+    });
+    if !request.tools.is_empty() {
+        body["tools"] = json!(request.tools);
+    }
+    let mut options = json!({});
+    if let Some(temperature) = request.temperature {
+        options["temperature"] = json!(temperature);
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        options["num_predict"] = json!(max_tokens);
+    }
+    body["options"] = options;
+    let response = send(|| client().post(&url).json(&body)).await?;
+
+    let mut lines = StreamReader::new(response);
+    let mut content = String::new();
+    let mut tool_calls: Vec<ToolCall> = Vec::new();
+    let mut finish = None;
+    let mut usage = None;
+    while let Some(line) = lines.next_line().await? {
+        let chunk: Value = serde_json::from_str(&line)?;
+        if let Some(error) = chunk["error"].as_str() {
+            return Err(PluginError::Plugin(format!("模型服务错误: {}", error)));
+        }
+        let message = &chunk["message"];
+        if let Some(text) = message["content"].as_str().filter(|t| !t.is_empty()) {
+            content.push_str(text);
+            sink(text);
+        }
+        for call in message["tool_calls"].as_array().into_iter().flatten() {
+            let function = &call["function"];
+            tool_calls.push(ToolCall {
+                id: format!("call_{}", tool_calls.len()),
+                function: FunctionCall {
+                    name: function["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: function
+                        .get("arguments")
+                        .map_or_else(|| "{}".to_string(), Value::to_string),
+                },
+                ..Default::default()
+            });
+        }
+        if chunk["done"].as_bool() == Some(true) {
+            finish = chunk["done_reason"].as_str().map(str::to_string);
+            usage = Some(Usage {
+                prompt_tokens: chunk["prompt_eval_count"].as_u64().unwrap_or(0),
+                completion_tokens: chunk["eval_count"].as_u64().unwrap_or(0),
+            });
+            break;
+        }
+    }
+    if !tool_calls.is_empty() {
+        finish = Some("tool_calls".to_string());
+    }
+
+    Ok(ChatResponse {
+        request_id: String::new(),
+        message: ChatMessage {
+            role: "assistant".to_string(),
+            content,
+            tool_calls,
+            tool_call_id: None,
+        },
+        finish_reason: finish,
+        usage,
+    })
+}
+
+/// 读取 Ollama 已下载的模型
+pub(super) async fn models(base_url: &str) -> Result<Vec<OllamaModel>> {
+    let url = format!("{}/api/tags", base_url.trim_end_matches('/'));
+    let response = send(|| client().get(&url)).await?;
+    let value: Value = response
+        .json()
+        .await
+        .map_err(|e| PluginError::Plugin(format!("读取模型列表失败: {}", e)))?;
+    Ok(value["models"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|model| OllamaModel {
+            name: model["name"].as_str().unwrap_or_default().to_string(),
+            size: model["size"].as_u64().unwrap_or(0),
+            modified_at: model["modified_at"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            parameter_size: model["details"]["parameter_size"]
+                .as_str()
+                .map(str::to_string),
+        })
+        .collect())
+}
+
+/// Ollama embed 接口的向量化
+pub(super) async fn embed(
+    config: &ProviderConfig,
+    model: &str,
+    input: &[String],
+) -> Result<(Vec<Vec<f32>>, Usage)> {
+    let url = format!("{}/api/embed", config.base_url);
+    let body = json!({ "model": model, "input": input });
+    let response = send(|| client().post(&url).json(&body)).await?;
+    let value: Value = response
+        .json()
+        .await
+        .map_err(|e| PluginError::Plugin(format!("解析向量失败: {}", e)))?;
+    let vectors = serde_json::from_value(value["embeddings"].clone())
+        .map_err(|e| PluginError::Plugin(format!("解析向量失败: {}", e)))?;
+    let usage = Usage {
+        prompt_tokens: value["prompt_eval_count"].as_u64().unwrap_or(0),
+        completion_tokens: 0,
+    };
+    Ok((vectors, usage))
+}
@@ -0,0 +1,175 @@
+use serde_json::{json, Value};
+
+use super::{
+    client, send, ChatMessage, ChatRequest, ChatResponse, ProviderConfig, Sink, SpeechRequest,
+    StreamReader, ToolCall, Usage,
+};
+use crate::plugins::deno::{PluginError, Result};
+
+/// OpenAI 兼容接口的流式对话
+pub(super) async fn chat(
+    config: &ProviderConfig,
+    key: &str,
+    request: &ChatRequest,
+    sink: &Sink,
+) -> Result<ChatResponse> {
+    let url = format!("{}/chat/completions", config.base_url);
+    let mut body = json!({
+        "model": request.model,
+        "messages": request.messages,
+        "stream": true,
+        "stream_options": { "include_usage": true },
+    });
+    if !request.tools.is_empty() {
+        body["tools"] = json!(request.tools);
+    }
+    if let Some(temperature) = request.temperature {
+        body["temperature"] = json!(temperature);
+    }
+    if let Some(max_tokens) = request.max_tokens {
+        body["max_tokens"] = json!(max_tokens);
+    }
+    let response = send(|| {
+        let builder = client().post(&url).json(&body);
+        // 本地服务通常不需要 Key
+        if key.is_empty() {
+            builder
+        } else {
+            builder.bearer_auth(key)
+        }
+    })
+    .await?;
+
+    let mut events = StreamReader::new(response);
+    let mut content = String::new();
+    let mut tool_calls: Vec<ToolCall> = Vec::new();
+    let mut finish_reason = None;
+    let mut usage = None;
+    while let Some(event) = events.next_event().await? {
+        if event.data == "[DONE]" {
+            break;
+        }
+        let chunk: Value = serde_json::from_str(&event.data)?;
+        if let Some(error) = chunk.get("error") {
+            return Err(PluginError::Plugin(format!(
+                "模型服务错误: {}",
+                error["message"].as_str().unwrap_or_default()
+            )));
+        }
+        if let Some(value) = chunk.get("usage").filter(|u| !u.is_null()) {
+            usage = Some(Usage {
+                prompt_tokens: value["prompt_tokens"].as_u64().unwrap_or(0),
+                completion_tokens: value["completion_tokens"].as_u64().unwrap_or(0),
+            });
+        }
+        let Some(choice) = chunk["choices"].get(0) else {
+            continue;
+        };
+        let delta = &choice["delta"];
+        if let Some(text) = delta["content"].as_str().filter(|t| !t.is_empty()) {
+            content.push_str(text);
+            sink(text);
+        }
+        // 工具调用按 index 分片返回, 逐段拼接
+        for call in delta["tool_calls"].as_array().into_iter().flatten() {
+            let index = call["index"].as_u64().unwrap_or(0) as usize;
+            while tool_calls.len() <= index {
+                tool_calls.push(ToolCall::default());
+            }
+            let entry = &mut tool_calls[index];
+            if let Some(id) = call["id"].as_str() {
+                entry.id = id.to_string();
+            }
+            if let Some(name) = call["function"]["name"].as_str() {
+                entry.function.name.push_str(name);
+            }
+            if let Some(arguments) = call["function"]["arguments"].as_str() {
+                entry.function.arguments.push_str(arguments);
+            }
+        }
+        if let Some(reason) = choice["finish_reason"].as_str() {
+            finish_reason = Some(reason.to_string());
+        }
+    }
+
+    Ok(ChatResponse {
+        request_id: String::new(),
+        message: ChatMessage {
+            role: "assistant".to_string(),
+            content,
+            tool_calls,
+            tool_call_id: None,
+        },
+        finish_reason,
+        usage,
+    })
+}
+
+/// OpenAI 兼容接口的向量化, 结果按输入顺序返回
+pub(super) async fn embed(
+    config: &ProviderConfig,
+    key: &str,
+    model: &str,
+    input: &[String],
+) -> Result<(Vec<Vec<f32>>, Usage)> {
+    let url = format!("{}/embeddings", config.base_url);
+    let body = json!({ "model": model, "input": input });
+    let response = send(|| {
+        let builder = client().post(&url).json(&body);
+        if key.is_empty() {
+            builder
+        } else {
+            builder.bearer_auth(key)
+        }
+    })
+    .await?;
+    let value: Value = response
+        .json()
+        .await
+        .map_err(|e| PluginError::Plugin(format!("解析向量失败: {}", e)))?;
+    let mut items: Vec<(u64, Vec<f32>)> = value["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|item| {
+            let vector = serde_json::from_value(item["embedding"].clone()).unwrap_or_default();
+            (item["index"].as_u64().unwrap_or(0), vector)
+        })
+        .collect();
+    items.sort_by_key(|(index, _)| *index);
+    let usage = Usage {
+        prompt_tokens: value["usage"]["prompt_tokens"].as_u64().unwrap_or(0),
+        completion_tokens: 0,
+    };
+    Ok((items.into_iter().map(|(_, v)| v).collect(), usage))
+}
+
+/// OpenAI 兼容接口的语音合成, 返回音频内容
+pub(super) async fn speech(
+    config: &ProviderConfig,
+    key: &str,
+    request: &SpeechRequest<'_>,
+) -> Result<Vec<u8>> {
+    let url = format!("{}/audio/speech", config.base_url);
+    let body = json!({
+        "model": request.model,
+        "input": request.text,
+        "voice": request.voice,
+        "response_format": request.format,
+        "speed": request.speed,
+    });
+    let response = send(|| {
+        let builder = client().post(&url).json(&body);
+        if key.is_empty() {
+            builder
+        } else {
+            builder.bearer_auth(key)
+        }
+    })
+    .await?;
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| PluginError::Plugin(format!("读取语音失败: {}", e)))?;
+    Ok(bytes.to_vec())
+}
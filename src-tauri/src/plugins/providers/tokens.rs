@@ -0,0 +1,181 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+use tiktoken_rs::CoreBPE;
+
+use super::ChatMessage;
+use crate::plugins::deno::Result;
+
+// 每条消息的格式开销, 与 OpenAI 的计算方式一致
+const TOKENS_PER_MESSAGE: usize = 3;
+// 回复开头的固定开销
+const TOKENS_PER_REPLY: usize = 3;
+
+// 已加载的分词器, 首次使用时加载
+static ENCODERS: Lazy<Mutex<HashMap<Tokenizer, Arc<CoreBPE>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 计数结果
+#[derive(Debug, Serialize, Clone)]
+pub struct TokenCount {
+    pub tokens: usize,
+    /// 使用的分词器, 估算时为 estimate
+    pub tokenizer: String,
+    /// 是否为估算值
+    pub approximate: bool,
+}
+
+/// 裁剪后的消息
+#[derive(Debug, Serialize, Clone)]
+pub struct TrimResult {
+    pub messages: Vec<ChatMessage>,
+    pub tokens: usize,
+    /// 被移除的消息数
+    pub dropped: usize,
+}
+
+// 模型对应的分词器, 只识别 OpenAI 的模型
+fn encoder(model: &str) -> Option<(Tokenizer, Arc<CoreBPE>)> {
+    let tokenizer = get_tokenizer(model)?;
+    let mut encoders = ENCODERS.lock().unwrap();
+    if let Some(bpe) = encoders.get(&tokenizer) {
+        return Some((tokenizer, bpe.clone()));
+    }
+    let bpe = match tokenizer {
+        Tokenizer::O200kBase => tiktoken_rs::o200k_base(),
+        Tokenizer::Cl100kBase => tiktoken_rs::cl100k_base(),
+        Tokenizer::P50kBase => tiktoken_rs::p50k_base(),
+        Tokenizer::P50kEdit => tiktoken_rs::p50k_edit(),
+        Tokenizer::R50kBase | Tokenizer::Gpt2 => tiktoken_rs::r50k_base(),
+    }
+    .map_err(|e| tracing::warn!(error = %e, "加载分词器失败"))
+    .ok()?;
+    let bpe = Arc::new(bpe);
+    encoders.insert(tokenizer, bpe.clone());
+    Some((tokenizer, bpe))
+}
+
+// 估算: 中日韩字符约每字一个 token, 其余约每 4 个字符一个 token
+fn estimate(text: &str) -> usize {
+    let (wide, narrow) = text.chars().fold((0, 0), |(wide, narrow), c| {
+        if c.len_utf8() >= 3 {
+            (wide + 1, narrow)
+        } else {
+            (wide, narrow + 1)
+        }
+    });
+    wide + narrow.div_ceil(4)
+}
+
+/// 文本计数器, 未知模型使用估算
+pub(crate) struct Counter {
+    tokenizer: Option<(Tokenizer, Arc<CoreBPE>)>,
+}
+
+impl Counter {
+    pub fn new(model: &str) -> Self {
+        Self {
+            tokenizer: encoder(model),
+        }
+    }
+
+    pub fn text(&self, text: &str) -> usize {
+        match &self.tokenizer {
+            Some((_, bpe)) => bpe.encode_with_special_tokens(text).len(),
+            None => estimate(text),
+        }
+    }
+
+    /// 单条消息, 包含格式开销与工具调用
+    pub fn message(&self, message: &ChatMessage) -> usize {
+        let mut tokens =
+            TOKENS_PER_MESSAGE + self.text(&message.role) + self.text(&message.content);
+        for call in &message.tool_calls {
+            tokens += self.text(&call.function.name) + self.text(&call.function.arguments);
+        }
+        if let Some(id) = &message.tool_call_id {
+            tokens += self.text(id);
+        }
+        tokens
+    }
+
+    pub fn messages(&self, messages: &[ChatMessage]) -> usize {
+        messages.iter().map(|m| self.message(m)).sum::<usize>() + TOKENS_PER_REPLY
+    }
+
+    fn name(&self) -> String {
+        match &self.tokenizer {
+            Some((tokenizer, _)) => format!("{:?}", tokenizer).to_lowercase(),
+            None => "estimate".to_string(),
+        }
+    }
+}
+
+/// 裁剪消息使其不超过 budget 个 token
+///
+/// 保留全部 system 消息, 从最早的对话开始移除, 不会以孤立的 tool 消息开头
+pub(crate) fn trim(model: &str, messages: Vec<ChatMessage>, budget: usize) -> TrimResult {
+    let counter = Counter::new(model);
+    let total = messages.len();
+    let (system, rest): (Vec<ChatMessage>, Vec<ChatMessage>) =
+        messages.into_iter().partition(|m| m.role == "system");
+    let mut tokens = counter.messages(&system);
+
+    // 从最新的消息向前保留
+    let mut start = rest.len();
+    for (index, message) in rest.iter().enumerate().rev() {
+        let cost = counter.message(message);
+        // 至少保留最后一条消息
+        if tokens + cost > budget && start < rest.len() {
+            break;
+        }
+        tokens += cost;
+        start = index;
+    }
+    // tool 消息必须跟在对应的工具调用之后
+    while start < rest.len() && rest[start].role == "tool" {
+        tokens -= counter.message(&rest[start]);
+        start += 1;
+    }
+
+    let mut kept = system;
+    kept.extend(rest.into_iter().skip(start));
+    TrimResult {
+        dropped: total - kept.len(),
+        messages: kept,
+        tokens,
+    }
+}
+
+/// 计算消息的 token 数
+///
+/// # 参数
+/// * `model` - 模型名, 用于选择分词器, 无法识别时按字符估算
+/// * `messages` - 对话消息
+#[tauri::command]
+pub async fn tokens_count(model: String, messages: Vec<ChatMessage>) -> Result<TokenCount> {
+    let counter = Counter::new(&model);
+    Ok(TokenCount {
+        tokens: counter.messages(&messages),
+        tokenizer: counter.name(),
+        approximate: counter.tokenizer.is_none(),
+    })
+}
+
+/// 裁剪对话历史以适应上下文长度
+///
+/// # 参数
+/// * `max_tokens` - 上下文长度
+/// * `reserve` - 为回复预留的 token 数
+#[tauri::command]
+pub async fn tokens_trim(
+    model: String,
+    messages: Vec<ChatMessage>,
+    max_tokens: usize,
+    reserve: Option<usize>,
+) -> Result<TrimResult> {
+    let budget = max_tokens.saturating_sub(reserve.unwrap_or(0));
+    Ok(trim(&model, messages, budget))
+}
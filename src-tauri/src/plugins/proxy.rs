@@ -0,0 +1,218 @@
+use reqwest::{ClientBuilder, NoProxy, Proxy};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use url::Url;
+
+use super::deno::{find_plugin, PluginError, Result};
+use super::grants::PermissionGrant;
+use super::{providers, secrets};
+use crate::utils::settings::{self, ProxyKind, ProxySettings};
+
+// 代理密码在系统密钥链中的账户
+const SCOPE: &str = "proxy";
+const PASSWORD: &str = "password";
+// 本机地址始终直连, 本地模型服务与应用内 HTTP 服务不经过代理
+const LOCAL_HOSTS: &[&str] = &["localhost", "127.0.0.1", "::1"];
+const TEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+fn proxy_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("代理设置无效: {}", message))
+}
+
+/// 代理设置与当前设置的校验结果
+#[derive(Debug, Serialize)]
+pub struct ProxyInfo {
+    #[serde(flatten)]
+    pub settings: ProxySettings,
+    /// 设置无效或无法读取密码时的错误, 此时请求不经过代理
+    pub error: Option<String>,
+}
+
+// 代理地址, 用户名与密码经过编码; password 为 None 时不附带认证信息; 未启用时返回 None
+fn proxy_url(config: &ProxySettings, password: Option<&str>) -> Result<Option<String>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+    let host = config.host.trim();
+    if host.is_empty() || config.port == 0 {
+        return Err(proxy_error("需要填写代理地址与端口"));
+    }
+    // socks5h 由代理服务器解析域名, 内网域名也能访问
+    let scheme = match config.kind {
+        ProxyKind::Http => "http",
+        ProxyKind::Https => "https",
+        ProxyKind::Socks5 => "socks5h",
+    };
+    let auth = match (
+        config.username.as_deref().filter(|u| !u.is_empty()),
+        password,
+    ) {
+        (Some(username), Some("")) => format!("{}@", urlencoding::encode(username)),
+        (Some(username), Some(password)) => format!(
+            "{}:{}@",
+            urlencoding::encode(username),
+            urlencoding::encode(password)
+        ),
+        _ => String::new(),
+    };
+    let url = format!("{}://{}{}:{}", scheme, auth, host, config.port);
+    Url::parse(&url).map_err(proxy_error)?;
+    Ok(Some(url))
+}
+
+// 不经过代理的地址, 逗号分隔, 与 NO_PROXY 环境变量格式相同
+fn bypass_list(config: &ProxySettings) -> String {
+    LOCAL_HOSTS
+        .iter()
+        .map(|host| host.to_string())
+        .chain(
+            config
+                .bypass
+                .iter()
+                .map(|host| host.trim().to_string())
+                .filter(|host| !host.is_empty()),
+        )
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// 代理地址与不经过代理的地址, credentials 为 false 时地址中不含用户名与密码
+fn current(credentials: bool) -> Result<Option<(String, String)>> {
+    let config = settings::get().proxy;
+    let password = match credentials {
+        true => Some(secrets::get(Some(SCOPE), PASSWORD)?),
+        false => None,
+    };
+    let url = proxy_url(&config, password.as_deref())?;
+    Ok(url.map(|url| (url, bypass_list(&config))))
+}
+
+// 设置无效时记录警告并按未启用处理, 错误通过 proxy_settings 返回给界面
+fn active(credentials: bool) -> Option<(String, String)> {
+    current(credentials).unwrap_or_else(|err| {
+        tracing::warn!(error = %err, "代理设置无效, 请求不经过代理");
+        None
+    })
+}
+
+/// 代理密码, 用于从日志与调用记录中替换
+pub(crate) fn password() -> Option<String> {
+    secrets::get(Some(SCOPE), PASSWORD)
+        .ok()
+        .filter(|password| !password.is_empty())
+}
+
+/// 为客户端配置代理, 未启用时保持 reqwest 默认行为 (读取系统环境变量中的代理)
+pub(crate) fn apply(builder: ClientBuilder) -> ClientBuilder {
+    let Some((url, bypass)) = active(true) else {
+        return builder;
+    };
+    match Proxy::all(&url) {
+        Ok(proxy) => builder.proxy(proxy.no_proxy(NoProxy::from_string(&bypass))),
+        Err(err) => {
+            tracing::warn!(error = %err, "代理地址无效, 请求不经过代理");
+            builder
+        }
+    }
+}
+
+/// 按代理设置新建客户端, 用于插件仓库、网址导入等不常用的请求
+pub(crate) fn client() -> reqwest::Client {
+    apply(reqwest::Client::builder())
+        .build()
+        .unwrap_or_default()
+}
+
+// 用户允许访问全部网络或读取全部环境变量的插件才能拿到代理的用户名与密码
+fn trusted(grants: &[PermissionGrant]) -> bool {
+    grants
+        .iter()
+        .any(|g| g.allowed && g.target.is_none() && (g.kind == "net" || g.kind == "env"))
+}
+
+/// 注入插件进程的代理环境变量, 同时提供大小写两种写法
+///
+/// 插件进程可以读取这些变量, 未获得 net 或 env 权限的插件只注入不含认证信息的代理地址
+pub(crate) fn env_vars(grants: &[PermissionGrant]) -> Vec<(String, String)> {
+    let Some((url, bypass)) = active(trusted(grants)) else {
+        return Vec::new();
+    };
+    [
+        ("HTTP_PROXY", url.as_str()),
+        ("HTTPS_PROXY", url.as_str()),
+        ("NO_PROXY", bypass.as_str()),
+    ]
+    .into_iter()
+    .flat_map(|(key, value)| {
+        [
+            (key.to_string(), value.to_string()),
+            (key.to_lowercase(), value.to_string()),
+        ]
+    })
+    .collect()
+}
+
+/// 按插件已获得的权限生成代理环境变量, 尚未安装的插件按未授权处理
+pub(crate) async fn env_vars_for(id: &str) -> Vec<(String, String)> {
+    let grants = find_plugin(id)
+        .await
+        .map(|plugin| plugin.grants)
+        .unwrap_or_default();
+    env_vars(&grants)
+}
+
+/// 读取代理设置, 设置无效时在 error 中给出原因
+#[tauri::command]
+pub async fn proxy_settings() -> Result<ProxyInfo> {
+    Ok(ProxyInfo {
+        settings: settings::get().proxy,
+        error: current(true).err().map(|err| err.to_string()),
+    })
+}
+
+/// 修改代理设置, 保存后新的请求与插件执行立即使用
+///
+/// # 参数
+/// * `password` - 代理密码, 不传时保留, 为空字符串时删除
+#[tauri::command]
+pub async fn proxy_configure(
+    config: ProxySettings,
+    password: Option<String>,
+) -> Result<ProxySettings> {
+    let stored = match password.as_deref() {
+        Some(value) => value.to_string(),
+        None => secrets::get(Some(SCOPE), PASSWORD)?,
+    };
+    proxy_url(&config, Some(&stored))?;
+    match password.as_deref() {
+        Some("") => secrets::delete(Some(SCOPE), PASSWORD)?,
+        Some(value) => secrets::set(Some(SCOPE), PASSWORD, value)?,
+        None => {}
+    }
+    let updated = settings::update(|s| s.proxy = config)?;
+    providers::reset_client();
+    Ok(updated.proxy)
+}
+
+/// 通过当前代理访问地址, 返回状态码与耗时 (毫秒)
+#[tauri::command]
+pub async fn proxy_test(url: String) -> Result<(u16, u64)> {
+    let url = Url::parse(url.trim()).map_err(|e| proxy_error(format!("无效的地址: {}", e)))?;
+    // 设置无效时直接报错, 不退回直连
+    let mut builder = reqwest::Client::builder().timeout(TEST_TIMEOUT);
+    if let Some((proxy, bypass)) = current(true)? {
+        let proxy = Proxy::all(&proxy).map_err(proxy_error)?;
+        builder = builder.proxy(proxy.no_proxy(NoProxy::from_string(&bypass)));
+    }
+    let client = builder.build().map_err(proxy_error)?;
+    let started = Instant::now();
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| PluginError::Plugin(format!("连接失败: {}", e)))?;
+    Ok((
+        response.status().as_u16(),
+        started.elapsed().as_millis() as u64,
+    ))
+}
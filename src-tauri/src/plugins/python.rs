@@ -0,0 +1,220 @@
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::RwLock;
+
+use super::artifacts;
+use super::deno::{
+    data_dir, parse_plugin_info, plugins_dir, EnvVar, Plugin, PluginError, PluginRuntime, Result,
+    DATA_DIR_ENV,
+};
+use super::env;
+use super::logs;
+use super::proxy;
+use super::reload;
+use super::runtime::{run_limited, RunOutput};
+
+// Python 插件约定: 模块级变量 `plugin` 为字典
+// {"name": ..., "description": ..., "tools": {"工具名": {"description": ..., "parameters": ..., "handler": fn}}}
+// handler 接收参数字典, 可以是普通函数或 async 函数.
+const BOOTSTRAP: &str = r#"
+import asyncio, importlib.util, inspect, json, sys
+
+_stdout = sys.stdout
+sys.stdout = sys.stderr
+
+spec = importlib.util.spec_from_file_location("echo_plugin", sys.argv[1])
+module = importlib.util.module_from_spec(spec)
+spec.loader.exec_module(module)
+plugin = getattr(module, "plugin")
+
+if sys.argv[2] == "describe":
+    tools = []
+    for name, tool in plugin.get("tools", {}).items():
+        item = {"name": name, "description": tool.get("description", "")}
+        if tool.get("parameters"):
+            item["parameters"] = tool["parameters"]
+        if tool.get("rate_limit"):
+            item["rate_limit"] = tool["rate_limit"]
+        if tool.get("retry"):
+            item["retry"] = tool["retry"]
+        if tool.get("skip_validation"):
+            item["skip_validation"] = True
+        if tool.get("cache"):
+            item["cache"] = tool["cache"]
+        if tool.get("dangerous"):
+            item["dangerous"] = True
+        tools.append(item)
+    result = {
+        "name": plugin.get("name", "undefined"),
+        "description": plugin.get("description", ""),
+        "version": plugin.get("version"),
+        "changelog": plugin.get("changelog"),
+        "tags": plugin.get("tags", []),
+        "category": plugin.get("category"),
+        "tests": plugin.get("tests", []),
+        "env": plugin.get("env", []),
+        "tools": tools,
+    }
+else:
+    tool = plugin.get("tools", {}).get(sys.argv[3])
+    if tool is None:
+        raise Exception("未知函数: " + sys.argv[3])
+    result = tool["handler"](json.loads(sys.stdin.read() or "{}"))
+    if inspect.iscoroutine(result):
+        result = asyncio.run(result)
+
+_stdout.write(json.dumps(result, ensure_ascii=False))
+_stdout.flush()
+"#;
+
+// Python 解释器
+#[derive(Clone)]
+enum Interpreter {
+    // uv 会读取脚本中的 PEP 723 依赖声明
+    Uv,
+    Python(String),
+}
+
+static PYTHON: Lazy<RwLock<Option<Interpreter>>> = Lazy::new(|| RwLock::new(detect()));
+
+fn detect() -> Option<Interpreter> {
+    let available = |program: &str| {
+        Command::new(program)
+            .arg("--version")
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    };
+    if available("uv") {
+        return Some(Interpreter::Uv);
+    }
+    ["python3", "python"]
+        .into_iter()
+        .find(|p| available(p))
+        .map(|p| Interpreter::Python(p.to_string()))
+}
+
+/// 重新检测 Python 解释器
+pub(crate) fn refresh() {
+    *PYTHON.write().unwrap() = detect();
+}
+
+pub(crate) fn source_path(id: &str) -> PathBuf {
+    plugins_dir().join(format!("{}.py", id))
+}
+
+/// 判断脚本内容是否为 Python
+pub(crate) fn is_python(content: &str) -> bool {
+    if content.contains("export default") {
+        return false;
+    }
+    content.lines().map(str::trim_start).any(|line| {
+        line.starts_with("def ")
+            || line.starts_with("async def ")
+            || (line.starts_with("from ") && line.contains(" import "))
+    })
+}
+
+// 提取 PEP 723 内联依赖声明, 写入引导脚本以便 uv 安装依赖
+fn script_metadata(content: &str) -> String {
+    let mut block = Vec::new();
+    let mut inside = false;
+    for line in content.lines() {
+        if line.trim_end() == "# /// script" {
+            inside = true;
+        }
+        if inside {
+            block.push(line);
+            if line.trim_end() == "# ///" && block.len() > 1 {
+                break;
+            }
+        }
+    }
+    if block.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", block.join("\n"))
+    }
+}
+
+async fn run(
+    id: &str,
+    plugin_file: &Path,
+    args: &[&str],
+    input: &str,
+    env_vars: &[EnvVar],
+) -> Result<RunOutput> {
+    let interpreter = PYTHON
+        .read()
+        .unwrap()
+        .clone()
+        .ok_or("未找到 Python, 请先安装 uv 或 Python 3")?;
+
+    let content = fs::read_to_string(plugin_file)?;
+    let bootstrap = plugins_dir().join(format!("temp_{}.py", crate::utils::gen::generate_id()));
+    fs::write(
+        &bootstrap,
+        format!("{}{}", script_metadata(&content), BOOTSTRAP),
+    )?;
+
+    let mut cmd = match interpreter {
+        Interpreter::Uv => {
+            let mut cmd = tokio::process::Command::new("uv");
+            cmd.args(["run", "--quiet", "--script"]);
+            cmd
+        }
+        Interpreter::Python(program) => tokio::process::Command::new(program),
+    };
+    cmd.arg(&bootstrap)
+        .arg(plugin_file)
+        .args(args)
+        .env("PYTHONIOENCODING", "utf-8");
+    // 以插件数据目录作为工作目录
+    let dir = data_dir(id)?;
+    cmd.current_dir(&dir)
+        .env(DATA_DIR_ENV, &dir)
+        .envs(proxy::env_vars_for(id).await);
+    for var in env_vars {
+        cmd.env(&var.key, &var.value);
+    }
+
+    let output = run_limited(cmd, Some(input.to_string())).await;
+    let _ = fs::remove_file(&bootstrap);
+    output
+}
+
+/// 写入 Python 插件并读取元数据
+pub(crate) async fn load(id: &str, content: &str) -> Result<Plugin> {
+    let plugin_file = source_path(id);
+    reload::remember(id, content);
+    fs::write(&plugin_file, content)?;
+
+    let env_vars = env::scoped(id)?;
+    let output = run(id, &plugin_file, &["describe"], "", &env_vars).await?;
+    let _ = logs::append_logs(id, None, &output.stderr);
+    let plugin_info: Value = serde_json::from_str(&output.into_stdout()?)?;
+    parse_plugin_info(id, PluginRuntime::Python, &plugin_info)
+}
+
+/// 执行 Python 插件的工具
+pub(crate) async fn execute(
+    id: &str,
+    tool: &str,
+    args: &Value,
+    artifacts_dir: &Path,
+) -> Result<Value> {
+    let plugin_file = source_path(id);
+    if !plugin_file.exists() {
+        return Err(PluginError::Plugin(format!("插件文件不存在: {}", id)));
+    }
+
+    let mut env_vars = env::for_plugin(id).await?;
+    env_vars.push(artifacts::env_var(artifacts_dir));
+    let input = serde_json::to_string(args)?;
+    let output = run(id, &plugin_file, &["call", tool], &input, &env_vars).await?;
+    let _ = logs::append_logs(id, Some(tool), &output.stderr);
+    serde_json::from_str(&output.into_stdout()?).map_err(|e| PluginError::Json(e.to_string()))
+}
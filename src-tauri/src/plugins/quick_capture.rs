@@ -0,0 +1,189 @@
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{
+    AppHandle, Emitter, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent,
+};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use super::deno::{PluginError, Result};
+use super::host;
+use crate::utils::settings;
+
+/// 快速提问窗口的标签
+pub const WINDOW_LABEL: &str = "quick-capture";
+const MAIN_WINDOW: &str = "main";
+const WINDOW_URL: &str = "/quick-capture";
+const WINDOW_WIDTH: f64 = 640.0;
+const WINDOW_HEIGHT: f64 = 120.0;
+
+// 当前注册的快捷键, 修改时注销
+static CURRENT: Lazy<Mutex<Option<Shortcut>>> = Lazy::new(|| Mutex::new(None));
+
+/// 快速提问窗口打开时发送的内容
+#[derive(Debug, Serialize, Clone)]
+pub struct QuickCaptureOpen {
+    /// 打开时剪贴板中的文本, 可作为选中内容引用
+    pub clipboard: Option<String>,
+}
+
+/// 从快速提问窗口提交到主窗口的内容
+#[derive(Debug, Serialize, Clone)]
+pub struct QuickCapture {
+    pub text: String,
+    pub clipboard: Option<String>,
+}
+
+/// 快捷键设置
+#[derive(Debug, Serialize)]
+pub struct QuickCaptureBinding {
+    pub shortcut: Option<String>,
+    /// 是否已成功注册, 与其他应用冲突时为 false
+    pub registered: bool,
+}
+
+fn capture_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("快速提问失败: {}", message))
+}
+
+// 获取快速提问窗口, 不存在时创建
+fn window(app: &AppHandle) -> Result<WebviewWindow> {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        return Ok(window);
+    }
+    let window = WebviewWindowBuilder::new(app, WINDOW_LABEL, WebviewUrl::App(WINDOW_URL.into()))
+        .title("快速提问")
+        .inner_size(WINDOW_WIDTH, WINDOW_HEIGHT)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .center()
+        .visible(false)
+        .build()
+        .map_err(capture_error)?;
+    // 失去焦点时收起, 与启动器的行为一致
+    let handle = window.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::Focused(false) => {
+            let _ = handle.hide();
+        }
+        WindowEvent::CloseRequested { api, .. } => {
+            let _ = handle.hide();
+            api.prevent_close();
+        }
+        _ => {}
+    });
+    Ok(window)
+}
+
+// 显示或隐藏快速提问窗口
+async fn toggle(app: AppHandle) -> Result<()> {
+    let window = window(&app)?;
+    if window.is_visible().unwrap_or(false) {
+        let _ = window.hide();
+        return Ok(());
+    }
+    let clipboard = host::clipboard_read()
+        .await
+        .ok()
+        .filter(|text| !text.trim().is_empty());
+    let _ = window.center();
+    window.show().map_err(capture_error)?;
+    let _ = window.set_focus();
+    app.emit_to(
+        WINDOW_LABEL,
+        "quick-capture://open",
+        QuickCaptureOpen { clipboard },
+    )
+    .map_err(capture_error)?;
+    Ok(())
+}
+
+fn parse(binding: &str) -> Result<Shortcut> {
+    binding
+        .parse()
+        .map_err(|e| capture_error(format!("无效的快捷键 {}: {}", binding, e)))
+}
+
+// 注册新的快捷键后注销旧的, 注册失败时保留原来的快捷键
+fn register(app: &AppHandle, binding: Option<&str>) -> Result<()> {
+    let shortcut = binding.map(parse).transpose()?;
+    let mut current = CURRENT.lock().unwrap();
+    if shortcut.is_some() && shortcut == *current {
+        return Ok(());
+    }
+    if let Some(shortcut) = shortcut {
+        app.global_shortcut()
+            .on_shortcut(shortcut, |app, _, event| {
+                if matches!(event.state(), ShortcutState::Pressed) {
+                    let app = app.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(err) = toggle(app).await {
+                            tracing::warn!(error = %err, "打开快速提问窗口失败");
+                        }
+                    });
+                }
+            })
+            .map_err(capture_error)?;
+    }
+    if let Some(old) = std::mem::replace(&mut *current, shortcut) {
+        let _ = app.global_shortcut().unregister(old);
+    }
+    Ok(())
+}
+
+/// 启动时注册设置中的快捷键
+pub fn start(app: &AppHandle) {
+    let binding = settings::get().hotkey.quick_capture;
+    if let Err(err) = register(app, binding.as_deref()) {
+        tracing::warn!(error = %err, "注册快速提问快捷键失败");
+    }
+}
+
+#[tauri::command]
+pub async fn quick_capture_binding() -> Result<QuickCaptureBinding> {
+    Ok(QuickCaptureBinding {
+        shortcut: settings::get().hotkey.quick_capture,
+        registered: CURRENT.lock().unwrap().is_some(),
+    })
+}
+
+/// 修改快速提问的快捷键
+///
+/// # 参数
+/// * `shortcut` - 如 CommandOrControl+Shift+Space, 为空时取消注册
+#[tauri::command]
+pub async fn quick_capture_set_binding(
+    app: AppHandle,
+    shortcut: Option<String>,
+) -> Result<QuickCaptureBinding> {
+    let shortcut = shortcut
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    register(&app, shortcut.as_deref())?;
+    settings::update(|s| s.hotkey.quick_capture = shortcut)?;
+    quick_capture_binding().await
+}
+
+/// 提交快速提问, 收起窗口并将内容以 quick-capture://submit 事件发送到主窗口
+#[tauri::command]
+pub async fn quick_capture_submit(
+    app: AppHandle,
+    text: String,
+    clipboard: Option<String>,
+) -> Result<()> {
+    if let Some(window) = app.get_webview_window(WINDOW_LABEL) {
+        let _ = window.hide();
+    }
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    app.emit_to(
+        MAIN_WINDOW,
+        "quick-capture://submit",
+        QuickCapture { text, clipboard },
+    )
+    .map_err(capture_error)
+}
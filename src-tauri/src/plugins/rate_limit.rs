@@ -0,0 +1,77 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::deno::{PluginError, Result};
+
+// 限流的时间窗口
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RatePeriod {
+    Second,
+    Minute,
+    Hour,
+    Day,
+}
+
+impl RatePeriod {
+    fn duration(self) -> Duration {
+        Duration::from_secs(match self {
+            RatePeriod::Second => 1,
+            RatePeriod::Minute => 60,
+            RatePeriod::Hour => 60 * 60,
+            RatePeriod::Day => 24 * 60 * 60,
+        })
+    }
+}
+
+/// 工具的调用频率限制, 如 { max: 5, per: "minute" }
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RateLimit {
+    pub max: u32,
+    pub per: RatePeriod,
+}
+
+// 每个工具在时间窗口内的调用时间
+static CALLS: Lazy<Mutex<HashMap<String, VecDeque<Instant>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 检查并记录一次调用, 超出限制时返回 RateLimited
+pub(crate) fn acquire(id: &str, tool: &str, limit: &RateLimit) -> Result<()> {
+    let window = limit.per.duration();
+    let now = Instant::now();
+    let mut calls = CALLS.lock().unwrap();
+    let history = calls.entry(format!("{}/{}", id, tool)).or_default();
+
+    while history
+        .front()
+        .is_some_and(|time| now.duration_since(*time) >= window)
+    {
+        history.pop_front();
+    }
+
+    if history.len() >= limit.max as usize {
+        // 最早的调用移出窗口后即可重试
+        let retry_after = history
+            .front()
+            .map(|time| window.saturating_sub(now.duration_since(*time)))
+            .unwrap_or(window);
+        return Err(PluginError::RateLimited {
+            retry_after: retry_after.as_secs_f64().ceil() as u64,
+        });
+    }
+
+    history.push_back(now);
+    Ok(())
+}
+
+/// 清除插件的调用记录
+pub(crate) fn reset(id: &str) {
+    let prefix = format!("{}/", id);
+    CALLS
+        .lock()
+        .unwrap()
+        .retain(|key, _| !key.starts_with(&prefix));
+}
@@ -0,0 +1,135 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+
+use super::db::with_db;
+use super::deno::{PluginError, Result};
+use super::proxy;
+use super::secrets;
+
+pub(crate) const REDACTED: &str = "***";
+// 较短的值容易误伤普通文本, 只替换足够长的密钥
+const MIN_SECRET_LEN: usize = 8;
+// 参数名包含以下片段时视为敏感信息
+const SECRET_KEYS: [&str; 6] = ["key", "token", "secret", "password", "auth", "credential"];
+
+// 常见的令牌格式, 即使未保存在环境变量中也会替换
+static TOKEN_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    [
+        // OpenAI / Anthropic 等 API Key
+        r"\bsk-[A-Za-z0-9_\-]{16,}",
+        // GitHub 令牌
+        r"\bgh[pousr]_[A-Za-z0-9]{30,}",
+        r"\bgithub_pat_[A-Za-z0-9_]{30,}",
+        // Slack 令牌
+        r"\bxox[abposr]-[A-Za-z0-9\-]{10,}",
+        // AWS Access Key
+        r"\bAKIA[0-9A-Z]{16}\b",
+        // Google API Key
+        r"\bAIza[0-9A-Za-z_\-]{35}",
+        // JWT
+        r"\beyJ[A-Za-z0-9_\-]+\.eyJ[A-Za-z0-9_\-]+\.[A-Za-z0-9_\-]+",
+        // Authorization 头
+        r"(?i)\bbearer\s+[A-Za-z0-9._~+/\-]{16,}=*",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).unwrap())
+    .collect()
+});
+
+/// 已保存的全部密钥值: 全局变量、插件变量、环境配置中的变量与代理密码
+pub(crate) fn known_secrets() -> Result<Vec<String>> {
+    let rows: Vec<(Option<String>, String, String)> = with_db(|conn| {
+        let mut rows = Vec::new();
+        let queries = [
+            "SELECT NULL, key, value FROM env_vars",
+            "SELECT plugin_id, key, value FROM plugin_env",
+            "SELECT 'profile:' || profile, key, value FROM env_profile_vars",
+        ];
+        for query in queries {
+            let mut stmt = conn.prepare(query)?;
+            for row in stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))? {
+                rows.push(row?);
+            }
+        }
+        Ok(rows)
+    })?;
+    let mut resolved = Vec::new();
+    for (scope, key, stored) in rows {
+        resolved.push(secrets::resolve(scope.as_deref(), &key, stored)?);
+    }
+    // 代理密码单独保存, 插件进程拿到的代理地址中可能包含编码后的密码
+    if let Some(password) = proxy::password() {
+        resolved.push(urlencoding::encode(&password).into_owned());
+        resolved.push(password);
+    }
+    let mut values = Vec::new();
+    for value in resolved {
+        if value.len() >= MIN_SECRET_LEN && !values.contains(&value) {
+            values.push(value);
+        }
+    }
+    // 先替换较长的值, 避免一个密钥是另一个的前缀时残留部分内容
+    values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+    Ok(values)
+}
+
+fn scrub_with(text: &str, secrets: &[String]) -> String {
+    let mut text = text.to_string();
+    for secret in secrets {
+        if text.contains(secret.as_str()) {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+    }
+    for pattern in TOKEN_PATTERNS.iter() {
+        if pattern.is_match(&text) {
+            text = pattern.replace_all(&text, REDACTED).to_string();
+        }
+    }
+    text
+}
+
+/// 替换文本中的密钥与常见令牌
+pub(crate) fn scrub(text: &str) -> String {
+    // 读取密钥失败时仍按令牌格式替换
+    scrub_with(text, &known_secrets().unwrap_or_default())
+}
+
+/// 替换敏感参数名对应的值, 以及字符串中的密钥
+pub(crate) fn scrub_value(value: &Value) -> Value {
+    fn walk(value: &Value, secrets: &[String]) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(k, v)| {
+                        let lower = k.to_lowercase();
+                        if SECRET_KEYS.iter().any(|s| lower.contains(s)) {
+                            (k.clone(), Value::String(REDACTED.to_string()))
+                        } else {
+                            (k.clone(), walk(v, secrets))
+                        }
+                    })
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(items.iter().map(|v| walk(v, secrets)).collect()),
+            Value::String(s) => Value::String(scrub_with(s, secrets)),
+            other => other.clone(),
+        }
+    }
+    walk(value, &known_secrets().unwrap_or_default())
+}
+
+/// 替换错误信息中的密钥, 错误返回给前端或写入记录前调用
+pub(crate) fn scrub_error(err: PluginError) -> PluginError {
+    match err {
+        PluginError::Io(message) => PluginError::Io(scrub(&message)),
+        PluginError::Json(message) => PluginError::Json(scrub(&message)),
+        PluginError::Toml(message) => PluginError::Toml(scrub(&message)),
+        PluginError::Plugin(message) => PluginError::Plugin(scrub(&message)),
+        PluginError::Database(message) => PluginError::Database(scrub(&message)),
+        PluginError::InvalidArgs(errors) => {
+            PluginError::InvalidArgs(errors.iter().map(|e| scrub(e)).collect())
+        }
+        other => other,
+    }
+}
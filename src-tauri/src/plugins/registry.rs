@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter};
+
+use super::deno::{load_plugin_list, plugins_dir, Plugin, PluginError, Result};
+use super::install::{digest, download_script, install, PluginSource};
+use super::proxy;
+use super::signature;
+use crate::utils::gen::generate_id;
+use crate::utils::settings;
+
+// 本地索引缓存的有效期 (秒)
+const INDEX_TTL_SECS: i64 = 3600;
+
+/// 仓库索引中的一个插件
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegistryEntry {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub version: String,
+    /// 插件脚本地址
+    pub url: String,
+    /// 脚本的 SHA-256, 提供时安装前校验
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// 发布者对脚本的 ed25519 签名 (base64)
+    #[serde(default)]
+    pub signature: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 所属仓库地址, 由本地填充
+    #[serde(default)]
+    pub registry: String,
+}
+
+// 仓库索引文件格式
+#[derive(Debug, Deserialize)]
+struct RegistryIndex {
+    plugins: Vec<RegistryEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct IndexCache {
+    fetched_at: i64,
+    entries: Vec<RegistryEntry>,
+}
+
+/// 可升级的插件
+#[derive(Debug, Serialize, Clone)]
+pub struct PluginUpdate {
+    pub id: String,
+    pub name: String,
+    pub current: Option<String>,
+    pub latest: String,
+}
+
+fn cache_file() -> PathBuf {
+    plugins_dir().join("registry.json")
+}
+
+fn read_cache() -> Option<IndexCache> {
+    let content = fs::read_to_string(cache_file()).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+async fn fetch_index(url: &str) -> Result<Vec<RegistryEntry>> {
+    let index: RegistryIndex = proxy::client()
+        .get(url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| PluginError::Plugin(format!("获取仓库索引失败 {}: {}", url, e)))?
+        .json()
+        .await
+        .map_err(|e| PluginError::Plugin(format!("仓库索引格式无效 {}: {}", url, e)))?;
+    Ok(index
+        .plugins
+        .into_iter()
+        .map(|entry| RegistryEntry {
+            registry: url.to_string(),
+            ..entry
+        })
+        .collect())
+}
+
+// 拉取所有仓库并写入缓存, 单个仓库失败时保留其余结果
+async fn refresh() -> Result<Vec<RegistryEntry>> {
+    let urls = settings::get().registry.urls;
+    let mut entries: Vec<RegistryEntry> = Vec::new();
+    let mut errors = Vec::new();
+    for url in &urls {
+        match fetch_index(url).await {
+            Ok(fetched) => {
+                for entry in fetched {
+                    if !entries.iter().any(|e| e.name == entry.name) {
+                        entries.push(entry);
+                    }
+                }
+            }
+            Err(err) => errors.push(err.to_string()),
+        }
+    }
+    if !urls.is_empty() && errors.len() == urls.len() {
+        return Err(PluginError::Plugin(errors.join("; ")));
+    }
+
+    let cache = IndexCache {
+        fetched_at: chrono::Utc::now().timestamp(),
+        entries,
+    };
+    fs::write(cache_file(), serde_json::to_string(&cache)?)?;
+    Ok(cache.entries)
+}
+
+// 缓存未过期时直接使用缓存
+async fn entries() -> Result<Vec<RegistryEntry>> {
+    match read_cache() {
+        Some(cache) if chrono::Utc::now().timestamp() - cache.fetched_at < INDEX_TTL_SECS => {
+            Ok(cache.entries)
+        }
+        _ => refresh().await,
+    }
+}
+
+// 版本号按 semver 比较, 无法解析时按字符串是否不同判断
+fn is_newer(latest: &str, current: Option<&str>) -> bool {
+    let Some(current) = current else {
+        return true;
+    };
+    match (
+        semver::Version::parse(latest.trim_start_matches('v')),
+        semver::Version::parse(current.trim_start_matches('v')),
+    ) {
+        (Ok(latest), Ok(current)) => latest > current,
+        _ => latest != current,
+    }
+}
+
+/// 重新拉取仓库索引
+#[tauri::command]
+pub async fn registry_refresh() -> Result<Vec<RegistryEntry>> {
+    refresh().await
+}
+
+/// 按名称、描述与标签搜索仓库中的插件
+#[tauri::command]
+pub async fn registry_search(query: Option<String>) -> Result<Vec<RegistryEntry>> {
+    let query = query.unwrap_or_default().to_lowercase();
+    Ok(entries()
+        .await?
+        .into_iter()
+        .filter(|entry| {
+            query.is_empty()
+                || entry.name.to_lowercase().contains(&query)
+                || entry.description.to_lowercase().contains(&query)
+                || entry.tags.iter().any(|t| t.to_lowercase().contains(&query))
+        })
+        .collect())
+}
+
+/// 从仓库安装插件, 已安装同名插件时原地升级
+#[tauri::command]
+pub async fn registry_install(name: String, allow_unsigned: Option<bool>) -> Result<Plugin> {
+    let entry = entries()
+        .await?
+        .into_iter()
+        .find(|entry| entry.name == name)
+        .ok_or_else(|| PluginError::Plugin(format!("仓库中没有插件: {}", name)))?;
+
+    let content = download_script(&entry.url).await?;
+    let sha256 = digest(&content);
+    if let Some(expected) = &entry.sha256 {
+        if !expected.eq_ignore_ascii_case(&sha256) {
+            return Err(PluginError::Plugin(format!("插件校验失败: {}", name)));
+        }
+    }
+    let info = signature::check(
+        &content,
+        entry.signature.as_deref(),
+        allow_unsigned.unwrap_or(false),
+    )?;
+
+    let id = load_plugin_list()
+        .await?
+        .into_values()
+        .find(|plugin| plugin.name == name)
+        .map(|plugin| plugin.id)
+        .unwrap_or_else(generate_id);
+    let source = PluginSource::Url {
+        url: entry.url,
+        sha256,
+    };
+    let mut plugin = install(id, content, source, info).await?;
+    super::service::reload(&plugin.id).await?;
+    // 插件未声明版本时使用索引中的版本
+    if plugin.version.is_none() {
+        plugin.version = Some(entry.version);
+        plugin = super::deno::register_plugin(plugin).await?;
+    }
+    Ok(plugin)
+}
+
+/// 对比已安装插件与仓库索引, 返回可升级的插件
+#[tauri::command]
+pub async fn plugins_check_updates() -> Result<Vec<PluginUpdate>> {
+    let entries = refresh().await?;
+    Ok(load_plugin_list()
+        .await?
+        .into_values()
+        .filter_map(|plugin| {
+            let entry = entries.iter().find(|entry| entry.name == plugin.name)?;
+            is_newer(&entry.version, plugin.version.as_deref()).then(|| PluginUpdate {
+                id: plugin.id,
+                name: plugin.name,
+                current: plugin.version,
+                latest: entry.version.clone(),
+            })
+        })
+        .collect())
+}
+
+/// 设置仓库索引地址
+#[tauri::command]
+pub async fn registry_configure(urls: Vec<String>) -> Result<Vec<String>> {
+    Ok(settings::update(|s| s.registry.urls = urls)?.registry.urls)
+}
+
+/// 启动时检查更新, 有可升级的插件时通知前端
+pub async fn notify_updates(app: AppHandle) {
+    if settings::get().registry.urls.is_empty() {
+        return;
+    }
+    if let Ok(updates) = plugins_check_updates().await {
+        if !updates.is_empty() {
+            let _ = app.emit("plugins://updates", updates);
+        }
+    }
+}
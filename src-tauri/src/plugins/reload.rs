@@ -0,0 +1,137 @@
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc;
+
+use super::deno::{
+    find_plugin, plugins_dir, process_plugin_content, Plugin, PluginError, PluginRuntime, Result,
+};
+use super::{logs, service};
+
+// 编辑器保存时通常触发多次事件, 静默一段时间后再重新加载
+const DEBOUNCE_MS: u64 = 300;
+
+/// 插件文件变化后发送给前端的事件
+#[derive(Debug, Serialize, Clone)]
+pub struct PluginChanged {
+    pub id: String,
+    pub plugin: Option<Plugin>,
+    pub error: Option<String>,
+}
+
+// 最近一次加载的源码摘要, 用于忽略应用自身的写入
+static KNOWN: Lazy<Mutex<HashMap<String, Vec<u8>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static WATCHER: Lazy<Mutex<Option<RecommendedWatcher>>> = Lazy::new(|| Mutex::new(None));
+
+/// 记录即将写入的源码, 之后的同内容文件事件不再重新加载
+pub(crate) fn remember(id: &str, content: &str) {
+    KNOWN
+        .lock()
+        .unwrap()
+        .insert(id.to_string(), Sha256::digest(content.as_bytes()).to_vec());
+}
+
+// 源码文件名对应的插件 id
+fn plugin_id(path: &Path) -> Option<String> {
+    let ext = path.extension()?.to_str()?;
+    if !matches!(ext, "ts" | "py") {
+        return None;
+    }
+    Some(path.file_stem()?.to_str()?.to_string())
+}
+
+// 重新读取源码并更新元数据, 内容未变化时返回 None
+async fn reload(id: &str, path: &Path) -> Result<Option<Plugin>> {
+    let plugin = find_plugin(id).await?;
+    if !matches!(
+        plugin.runtime,
+        PluginRuntime::Deno | PluginRuntime::Node | PluginRuntime::Python
+    ) {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)?;
+    let digest = Sha256::digest(content.as_bytes()).to_vec();
+    if KNOWN.lock().unwrap().get(id) == Some(&digest) {
+        return Ok(None);
+    }
+
+    let plugin = process_plugin_content(id.to_string(), content).await?;
+    service::reload(id).await?;
+    Ok(Some(plugin))
+}
+
+async fn debounce(app: AppHandle, mut events: mpsc::UnboundedReceiver<String>) {
+    let delay = Duration::from_millis(DEBOUNCE_MS);
+    while let Some(first) = events.recv().await {
+        let mut pending = HashSet::from([first]);
+        loop {
+            match tokio::time::timeout(delay, events.recv()).await {
+                Ok(Some(id)) => {
+                    pending.insert(id);
+                }
+                Ok(None) => return,
+                Err(_) => break,
+            }
+        }
+
+        for id in pending {
+            // 未注册的文件 (如执行时的临时脚本) 直接忽略
+            if find_plugin(&id).await.is_err() {
+                continue;
+            }
+            let path = match plugins_dir().join(format!("{}.ts", id)) {
+                path if path.exists() => path,
+                _ => plugins_dir().join(format!("{}.py", id)),
+            };
+            let changed = match reload(&id, &path).await {
+                Ok(Some(plugin)) => PluginChanged {
+                    id,
+                    plugin: Some(plugin),
+                    error: None,
+                },
+                Ok(None) => continue,
+                Err(err) => {
+                    tracing::warn!(plugin = %id, error = %err, "重新加载失败");
+                    let _ = logs::append_logs(&id, None, &format!("重新加载失败: {}", err));
+                    PluginChanged {
+                        id,
+                        plugin: None,
+                        error: Some(err.to_string()),
+                    }
+                }
+            };
+            let _ = app.emit("plugins://changed", changed);
+        }
+    }
+}
+
+/// 监听插件目录, 源码在外部被修改时重新加载并通知前端
+pub fn start(app: AppHandle) -> Result<()> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            return;
+        }
+        for id in event.paths.iter().filter_map(|path| plugin_id(path)) {
+            let _ = tx.send(id);
+        }
+    })
+    .map_err(|e| PluginError::Plugin(format!("无法创建文件监听: {}", e)))?;
+    watcher
+        .watch(&plugins_dir(), RecursiveMode::NonRecursive)
+        .map_err(|e| PluginError::Plugin(format!("无法监听目录: {}", e)))?;
+
+    tauri::async_runtime::spawn(debounce(app, rx));
+    *WATCHER.lock().unwrap() = Some(watcher);
+    Ok(())
+}
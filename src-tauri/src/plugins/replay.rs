@@ -0,0 +1,306 @@
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::approval;
+use super::artifacts::{self, Artifact};
+use super::db::with_db;
+use super::deno::{
+    find_plugin, plugin_remove, process_plugin_content, read_content, resolve_tool, run_tool,
+    EnvVar, Plugin, PluginError, Result,
+};
+use super::directory;
+use super::env::{self, Scope};
+use super::history::{self, ExecutionRecord};
+use super::install::digest;
+use super::profile;
+use super::redact;
+use super::schema;
+use super::secrets;
+use super::storage;
+use super::versions;
+use crate::utils::gen::generate_id;
+
+// 执行时的环境变量: 新记录加密保存取值, 早期记录只有加盐的摘要, 只能判断取值是否变化
+enum EnvSnapshot {
+    Values(BTreeMap<String, String>),
+    Digests(BTreeMap<String, String>),
+}
+
+// 执行时记录的快照
+struct Snapshot {
+    plugin_id: String,
+    tool: String,
+    version: Option<String>,
+    args: Value,
+    env: EnvSnapshot,
+    profile: Option<String>,
+}
+
+/// 重放得到的结果, 格式与调用记录一致
+#[derive(Debug, Serialize)]
+pub struct ReplayResult {
+    pub success: bool,
+    pub output: String,
+    pub duration_ms: u64,
+    pub artifacts: Vec<Artifact>,
+}
+
+/// 原结果与重放结果的对比
+#[derive(Debug, Serialize)]
+pub struct ReplayReport {
+    pub original: ExecutionRecord,
+    pub replay: ReplayResult,
+    /// 记录时的版本即当前安装的版本
+    pub same_version: bool,
+    /// 当前取值与记录时不同、新增或已删除的环境变量
+    pub env_changed: Vec<String>,
+    /// 重放使用了记录时的环境变量取值; 早期记录没有保存取值, 只能使用当前的值
+    pub env_restored: bool,
+    /// 两次结果是否一致
+    pub matches: bool,
+}
+
+async fn env_values(id: &str) -> Result<BTreeMap<String, String>> {
+    Ok(env::for_plugin(id)
+        .await?
+        .into_iter()
+        .map(|var| (var.key, var.value))
+        .collect())
+}
+
+// 加盐的摘要, 无法加密时代替取值保存
+fn env_digests(execution_id: &str, values: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+    values
+        .iter()
+        .map(|(key, value)| {
+            let hash = digest(&format!("{}:{}", execution_id, value));
+            (key.clone(), hash)
+        })
+        .collect()
+}
+
+/// 保存执行快照, 在插件运行前调用
+///
+/// 参数与环境变量加密后保存以便原样重放; 无法加密时参数去除敏感信息, 环境变量只保存摘要。
+/// 版本为源码摘要, WASM 与 Shell 插件不记录版本
+pub(crate) async fn save(
+    execution_id: &str,
+    plugin: &Plugin,
+    tool: &str,
+    args: &Value,
+) -> Result<()> {
+    let values = env_values(&plugin.id).await?;
+    let env = match secrets::seal(&serde_json::to_string(&values)?) {
+        Ok(sealed) => sealed,
+        Err(_) => serde_json::to_string(&env_digests(execution_id, &values))?,
+    };
+    let args = match secrets::seal(&serde_json::to_string(args)?) {
+        Ok(sealed) => sealed,
+        Err(_) => serde_json::to_string(&redact::scrub_value(args))?,
+    };
+    let version = plugin.content_hash.as_ref().map(|hash| hash.sha256.clone());
+    with_db(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO execution_snapshots
+                 (execution_id, plugin_id, tool, version, args, env, profile, time)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                execution_id,
+                plugin.id,
+                tool,
+                version,
+                args,
+                env,
+                profile::current(),
+                chrono::Utc::now().timestamp_millis()
+            ],
+        )?;
+        Ok(())
+    })
+}
+
+fn load(execution_id: &str) -> Result<Option<Snapshot>> {
+    let row: Option<(
+        String,
+        String,
+        Option<String>,
+        String,
+        String,
+        Option<String>,
+    )> = with_db(|conn| {
+        Ok(conn
+            .query_row(
+                "SELECT plugin_id, tool, version, args, env, profile
+                     FROM execution_snapshots WHERE execution_id = ?1",
+                [execution_id],
+                |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                        row.get(5)?,
+                    ))
+                },
+            )
+            .optional()?)
+    })?;
+    let Some((plugin_id, tool, version, args, env, profile)) = row else {
+        return Ok(None);
+    };
+    Ok(Some(Snapshot {
+        plugin_id,
+        tool,
+        version,
+        args: serde_json::from_str(&secrets::unseal(&args)?)?,
+        env: if secrets::is_sealed(&env) {
+            EnvSnapshot::Values(serde_json::from_str(&secrets::unseal(&env)?)?)
+        } else {
+            EnvSnapshot::Digests(serde_json::from_str(&env)?)
+        },
+        profile,
+    }))
+}
+
+/// 删除全部执行快照
+pub(crate) fn clear() -> Result<()> {
+    with_db(|conn| {
+        conn.execute("DELETE FROM execution_snapshots", [])?;
+        Ok(())
+    })
+}
+
+// 以临时 id 安装指定的源码, 复制目录、存储与环境变量, 返回临时 id
+//
+// env 不为空时以记录的取值作为临时插件自己的变量, 覆盖当前的全局变量与环境配置
+async fn pin(
+    plugin: &Plugin,
+    mut content: String,
+    env: Option<&BTreeMap<String, String>>,
+) -> Result<String> {
+    let id = generate_id();
+    let installed = async {
+        if directory::copy(&plugin.id, &id)? {
+            content = directory::retarget_shim(&content, &plugin.id, &id);
+        }
+        process_plugin_content(id.clone(), content).await?;
+        storage::copy(&plugin.id, &id).await?;
+        env::copy(&plugin.id, &id)?;
+        if let Some(values) = env {
+            let vars: Vec<EnvVar> = values
+                .iter()
+                .map(|(key, value)| EnvVar {
+                    key: key.clone(),
+                    value: value.clone(),
+                })
+                .collect();
+            with_db(|conn| env::write_vars(conn, Scope::Plugin(&id), &vars))?;
+        }
+        Ok::<_, PluginError>(())
+    }
+    .await;
+    if let Err(err) = installed {
+        let _ = plugin_remove(id).await;
+        return Err(err);
+    }
+    Ok(id)
+}
+
+// 执行一次, 与正常执行一样检查工具、参数并在需要时请求确认, 不读写缓存, 也不写入调用记录
+async fn run(id: &str, label: &str, snapshot: &Snapshot) -> Result<ReplayResult> {
+    let target = resolve_tool(&find_plugin(id).await?, &snapshot.tool).await?;
+    schema::validate_args(&target, &snapshot.args)?;
+    approval::confirm(id, label, &snapshot.tool, "执行记录重放").await?;
+
+    let started = std::time::Instant::now();
+    let dir = artifacts::create_dir(&generate_id())?;
+    let result = run_tool(id, &snapshot.tool, &snapshot.args, &dir, &[]).await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let (success, output) = history::summarize(&result);
+    Ok(ReplayResult {
+        success,
+        output,
+        duration_ms,
+        artifacts: artifacts::collect(&dir)?,
+    })
+}
+
+// 当前取值与记录时不同、新增或已删除的变量名
+fn changed_keys(
+    execution_id: &str,
+    recorded: &EnvSnapshot,
+    current: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let current_digests;
+    let (recorded, current) = match recorded {
+        EnvSnapshot::Values(values) => (values, current),
+        EnvSnapshot::Digests(digests) => {
+            current_digests = env_digests(execution_id, current);
+            (digests, &current_digests)
+        }
+    };
+    let keys: BTreeSet<&String> = recorded.keys().chain(current.keys()).collect();
+    keys.into_iter()
+        .filter(|key| recorded.get(*key) != current.get(*key))
+        .cloned()
+        .collect()
+}
+
+/// 按执行记录重放: 使用记录时的插件版本、工具、参数、环境变量与环境配置, 返回原结果与新结果
+///
+/// 插件版本或环境变量与记录时不同时, 以临时 id 安装记录时的版本与变量取值执行, 结束后删除;
+/// 早期记录没有保存变量取值, 使用当前的值并在结果中列出变化的变量名
+#[tauri::command]
+pub async fn execution_replay(execution_id: String) -> Result<ReplayReport> {
+    let original = history::find(&execution_id)?
+        .ok_or_else(|| PluginError::Plugin(format!("执行记录不存在: {}", execution_id)))?;
+    let snapshot = load(&execution_id)?
+        .ok_or_else(|| PluginError::Plugin("该执行没有可重放的快照".to_string()))?;
+    let plugin = find_plugin(&snapshot.plugin_id).await?;
+    if !plugin.enabled {
+        return Err(PluginError::Disabled(plugin.id));
+    }
+    let current = plugin.content_hash.as_ref().map(|hash| hash.sha256.clone());
+
+    let outcome = profile::scope(snapshot.profile.clone(), async {
+        let env_changed =
+            changed_keys(&execution_id, &snapshot.env, &env_values(&plugin.id).await?);
+        let restore = match snapshot.env {
+            EnvSnapshot::Values(ref values) if !env_changed.is_empty() => Some(values),
+            _ => None,
+        };
+        let version = snapshot
+            .version
+            .as_ref()
+            .filter(|version| Some(*version) != current.as_ref());
+        let pinned = match (version, restore) {
+            (None, None) => None,
+            (Some(version), _) => {
+                let content =
+                    versions::plugin_version_content(plugin.id.clone(), version.clone()).await?;
+                Some(pin(&plugin, content, restore).await?)
+            }
+            (None, Some(_)) => Some(pin(&plugin, read_content(&plugin)?, restore).await?),
+        };
+        let id = pinned.as_deref().unwrap_or(&plugin.id);
+        let result = run(id, &plugin.id, &snapshot).await;
+        if let Some(ref id) = pinned {
+            let _ = plugin_remove(id.clone()).await;
+        }
+        Ok::<_, PluginError>((result?, version.is_none(), env_changed, restore.is_some()))
+    })
+    .await;
+    let (replay, same_version, env_changed, env_restored) = outcome??;
+
+    Ok(ReplayReport {
+        matches: replay.success == original.success && replay.output == original.output,
+        original,
+        replay,
+        same_version,
+        env_changed,
+        env_restored,
+    })
+}
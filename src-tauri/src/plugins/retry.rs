@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::deno::PluginError;
+
+// 单个工具允许的最大尝试次数
+const MAX_ATTEMPTS: u32 = 10;
+
+/// 工具的重试策略, 如 { attempts: 3, backoffMs: 500, retryOn: ["ETIMEDOUT"] }
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetryPolicy {
+    /// 总尝试次数, 包含首次执行
+    pub attempts: u32,
+    /// 首次重试前的等待时间 (毫秒)
+    #[serde(alias = "backoff_ms")]
+    pub backoff_ms: u64,
+    /// 是否按指数递增等待时间
+    pub exponential: bool,
+    /// 错误信息包含任一片段时重试, 为空时重试所有错误
+    #[serde(alias = "retry_on")]
+    pub retry_on: Vec<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            backoff_ms: 500,
+            exponential: true,
+            retry_on: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub(crate) fn max_attempts(&self) -> u32 {
+        self.attempts.clamp(1, MAX_ATTEMPTS)
+    }
+
+    /// 判断错误是否可以重试, 限流错误不重试
+    pub(crate) fn should_retry(&self, err: &PluginError) -> bool {
+        if matches!(err, PluginError::RateLimited { .. }) {
+            return false;
+        }
+        if self.retry_on.is_empty() {
+            return true;
+        }
+        let message = err.to_string();
+        self.retry_on
+            .iter()
+            .any(|pattern| message.contains(pattern))
+    }
+
+    /// 第 attempt 次失败后的等待时间
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let factor = if self.exponential {
+            2u64.saturating_pow(attempt.saturating_sub(1))
+        } else {
+            1
+        };
+        Duration::from_millis(self.backoff_ms.saturating_mul(factor))
+    }
+}
@@ -0,0 +1,706 @@
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+use super::bridge::{Bridge, RPC_PREFIX};
+use super::deno::{plugins_dir, EnvVar, PluginError, Result, DATA_DIR_ENV};
+use super::grants::{self, PermissionGrant};
+use super::{node, proxy, python};
+use crate::utils::file::get_config_dir;
+use crate::utils::gen::generate_id;
+use crate::utils::settings::{self, ResourceLimits};
+
+/// 随应用打包的 Deno 版本
+pub const DENO_VERSION: &str = "2.1.4";
+
+/// Deno 运行时, 安装或重新检测后会被替换
+static DENO_RUNTIME: Lazy<RwLock<DenoRuntime>> = Lazy::new(|| RwLock::new(DenoRuntime::new()));
+
+/// 获取当前的 Deno 运行时
+pub(crate) fn deno() -> DenoRuntime {
+    DENO_RUNTIME.read().unwrap().clone()
+}
+
+// 脚本执行前的控制台重定向, 保证 stdout 只输出结果
+const CONSOLE_REDIRECT: &str = r#"
+        console.log = console.info = console.debug = (...args) => console.error(...args);
+        const __echoEncoder = new TextEncoder();
+        const __echoWrite = async (text) => {
+            const data = __echoEncoder.encode(text);
+            let written = 0;
+            while (written < data.length) {
+                written += await Deno.stdout.write(data.subarray(written));
+            }
+        };
+
+        // 宿主调用: 请求写入 stdout, 响应从 stdin 逐行读取
+        const __echoPending = new Map();
+        let __echoRpcId = 0;
+        let __echoReader = null;
+        const __echoReadLoop = async () => {
+            const decoder = new TextDecoder();
+            let buffer = "";
+            for await (const chunk of Deno.stdin.readable) {
+                buffer += decoder.decode(chunk, { stream: true });
+                let index;
+                while ((index = buffer.indexOf("\n")) >= 0) {
+                    const line = buffer.slice(0, index);
+                    buffer = buffer.slice(index + 1);
+                    if (!line.trim()) continue;
+                    const message = JSON.parse(line);
+                    const pending = __echoPending.get(message.id);
+                    if (!pending) continue;
+                    __echoPending.delete(message.id);
+                    if (message.error !== undefined) {
+                        pending.reject(new Error(message.error));
+                    } else {
+                        pending.resolve(message.result);
+                    }
+                }
+            }
+            for (const pending of __echoPending.values()) {
+                pending.reject(new Error("宿主连接已关闭"));
+            }
+            __echoPending.clear();
+        };
+        const __echoCall = (method, params) => {
+            if (!__echoReader) {
+                __echoReader = __echoReadLoop();
+            }
+            const id = ++__echoRpcId;
+            return new Promise((resolve, reject) => {
+                __echoPending.set(id, { resolve, reject });
+                __echoWrite("RPC_PREFIX" + JSON.stringify({ id, method, params: params ?? null }) + "\n")
+                    .catch(reject);
+            });
+        };
+        globalThis.Echo = {
+            storage: {
+                get: (key) => __echoCall("storage.get", { key }),
+                set: (key, value) => __echoCall("storage.set", { key, value: value ?? null }),
+                delete: (key) => __echoCall("storage.delete", { key }),
+                keys: () => __echoCall("storage.keys", {}),
+            },
+            invoke: (plugin, tool, args) => __echoCall("invoke", { plugin, tool, args: args ?? {} }),
+            // 以下能力需要在插件的 permissions 中声明
+            clipboard: {
+                read: () => __echoCall("clipboard.read", {}),
+                write: (text) => __echoCall("clipboard.write", { text: String(text) }),
+            },
+            notify: (title, body) => __echoCall("notification.show", { title, body: body ?? "" }),
+            openUrl: (url) => __echoCall("open.url", { url }),
+            dialog: {
+                message: (message, title) => __echoCall("dialog.message", { message, title }),
+                confirm: (message, title) => __echoCall("dialog.confirm", { message, title }),
+            },
+        };
+
+        const __echoOutput = async (value) => {
+            await __echoWrite(JSON.stringify(value ?? null));
+            // 读取宿主响应会使进程保持运行, 输出结果后直接退出
+            if (__echoReader) {
+                Deno.exit(0);
+            }
+        };
+"#;
+
+// Deno 可执行文件来源
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DenoSource {
+    Custom,
+    Sidecar,
+    Installed,
+    System,
+}
+
+// 运行时信息
+#[derive(Debug, Serialize, Clone)]
+pub struct RuntimeInfo {
+    pub installed: bool,
+    pub source: Option<DenoSource>,
+    pub path: Option<String>,
+    pub version: Option<String>,
+    pub bundled_version: String,
+    pub offline: bool,
+}
+
+// 安装进度
+#[derive(Debug, Serialize, Clone)]
+pub struct InstallProgress {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+// 一次脚本执行的输入
+#[derive(Default)]
+pub(crate) struct DenoTask {
+    pub script: String,
+    pub env_vars: Vec<EnvVar>,
+    /// 依赖锁文件, 存在时以 --frozen 校验
+    pub lock_file: Option<PathBuf>,
+    /// 插件数据目录, 作为工作目录且仅允许写入该目录
+    pub data_dir: Option<PathBuf>,
+    /// 本次执行的产物目录, 同样允许写入
+    pub artifacts_dir: Option<PathBuf>,
+    /// 宿主调用的处理方, 为空时不响应宿主调用
+    pub bridge: Option<Bridge>,
+    /// 用户允许的额外权限
+    pub grants: Vec<PermissionGrant>,
+}
+
+/// 将要运行的 Deno 命令, 用于预览而不实际执行
+#[derive(Debug, Serialize)]
+pub struct TaskPlan {
+    pub program: Option<String>,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    /// 写入临时文件的完整启动脚本
+    pub script: String,
+}
+
+// 插件进程输出
+pub(crate) struct RunOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl From<std::process::Output> for RunOutput {
+    fn from(output: std::process::Output) -> Self {
+        Self {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        }
+    }
+}
+
+impl RunOutput {
+    // 执行失败时以 stderr 作为错误信息
+    pub fn into_stdout(self) -> Result<String> {
+        if self.success {
+            Ok(self.stdout)
+        } else {
+            Err(PluginError::Plugin(self.stderr))
+        }
+    }
+}
+
+// Deno 运行时封装
+#[derive(Clone)]
+pub(crate) struct DenoRuntime {
+    program: Option<PathBuf>,
+    source: Option<DenoSource>,
+    version: Option<String>,
+    base_args: Vec<String>,
+}
+
+// 运行时实现
+impl DenoRuntime {
+    // 运行时初始化, 依次尝试自定义路径、打包的 sidecar、应用内安装的 Deno 和系统 Deno
+    fn new() -> Self {
+        let custom = settings::get().runtime.deno_path.map(PathBuf::from);
+        let candidates = [
+            (custom, DenoSource::Custom),
+            (sidecar_path(), DenoSource::Sidecar),
+            (
+                installed_path().filter(|p| p.exists()),
+                DenoSource::Installed,
+            ),
+            (Some(PathBuf::from("deno")), DenoSource::System),
+        ];
+
+        let detected = candidates
+            .into_iter()
+            .filter_map(|(path, source)| path.map(|p| (p, source)))
+            .find_map(|(path, source)| probe_version(&path).map(|v| (path, source, v)));
+
+        let (program, source, version) = match detected {
+            Some((path, source, version)) => (Some(path), Some(source), Some(version)),
+            None => (None, None, None),
+        };
+
+        Self {
+            program,
+            source,
+            version,
+            // 不预先授予权限, 由 task_args 按任务与用户的决定授予
+            base_args: vec![
+                "run".to_string(),
+                "--no-check".to_string(),
+                "--no-prompt".to_string(),
+            ],
+        }
+    }
+
+    pub fn is_installed(&self) -> bool {
+        self.program.is_some()
+    }
+
+    fn info(&self) -> RuntimeInfo {
+        RuntimeInfo {
+            installed: self.program.is_some(),
+            source: self.source,
+            path: self
+                .program
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string()),
+            version: self.version.clone(),
+            bundled_version: DENO_VERSION.to_string(),
+            offline: settings::get().runtime.offline,
+        }
+    }
+
+    // 构建 Deno 命令, 依赖统一缓存到应用管理的 DENO_DIR
+    fn command(&self) -> std::io::Result<tokio::process::Command> {
+        let program = self.program.as_ref().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Deno 未安装，请在设置中安装运行时或手动安装 Deno: https://deno.land/#installation",
+            )
+        })?;
+
+        let mut cmd = tokio::process::Command::new(program);
+        if let Some(dir) = deno_dir() {
+            cmd.env("DENO_DIR", dir);
+        }
+        Ok(cmd)
+    }
+
+    // 缓存脚本的远程依赖, 指定锁文件时同时写入锁文件
+    pub async fn cache(&self, file: &Path, lock_file: Option<&Path>) -> std::io::Result<RunOutput> {
+        let mut cmd = self.command()?;
+        cmd.arg("cache");
+        if let Some(lock) = lock_file {
+            cmd.arg(format!("--lock={}", lock.to_string_lossy()));
+        }
+        if settings::get().runtime.offline {
+            cmd.arg("--cached-only");
+        }
+        Ok(cmd.arg(file).output().await?.into())
+    }
+
+    // 类型检查脚本, 诊断信息输出到 stderr
+    pub async fn check(&self, file: &Path) -> std::io::Result<RunOutput> {
+        let mut cmd = self.command()?;
+        cmd.env("NO_COLOR", "1").arg("check");
+        if settings::get().runtime.offline {
+            cmd.arg("--cached-only");
+        }
+        Ok(cmd.arg(file).output().await?.into())
+    }
+
+    // 以 JSON 格式输出脚本的 lint 结果
+    pub async fn lint(&self, file: &Path) -> std::io::Result<RunOutput> {
+        let mut cmd = self.command()?;
+        cmd.env("NO_COLOR", "1").args(["lint", "--json"]);
+        Ok(cmd.arg(file).output().await?.into())
+    }
+
+    // 脚本前加上控制台重定向与宿主调用
+    fn bootstrap(task: &DenoTask) -> String {
+        let prelude = CONSOLE_REDIRECT.replace("RPC_PREFIX", RPC_PREFIX);
+        format!("{}{}", prelude, task.script)
+    }
+
+    // 执行参数: 默认只能读取插件目录、写入数据目录与产物目录、读取注入的环境变量
+    fn task_args(&self, task: &DenoTask, script: &Path) -> Vec<String> {
+        let mut args = self.base_args.clone();
+        let mut defaults = vec![("read", plugins_dir().to_string_lossy().to_string())];
+        if let Some(ref dir) = task.data_dir {
+            defaults.push(("write", dir.to_string_lossy().to_string()));
+            defaults.push(("env", DATA_DIR_ENV.to_string()));
+        }
+        if let Some(ref dir) = task.artifacts_dir {
+            defaults.push(("write", dir.to_string_lossy().to_string()));
+        }
+        defaults.extend(task.env_vars.iter().map(|var| ("env", var.key.clone())));
+        args.extend(grants::flags(&defaults, &task.grants));
+        if settings::get().runtime.offline {
+            args.push("--cached-only".to_string());
+        }
+        if let Some(ref lock) = task.lock_file {
+            args.push(format!("--lock={}", lock.to_string_lossy()));
+            args.push("--frozen".to_string());
+        }
+        if let Some(mb) = settings::get().runtime.limits.memory_mb {
+            args.push(format!("--v8-flags=--max-old-space-size={}", mb));
+        }
+        args.push(script.to_string_lossy().to_string());
+        args
+    }
+
+    /// 执行时注入的环境变量名
+    pub(crate) fn env_keys(&self, task: &DenoTask) -> Vec<String> {
+        let mut keys = Vec::new();
+        if deno_dir().is_some() {
+            keys.push("DENO_DIR".to_string());
+        }
+        if task.data_dir.is_some() {
+            keys.push(DATA_DIR_ENV.to_string());
+        }
+        keys.extend(task.env_vars.iter().map(|var| var.key.clone()));
+        keys
+    }
+
+    /// 生成执行计划, 不写入文件也不启动进程
+    pub(crate) fn plan(&self, task: &DenoTask) -> TaskPlan {
+        let script = plugins_dir().join("temp_<id>.ts");
+        TaskPlan {
+            program: self
+                .program
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string()),
+            args: self.task_args(task, &script),
+            cwd: task
+                .data_dir
+                .as_ref()
+                .map(|dir| dir.to_string_lossy().to_string()),
+            script: Self::bootstrap(task),
+        }
+    }
+
+    /// 写入临时脚本并构建执行命令, 调用方负责在结束后删除返回的临时文件
+    pub(crate) fn prepare_task(
+        &self,
+        task: &DenoTask,
+    ) -> Result<(tokio::process::Command, PathBuf)> {
+        let mut cmd = self.command()?;
+
+        // 临时文件, 每次执行使用独立的文件名以便并发执行
+        let temp_file = plugins_dir().join(format!("temp_{}.ts", generate_id()));
+        fs::write(&temp_file, Self::bootstrap(task))?;
+        cmd.args(self.task_args(task, &temp_file));
+        if let Some(ref dir) = task.data_dir {
+            cmd.current_dir(dir).env(DATA_DIR_ENV, dir);
+        }
+        // 代理设置在前, 插件自己配置的同名变量优先
+        cmd.envs(proxy::env_vars(&task.grants));
+        for var in &task.env_vars {
+            cmd.env(&var.key, &var.value);
+        }
+        Ok((cmd, temp_file))
+    }
+
+    // 执行插件
+    pub async fn execute(&self, task: &DenoTask) -> Result<RunOutput> {
+        let (cmd, temp_file) = self.prepare_task(task)?;
+        let output = match task.bridge {
+            Some(ref bridge) => run_bridged(cmd, bridge).await,
+            None => run_limited(cmd, None).await,
+        };
+        let _ = fs::remove_file(temp_file);
+        output
+    }
+}
+
+// 设置子进程的标准输入输出与 CPU 时间上限
+fn prepare(cmd: &mut tokio::process::Command, stdin: Stdio, limits: &ResourceLimits) {
+    cmd.stdin(stdin)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    #[cfg(unix)]
+    if let Some(secs) = limits.cpu_seconds {
+        // 子进程中设置 CPU 时间上限, 超出后由系统终止
+        unsafe {
+            cmd.pre_exec(move || {
+                let limit = libc::rlimit {
+                    rlim_cur: secs as libc::rlim_t,
+                    rlim_max: secs as libc::rlim_t,
+                };
+                if libc::setrlimit(libc::RLIMIT_CPU, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = limits;
+}
+
+// 超时后丢弃 future, 子进程随之被终止
+async fn with_timeout<T>(
+    limits: &ResourceLimits,
+    future: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    match limits.timeout_secs {
+        Some(secs) => tokio::time::timeout(Duration::from_secs(secs), future)
+            .await
+            .map_err(|_| PluginError::Plugin(format!("执行超时 ({} 秒), 进程已终止", secs)))?,
+        None => future.await,
+    }
+}
+
+/// 在资源限制下运行插件进程, 超时后终止进程
+pub(crate) async fn run_limited(
+    mut cmd: tokio::process::Command,
+    input: Option<String>,
+) -> Result<RunOutput> {
+    let limits = settings::get().runtime.limits;
+    let stdin = if input.is_some() {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    };
+    prepare(&mut cmd, stdin, &limits);
+
+    let mut child = cmd.spawn()?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes()).await?;
+    }
+
+    with_timeout(&limits, async {
+        Ok(child.wait_with_output().await?.into())
+    })
+    .await
+}
+
+/// 运行插件进程并通过 stdio 响应宿主调用
+///
+/// stdout 中以 RPC_PREFIX 开头的行为宿主调用请求, 结果逐行写回 stdin, 其余输出作为执行结果
+pub(crate) async fn run_bridged(
+    mut cmd: tokio::process::Command,
+    bridge: &Bridge,
+) -> Result<RunOutput> {
+    let limits = settings::get().runtime.limits;
+    prepare(&mut cmd, Stdio::piped(), &limits);
+
+    let mut child = cmd.spawn()?;
+    let mut stdin = child.stdin.take().ok_or("无法打开插件进程的标准输入")?;
+    let stdout = child.stdout.take().ok_or("无法打开插件进程的标准输出")?;
+    let mut stderr = child.stderr.take().ok_or("无法打开插件进程的错误输出")?;
+
+    with_timeout(&limits, async {
+        // 单独读取 stderr, 避免缓冲区写满阻塞子进程
+        let stderr_task = tokio::spawn(async move {
+            let mut buffer = String::new();
+            let _ = stderr.read_to_string(&mut buffer).await;
+            buffer
+        });
+
+        let mut output = String::new();
+        let mut lines = BufReader::new(stdout).lines();
+        while let Some(line) = lines.next_line().await? {
+            let Some(request) = line.strip_prefix(RPC_PREFIX) else {
+                output.push_str(&line);
+                output.push('\n');
+                continue;
+            };
+            let response = bridge.respond(request).await;
+            stdin
+                .write_all(format!("{}\n", response).as_bytes())
+                .await?;
+            stdin.flush().await?;
+        }
+        drop(stdin);
+
+        let status = child.wait().await?;
+        Ok(RunOutput {
+            success: status.success(),
+            stdout: output,
+            stderr: stderr_task.await.unwrap_or_default(),
+        })
+    })
+    .await
+}
+
+// sidecar 与主程序位于同一目录, 打包时会去掉目标三元组后缀
+fn sidecar_path() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let path = exe
+        .parent()?
+        .join(format!("deno{}", std::env::consts::EXE_SUFFIX));
+    path.exists().then_some(path)
+}
+
+// 读取版本号, 如 "deno 2.1.4 (stable, release, x86_64-pc-windows-msvc)"
+fn probe_version(program: &PathBuf) -> Option<String> {
+    let output = Command::new(program).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .map(|v| v.to_string())
+}
+
+// 应用内安装的 Deno 路径
+fn installed_path() -> Option<PathBuf> {
+    let mut path = get_config_dir()?;
+    path.push("runtime");
+    path.push(format!("deno{}", std::env::consts::EXE_SUFFIX));
+    Some(path)
+}
+
+// 应用管理的依赖缓存目录
+fn deno_dir() -> Option<PathBuf> {
+    let mut path = get_config_dir()?;
+    path.push("runtime");
+    path.push("deno_dir");
+    Some(path)
+}
+
+// 当前平台对应的 Deno 发布包目标
+fn release_target() -> Option<&'static str> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("windows", "x86_64") => Some("x86_64-pc-windows-msvc"),
+        ("macos", "x86_64") => Some("x86_64-apple-darwin"),
+        ("macos", "aarch64") => Some("aarch64-apple-darwin"),
+        ("linux", "x86_64") => Some("x86_64-unknown-linux-gnu"),
+        ("linux", "aarch64") => Some("aarch64-unknown-linux-gnu"),
+        _ => None,
+    }
+}
+
+// 从 sha256sum 文件中取出哈希值, 兼容 `hash  file` 与 PowerShell Get-FileHash 格式
+fn parse_checksum(content: &str) -> Option<String> {
+    content
+        .split_whitespace()
+        .find(|token| token.len() == 64 && token.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|token| token.to_lowercase())
+}
+
+#[tauri::command]
+pub async fn runtime_info() -> Result<RuntimeInfo> {
+    Ok(deno().info())
+}
+
+/// 下载并安装 Deno 到配置目录, 通过 `runtime://install-progress` 事件报告进度
+#[tauri::command]
+pub async fn runtime_install(app: AppHandle) -> Result<RuntimeInfo> {
+    let target = release_target().ok_or("当前平台不支持自动安装 Deno")?;
+    let target_path = installed_path().ok_or("无法获取配置目录")?;
+    let url = format!(
+        "https://github.com/denoland/deno/releases/download/v{}/deno-{}.zip",
+        DENO_VERSION, target
+    );
+
+    let client = proxy::client();
+    let checksum = client
+        .get(format!("{}.sha256sum", url))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("获取校验文件失败: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("获取校验文件失败: {}", e))?;
+    let expected = parse_checksum(&checksum).ok_or("校验文件格式无效")?;
+
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("下载 Deno 失败: {}", e))?;
+    let total = response.content_length();
+    let mut downloaded: u64 = 0;
+    let mut bytes = Vec::with_capacity(total.unwrap_or(0) as usize);
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("下载 Deno 失败: {}", e))?;
+        downloaded += chunk.len() as u64;
+        bytes.extend_from_slice(&chunk);
+        let _ = app.emit(
+            "runtime://install-progress",
+            InstallProgress { downloaded, total },
+        );
+    }
+
+    let actual = format!("{:x}", Sha256::digest(&bytes));
+    if actual != expected {
+        return Err(PluginError::Plugin(format!(
+            "校验失败: 期望 {}, 实际 {}",
+            expected, actual
+        )));
+    }
+
+    // 解压可执行文件
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("解压失败: {}", e))?;
+    let mut entry = archive
+        .by_name(&format!("deno{}", std::env::consts::EXE_SUFFIX))
+        .map_err(|e| format!("解压失败: {}", e))?;
+    if let Some(dir) = target_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut out = fs::File::create(&target_path)?;
+    std::io::copy(&mut entry, &mut out)?;
+    drop(out);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&target_path, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(refresh())
+}
+
+// 重新检测运行时并替换当前实例
+fn refresh() -> RuntimeInfo {
+    python::refresh();
+    node::refresh();
+    let runtime = DenoRuntime::new();
+    let info = runtime.info();
+    *DENO_RUNTIME.write().unwrap() = runtime;
+    info
+}
+
+/// 重新检测 Deno、Python 与 Node.js, 无需重启即可使用新安装的运行时
+#[tauri::command]
+pub async fn runtime_refresh() -> Result<RuntimeInfo> {
+    Ok(refresh())
+}
+
+/// 设置自定义 Deno 路径, 传入 None 时恢复自动检测
+#[tauri::command]
+pub async fn runtime_set_path(path: Option<String>) -> Result<RuntimeInfo> {
+    let path = path.filter(|p| !p.trim().is_empty());
+    if let Some(ref p) = path {
+        if probe_version(&PathBuf::from(p)).is_none() {
+            return Err(PluginError::Plugin(format!(
+                "无效的 Deno 可执行文件: {}",
+                p
+            )));
+        }
+    }
+    settings::update(|s| s.runtime.deno_path = path)?;
+    Ok(refresh())
+}
+
+/// 读取插件进程的资源限制
+#[tauri::command]
+pub async fn runtime_limits_get() -> Result<ResourceLimits> {
+    Ok(settings::get().runtime.limits)
+}
+
+/// 设置插件进程的资源限制
+#[tauri::command]
+pub async fn runtime_limits_set(limits: ResourceLimits) -> Result<ResourceLimits> {
+    let settings = settings::update(|s| s.runtime.limits = limits)?;
+    Ok(settings.runtime.limits)
+}
+
+/// 切换离线模式, 开启后只使用已缓存的依赖
+#[tauri::command]
+pub async fn runtime_set_offline(enabled: bool) -> Result<RuntimeInfo> {
+    settings::update(|s| s.runtime.offline = enabled)?;
+    Ok(deno().info())
+}
@@ -0,0 +1,331 @@
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use super::approval;
+use super::db::with_db;
+use super::deno::{find_plugin, PluginError, Result};
+use super::notification;
+use crate::utils::gen::generate_id;
+
+// 每个定时任务保留的最大运行记录数
+const MAX_RUN_RECORDS: usize = 200;
+// 默认返回的运行记录数
+const DEFAULT_RUN_LIMIT: usize = 50;
+// 结果保留的最大字符数
+const MAX_OUTPUT_CHARS: usize = 1000;
+
+/// 定时执行的工具调用, cron 与 interval_secs 二选一
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Schedule {
+    pub id: String,
+    pub plugin_id: String,
+    pub tool: String,
+    pub args: Value,
+    /// cron 表达式, 支持 5 段 (分 时 日 月 周) 或带秒的 6 段
+    pub cron: Option<String>,
+    /// 固定间隔 (秒)
+    pub interval_secs: Option<u64>,
+    pub created_at: i64,
+    pub last_run: Option<i64>,
+    pub next_run: Option<i64>,
+}
+
+/// 定时任务的一次运行记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduleRun {
+    pub time: i64,
+    pub success: bool,
+    pub duration_ms: u64,
+    pub output: String,
+}
+
+static SCHEDULES: Lazy<Mutex<Option<Vec<Schedule>>>> = Lazy::new(|| Mutex::new(None));
+// 正在运行的任务, 上一次未结束时跳过本次触发
+static RUNNING: Lazy<std::sync::Mutex<HashSet<String>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashSet::new()));
+
+/// 写入一个定时任务
+pub(crate) fn insert_schedule(conn: &Connection, schedule: &Schedule) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO schedules (id, plugin_id, data) VALUES (?1, ?2, ?3)",
+        params![
+            schedule.id,
+            schedule.plugin_id,
+            serde_json::to_string(schedule)?
+        ],
+    )?;
+    Ok(())
+}
+
+/// 写入一条运行记录
+pub(crate) fn insert_run(conn: &Connection, id: &str, run: &ScheduleRun) -> Result<()> {
+    conn.execute(
+        "INSERT INTO schedule_runs (schedule_id, time, data) VALUES (?1, ?2, ?3)",
+        params![id, run.time, serde_json::to_string(run)?],
+    )?;
+    Ok(())
+}
+
+fn read_schedules() -> Result<Vec<Schedule>> {
+    with_db(|conn| {
+        let mut stmt = conn.prepare("SELECT data FROM schedules ORDER BY rowid")?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut schedules = Vec::new();
+        for data in rows {
+            schedules.push(serde_json::from_str(&data)?);
+        }
+        Ok(schedules)
+    })
+}
+
+fn write_schedules(schedules: &[Schedule]) -> Result<()> {
+    with_db(|conn| {
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM schedules", [])?;
+        for schedule in schedules {
+            insert_schedule(&tx, schedule)?;
+        }
+        tx.commit()?;
+        Ok(())
+    })
+}
+
+fn delete_runs(id: &str) -> Result<()> {
+    with_db(|conn| {
+        conn.execute("DELETE FROM schedule_runs WHERE schedule_id = ?1", [id])?;
+        Ok(())
+    })
+}
+
+// 在缓存的任务列表上执行修改并写回磁盘
+async fn with_schedules<T>(f: impl FnOnce(&mut Vec<Schedule>) -> Result<T>) -> Result<T> {
+    let mut cache = SCHEDULES.lock().await;
+    if cache.is_none() {
+        *cache = Some(read_schedules()?);
+    }
+    let schedules = cache.as_mut().unwrap();
+    let before = serde_json::to_string(schedules)?;
+    let result = f(schedules)?;
+    if serde_json::to_string(schedules)? != before {
+        write_schedules(schedules)?;
+    }
+    Ok(result)
+}
+
+// 5 段表达式补充秒字段
+fn parse_cron(expr: &str) -> Result<cron::Schedule> {
+    let expr = expr.trim();
+    let full = if expr.split_whitespace().count() == 5 {
+        format!("0 {}", expr)
+    } else {
+        expr.to_string()
+    };
+    cron::Schedule::from_str(&full)
+        .map_err(|e| PluginError::Plugin(format!("无效的 cron 表达式: {}", e)))
+}
+
+// 计算下一次运行时间 (毫秒时间戳)
+fn next_run(schedule: &Schedule, after: DateTime<Utc>) -> Result<Option<i64>> {
+    if let Some(ref expr) = schedule.cron {
+        return Ok(parse_cron(expr)?
+            .after(&after.with_timezone(&chrono::Local))
+            .next()
+            .map(|time| time.timestamp_millis()));
+    }
+    Ok(schedule
+        .interval_secs
+        .map(|secs| after.timestamp_millis() + secs as i64 * 1000))
+}
+
+fn append_run(id: &str, run: &ScheduleRun) -> Result<()> {
+    with_db(|conn| {
+        insert_run(conn, id, run)?;
+        // 只保留最近的记录
+        conn.execute(
+            "DELETE FROM schedule_runs WHERE schedule_id = ?1 AND id NOT IN
+                 (SELECT id FROM schedule_runs WHERE schedule_id = ?1 ORDER BY id DESC LIMIT ?2)",
+            params![id, MAX_RUN_RECORDS as i64],
+        )?;
+        Ok(())
+    })
+}
+
+// 执行一次定时任务并记录结果
+async fn run_schedule(schedule: Schedule) {
+    let started = std::time::Instant::now();
+    // 无人值守, 需要确认的工具直接拒绝
+    let result = approval::execute_confirmed(
+        &schedule.plugin_id,
+        &schedule.tool,
+        schedule.args.clone(),
+        "定时任务",
+        false,
+    )
+    .await;
+    let (success, output) = match result {
+        Ok(result) => (true, result.result.to_string()),
+        Err(err) => (false, err.to_string()),
+    };
+    tracing::info!(schedule = %schedule.id, plugin = %schedule.plugin_id, success, "定时任务已执行");
+    let run = ScheduleRun {
+        time: Utc::now().timestamp_millis(),
+        success,
+        duration_ms: started.elapsed().as_millis() as u64,
+        output: output.chars().take(MAX_OUTPUT_CHARS).collect(),
+    };
+    let _ = append_run(&schedule.id, &run);
+    RUNNING.lock().unwrap().remove(&schedule.id);
+}
+
+// 取出到期的任务并更新下一次运行时间
+async fn take_due() -> Result<Vec<Schedule>> {
+    let now = Utc::now();
+    with_schedules(|schedules| {
+        let mut due = Vec::new();
+        for schedule in schedules.iter_mut() {
+            if schedule
+                .next_run
+                .is_some_and(|time| time <= now.timestamp_millis())
+            {
+                schedule.last_run = Some(now.timestamp_millis());
+                schedule.next_run = next_run(schedule, now)?;
+                due.push(schedule.clone());
+            }
+        }
+        Ok(due)
+    })
+    .await
+}
+
+/// 删除插件的全部定时任务
+pub(crate) async fn remove_for_plugin(plugin_id: &str) -> Result<()> {
+    let removed = with_schedules(|schedules| {
+        let (removed, kept): (Vec<_>, Vec<_>) =
+            schedules.drain(..).partition(|s| s.plugin_id == plugin_id);
+        *schedules = kept;
+        Ok(removed)
+    })
+    .await?;
+    for schedule in removed {
+        delete_runs(&schedule.id)?;
+    }
+    Ok(())
+}
+
+/// 定时任务循环, 应用启动时运行, 同时负责到期的提醒
+pub async fn run_scheduler() {
+    // 应用关闭期间错过的任务不补跑, 从当前时间重新计算
+    let now = Utc::now();
+    let _ = with_schedules(|schedules| {
+        for schedule in schedules.iter_mut() {
+            schedule.next_run = next_run(schedule, now)?;
+        }
+        Ok(())
+    })
+    .await;
+
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        ticker.tick().await;
+        notification::fire_due().await;
+        for schedule in take_due().await.unwrap_or_default() {
+            if !RUNNING.lock().unwrap().insert(schedule.id.clone()) {
+                continue;
+            }
+            tokio::spawn(run_schedule(schedule));
+        }
+    }
+}
+
+/// 创建定时任务, cron 与 interval_secs 需且只能指定一个
+#[tauri::command]
+pub async fn schedule_create(
+    plugin_id: String,
+    tool: String,
+    args: Option<Value>,
+    cron: Option<String>,
+    interval_secs: Option<u64>,
+) -> Result<Schedule> {
+    let plugin = find_plugin(&plugin_id).await?;
+    if !plugin.tools.iter().any(|t| t.name == tool) {
+        return Err(PluginError::Plugin(format!("未知函数: {}", tool)));
+    }
+    match (&cron, interval_secs) {
+        (Some(_), None) | (None, Some(_)) => {}
+        _ => {
+            return Err(PluginError::Plugin(
+                "cron 与 interval_secs 需且只能指定一个".to_string(),
+            ))
+        }
+    }
+    if interval_secs == Some(0) {
+        return Err(PluginError::Plugin("间隔必须大于 0".to_string()));
+    }
+
+    let now = Utc::now();
+    let mut schedule = Schedule {
+        id: generate_id(),
+        plugin_id,
+        tool,
+        args: args.unwrap_or_else(|| serde_json::json!({})),
+        cron,
+        interval_secs,
+        created_at: now.timestamp_millis(),
+        last_run: None,
+        next_run: None,
+    };
+    schedule.next_run = next_run(&schedule, now)?;
+
+    let created = schedule.clone();
+    with_schedules(move |schedules| {
+        schedules.push(schedule);
+        Ok(())
+    })
+    .await?;
+    Ok(created)
+}
+
+#[tauri::command]
+pub async fn schedule_list() -> Result<Vec<Schedule>> {
+    with_schedules(|schedules| Ok(schedules.clone())).await
+}
+
+#[tauri::command]
+pub async fn schedule_delete(id: String) -> Result<()> {
+    with_schedules(|schedules| {
+        schedules.retain(|s| s.id != id);
+        Ok(())
+    })
+    .await?;
+    delete_runs(&id)
+}
+
+/// 读取定时任务最近的运行记录
+#[tauri::command]
+pub async fn schedule_history(id: String, limit: Option<usize>) -> Result<Vec<ScheduleRun>> {
+    let limit = limit.unwrap_or(DEFAULT_RUN_LIMIT) as i64;
+    let mut runs: Vec<ScheduleRun> = with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT data FROM schedule_runs WHERE schedule_id = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let rows = stmt
+            .query_map(params![id, limit], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows
+            .iter()
+            .filter_map(|data| serde_json::from_str(data).ok())
+            .collect())
+    })?;
+    // 与之前一致, 按时间正序返回
+    runs.reverse();
+    Ok(runs)
+}
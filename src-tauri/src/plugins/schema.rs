@@ -0,0 +1,31 @@
+use jsonschema::JSONSchema;
+use serde_json::Value;
+
+use super::deno::{PluginError, Result, Tool};
+
+/// 按工具声明的 JSON Schema 校验参数, 未声明或关闭校验时直接通过
+pub(crate) fn validate_args(tool: &Tool, args: &Value) -> Result<()> {
+    if tool.skip_validation {
+        return Ok(());
+    }
+    let Some(schema) = tool.parameters.as_ref() else {
+        return Ok(());
+    };
+
+    let compiled = JSONSchema::compile(schema)
+        .map_err(|e| PluginError::Plugin(format!("工具参数 schema 无效: {}: {}", tool.name, e)))?;
+    if let Err(errors) = compiled.validate(args) {
+        let errors = errors
+            .map(|err| {
+                let path = err.instance_path.to_string();
+                if path.is_empty() {
+                    err.to_string()
+                } else {
+                    format!("{}: {}", path, err)
+                }
+            })
+            .collect();
+        return Err(PluginError::InvalidArgs(errors));
+    }
+    Ok(())
+}
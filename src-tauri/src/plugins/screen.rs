@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::artifacts::{self, Artifact};
+use super::deno::{PluginError, Result};
+use crate::utils::gen::generate_id;
+
+const FILE_NAME: &str = "screenshot.png";
+// 隐藏应用窗口后等待重绘的时间
+const HIDE_DELAY: Duration = Duration::from_millis(300);
+
+/// 截图方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureMode {
+    /// 全部屏幕
+    #[default]
+    Screen,
+    /// 当前活动窗口
+    Window,
+    /// 由用户框选区域
+    Region,
+}
+
+/// 截图结果, 保存在产物目录中, 可作为对话附件或交给 OCR 识别
+#[derive(Debug, Serialize, Clone)]
+pub struct ScreenCapture {
+    /// 产物目录的 id
+    pub id: String,
+    pub mode: CaptureMode,
+    #[serde(flatten)]
+    pub artifact: Artifact,
+}
+
+fn capture_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("截图失败: {}", message))
+}
+
+// 依次尝试候选命令, 程序不存在时换下一个
+fn run_first(candidates: &[(&str, Vec<String>)], output: &Path) -> Result<()> {
+    let mut missing = Vec::new();
+    for (program, args) in candidates {
+        match Command::new(program).args(args).output() {
+            Ok(result) if result.status.success() && output.is_file() => return Ok(()),
+            Ok(result) => {
+                let stderr = String::from_utf8_lossy(&result.stderr).trim().to_string();
+                return Err(capture_error(if stderr.is_empty() {
+                    "已取消截图".to_string()
+                } else {
+                    stderr
+                }));
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => missing.push(*program),
+            Err(err) => return Err(capture_error(err)),
+        }
+    }
+    Err(capture_error(format!(
+        "未找到截图工具, 请安装以下任一程序: {}",
+        missing.join(", ")
+    )))
+}
+
+#[cfg(target_os = "macos")]
+fn capture(mode: CaptureMode, output: &Path) -> Result<()> {
+    // -x 不播放快门声; 窗口模式由用户点选窗口, Esc 取消时不会生成文件
+    let flags: &[&str] = match mode {
+        CaptureMode::Screen => &["-x"],
+        CaptureMode::Window => &["-x", "-o", "-i", "-w"],
+        CaptureMode::Region => &["-x", "-i", "-s"],
+    };
+    let mut args: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+    args.push(output.to_string_lossy().to_string());
+    run_first(&[("screencapture", args)], output)
+}
+
+#[cfg(windows)]
+fn capture(mode: CaptureMode, output: &Path) -> Result<()> {
+    let path = output.to_string_lossy().replace('\'', "''");
+    let script = match mode {
+        CaptureMode::Screen => format!(
+            r#"Add-Type -AssemblyName System.Windows.Forms, System.Drawing
+$b = [System.Windows.Forms.SystemInformation]::VirtualScreen
+$bmp = New-Object System.Drawing.Bitmap $b.Width, $b.Height
+$g = [System.Drawing.Graphics]::FromImage($bmp)
+$g.CopyFromScreen($b.Left, $b.Top, 0, 0, $bmp.Size)
+$bmp.Save('{path}', [System.Drawing.Imaging.ImageFormat]::Png)"#
+        ),
+        CaptureMode::Window => format!(
+            r#"Add-Type -AssemblyName System.Drawing
+Add-Type @'
+using System;
+using System.Runtime.InteropServices;
+public struct RECT {{ public int Left, Top, Right, Bottom; }}
+public static class Win {{
+    [DllImport("user32.dll")] public static extern IntPtr GetForegroundWindow();
+    [DllImport("user32.dll")] public static extern bool GetWindowRect(IntPtr h, out RECT r);
+}}
+'@
+$r = New-Object RECT
+[void][Win]::GetWindowRect([Win]::GetForegroundWindow(), [ref]$r)
+$w = $r.Right - $r.Left; $h = $r.Bottom - $r.Top
+$bmp = New-Object System.Drawing.Bitmap $w, $h
+$g = [System.Drawing.Graphics]::FromImage($bmp)
+$g.CopyFromScreen($r.Left, $r.Top, 0, 0, $bmp.Size)
+$bmp.Save('{path}', [System.Drawing.Imaging.ImageFormat]::Png)"#
+        ),
+        // 系统截图工具将结果放入剪贴板, 等待图片出现后保存
+        CaptureMode::Region => format!(
+            r#"Add-Type -AssemblyName System.Windows.Forms, System.Drawing
+[System.Windows.Forms.Clipboard]::Clear()
+Start-Process 'ms-screenclip:'
+for ($i = 0; $i -lt 600; $i++) {{
+    Start-Sleep -Milliseconds 100
+    if ([System.Windows.Forms.Clipboard]::ContainsImage()) {{
+        [System.Windows.Forms.Clipboard]::GetImage().Save('{path}', [System.Drawing.Imaging.ImageFormat]::Png)
+        exit 0
+    }}
+}}
+exit 1"#
+        ),
+    };
+    let args = vec![
+        "-NoProfile".to_string(),
+        "-STA".to_string(),
+        "-Command".to_string(),
+        script,
+    ];
+    run_first(&[("powershell", args)], output)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn capture(mode: CaptureMode, output: &Path) -> Result<()> {
+    let path = output.to_string_lossy().to_string();
+    let args = |flags: &[&str]| {
+        let mut args: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+        args.push(path.clone());
+        args
+    };
+    // Wayland 上的 grim 需要 slurp 选择区域, 通过 sh 组合
+    let grim_region = vec![
+        "-c".to_string(),
+        r#"grim -g "$(slurp)" "$0""#.to_string(),
+        path.clone(),
+    ];
+    let candidates = match mode {
+        CaptureMode::Screen => vec![
+            ("grim", args(&[])),
+            ("gnome-screenshot", args(&["-f"])),
+            ("spectacle", args(&["-b", "-n", "-f", "-o"])),
+            ("maim", args(&[])),
+            ("scrot", args(&["-o"])),
+        ],
+        CaptureMode::Window => vec![
+            ("gnome-screenshot", args(&["-w", "-f"])),
+            ("spectacle", args(&["-b", "-n", "-a", "-o"])),
+            ("scrot", args(&["-u", "-o"])),
+        ],
+        CaptureMode::Region => vec![
+            ("gnome-screenshot", args(&["-a", "-f"])),
+            ("spectacle", args(&["-b", "-n", "-r", "-o"])),
+            ("maim", args(&["-s"])),
+            ("scrot", args(&["-s", "-o"])),
+            ("sh", grim_region),
+        ],
+    };
+    run_first(&candidates, output)
+}
+
+/// 截取屏幕, 结果保存为 PNG 产物
+///
+/// # 参数
+/// * `mode` - screen、window 或 region, 默认 screen
+/// * `hide_app` - 截图前隐藏本应用的窗口, 默认 true
+#[tauri::command]
+pub async fn screen_capture(
+    app: AppHandle,
+    mode: Option<CaptureMode>,
+    hide_app: Option<bool>,
+) -> Result<ScreenCapture> {
+    let mode = mode.unwrap_or_default();
+    let id = generate_id();
+    let dir = artifacts::create_dir(&id)?;
+    let output = dir.join(FILE_NAME);
+    let hidden: Vec<_> = if hide_app.unwrap_or(true) {
+        app.webview_windows()
+            .into_values()
+            .filter(|window| window.is_visible().unwrap_or(false))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    for window in &hidden {
+        let _ = window.hide();
+    }
+    if !hidden.is_empty() {
+        tokio::time::sleep(HIDE_DELAY).await;
+    }
+
+    let path = output.clone();
+    let result = tokio::task::spawn_blocking(move || capture(mode, &path))
+        .await
+        .map_err(capture_error)
+        .and_then(|result| result);
+
+    for window in &hidden {
+        let _ = window.show();
+    }
+    if let Err(err) = result {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(err);
+    }
+    Ok(ScreenCapture {
+        id,
+        mode,
+        artifact: Artifact {
+            name: FILE_NAME.to_string(),
+            path: output.to_string_lossy().to_string(),
+            mime: "image/png".to_string(),
+            size: fs::metadata(&output)?.len(),
+        },
+    })
+}
@@ -0,0 +1,307 @@
+use rusqlite::{Connection, ToSql};
+use serde::{Deserialize, Serialize};
+
+use super::db::with_db;
+use super::deno::Result;
+
+// 默认返回条数
+const DEFAULT_LIMIT: usize = 20;
+// 摘要的字符数
+const SNIPPET_CHARS: usize = 120;
+// trigram 分词只能匹配不少于 3 个字符的词, 更短的词改用 LIKE
+const MIN_FTS_CHARS: usize = 3;
+
+/// 搜索范围
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchScope {
+    /// 对话消息
+    Message,
+    /// 会话标题
+    Conversation,
+    /// 插件名称与描述
+    Plugin,
+    /// 工具名称与描述
+    Tool,
+    /// 工具执行结果
+    ToolResult,
+}
+
+/// 搜索结果
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchHit {
+    pub scope: SearchScope,
+    /// 消息 id、会话 id、插件 id、工具名或执行 id
+    pub id: String,
+    /// 所属的会话或插件
+    pub parent: Option<String>,
+    pub title: String,
+    pub snippet: String,
+    /// 摘要中匹配部分的字符区间 [start, end)
+    pub highlights: Vec<(usize, usize)>,
+    pub time: Option<i64>,
+}
+
+// 各范围对应的全文索引与原表
+struct Source {
+    scope: SearchScope,
+    index: &'static str,
+    table: &'static str,
+    rowid: &'static str,
+    // 依次为 id、parent、title、正文、时间
+    columns: &'static str,
+    joins: &'static str,
+    // LIKE 匹配的文本
+    text: &'static str,
+    order: &'static str,
+}
+
+const SOURCES: [Source; 5] = [
+    Source {
+        scope: SearchScope::Message,
+        index: "search_messages",
+        table: "chat_messages m",
+        rowid: "m.id",
+        columns: "CAST(m.id AS TEXT), m.conversation_id, c.title, m.content, m.created_at",
+        joins: "JOIN conversations c ON c.id = m.conversation_id",
+        text: "m.content",
+        order: "m.created_at DESC",
+    },
+    Source {
+        scope: SearchScope::Conversation,
+        index: "search_conversations",
+        table: "conversations c",
+        rowid: "c.rowid",
+        columns: "c.id, NULL, c.title, c.title, c.updated_at",
+        joins: "",
+        text: "c.title",
+        order: "c.updated_at DESC",
+    },
+    Source {
+        scope: SearchScope::Plugin,
+        index: "search_plugins",
+        table: "plugins p",
+        rowid: "p.rowid",
+        columns: "p.id, NULL, p.name, COALESCE(p.description, ''), NULL",
+        joins: "",
+        text: "p.name || ' ' || COALESCE(p.description, '')",
+        order: "p.name",
+    },
+    Source {
+        scope: SearchScope::Tool,
+        index: "search_tools",
+        table: "tools t",
+        rowid: "t.rowid",
+        columns: "t.name, t.plugin_id, t.name, t.description, NULL",
+        joins: "",
+        text: "t.name || ' ' || t.description",
+        order: "t.name",
+    },
+    Source {
+        scope: SearchScope::ToolResult,
+        index: "search_results",
+        table: "execution_history h",
+        rowid: "h.id",
+        columns: "COALESCE(h.execution_id, CAST(h.id AS TEXT)), h.plugin_id, \
+                  h.plugin_id || '/' || h.tool, h.output, h.time",
+        joins: "",
+        text: "h.output",
+        order: "h.time DESC",
+    },
+];
+
+/// 重建全部索引
+pub(crate) fn rebuild(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "DELETE FROM search_messages;
+         INSERT INTO search_messages (rowid, body) SELECT id, content FROM chat_messages;
+         DELETE FROM search_conversations;
+         INSERT INTO search_conversations (rowid, title) SELECT rowid, title FROM conversations;
+         DELETE FROM search_plugins;
+         INSERT INTO search_plugins (rowid, name, description)
+             SELECT rowid, name, COALESCE(description, '') FROM plugins;
+         DELETE FROM search_tools;
+         INSERT INTO search_tools (rowid, name, description)
+             SELECT rowid, name, description FROM tools;
+         DELETE FROM search_results;
+         INSERT INTO search_results (rowid, body) SELECT id, output FROM execution_history;",
+    )?;
+    Ok(())
+}
+
+// 每个词作为短语匹配, 避免查询中的符号被当作 FTS 语法
+fn fts_query(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn like_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn chars_match(text: &[char], at: usize, term: &[char]) -> bool {
+    at + term.len() <= text.len()
+        && text[at..at + term.len()]
+            .iter()
+            .zip(term)
+            .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+}
+
+// 截取第一个匹配附近的文本, 并标出其中全部匹配的位置
+fn snippet(text: &str, terms: &[String]) -> (String, Vec<(usize, usize)>) {
+    let chars: Vec<char> = text.chars().collect();
+    let terms: Vec<Vec<char>> = terms.iter().map(|t| t.chars().collect()).collect();
+    let first = (0..chars.len())
+        .find(|&i| terms.iter().any(|t| chars_match(&chars, i, t)))
+        .unwrap_or(0);
+    let start = first.saturating_sub(SNIPPET_CHARS / 4);
+    let end = (start + SNIPPET_CHARS).min(chars.len());
+    let window = &chars[start..end];
+
+    let mut highlights = Vec::new();
+    let mut i = 0;
+    while i < window.len() {
+        match terms.iter().find(|t| chars_match(window, i, t)) {
+            Some(term) => {
+                highlights.push((i, i + term.len()));
+                i += term.len();
+            }
+            None => i += 1,
+        }
+    }
+    let mut snippet: String = window.iter().collect();
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    if start > 0 {
+        snippet.insert(0, '…');
+        highlights = highlights
+            .into_iter()
+            .map(|(s, e)| (s + 1, e + 1))
+            .collect();
+    }
+    (snippet, highlights)
+}
+
+type Row = (String, Option<String>, String, String, Option<i64>, f64);
+
+fn query_source(
+    conn: &Connection,
+    source: &Source,
+    terms: &[String],
+    limit: usize,
+) -> Result<Vec<Row>> {
+    let fts = terms.iter().all(|t| t.chars().count() >= MIN_FTS_CHARS);
+    let (sql, values): (String, Vec<String>) = if fts {
+        (
+            format!(
+                "SELECT {columns}, {index}.rank FROM {index} JOIN {table} ON {rowid} = {index}.rowid
+                 {joins} WHERE {index} MATCH ?1 ORDER BY {index}.rank LIMIT {limit}",
+                columns = source.columns,
+                index = source.index,
+                table = source.table,
+                rowid = source.rowid,
+                joins = source.joins,
+                limit = limit
+            ),
+            vec![fts_query(terms)],
+        )
+    } else {
+        let conditions: Vec<String> = (1..=terms.len())
+            .map(|i| format!("{} LIKE ?{} ESCAPE '\\'", source.text, i))
+            .collect();
+        (
+            format!(
+                "SELECT {}, 0.0 FROM {} {} WHERE {} ORDER BY {} LIMIT {}",
+                source.columns,
+                source.table,
+                source.joins,
+                conditions.join(" AND "),
+                source.order,
+                limit
+            ),
+            terms.iter().map(|t| like_pattern(t)).collect(),
+        )
+    };
+    let params: Vec<&dyn ToSql> = values.iter().map(|v| v as &dyn ToSql).collect();
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt
+        .query_map(params.as_slice(), |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+/// 全文搜索消息、会话、插件、工具与执行结果
+///
+/// # 参数
+/// * `query` - 以空格分隔的关键词, 需全部匹配
+/// * `scopes` - 搜索范围, 为空时搜索全部
+/// * `limit` - 最多返回的条数
+#[tauri::command]
+pub async fn search(
+    query: String,
+    scopes: Option<Vec<SearchScope>>,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_string).collect();
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(1);
+    let scopes = scopes.filter(|s| !s.is_empty());
+
+    let mut rows = Vec::new();
+    with_db(|conn| {
+        for source in SOURCES.iter().filter(|s| {
+            scopes
+                .as_ref()
+                .map_or(true, |scopes| scopes.contains(&s.scope))
+        }) {
+            for row in query_source(conn, source, &terms, limit)? {
+                rows.push((source.scope, row));
+            }
+        }
+        Ok(())
+    })?;
+    // bm25 越小越相关
+    rows.sort_by(|a, b| a.1 .5.total_cmp(&b.1 .5));
+    rows.truncate(limit);
+
+    Ok(rows
+        .into_iter()
+        .map(|(scope, (id, parent, title, body, time, _))| {
+            let (snippet, highlights) = snippet(&body, &terms);
+            SearchHit {
+                scope,
+                id,
+                parent,
+                title,
+                snippet,
+                highlights,
+                time,
+            }
+        })
+        .collect())
+}
+
+/// 重建搜索索引
+#[tauri::command]
+pub async fn search_reindex() -> Result<()> {
+    with_db(|conn| rebuild(conn))
+}
@@ -0,0 +1,282 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use rusqlite::params;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use super::db::with_db;
+use super::deno::{PluginError, Result};
+use crate::utils::file::{get_config_dir, write_atomic};
+use crate::utils::workspace::{self, DEFAULT_WORKSPACE};
+
+// 系统密钥链中的服务名
+const SERVICE: &str = "com.wangenius.ghostie";
+// 密钥链不可用时 (如没有 Secret Service 的 Linux 或无图形界面的会话) 改用配置目录中的加密文件,
+// 主密钥单独保存在只有当前用户可读的文件中
+const FILE_STORE: &str = "secrets.enc";
+const MASTER_KEY_FILE: &str = "secrets.key";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+// 加密数据库中内容使用的数据密钥, 本身保存在密钥链或加密文件中
+const INTERNAL_SCOPE: &str = "_internal";
+const DATA_KEY: &str = "data_key";
+const SEALED_PREFIX: &str = "sealed:";
+
+// 已读取的密钥, 避免每次执行都访问密钥链
+static CACHE: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+// 密钥链不可用后不再重试, 避免每次都等待超时
+static KEYRING_UNAVAILABLE: AtomicBool = AtomicBool::new(false);
+static FILE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+// 全局变量以变量名为账户, 插件变量加上插件 id 前缀
+// 非默认工作区再加上工作区前缀, 不同工作区的密钥互不可见
+fn account(scope: Option<&str>, key: &str) -> String {
+    let account = match scope {
+        Some(id) => format!("{}/{}", id, key),
+        None => key.to_string(),
+    };
+    let workspace = workspace::current();
+    if workspace == DEFAULT_WORKSPACE {
+        account
+    } else {
+        format!("{}:{}", workspace, account)
+    }
+}
+
+fn keyring_error(err: keyring::Error) -> PluginError {
+    PluginError::Plugin(format!("系统密钥链出错: {}", err))
+}
+
+fn store_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("加密密钥文件不可用: {}", message))
+}
+
+// 在密钥链上操作, 返回 None 表示密钥链不可用, 调用方改用加密文件
+fn with_keyring<T>(
+    account: &str,
+    f: impl FnOnce(&keyring::Entry) -> keyring::Result<T>,
+) -> Result<Option<T>> {
+    if KEYRING_UNAVAILABLE.load(Ordering::Relaxed) {
+        return Ok(None);
+    }
+    match keyring::Entry::new(SERVICE, account).and_then(|entry| f(&entry)) {
+        Ok(value) => Ok(Some(value)),
+        Err(err @ (keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_))) => {
+            tracing::warn!(error = %err, "系统密钥链不可用, 改用加密文件保存密钥");
+            KEYRING_UNAVAILABLE.store(true, Ordering::Relaxed);
+            Ok(None)
+        }
+        Err(err) => Err(keyring_error(err)),
+    }
+}
+
+fn encrypt(key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(store_error)?;
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|_| store_error("加密失败"))?;
+    Ok([&nonce[..], &ciphertext].concat())
+}
+
+fn decrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < NONCE_LEN {
+        return Err(store_error("数据已损坏"));
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(store_error)?;
+    cipher
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| store_error("解密失败, 主密钥与加密文件不匹配"))
+}
+
+fn store_path(name: &str) -> Result<PathBuf> {
+    Ok(get_config_dir()
+        .ok_or_else(|| store_error("无法获取配置目录"))?
+        .join(name))
+}
+
+// 读取主密钥, 不存在时生成
+fn master_key() -> Result<Vec<u8>> {
+    let path = store_path(MASTER_KEY_FILE)?;
+    if let Ok(key) = fs::read(&path) {
+        if key.len() == KEY_LEN {
+            return Ok(key);
+        }
+        return Err(store_error("主密钥文件已损坏"));
+    }
+    let mut key = vec![0u8; KEY_LEN];
+    rand::thread_rng().fill_bytes(&mut key);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&path)?;
+    std::io::Write::write_all(&mut file, &key)?;
+    file.sync_all()?;
+    Ok(key)
+}
+
+fn load_file() -> Result<HashMap<String, String>> {
+    let path = store_path(FILE_STORE)?;
+    let Ok(data) = fs::read(&path) else {
+        return Ok(HashMap::new());
+    };
+    let plaintext = decrypt(&master_key()?, &data)?;
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+fn save_file(values: &HashMap<String, String>) -> Result<()> {
+    let data = encrypt(&master_key()?, &serde_json::to_vec(values)?)?;
+    write_atomic(&store_path(FILE_STORE)?, &data)?;
+    Ok(())
+}
+
+// 修改加密文件中的一项, value 为 None 时删除
+fn update_file(account: &str, value: Option<&str>) -> Result<()> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    if value.is_none() && !store_path(FILE_STORE)?.exists() {
+        return Ok(());
+    }
+    let mut values = load_file()?;
+    let changed = match value {
+        Some(value) => {
+            values.insert(account.to_string(), value.to_string()) != Some(value.to_string())
+        }
+        None => values.remove(account).is_some(),
+    };
+    if changed {
+        save_file(&values)?;
+    }
+    Ok(())
+}
+
+fn file_get(account: &str) -> Result<Option<String>> {
+    let _guard = FILE_LOCK.lock().unwrap();
+    Ok(load_file()?.remove(account))
+}
+
+/// 读取密钥, 不存在时返回空字符串
+pub(crate) fn get(scope: Option<&str>, key: &str) -> Result<String> {
+    let account = account(scope, key);
+    if let Some(value) = CACHE.lock().unwrap().get(&account) {
+        return Ok(value.clone());
+    }
+    let stored = with_keyring(&account, |entry| match entry.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err),
+    })?;
+    let value = match stored {
+        Some(Some(value)) => value,
+        // 密钥链中没有时再查加密文件, 密钥链不可用期间写入的值保存在那里
+        _ => file_get(&account)?.unwrap_or_default(),
+    };
+    CACHE.lock().unwrap().insert(account, value.clone());
+    Ok(value)
+}
+
+/// 写入密钥
+pub(crate) fn set(scope: Option<&str>, key: &str, value: &str) -> Result<()> {
+    let account = account(scope, key);
+    if with_keyring(&account, |entry| entry.set_password(value))?.is_some() {
+        update_file(&account, None)?;
+    } else {
+        update_file(&account, Some(value))?;
+    }
+    CACHE.lock().unwrap().insert(account, value.to_string());
+    Ok(())
+}
+
+/// 删除密钥, 不存在时忽略
+pub(crate) fn delete(scope: Option<&str>, key: &str) -> Result<()> {
+    let account = account(scope, key);
+    CACHE.lock().unwrap().remove(&account);
+    with_keyring(&account, |entry| match entry.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(err) => Err(err),
+    })?;
+    update_file(&account, None)
+}
+
+// 数据密钥, 首次使用时生成
+fn data_key() -> Result<Vec<u8>> {
+    let stored = get(Some(INTERNAL_SCOPE), DATA_KEY)?;
+    if !stored.is_empty() {
+        return STANDARD
+            .decode(stored)
+            .map_err(|_| store_error("数据密钥已损坏"));
+    }
+    let mut key = vec![0u8; KEY_LEN];
+    rand::thread_rng().fill_bytes(&mut key);
+    set(Some(INTERNAL_SCOPE), DATA_KEY, &STANDARD.encode(&key))?;
+    Ok(key)
+}
+
+/// 加密需要保存在数据库中、之后还要还原的敏感内容, 如执行快照中的参数与环境变量
+pub(crate) fn seal(plaintext: &str) -> Result<String> {
+    let data = encrypt(&data_key()?, plaintext.as_bytes())?;
+    Ok(format!("{}{}", SEALED_PREFIX, STANDARD.encode(data)))
+}
+
+/// 是否为 seal 加密的内容
+pub(crate) fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX)
+}
+
+/// 还原 seal 加密的内容, 没有加密标记时原样返回
+pub(crate) fn unseal(value: &str) -> Result<String> {
+    let Some(encoded) = value.strip_prefix(SEALED_PREFIX) else {
+        return Ok(value.to_string());
+    };
+    let data = STANDARD
+        .decode(encoded)
+        .map_err(|_| store_error("数据已损坏"))?;
+    String::from_utf8(decrypt(&data_key()?, &data)?).map_err(store_error)
+}
+
+/// 数据库中保存的值, 非空表示尚未迁移到密钥链的明文
+pub(crate) fn resolve(scope: Option<&str>, key: &str, stored: String) -> Result<String> {
+    if stored.is_empty() {
+        get(scope, key)
+    } else {
+        Ok(stored)
+    }
+}
+
+/// 将数据库中的明文值迁移到密钥链, 应用启动时调用, 密钥链不可用时迁移到加密文件
+pub fn migrate() -> Result<usize> {
+    with_db(|conn| {
+        let mut plain: Vec<(Option<String>, String, String)> = Vec::new();
+        let mut stmt = conn.prepare("SELECT key, value FROM env_vars WHERE value != ''")?;
+        for row in stmt.query_map([], |row| Ok((None, row.get(0)?, row.get(1)?)))? {
+            plain.push(row?);
+        }
+        let mut stmt =
+            conn.prepare("SELECT plugin_id, key, value FROM plugin_env WHERE value != ''")?;
+        for row in stmt.query_map([], |row| Ok((Some(row.get(0)?), row.get(1)?, row.get(2)?)))? {
+            plain.push(row?);
+        }
+
+        let mut migrated = 0;
+        for (scope, key, value) in plain {
+            set(scope.as_deref(), &key, &value)?;
+            match scope {
+                Some(id) => conn.execute(
+                    "UPDATE plugin_env SET value = '' WHERE plugin_id = ?1 AND key = ?2",
+                    params![id, key],
+                )?,
+                None => conn.execute("UPDATE env_vars SET value = '' WHERE key = ?1", [&key])?,
+            };
+            migrated += 1;
+        }
+        Ok(migrated)
+    })
+}
@@ -0,0 +1,323 @@
+use axum::body::{Body, Bytes};
+use axum::extract::{Path, Query, Request};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use once_cell::sync::Lazy;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+
+use super::deno::{plugin_execute, plugins_list, PluginError, Result};
+use super::history;
+use super::mcp_server;
+use crate::utils::settings::{self, ServerSettings};
+
+// 访问令牌长度
+const TOKEN_LENGTH: usize = 32;
+// 停止服务时等待连接结束的时间, 超时后强制关闭, 如未断开的 SSE 连接
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 本地 HTTP 服务状态
+#[derive(Debug, Serialize, Clone)]
+pub struct ServerInfo {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    pub token: Option<String>,
+    pub cors_origins: Vec<String>,
+}
+
+// 正在运行的服务, 发送信号后停止
+struct ServerHandle {
+    port: u16,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+static SERVER: Lazy<Mutex<Option<ServerHandle>>> = Lazy::new(|| Mutex::new(None));
+
+pub(crate) fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+fn error_response(err: PluginError) -> Response {
+    let status = match err {
+        PluginError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+        PluginError::InvalidArgs(_) | PluginError::Json(_) => StatusCode::BAD_REQUEST,
+        PluginError::Disabled(_) => StatusCode::FORBIDDEN,
+        PluginError::Duplicate { .. } => StatusCode::CONFLICT,
+        // 请求已登记, 等待用户在应用中确认
+        PluginError::ApprovalRequired { .. } => StatusCode::ACCEPTED,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    let message = err.to_string();
+    (status, Json(json!({ "error": err, "message": message }))).into_response()
+}
+
+// 逐字节比较, 耗时与第一个不同字节的位置无关
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+// 校验 Bearer 令牌
+fn authorized(headers: &HeaderMap) -> bool {
+    let Some(token) = settings::get().server.token else {
+        return false;
+    };
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|provided| constant_time_eq(provided.trim().as_bytes(), token.as_bytes()))
+}
+
+fn unauthorized() -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(json!({ "message": "令牌无效" })),
+    )
+        .into_response()
+}
+
+// 请求体为空时参数为空对象
+fn parse_args(body: &[u8]) -> Result<Value> {
+    if body.is_empty() {
+        Ok(json!({}))
+    } else {
+        Ok(serde_json::from_slice(body)?)
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ExecuteQuery {
+    profile: Option<String>,
+    dry_run: Option<bool>,
+}
+
+// 与应用内调用相同的流程: 确认、执行并写入调用记录
+async fn execute(
+    headers: HeaderMap,
+    plugin_id: String,
+    tool: String,
+    query: ExecuteQuery,
+    body: Bytes,
+) -> Response {
+    if !authorized(&headers) {
+        return unauthorized();
+    }
+    let args = match parse_args(&body) {
+        Ok(args) => args,
+        Err(err) => return error_response(err),
+    };
+    match plugin_execute(plugin_id, tool, args, query.profile, query.dry_run).await {
+        Ok(result) => Json(result).into_response(),
+        Err(err) => error_response(err),
+    }
+}
+
+// POST /run/{plugin_id}/{tool}, 请求体为工具参数
+async fn run_handler(
+    headers: HeaderMap,
+    Path((plugin_id, tool)): Path<(String, String)>,
+    body: Bytes,
+) -> Response {
+    execute(headers, plugin_id, tool, ExecuteQuery::default(), body).await
+}
+
+// POST /plugins/{id}/tools/{tool}?profile=&dry_run=, 请求体为工具参数
+async fn tool_handler(
+    headers: HeaderMap,
+    Path((plugin_id, tool)): Path<(String, String)>,
+    Query(query): Query<ExecuteQuery>,
+    body: Bytes,
+) -> Response {
+    execute(headers, plugin_id, tool, query, body).await
+}
+
+// GET /plugins, 按名称排列
+async fn plugins_handler(headers: HeaderMap) -> Response {
+    if !authorized(&headers) {
+        return unauthorized();
+    }
+    match plugins_list(None).await {
+        Ok(plugins) => {
+            let mut plugins: Vec<_> = plugins.into_values().collect();
+            plugins.sort_by(|a, b| a.name.cmp(&b.name));
+            Json(plugins).into_response()
+        }
+        Err(err) => error_response(err),
+    }
+}
+
+// GET /executions/{id}, 返回调用记录
+async fn execution_handler(headers: HeaderMap, Path(id): Path<String>) -> Response {
+    if !authorized(&headers) {
+        return unauthorized();
+    }
+    match history::find(&id) {
+        Ok(Some(record)) => Json(record).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "message": format!("执行记录不存在: {}", id) })),
+        )
+            .into_response(),
+        Err(err) => error_response(err),
+    }
+}
+
+// 按设置的来源添加跨域响应头, 并直接响应预检请求
+async fn cors(request: Request, next: Next) -> Response {
+    let origins = settings::get().server.cors_origins;
+    let allowed = request
+        .headers()
+        .get(header::ORIGIN)
+        .and_then(|origin| origin.to_str().ok())
+        .filter(|origin| origins.iter().any(|o| o == "*" || o == origin))
+        .and_then(|origin| HeaderValue::from_str(origin).ok());
+    let mut response = if request.method() == Method::OPTIONS {
+        Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .body(Body::empty())
+            .unwrap()
+    } else {
+        next.run(request).await
+    };
+    if let Some(origin) = allowed {
+        let headers = response.headers_mut();
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static("GET, POST, OPTIONS"),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            HeaderValue::from_static("Authorization, Content-Type"),
+        );
+        headers.insert(header::VARY, HeaderValue::from_static("Origin"));
+    }
+    response
+}
+
+fn router() -> Router {
+    Router::new()
+        .route("/run/:plugin_id/:tool", post(run_handler))
+        .route("/plugins", get(plugins_handler))
+        .route("/plugins/:plugin_id/tools/:tool", post(tool_handler))
+        .route("/executions/:id", get(execution_handler))
+        .merge(mcp_server::routes())
+        .layer(middleware::from_fn(cors))
+}
+
+async fn start(config: &ServerSettings) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", config.port))
+        .await
+        .map_err(|e| PluginError::Plugin(format!("无法监听端口 {}: {}", config.port, e)))?;
+    let (shutdown, rx) = oneshot::channel();
+    tracing::info!(port = config.port, "本地 HTTP 服务已启动");
+    let task = tokio::spawn(async move {
+        if let Err(err) = axum::serve(listener, router())
+            .with_graceful_shutdown(async {
+                let _ = rx.await;
+            })
+            .await
+        {
+            tracing::error!(error = %err, "本地 HTTP 服务异常退出");
+        }
+    });
+    *SERVER.lock().await = Some(ServerHandle {
+        port: config.port,
+        shutdown,
+        task,
+    });
+    Ok(())
+}
+
+// 等待服务退出并释放端口, 之后可以立即在同一端口重新启动
+async fn stop() {
+    if let Some(handle) = SERVER.lock().await.take() {
+        let _ = handle.shutdown.send(());
+        let mut task = handle.task;
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, &mut task)
+            .await
+            .is_err()
+        {
+            task.abort();
+            let _ = task.await;
+        }
+    }
+}
+
+/// 按当前设置启动或停止服务, 首次启用时生成令牌
+pub async fn apply_settings() -> Result<ServerInfo> {
+    stop().await;
+    let mut config = settings::get().server;
+    if config.enabled {
+        if config.token.is_none() {
+            let token = generate_token();
+            config = settings::update(|s| s.server.token = Some(token))?.server;
+        }
+        start(&config).await?;
+    }
+    info().await
+}
+
+async fn info() -> Result<ServerInfo> {
+    let config = settings::get().server;
+    let server = SERVER.lock().await;
+    Ok(ServerInfo {
+        enabled: config.enabled,
+        running: server.is_some(),
+        port: server.as_ref().map_or(config.port, |handle| handle.port),
+        token: config.token,
+        cors_origins: config.cors_origins,
+    })
+}
+
+#[tauri::command]
+pub async fn server_status() -> Result<ServerInfo> {
+    info().await
+}
+
+/// 修改服务的开关与端口并立即生效
+#[tauri::command]
+pub async fn server_configure(enabled: bool, port: Option<u16>) -> Result<ServerInfo> {
+    settings::update(|s| {
+        s.server.enabled = enabled;
+        if let Some(port) = port {
+            s.server.port = port;
+        }
+    })?;
+    apply_settings().await
+}
+
+/// 生成新的访问令牌, 旧令牌立即失效
+#[tauri::command]
+pub async fn server_rotate_token() -> Result<ServerInfo> {
+    let token = generate_token();
+    settings::update(|s| s.server.token = Some(token))?;
+    info().await
+}
+
+/// 设置允许跨域访问的来源, 立即生效
+#[tauri::command]
+pub async fn server_set_cors(origins: Vec<String>) -> Result<ServerInfo> {
+    let origins: Vec<String> = origins
+        .into_iter()
+        .map(|origin| origin.trim().trim_end_matches('/').to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect();
+    settings::update(|s| s.server.cors_origins = origins)?;
+    info().await
+}
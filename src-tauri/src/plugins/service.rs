@@ -0,0 +1,321 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::watch;
+
+use super::bridge::{Bridge, RPC_PREFIX};
+use super::deno::{
+    data_dir, find_plugin, load_plugin_list, plugins_dir, PluginError, PluginRuntime, Result,
+};
+use super::env;
+use super::logs;
+use super::runtime::{deno, DenoTask};
+
+// 日志中服务输出使用的工具名
+const SERVICE_TOOL: &str = "service";
+// 连续崩溃超过该次数后不再重启
+const MAX_RESTARTS: u32 = 10;
+// 重启等待时间上限 (秒)
+const MAX_BACKOFF_SECS: u64 = 60;
+// 稳定运行超过该时间后重置崩溃计数 (秒)
+const STABLE_SECS: u64 = 300;
+
+/// 后台服务配置, 插件默认导出中的 service: { autostart, run }
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ServiceConfig {
+    /// 应用启动时自动运行
+    #[serde(default)]
+    pub autostart: bool,
+}
+
+/// 服务运行状态
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ServiceStatus {
+    pub id: String,
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub started_at: Option<i64>,
+    /// 累计重启次数
+    pub restarts: u32,
+    pub last_exit: Option<String>,
+}
+
+struct ServiceHandle {
+    stop: watch::Sender<bool>,
+    status: Arc<Mutex<ServiceStatus>>,
+}
+
+static SERVICES: Lazy<Mutex<HashMap<String, ServiceHandle>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 服务入口脚本, service 可以是函数或带 run 方法的对象
+fn service_script(id: &str) -> String {
+    format!(
+        r#"
+        const plugin = await import('file://{plugin_path}');
+        const service = plugin.default.service;
+        await (typeof service === "function" ? service() : service.run());
+        "#,
+        plugin_path = plugins_dir()
+            .join(format!("{}.ts", id))
+            .to_string_lossy()
+            .replace('\\', "/")
+    )
+}
+
+// 运行一次服务进程, 返回退出信息; 收到停止信号时终止进程并返回 None
+async fn run_once(
+    id: &str,
+    status: &Arc<Mutex<ServiceStatus>>,
+    stop: &mut watch::Receiver<bool>,
+) -> Result<Option<(bool, String)>> {
+    let task = DenoTask {
+        script: service_script(id),
+        env_vars: env::for_plugin(id).await?,
+        lock_file: None,
+        data_dir: Some(data_dir(id)?),
+        grants: find_plugin(id).await?.grants,
+        ..Default::default()
+    };
+    let (mut cmd, temp_file) = deno().prepare_task(&task)?;
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let spawned = cmd.spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(err) => {
+            let _ = std::fs::remove_file(&temp_file);
+            return Err(err.into());
+        }
+    };
+
+    {
+        let mut status = status.lock().unwrap();
+        status.running = true;
+        status.pid = child.id();
+        status.started_at = Some(chrono::Utc::now().timestamp_millis());
+    }
+
+    // 服务的控制台输出写入插件日志
+    let stderr = child.stderr.take();
+    let log_id = id.to_string();
+    let stderr_task = tokio::spawn(async move {
+        if let Some(stderr) = stderr {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let _ = logs::append_logs(&log_id, Some(SERVICE_TOOL), &line);
+            }
+        }
+    });
+
+    // 服务同样可以使用宿主接口
+    let stdout = child.stdout.take();
+    let mut stdin = child.stdin.take();
+    let bridge = Bridge::new(id, SERVICE_TOOL, &[]);
+    let bridge_id = id.to_string();
+    let stdout_task = async move {
+        let Some(stdout) = stdout else {
+            return;
+        };
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            match (line.strip_prefix(RPC_PREFIX), stdin.as_mut()) {
+                (Some(request), Some(stdin)) => {
+                    let response = bridge.respond(request).await;
+                    let _ = stdin.write_all(format!("{}\n", response).as_bytes()).await;
+                    let _ = stdin.flush().await;
+                }
+                _ => {
+                    let _ = logs::append_logs(&bridge_id, Some(SERVICE_TOOL), &line);
+                }
+            }
+        }
+    };
+
+    let result = tokio::select! {
+        exit = async {
+            stdout_task.await;
+            child.wait().await
+        } => {
+            let exit = exit?;
+            Some((exit.success(), exit.to_string()))
+        }
+        _ = stop.changed() => {
+            None
+        }
+    };
+    if result.is_none() {
+        let _ = child.kill().await;
+    }
+    let _ = stderr_task.await;
+    let _ = std::fs::remove_file(&temp_file);
+
+    let mut status = status.lock().unwrap();
+    status.running = false;
+    status.pid = None;
+    if let Some((_, ref exit)) = result {
+        status.last_exit = Some(exit.clone());
+    }
+    Ok(result)
+}
+
+// 监督服务进程, 异常退出时按指数退避重启
+async fn supervise(id: String, status: Arc<Mutex<ServiceStatus>>, mut stop: watch::Receiver<bool>) {
+    let mut crashes = 0u32;
+    loop {
+        let started = std::time::Instant::now();
+        let exit = match run_once(&id, &status, &mut stop).await {
+            Ok(Some(exit)) => exit,
+            Ok(None) => break,
+            Err(err) => (false, err.to_string()),
+        };
+        tracing::info!(plugin = %id, success = exit.0, "服务已退出");
+        let _ = logs::append_logs(&id, Some(SERVICE_TOOL), &format!("服务已退出: {}", exit.1));
+        // 正常退出视为服务主动结束, 不再重启
+        if exit.0 {
+            break;
+        }
+
+        if started.elapsed() >= Duration::from_secs(STABLE_SECS) {
+            crashes = 0;
+        }
+        crashes += 1;
+        if crashes > MAX_RESTARTS {
+            tracing::error!(plugin = %id, crashes, "服务连续崩溃次数过多, 已停止重启");
+            let _ = logs::append_logs(&id, Some(SERVICE_TOOL), "服务连续崩溃次数过多, 已停止重启");
+            break;
+        }
+        status.lock().unwrap().restarts += 1;
+
+        let delay = Duration::from_secs(2u64.saturating_pow(crashes - 1).min(MAX_BACKOFF_SECS));
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = stop.changed() => break,
+        }
+    }
+    // 只移除自己的记录, 期间可能已被停止并重新启动
+    let mut services = SERVICES.lock().unwrap();
+    if services
+        .get(&id)
+        .is_some_and(|handle| Arc::ptr_eq(&handle.status, &status))
+    {
+        services.remove(&id);
+    }
+}
+
+// 声明了后台服务的插件
+async fn plugins_with_service(autostart_only: bool) -> Result<Vec<String>> {
+    Ok(load_plugin_list()
+        .await?
+        .into_values()
+        .filter(|plugin| plugin.runtime == PluginRuntime::Deno && plugin.enabled)
+        .filter(|plugin| {
+            plugin
+                .service
+                .as_ref()
+                .is_some_and(|service| service.autostart || !autostart_only)
+        })
+        .map(|plugin| plugin.id)
+        .collect())
+}
+
+fn current_status(id: &str) -> ServiceStatus {
+    SERVICES
+        .lock()
+        .unwrap()
+        .get(id)
+        .map(|handle| handle.status.lock().unwrap().clone())
+        .unwrap_or_else(|| ServiceStatus {
+            id: id.to_string(),
+            ..Default::default()
+        })
+}
+
+/// 启动插件的后台服务, 已在运行时直接返回状态
+pub(crate) async fn start(id: &str) -> Result<ServiceStatus> {
+    let plugin = find_plugin(id).await?;
+    if plugin.runtime != PluginRuntime::Deno || plugin.service.is_none() {
+        return Err(PluginError::Plugin(format!("插件未声明后台服务: {}", id)));
+    }
+    if !plugin.enabled {
+        return Err(PluginError::Disabled(id.to_string()));
+    }
+
+    let (status, stop_rx) = {
+        let mut services = SERVICES.lock().unwrap();
+        if let Some(handle) = services.get(id) {
+            return Ok(handle.status.lock().unwrap().clone());
+        }
+        let (stop, stop_rx) = watch::channel(false);
+        let status = Arc::new(Mutex::new(ServiceStatus {
+            id: id.to_string(),
+            ..Default::default()
+        }));
+        services.insert(
+            id.to_string(),
+            ServiceHandle {
+                stop,
+                status: status.clone(),
+            },
+        );
+        (status, stop_rx)
+    };
+
+    tokio::spawn(supervise(id.to_string(), status.clone(), stop_rx));
+    let status = status.lock().unwrap().clone();
+    Ok(status)
+}
+
+/// 插件更新后重启正在运行的服务
+pub(crate) async fn reload(id: &str) -> Result<()> {
+    let running = SERVICES.lock().unwrap().contains_key(id);
+    if running {
+        stop(id);
+        start(id).await?;
+    }
+    Ok(())
+}
+
+/// 停止插件的后台服务
+pub(crate) fn stop(id: &str) {
+    if let Some(handle) = SERVICES.lock().unwrap().remove(id) {
+        let _ = handle.stop.send(true);
+    }
+}
+
+/// 启动所有声明了 autostart 的服务
+pub async fn start_autostart() {
+    for id in plugins_with_service(true).await.unwrap_or_default() {
+        if let Err(err) = start(&id).await {
+            tracing::warn!(plugin = %id, error = %err, "服务启动失败");
+            let _ = logs::append_logs(&id, Some(SERVICE_TOOL), &format!("服务启动失败: {}", err));
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn plugin_service_start(id: String) -> Result<ServiceStatus> {
+    start(&id).await
+}
+
+#[tauri::command]
+pub async fn plugin_service_stop(id: String) -> Result<ServiceStatus> {
+    stop(&id);
+    Ok(current_status(&id))
+}
+
+/// 查询服务状态, 不传 id 时返回所有声明了服务的插件
+#[tauri::command]
+pub async fn plugin_service_status(id: Option<String>) -> Result<Vec<ServiceStatus>> {
+    let ids = match id {
+        Some(id) => vec![id],
+        None => plugins_with_service(false).await?,
+    };
+    Ok(ids.iter().map(|id| current_status(id)).collect())
+}
@@ -0,0 +1,183 @@
+use super::artifacts::ARTIFACTS_DIR_ENV;
+use super::deno::{
+    data_dir, find_plugin, register_plugin, Plugin, PluginError, PluginRuntime, Result, Tool,
+    DATA_DIR_ENV,
+};
+use super::env;
+use super::logs;
+use super::proxy;
+use super::runtime::run_limited;
+use crate::utils::gen::generate_id;
+use serde_json::Value;
+use std::path::Path;
+
+// 按空白拆分命令模板, 支持单双引号
+fn split_template(template: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut has_token = false;
+
+    for c in template.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                has_token = true;
+            }
+            None if c.is_whitespace() => {
+                if has_token {
+                    tokens.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            None => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(PluginError::Plugin("命令模板引号未闭合".to_string()));
+    }
+    if has_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+fn value_to_arg(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+// 替换参数占位符, 每个参数只作为单独的 argv 传入, 不经过 shell 解析
+fn render_args(template: &str, args: &Value) -> Result<Vec<String>> {
+    let mut argv = Vec::new();
+    for token in split_template(template)? {
+        // 单独的占位符: 数组展开为多个参数, 缺省时省略
+        if let Some(name) = token
+            .strip_prefix("{{")
+            .and_then(|t| t.strip_suffix("}}"))
+            .map(str::trim)
+        {
+            match args.get(name) {
+                Some(Value::Array(items)) => argv.extend(items.iter().map(value_to_arg)),
+                Some(Value::Null) | None => {}
+                Some(value) => argv.push(value_to_arg(value)),
+            }
+            continue;
+        }
+
+        let mut rendered = String::new();
+        let mut rest = token.as_str();
+        while let Some(start) = rest.find("{{") {
+            let end = rest[start..]
+                .find("}}")
+                .map(|e| start + e)
+                .ok_or_else(|| PluginError::Plugin(format!("无效的占位符: {}", token)))?;
+            rendered.push_str(&rest[..start]);
+            let name = rest[start + 2..end].trim();
+            rendered.push_str(&args.get(name).map(value_to_arg).unwrap_or_default());
+            rest = &rest[end + 2..];
+        }
+        rendered.push_str(rest);
+        argv.push(rendered);
+    }
+    Ok(argv)
+}
+
+/// 直接执行 Shell 插件的命令
+pub(crate) async fn execute(
+    plugin: &Plugin,
+    tool: &str,
+    args: &Value,
+    artifacts_dir: &Path,
+) -> Result<Value> {
+    let target = plugin
+        .tools
+        .iter()
+        .find(|t| t.name == tool)
+        .ok_or_else(|| PluginError::Plugin(format!("未知函数: {}", tool)))?;
+    let template = target
+        .command
+        .as_deref()
+        .ok_or_else(|| PluginError::Plugin(format!("工具缺少命令模板: {}", tool)))?;
+
+    let argv = render_args(template, args)?;
+    let (program, rest) = argv
+        .split_first()
+        .ok_or_else(|| PluginError::Plugin("命令模板为空".to_string()))?;
+
+    let mut cmd = tokio::process::Command::new(program);
+    cmd.args(rest);
+    // 未指定工作目录时使用插件数据目录
+    let dir = data_dir(&plugin.id)?;
+    cmd.current_dir(
+        target
+            .cwd
+            .as_deref()
+            .map(Path::new)
+            .unwrap_or(dir.as_path()),
+    )
+    .env(DATA_DIR_ENV, &dir)
+    .env(ARTIFACTS_DIR_ENV, artifacts_dir)
+    .envs(proxy::env_vars(&plugin.grants));
+    for var in env::for_plugin(&plugin.id).await? {
+        cmd.env(&var.key, &var.value);
+    }
+
+    let output = run_limited(cmd, None).await?;
+    let _ = logs::append_logs(&plugin.id, Some(tool), &output.stderr);
+    let stdout = output.into_stdout()?;
+    // 输出为 JSON 时按 JSON 返回, 否则返回文本
+    Ok(serde_json::from_str(&stdout)
+        .unwrap_or_else(|_| Value::String(stdout.trim_end().to_string())))
+}
+
+/// 创建或更新 Shell 插件, 工具通过 command 与 cwd 字段定义
+#[tauri::command]
+pub async fn plugin_save_shell(
+    id: Option<String>,
+    name: String,
+    description: Option<String>,
+    tools: Vec<Tool>,
+    env: Option<Vec<String>>,
+) -> Result<Plugin> {
+    for tool in &tools {
+        let template = tool
+            .command
+            .as_deref()
+            .ok_or_else(|| PluginError::Plugin(format!("工具缺少命令模板: {}", tool.name)))?;
+        if split_template(template)?.is_empty() {
+            return Err(PluginError::Plugin(format!("命令模板为空: {}", tool.name)));
+        }
+    }
+
+    let (id, enabled, env) = match id {
+        Some(id) => {
+            let plugin = find_plugin(&id).await?;
+            if plugin.runtime != PluginRuntime::Shell {
+                return Err(PluginError::Plugin(format!("插件不是 Shell 插件: {}", id)));
+            }
+            (id, plugin.enabled, env.unwrap_or(plugin.env))
+        }
+        None => (generate_id(), true, env.unwrap_or_default()),
+    };
+
+    register_plugin(Plugin {
+        id,
+        name,
+        description,
+        enabled,
+        env,
+        runtime: PluginRuntime::Shell,
+        tools,
+        ..Default::default()
+    })
+    .await
+}
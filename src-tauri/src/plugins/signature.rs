@@ -0,0 +1,152 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use super::deno::{PluginError, Result};
+use crate::utils::settings::{self, TrustedKey};
+
+// 内嵌签名的标记, 位于单独的注释行中, 如 "// @signature <base64>"
+const SIGNATURE_MARKER: &str = "@signature ";
+
+/// 插件源码的签名校验结果
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum SignatureStatus {
+    /// 由受信任的发布者签名
+    Verified {
+        publisher: String,
+    },
+    Unsigned,
+    /// 签名无效或签名者不受信任
+    Invalid,
+}
+
+/// 记录在插件元数据中的签名信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SignatureInfo {
+    #[serde(flatten)]
+    pub status: SignatureStatus,
+    /// 用户是否明确允许安装未通过校验的插件
+    #[serde(default)]
+    pub overridden: bool,
+}
+
+// 拆分内嵌签名, 返回签名与去掉签名行后的内容
+fn split_embedded(content: &str) -> Option<(String, String)> {
+    let mut signature = None;
+    let mut rest = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        let body = trimmed
+            .strip_prefix("//")
+            .or_else(|| trimmed.strip_prefix('#'))
+            .map(str::trim_start);
+        match body.and_then(|b| b.strip_prefix(SIGNATURE_MARKER)) {
+            Some(value) if signature.is_none() => signature = Some(value.trim().to_string()),
+            _ => rest.push_str(line),
+        }
+    }
+    signature.map(|signature| (signature, rest))
+}
+
+/// 去掉内嵌签名行后的内容
+pub(crate) fn strip(content: &str) -> String {
+    split_embedded(content)
+        .map(|(_, rest)| rest)
+        .unwrap_or_else(|| content.to_string())
+}
+
+fn decode_key(key: &TrustedKey) -> Option<VerifyingKey> {
+    let bytes: [u8; 32] = STANDARD
+        .decode(key.public_key.trim())
+        .ok()?
+        .try_into()
+        .ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+/// 校验插件源码, detached 为单独提供的签名, 否则读取内嵌签名
+pub(crate) fn verify(content: &str, detached: Option<&str>) -> SignatureStatus {
+    let (signature, message) = match detached {
+        Some(signature) => (signature.trim().to_string(), content.to_string()),
+        None => match split_embedded(content) {
+            Some(parts) => parts,
+            None => return SignatureStatus::Unsigned,
+        },
+    };
+    let Some(signature) = STANDARD
+        .decode(signature)
+        .ok()
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+    else {
+        return SignatureStatus::Invalid;
+    };
+
+    settings::get()
+        .signature
+        .trusted_keys
+        .iter()
+        .find(|key| {
+            decode_key(key).is_some_and(|k| k.verify(message.as_bytes(), &signature).is_ok())
+        })
+        .map(|key| SignatureStatus::Verified {
+            publisher: key.name.clone(),
+        })
+        .unwrap_or(SignatureStatus::Invalid)
+}
+
+/// 未通过校验时的提示
+pub(crate) fn rejection(status: &SignatureStatus) -> &'static str {
+    match status {
+        SignatureStatus::Unsigned => "插件未签名, 需要确认后才能安装",
+        _ => "插件签名无效或发布者不受信任, 需要确认后才能安装",
+    }
+}
+
+/// 导入前校验签名; 未通过校验的插件返回 Unsigned 错误, 用户确认后以 allow_unsigned 重试, 确认记录在插件元数据中
+pub(crate) fn check(
+    content: &str,
+    detached: Option<&str>,
+    allow_unsigned: bool,
+) -> Result<SignatureInfo> {
+    let status = verify(content, detached);
+    let verified = matches!(status, SignatureStatus::Verified { .. });
+    if !verified && !allow_unsigned {
+        return Err(PluginError::Unsigned { status });
+    }
+    Ok(SignatureInfo {
+        status,
+        overridden: !verified,
+    })
+}
+
+/// 列出受信任的发布者公钥
+#[tauri::command]
+pub async fn signature_trusted_keys() -> Result<Vec<TrustedKey>> {
+    Ok(settings::get().signature.trusted_keys)
+}
+
+/// 添加受信任的发布者公钥 (base64 编码的 ed25519 公钥)
+#[tauri::command]
+pub async fn signature_trust_key(name: String, public_key: String) -> Result<Vec<TrustedKey>> {
+    let key = TrustedKey { name, public_key };
+    if decode_key(&key).is_none() {
+        return Err(PluginError::Plugin("无效的 ed25519 公钥".to_string()));
+    }
+    Ok(settings::update(|s| {
+        s.signature.trusted_keys.retain(|k| k.name != key.name);
+        s.signature.trusted_keys.push(key);
+    })?
+    .signature
+    .trusted_keys)
+}
+
+/// 移除受信任的发布者
+#[tauri::command]
+pub async fn signature_untrust_key(name: String) -> Result<Vec<TrustedKey>> {
+    Ok(
+        settings::update(|s| s.signature.trusted_keys.retain(|k| k.name != name))?
+            .signature
+            .trusted_keys,
+    )
+}
@@ -0,0 +1,168 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use super::db::with_db;
+use super::deno::{load_plugin_list, Result};
+use super::history::ExecutionRecord;
+use super::i18n;
+use super::meta;
+
+/// 单个工具的调用统计
+#[derive(Debug, Serialize)]
+pub struct ToolStats {
+    pub tool: String,
+    pub invocations: u64,
+    pub failures: u64,
+    pub total_duration_ms: u64,
+    pub average_duration_ms: u64,
+    pub last_used: Option<i64>,
+    /// 最近一次成功的时间, 从未成功时为空
+    pub last_success: Option<i64>,
+}
+
+/// 插件的调用统计, 汇总其全部工具
+#[derive(Debug, Serialize)]
+pub struct PluginStats {
+    pub plugin_id: String,
+    /// 插件名称, 插件已删除时为空
+    pub name: Option<String>,
+    pub invocations: u64,
+    pub failures: u64,
+    pub total_duration_ms: u64,
+    pub average_duration_ms: u64,
+    pub last_used: Option<i64>,
+    pub last_success: Option<i64>,
+    pub tools: Vec<ToolStats>,
+}
+
+fn average(total: u64, count: u64) -> u64 {
+    if count == 0 {
+        0
+    } else {
+        total / count
+    }
+}
+
+/// 累加一次调用, 与调用记录在同一连接上写入
+pub(crate) fn record(conn: &Connection, entry: &ExecutionRecord) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tool_stats
+             (plugin_id, tool, invocations, failures, total_duration_ms, last_used, last_success)
+         VALUES (?1, ?2, 1, ?3, ?4, ?5, ?6)
+         ON CONFLICT(plugin_id, tool) DO UPDATE SET
+             invocations = invocations + 1,
+             failures = failures + excluded.failures,
+             total_duration_ms = total_duration_ms + excluded.total_duration_ms,
+             last_used = excluded.last_used,
+             last_success = COALESCE(excluded.last_success, last_success)",
+        params![
+            entry.plugin_id,
+            entry.tool,
+            if entry.success { 0 } else { 1 },
+            entry.duration_ms as i64,
+            entry.time,
+            entry.success.then_some(entry.time)
+        ],
+    )?;
+    Ok(())
+}
+
+/// 由已有的调用记录生成统计, 升级数据库时调用
+pub(crate) fn backfill(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "INSERT OR REPLACE INTO tool_stats
+             (plugin_id, tool, invocations, failures, total_duration_ms, last_used, last_success)
+         SELECT plugin_id, tool, COUNT(*), SUM(success = 0), SUM(duration_ms), MAX(time),
+                MAX(CASE WHEN success THEN time END)
+         FROM execution_history GROUP BY plugin_id, tool;",
+    )?;
+    Ok(())
+}
+
+/// 删除插件的统计
+pub(crate) fn remove(id: &str) -> Result<()> {
+    with_db(|conn| {
+        conn.execute("DELETE FROM tool_stats WHERE plugin_id = ?1", [id])?;
+        Ok(())
+    })
+}
+
+/// 查询调用统计, 未指定插件时返回全部插件, 按调用次数从多到少排列
+#[tauri::command]
+pub async fn plugin_stats(id: Option<String>) -> Result<Vec<PluginStats>> {
+    let rows: Vec<(String, ToolStats)> = with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT plugin_id, tool, invocations, failures, total_duration_ms, last_used,
+                    last_success
+             FROM tool_stats WHERE ?1 IS NULL OR plugin_id = ?1
+             ORDER BY plugin_id, invocations DESC, tool",
+        )?;
+        let rows = stmt
+            .query_map([&id], |row| {
+                let invocations = row.get::<_, i64>(2)? as u64;
+                let total_duration_ms = row.get::<_, i64>(4)? as u64;
+                Ok((
+                    row.get(0)?,
+                    ToolStats {
+                        tool: row.get(1)?,
+                        invocations,
+                        failures: row.get::<_, i64>(3)? as u64,
+                        total_duration_ms,
+                        average_duration_ms: average(total_duration_ms, invocations),
+                        last_used: row.get(5)?,
+                        last_success: row.get(6)?,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })?;
+
+    let plugins = load_plugin_list().await?;
+    let locale = i18n::current_locale(None);
+    let mut stats: Vec<PluginStats> = Vec::new();
+    for (plugin_id, tool) in rows {
+        if stats.last().map_or(true, |s| s.plugin_id != plugin_id) {
+            let name = plugins.get(&plugin_id).map(|plugin| {
+                let mut plugin = plugin.clone();
+                meta::display(&mut plugin, locale.as_deref());
+                plugin.name
+            });
+            stats.push(PluginStats {
+                plugin_id,
+                name,
+                invocations: 0,
+                failures: 0,
+                total_duration_ms: 0,
+                average_duration_ms: 0,
+                last_used: None,
+                last_success: None,
+                tools: Vec::new(),
+            });
+        }
+        let entry = stats.last_mut().unwrap();
+        entry.invocations += tool.invocations;
+        entry.failures += tool.failures;
+        entry.total_duration_ms += tool.total_duration_ms;
+        entry.last_used = entry.last_used.max(tool.last_used);
+        entry.last_success = entry.last_success.max(tool.last_success);
+        entry.tools.push(tool);
+    }
+    for entry in stats.iter_mut() {
+        entry.average_duration_ms = average(entry.total_duration_ms, entry.invocations);
+    }
+    stats.sort_by(|a, b| b.invocations.cmp(&a.invocations));
+    Ok(stats)
+}
+
+/// 清空调用统计, 未指定插件时清空全部
+#[tauri::command]
+pub async fn plugin_stats_reset(id: Option<String>) -> Result<()> {
+    with_db(|conn| {
+        conn.execute(
+            "DELETE FROM tool_stats WHERE ?1 IS NULL OR plugin_id = ?1",
+            [&id],
+        )?;
+        Ok(())
+    })
+}
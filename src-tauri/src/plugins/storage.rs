@@ -0,0 +1,81 @@
+use once_cell::sync::Lazy;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::PathBuf;
+use tokio::sync::Mutex;
+
+use super::deno::{plugins_dir, Result};
+
+// 串行化存储文件的读写
+static STORAGE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+// 每个插件一个 JSON 文件, 位于数据目录之外, 插件只能通过宿主接口访问
+fn storage_path(id: &str) -> PathBuf {
+    let dir = plugins_dir().join("storage");
+    let _ = fs::create_dir_all(&dir);
+    dir.join(format!("{}.json", id))
+}
+
+fn read(id: &str) -> Result<Map<String, Value>> {
+    let path = storage_path(id);
+    if !path.exists() {
+        return Ok(Map::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn write(id: &str, store: &Map<String, Value>) -> Result<()> {
+    fs::write(storage_path(id), serde_json::to_string(store)?)?;
+    Ok(())
+}
+
+/// 读取键值, 不存在时返回 null
+pub(crate) async fn get(id: &str, key: &str) -> Result<Value> {
+    let _guard = STORAGE_LOCK.lock().await;
+    Ok(read(id)?.remove(key).unwrap_or(Value::Null))
+}
+
+/// 写入键值
+pub(crate) async fn set(id: &str, key: &str, value: Value) -> Result<()> {
+    let _guard = STORAGE_LOCK.lock().await;
+    let mut store = read(id)?;
+    store.insert(key.to_string(), value);
+    write(id, &store)
+}
+
+/// 删除键值, 返回键是否存在
+pub(crate) async fn delete(id: &str, key: &str) -> Result<bool> {
+    let _guard = STORAGE_LOCK.lock().await;
+    let mut store = read(id)?;
+    let existed = store.remove(key).is_some();
+    if existed {
+        write(id, &store)?;
+    }
+    Ok(existed)
+}
+
+/// 列出所有键
+pub(crate) async fn keys(id: &str) -> Result<Vec<String>> {
+    let _guard = STORAGE_LOCK.lock().await;
+    Ok(read(id)?.keys().cloned().collect())
+}
+
+/// 复制插件的全部存储
+pub(crate) async fn copy(from: &str, to: &str) -> Result<()> {
+    let _guard = STORAGE_LOCK.lock().await;
+    let path = storage_path(from);
+    if path.exists() {
+        fs::copy(path, storage_path(to))?;
+    }
+    Ok(())
+}
+
+/// 删除插件的全部存储
+pub(crate) async fn remove(id: &str) -> Result<()> {
+    let _guard = STORAGE_LOCK.lock().await;
+    let path = storage_path(id);
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
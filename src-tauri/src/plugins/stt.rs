@@ -0,0 +1,478 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+use tokio::io::AsyncWriteExt;
+
+use super::deno::{PluginError, Result};
+use super::proxy;
+use crate::utils::file::get_config_dir;
+use crate::utils::gen::generate_id;
+
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+const DEFAULT_MODEL: &str = "base";
+
+// 可下载的 whisper.cpp 模型及大致大小 (MB)
+const MODELS: [(&str, u64); 9] = [
+    ("tiny", 75),
+    ("tiny.en", 75),
+    ("base", 142),
+    ("base.en", 142),
+    ("small", 466),
+    ("small.en", 466),
+    ("medium", 1500),
+    ("large-v3-turbo", 1600),
+    ("large-v3", 3100),
+];
+
+// 正在下载的模型
+static DOWNLOADING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 语音识别模型
+#[derive(Debug, Serialize, Clone)]
+pub struct SttModel {
+    pub name: String,
+    pub size_mb: u64,
+    pub downloaded: bool,
+    pub path: Option<String>,
+}
+
+/// 下载进度, 以 stt://download-progress 事件发送
+#[derive(Debug, Serialize, Clone)]
+pub struct DownloadProgress {
+    pub name: String,
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+/// 待转写的音频, 文件路径或 base64 编码的内容
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum AudioInput {
+    Path {
+        path: String,
+    },
+    Bytes {
+        base64: String,
+        /// 音频格式的扩展名, 如 wav、webm, 默认 wav
+        #[serde(default)]
+        extension: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TranscriptSegment {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+}
+
+/// 转写过程中识别出的片段, 以 stt://partial 事件发送
+#[derive(Debug, Serialize, Clone)]
+pub struct PartialTranscript {
+    pub id: String,
+    pub index: usize,
+    #[serde(flatten)]
+    pub segment: TranscriptSegment,
+}
+
+/// 转写结果
+#[derive(Debug, Serialize, Clone)]
+pub struct Transcript {
+    pub id: String,
+    pub text: String,
+    /// 识别或指定的语言, 如 zh、en
+    pub language: Option<String>,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+fn stt_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("语音转写失败: {}", message))
+}
+
+fn models_dir() -> Result<PathBuf> {
+    let mut path = get_config_dir().ok_or("无法获取配置目录")?;
+    path.push("models");
+    path.push("whisper");
+    Ok(path)
+}
+
+fn model_path(name: &str) -> Result<PathBuf> {
+    if !MODELS.iter().any(|(n, _)| *n == name) {
+        return Err(PluginError::Plugin(format!("未知的语音模型: {}", name)));
+    }
+    Ok(models_dir()?.join(format!("ggml-{}.bin", name)))
+}
+
+// 模型名或模型文件路径, 未指定时使用已下载的默认模型
+fn resolve_model(model: Option<&str>) -> Result<PathBuf> {
+    if let Some(model) = model.filter(|m| !m.trim().is_empty()) {
+        let file = PathBuf::from(model);
+        if file.is_file() {
+            return Ok(file);
+        }
+        let path = model_path(model)?;
+        if !path.is_file() {
+            return Err(stt_error(format!("模型未下载: {}", model)));
+        }
+        return Ok(path);
+    }
+    std::iter::once(DEFAULT_MODEL)
+        .chain(MODELS.iter().map(|(name, _)| *name))
+        .filter_map(|name| model_path(name).ok())
+        .find(|path| path.is_file())
+        .ok_or_else(|| stt_error("没有可用的语音模型, 请先下载"))
+}
+
+fn model_info(name: &str, size_mb: u64) -> SttModel {
+    let path = model_path(name).ok().filter(|p| p.is_file());
+    SttModel {
+        name: name.to_string(),
+        size_mb,
+        downloaded: path.is_some(),
+        path: path.map(|p| p.to_string_lossy().to_string()),
+    }
+}
+
+#[cfg(feature = "whisper")]
+mod engine {
+    use once_cell::sync::Lazy;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+    use std::sync::{Arc, Mutex};
+    use whisper_rs::{
+        FullParams, SamplingStrategy, SegmentCallbackData, WhisperContext, WhisperContextParameters,
+    };
+
+    use super::{stt_error, TranscriptSegment};
+    use crate::plugins::deno::Result;
+    use crate::utils::gen::generate_id;
+
+    // whisper 要求 16kHz 单声道
+    const SAMPLE_RATE: u32 = 16000;
+
+    // 最近使用的模型, 避免每次转写都重新加载
+    static CONTEXT: Lazy<Mutex<Option<(PathBuf, Arc<WhisperContext>)>>> =
+        Lazy::new(|| Mutex::new(None));
+
+    fn context(path: &Path) -> Result<Arc<WhisperContext>> {
+        let mut cached = CONTEXT.lock().unwrap();
+        if let Some((loaded, context)) = cached.as_ref() {
+            if loaded == path {
+                return Ok(context.clone());
+            }
+        }
+        let context = WhisperContext::new_with_params(
+            &path.to_string_lossy(),
+            WhisperContextParameters::default(),
+        )
+        .map_err(stt_error)?;
+        let context = Arc::new(context);
+        *cached = Some((path.to_path_buf(), context.clone()));
+        Ok(context)
+    }
+
+    // 混合为单声道并线性重采样到 16kHz
+    fn read_wav(path: &Path) -> Result<Vec<f32>> {
+        let mut reader = hound::WavReader::open(path).map_err(stt_error)?;
+        let spec = reader.spec();
+        let samples: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader
+                .samples::<f32>()
+                .collect::<std::result::Result<_, _>>()
+                .map_err(stt_error)?,
+            hound::SampleFormat::Int => {
+                let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.map(|s| s as f32 / scale))
+                    .collect::<std::result::Result<_, _>>()
+                    .map_err(stt_error)?
+            }
+        };
+        let channels = spec.channels.max(1) as usize;
+        let mono: Vec<f32> = samples
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect();
+        if spec.sample_rate == SAMPLE_RATE || mono.is_empty() {
+            return Ok(mono);
+        }
+        let ratio = spec.sample_rate as f64 / SAMPLE_RATE as f64;
+        let length = (mono.len() as f64 / ratio) as usize;
+        Ok((0..length)
+            .map(|i| {
+                let position = i as f64 * ratio;
+                let index = position as usize;
+                let next = mono.get(index + 1).copied().unwrap_or(mono[index]);
+                let fraction = (position - index as f64) as f32;
+                mono[index] * (1.0 - fraction) + next * fraction
+            })
+            .collect())
+    }
+
+    // 非 WAV 格式通过 ffmpeg 转换
+    fn load_audio(path: &Path) -> Result<Vec<f32>> {
+        let is_wav = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("wav"));
+        if is_wav {
+            return read_wav(path);
+        }
+        let converted = std::env::temp_dir().join(format!("ghostie-stt-{}.wav", generate_id()));
+        let output = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error", "-i"])
+            .arg(path)
+            .args(["-ar", "16000", "-ac", "1"])
+            .arg(&converted)
+            .output()
+            .map_err(|e| {
+                stt_error(format!(
+                    "无法运行 ffmpeg, 非 WAV 音频需要安装 ffmpeg: {}",
+                    e
+                ))
+            })?;
+        if !output.status.success() {
+            return Err(stt_error(String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        let samples = read_wav(&converted);
+        let _ = std::fs::remove_file(&converted);
+        samples
+    }
+
+    /// 转写音频, 每识别出一个片段调用一次 on_segment
+    pub fn transcribe(
+        model: &Path,
+        audio: &Path,
+        language: Option<&str>,
+        mut on_segment: impl FnMut(TranscriptSegment) + 'static,
+    ) -> Result<(Vec<TranscriptSegment>, Option<String>)> {
+        let samples = load_audio(audio)?;
+        let context = context(model)?;
+        let mut state = context.create_state().map_err(stt_error)?;
+
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_language(Some(language.unwrap_or("auto")));
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_special(false);
+        params.set_print_timestamps(false);
+        let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+        params.set_n_threads(threads.min(8) as i32);
+        // 时间戳单位为 10 毫秒
+        params.set_segment_callback_safe(move |data: SegmentCallbackData| {
+            on_segment(TranscriptSegment {
+                start_ms: data.start_timestamp * 10,
+                end_ms: data.end_timestamp * 10,
+                text: data.text.trim().to_string(),
+            })
+        });
+        state.full(params, &samples).map_err(stt_error)?;
+
+        let count = state.full_n_segments().map_err(stt_error)?;
+        let mut segments = Vec::with_capacity(count.max(0) as usize);
+        for i in 0..count {
+            segments.push(TranscriptSegment {
+                start_ms: state.full_get_segment_t0(i).map_err(stt_error)? * 10,
+                end_ms: state.full_get_segment_t1(i).map_err(stt_error)? * 10,
+                text: state
+                    .full_get_segment_text(i)
+                    .map_err(stt_error)?
+                    .trim()
+                    .to_string(),
+            });
+        }
+        let detected = state
+            .full_lang_id_from_state()
+            .ok()
+            .and_then(whisper_rs::get_lang_str)
+            .map(str::to_string);
+        Ok((segments, detected))
+    }
+}
+
+#[cfg(not(feature = "whisper"))]
+mod engine {
+    use std::path::Path;
+
+    use super::TranscriptSegment;
+    use crate::plugins::deno::{PluginError, Result};
+
+    pub fn transcribe(
+        _model: &Path,
+        _audio: &Path,
+        _language: Option<&str>,
+        _on_segment: impl FnMut(TranscriptSegment) + 'static,
+    ) -> Result<(Vec<TranscriptSegment>, Option<String>)> {
+        Err(PluginError::Plugin(
+            "当前版本未启用语音转写, 请使用 whisper 特性构建".to_string(),
+        ))
+    }
+}
+
+/// 转写音频, 识别出的片段以 stt://partial 事件实时推送
+///
+/// # 参数
+/// * `audio` - `{ "path": "..." }` 或 `{ "base64": "...", "extension": "webm" }`, 非 WAV 格式需要 ffmpeg
+/// * `model` - 已下载的模型名或模型文件路径, 默认使用 base
+/// * `language` - 语言代码, 如 zh、en, 默认自动识别
+/// * `id` - 用于匹配事件, 为空时自动生成
+#[tauri::command]
+pub async fn transcribe(
+    app: AppHandle,
+    audio: AudioInput,
+    model: Option<String>,
+    language: Option<String>,
+    id: Option<String>,
+) -> Result<Transcript> {
+    let id = id.unwrap_or_else(generate_id);
+    let model = resolve_model(model.as_deref())?;
+    let language = language.filter(|l| !l.trim().is_empty() && l != "auto");
+    let (path, temporary) = match audio {
+        AudioInput::Path { path } => (PathBuf::from(path), false),
+        AudioInput::Bytes { base64, extension } => {
+            let data = base64.rsplit(',').next().unwrap_or_default();
+            let bytes = STANDARD
+                .decode(data.trim())
+                .map_err(|e| stt_error(format!("无效的音频数据: {}", e)))?;
+            let extension = extension.unwrap_or_else(|| "wav".to_string());
+            let path = std::env::temp_dir().join(format!(
+                "ghostie-stt-{}.{}",
+                generate_id(),
+                extension.trim_start_matches('.')
+            ));
+            fs::write(&path, bytes)?;
+            (path, true)
+        }
+    };
+    if !path.is_file() {
+        return Err(stt_error(format!("音频文件不存在: {}", path.display())));
+    }
+
+    let event_id = id.clone();
+    let audio = path.clone();
+    let hint = language.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let mut index = 0;
+        engine::transcribe(&model, &audio, hint.as_deref(), move |segment| {
+            let _ = app.emit(
+                "stt://partial",
+                PartialTranscript {
+                    id: event_id.clone(),
+                    index,
+                    segment,
+                },
+            );
+            index += 1;
+        })
+    })
+    .await
+    .map_err(stt_error);
+    if temporary {
+        let _ = fs::remove_file(&path);
+    }
+    let (segments, detected) = result??;
+
+    let text = segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(Transcript {
+        id,
+        text,
+        language: language.or(detected),
+        segments,
+    })
+}
+
+/// 列出可用的语音模型及是否已下载
+#[tauri::command]
+pub async fn stt_models_list() -> Result<Vec<SttModel>> {
+    Ok(MODELS
+        .iter()
+        .map(|(name, size)| model_info(name, *size))
+        .collect())
+}
+
+async fn download(app: &AppHandle, name: &str, path: &Path) -> Result<()> {
+    let url = format!("{}/ggml-{}.bin", MODEL_BASE_URL, name);
+    let response = proxy::client()
+        .get(&url)
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("下载模型失败: {}", e))?;
+    let total = response.content_length();
+    // 先写入临时文件, 完成后再改名, 中断时不留下不完整的模型
+    let partial = path.with_extension("part");
+    let mut file = tokio::fs::File::create(&partial).await?;
+    let mut downloaded: u64 = 0;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("下载模型失败: {}", e))?;
+        file.write_all(&chunk).await?;
+        downloaded += chunk.len() as u64;
+        let _ = app.emit(
+            "stt://download-progress",
+            DownloadProgress {
+                name: name.to_string(),
+                downloaded,
+                total,
+            },
+        );
+    }
+    file.flush().await?;
+    drop(file);
+    if total.is_some_and(|total| total != downloaded) {
+        let _ = fs::remove_file(&partial);
+        return Err(PluginError::Plugin("下载模型失败: 文件不完整".to_string()));
+    }
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/// 下载语音模型, 通过 stt://download-progress 事件报告进度
+#[tauri::command]
+pub async fn stt_model_download(app: AppHandle, name: String) -> Result<SttModel> {
+    let size = MODELS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, size)| *size)
+        .ok_or_else(|| PluginError::Plugin(format!("未知的语音模型: {}", name)))?;
+    let path = model_path(&name)?;
+    if path.is_file() {
+        return Ok(model_info(&name, size));
+    }
+    if !DOWNLOADING.lock().unwrap().insert(name.clone()) {
+        return Err(PluginError::Plugin(format!("模型正在下载: {}", name)));
+    }
+    let result = async {
+        fs::create_dir_all(models_dir()?)?;
+        download(&app, &name, &path).await
+    }
+    .await;
+    DOWNLOADING.lock().unwrap().remove(&name);
+    if let Err(err) = result {
+        let _ = fs::remove_file(path.with_extension("part"));
+        return Err(err);
+    }
+    tracing::info!(model = %name, "语音模型已下载");
+    Ok(model_info(&name, size))
+}
+
+/// 删除已下载的语音模型
+#[tauri::command]
+pub async fn stt_model_delete(name: String) -> Result<()> {
+    let path = model_path(&name)?;
+    if path.is_file() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,1002 @@
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use rand::RngCore;
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use url::Url;
+
+use super::bots::{self, Bot};
+use super::bundle::{self, ConflictStrategy};
+use super::deno::{
+    self, load_env_vars, load_plugin_list, plugins_dir, EnvVar, Plugin, PluginError, Result,
+};
+use super::providers::{self, ProviderConfig};
+use super::{directory, env, secrets};
+use crate::utils::file::write_atomic;
+use crate::utils::gen::generate_id;
+use crate::utils::settings::{
+    self, ProxySettings, Settings, SyncBackend, SyncConflictPolicy, SyncSettings,
+};
+use crate::utils::workspace;
+
+// 密钥链中的作用域与变量名
+const SCOPE: &str = "sync";
+const PASSWORD: &str = "password";
+const PASSPHRASE: &str = "passphrase";
+const MANIFEST: &str = "manifest.json";
+const MANIFEST_VERSION: u32 = 1;
+// 加密数据的格式标记
+const MAGIC: &[u8] = b"GSY1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KDF_ROUNDS: u32 = 100_000;
+// 计算密钥项哈希的固定盐, 各设备对相同内容得到相同的哈希
+const HASH_SALT: &[u8] = b"ghostie-sync-hash";
+const DEFAULT_REGION: &str = "us-east-1";
+// 包含密钥明文的项, 只在设置了加密口令时同步
+const SECRET_ITEMS: [&str; 2] = ["env", "providers"];
+
+// 每次修改设置后递增, 旧的自动同步循环发现不一致时退出
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+// 同一时间只运行一次同步
+static RUNNING: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 本机与远端都在上次同步后修改了同一项
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncConflict {
+    /// 同步项, 如 settings、bots/<id>、plugins/<id>
+    pub key: String,
+    pub local_modified_at: i64,
+    pub remote_updated_at: i64,
+    /// 远端修改来自的设备
+    pub remote_device: String,
+}
+
+/// 解决冲突时保留的一方
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncSide {
+    Local,
+    Remote,
+}
+
+/// 一次同步的结果
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct SyncReport {
+    pub pushed: Vec<String>,
+    pub pulled: Vec<String>,
+    /// 因其他设备删除而在本机删除的项
+    pub deleted: Vec<String>,
+    /// 等待用户选择的冲突
+    pub conflicts: Vec<SyncConflict>,
+    /// 同步失败的项, 不影响其他项
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncStatus {
+    pub enabled: bool,
+    /// 是否已填写同步地址
+    pub configured: bool,
+    /// 是否设置了加密口令, 未设置时不同步环境变量与 API Key
+    pub encrypted: bool,
+    pub running: bool,
+    pub device: String,
+    pub last_sync: Option<i64>,
+    pub last_error: Option<String>,
+    /// 上次同步后本机修改的项
+    pub pending: Vec<String>,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+// 远端清单中的一项
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct RemoteItem {
+    hash: String,
+    updated_at: i64,
+    device: String,
+    /// 删除标记, 其他设备同步时删除本机的对应项
+    #[serde(default)]
+    deleted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct RemoteManifest {
+    version: u32,
+    items: BTreeMap<String, RemoteItem>,
+}
+
+// 本机记录的单项同步状态
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+struct ItemState {
+    /// 上次同步时本机内容的哈希
+    local: Option<String>,
+    /// 上次同步时远端清单中的哈希
+    remote: Option<String>,
+    /// 最近一次看到的本机哈希, 变化时更新修改时间
+    seen: Option<String>,
+    modified_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct SyncState {
+    device: String,
+    items: BTreeMap<String, ItemState>,
+    last_sync: Option<i64>,
+    last_error: Option<String>,
+    conflicts: Vec<SyncConflict>,
+}
+
+// 同步的模型服务配置, 包含 API Key
+#[derive(Debug, Serialize, Deserialize)]
+struct SyncedProvider {
+    config: ProviderConfig,
+    api_key: String,
+}
+
+fn sync_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("同步失败: {}", message))
+}
+
+fn state_path() -> Result<PathBuf> {
+    Ok(workspace::dir()
+        .ok_or_else(|| sync_error("无法获取配置目录"))?
+        .join("sync_state.json"))
+}
+
+fn load_state() -> Result<SyncState> {
+    let mut state: SyncState = fs::read(state_path()?)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default();
+    if state.device.is_empty() {
+        state.device = generate_id();
+    }
+    Ok(state)
+}
+
+fn save_state(state: &SyncState) -> Result<()> {
+    write_atomic(&state_path()?, &serde_json::to_vec_pretty(state)?)?;
+    Ok(())
+}
+
+fn is_secret(key: &str) -> bool {
+    SECRET_ITEMS.contains(&key)
+}
+
+// 只有助手与插件会因其他设备删除而删除
+fn deletable(key: &str) -> bool {
+    key.starts_with("bots/") || key.starts_with("plugins/")
+}
+
+// 远端文件名, 不使用多级目录
+fn item_name(key: &str) -> String {
+    format!("items/{}", key.replace('/', "__"))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC 接受任意长度的密钥");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// 普通项使用 SHA-256, 密钥项使用由口令派生的 HMAC, 远端清单不暴露密钥的哈希
+fn digest(content: &[u8], hash_key: Option<&[u8]>) -> String {
+    match hash_key {
+        Some(key) => hex(&hmac(key, content)),
+        None => format!("{:x}", Sha256::digest(content)),
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    key
+}
+
+// 格式: 标记 | 盐 | nonce | 密文, 每次加密使用新的盐与 nonce
+fn encrypt(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let cipher =
+        XChaCha20Poly1305::new_from_slice(&derive_key(passphrase, &salt)).map_err(sync_error)?;
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|_| sync_error("加密失败"))?;
+    Ok([MAGIC, &salt, &nonce, &ciphertext].concat())
+}
+
+fn decrypt(passphrase: &str, data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < MAGIC.len() + SALT_LEN + NONCE_LEN || !data.starts_with(MAGIC) {
+        return Err(sync_error("远端数据未加密或已损坏"));
+    }
+    let (salt, rest) = data[MAGIC.len()..].split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let cipher =
+        XChaCha20Poly1305::new_from_slice(&derive_key(passphrase, salt)).map_err(sync_error)?;
+    cipher
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| sync_error("解密失败, 请检查各设备的加密口令是否一致"))
+}
+
+// S3 签名要求的 URI 编码
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    Err(sync_error(format!(
+        "{} {}",
+        status,
+        body.chars().take(200).collect::<String>()
+    )))
+}
+
+enum Backend {
+    Webdav {
+        base: String,
+        username: Option<String>,
+        password: String,
+    },
+    S3 {
+        endpoint: Url,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+// 远端存储, 文件保存在 prefix 目录下
+struct Store {
+    backend: Backend,
+    prefix: String,
+}
+
+impl Store {
+    fn new(config: &SyncSettings) -> Result<Self> {
+        let endpoint = config.endpoint.trim().trim_end_matches('/').to_string();
+        if endpoint.is_empty() {
+            return Err(sync_error("未设置同步地址"));
+        }
+        let url =
+            Url::parse(&endpoint).map_err(|e| sync_error(format!("无效的同步地址: {}", e)))?;
+        let username = config.username.clone().filter(|name| !name.is_empty());
+        let password = secrets::get(Some(SCOPE), PASSWORD)?;
+        let backend = match config.backend {
+            SyncBackend::Webdav => Backend::Webdav {
+                base: endpoint,
+                username,
+                password,
+            },
+            SyncBackend::S3 => Backend::S3 {
+                endpoint: url,
+                bucket: config
+                    .bucket
+                    .clone()
+                    .filter(|bucket| !bucket.trim().is_empty())
+                    .ok_or_else(|| sync_error("未设置 S3 存储桶"))?,
+                region: config
+                    .region
+                    .clone()
+                    .filter(|region| !region.is_empty())
+                    .unwrap_or_else(|| DEFAULT_REGION.to_string()),
+                access_key: username.ok_or_else(|| sync_error("未设置 Access Key ID"))?,
+                secret_key: password,
+            },
+        };
+        Ok(Store {
+            backend,
+            prefix: config.prefix.trim_matches('/').to_string(),
+        })
+    }
+
+    fn path(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", self.prefix, name)
+        }
+    }
+
+    fn request(&self, method: Method, path: &str, body: Vec<u8>) -> reqwest::RequestBuilder {
+        let client = providers::client();
+        match &self.backend {
+            Backend::Webdav {
+                base,
+                username,
+                password,
+            } => {
+                let builder = client
+                    .request(method, format!("{}/{}", base, path))
+                    .body(body);
+                match username {
+                    Some(username) => builder.basic_auth(username, Some(password)),
+                    None => builder,
+                }
+            }
+            Backend::S3 {
+                endpoint,
+                bucket,
+                region,
+                access_key,
+                secret_key,
+            } => {
+                // AWS Signature V4, 使用路径形式的地址以兼容各类 S3 服务
+                let now = Utc::now();
+                let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+                let date = now.format("%Y%m%d").to_string();
+                let uri = format!(
+                    "{}/{}/{}",
+                    endpoint.path().trim_end_matches('/'),
+                    uri_encode(bucket),
+                    path.split('/')
+                        .map(uri_encode)
+                        .collect::<Vec<_>>()
+                        .join("/")
+                );
+                let host = match endpoint.port() {
+                    Some(port) => format!("{}:{}", endpoint.host_str().unwrap_or_default(), port),
+                    None => endpoint.host_str().unwrap_or_default().to_string(),
+                };
+                let payload = format!("{:x}", Sha256::digest(&body));
+                let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+                let canonical = format!(
+                    "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+                    method, uri, host, payload, amz_date, signed_headers, payload
+                );
+                let scope = format!("{}/{}/s3/aws4_request", date, region);
+                let to_sign = format!(
+                    "AWS4-HMAC-SHA256\n{}\n{}\n{:x}",
+                    amz_date,
+                    scope,
+                    Sha256::digest(canonical.as_bytes())
+                );
+                let mut key = hmac(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+                for part in [region.as_str(), "s3", "aws4_request"] {
+                    key = hmac(&key, part.as_bytes());
+                }
+                let signature = hex(&hmac(&key, to_sign.as_bytes()));
+                client
+                    .request(method, format!("{}://{}{}", endpoint.scheme(), host, uri))
+                    .header("x-amz-date", amz_date)
+                    .header("x-amz-content-sha256", payload)
+                    .header(
+                        "authorization",
+                        format!(
+                            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                            access_key, scope, signed_headers, signature
+                        ),
+                    )
+                    .body(body)
+            }
+        }
+    }
+
+    async fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        let response = self
+            .request(Method::GET, &self.path(name), Vec::new())
+            .send()
+            .await
+            .map_err(sync_error)?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let bytes = check(response).await?.bytes().await.map_err(sync_error)?;
+        Ok(Some(bytes.to_vec()))
+    }
+
+    async fn put(&self, name: &str, body: Vec<u8>) -> Result<()> {
+        let path = self.path(name);
+        let response = self
+            .request(Method::PUT, &path, body.clone())
+            .send()
+            .await
+            .map_err(sync_error)?;
+        // WebDAV 的上级目录不存在时先创建目录
+        let response = match (&self.backend, response.status()) {
+            (Backend::Webdav { .. }, StatusCode::NOT_FOUND | StatusCode::CONFLICT) => {
+                self.create_dirs(&path).await?;
+                self.request(Method::PUT, &path, body)
+                    .send()
+                    .await
+                    .map_err(sync_error)?
+            }
+            _ => response,
+        };
+        check(response).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        let response = self
+            .request(Method::DELETE, &self.path(name), Vec::new())
+            .send()
+            .await
+            .map_err(sync_error)?;
+        if response.status() != StatusCode::NOT_FOUND {
+            check(response).await?;
+        }
+        Ok(())
+    }
+
+    async fn create_dirs(&self, path: &str) -> Result<()> {
+        let mkcol = Method::from_bytes(b"MKCOL").map_err(sync_error)?;
+        let segments: Vec<&str> = path.split('/').collect();
+        for end in 1..segments.len() {
+            let dir = format!("{}/", segments[..end].join("/"));
+            let response = self
+                .request(mkcol.clone(), &dir, Vec::new())
+                .send()
+                .await
+                .map_err(sync_error)?;
+            // 405 表示目录已存在
+            if response.status() != StatusCode::METHOD_NOT_ALLOWED {
+                check(response).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// 插件的元数据与源码文件, 不包含依赖缓存时间等本机状态
+fn plugin_hash(plugin: &Plugin) -> Result<String> {
+    let mut shared = plugin.clone();
+    shared.deps_cached_at = None;
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(&shared)?);
+    let mut files: Vec<PathBuf> = ["ts", "py", "wasm", "lock"]
+        .iter()
+        .map(|ext| plugins_dir().join(format!("{}.{}", plugin.id, ext)))
+        .collect();
+    let dir = directory::plugin_dir(&plugin.id);
+    files.extend(
+        directory::list_files(&plugin.id)?
+            .into_iter()
+            .map(|file| dir.join(file)),
+    );
+    for file in files {
+        if let Ok(bytes) = fs::read(&file) {
+            let relative = file.strip_prefix(&plugins_dir()).unwrap_or(&file);
+            hasher.update(relative.to_string_lossy().replace('\\', "/").as_bytes());
+            hasher.update(bytes);
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// 本机各同步项的哈希, 内容在上传时再生成
+async fn local_items(hash_key: Option<&[u8]>) -> Result<BTreeMap<String, String>> {
+    let mut items = BTreeMap::new();
+    items.insert(
+        "settings".to_string(),
+        digest(&content("settings").await?, None),
+    );
+    if let Some(key) = hash_key {
+        for item in SECRET_ITEMS {
+            items.insert(item.to_string(), digest(&content(item).await?, Some(key)));
+        }
+    }
+    for bot in bots::bot_list().await? {
+        items.insert(
+            format!("bots/{}", bot.id),
+            digest(&serde_json::to_vec(&bot)?, None),
+        );
+    }
+    for plugin in load_plugin_list().await?.values() {
+        items.insert(format!("plugins/{}", plugin.id), plugin_hash(plugin)?);
+    }
+    Ok(items)
+}
+
+// 上传的内容, 插件以插件包的形式上传
+async fn content(key: &str) -> Result<Vec<u8>> {
+    match key {
+        "settings" => {
+            // 同步与代理设置只保存在本机
+            let mut shared = settings::get();
+            shared.sync = SyncSettings::default();
+            shared.proxy = ProxySettings::default();
+            Ok(toml::to_string(&shared)?.into_bytes())
+        }
+        "env" => {
+            let mut vars = load_env_vars().await?;
+            vars.sort_by(|a, b| a.key.cmp(&b.key));
+            Ok(serde_json::to_vec(&vars)?)
+        }
+        "providers" => {
+            let mut list = providers::provider_list()
+                .await?
+                .into_iter()
+                .map(|config| {
+                    Ok(SyncedProvider {
+                        api_key: providers::api_key(&config.id)?,
+                        config,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            list.sort_by(|a, b| a.config.id.cmp(&b.config.id));
+            Ok(serde_json::to_vec(&list)?)
+        }
+        _ => {
+            if let Some(id) = key.strip_prefix("bots/") {
+                return Ok(serde_json::to_vec(&bots::find_bot(id)?)?);
+            }
+            let id = key
+                .strip_prefix("plugins/")
+                .ok_or_else(|| sync_error(format!("未知的同步项: {}", key)))?;
+            let path = temp_bundle()?;
+            let result = bundle::plugin_export(id.to_string(), Some(path_string(&path))).await;
+            let bytes = result.and_then(|_| Ok(fs::read(&path)?));
+            let _ = fs::remove_file(&path);
+            bytes
+        }
+    }
+}
+
+fn temp_bundle() -> Result<PathBuf> {
+    let dir = plugins_dir().join("cache");
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(format!("sync-{}.zip", generate_id())))
+}
+
+fn path_string(path: &std::path::Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+// 写入远端的内容; 环境变量与模型服务只新增或更新, 不删除本机独有的项
+async fn apply(key: &str, data: &[u8]) -> Result<()> {
+    match key {
+        "settings" => {
+            let incoming: Settings = toml::from_str(&String::from_utf8_lossy(data))?;
+            settings::update(|s| {
+                let (sync, proxy) = (s.sync.clone(), s.proxy.clone());
+                *s = incoming;
+                s.sync = sync;
+                s.proxy = proxy;
+            })?;
+        }
+        "env" => {
+            for var in serde_json::from_slice::<Vec<EnvVar>>(data)? {
+                env::env_set(var.key, var.value).await?;
+            }
+        }
+        "providers" => {
+            for item in serde_json::from_slice::<Vec<SyncedProvider>>(data)? {
+                providers::provider_save(item.config, Some(item.api_key)).await?;
+            }
+        }
+        _ if key.starts_with("bots/") => {
+            let bot: Bot = serde_json::from_slice(data)?;
+            bots::write_bot(&bot)?;
+        }
+        _ if key.starts_with("plugins/") => {
+            let path = temp_bundle()?;
+            fs::write(&path, data)?;
+            // 同步的插件包来自用户自己的设备, 不要求签名
+            let result = bundle::plugin_import_bundle(
+                path_string(&path),
+                Some(ConflictStrategy::Replace),
+                Some(true),
+            )
+            .await;
+            let _ = fs::remove_file(&path);
+            result?;
+        }
+        _ => return Err(sync_error(format!("未知的同步项: {}", key))),
+    }
+    Ok(())
+}
+
+async fn remove_local(key: &str) -> Result<()> {
+    if let Some(id) = key.strip_prefix("bots/") {
+        bots::bot_delete(id.to_string()).await
+    } else if let Some(id) = key.strip_prefix("plugins/") {
+        deno::plugin_remove(id.to_string()).await
+    } else {
+        Ok(())
+    }
+}
+
+// 一次同步的上下文
+struct Session {
+    store: Store,
+    manifest: RemoteManifest,
+    state: SyncState,
+    passphrase: String,
+    hash_key: Option<Vec<u8>>,
+    now: i64,
+    /// 远端清单是否需要写回
+    changed: bool,
+    report: SyncReport,
+}
+
+impl Session {
+    async fn open() -> Result<Self> {
+        let store = Store::new(&settings::get().sync)?;
+        let passphrase = secrets::get(Some(SCOPE), PASSPHRASE)?;
+        let hash_key =
+            (!passphrase.is_empty()).then(|| derive_key(&passphrase, HASH_SALT).to_vec());
+        let manifest: RemoteManifest = match store.get(MANIFEST).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)?,
+            None => RemoteManifest::default(),
+        };
+        if manifest.version > MANIFEST_VERSION {
+            return Err(sync_error(format!(
+                "远端数据由更新版本的应用写入 (版本 {}), 请先升级",
+                manifest.version
+            )));
+        }
+        Ok(Session {
+            store,
+            manifest,
+            state: load_state()?,
+            passphrase,
+            hash_key,
+            now: Utc::now().timestamp_millis(),
+            changed: false,
+            report: SyncReport::default(),
+        })
+    }
+
+    fn remote_item(&self, hash: String, deleted: bool) -> RemoteItem {
+        RemoteItem {
+            hash,
+            updated_at: self.now,
+            device: self.state.device.clone(),
+            deleted,
+        }
+    }
+
+    // 上传本机内容, 本机已删除时在远端留下删除标记
+    async fn push(&mut self, key: &str, local: Option<&String>) -> Result<()> {
+        match local {
+            Some(hash) => {
+                let mut data = content(key).await?;
+                if is_secret(key) {
+                    data = encrypt(&self.passphrase, &data)?;
+                }
+                self.store.put(&item_name(key), data).await?;
+                let remote = self.remote_item(hash.clone(), false);
+                self.manifest.items.insert(key.to_string(), remote);
+            }
+            None if deletable(key) => {
+                self.store.delete(&item_name(key)).await?;
+                let remote = self.remote_item(String::new(), true);
+                self.manifest.items.insert(key.to_string(), remote);
+            }
+            None => return Ok(()),
+        }
+        let item = self.state.items.entry(key.to_string()).or_default();
+        item.local = local.cloned();
+        item.remote = self.manifest.items.get(key).map(|r| r.hash.clone());
+        self.changed = true;
+        self.report.pushed.push(key.to_string());
+        Ok(())
+    }
+
+    // 使用远端内容, 写入后的本机哈希在同步结束时重新计算
+    async fn pull(&mut self, key: &str, remote: &RemoteItem, local: Option<&String>) -> Result<()> {
+        if remote.deleted {
+            if local.is_some() {
+                remove_local(key).await?;
+                self.report.deleted.push(key.to_string());
+            }
+        } else {
+            let data = self
+                .store
+                .get(&item_name(key))
+                .await?
+                .ok_or_else(|| sync_error(format!("远端缺少同步项: {}", key)))?;
+            let data = if is_secret(key) {
+                decrypt(&self.passphrase, &data)?
+            } else {
+                data
+            };
+            apply(key, &data).await?;
+            self.report.pulled.push(key.to_string());
+        }
+        let item = self.state.items.entry(key.to_string()).or_default();
+        item.remote = Some(remote.hash.clone());
+        if remote.deleted {
+            item.local = None;
+            item.seen = None;
+        }
+        Ok(())
+    }
+
+    async fn sync_item(
+        &mut self,
+        key: &str,
+        local: Option<&String>,
+        policy: SyncConflictPolicy,
+    ) -> Result<()> {
+        if is_secret(key) && self.hash_key.is_none() {
+            return Ok(());
+        }
+        let base = self.state.items.get(key).cloned().unwrap_or_default();
+        let remote = self.manifest.items.get(key).cloned();
+        let Some(remote) = remote else {
+            // 远端没有记录时 (如更换了同步地址) 重新上传本机内容
+            if local.is_none() {
+                return Ok(());
+            }
+            return self.push(key, local).await;
+        };
+        let local_changed = local != base.local.as_ref();
+        let remote_changed = Some(&remote.hash) != base.remote.as_ref();
+        match (local_changed, remote_changed) {
+            (false, false) => Ok(()),
+            (true, false) => {
+                if local.is_none() && remote.deleted {
+                    return Ok(());
+                }
+                self.push(key, local).await
+            }
+            (false, true) => self.pull(key, &remote, local).await,
+            (true, true) => {
+                // 双方改成了相同的内容, 或都已删除
+                let same = match local {
+                    Some(hash) => !remote.deleted && remote.hash == *hash,
+                    None => remote.deleted,
+                };
+                if same {
+                    let item = self.state.items.entry(key.to_string()).or_default();
+                    item.local = local.cloned();
+                    item.remote = Some(remote.hash);
+                    return Ok(());
+                }
+                match policy {
+                    SyncConflictPolicy::LastWriterWins if base.modified_at >= remote.updated_at => {
+                        self.push(key, local).await
+                    }
+                    SyncConflictPolicy::LastWriterWins => self.pull(key, &remote, local).await,
+                    SyncConflictPolicy::Prompt => {
+                        self.report.conflicts.push(SyncConflict {
+                            key: key.to_string(),
+                            local_modified_at: base.modified_at,
+                            remote_updated_at: remote.updated_at,
+                            remote_device: remote.device,
+                        });
+                        Ok(())
+                    }
+                }
+            }
+        }
+    }
+
+    async fn sync_all(&mut self, policy: SyncConflictPolicy) -> Result<()> {
+        let local = local_items(self.hash_key.as_deref()).await?;
+        let keys: BTreeSet<String> = local
+            .keys()
+            .chain(self.manifest.items.keys())
+            .chain(self.state.items.keys())
+            .cloned()
+            .collect();
+        // 记录本机发现修改的时间, 用于最后写入者优先
+        for key in &keys {
+            let current = local.get(key);
+            let item = self.state.items.entry(key.clone()).or_default();
+            if item.seen.as_ref() != current {
+                item.seen = current.cloned();
+                item.modified_at = self.now;
+            }
+        }
+        for key in &keys {
+            if let Err(err) = self.sync_item(key, local.get(key), policy).await {
+                tracing::warn!(key = %key, error = %err, "同步项失败");
+                self.report.errors.push(format!("{}: {}", key, err));
+            }
+        }
+        Ok(())
+    }
+
+    async fn resolve(&mut self, key: &str, keep: SyncSide) -> Result<()> {
+        self.report.conflicts = self
+            .state
+            .conflicts
+            .iter()
+            .filter(|conflict| conflict.key != key)
+            .cloned()
+            .collect();
+        let local = local_items(self.hash_key.as_deref()).await?;
+        match (keep, self.manifest.items.get(key).cloned()) {
+            (SyncSide::Local, _) => self.push(key, local.get(key)).await,
+            (SyncSide::Remote, Some(remote)) => self.pull(key, &remote, local.get(key)).await,
+            (SyncSide::Remote, None) => Err(sync_error(format!("远端没有该项: {}", key))),
+        }
+    }
+
+    // 写回远端清单与本机状态, 出错时也保存已完成的部分
+    async fn finish(mut self, mut result: Result<()>) -> Result<SyncReport> {
+        if self.changed {
+            self.manifest.version = MANIFEST_VERSION;
+            let saved = match serde_json::to_vec_pretty(&self.manifest) {
+                Ok(bytes) => self.store.put(MANIFEST, bytes).await,
+                Err(err) => Err(err.into()),
+            };
+            result = result.and(saved);
+        }
+        if !self.report.pulled.is_empty() {
+            match local_items(self.hash_key.as_deref()).await {
+                Ok(refreshed) => {
+                    for key in &self.report.pulled {
+                        let item = self.state.items.entry(key.clone()).or_default();
+                        item.local = refreshed.get(key).cloned();
+                        item.seen = item.local.clone();
+                        item.modified_at = self.now;
+                    }
+                }
+                Err(err) => result = result.and(Err(err)),
+            }
+        }
+        self.state.conflicts = self.report.conflicts.clone();
+        self.state.last_error = match &result {
+            Ok(()) => self.report.errors.first().cloned(),
+            Err(err) => Some(err.to_string()),
+        };
+        if result.is_ok() {
+            self.state.last_sync = Some(self.now);
+        }
+        save_state(&self.state)?;
+        result.map(|_| self.report)
+    }
+}
+
+async fn run(app: &AppHandle) -> Result<SyncReport> {
+    let _guard = RUNNING
+        .try_lock()
+        .map_err(|_| sync_error("同步正在进行中"))?;
+    let policy = settings::get().sync.conflict;
+    let mut session = Session::open().await?;
+    let result = session.sync_all(policy).await;
+    let report = session.finish(result).await?;
+    if !report.conflicts.is_empty() {
+        let _ = app.emit("sync://conflict", &report.conflicts);
+    }
+    let _ = app.emit("sync://done", &report);
+    Ok(report)
+}
+
+/// 按设置启动或停止自动同步
+pub fn start(app: AppHandle) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let config = settings::get().sync;
+    if !config.enabled || config.endpoint.trim().is_empty() {
+        return;
+    }
+    let interval = Duration::from_secs(config.interval_minutes.max(1) * 60);
+    tauri::async_runtime::spawn(async move {
+        while GENERATION.load(Ordering::SeqCst) == generation {
+            if let Err(err) = run(&app).await {
+                tracing::warn!(error = %err, "自动同步失败");
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn sync_settings() -> Result<SyncSettings> {
+    Ok(settings::get().sync)
+}
+
+/// 修改同步设置, 保存后按新设置重新开始自动同步
+///
+/// # 参数
+/// * `password` - WebDAV 密码或 S3 Secret Access Key, 不传时保留, 为空字符串时删除
+/// * `passphrase` - 加密环境变量与 API Key 的口令, 各设备需相同, 不传时保留, 为空字符串时删除
+#[tauri::command]
+pub async fn sync_configure(
+    app: AppHandle,
+    config: SyncSettings,
+    password: Option<String>,
+    passphrase: Option<String>,
+) -> Result<SyncSettings> {
+    if !config.endpoint.trim().is_empty() {
+        Url::parse(config.endpoint.trim())
+            .map_err(|e| sync_error(format!("无效的同步地址: {}", e)))?;
+    }
+    for (name, value) in [(PASSWORD, password), (PASSPHRASE, passphrase)] {
+        match value.as_deref() {
+            Some("") => secrets::delete(Some(SCOPE), name)?,
+            Some(value) => secrets::set(Some(SCOPE), name, value)?,
+            None => {}
+        }
+    }
+    let updated = settings::update(|s| s.sync = config)?;
+    start(app);
+    Ok(updated.sync)
+}
+
+/// 立即同步插件、助手与设置
+///
+/// 冲突按设置中的方式处理, prompt 时以 sync://conflict 事件发送, 由 sync_resolve 逐项解决
+#[tauri::command]
+pub async fn sync_now(app: AppHandle) -> Result<SyncReport> {
+    run(&app).await
+}
+
+/// 同步状态与上次同步后本机修改的项
+#[tauri::command]
+pub async fn sync_status() -> Result<SyncStatus> {
+    let config = settings::get().sync;
+    let state = load_state()?;
+    let passphrase = secrets::get(Some(SCOPE), PASSPHRASE)?;
+    let hash_key = (!passphrase.is_empty()).then(|| derive_key(&passphrase, HASH_SALT).to_vec());
+    let local = local_items(hash_key.as_deref()).await?;
+    let mut pending: Vec<String> = local
+        .iter()
+        .filter(|(key, hash)| {
+            state.items.get(*key).and_then(|item| item.local.as_ref()) != Some(hash)
+        })
+        .map(|(key, _)| key.clone())
+        .collect();
+    // 本机已删除但尚未同步的项
+    pending.extend(
+        state
+            .items
+            .iter()
+            .filter(|(key, item)| {
+                deletable(key) && item.local.is_some() && !local.contains_key(*key)
+            })
+            .map(|(key, _)| key.clone()),
+    );
+    let running = RUNNING.try_lock().is_err();
+    Ok(SyncStatus {
+        enabled: config.enabled,
+        configured: !config.endpoint.trim().is_empty(),
+        encrypted: hash_key.is_some(),
+        running,
+        device: state.device,
+        last_sync: state.last_sync,
+        last_error: state.last_error,
+        pending,
+        conflicts: state.conflicts,
+    })
+}
+
+/// 解决冲突
+///
+/// # 参数
+/// * `keep` - local 上传本机内容覆盖远端, remote 使用远端内容覆盖本机
+#[tauri::command]
+pub async fn sync_resolve(key: String, keep: SyncSide) -> Result<SyncReport> {
+    let _guard = RUNNING
+        .try_lock()
+        .map_err(|_| sync_error("同步正在进行中"))?;
+    let mut session = Session::open().await?;
+    let result = session.resolve(&key, keep).await;
+    session.finish(result).await
+}
@@ -0,0 +1,187 @@
+use serde::Deserialize;
+
+use super::deno::{
+    process_plugin_content, register_plugin, PluginError, PluginWithContent, Result,
+};
+use super::signature;
+use crate::utils::gen::generate_id;
+
+/// 内置的插件模板
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum PluginTemplate {
+    Empty,
+    HttpApi,
+    Scraper,
+    FileProcessor,
+}
+
+const EMPTY: &str = r#"export default {
+  name: __NAME__,
+  description: "",
+  tools: {
+    hello: {
+      description: "返回问候语",
+      parameters: {
+        type: "object",
+        properties: {
+          name: { type: "string", description: "名字" },
+        },
+        required: ["name"],
+      },
+      handler: async ({ name }: { name: string }) => {
+        return `你好, ${name}`;
+      },
+    },
+  },
+};
+"#;
+
+const HTTP_API: &str = r#"// 在环境变量中配置 API_BASE_URL 与 API_KEY
+const baseUrl = Deno.env.get("API_BASE_URL") ?? "https://api.example.com";
+
+async function request(path: string, init: RequestInit = {}) {
+  const response = await fetch(`${baseUrl}${path}`, {
+    ...init,
+    headers: {
+      "Content-Type": "application/json",
+      Authorization: `Bearer ${Deno.env.get("API_KEY") ?? ""}`,
+      ...init.headers,
+    },
+  });
+  if (!response.ok) {
+    throw new Error(`请求失败: ${response.status} ${await response.text()}`);
+  }
+  return await response.json();
+}
+
+export default {
+  name: __NAME__,
+  description: "HTTP API 封装",
+  tools: {
+    get_item: {
+      description: "按 id 获取资源",
+      parameters: {
+        type: "object",
+        properties: {
+          id: { type: "string", description: "资源 id" },
+        },
+        required: ["id"],
+      },
+      handler: async ({ id }: { id: string }) => {
+        return await request(`/items/${encodeURIComponent(id)}`);
+      },
+    },
+    create_item: {
+      description: "创建资源",
+      parameters: {
+        type: "object",
+        properties: {
+          data: { type: "object", description: "资源内容" },
+        },
+        required: ["data"],
+      },
+      handler: async ({ data }: { data: Record<string, unknown> }) => {
+        return await request("/items", { method: "POST", body: JSON.stringify(data) });
+      },
+    },
+  },
+};
+"#;
+
+const SCRAPER: &str = r#"import { DOMParser } from "jsr:@b-fuze/deno-dom";
+
+export default {
+  name: __NAME__,
+  description: "网页抓取",
+  tools: {
+    fetch_page: {
+      description: "抓取网页, 返回标题、正文与链接",
+      parameters: {
+        type: "object",
+        properties: {
+          url: { type: "string", description: "网页地址" },
+          selector: { type: "string", description: "可选的 CSS 选择器, 只提取匹配的内容" },
+        },
+        required: ["url"],
+      },
+      handler: async ({ url, selector }: { url: string; selector?: string }) => {
+        const response = await fetch(url);
+        const html = await response.text();
+        const doc = new DOMParser().parseFromString(html, "text/html");
+        const root = selector ? doc?.querySelector(selector) : doc?.body;
+        return {
+          title: doc?.title ?? "",
+          text: root?.textContent?.trim() ?? "",
+          links: [...(root?.querySelectorAll("a[href]") ?? [])].map((a) => a.getAttribute("href")),
+        };
+      },
+    },
+  },
+};
+"#;
+
+const FILE_PROCESSOR: &str = r#"// 结果文件写入 ECHO_ARTIFACTS_DIR, 会作为产物返回
+const artifactsDir = Deno.env.get("ECHO_ARTIFACTS_DIR") ?? ".";
+
+export default {
+  name: __NAME__,
+  description: "文件处理",
+  tools: {
+    process_file: {
+      description: "读取文本文件, 统计行数并输出转换为大写的副本",
+      parameters: {
+        type: "object",
+        properties: {
+          path: { type: "string", description: "文件路径" },
+        },
+        required: ["path"],
+      },
+      handler: async ({ path }: { path: string }) => {
+        const content = await Deno.readTextFile(path);
+        const name = path.split(/[\\/]/).pop() ?? "output.txt";
+        await Deno.writeTextFile(`${artifactsDir}/${name}`, content.toUpperCase());
+        return { lines: content.split("\n").length, output: name };
+      },
+    },
+  },
+};
+"#;
+
+impl PluginTemplate {
+    fn source(self) -> &'static str {
+        match self {
+            PluginTemplate::Empty => EMPTY,
+            PluginTemplate::HttpApi => HTTP_API,
+            PluginTemplate::Scraper => SCRAPER,
+            PluginTemplate::FileProcessor => FILE_PROCESSOR,
+        }
+    }
+}
+
+/// 从内置模板创建插件, 返回生成的源码供编辑
+///
+/// 模板生成的源码没有签名, 与导入一样需要用户确认后以 allow_unsigned 调用
+#[tauri::command]
+pub async fn plugin_create(
+    template: PluginTemplate,
+    name: String,
+    allow_unsigned: Option<bool>,
+) -> Result<PluginWithContent> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(PluginError::Plugin("插件名称不能为空".to_string()));
+    }
+    let content = template
+        .source()
+        .replace("__NAME__", &serde_json::to_string(name)?);
+    let signature = signature::check(&content, None, allow_unsigned.unwrap_or(false))?;
+    let mut info = process_plugin_content(generate_id(), content.clone()).await?;
+    info.signature = Some(signature);
+    let info = register_plugin(info).await?;
+    Ok(PluginWithContent {
+        info,
+        content,
+        files: None,
+    })
+}
@@ -0,0 +1,226 @@
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use tracing::Level;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{self, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+use super::deno::{PluginError, Result};
+use crate::utils::file::get_config_dir;
+use crate::utils::settings;
+
+const DEFAULT_LEVEL: &str = "info";
+// 日志文件名为 echo.<日期>.jsonl, 每天轮换一次
+const FILE_PREFIX: &str = "echo";
+const FILE_SUFFIX: &str = "jsonl";
+// 保留的日志文件数
+const MAX_LOG_FILES: usize = 7;
+// 默认返回的日志条数
+const DEFAULT_QUERY_LIMIT: usize = 500;
+
+// 运行时调整日志级别
+static FILTER: OnceCell<reload::Handle<EnvFilter, Registry>> = OnceCell::new();
+// 写入线程的句柄, 释放后未写入的日志会丢失, 因此在整个进程期间保留
+static GUARD: OnceCell<WorkerGuard> = OnceCell::new();
+
+/// 一条日志
+#[derive(Debug, Serialize)]
+pub struct LogEntry {
+    pub time: i64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    /// 事件的其余字段
+    pub fields: Value,
+    /// 事件所在的 span, 由外到内
+    pub spans: Value,
+}
+
+fn log_dir() -> Option<PathBuf> {
+    let dir = get_config_dir()?.join("logs");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+fn parse_level(level: &str) -> Result<Level> {
+    level
+        .parse()
+        .map_err(|_| PluginError::Plugin(format!("无效的日志级别: {}", level)))
+}
+
+// 依赖库只输出警告, 应用自身按设置的级别输出
+fn filter(level: Level) -> EnvFilter {
+    EnvFilter::new(format!("warn,ghostie={}", level))
+}
+
+/// 初始化日志, 应用启动时调用一次
+pub fn init() {
+    let level = settings::get()
+        .log_level
+        .and_then(|level| parse_level(&level).ok())
+        .unwrap_or(Level::INFO);
+    let Some(dir) = log_dir() else {
+        return;
+    };
+    let Ok(appender) = rolling::Builder::new()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(FILE_PREFIX)
+        .filename_suffix(FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)
+    else {
+        return;
+    };
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let (filter, handle) = reload::Layer::new(filter(level));
+    let file = fmt::layer()
+        .json()
+        .with_current_span(false)
+        .with_span_list(true)
+        .with_writer(writer);
+    // 开发时同时输出到终端
+    let console = cfg!(debug_assertions).then(|| fmt::layer().with_writer(std::io::stderr));
+    if tracing_subscriber::registry()
+        .with(filter)
+        .with(file)
+        .with(console)
+        .try_init()
+        .is_ok()
+    {
+        let _ = FILTER.set(handle);
+        let _ = GUARD.set(guard);
+    }
+}
+
+/// 当前的日志级别
+#[tauri::command]
+pub async fn logs_level() -> Result<String> {
+    Ok(settings::get()
+        .log_level
+        .unwrap_or_else(|| DEFAULT_LEVEL.to_string()))
+}
+
+/// 调整日志级别, 立即生效并保存到设置中
+#[tauri::command]
+pub async fn logs_set_level(level: String) -> Result<String> {
+    let parsed = parse_level(&level)?;
+    if let Some(handle) = FILTER.get() {
+        handle
+            .reload(filter(parsed))
+            .map_err(|e| PluginError::Plugin(e.to_string()))?;
+    }
+    let level = parsed.to_string().to_lowercase();
+    settings::update(|s| s.log_level = Some(level.clone()))?;
+    tracing::info!(level = %level, "日志级别已调整");
+    Ok(level)
+}
+
+// 按日期从早到晚排列的日志文件
+fn log_files() -> Vec<PathBuf> {
+    let Some(dir) = log_dir() else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn parse_entry(line: &str) -> Option<LogEntry> {
+    let mut value: Value = serde_json::from_str(line).ok()?;
+    let time = chrono::DateTime::parse_from_rfc3339(value["timestamp"].as_str()?)
+        .ok()?
+        .timestamp_millis();
+    let mut fields = value["fields"].take();
+    let message = fields
+        .as_object_mut()
+        .and_then(|fields| fields.remove("message"))
+        .and_then(|message| message.as_str().map(str::to_string))
+        .unwrap_or_default();
+    Some(LogEntry {
+        time,
+        level: value["level"].as_str().unwrap_or_default().to_string(),
+        target: value["target"].as_str().unwrap_or_default().to_string(),
+        message,
+        fields,
+        spans: value["spans"].take(),
+    })
+}
+
+/// 查询日志, 最新的在前
+///
+/// # 参数
+/// * `level` - 最低级别, 如 warn 时返回 warn 与 error
+/// * `since` - 起始时间 (毫秒时间戳)
+/// * `search` - 在消息与字段中搜索, 不区分大小写
+#[tauri::command]
+pub async fn logs_query(
+    level: Option<String>,
+    since: Option<i64>,
+    search: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEntry>> {
+    let level = level.as_deref().map(parse_level).transpose()?;
+    let search = search
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty());
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+
+    let mut entries = Vec::new();
+    for path in log_files() {
+        let file = fs::File::open(&path)?;
+        for line in BufReader::new(file).lines() {
+            let Some(entry) = parse_entry(&line?) else {
+                continue;
+            };
+            if since.is_some_and(|since| entry.time < since) {
+                continue;
+            }
+            // Level 的比较中越详细的级别越大
+            if let Some(level) = level {
+                if entry.level.parse::<Level>().map_or(true, |l| l > level) {
+                    continue;
+                }
+            }
+            if let Some(ref search) = search {
+                let haystack = format!("{} {}", entry.message, entry.fields).to_lowercase();
+                if !haystack.contains(search.as_str()) {
+                    continue;
+                }
+            }
+            entries.push(entry);
+        }
+    }
+    entries.reverse();
+    entries.truncate(limit);
+    Ok(entries)
+}
+
+/// 将全部日志合并导出到指定文件, 返回导出的行数
+#[tauri::command]
+pub async fn logs_export(path: String) -> Result<usize> {
+    let mut out = fs::File::create(&path)?;
+    let mut count = 0;
+    for file in log_files() {
+        for line in BufReader::new(fs::File::open(file)?).lines() {
+            writeln!(out, "{}", line?)?;
+            count += 1;
+        }
+    }
+    out.flush()?;
+    Ok(count)
+}
@@ -0,0 +1,246 @@
+use serde::Serialize;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Listener, Manager, Window, WindowEvent};
+
+use super::db::with_db;
+use super::deno::{self, PluginError, Result};
+use super::host;
+use crate::utils::settings::{self, PinnedTool, TraySettings};
+
+const TRAY_ID: &str = "main";
+const MAIN_WINDOW: &str = "main";
+// 托盘中显示的最近对话数量与标题长度
+const RECENT_LIMIT: usize = 5;
+const TITLE_LENGTH: usize = 30;
+const CONVERSATION_PREFIX: &str = "conversation:";
+const TOOL_PREFIX: &str = "tool:";
+
+/// 从托盘打开的工具, 以 tray://tool 事件发送到主窗口
+#[derive(Debug, Serialize, Clone)]
+pub struct TrayTool {
+    pub plugin: String,
+    pub tool: String,
+}
+
+fn tray_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("托盘菜单失败: {}", message))
+}
+
+fn recent_conversations() -> Result<Vec<(String, String)>> {
+    with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, title FROM conversations WHERE archived = 0
+             ORDER BY updated_at DESC LIMIT ?1",
+        )?;
+        let rows = stmt
+            .query_map([RECENT_LIMIT as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })
+}
+
+fn short_title(title: &str) -> String {
+    let title = title.trim();
+    if title.is_empty() {
+        return "新对话".to_string();
+    }
+    let mut short: String = title.chars().take(TITLE_LENGTH).collect();
+    if short.len() < title.len() {
+        short.push('…');
+    }
+    short
+}
+
+// 根据存储中的对话与固定的工具生成菜单
+async fn build_menu(app: &AppHandle) -> Result<Menu<tauri::Wry>> {
+    let menu = Menu::new(app).map_err(tray_error)?;
+    let item = |id: &str, text: &str| MenuItem::with_id(app, id, text, true, None::<&str>);
+
+    let recent = Submenu::new(app, "最近对话", true).map_err(tray_error)?;
+    let conversations = recent_conversations()?;
+    if conversations.is_empty() {
+        let empty = MenuItem::new(app, "暂无对话", false, None::<&str>).map_err(tray_error)?;
+        recent.append(&empty).map_err(tray_error)?;
+    }
+    for (id, title) in conversations {
+        let entry = item(
+            &format!("{}{}", CONVERSATION_PREFIX, id),
+            &short_title(&title),
+        )
+        .map_err(tray_error)?;
+        recent.append(&entry).map_err(tray_error)?;
+    }
+    menu.append(&recent).map_err(tray_error)?;
+
+    let pinned = settings::get().tray.pinned_tools;
+    if !pinned.is_empty() {
+        let tools = Submenu::new(app, "固定的工具", true).map_err(tray_error)?;
+        for PinnedTool { plugin, tool } in pinned {
+            // 已删除的插件不再显示
+            let Ok(info) = deno::find_plugin(&plugin).await else {
+                continue;
+            };
+            let entry = item(
+                &format!("{}{}/{}", TOOL_PREFIX, plugin, tool),
+                &format!("{} / {}", info.name, tool),
+            )
+            .map_err(tray_error)?;
+            tools.append(&entry).map_err(tray_error)?;
+        }
+        menu.append(&tools).map_err(tray_error)?;
+    }
+
+    let separator = PredefinedMenuItem::separator(app).map_err(tray_error)?;
+    menu.append_items(&[
+        &item("ask_clipboard", "粘贴并提问").map_err(tray_error)?,
+        &separator,
+        &item("show", "显示").map_err(tray_error)?,
+        &item("hide", "隐藏").map_err(tray_error)?,
+        &item("quit", "退出").map_err(tray_error)?,
+    ])
+    .map_err(tray_error)?;
+    Ok(menu)
+}
+
+fn show_main(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn emit_main<S: Serialize + Clone>(app: &AppHandle, event: &str, payload: S) {
+    show_main(app);
+    if let Err(err) = app.emit_to(MAIN_WINDOW, event, payload) {
+        tracing::warn!(error = %err, event, "发送托盘事件失败");
+    }
+}
+
+fn handle_menu(app: &AppHandle, id: &str) {
+    if let Some(conversation) = id.strip_prefix(CONVERSATION_PREFIX) {
+        emit_main(app, "tray://conversation", conversation.to_string());
+        return;
+    }
+    if let Some((plugin, tool)) = id.strip_prefix(TOOL_PREFIX).and_then(|t| t.split_once('/')) {
+        let payload = TrayTool {
+            plugin: plugin.to_string(),
+            tool: tool.to_string(),
+        };
+        emit_main(app, "tray://tool", payload);
+        return;
+    }
+    match id {
+        "ask_clipboard" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                match host::clipboard_read().await {
+                    Ok(text) if !text.trim().is_empty() => emit_main(&app, "tray://ask", text),
+                    Ok(_) => {}
+                    Err(err) => tracing::warn!(error = %err, "读取剪贴板失败"),
+                }
+            });
+        }
+        "show" => show_main(app),
+        "hide" => {
+            if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+                let _ = window.hide();
+            }
+        }
+        "quit" => app.exit(0),
+        _ => {}
+    }
+}
+
+/// 重新生成托盘菜单
+pub async fn refresh(app: &AppHandle) -> Result<()> {
+    let menu = build_menu(app).await?;
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        tray.set_menu(Some(menu)).map_err(tray_error)?;
+    }
+    Ok(())
+}
+
+/// 创建托盘图标, 收到 tray://refresh 事件时重新生成菜单
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    // 先使用基本菜单, 读取存储后再替换
+    let menu = Menu::with_items(
+        app,
+        &[
+            &MenuItem::with_id(app, "show", "显示", true, None::<&str>)?,
+            &MenuItem::with_id(app, "hide", "隐藏", true, None::<&str>)?,
+            &MenuItem::with_id(app, "quit", "退出", true, None::<&str>)?,
+        ],
+    )?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .on_menu_event(|app, event| handle_menu(app, event.id().as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click { .. } = event {
+                if let Some(window) = tray.app_handle().get_webview_window(MAIN_WINDOW) {
+                    let _ = window.show();
+                }
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    let handle = app.clone();
+    app.listen_any("tray://refresh", move |_| spawn_refresh(handle.clone()));
+    spawn_refresh(app.clone());
+    Ok(())
+}
+
+fn spawn_refresh(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = refresh(&app).await {
+            tracing::warn!(error = %err, "更新托盘菜单失败");
+        }
+    });
+}
+
+/// 开启关闭到托盘时, 关闭主窗口只隐藏窗口
+pub fn handle_window_event(window: &Window, event: &WindowEvent) {
+    if let WindowEvent::CloseRequested { api, .. } = event {
+        if window.label() == MAIN_WINDOW && settings::get().tray.close_to_tray {
+            let _ = window.hide();
+            api.prevent_close();
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn tray_settings() -> Result<TraySettings> {
+    Ok(settings::get().tray)
+}
+
+/// 修改托盘设置并更新菜单
+///
+/// # 参数
+/// * `pinned_tools` - 不传时保持不变
+#[tauri::command]
+pub async fn tray_configure(
+    app: AppHandle,
+    close_to_tray: Option<bool>,
+    pinned_tools: Option<Vec<PinnedTool>>,
+) -> Result<TraySettings> {
+    let updated = settings::update(|s| {
+        if let Some(close_to_tray) = close_to_tray {
+            s.tray.close_to_tray = close_to_tray;
+        }
+        if let Some(mut pinned) = pinned_tools {
+            pinned.dedup();
+            s.tray.pinned_tools = pinned;
+        }
+    })?;
+    refresh(&app).await?;
+    Ok(updated.tray)
+}
+
+/// 对话或插件变化后更新托盘菜单
+#[tauri::command]
+pub async fn tray_refresh(app: AppHandle) -> Result<()> {
+    refresh(&app).await
+}
@@ -0,0 +1,263 @@
+use globset::{Glob, GlobMatcher};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use super::approval;
+use super::deno::{find_plugin, plugins_dir, PluginError, Result};
+use super::logs;
+use crate::utils::gen::generate_id;
+
+// 默认的去抖时间 (毫秒)
+const DEFAULT_DEBOUNCE_MS: u64 = 500;
+// 默认传入文件路径的参数名
+const DEFAULT_ARG_NAME: &str = "path";
+
+/// 文件变化触发器, 匹配的文件新增或修改时执行工具
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Trigger {
+    pub id: String,
+    pub plugin_id: String,
+    pub tool: String,
+    /// 监听的目录
+    pub path: String,
+    /// 文件匹配规则, 相对监听目录, 如 "**/*.csv"
+    pub pattern: String,
+    pub recursive: bool,
+    /// 文件路径写入的参数名
+    pub arg_name: String,
+    /// 其余固定参数
+    pub args: Value,
+    pub debounce_ms: u64,
+    pub created_at: i64,
+}
+
+/// 创建触发器的参数
+#[derive(Debug, Deserialize)]
+pub struct TriggerOptions {
+    pub plugin_id: String,
+    pub tool: String,
+    pub path: String,
+    pub pattern: Option<String>,
+    pub recursive: Option<bool>,
+    pub arg_name: Option<String>,
+    pub args: Option<Value>,
+    pub debounce_ms: Option<u64>,
+}
+
+// 正在运行的监听器, 移除后停止监听
+static WATCHERS: Lazy<Mutex<HashMap<String, RecommendedWatcher>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+// 串行化触发器列表的读写
+static TRIGGERS_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+fn triggers_file() -> PathBuf {
+    plugins_dir().join("triggers.json")
+}
+
+fn read_triggers() -> Result<Vec<Trigger>> {
+    let path = triggers_file();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn write_triggers(triggers: &[Trigger]) -> Result<()> {
+    fs::write(triggers_file(), serde_json::to_string_pretty(triggers)?)?;
+    Ok(())
+}
+
+fn compile_pattern(pattern: &str) -> Result<GlobMatcher> {
+    Glob::new(pattern)
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| PluginError::Plugin(format!("无效的匹配规则: {}", e)))
+}
+
+// 按相对路径或文件名匹配
+fn matches(matcher: &GlobMatcher, root: &Path, path: &Path) -> bool {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    matcher.is_match(relative) || path.file_name().is_some_and(|name| matcher.is_match(name))
+}
+
+// 对一批变化的文件执行工具
+async fn fire(trigger: &Trigger, paths: BTreeSet<PathBuf>) {
+    for path in paths {
+        let mut args = match &trigger.args {
+            Value::Object(map) => map.clone(),
+            _ => serde_json::Map::new(),
+        };
+        args.insert(
+            trigger.arg_name.clone(),
+            Value::String(path.to_string_lossy().to_string()),
+        );
+        // 无人值守, 需要确认的工具直接拒绝
+        let result = approval::execute_confirmed(
+            &trigger.plugin_id,
+            &trigger.tool,
+            Value::Object(args),
+            "文件触发",
+            false,
+        )
+        .await;
+        if let Err(err) = result {
+            tracing::warn!(plugin = %trigger.plugin_id, error = %err, "文件触发执行失败");
+            let _ = logs::append_logs(
+                &trigger.plugin_id,
+                Some(&trigger.tool),
+                &format!("文件触发执行失败 ({}): {}", path.to_string_lossy(), err),
+            );
+        }
+    }
+}
+
+// 收集事件, 在静默 debounce_ms 后统一触发
+async fn debounce(trigger: Trigger, mut events: mpsc::UnboundedReceiver<PathBuf>) {
+    let delay = Duration::from_millis(trigger.debounce_ms);
+    while let Some(first) = events.recv().await {
+        let mut pending = BTreeSet::from([first]);
+        loop {
+            match tokio::time::timeout(delay, events.recv()).await {
+                Ok(Some(path)) => {
+                    pending.insert(path);
+                }
+                Ok(None) => return,
+                Err(_) => break,
+            }
+        }
+        fire(&trigger, pending).await;
+    }
+}
+
+// 启动触发器的文件监听
+fn start(trigger: &Trigger) -> Result<()> {
+    let root = PathBuf::from(&trigger.path);
+    if !root.is_dir() {
+        return Err(PluginError::Plugin(format!("目录不存在: {}", trigger.path)));
+    }
+    let matcher = compile_pattern(&trigger.pattern)?;
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    let watch_root = root.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+            return;
+        }
+        for path in event.paths {
+            if path.is_file() && matches(&matcher, &watch_root, &path) {
+                let _ = tx.send(path);
+            }
+        }
+    })
+    .map_err(|e| PluginError::Plugin(format!("无法创建文件监听: {}", e)))?;
+    let mode = if trigger.recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher
+        .watch(&root, mode)
+        .map_err(|e| PluginError::Plugin(format!("无法监听目录: {}", e)))?;
+
+    tokio::spawn(debounce(trigger.clone(), rx));
+    WATCHERS.lock().unwrap().insert(trigger.id.clone(), watcher);
+    Ok(())
+}
+
+fn stop(id: &str) {
+    WATCHERS.lock().unwrap().remove(id);
+}
+
+/// 启动所有已保存的触发器, 应用启动时调用
+pub async fn start_all() {
+    let _guard = TRIGGERS_LOCK.lock().await;
+    for trigger in read_triggers().unwrap_or_default() {
+        if let Err(err) = start(&trigger) {
+            tracing::warn!(plugin = %trigger.plugin_id, error = %err, "文件触发器启动失败");
+            let _ = logs::append_logs(
+                &trigger.plugin_id,
+                Some(&trigger.tool),
+                &format!("文件触发器启动失败: {}", err),
+            );
+        }
+    }
+}
+
+/// 删除插件的全部触发器
+pub(crate) async fn remove_for_plugin(plugin_id: &str) -> Result<()> {
+    let _guard = TRIGGERS_LOCK.lock().await;
+    let mut triggers = read_triggers()?;
+    triggers.retain(|trigger| {
+        let matched = trigger.plugin_id == plugin_id;
+        if matched {
+            stop(&trigger.id);
+        }
+        !matched
+    });
+    write_triggers(&triggers)
+}
+
+#[tauri::command]
+pub async fn trigger_create(options: TriggerOptions) -> Result<Trigger> {
+    let plugin = find_plugin(&options.plugin_id).await?;
+    if !plugin.tools.iter().any(|t| t.name == options.tool) {
+        return Err(PluginError::Plugin(format!("未知函数: {}", options.tool)));
+    }
+
+    let trigger = Trigger {
+        id: generate_id(),
+        plugin_id: options.plugin_id,
+        tool: options.tool,
+        path: options.path,
+        pattern: options.pattern.unwrap_or_else(|| "*".to_string()),
+        recursive: options.recursive.unwrap_or(true),
+        arg_name: options
+            .arg_name
+            .unwrap_or_else(|| DEFAULT_ARG_NAME.to_string()),
+        args: options.args.unwrap_or_else(|| serde_json::json!({})),
+        debounce_ms: options.debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS),
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+
+    let _guard = TRIGGERS_LOCK.lock().await;
+    start(&trigger)?;
+    let mut triggers = read_triggers()?;
+    triggers.push(trigger.clone());
+    write_triggers(&triggers)?;
+    Ok(trigger)
+}
+
+/// 列出触发器, active 表示监听是否在运行
+#[tauri::command]
+pub async fn trigger_list() -> Result<Vec<Value>> {
+    let _guard = TRIGGERS_LOCK.lock().await;
+    let watchers = WATCHERS.lock().unwrap();
+    read_triggers()?
+        .into_iter()
+        .map(|trigger| {
+            let active = watchers.contains_key(&trigger.id);
+            let mut value = serde_json::to_value(trigger)?;
+            value["active"] = Value::Bool(active);
+            Ok(value)
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn trigger_delete(id: String) -> Result<()> {
+    let _guard = TRIGGERS_LOCK.lock().await;
+    stop(&id);
+    let mut triggers = read_triggers()?;
+    triggers.retain(|trigger| trigger.id != id);
+    write_triggers(&triggers)
+}
@@ -0,0 +1,291 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+
+use super::deno::{plugins_dir, PluginError, Result};
+use super::providers::{self, ProviderKind, SpeechRequest};
+use crate::utils::file::get_config_dir;
+use crate::utils::gen::generate_id;
+
+/// 本地 piper 引擎的服务名
+pub const PIPER: &str = "piper";
+const DEFAULT_MODEL: &str = "tts-1";
+const DEFAULT_VOICE: &str = "alloy";
+// OpenAI 兼容接口的内置音色
+const OPENAI_VOICES: [&str; 9] = [
+    "alloy", "ash", "coral", "echo", "fable", "nova", "onyx", "sage", "shimmer",
+];
+
+// 正在播放的音频, 停止时清空
+static PLAYING: Lazy<Mutex<Option<Arc<rodio::Sink>>>> = Lazy::new(|| Mutex::new(None));
+
+/// 合成参数
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct SpeakOptions {
+    /// OpenAI 兼容接口使用的模型, 默认 tts-1
+    pub model: Option<String>,
+    /// mp3 或 wav, piper 只输出 wav
+    pub format: Option<String>,
+    /// 语速, 1.0 为正常速度
+    pub speed: f32,
+    /// 是否立即播放, 为 false 时只生成文件
+    pub play: bool,
+}
+
+impl Default for SpeakOptions {
+    fn default() -> Self {
+        Self {
+            model: None,
+            format: None,
+            speed: 1.0,
+            play: true,
+        }
+    }
+}
+
+/// 合成结果
+#[derive(Debug, Serialize, Clone)]
+pub struct SpeechResult {
+    /// 音频文件路径
+    pub path: String,
+    pub format: String,
+    pub played: bool,
+}
+
+/// 可用的音色
+#[derive(Debug, Serialize, Clone)]
+pub struct Voice {
+    pub id: String,
+    pub name: String,
+    pub language: Option<String>,
+    pub provider: String,
+}
+
+fn tts_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("语音合成失败: {}", message))
+}
+
+// piper 的音色模型目录, 每个音色为 .onnx 文件及同名的 .onnx.json 配置
+fn piper_dir() -> Result<PathBuf> {
+    let mut path = get_config_dir().ok_or("无法获取配置目录")?;
+    path.push("models");
+    path.push("piper");
+    Ok(path)
+}
+
+// 优先使用配置目录中的 piper, 否则从 PATH 中查找
+fn piper_program() -> PathBuf {
+    let bundled = get_config_dir().map(|dir| {
+        dir.join("piper")
+            .join(format!("piper{}", std::env::consts::EXE_SUFFIX))
+    });
+    bundled
+        .filter(|p| p.is_file())
+        .unwrap_or_else(|| PathBuf::from("piper"))
+}
+
+fn piper_voices() -> Result<Vec<Voice>> {
+    let dir = piper_dir()?;
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut voices: Vec<Voice> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|e| e == "onnx"))
+        .filter_map(|path| {
+            let id = path.file_stem()?.to_string_lossy().to_string();
+            let config: Value = fs::read_to_string(path.with_extension("onnx.json"))
+                .ok()
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or(Value::Null);
+            Some(Voice {
+                name: config["dataset"].as_str().unwrap_or(&id).to_string(),
+                language: config["language"]["code"].as_str().map(str::to_string),
+                provider: PIPER.to_string(),
+                id,
+            })
+        })
+        .collect();
+    voices.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(voices)
+}
+
+fn speak_piper(text: &str, voice: Option<&str>, speed: f32, output: &Path) -> Result<()> {
+    let voice = match voice {
+        Some(voice) => voice.to_string(),
+        None => piper_voices()?
+            .into_iter()
+            .next()
+            .map(|v| v.id)
+            .ok_or_else(|| {
+                tts_error("没有可用的 piper 音色, 请将音色模型放到 models/piper 目录")
+            })?,
+    };
+    let model = piper_dir()?.join(format!("{}.onnx", voice));
+    if !model.is_file() {
+        return Err(tts_error(format!("piper 音色不存在: {}", voice)));
+    }
+    // length_scale 越大语速越慢
+    let length_scale = 1.0 / speed.clamp(0.25, 4.0);
+    let mut child = Command::new(piper_program())
+        .arg("--model")
+        .arg(&model)
+        .arg("--output_file")
+        .arg(output)
+        .args(["--length_scale", &length_scale.to_string()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| tts_error(format!("无法运行 piper: {}", e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let result = child.wait_with_output()?;
+    if !result.status.success() {
+        return Err(tts_error(String::from_utf8_lossy(&result.stderr).trim()));
+    }
+    Ok(())
+}
+
+// 在独立线程中播放, 输出设备只能在创建它的线程中使用
+fn play(path: &Path) -> Result<()> {
+    stop();
+    let file = fs::File::open(path)?;
+    let (ready, started) = mpsc::channel();
+    std::thread::spawn(move || {
+        let output = rodio::OutputStream::try_default()
+            .map_err(tts_error)
+            .and_then(|(stream, handle)| {
+                let sink = rodio::Sink::try_new(&handle).map_err(tts_error)?;
+                let source = rodio::Decoder::new(BufReader::new(file)).map_err(tts_error)?;
+                sink.append(source);
+                Ok((stream, Arc::new(sink)))
+            });
+        let (_stream, sink) = match output {
+            Ok(output) => output,
+            Err(err) => {
+                let _ = ready.send(Err(err));
+                return;
+            }
+        };
+        *PLAYING.lock().unwrap() = Some(sink.clone());
+        let _ = ready.send(Ok(()));
+        sink.sleep_until_end();
+        let mut playing = PLAYING.lock().unwrap();
+        if playing.as_ref().is_some_and(|p| Arc::ptr_eq(p, &sink)) {
+            *playing = None;
+        }
+    });
+    started.recv().map_err(tts_error)?
+}
+
+// 停止播放, 返回之前是否在播放
+fn stop() -> bool {
+    match PLAYING.lock().unwrap().take() {
+        Some(sink) => {
+            sink.stop();
+            true
+        }
+        None => false,
+    }
+}
+
+/// 朗读文本, 播放的同时保存为音频文件
+///
+/// # 参数
+/// * `voice` - 音色 id, 见 tts_voices
+/// * `provider` - `piper` 或 OpenAI 兼容的模型服务 id, 默认 piper
+#[tauri::command]
+pub async fn tts_speak(
+    text: String,
+    voice: Option<String>,
+    provider: Option<String>,
+    options: Option<SpeakOptions>,
+) -> Result<SpeechResult> {
+    if text.trim().is_empty() {
+        return Err(tts_error("文本不能为空"));
+    }
+    let options = options.unwrap_or_default();
+    let provider = provider.unwrap_or_else(|| PIPER.to_string());
+    let voice = voice.filter(|v| !v.trim().is_empty());
+    let format = if provider == PIPER {
+        "wav".to_string()
+    } else {
+        options.format.clone().unwrap_or_else(|| "mp3".to_string())
+    };
+    if !matches!(format.as_str(), "mp3" | "wav") {
+        return Err(tts_error(format!("不支持的音频格式: {}", format)));
+    }
+    let dir = plugins_dir().join("speech");
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.{}", generate_id(), format));
+
+    if provider == PIPER {
+        let output = path.clone();
+        tokio::task::spawn_blocking(move || {
+            speak_piper(&text, voice.as_deref(), options.speed, &output)
+        })
+        .await
+        .map_err(tts_error)??;
+    } else {
+        let request = SpeechRequest {
+            model: options.model.as_deref().unwrap_or(DEFAULT_MODEL),
+            voice: voice.as_deref().unwrap_or(DEFAULT_VOICE),
+            text: &text,
+            format: &format,
+            speed: options.speed.clamp(0.25, 4.0),
+        };
+        let audio = providers::speech(&provider, &request).await?;
+        fs::write(&path, audio)?;
+    }
+
+    if options.play {
+        play(&path)?;
+    }
+    Ok(SpeechResult {
+        path: path.to_string_lossy().to_string(),
+        format,
+        played: options.play,
+    })
+}
+
+/// 停止朗读, 返回之前是否在播放
+#[tauri::command]
+pub async fn tts_stop() -> Result<bool> {
+    Ok(stop())
+}
+
+/// 列出可用的音色
+///
+/// # 参数
+/// * `provider` - `piper` 或 OpenAI 兼容的模型服务 id, 默认 piper
+#[tauri::command]
+pub async fn tts_voices(provider: Option<String>) -> Result<Vec<Voice>> {
+    let provider = provider.unwrap_or_else(|| PIPER.to_string());
+    if provider == PIPER {
+        return piper_voices();
+    }
+    let config = providers::find_provider(&provider)?;
+    if !matches!(config.kind, ProviderKind::Openai) {
+        return Err(tts_error(format!(
+            "模型服务 {} 不支持语音合成",
+            config.name
+        )));
+    }
+    Ok(OPENAI_VOICES
+        .iter()
+        .map(|voice| Voice {
+            id: voice.to_string(),
+            name: voice.to_string(),
+            language: None,
+            provider: provider.clone(),
+        })
+        .collect())
+}
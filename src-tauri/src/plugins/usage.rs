@@ -0,0 +1,418 @@
+use chrono::TimeZone;
+use rusqlite::{params, ToSql};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::fs;
+
+use super::db::with_db;
+use super::deno::{PluginError, Result};
+use super::providers::Usage;
+
+// 价格按每百万 token 计
+const PRICE_UNIT: f64 = 1_000_000.0;
+
+/// 模型的单价, provider 为空时适用于所有服务中的同名模型
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelPrice {
+    #[serde(default)]
+    pub provider: String,
+    pub model: String,
+    /// 每百万输入 token 的价格
+    pub prompt_price: f64,
+    /// 每百万输出 token 的价格
+    pub completion_price: f64,
+}
+
+/// 一次模型请求的用量
+#[derive(Debug, Serialize, Clone)]
+pub struct UsageRecord {
+    pub time: i64,
+    pub provider: String,
+    pub model: String,
+    pub bot: Option<String>,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// 按请求时的单价计算, 之后修改价格不影响已有记录
+    pub cost: f64,
+    pub duration_ms: u64,
+}
+
+/// 统计的时间范围 (毫秒时间戳)
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct UsageRange {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+/// 汇总维度
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageGroup {
+    /// 按本地日期
+    Day,
+    Provider,
+    Model,
+    /// 按助手
+    Bot,
+}
+
+impl UsageGroup {
+    fn column(self) -> &'static str {
+        match self {
+            UsageGroup::Day => "date(time / 1000, 'unixepoch', 'localtime')",
+            UsageGroup::Provider => "provider",
+            UsageGroup::Model => "model",
+            UsageGroup::Bot => "bot",
+        }
+    }
+}
+
+/// 汇总的一行, 未参与分组的维度为空
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct UsageRow {
+    pub day: Option<String>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub bot: Option<String>,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UsageReport {
+    /// 按费用从高到低排列, 按日期分组时按日期排列
+    pub rows: Vec<UsageRow>,
+    pub total: UsageRow,
+}
+
+// 先匹配服务与模型, 再匹配只指定模型的价格
+fn find_price(provider: &str, model: &str) -> Result<Option<(f64, f64)>> {
+    with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT prompt_price, completion_price FROM model_prices
+             WHERE model = ?2 AND provider IN (?1, '') ORDER BY provider = '' LIMIT 1",
+        )?;
+        let mut rows = stmt.query_map([provider, model], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.next().transpose()?)
+    })
+}
+
+/// 记录一次模型请求的用量
+pub(crate) fn record(
+    provider: &str,
+    model: &str,
+    bot: Option<&str>,
+    usage: &Usage,
+    duration_ms: u64,
+) -> Result<()> {
+    let (prompt_price, completion_price) = find_price(provider, model)?.unwrap_or((0.0, 0.0));
+    let cost = (usage.prompt_tokens as f64 * prompt_price
+        + usage.completion_tokens as f64 * completion_price)
+        / PRICE_UNIT;
+    with_db(|conn| {
+        conn.execute(
+            "INSERT INTO llm_usage
+                 (time, provider, model, bot, prompt_tokens, completion_tokens, cost, duration_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                chrono::Utc::now().timestamp_millis(),
+                provider,
+                model,
+                bot,
+                usage.prompt_tokens as i64,
+                usage.completion_tokens as i64,
+                cost,
+                duration_ms as i64
+            ],
+        )?;
+        Ok(())
+    })
+}
+
+fn range_condition(range: &UsageRange) -> (&'static str, Vec<Option<i64>>) {
+    (
+        "(?1 IS NULL OR time >= ?1) AND (?2 IS NULL OR time < ?2)",
+        vec![range.since, range.until],
+    )
+}
+
+fn query_rows(range: &UsageRange, group_by: &[UsageGroup]) -> Result<Vec<UsageRow>> {
+    let groups: Vec<UsageGroup> = [
+        UsageGroup::Day,
+        UsageGroup::Provider,
+        UsageGroup::Model,
+        UsageGroup::Bot,
+    ]
+    .into_iter()
+    .filter(|g| group_by.contains(g))
+    .collect();
+    // 固定输出四个维度, 未分组的维度取 NULL
+    let columns = [
+        UsageGroup::Day,
+        UsageGroup::Provider,
+        UsageGroup::Model,
+        UsageGroup::Bot,
+    ]
+    .iter()
+    .map(|g| {
+        if groups.contains(g) {
+            g.column()
+        } else {
+            "NULL"
+        }
+    })
+    .collect::<Vec<_>>()
+    .join(", ");
+    let group_clause = if groups.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "GROUP BY {}",
+            groups
+                .iter()
+                .map(|g| g.column())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    let order = if groups.contains(&UsageGroup::Day) {
+        "1, 8 DESC"
+    } else {
+        "8 DESC"
+    };
+    let (condition, values) = range_condition(range);
+    let sql = format!(
+        "SELECT {}, COUNT(*), COALESCE(SUM(prompt_tokens), 0), COALESCE(SUM(completion_tokens), 0),
+                COALESCE(SUM(cost), 0.0)
+         FROM llm_usage WHERE {} {} ORDER BY {}",
+        columns, condition, group_clause, order
+    );
+    with_db(|conn| {
+        let params: Vec<&dyn ToSql> = values.iter().map(|v| v as &dyn ToSql).collect();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(params.as_slice(), |row| {
+                Ok(UsageRow {
+                    day: row.get(0)?,
+                    provider: row.get(1)?,
+                    model: row.get(2)?,
+                    bot: row.get(3)?,
+                    requests: row.get::<_, i64>(4)? as u64,
+                    prompt_tokens: row.get::<_, i64>(5)? as u64,
+                    completion_tokens: row.get::<_, i64>(6)? as u64,
+                    cost: row.get(7)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })
+}
+
+fn query_records(range: &UsageRange) -> Result<Vec<UsageRecord>> {
+    let (condition, values) = range_condition(range);
+    let sql = format!(
+        "SELECT time, provider, model, bot, prompt_tokens, completion_tokens, cost, duration_ms
+         FROM llm_usage WHERE {} ORDER BY time",
+        condition
+    );
+    with_db(|conn| {
+        let params: Vec<&dyn ToSql> = values.iter().map(|v| v as &dyn ToSql).collect();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(params.as_slice(), |row| {
+                Ok(UsageRecord {
+                    time: row.get(0)?,
+                    provider: row.get(1)?,
+                    model: row.get(2)?,
+                    bot: row.get(3)?,
+                    prompt_tokens: row.get::<_, i64>(4)? as u64,
+                    completion_tokens: row.get::<_, i64>(5)? as u64,
+                    cost: row.get(6)?,
+                    duration_ms: row.get::<_, i64>(7)? as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })
+}
+
+// 含逗号、引号或换行的字段加引号
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line(out: &mut String, fields: &[String]) {
+    let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+    let _ = writeln!(out, "{}", line.join(","));
+}
+
+/// 汇总模型用量与费用
+///
+/// # 参数
+/// * `range` - 起止时间, 为空时统计全部
+/// * `group_by` - 汇总维度 day、provider、model、bot, 为空时只返回合计
+#[tauri::command]
+pub async fn usage_report(
+    range: Option<UsageRange>,
+    group_by: Option<Vec<UsageGroup>>,
+) -> Result<UsageReport> {
+    let range = range.unwrap_or_default();
+    let rows = query_rows(&range, &group_by.unwrap_or_default())?;
+    let total = rows.iter().fold(UsageRow::default(), |mut total, row| {
+        total.requests += row.requests;
+        total.prompt_tokens += row.prompt_tokens;
+        total.completion_tokens += row.completion_tokens;
+        total.cost += row.cost;
+        total
+    });
+    Ok(UsageReport { rows, total })
+}
+
+/// 导出用量为 CSV, 指定 group_by 时导出汇总, 否则导出每次请求的记录
+#[tauri::command]
+pub async fn usage_export(
+    range: Option<UsageRange>,
+    group_by: Option<Vec<UsageGroup>>,
+    path: String,
+) -> Result<String> {
+    let range = range.unwrap_or_default();
+    let mut out = String::new();
+    match group_by.filter(|g| !g.is_empty()) {
+        Some(group_by) => {
+            csv_line(
+                &mut out,
+                &[
+                    "day",
+                    "provider",
+                    "model",
+                    "bot",
+                    "requests",
+                    "prompt_tokens",
+                    "completion_tokens",
+                    "cost",
+                ]
+                .map(String::from),
+            );
+            for row in query_rows(&range, &group_by)? {
+                csv_line(
+                    &mut out,
+                    &[
+                        row.day.unwrap_or_default(),
+                        row.provider.unwrap_or_default(),
+                        row.model.unwrap_or_default(),
+                        row.bot.unwrap_or_default(),
+                        row.requests.to_string(),
+                        row.prompt_tokens.to_string(),
+                        row.completion_tokens.to_string(),
+                        format!("{:.6}", row.cost),
+                    ],
+                );
+            }
+        }
+        None => {
+            csv_line(
+                &mut out,
+                &[
+                    "time",
+                    "provider",
+                    "model",
+                    "bot",
+                    "prompt_tokens",
+                    "completion_tokens",
+                    "cost",
+                    "duration_ms",
+                ]
+                .map(String::from),
+            );
+            for record in query_records(&range)? {
+                let time = chrono::Local
+                    .timestamp_millis_opt(record.time)
+                    .single()
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_default();
+                csv_line(
+                    &mut out,
+                    &[
+                        time,
+                        record.provider,
+                        record.model,
+                        record.bot.unwrap_or_default(),
+                        record.prompt_tokens.to_string(),
+                        record.completion_tokens.to_string(),
+                        format!("{:.6}", record.cost),
+                        record.duration_ms.to_string(),
+                    ],
+                );
+            }
+        }
+    }
+    fs::write(&path, out)?;
+    Ok(path)
+}
+
+#[tauri::command]
+pub async fn usage_price_list() -> Result<Vec<ModelPrice>> {
+    with_db(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT provider, model, prompt_price, completion_price FROM model_prices
+             ORDER BY model, provider",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(ModelPrice {
+                    provider: row.get(0)?,
+                    model: row.get(1)?,
+                    prompt_price: row.get(2)?,
+                    completion_price: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })
+}
+
+/// 设置模型的单价, 只影响之后的请求
+#[tauri::command]
+pub async fn usage_price_set(price: ModelPrice) -> Result<()> {
+    let model = price.model.trim();
+    if model.is_empty() {
+        return Err(PluginError::Plugin("模型名称不能为空".to_string()));
+    }
+    if price.prompt_price < 0.0 || price.completion_price < 0.0 {
+        return Err(PluginError::Plugin("价格不能为负数".to_string()));
+    }
+    with_db(|conn| {
+        conn.execute(
+            "INSERT INTO model_prices (provider, model, prompt_price, completion_price)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(provider, model) DO UPDATE SET
+                 prompt_price = excluded.prompt_price,
+                 completion_price = excluded.completion_price",
+            params![
+                price.provider.trim(),
+                model,
+                price.prompt_price,
+                price.completion_price
+            ],
+        )?;
+        Ok(())
+    })
+}
+
+#[tauri::command]
+pub async fn usage_price_delete(provider: Option<String>, model: String) -> Result<()> {
+    with_db(|conn| {
+        conn.execute(
+            "DELETE FROM model_prices WHERE provider = ?1 AND model = ?2",
+            params![provider.unwrap_or_default(), model],
+        )?;
+        Ok(())
+    })
+}
@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use super::deno::{PluginError, Result};
+use super::python;
+use super::runtime::deno;
+use crate::utils::gen::generate_id;
+
+/// 诊断的严重程度
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// 一条诊断信息, 行列从 1 开始
+#[derive(Debug, Serialize, Clone)]
+pub struct Diagnostic {
+    /// 来源: check 或 lint
+    pub source: &'static str,
+    pub severity: Severity,
+    pub code: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+/// 校验结果, 存在错误时 valid 为 false
+#[derive(Debug, Serialize)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LintOutput {
+    #[serde(default)]
+    diagnostics: Vec<LintDiagnostic>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LintDiagnostic {
+    range: Option<LintRange>,
+    message: String,
+    code: Option<String>,
+    hint: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LintRange {
+    start: LintPosition,
+}
+
+#[derive(Debug, Deserialize)]
+struct LintPosition {
+    line: u32,
+    col: u32,
+}
+
+// 从 "at file:///path/plugin.ts:3:7" 中读取行列
+fn parse_location(line: &str) -> Option<(u32, u32)> {
+    let location = line.trim().strip_prefix("at ")?;
+    let mut parts = location.rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    Some((line, column))
+}
+
+// 解析 deno check 的输出, 形如 "TS2322 [ERROR]: 信息" 后跟代码片段与位置
+fn parse_check(stderr: &str) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    for line in stderr.lines() {
+        if let Some((code, message)) = line.split_once(" [ERROR]: ") {
+            diagnostics.push(Diagnostic {
+                source: "check",
+                severity: Severity::Error,
+                code: Some(code.trim().to_string()),
+                line: None,
+                column: None,
+                message: message.trim().to_string(),
+            });
+        } else if let Some(message) = line.strip_prefix("error: ") {
+            // 模块解析失败等非类型错误
+            if !message.starts_with("Type checking failed") {
+                diagnostics.push(Diagnostic {
+                    source: "check",
+                    severity: Severity::Error,
+                    code: None,
+                    line: None,
+                    column: None,
+                    message: message.trim().to_string(),
+                });
+            }
+        } else if let Some((row, column)) = parse_location(line) {
+            if let Some(last) = diagnostics.last_mut().filter(|d| d.line.is_none()) {
+                last.line = Some(row);
+                last.column = Some(column);
+            }
+        }
+    }
+    diagnostics
+}
+
+fn parse_lint(stdout: &str) -> Vec<Diagnostic> {
+    let Ok(output) = serde_json::from_str::<LintOutput>(stdout) else {
+        return Vec::new();
+    };
+    output
+        .diagnostics
+        .into_iter()
+        .map(|d| Diagnostic {
+            source: "lint",
+            severity: Severity::Warning,
+            code: d.code,
+            line: d.range.as_ref().map(|r| r.start.line),
+            column: d.range.as_ref().map(|r| r.start.col + 1),
+            message: match d.hint {
+                Some(hint) => format!("{} ({})", d.message, hint),
+                None => d.message,
+            },
+        })
+        .collect()
+}
+
+/// 在临时目录中对插件源码做类型检查与 lint, 不保存插件
+#[tauri::command]
+pub async fn plugin_validate(content: String) -> Result<ValidationReport> {
+    if python::is_python(&content) {
+        return Err(PluginError::Plugin("仅支持校验 JS/TS 插件".to_string()));
+    }
+    let dir = std::env::temp_dir().join(format!("echo_validate_{}", generate_id()));
+    fs::create_dir_all(&dir)?;
+    let file = dir.join("plugin.ts");
+    fs::write(&file, &content)?;
+
+    let runtime = deno();
+    let result = async {
+        let check = runtime.check(&file).await?;
+        let mut diagnostics = parse_check(&check.stderr);
+        // 检查失败但未能解析出具体诊断时返回原始输出
+        if !check.success && diagnostics.is_empty() {
+            diagnostics.push(Diagnostic {
+                source: "check",
+                severity: Severity::Error,
+                code: None,
+                line: None,
+                column: None,
+                message: check.stderr.trim().to_string(),
+            });
+        }
+        let lint = runtime.lint(&file).await?;
+        diagnostics.extend(parse_lint(&lint.stdout));
+        Ok::<_, PluginError>(diagnostics)
+    }
+    .await;
+    let _ = fs::remove_dir_all(&dir);
+
+    let diagnostics = result?;
+    Ok(ValidationReport {
+        valid: !diagnostics.iter().any(|d| d.severity == Severity::Error),
+        diagnostics,
+    })
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+
+use super::deno::{
+    find_plugin, plugin_update, plugins_dir, Plugin, PluginError, PluginRuntime, Result,
+};
+use super::python;
+
+// 每个插件最多保留的历史版本数
+const MAX_VERSIONS: usize = 50;
+
+/// 插件源码的一个历史版本
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PluginVersion {
+    /// 源码的 SHA-256, 同时作为版本标识
+    pub hash: String,
+    pub created_at: i64,
+    /// 插件声明的版本号与更新说明
+    pub version: Option<String>,
+    pub changelog: Option<String>,
+    pub size: usize,
+}
+
+fn versions_dir(id: &str) -> PathBuf {
+    plugins_dir().join("versions").join(id)
+}
+
+fn index_file(id: &str) -> PathBuf {
+    versions_dir(id).join("index.json")
+}
+
+fn read_index(id: &str) -> Vec<PluginVersion> {
+    fs::read_to_string(index_file(id))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// 插件当前的源码
+fn current_source(plugin: &Plugin) -> Option<String> {
+    let path = match plugin.runtime {
+        PluginRuntime::Deno | PluginRuntime::Node => {
+            plugins_dir().join(format!("{}.ts", plugin.id))
+        }
+        PluginRuntime::Python => python::source_path(&plugin.id),
+        PluginRuntime::Wasm | PluginRuntime::Shell | PluginRuntime::Mcp => return None,
+    };
+    fs::read_to_string(path).ok()
+}
+
+/// 保存一份源码快照, 内容相同的版本只保存一次
+pub(crate) fn snapshot(plugin: &Plugin, content: &str) -> Result<()> {
+    let hash = format!("{:x}", Sha256::digest(content.as_bytes()));
+    let mut index = read_index(&plugin.id);
+    if index.iter().any(|v| v.hash == hash) {
+        return Ok(());
+    }
+
+    let dir = versions_dir(&plugin.id);
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(&hash), content)?;
+    index.push(PluginVersion {
+        hash,
+        created_at: chrono::Utc::now().timestamp_millis(),
+        version: plugin.version.clone(),
+        changelog: plugin.changelog.clone(),
+        size: content.len(),
+    });
+    // 超出上限时删除最旧的版本
+    while index.len() > MAX_VERSIONS {
+        let oldest = index.remove(0);
+        let _ = fs::remove_file(dir.join(oldest.hash));
+    }
+    fs::write(
+        index_file(&plugin.id),
+        serde_json::to_string_pretty(&index)?,
+    )?;
+    Ok(())
+}
+
+/// 覆盖源码前保存当前版本
+pub(crate) fn snapshot_current(plugin: &Plugin) -> Result<()> {
+    match current_source(plugin) {
+        Some(content) => snapshot(plugin, &content),
+        None => Ok(()),
+    }
+}
+
+/// 删除插件的全部历史版本
+pub(crate) fn remove(id: &str) -> Result<()> {
+    let dir = versions_dir(id);
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+/// 列出插件的历史版本, 最新的在前
+#[tauri::command]
+pub async fn plugin_versions(id: String) -> Result<Vec<PluginVersion>> {
+    let mut index = read_index(&id);
+    index.reverse();
+    Ok(index)
+}
+
+/// 读取历史版本的源码
+#[tauri::command]
+pub async fn plugin_version_content(id: String, version: String) -> Result<String> {
+    if !read_index(&id).iter().any(|v| v.hash == version) {
+        return Err(PluginError::Plugin(format!("版本不存在: {}", version)));
+    }
+    Ok(fs::read_to_string(versions_dir(&id).join(version))?)
+}
+
+/// 回滚到指定的历史版本
+#[tauri::command]
+pub async fn plugin_rollback(id: String, version: String) -> Result<Plugin> {
+    find_plugin(&id).await?;
+    let content = plugin_version_content(id.clone(), version).await?;
+    plugin_update(id, content).await
+}
@@ -0,0 +1,227 @@
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use wasi_common::pipe::{ReadPipe, WritePipe};
+use wasi_common::WasiCtx;
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::sync::{ambient_authority, Dir, WasiCtxBuilder};
+
+use super::artifacts::ARTIFACTS_DIR_ENV;
+use super::deno::{
+    find_plugin, parse_plugin_info, plugins_dir, register_plugin, Plugin, PluginError,
+    PluginRuntime, Result,
+};
+use super::logs;
+use super::runtime::RunOutput;
+use crate::utils::gen::generate_id;
+use crate::utils::settings;
+
+// WASM 插件约定:
+// 以 WASI 命令方式运行, argv[1] 为 "describe" 时向 stdout 输出插件元数据 JSON,
+// 为 "call" 时 argv[2] 为工具名, 参数 JSON 从 stdin 读取, 结果 JSON 写入 stdout.
+// 执行工具时产物目录挂载为 /artifacts, 并通过 ECHO_ARTIFACTS_DIR 告知模块,
+// 除此之外模块没有文件系统、网络与环境变量访问权限.
+
+// 产物目录在模块内的挂载路径
+const ARTIFACTS_GUEST_DIR: &str = "/artifacts";
+
+fn wasm_path(id: &str) -> PathBuf {
+    plugins_dir().join(format!("{}.wasm", id))
+}
+
+fn wasm_error(err: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("WASM 错误: {}", err))
+}
+
+struct WasmState {
+    wasi: WasiCtx,
+    limits: StoreLimits,
+}
+
+// 同步运行 WASI 模块
+fn run_blocking(
+    module_path: &Path,
+    args: &[String],
+    input: &str,
+    artifacts_dir: Option<&Path>,
+) -> Result<RunOutput> {
+    let limits = settings::get().runtime.limits;
+    let mut config = Config::new();
+    config.epoch_interruption(true);
+    let engine = Engine::new(&config).map_err(wasm_error)?;
+    let module = Module::from_file(&engine, module_path).map_err(wasm_error)?;
+    let mut linker = Linker::new(&engine);
+    wasmtime_wasi::add_to_linker(&mut linker, |state: &mut WasmState| &mut state.wasi)
+        .map_err(wasm_error)?;
+
+    let stdout = WritePipe::new_in_memory();
+    let stderr = WritePipe::new_in_memory();
+    let mut argv = vec!["plugin".to_string()];
+    argv.extend_from_slice(args);
+
+    let mut builder = WasiCtxBuilder::new();
+    builder
+        .stdin(Box::new(ReadPipe::from(input.to_string())))
+        .stdout(Box::new(stdout.clone()))
+        .stderr(Box::new(stderr.clone()))
+        .args(&argv)
+        .map_err(wasm_error)?;
+    if let Some(dir) = artifacts_dir {
+        let dir = Dir::open_ambient_dir(dir, ambient_authority()).map_err(wasm_error)?;
+        builder
+            .preopened_dir(dir, ARTIFACTS_GUEST_DIR)
+            .map_err(wasm_error)?
+            .env(ARTIFACTS_DIR_ENV, ARTIFACTS_GUEST_DIR)
+            .map_err(wasm_error)?;
+    }
+    let wasi = builder.build();
+    let mut store_limits = StoreLimitsBuilder::new();
+    if let Some(mb) = limits.memory_mb {
+        store_limits = store_limits.memory_size(mb as usize * 1024 * 1024);
+    }
+    let mut store = Store::new(
+        &engine,
+        WasmState {
+            wasi,
+            limits: store_limits.build(),
+        },
+    );
+    store.limiter(|state| &mut state.limits);
+    // 超时后递增 epoch 中断执行
+    store.set_epoch_deadline(1);
+    if let Some(secs) = limits.timeout_secs {
+        let engine = engine.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_secs(secs));
+            engine.increment_epoch();
+        });
+    }
+    linker.module(&mut store, "", &module).map_err(wasm_error)?;
+
+    let result = linker
+        .get_default(&mut store, "")
+        .and_then(|func| func.typed::<(), ()>(&store))
+        .and_then(|func| func.call(&mut store, ()));
+    drop(store);
+
+    let read_pipe = |pipe: WritePipe<std::io::Cursor<Vec<u8>>>| {
+        pipe.try_into_inner()
+            .map(|cursor| String::from_utf8_lossy(&cursor.into_inner()).to_string())
+            .unwrap_or_default()
+    };
+    let stdout = read_pipe(stdout);
+    let stderr = read_pipe(stderr);
+
+    // 调用 proc_exit(0) 也视为正常退出
+    let success = match result {
+        Ok(()) => true,
+        Err(err) => match err.downcast_ref::<wasmtime_wasi::I32Exit>() {
+            Some(exit) => exit.0 == 0,
+            None if err.downcast_ref::<wasmtime::Trap>() == Some(&wasmtime::Trap::Interrupt) => {
+                return Err(PluginError::Plugin(format!(
+                    "执行超时 ({} 秒), 进程已终止",
+                    limits.timeout_secs.unwrap_or_default()
+                )))
+            }
+            None => {
+                return Ok(RunOutput {
+                    success: false,
+                    stdout,
+                    stderr: format!("{}\n{}", stderr, err),
+                })
+            }
+        },
+    };
+
+    Ok(RunOutput {
+        success,
+        stdout,
+        stderr,
+    })
+}
+
+async fn run(
+    module_path: PathBuf,
+    args: Vec<String>,
+    input: String,
+    artifacts_dir: Option<PathBuf>,
+) -> Result<RunOutput> {
+    tokio::task::spawn_blocking(move || {
+        run_blocking(&module_path, &args, &input, artifacts_dir.as_deref())
+    })
+    .await
+    .map_err(wasm_error)?
+}
+
+/// 执行 WASM 插件的工具
+pub(crate) async fn execute(
+    id: &str,
+    tool: &str,
+    args: &Value,
+    artifacts_dir: &Path,
+) -> Result<Value> {
+    let path = wasm_path(id);
+    if !path.exists() {
+        return Err(PluginError::Plugin(format!("插件文件不存在: {}", id)));
+    }
+
+    let output = run(
+        path,
+        vec!["call".to_string(), tool.to_string()],
+        serde_json::to_string(args)?,
+        Some(artifacts_dir.to_path_buf()),
+    )
+    .await?;
+    let _ = logs::append_logs(id, Some(tool), &output.stderr);
+    serde_json::from_str(&output.into_stdout()?).map_err(|e| PluginError::Json(e.to_string()))
+}
+
+/// 导入 WASM 插件, 传入 id 时覆盖已有插件
+#[tauri::command]
+pub async fn plugin_import_wasm(path: String, id: Option<String>) -> Result<Plugin> {
+    let (id, enabled) = match id {
+        Some(id) => {
+            let plugin = find_plugin(&id).await?;
+            if plugin.runtime != PluginRuntime::Wasm {
+                return Err(PluginError::Plugin(format!("插件不是 WASM 插件: {}", id)));
+            }
+            (id, plugin.enabled)
+        }
+        None => (generate_id(), true),
+    };
+
+    let mut plugin = load(&id, &fs::read(&path)?).await?;
+    plugin.enabled = enabled;
+    register_plugin(plugin).await
+}
+
+/// 写入 WASM 模块并读取元数据, 先读取元数据, 成功后再替换正式文件
+pub(crate) async fn load(id: &str, module: &[u8]) -> Result<Plugin> {
+    let staged = plugins_dir().join(format!("{}.wasm.new", id));
+    fs::write(&staged, module)?;
+    match describe(id, &staged).await {
+        Ok(plugin) => {
+            fs::rename(&staged, wasm_path(id))?;
+            Ok(plugin)
+        }
+        Err(err) => {
+            let _ = fs::remove_file(&staged);
+            Err(err)
+        }
+    }
+}
+
+// 读取 WASM 模块声明的元数据
+async fn describe(id: &str, module_path: &Path) -> Result<Plugin> {
+    let output = run(
+        module_path.to_path_buf(),
+        vec!["describe".to_string()],
+        String::new(),
+        None,
+    )
+    .await?;
+    let _ = logs::append_logs(id, None, &output.stderr);
+    let plugin_info: Value = serde_json::from_str(&output.into_stdout()?)?;
+    parse_plugin_info(id, PluginRuntime::Wasm, &plugin_info)
+}
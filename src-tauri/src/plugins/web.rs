@@ -0,0 +1,443 @@
+use futures_util::StreamExt;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use url::Url;
+
+use super::deno::{PluginError, Result};
+use super::ingest::decode_entities;
+use super::proxy;
+
+// 请求标识, 也用于匹配 robots.txt 中的规则
+const USER_AGENT: &str = concat!("Ghostie/", env!("CARGO_PKG_VERSION"));
+const ROBOTS_AGENT: &str = "ghostie";
+pub(crate) const DEFAULT_TIMEOUT_SECS: u64 = 20;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+pub(crate) const DEFAULT_MAX_REDIRECTS: usize = 5;
+// 下载的最大字节数
+const DEFAULT_MAX_BYTES: usize = 5 * 1024 * 1024;
+// robots.txt 的缓存时间 (毫秒)
+const ROBOTS_TTL_MS: i64 = 60 * 60 * 1000;
+
+/// 抓取参数
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct FetchOptions {
+    pub timeout_secs: u64,
+    pub max_redirects: usize,
+    pub max_bytes: usize,
+    /// 是否遵守站点的 robots.txt
+    pub respect_robots: bool,
+    /// 为 true 时不做正文提取, 转换整个页面
+    pub full_page: bool,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            max_redirects: DEFAULT_MAX_REDIRECTS,
+            max_bytes: DEFAULT_MAX_BYTES,
+            respect_robots: true,
+            full_page: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PageLink {
+    pub text: String,
+    pub url: String,
+}
+
+/// 抓取结果
+#[derive(Debug, Serialize, Clone)]
+pub struct WebPage {
+    /// 跳转后的最终地址
+    pub url: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// 提取的正文, Markdown 格式
+    pub text: String,
+    /// 正文中的链接, 已转换为绝对地址
+    pub links: Vec<PageLink>,
+    /// 页面超过大小上限时只处理了前一部分
+    pub truncated: bool,
+}
+
+// robots.txt 中适用于本应用的规则, 值为 true 表示 Allow
+struct Robots {
+    rules: Vec<(bool, String)>,
+}
+
+static ROBOTS: Lazy<Mutex<HashMap<String, (i64, Arc<Robots>)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 与正文无关的元素, 连同内容一起删除
+static BOILERPLATE: Lazy<Regex> = Lazy::new(|| {
+    let tags = [
+        "script", "style", "noscript", "template", "svg", "iframe", "nav", "header", "footer",
+        "aside", "form", "button", "select",
+    ];
+    let pattern = tags
+        .iter()
+        .map(|tag| format!(r"<{0}\b.*?</{0}\s*>", tag))
+        .collect::<Vec<_>>()
+        .join("|");
+    Regex::new(&format!(r"(?is)<!--.*?-->|{}", pattern)).unwrap()
+});
+static TITLE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<title\b[^>]*>(.*?)</title>").unwrap());
+static META: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<meta\b([^>]*)>").unwrap());
+static ARTICLE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<article\b[^>]*>(.*?)</article\s*>").unwrap());
+static MAIN: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<main\b[^>]*>(.*?)</main\s*>").unwrap());
+static BODY: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<body\b[^>]*>(.*)</body\s*>").unwrap());
+static ATTR: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)([a-z:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#).unwrap()
+});
+static PRE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<pre\b[^>]*>(.*?)</pre\s*>").unwrap());
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new("\u{0}(\\d+)\u{0}").unwrap());
+static WHITESPACE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s+").unwrap());
+static IMAGE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<img\b([^>]*)>").unwrap());
+static LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<a\b([^>]*)>(.*?)</a\s*>").unwrap());
+static HEADING: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<h([1-6])\b[^>]*>(.*?)</h[1-6]\s*>").unwrap());
+static STRONG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<(?:strong|b)\b[^>]*>(.*?)</(?:strong|b)\s*>").unwrap());
+static EMPHASIS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<(?:em|i)\b[^>]*>(.*?)</(?:em|i)\s*>").unwrap());
+static CODE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<code\b[^>]*>(.*?)</code\s*>").unwrap());
+static LIST_ITEM: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<li\b[^>]*>").unwrap());
+static LINE_BREAK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<br\b[^>]*>").unwrap());
+static BLOCK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(?i)</?(?:p|div|section|article|main|ul|ol|li|table|thead|tbody|tr|blockquote|",
+        r"figure|figcaption|dl|dt|dd|hr)\b[^>]*>"
+    ))
+    .unwrap()
+});
+static TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+static BLANK_LINES: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n{3,}").unwrap());
+
+fn fetch_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("抓取网页失败: {}", message))
+}
+
+/// 读取 HTML 标签中的属性值
+pub(crate) fn attribute(attrs: &str, name: &str) -> Option<String> {
+    ATTR.captures_iter(attrs)
+        .find(|c| c[1].eq_ignore_ascii_case(name))
+        .and_then(|c| c.get(2).or_else(|| c.get(3)).or_else(|| c.get(4)))
+        .map(|m| decode_entities(m.as_str()).trim().to_string())
+}
+
+/// 去掉标签并合并空白, 用于标题、链接文字等行内文本
+pub(crate) fn inline_text(html: &str) -> String {
+    let text = decode_entities(&TAG.replace_all(html, ""));
+    WHITESPACE.replace_all(&text, " ").trim().to_string()
+}
+
+fn meta_content(html: &str, keys: &[&str]) -> Option<String> {
+    META.captures_iter(html).find_map(|c| {
+        let attrs = &c[1];
+        let key = attribute(attrs, "property").or_else(|| attribute(attrs, "name"))?;
+        if keys.iter().any(|k| key.eq_ignore_ascii_case(k)) {
+            attribute(attrs, "content").filter(|v| !v.is_empty())
+        } else {
+            None
+        }
+    })
+}
+
+// 选出正文所在的区域: 最长的 article, 其次 main, 最后 body
+fn content_region(html: &str) -> &str {
+    let longest = ARTICLE
+        .captures_iter(html)
+        .filter_map(|c| c.get(1))
+        .max_by_key(|m| m.as_str().len());
+    if let Some(article) = longest.filter(|m| inline_text(m.as_str()).len() > 200) {
+        return article.as_str();
+    }
+    if let Some(main) = MAIN.captures(html).and_then(|c| c.get(1)) {
+        return main.as_str();
+    }
+    BODY.captures(html)
+        .and_then(|c| c.get(1))
+        .map_or(html, |m| m.as_str())
+}
+
+// 只保留 http(s) 地址
+fn resolve_url(base: &Url, href: &str) -> Option<Url> {
+    let url = base.join(href).ok()?;
+    matches!(url.scheme(), "http" | "https").then_some(url)
+}
+
+// 将 HTML 转换为 Markdown, 返回正文与其中的链接
+fn html_to_markdown(html: &str, base: &Url, full_page: bool) -> (String, Vec<PageLink>) {
+    let html = BOILERPLATE.replace_all(html, "");
+    let region = if full_page {
+        BODY.captures(&html)
+            .and_then(|c| c.get(1))
+            .map_or(html.as_ref(), |m| m.as_str())
+            .to_string()
+    } else {
+        content_region(&html).to_string()
+    };
+
+    // 预格式化的代码块保留原样, 其余部分合并空白
+    let mut blocks = Vec::new();
+    let text = PRE.replace_all(&region, |c: &Captures| {
+        let code = decode_entities(&TAG.replace_all(&c[1], ""));
+        blocks.push(format!("\n\n```\n{}\n```\n\n", code.trim_matches('\n')));
+        format!("\u{0}{}\u{0}", blocks.len() - 1)
+    });
+    let text = WHITESPACE.replace_all(&text, " ");
+
+    let text = IMAGE.replace_all(&text, |c: &Captures| {
+        let alt = attribute(&c[1], "alt").unwrap_or_default();
+        match attribute(&c[1], "src").and_then(|src| resolve_url(base, &src)) {
+            Some(src) if !alt.is_empty() => format!("![{}]({})", alt, src),
+            _ => String::new(),
+        }
+    });
+    let mut links = Vec::new();
+    let mut seen = HashSet::new();
+    let text = LINK.replace_all(&text, |c: &Captures| {
+        let label = inline_text(&c[2]);
+        let Some(url) = attribute(&c[1], "href").and_then(|href| resolve_url(base, &href)) else {
+            return label;
+        };
+        if label.is_empty() {
+            return String::new();
+        }
+        if seen.insert(url.to_string()) {
+            links.push(PageLink {
+                text: label.clone(),
+                url: url.to_string(),
+            });
+        }
+        format!("[{}]({})", label, url)
+    });
+    let text = HEADING.replace_all(&text, |c: &Captures| {
+        let level: usize = c[1].parse().unwrap_or(1);
+        format!("\n\n{} {}\n\n", "#".repeat(level), inline_text(&c[2]))
+    });
+    let text = STRONG.replace_all(&text, "**$1**");
+    let text = EMPHASIS.replace_all(&text, "*$1*");
+    let text = CODE.replace_all(&text, "`$1`");
+    let text = LIST_ITEM.replace_all(&text, "\n- ");
+    let text = LINE_BREAK.replace_all(&text, "\n");
+    let text = BLOCK.replace_all(&text, "\n\n");
+    let text = decode_entities(&TAG.replace_all(&text, ""));
+
+    let text = text.lines().map(str::trim).collect::<Vec<_>>().join("\n");
+    let text = PLACEHOLDER.replace_all(&text, |c: &Captures| {
+        c[1].parse::<usize>()
+            .ok()
+            .and_then(|i| blocks.get(i).cloned())
+            .unwrap_or_default()
+    });
+    let text = BLANK_LINES.replace_all(&text, "\n\n").trim().to_string();
+    (text, links)
+}
+
+fn parse_robots(content: &str) -> Robots {
+    // 以连续的 User-agent 行开始一组规则
+    let mut groups: Vec<(Vec<String>, Vec<(bool, String)>)> = Vec::new();
+    let mut in_agents = false;
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim().to_lowercase().as_str() {
+            "user-agent" => {
+                if !in_agents {
+                    groups.push((Vec::new(), Vec::new()));
+                }
+                in_agents = true;
+                if let Some(group) = groups.last_mut() {
+                    group.0.push(value.to_lowercase());
+                }
+            }
+            rule @ ("allow" | "disallow") => {
+                in_agents = false;
+                // 空的 Disallow 表示不限制
+                if let Some(group) = groups.last_mut().filter(|_| !value.is_empty()) {
+                    group.1.push((rule == "allow", value));
+                }
+            }
+            _ => {}
+        }
+    }
+    let specific = groups.iter().find(|(agents, _)| {
+        agents
+            .iter()
+            .any(|a| a != "*" && ROBOTS_AGENT.contains(a.as_str()))
+    });
+    let group = specific.or_else(|| {
+        groups
+            .iter()
+            .find(|(agents, _)| agents.iter().any(|a| a == "*"))
+    });
+    Robots {
+        rules: group.map(|g| g.1.clone()).unwrap_or_default(),
+    }
+}
+
+// 支持 * 通配与结尾的 $
+fn robots_match(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut regex = format!("^{}", regex::escape(pattern).replace(r"\*", ".*"));
+    if anchored {
+        regex.push('$');
+    }
+    Regex::new(&regex).is_ok_and(|r| r.is_match(path))
+}
+
+impl Robots {
+    // 最长的匹配规则生效, 长度相同时 Allow 优先
+    fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| robots_match(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .map(|(allow, _)| *allow)
+            .unwrap_or(true)
+    }
+}
+
+async fn robots_for(client: &reqwest::Client, url: &Url) -> Arc<Robots> {
+    let origin = url.origin().ascii_serialization();
+    let now = chrono::Utc::now().timestamp_millis();
+    if let Some((fetched, robots)) = ROBOTS.lock().unwrap().get(&origin) {
+        if now - fetched < ROBOTS_TTL_MS {
+            return robots.clone();
+        }
+    }
+    // 无法获取时不做限制
+    let content = match client.get(format!("{}/robots.txt", origin)).send().await {
+        Ok(response) if response.status().is_success() => response.text().await.unwrap_or_default(),
+        _ => String::new(),
+    };
+    let robots = Arc::new(parse_robots(&content));
+    ROBOTS.lock().unwrap().insert(origin, (now, robots.clone()));
+    robots
+}
+
+// 按响应头中的字符集解码, 未声明时自动检测
+fn decode_body(bytes: &[u8], content_type: &str) -> String {
+    let declared = content_type
+        .split(';')
+        .filter_map(|part| part.trim().strip_prefix("charset="))
+        .next()
+        .and_then(|label| encoding_rs::Encoding::for_label(label.trim_matches('"').as_bytes()));
+    if let Some(encoding) = declared {
+        return encoding.decode(bytes).0.into_owned();
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true).decode(bytes).0.into_owned()
+}
+
+/// 访问网页使用的客户端, 带有应用标识、超时与跳转次数限制
+pub(crate) fn client(timeout_secs: u64, max_redirects: usize) -> Result<reqwest::Client> {
+    proxy::apply(reqwest::Client::builder())
+        .user_agent(USER_AGENT)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(Duration::from_secs(timeout_secs.max(1)))
+        .redirect(reqwest::redirect::Policy::limited(max_redirects))
+        .build()
+        .map_err(fetch_error)
+}
+
+/// 下载网页并提取正文
+pub(crate) async fn fetch(url: &str, options: &FetchOptions) -> Result<WebPage> {
+    let url = Url::parse(url.trim()).map_err(|e| fetch_error(format!("无效的地址: {}", e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(fetch_error("只支持 http 与 https 地址"));
+    }
+    let client = client(options.timeout_secs, options.max_redirects)?;
+    if options.respect_robots && !robots_for(&client, &url).await.allows(url.path()) {
+        return Err(fetch_error("站点的 robots.txt 不允许抓取该页面"));
+    }
+
+    let response = client.get(url.clone()).send().await.map_err(fetch_error)?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(fetch_error(format!("HTTP {}", status)));
+    }
+    let final_url = response.url().clone();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_lowercase();
+    let mut body = Vec::new();
+    let mut truncated = false;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        body.extend_from_slice(&chunk.map_err(fetch_error)?);
+        if body.len() > options.max_bytes {
+            body.truncate(options.max_bytes);
+            truncated = true;
+            break;
+        }
+    }
+    let content = decode_body(&body, &content_type);
+
+    let is_html = content_type.contains("html")
+        || (content_type.is_empty() && content.trim_start().starts_with('<'));
+    if is_html {
+        let title = meta_content(&content, &["og:title"])
+            .or_else(|| TITLE.captures(&content).map(|c| inline_text(&c[1])))
+            .unwrap_or_default();
+        let description = meta_content(&content, &["description", "og:description"]);
+        let (text, links) = html_to_markdown(&content, &final_url, options.full_page);
+        return Ok(WebPage {
+            url: final_url.to_string(),
+            title,
+            description,
+            text,
+            links,
+            truncated,
+        });
+    }
+    if content_type.starts_with("text/") || content_type.contains("json") {
+        let title = final_url
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .unwrap_or_default()
+            .to_string();
+        return Ok(WebPage {
+            url: final_url.to_string(),
+            title,
+            description: None,
+            text: content,
+            links: Vec::new(),
+            truncated,
+        });
+    }
+    Err(fetch_error(format!("不支持的内容类型: {}", content_type)))
+}
+
+/// 抓取网页并提取为 Markdown
+///
+/// # 参数
+/// * `url` - http 或 https 地址
+/// * `options` - 超时、跳转次数与大小上限, 默认遵守 robots.txt
+#[tauri::command]
+pub async fn web_fetch(url: String, options: Option<FetchOptions>) -> Result<WebPage> {
+    fetch(&url, &options.unwrap_or_default()).await
+}
@@ -0,0 +1,296 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use super::deno::{PluginError, Result};
+use super::secrets;
+use super::web::{self, attribute, inline_text};
+use crate::utils::settings::{self, WebSearchSettings};
+
+const DEFAULT_COUNT: usize = 10;
+const MAX_COUNT: usize = 20;
+// 密钥链中的作用域与键名
+const SECRET_SCOPE: &str = "web_search";
+const BRAVE_KEY: &str = "brave";
+const BRAVE_ENDPOINT: &str = "https://api.search.brave.com/res/v1/web/search";
+const DUCKDUCKGO_ENDPOINT: &str = "https://html.duckduckgo.com/html/";
+
+static DDG_LINK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<a\b([^>]*\bclass\s*=\s*"[^"]*\bresult__a\b[^"]*"[^>]*)>(.*?)</a\s*>"#)
+        .unwrap()
+});
+static DDG_SNIPPET: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r#"(?is)<(?:a|div|td)\b[^>]*\bclass\s*=\s*"[^"]*\bresult__snippet\b[^"]*"[^>]*>"#,
+        r"(.*?)</(?:a|div|td)\s*>"
+    ))
+    .unwrap()
+});
+
+/// 搜索服务
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchProvider {
+    Searxng,
+    Brave,
+    Duckduckgo,
+}
+
+impl SearchProvider {
+    fn parse(name: &str) -> Result<Self> {
+        serde_json::from_value(Value::String(name.trim().to_lowercase()))
+            .map_err(|_| search_error(format!("未知的搜索服务: {}", name)))
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Searxng => "searxng",
+            Self::Brave => "brave",
+            Self::Duckduckgo => "duckduckgo",
+        }
+    }
+}
+
+/// 统一格式的搜索结果
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+    /// 返回该结果的搜索服务
+    pub provider: SearchProvider,
+}
+
+/// 搜索设置, 不包含密钥本身
+#[derive(Debug, Serialize)]
+pub struct WebSearchConfig {
+    pub provider: SearchProvider,
+    pub searxng_url: Option<String>,
+    pub has_brave_key: bool,
+}
+
+fn search_error(message: impl std::fmt::Display) -> PluginError {
+    PluginError::Plugin(format!("网页搜索失败: {}", message))
+}
+
+// 未设置时: 配置了 SearXNG 使用 SearXNG, 有 Brave 密钥使用 Brave, 否则使用 DuckDuckGo
+fn default_provider(config: &WebSearchSettings) -> Result<SearchProvider> {
+    if let Some(name) = config.provider.as_deref() {
+        return SearchProvider::parse(name);
+    }
+    if config.searxng_url.is_some() {
+        return Ok(SearchProvider::Searxng);
+    }
+    if !secrets::get(Some(SECRET_SCOPE), BRAVE_KEY)?.is_empty() {
+        return Ok(SearchProvider::Brave);
+    }
+    Ok(SearchProvider::Duckduckgo)
+}
+
+fn result(provider: SearchProvider, title: &str, url: &str, snippet: &str) -> Option<SearchResult> {
+    let url = url.trim();
+    if url.is_empty() {
+        return None;
+    }
+    Some(SearchResult {
+        title: inline_text(title),
+        url: url.to_string(),
+        snippet: inline_text(snippet),
+        provider,
+    })
+}
+
+async fn get_json(request: reqwest::RequestBuilder) -> Result<Value> {
+    let response = request.send().await.map_err(search_error)?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(search_error(format!("HTTP {}: {}", status, body.trim())));
+    }
+    response.json().await.map_err(search_error)
+}
+
+async fn searxng(client: &reqwest::Client, base: &str, query: &str) -> Result<Vec<SearchResult>> {
+    let url = format!("{}/search", base.trim_end_matches('/'));
+    let body = get_json(client.get(url).query(&[("q", query), ("format", "json")])).await?;
+    Ok(body["results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            result(
+                SearchProvider::Searxng,
+                item["title"].as_str().unwrap_or_default(),
+                item["url"].as_str().unwrap_or_default(),
+                item["content"].as_str().unwrap_or_default(),
+            )
+        })
+        .collect())
+}
+
+async fn brave(client: &reqwest::Client, query: &str, count: usize) -> Result<Vec<SearchResult>> {
+    let key = secrets::get(Some(SECRET_SCOPE), BRAVE_KEY)?;
+    if key.is_empty() {
+        return Err(search_error("未设置 Brave Search API 密钥"));
+    }
+    let count = count.to_string();
+    let body = get_json(
+        client
+            .get(BRAVE_ENDPOINT)
+            .header("X-Subscription-Token", key)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .query(&[("q", query), ("count", count.as_str())]),
+    )
+    .await?;
+    Ok(body["web"]["results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            result(
+                SearchProvider::Brave,
+                item["title"].as_str().unwrap_or_default(),
+                item["url"].as_str().unwrap_or_default(),
+                item["description"].as_str().unwrap_or_default(),
+            )
+        })
+        .collect())
+}
+
+// 结果链接经过 DuckDuckGo 跳转, 真实地址在 uddg 参数中
+fn duckduckgo_target(href: &str) -> Option<String> {
+    let url = Url::parse("https://duckduckgo.com").ok()?.join(href).ok()?;
+    if url.path() == "/l/" {
+        return url
+            .query_pairs()
+            .find(|(key, _)| key == "uddg")
+            .map(|(_, value)| value.to_string());
+    }
+    // 广告链接指向站内的 y.js
+    (url.domain() != Some("duckduckgo.com")).then(|| url.to_string())
+}
+
+async fn duckduckgo(client: &reqwest::Client, query: &str) -> Result<Vec<SearchResult>> {
+    let response = client
+        .post(DUCKDUCKGO_ENDPOINT)
+        .form(&[("q", query)])
+        .send()
+        .await
+        .map_err(search_error)?;
+    if !response.status().is_success() {
+        return Err(search_error(format!("HTTP {}", response.status())));
+    }
+    let html = response.text().await.map_err(search_error)?;
+
+    // 摘要位于当前链接与下一个链接之间
+    let links: Vec<_> = DDG_LINK.captures_iter(&html).collect();
+    let mut results = Vec::new();
+    for (i, link) in links.iter().enumerate() {
+        let start = link.get(0).map_or(0, |m| m.end());
+        let end = links
+            .get(i + 1)
+            .and_then(|next| next.get(0))
+            .map_or(html.len(), |m| m.start());
+        let snippet = DDG_SNIPPET
+            .captures(&html[start..end])
+            .map(|c| c[1].to_string())
+            .unwrap_or_default();
+        let Some(url) = attribute(&link[1], "href").and_then(|href| duckduckgo_target(&href))
+        else {
+            continue;
+        };
+        results.extend(result(SearchProvider::Duckduckgo, &link[2], &url, &snippet));
+    }
+    Ok(results)
+}
+
+/// 使用指定或默认的服务搜索
+pub(crate) async fn search(
+    query: &str,
+    provider: Option<&str>,
+    count: usize,
+) -> Result<Vec<SearchResult>> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(search_error("搜索内容不能为空"));
+    }
+    let config = settings::get().web_search;
+    let provider = match provider {
+        Some(name) => SearchProvider::parse(name)?,
+        None => default_provider(&config)?,
+    };
+    let count = count.clamp(1, MAX_COUNT);
+    let client = web::client(web::DEFAULT_TIMEOUT_SECS, web::DEFAULT_MAX_REDIRECTS)?;
+    let mut results = match provider {
+        SearchProvider::Searxng => {
+            let base = config
+                .searxng_url
+                .as_deref()
+                .ok_or_else(|| search_error("未设置 SearXNG 地址"))?;
+            searxng(&client, base, query).await?
+        }
+        SearchProvider::Brave => brave(&client, query, count).await?,
+        SearchProvider::Duckduckgo => duckduckgo(&client, query).await?,
+    };
+    results.truncate(count);
+    tracing::debug!(
+        provider = provider.name(),
+        results = results.len(),
+        "网页搜索完成"
+    );
+    Ok(results)
+}
+
+/// 搜索网页, 不同服务的结果统一为标题、地址与摘要
+///
+/// # 参数
+/// * `provider` - searxng、brave 或 duckduckgo, 为空时使用设置中的默认服务
+/// * `count` - 返回的结果数, 默认 10, 最多 20
+#[tauri::command]
+pub async fn web_search(
+    query: String,
+    provider: Option<String>,
+    count: Option<usize>,
+) -> Result<Vec<SearchResult>> {
+    search(&query, provider.as_deref(), count.unwrap_or(DEFAULT_COUNT)).await
+}
+
+#[tauri::command]
+pub async fn web_search_settings() -> Result<WebSearchConfig> {
+    let config = settings::get().web_search;
+    Ok(WebSearchConfig {
+        provider: default_provider(&config)?,
+        searxng_url: config.searxng_url,
+        has_brave_key: !secrets::get(Some(SECRET_SCOPE), BRAVE_KEY)?.is_empty(),
+    })
+}
+
+/// 修改搜索设置
+///
+/// # 参数
+/// * `brave_api_key` - 为空字符串时删除已保存的密钥, 不传时保持不变
+#[tauri::command]
+pub async fn web_search_configure(
+    provider: Option<SearchProvider>,
+    searxng_url: Option<String>,
+    brave_api_key: Option<String>,
+) -> Result<WebSearchConfig> {
+    let searxng_url = searxng_url
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .filter(|url| !url.is_empty());
+    if let Some(url) = &searxng_url {
+        Url::parse(url).map_err(|e| search_error(format!("无效的 SearXNG 地址: {}", e)))?;
+    }
+    match brave_api_key.as_deref().map(str::trim) {
+        None => {}
+        Some("") => secrets::delete(Some(SECRET_SCOPE), BRAVE_KEY)?,
+        Some(key) => secrets::set(Some(SECRET_SCOPE), BRAVE_KEY, key)?,
+    }
+    settings::update(|s| {
+        s.web_search.provider = provider.map(|p| p.name().to_string());
+        s.web_search.searxng_url = searxng_url;
+    })?;
+    web_search_settings().await
+}
@@ -0,0 +1,774 @@
+use futures_util::future::join_all;
+use once_cell::sync::Lazy;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::oneshot;
+
+use super::approval;
+use super::db::with_db;
+use super::deno::{PluginError, Result};
+use super::host;
+use super::providers::{self, ChatMessage, ChatRequest, Sink};
+use crate::utils::gen::generate_id;
+
+// 每个工作流保留的最大运行记录数
+const MAX_RUN_RECORDS: i64 = 50;
+// 默认的重试间隔 (毫秒)
+const DEFAULT_RETRY_DELAY_MS: u64 = 1000;
+// 比较运算符, 较长的在前以免 >= 被识别为 >
+const OPERATORS: [&str; 7] = ["==", "!=", ">=", "<=", " contains ", ">", "<"];
+
+/// 工作流定义, 步骤按依赖关系组成有向无环图
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Workflow {
+    /// 新建时留空
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// 变量的默认值, 运行时传入的变量覆盖同名项
+    #[serde(default)]
+    pub variables: Map<String, Value>,
+    pub steps: Vec<Step>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+/// 工作流中的一个步骤, 输出以步骤 id 为名写入变量
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Step {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: StepKind,
+    /// 依赖的步骤, 全部完成后才会运行
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// 失败后的重试次数
+    #[serde(default)]
+    pub retries: u32,
+    #[serde(default)]
+    pub retry_delay_ms: Option<u64>,
+}
+
+/// 步骤类型, 字符串中的 {{path}} 在运行时替换为变量
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepKind {
+    /// 请求模型, 输出为回复文本
+    Prompt {
+        provider: String,
+        model: String,
+        prompt: String,
+        #[serde(default)]
+        system: Option<String>,
+        #[serde(default)]
+        temperature: Option<f32>,
+    },
+    /// 调用插件工具, 输出为工具结果
+    Tool {
+        plugin_id: String,
+        tool: String,
+        #[serde(default)]
+        args: Value,
+    },
+    /// 判断条件并选择分支, 未选中分支的步骤被跳过
+    ///
+    /// 表达式为 `左 运算符 右` 或单个值, 运算符支持 == != > < >= <= contains
+    Condition {
+        expression: String,
+        #[serde(default)]
+        then: Vec<String>,
+        #[serde(default, rename = "else")]
+        otherwise: Vec<String>,
+    },
+    /// 对列表中的每一项执行步骤, 当前项与序号分别为 item 与 index
+    Map { items: String, step: Box<StepKind> },
+    /// 弹窗等待用户确认, 拒绝时运行失败
+    Approval { message: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Skipped,
+    Cancelled,
+}
+
+/// 步骤的运行状态
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StepState {
+    pub step_id: String,
+    pub status: StepStatus,
+    pub attempts: u32,
+    pub output: Option<Value>,
+    pub error: Option<String>,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+}
+
+/// 工作流的一次运行
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkflowRun {
+    pub id: String,
+    pub workflow_id: String,
+    pub status: RunStatus,
+    pub steps: Vec<StepState>,
+    /// 运行结束时的全部变量, 包含各步骤的输出
+    pub variables: Map<String, Value>,
+    pub error: Option<String>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+/// 运行进度, 以 workflow://progress 事件发送
+#[derive(Debug, Serialize, Clone)]
+pub struct WorkflowProgress {
+    pub run_id: String,
+    pub workflow_id: String,
+    pub status: RunStatus,
+    /// 状态变化的步骤, 为空时表示运行本身的状态变化
+    pub step: Option<StepState>,
+}
+
+type Emit = Arc<dyn Fn(WorkflowProgress) + Send + Sync>;
+
+// 进行中的运行
+static RUNS: Lazy<Mutex<HashMap<String, WorkflowRun>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static CANCELS: Lazy<Mutex<HashMap<String, oneshot::Sender<()>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn invalid(message: String) -> PluginError {
+    PluginError::Plugin(message)
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+// 条件步骤的分支目标隐式依赖该条件步骤
+fn dependencies(workflow: &Workflow) -> HashMap<&str, HashSet<&str>> {
+    let mut deps: HashMap<&str, HashSet<&str>> = workflow
+        .steps
+        .iter()
+        .map(|s| {
+            (
+                s.id.as_str(),
+                s.depends_on.iter().map(String::as_str).collect(),
+            )
+        })
+        .collect();
+    for step in &workflow.steps {
+        if let StepKind::Condition {
+            then, otherwise, ..
+        } = &step.kind
+        {
+            for target in then.iter().chain(otherwise) {
+                if let Some(set) = deps.get_mut(target.as_str()) {
+                    set.insert(step.id.as_str());
+                }
+            }
+        }
+    }
+    deps
+}
+
+// 检查 id 唯一、引用存在且没有环
+fn validate(workflow: &Workflow) -> Result<()> {
+    if workflow.name.trim().is_empty() {
+        return Err(invalid("工作流名称不能为空".to_string()));
+    }
+    if workflow.steps.is_empty() {
+        return Err(invalid("工作流至少需要一个步骤".to_string()));
+    }
+    let mut ids = HashSet::new();
+    for step in &workflow.steps {
+        if step.id.trim().is_empty() {
+            return Err(invalid("步骤 id 不能为空".to_string()));
+        }
+        if !ids.insert(step.id.as_str()) {
+            return Err(invalid(format!("步骤 id 重复: {}", step.id)));
+        }
+    }
+    for step in &workflow.steps {
+        let mut references: Vec<&String> = step.depends_on.iter().collect();
+        if let StepKind::Condition {
+            then, otherwise, ..
+        } = &step.kind
+        {
+            references.extend(then.iter().chain(otherwise));
+        }
+        if let Some(missing) = references.iter().find(|id| !ids.contains(id.as_str())) {
+            return Err(invalid(format!(
+                "步骤 {} 引用了不存在的步骤: {}",
+                step.id, missing
+            )));
+        }
+        if let StepKind::Map { step: inner, .. } = &step.kind {
+            if matches!(**inner, StepKind::Condition { .. } | StepKind::Map { .. }) {
+                return Err(invalid(format!(
+                    "步骤 {} 的列表步骤不支持嵌套条件或列表",
+                    step.id
+                )));
+            }
+        }
+    }
+
+    // 按拓扑顺序逐个移除没有未完成依赖的步骤, 剩余的步骤构成环
+    let deps = dependencies(workflow);
+    let mut done: HashSet<&str> = HashSet::new();
+    while done.len() < deps.len() {
+        let ready: Vec<&str> = deps
+            .iter()
+            .filter(|(id, d)| !done.contains(*id) && d.iter().all(|d| done.contains(d)))
+            .map(|(id, _)| *id)
+            .collect();
+        if ready.is_empty() {
+            return Err(invalid("步骤的依赖存在循环".to_string()));
+        }
+        done.extend(ready);
+    }
+    Ok(())
+}
+
+// 按点分隔的路径读取变量, 数组用序号访问
+fn lookup<'a>(vars: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    let mut parts = path.split('.');
+    let mut value = vars.get(parts.next()?.trim())?;
+    for part in parts {
+        let part = part.trim();
+        value = match value {
+            Value::Object(map) => map.get(part)?,
+            Value::Array(items) => items.get(part.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+// 替换文本中的 {{path}}, 不存在的变量替换为空
+fn render(template: &str, vars: &Map<String, Value>) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let path = &rest[start + 2..start + end];
+        out.push_str(&lookup(vars, path).map(to_text).unwrap_or_default());
+        rest = &rest[start + end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// 递归替换参数中的变量, 整个字符串只有一个占位符时保留变量原本的类型
+fn render_value(value: &Value, vars: &Map<String, Value>) -> Value {
+    match value {
+        Value::String(text) => {
+            let trimmed = text.trim();
+            if let Some(path) = trimmed
+                .strip_prefix("{{")
+                .and_then(|t| t.strip_suffix("}}"))
+                .filter(|p| !p.contains("{{") && !p.contains("}}"))
+            {
+                return lookup(vars, path).cloned().unwrap_or(Value::Null);
+            }
+            Value::String(render(text, vars))
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|v| render_value(v, vars)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), render_value(v, vars)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+fn truthy(text: &str) -> bool {
+    !matches!(text.trim(), "" | "false" | "0" | "null")
+}
+
+fn evaluate(expression: &str, vars: &Map<String, Value>) -> bool {
+    for op in OPERATORS {
+        let Some(at) = expression.find(op) else {
+            continue;
+        };
+        let left = render(&expression[..at], vars).trim().to_string();
+        let right = render(&expression[at + op.len()..], vars)
+            .trim()
+            .to_string();
+        let right = right.trim_matches('"');
+        let numbers = left.parse::<f64>().ok().zip(right.parse::<f64>().ok());
+        return match (op.trim(), numbers) {
+            ("==", Some((l, r))) => l == r,
+            ("!=", Some((l, r))) => l != r,
+            ("==", None) => left == right,
+            ("!=", None) => left != right,
+            (">", Some((l, r))) => l > r,
+            ("<", Some((l, r))) => l < r,
+            (">=", Some((l, r))) => l >= r,
+            ("<=", Some((l, r))) => l <= r,
+            ("contains", _) => left.contains(right),
+            // 非数字不支持大小比较
+            _ => false,
+        };
+    }
+    truthy(&render(expression, vars))
+}
+
+// 执行列表以外的步骤, 列表步骤中的单项也由此执行
+async fn execute_single(kind: &StepKind, vars: &Map<String, Value>) -> Result<Value> {
+    match kind {
+        StepKind::Prompt {
+            provider,
+            model,
+            prompt,
+            system,
+            temperature,
+        } => {
+            let mut messages = Vec::new();
+            if let Some(system) = system {
+                messages.push(ChatMessage {
+                    role: "system".to_string(),
+                    content: render(system, vars),
+                    tool_calls: Vec::new(),
+                    tool_call_id: None,
+                });
+            }
+            messages.push(ChatMessage {
+                role: "user".to_string(),
+                content: render(prompt, vars),
+                tool_calls: Vec::new(),
+                tool_call_id: None,
+            });
+            let request = ChatRequest {
+                provider: provider.clone(),
+                model: model.clone(),
+                messages,
+                tools: Vec::new(),
+                temperature: *temperature,
+                max_tokens: None,
+                request_id: None,
+                bot: None,
+            };
+            let sink: Sink = Arc::new(|_: &str| {});
+            let response = providers::chat(&request, sink).await?;
+            Ok(Value::String(response.message.content))
+        }
+        StepKind::Tool {
+            plugin_id,
+            tool,
+            args,
+        } => {
+            let args = render_value(args, vars);
+            let result = approval::execute_confirmed(plugin_id, tool, args, "工作流", true).await?;
+            Ok(result.result)
+        }
+        StepKind::Condition { expression, .. } => Ok(Value::Bool(evaluate(expression, vars))),
+        StepKind::Approval { message } => {
+            let message = render(message, vars);
+            if host::dialog(Some("工作流确认".to_string()), message, true).await {
+                Ok(Value::Bool(true))
+            } else {
+                Err(invalid("用户拒绝了继续运行".to_string()))
+            }
+        }
+        StepKind::Map { .. } => Err(invalid("列表步骤不支持嵌套".to_string())),
+    }
+}
+
+async fn execute_kind(kind: &StepKind, vars: &Map<String, Value>) -> Result<Value> {
+    let StepKind::Map { items, step } = kind else {
+        return execute_single(kind, vars).await;
+    };
+    let list = match render_value(&Value::String(items.clone()), vars) {
+        Value::Array(list) => list,
+        // 文本形式的 JSON 数组
+        Value::String(text) => {
+            serde_json::from_str(&text).map_err(|_| invalid(format!("不是列表: {}", text)))?
+        }
+        other => return Err(invalid(format!("不是列表: {}", other))),
+    };
+    let mut outputs = Vec::with_capacity(list.len());
+    for (index, item) in list.into_iter().enumerate() {
+        let mut scope = vars.clone();
+        scope.insert("item".to_string(), item);
+        scope.insert("index".to_string(), Value::from(index));
+        outputs.push(execute_single(step, &scope).await?);
+    }
+    Ok(Value::Array(outputs))
+}
+
+// 执行步骤并按配置重试, 返回结果与尝试次数
+async fn execute_step(step: &Step, vars: &Map<String, Value>) -> (Result<Value>, u32) {
+    // 用户的拒绝不重试
+    let retries = match step.kind {
+        StepKind::Approval { .. } | StepKind::Condition { .. } => 0,
+        _ => step.retries,
+    };
+    let delay = Duration::from_millis(step.retry_delay_ms.unwrap_or(DEFAULT_RETRY_DELAY_MS));
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match execute_kind(&step.kind, vars).await {
+            Ok(value) => return (Ok(value), attempts),
+            Err(err) if attempts > retries => return (Err(err), attempts),
+            Err(err) => {
+                tracing::warn!(step = %step.id, attempts, error = %err, "工作流步骤失败, 稍后重试");
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+// 修改进行中的运行并发送进度
+fn update(emit: &Emit, run_id: &str, step_id: Option<&str>, f: impl FnOnce(&mut WorkflowRun)) {
+    let progress = {
+        let mut runs = RUNS.lock().unwrap();
+        let Some(run) = runs.get_mut(run_id) else {
+            return;
+        };
+        f(run);
+        WorkflowProgress {
+            run_id: run.id.clone(),
+            workflow_id: run.workflow_id.clone(),
+            status: run.status,
+            step: step_id.and_then(|id| run.steps.iter().find(|s| s.step_id == id).cloned()),
+        }
+    };
+    emit(progress);
+}
+
+fn set_step(run: &mut WorkflowRun, step_id: &str, f: impl FnOnce(&mut StepState)) {
+    if let Some(state) = run.steps.iter_mut().find(|s| s.step_id == step_id) {
+        f(state);
+    }
+}
+
+fn write_run(run: &WorkflowRun) -> Result<()> {
+    let data = serde_json::to_string(run)?;
+    with_db(|conn| {
+        conn.execute(
+            "INSERT INTO workflow_runs (id, workflow_id, started_at, data) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(id) DO UPDATE SET data = excluded.data",
+            params![run.id, run.workflow_id, run.started_at, data],
+        )?;
+        // 只保留最近的记录
+        conn.execute(
+            "DELETE FROM workflow_runs WHERE workflow_id = ?1 AND id NOT IN
+                 (SELECT id FROM workflow_runs WHERE workflow_id = ?1
+                  ORDER BY started_at DESC LIMIT ?2)",
+            params![run.workflow_id, MAX_RUN_RECORDS],
+        )?;
+        Ok(())
+    })
+}
+
+// 按依赖分批运行步骤, 同一批中的步骤并发执行
+async fn execute(workflow: Workflow, run_id: String, mut vars: Map<String, Value>, emit: Emit) {
+    let deps = dependencies(&workflow);
+    let mut finished: HashSet<String> = HashSet::new();
+    let mut skipped: HashSet<String> = HashSet::new();
+    let mut failure = None;
+
+    while failure.is_none() && finished.len() + skipped.len() < workflow.steps.len() {
+        let pending = workflow
+            .steps
+            .iter()
+            .filter(|s| !finished.contains(&s.id) && !skipped.contains(&s.id))
+            .collect::<Vec<_>>();
+        let mut ready = Vec::new();
+        for step in pending {
+            let step_deps = &deps[step.id.as_str()];
+            if !step_deps
+                .iter()
+                .all(|d| finished.contains(*d) || skipped.contains(*d))
+            {
+                continue;
+            }
+            // 依赖全部被跳过时同样跳过
+            if !step_deps.is_empty() && step_deps.iter().all(|d| skipped.contains(*d)) {
+                skipped.insert(step.id.clone());
+                update(&emit, &run_id, Some(step.id.as_str()), |run| {
+                    set_step(run, &step.id, |s| s.status = StepStatus::Skipped)
+                });
+                continue;
+            }
+            ready.push(step);
+        }
+        if ready.is_empty() {
+            continue;
+        }
+
+        for step in &ready {
+            update(&emit, &run_id, Some(step.id.as_str()), |run| {
+                set_step(run, &step.id, |s| {
+                    s.status = StepStatus::Running;
+                    s.started_at = Some(now());
+                })
+            });
+        }
+        let results = join_all(ready.iter().map(|step| execute_step(step, &vars))).await;
+        for (step, (result, attempts)) in ready.into_iter().zip(results) {
+            finished.insert(step.id.clone());
+            let (status, output, error) = match result {
+                Ok(output) => (StepStatus::Succeeded, Some(output), None),
+                Err(err) => {
+                    failure = Some(format!("步骤 {} 失败: {}", step.id, err));
+                    (StepStatus::Failed, None, Some(err.to_string()))
+                }
+            };
+            if let (
+                StepKind::Condition {
+                    then, otherwise, ..
+                },
+                Some(Value::Bool(chosen)),
+            ) = (&step.kind, &output)
+            {
+                let branch = if *chosen { otherwise } else { then };
+                skipped.extend(branch.iter().cloned());
+                for id in branch {
+                    update(&emit, &run_id, Some(id.as_str()), |run| {
+                        set_step(run, id, |s| s.status = StepStatus::Skipped)
+                    });
+                }
+            }
+            if let Some(output) = &output {
+                vars.insert(step.id.clone(), output.clone());
+            }
+            update(&emit, &run_id, Some(step.id.as_str()), |run| {
+                set_step(run, &step.id, |s| {
+                    s.status = status;
+                    s.attempts = attempts;
+                    s.output = output;
+                    s.error = error;
+                    s.finished_at = Some(now());
+                });
+                run.variables = vars.clone();
+            });
+        }
+    }
+
+    update(&emit, &run_id, None, |run| {
+        for state in run.steps.iter_mut() {
+            if state.status == StepStatus::Pending {
+                state.status = StepStatus::Skipped;
+            }
+        }
+        run.status = if failure.is_some() {
+            RunStatus::Failed
+        } else {
+            RunStatus::Succeeded
+        };
+        run.error = failure;
+        run.finished_at = Some(now());
+    });
+}
+
+fn find_workflow(id: &str) -> Result<Workflow> {
+    let data: Option<String> = with_db(|conn| {
+        Ok(conn
+            .query_row("SELECT data FROM workflows WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    })?;
+    let data = data.ok_or_else(|| invalid(format!("工作流不存在: {}", id)))?;
+    Ok(serde_json::from_str(&data)?)
+}
+
+/// 新建或更新工作流, id 为空时新建
+#[tauri::command]
+pub async fn workflow_save(mut workflow: Workflow) -> Result<Workflow> {
+    workflow.name = workflow.name.trim().to_string();
+    validate(&workflow)?;
+    let now = now();
+    if workflow.id.is_empty() {
+        workflow.id = generate_id();
+        workflow.created_at = now;
+    } else if let Ok(existing) = find_workflow(&workflow.id) {
+        workflow.created_at = existing.created_at;
+    }
+    workflow.updated_at = now;
+    let data = serde_json::to_string(&workflow)?;
+    with_db(|conn| {
+        conn.execute(
+            "INSERT INTO workflows (id, data) VALUES (?1, ?2)
+             ON CONFLICT(id) DO UPDATE SET data = excluded.data",
+            [&workflow.id, &data],
+        )?;
+        Ok(())
+    })?;
+    Ok(workflow)
+}
+
+#[tauri::command]
+pub async fn workflow_list() -> Result<Vec<Workflow>> {
+    let rows: Vec<String> = with_db(|conn| {
+        let mut stmt = conn.prepare("SELECT data FROM workflows")?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+    })?;
+    let mut workflows = rows
+        .iter()
+        .map(|data| Ok(serde_json::from_str(data)?))
+        .collect::<Result<Vec<Workflow>>>()?;
+    workflows.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    Ok(workflows)
+}
+
+/// 删除工作流及其运行记录
+#[tauri::command]
+pub async fn workflow_delete(id: String) -> Result<()> {
+    with_db(|conn| {
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM workflows WHERE id = ?1", [&id])?;
+        tx.execute("DELETE FROM workflow_runs WHERE workflow_id = ?1", [&id])?;
+        tx.commit()?;
+        Ok(())
+    })
+}
+
+/// 在后台运行工作流, 返回运行 id, 进度以 workflow://progress 事件推送
+///
+/// # 参数
+/// * `variables` - 覆盖工作流中同名变量的默认值
+#[tauri::command]
+pub async fn workflow_run(
+    app: AppHandle,
+    id: String,
+    variables: Option<Map<String, Value>>,
+) -> Result<String> {
+    let workflow = find_workflow(&id)?;
+    validate(&workflow)?;
+    let mut vars = workflow.variables.clone();
+    vars.extend(variables.unwrap_or_default());
+
+    let run = WorkflowRun {
+        id: generate_id(),
+        workflow_id: workflow.id.clone(),
+        status: RunStatus::Running,
+        steps: workflow
+            .steps
+            .iter()
+            .map(|s| StepState {
+                step_id: s.id.clone(),
+                status: StepStatus::Pending,
+                attempts: 0,
+                output: None,
+                error: None,
+                started_at: None,
+                finished_at: None,
+            })
+            .collect(),
+        variables: vars.clone(),
+        error: None,
+        started_at: now(),
+        finished_at: None,
+    };
+    let run_id = run.id.clone();
+    write_run(&run)?;
+    RUNS.lock().unwrap().insert(run_id.clone(), run);
+    let (cancel, cancelled) = oneshot::channel();
+    CANCELS.lock().unwrap().insert(run_id.clone(), cancel);
+
+    let emit: Emit = Arc::new(move |progress| {
+        let _ = app.emit("workflow://progress", progress);
+    });
+    let task_run_id = run_id.clone();
+    tokio::spawn(async move {
+        let run_id = task_run_id;
+        tracing::info!(workflow = %workflow.id, run = %run_id, "工作流开始运行");
+        tokio::select! {
+            _ = execute(workflow, run_id.clone(), vars, emit.clone()) => {}
+            _ = cancelled => {
+                update(&emit, &run_id, None, |run| {
+                    for state in run.steps.iter_mut() {
+                        if matches!(state.status, StepStatus::Pending | StepStatus::Running) {
+                            state.status = StepStatus::Cancelled;
+                        }
+                    }
+                    run.status = RunStatus::Cancelled;
+                    run.finished_at = Some(now());
+                });
+            }
+        }
+        CANCELS.lock().unwrap().remove(&run_id);
+        if let Some(run) = RUNS.lock().unwrap().remove(&run_id) {
+            tracing::info!(run = %run_id, status = ?run.status, "工作流运行结束");
+            if let Err(err) = write_run(&run) {
+                tracing::warn!(run = %run_id, error = %err, "保存运行记录失败");
+            }
+        }
+    });
+    Ok(run_id)
+}
+
+/// 取消进行中的运行
+#[tauri::command]
+pub async fn workflow_cancel(run_id: String) -> Result<()> {
+    if let Some(cancel) = CANCELS.lock().unwrap().remove(&run_id) {
+        let _ = cancel.send(());
+    }
+    Ok(())
+}
+
+/// 读取运行状态, 包含进行中与已结束的运行
+#[tauri::command]
+pub async fn workflow_status(run_id: String) -> Result<WorkflowRun> {
+    if let Some(run) = RUNS.lock().unwrap().get(&run_id) {
+        return Ok(run.clone());
+    }
+    let data: Option<String> = with_db(|conn| {
+        Ok(conn
+            .query_row(
+                "SELECT data FROM workflow_runs WHERE id = ?1",
+                [&run_id],
+                |row| row.get(0),
+            )
+            .optional()?)
+    })?;
+    let data = data.ok_or_else(|| invalid(format!("运行记录不存在: {}", run_id)))?;
+    let mut run: WorkflowRun = serde_json::from_str(&data)?;
+    // 不在进行中却仍为运行状态, 说明应用在运行期间退出
+    if run.status == RunStatus::Running {
+        run.status = RunStatus::Failed;
+        run.error = Some("应用退出, 运行已中断".to_string());
+    }
+    Ok(run)
+}
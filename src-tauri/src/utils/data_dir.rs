@@ -0,0 +1,224 @@
+use super::file::write_atomic;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::AppHandle;
+use walkdir::WalkDir;
+
+// 默认数据目录下记录实际位置的文件, 迁移数据时保留在原处
+const POINTER_FILE: &str = "data_dir.json";
+
+/// 数据目录信息
+#[derive(Debug, Serialize, Clone)]
+pub struct DataDirInfo {
+    /// 当前使用的数据目录
+    pub path: String,
+    pub default_path: String,
+    pub is_default: bool,
+    /// 等待下次启动时迁移到的目录
+    pub pending: Option<String>,
+    /// 上次迁移失败的原因, 失败时继续使用原目录
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+struct Pointer {
+    /// 自定义的数据目录, None 表示使用默认目录
+    path: Option<PathBuf>,
+    /// 下次启动时把数据从 path 迁移到这里
+    pending: Option<PathBuf>,
+    last_error: Option<String>,
+}
+
+// 启动时解析一次, 修改后需要重启才会生效
+static ROOT: Lazy<RwLock<Option<PathBuf>>> = Lazy::new(|| RwLock::new(resolve()));
+
+/// 默认数据目录 ~/.ghostie
+pub fn default_root() -> Option<PathBuf> {
+    let home = env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok()?;
+    Some(PathBuf::from(home).join(".ghostie"))
+}
+
+fn pointer_path() -> Option<PathBuf> {
+    Some(default_root()?.join(POINTER_FILE))
+}
+
+fn load_pointer() -> Pointer {
+    pointer_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_pointer(pointer: &Pointer) -> Result<(), String> {
+    let path = pointer_path().ok_or("无法获取用户目录")?;
+    fs::create_dir_all(path.parent().unwrap()).map_err(|e| format!("创建目录失败: {}", e))?;
+    let content =
+        serde_json::to_string_pretty(pointer).map_err(|e| format!("序列化失败: {}", e))?;
+    write_atomic(&path, content.as_bytes()).map_err(|e| format!("保存数据目录失败: {}", e))
+}
+
+fn resolve() -> Option<PathBuf> {
+    load_pointer().path.or_else(default_root)
+}
+
+/// 当前数据目录, 配置、插件与数据都保存在这里
+pub fn root() -> Option<PathBuf> {
+    ROOT.read().unwrap().clone()
+}
+
+fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(true)
+}
+
+// 目录中除了指向文件以外的条目
+fn entries(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name() != POINTER_FILE {
+            entries.push(entry.path());
+        }
+    }
+    Ok(entries)
+}
+
+// 先完整复制再删除源文件, 复制失败时删除已复制的部分, 原目录保持不变
+fn move_dir(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| format!("创建目录失败: {}", e))?;
+    let sources = entries(from).map_err(|e| format!("读取数据目录失败: {}", e))?;
+
+    let copy = || -> std::io::Result<()> {
+        for source in &sources {
+            for entry in WalkDir::new(source).follow_links(false) {
+                let entry = entry?;
+                let relative = entry.path().strip_prefix(from).unwrap();
+                let target = to.join(relative);
+                if entry.file_type().is_dir() {
+                    fs::create_dir_all(&target)?;
+                } else {
+                    let copied = fs::copy(entry.path(), &target)?;
+                    if copied != entry.metadata()?.len() {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            format!("文件大小不一致: {}", relative.display()),
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    };
+    if let Err(err) = copy() {
+        if let Ok(created) = entries(to) {
+            for path in created {
+                let _ = if path.is_dir() {
+                    fs::remove_dir_all(&path)
+                } else {
+                    fs::remove_file(&path)
+                };
+            }
+        }
+        return Err(format!("复制数据失败: {}", err));
+    }
+
+    // 复制完成后才删除原数据, 删除失败只会留下多余的文件
+    for source in sources {
+        let _ = if source.is_dir() {
+            fs::remove_dir_all(&source)
+        } else {
+            fs::remove_file(&source)
+        };
+    }
+    Ok(())
+}
+
+/// 执行等待中的迁移, 需要在打开数据库、日志等任何文件之前调用
+pub fn apply_pending() {
+    let mut pointer = load_pointer();
+    let Some(target) = pointer.pending.take() else {
+        return;
+    };
+    let Some(current) = pointer.path.clone().or_else(default_root) else {
+        return;
+    };
+    match move_dir(&current, &target) {
+        Ok(()) => {
+            pointer.path = Some(target).filter(|path| Some(path) != default_root().as_ref());
+            pointer.last_error = None;
+        }
+        Err(err) => pointer.last_error = Some(err),
+    }
+    let _ = save_pointer(&pointer);
+    *ROOT.write().unwrap() = resolve();
+}
+
+/// 读取数据目录的位置
+#[tauri::command]
+pub async fn data_dir_get() -> Result<DataDirInfo, String> {
+    let pointer = load_pointer();
+    let default = default_root().ok_or("无法获取用户目录")?;
+    let current = root().unwrap_or_else(|| default.clone());
+    Ok(DataDirInfo {
+        path: current.to_string_lossy().to_string(),
+        default_path: default.to_string_lossy().to_string(),
+        is_default: current == default,
+        pending: pointer
+            .pending
+            .map(|path| path.to_string_lossy().to_string()),
+        last_error: pointer.last_error,
+    })
+}
+
+/// 修改数据目录并重启应用
+///
+/// # 参数
+/// * `path` - 新的数据目录, 传入默认目录即恢复默认位置
+/// * `move_existing` - 为 true 时在重启后把现有数据迁移过去, 目标目录必须为空;
+///   为 false 时直接使用目标目录中已有的数据
+#[tauri::command]
+pub async fn data_dir_set(app: AppHandle, path: String, move_existing: bool) -> Result<(), String> {
+    let target = PathBuf::from(&path);
+    if !target.is_absolute() {
+        return Err("数据目录必须是绝对路径".to_string());
+    }
+    let default = default_root().ok_or("无法获取用户目录")?;
+    let current = root().unwrap_or_else(|| default.clone());
+    if target == current {
+        return Ok(());
+    }
+    if target.starts_with(&current) || current.starts_with(&target) {
+        return Err("新目录不能位于当前数据目录之内或包含当前数据目录".to_string());
+    }
+
+    // 确认目标目录可以写入
+    fs::create_dir_all(&target).map_err(|e| format!("无法创建数据目录: {}", e))?;
+    let probe = target.join(".ghostie-write-test");
+    fs::write(&probe, b"").map_err(|e| format!("数据目录不可写入: {}", e))?;
+    let _ = fs::remove_file(&probe);
+
+    let mut pointer = load_pointer();
+    pointer.last_error = None;
+    if move_existing {
+        // 恢复默认目录时, 默认目录中只剩下指向文件
+        let empty = if target == default {
+            entries(&target).map(|e| e.is_empty()).unwrap_or(true)
+        } else {
+            is_empty_dir(&target)
+        };
+        if !empty {
+            return Err("目标目录不为空, 无法迁移现有数据".to_string());
+        }
+        pointer.pending = Some(target);
+    } else {
+        pointer.pending = None;
+        pointer.path = Some(target).filter(|path| path != &default);
+    }
+    save_pointer(&pointer)?;
+    app.restart();
+}
@@ -51,3 +51,8 @@ pub fn read_pdf(path: &str) -> Result<String> {
         Err(e) => Err(anyhow::anyhow!("PDF 解析错误: {}", e)),
     }
 }
+
+/// 按页提取 PDF 文本, 页码从 1 开始对应返回值的下标加 1
+pub fn read_pdf_pages(path: &str) -> Result<Vec<String>> {
+    pdf_extract::extract_text_by_pages(path).map_err(|e| anyhow::anyhow!("PDF 解析错误: {}", e))
+}
@@ -1,6 +1,6 @@
+use super::data_dir;
 use crate::utils::document::{read_docx, read_pdf};
 use anyhow::Result;
-use std::env;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
@@ -22,11 +22,31 @@ pub struct FileMetadata {
     pub is_dir: bool,
 }
 
-/// 获取配置目录，如果目录不存在则创建
+/// 原子写入文件: 先写入临时文件并落盘, 再替换目标文件, 写入前保留一份 .bak 备份
+pub fn write_atomic(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let tmp = path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    drop(file);
+
+    if path.exists() {
+        fs::copy(path, path.with_extension("bak"))?;
+    }
+    fs::rename(&tmp, path)?;
+    // 同步目录项, 保证重命名本身已落盘
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// 获取配置目录，如果目录不存在则创建, 默认为 ~/.ghostie, 可通过 data_dir_set 修改
 pub fn get_config_dir() -> Option<PathBuf> {
-    let home = env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok()?;
-    let mut path = PathBuf::from(home);
-    path.push(".ghostie");
+    let path = data_dir::root()?;
 
     // 如果目录不存在，尝试创建
     if !path.exists() {
@@ -0,0 +1,121 @@
+use super::file::{get_config_dir, write_atomic};
+use super::{settings, workspace};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 一个迁移步骤, 将数据从 version - 1 升级到 version
+pub struct Step {
+    pub version: i64,
+    pub description: &'static str,
+}
+
+/// 一项数据的迁移结果
+#[derive(Debug, Serialize, Clone)]
+pub struct MigrationReport {
+    /// 数据名称, 如 database、settings
+    pub artifact: String,
+    pub from: i64,
+    pub to: i64,
+    /// 已执行步骤的说明
+    pub applied: Vec<String>,
+    /// 迁移前的备份文件
+    pub backup: Option<String>,
+    /// 迁移失败时的错误, 失败的数据保持原样
+    pub error: Option<String>,
+    pub migrated_at: i64,
+}
+
+// 本次启动中执行过的迁移
+static REPORTS: Lazy<Mutex<Vec<MigrationReport>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// 按版本顺序执行高于 from 的步骤, 任一步骤失败时停止, 调用方需丢弃已做的修改
+///
+/// # 参数
+/// * `artifact` - 数据名称, 写入报告
+/// * `from` - 当前数据的版本
+/// * `steps` - 按版本递增排列的迁移步骤
+/// * `apply` - 执行指定版本的步骤
+pub fn run<E: std::fmt::Display>(
+    artifact: &str,
+    from: i64,
+    steps: &[Step],
+    mut apply: impl FnMut(i64) -> Result<(), E>,
+) -> MigrationReport {
+    let mut report = MigrationReport {
+        artifact: artifact.to_string(),
+        from,
+        to: from,
+        applied: Vec::new(),
+        backup: None,
+        error: None,
+        migrated_at: Utc::now().timestamp_millis(),
+    };
+    for step in steps.iter().filter(|step| step.version > from) {
+        if let Err(err) = apply(step.version) {
+            report.to = from;
+            report.applied.clear();
+            report.error = Some(format!("{} (v{}): {}", step.description, step.version, err));
+            break;
+        }
+        report.to = step.version;
+        report.applied.push(step.description.to_string());
+    }
+    report
+}
+
+// 备份保存在当前工作区的 backups/migrations 目录下
+fn backup_dir() -> Result<PathBuf, String> {
+    let dir = workspace::dir()
+        .or_else(get_config_dir)
+        .ok_or("无法获取配置目录")?
+        .join("backups")
+        .join("migrations");
+    fs::create_dir_all(&dir).map_err(|e| format!("创建备份目录失败: {}", e))?;
+    Ok(dir)
+}
+
+/// 生成迁移前的备份路径, 如 backups/migrations/settings.toml.v1.1700000000000
+pub fn backup_path(file_name: &str, label: &str) -> Result<PathBuf, String> {
+    Ok(backup_dir()?.join(format!(
+        "{}.{}.{}",
+        file_name,
+        label,
+        Utc::now().timestamp_millis()
+    )))
+}
+
+/// 迁移前复制一份文件
+pub fn backup_file(path: &Path, label: &str) -> Result<PathBuf, String> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or("无效的文件路径")?;
+    let backup = backup_path(&file_name, label)?;
+    fs::copy(path, &backup).map_err(|e| format!("备份 {} 失败: {}", file_name, e))?;
+    Ok(backup)
+}
+
+/// 记录迁移结果, 同时追加到备份目录下的 history.jsonl
+pub fn record(report: MigrationReport) {
+    if let Ok(path) = backup_dir().map(|dir| dir.join("history.jsonl")) {
+        let mut content = fs::read_to_string(&path).unwrap_or_default();
+        if let Ok(line) = serde_json::to_string(&report) {
+            content.push_str(&line);
+            content.push('\n');
+            let _ = write_atomic(&path, content.as_bytes());
+        }
+    }
+    REPORTS.lock().unwrap().push(report);
+}
+
+/// 本次启动执行的迁移, 没有需要升级的数据时为空
+#[tauri::command]
+pub async fn migration_report() -> Result<Vec<MigrationReport>, String> {
+    // 确保设置已经加载, 数据库在启动时已经打开
+    let _ = settings::get();
+    Ok(REPORTS.lock().unwrap().clone())
+}
@@ -1,5 +1,9 @@
+pub mod data_dir;
 pub mod document;
 pub mod file;
 pub mod gen;
+pub mod migrate;
+pub mod settings;
 pub mod update;
 pub mod window;
+pub mod workspace;
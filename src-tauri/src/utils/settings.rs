@@ -0,0 +1,449 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use super::file::write_atomic;
+use super::migrate::{self, Step};
+use super::workspace;
+
+// 设置文件的结构版本, 写入 settings.toml 的 schema_version
+const SETTINGS_VERSION: i64 = 1;
+
+// 旧版本设置的升级步骤, 新增字段依靠 serde(default) 补全, 只有重命名或改变类型时才需要新步骤
+const MIGRATIONS: &[Step] = &[Step {
+    version: 1,
+    description: "记录设置文件的结构版本",
+}];
+
+/// 插件进程的资源限制, None 表示不限制
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ResourceLimits {
+    /// JS 堆内存上限 (MB)
+    pub memory_mb: Option<u32>,
+    /// CPU 时间上限 (秒), 仅在类 Unix 系统生效
+    pub cpu_seconds: Option<u64>,
+    /// 执行超时 (秒), 超时后终止进程
+    pub timeout_secs: Option<u64>,
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            memory_mb: Some(1024),
+            cpu_seconds: None,
+            timeout_secs: Some(120),
+        }
+    }
+}
+
+// 插件运行时设置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RuntimeSettings {
+    /// 自定义 Deno 可执行文件路径
+    pub deno_path: Option<String>,
+    /// 离线模式, 执行时只使用已缓存的依赖
+    pub offline: bool,
+    pub limits: ResourceLimits,
+}
+
+/// 本地 HTTP 服务设置, 默认关闭
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ServerSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// 访问令牌, 请求需携带 Authorization: Bearer <token>
+    pub token: Option<String>,
+    /// 允许跨域访问的来源, 如 http://localhost:3000, * 表示全部
+    pub cors_origins: Vec<String>,
+    /// 可以通过 MCP 调用插件的外部应用
+    pub mcp_clients: Vec<McpClientAccess>,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 17321,
+            token: None,
+            cors_origins: Vec::new(),
+            mcp_clients: Vec::new(),
+        }
+    }
+}
+
+/// 外部 MCP 客户端的访问配置, 每个客户端使用独立的令牌
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct McpClientAccess {
+    pub id: String,
+    pub name: String,
+    pub token: String,
+    /// 允许调用的插件 id, 为空时不暴露任何插件
+    #[serde(default)]
+    pub plugins: Vec<String>,
+}
+
+/// 插件仓库设置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct RegistrySettings {
+    /// 仓库索引地址, 按顺序合并, 同名插件以靠前的为准
+    pub urls: Vec<String>,
+}
+
+/// 受信任的插件发布者
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrustedKey {
+    pub name: String,
+    /// base64 编码的 ed25519 公钥
+    pub public_key: String,
+}
+
+/// 插件签名设置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SignatureSettings {
+    /// 受信任的发布者公钥, 未由这些公钥签名的插件需要用户确认后才能导入
+    pub trusted_keys: Vec<TrustedKey>,
+}
+
+/// 网页搜索设置, API 密钥保存在系统密钥链中
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct WebSearchSettings {
+    /// 默认的搜索服务: searxng、brave 或 duckduckgo
+    pub provider: Option<String>,
+    /// SearXNG 实例地址, 如 https://searx.example.com
+    pub searxng_url: Option<String>,
+}
+
+/// 快捷键设置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct HotkeySettings {
+    /// 打开快速提问窗口的全局快捷键, 如 CommandOrControl+Shift+Space, 为空时不注册
+    pub quick_capture: Option<String>,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            quick_capture: Some("CommandOrControl+Shift+Space".to_string()),
+        }
+    }
+}
+
+/// 固定在托盘菜单中的插件工具
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PinnedTool {
+    pub plugin: String,
+    pub tool: String,
+}
+
+/// 托盘设置
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TraySettings {
+    /// 关闭主窗口时隐藏到托盘而不是退出
+    pub close_to_tray: bool,
+    pub pinned_tools: Vec<PinnedTool>,
+}
+
+/// 剪贴板内容的匹配方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardMatch {
+    /// 单个 http 或 https 链接
+    Url,
+    /// 代码块或多行代码
+    Code,
+    /// 超过 min_length 个字符的文本
+    Length,
+    /// 匹配 pattern 正则表达式
+    Regex,
+}
+
+/// 剪贴板匹配规则
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipboardRule {
+    pub name: String,
+    pub kind: ClipboardMatch,
+    #[serde(default)]
+    pub min_length: Option<usize>,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// 绑定的插件工具, 为空时只发送事件由界面提供操作
+    #[serde(default)]
+    pub plugin_id: Option<String>,
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// 剪贴板文本写入的参数名, 默认 text
+    #[serde(default)]
+    pub arg_name: Option<String>,
+}
+
+/// 剪贴板监听设置, 默认关闭
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ClipboardSettings {
+    pub enabled: bool,
+    /// 检查剪贴板的间隔 (毫秒)
+    pub interval_ms: u64,
+    /// 跳过包含已保存密钥或常见令牌格式的内容
+    pub ignore_secrets: bool,
+    /// 超过该字符数的内容不做处理
+    pub max_length: usize,
+    pub rules: Vec<ClipboardRule>,
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        let rule = |name: &str, kind| ClipboardRule {
+            name: name.to_string(),
+            kind,
+            min_length: None,
+            pattern: None,
+            plugin_id: None,
+            tool: None,
+            arg_name: None,
+        };
+        Self {
+            enabled: false,
+            interval_ms: 1000,
+            ignore_secrets: true,
+            max_length: 100_000,
+            rules: vec![
+                rule("链接", ClipboardMatch::Url),
+                rule("代码", ClipboardMatch::Code),
+            ],
+        }
+    }
+}
+
+/// 后台运行设置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BackgroundSettings {
+    /// 关闭窗口后继续在后台运行定时任务、本地服务与后台插件
+    pub enabled: bool,
+}
+
+impl Default for BackgroundSettings {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// 自动更新设置
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct UpdateSettings {
+    /// 更新通道: stable 或 beta
+    pub channel: String,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            channel: "stable".to_string(),
+        }
+    }
+}
+
+/// 同步服务的类型
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncBackend {
+    #[default]
+    Webdav,
+    /// S3 及兼容 S3 接口的对象存储
+    S3,
+}
+
+/// 本机与远端都修改了同一项时的处理方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncConflictPolicy {
+    /// 以最后修改的一方为准
+    #[default]
+    LastWriterWins,
+    /// 记录冲突, 由用户逐项选择
+    Prompt,
+}
+
+/// 配置同步设置, 密码与加密口令保存在系统密钥链中, 不参与同步
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SyncSettings {
+    /// 按间隔自动同步
+    pub enabled: bool,
+    pub backend: SyncBackend,
+    /// WebDAV 目录地址或 S3 服务地址
+    pub endpoint: String,
+    /// S3 存储桶
+    pub bucket: Option<String>,
+    /// S3 区域, 默认 us-east-1
+    pub region: Option<String>,
+    /// WebDAV 用户名或 S3 Access Key ID
+    pub username: Option<String>,
+    /// 远端保存同步数据的目录
+    pub prefix: String,
+    /// 自动同步的间隔 (分钟)
+    pub interval_minutes: u64,
+    pub conflict: SyncConflictPolicy,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: SyncBackend::default(),
+            endpoint: String::new(),
+            bucket: None,
+            region: None,
+            username: None,
+            prefix: "ghostie".to_string(),
+            interval_minutes: 30,
+            conflict: SyncConflictPolicy::default(),
+        }
+    }
+}
+
+/// 代理协议
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyKind {
+    #[default]
+    Http,
+    Https,
+    Socks5,
+}
+
+/// 网络代理设置, 用于模型服务、插件仓库、网址导入与插件执行, 密码保存在系统密钥链中
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ProxySettings {
+    pub enabled: bool,
+    pub kind: ProxyKind,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    /// 不经过代理的地址, 如 internal.example.com、.corp.example.com、10.0.0.0/8
+    pub bypass: Vec<String>,
+}
+
+/// 应用设置, 保存在配置目录的 settings.toml 中
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Settings {
+    /// 插件名称与描述使用的语言, 如 zh-CN
+    pub locale: Option<String>,
+    /// 启用的环境配置名称
+    pub profile: Option<String>,
+    /// 日志级别, 默认 info
+    pub log_level: Option<String>,
+    pub runtime: RuntimeSettings,
+    pub server: ServerSettings,
+    pub registry: RegistrySettings,
+    pub signature: SignatureSettings,
+    pub web_search: WebSearchSettings,
+    pub hotkey: HotkeySettings,
+    pub tray: TraySettings,
+    pub clipboard: ClipboardSettings,
+    pub background: BackgroundSettings,
+    pub update: UpdateSettings,
+    pub sync: SyncSettings,
+    pub proxy: ProxySettings,
+}
+
+static SETTINGS: Lazy<RwLock<Settings>> = Lazy::new(|| RwLock::new(load_from_disk()));
+
+// 每个工作区有独立的设置文件
+fn settings_path() -> Option<PathBuf> {
+    let mut path = workspace::dir()?;
+    path.push("settings.toml");
+    Some(path)
+}
+
+fn load_from_disk() -> Settings {
+    let Some(path) = settings_path() else {
+        return Settings::default();
+    };
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Settings::default();
+    };
+    let mut value: toml::Value = match toml::from_str(&content) {
+        Ok(value) => value,
+        Err(_) => {
+            // 无法解析时先保留原文件, 避免下次保存时覆盖
+            let _ = migrate::backup_file(&path, "unreadable");
+            return Settings::default();
+        }
+    };
+
+    let from = value
+        .get("schema_version")
+        .and_then(toml::Value::as_integer)
+        .unwrap_or(0);
+    if from < SETTINGS_VERSION {
+        let backup = migrate::backup_file(&path, &format!("v{}", from));
+        let original = value.clone();
+        let mut report = migrate::run("settings", from, MIGRATIONS, |version| {
+            apply_migration(&mut value, version)
+        });
+        report.backup = backup.ok().map(|path| path.to_string_lossy().to_string());
+        if report.error.is_some() {
+            // 迁移失败时不写回文件, 按原内容尽量读取
+            value = original;
+        } else if let Err(err) = write_value(&path, value.clone()) {
+            report.error = Some(err);
+        }
+        migrate::record(report);
+    }
+
+    value.try_into().unwrap_or_else(|_| {
+        let _ = migrate::backup_file(&path, "unreadable");
+        Settings::default()
+    })
+}
+
+// 执行指定版本的迁移步骤
+fn apply_migration(_value: &mut toml::Value, version: i64) -> Result<(), String> {
+    match version {
+        // 旧文件没有版本号, 内容无需调整, 写回时会补上 schema_version
+        1 => Ok(()),
+        _ => Err(format!("未知的设置版本: {}", version)),
+    }
+}
+
+// 写入设置文件并附上结构版本
+fn write_value(path: &Path, mut value: toml::Value) -> Result<(), String> {
+    if let Some(table) = value.as_table_mut() {
+        table.insert(
+            "schema_version".to_string(),
+            toml::Value::Integer(SETTINGS_VERSION),
+        );
+    }
+    let content = toml::to_string(&value).map_err(|e| format!("序列化设置失败: {}", e))?;
+    write_atomic(path, content.as_bytes()).map_err(|e| format!("保存设置失败: {}", e))
+}
+
+/// 读取当前设置
+pub fn get() -> Settings {
+    SETTINGS.read().unwrap().clone()
+}
+
+/// 修改设置并写回磁盘
+pub fn update<F: FnOnce(&mut Settings)>(f: F) -> Result<Settings, String> {
+    let mut settings = SETTINGS.write().unwrap();
+    f(&mut settings);
+
+    let path = settings_path().ok_or("无法获取配置目录")?;
+    let value = toml::Value::try_from(&*settings).map_err(|e| format!("序列化设置失败: {}", e))?;
+    write_value(&path, value)?;
+    Ok(settings.clone())
+}
@@ -1,37 +1,182 @@
-use tauri::AppHandle;
-use tauri_plugin_updater::UpdaterExt;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_updater::{Update, Updater, UpdaterExt};
+use url::Url;
+
+use super::settings;
+
+// 各通道的更新清单, beta 通道发布在固定的 beta 标签下
+const STABLE_ENDPOINT: &str =
+    "https://github.com/wangenius/ghostie/releases/latest/download/latest.json";
+const BETA_ENDPOINT: &str =
+    "https://github.com/wangenius/ghostie/releases/download/beta/latest.json";
+const CHANNELS: [&str; 2] = ["stable", "beta"];
+
+// 已下载但尚未安装的更新
+static PENDING: Lazy<Mutex<Option<(Update, Vec<u8>)>>> = Lazy::new(|| Mutex::new(None));
+
+/// 更新信息
+#[derive(Debug, Serialize, Clone)]
+pub struct UpdateInfo {
+    pub channel: String,
+    pub current_version: String,
+    pub available: bool,
+    pub version: Option<String>,
+    pub date: Option<String>,
+    /// 更新说明
+    pub notes: Option<String>,
+    /// 是否已下载, 等待安装
+    pub downloaded: bool,
+}
+
+/// 下载进度, 以 update://progress 事件发送
+#[derive(Debug, Serialize, Clone)]
+pub struct UpdateProgress {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+fn channel() -> String {
+    settings::get().update.channel
+}
+
+fn updater(app: &AppHandle, channel: &str) -> Result<Updater, String> {
+    let endpoint = match channel {
+        "beta" => BETA_ENDPOINT,
+        _ => STABLE_ENDPOINT,
+    };
+    let url = Url::parse(endpoint).map_err(|e| e.to_string())?;
+    app.updater_builder()
+        .endpoints(vec![url])
+        .map_err(|e| e.to_string())?
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+fn info(app: &AppHandle, channel: String, update: Option<&Update>, downloaded: bool) -> UpdateInfo {
+    UpdateInfo {
+        channel,
+        current_version: app.package_info().version.to_string(),
+        available: update.is_some(),
+        version: update.map(|u| u.version.clone()),
+        date: update.and_then(|u| u.date).map(|d| d.to_string()),
+        notes: update.and_then(|u| u.body.clone()),
+        downloaded,
+    }
+}
+
+// 下载更新并暂存, 进度以事件发送
+async fn download(app: &AppHandle, update: &Update) -> Result<Vec<u8>, String> {
+    let mut downloaded = 0u64;
+    let handle = app.clone();
+    let bytes = update
+        .download(
+            move |chunk_length, total| {
+                downloaded += chunk_length as u64;
+                let _ = handle.emit("update://progress", UpdateProgress { downloaded, total });
+            },
+            || {},
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("update://downloaded", update.version.clone());
+    Ok(bytes)
+}
+
+/// 退出时安装已下载的更新
+pub fn install_pending() {
+    if let Some((update, bytes)) = PENDING.lock().unwrap().take() {
+        if let Err(err) = update.install(bytes) {
+            tracing::warn!(error = %err, "安装更新失败");
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn check_update(app: AppHandle) -> Result<bool, String> {
-    match app.updater() {
-        Ok(updater) => match updater.check().await {
-            Ok(update) => Ok(update.is_some()),
-            Err(e) => Err(e.to_string()),
-        },
-        Err(e) => Err(e.to_string()),
-    }
+    let update = updater(&app, &channel())?
+        .check()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(update.is_some())
 }
 
 #[tauri::command]
 pub async fn install_update(app: AppHandle) -> Result<(), String> {
-    let updater = app.updater().map_err(|e| e.to_string())?;
-    let update = updater.check().await.map_err(|e| e.to_string())?;
+    let update = updater(&app, &channel())?
+        .check()
+        .await
+        .map_err(|e| e.to_string())?;
 
     if let Some(update) = update {
-        let mut downloaded = 0;
-        let progress = move |chunk_length: usize, content_length: Option<u64>| {
-            downloaded += chunk_length;
-            println!("已下载 {downloaded} / {content_length:?}");
-        };
-        let finished = || println!("下载完成");
-
-        update
-            .download_and_install(progress, finished)
-            .await
-            .map_err(|e| e.to_string())?;
-
+        let bytes = download(&app, &update).await?;
+        update.install(bytes).map_err(|e| e.to_string())?;
         println!("更新已安装");
         app.restart();
     }
 
     Ok(())
 }
+
+#[tauri::command]
+pub async fn update_channel_get() -> Result<String, String> {
+    Ok(channel())
+}
+
+/// 切换更新通道, 已下载的其他通道的更新会被丢弃
+#[tauri::command]
+pub async fn update_channel_set(channel: String) -> Result<String, String> {
+    let channel = channel.trim().to_lowercase();
+    if !CHANNELS.contains(&channel.as_str()) {
+        return Err(format!("未知的更新通道: {}", channel));
+    }
+    settings::update(|s| s.update.channel = channel.clone())?;
+    PENDING.lock().unwrap().take();
+    Ok(channel)
+}
+
+/// 检查当前通道的更新, 返回版本与更新说明
+#[tauri::command]
+pub async fn update_check(app: AppHandle) -> Result<UpdateInfo, String> {
+    let channel = channel();
+    let update = updater(&app, &channel)?
+        .check()
+        .await
+        .map_err(|e| e.to_string())?;
+    let downloaded = match (&update, PENDING.lock().unwrap().as_ref()) {
+        (Some(update), Some((pending, _))) => pending.version == update.version,
+        _ => false,
+    };
+    Ok(info(&app, channel, update.as_ref(), downloaded))
+}
+
+/// 在后台下载更新, 进度以 update://progress 事件发送, 完成后在退出或调用 update_install 时安装
+#[tauri::command]
+pub async fn update_download(app: AppHandle) -> Result<UpdateInfo, String> {
+    let channel = channel();
+    let update = updater(&app, &channel)?
+        .check()
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("已是最新版本")?;
+    let bytes = download(&app, &update).await?;
+    let result = info(&app, channel, Some(&update), true);
+    *PENDING.lock().unwrap() = Some((update, bytes));
+    Ok(result)
+}
+
+/// 安装已下载的更新, restart 为 true 时立即重启, 否则在下次退出时生效
+#[tauri::command]
+pub async fn update_install(app: AppHandle, restart: Option<bool>) -> Result<(), String> {
+    let staged = PENDING.lock().unwrap().is_some();
+    if !staged {
+        update_download(app.clone()).await?;
+    }
+    if restart.unwrap_or(true) {
+        install_pending();
+        app.restart();
+    }
+    Ok(())
+}
@@ -1,9 +1,44 @@
-use super::file::get_config_dir;
+use super::file::{get_config_dir, write_atomic};
+use once_cell::sync::Lazy;
 use open;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tauri::{LogicalSize, Manager, Size, WindowEvent};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{
+    AppHandle, LogicalSize, Manager, Size, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+    WindowEvent,
+};
 use urlencoding;
 
+// 独立窗口的默认大小
+const DEFAULT_WIDTH: f64 = 900.0;
+const DEFAULT_HEIGHT: f64 = 700.0;
+// 最多保留的窗口状态数, 超出时删除最早关闭的
+const MAX_WINDOW_STATES: usize = 100;
+// 移动或缩放停止后再写入磁盘
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// 独立窗口的位置与大小 (逻辑像素)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WindowState {
+    pub label: String,
+    pub kind: String,
+    pub context_id: String,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub width: f64,
+    pub height: f64,
+    /// 退出时仍打开的窗口在下次启动时恢复
+    pub open: bool,
+}
+
+static STATES: Lazy<Mutex<Vec<WindowState>>> = Lazy::new(|| Mutex::new(load_states()));
+static SAVE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
 #[tauri::command]
 pub async fn open_window(
     app: tauri::AppHandle,
@@ -79,3 +114,201 @@ pub async fn open_config_dir(_: tauri::AppHandle) -> Result<(), String> {
     open::that(config_dir).map_err(|e| format!("Failed to open config directory: {}", e))?;
     Ok(())
 }
+
+fn states_path() -> Option<PathBuf> {
+    Some(get_config_dir()?.join("windows.json"))
+}
+
+fn load_states() -> Vec<WindowState> {
+    states_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_states() {
+    let Some(path) = states_path() else {
+        return;
+    };
+    let content = match serde_json::to_vec_pretty(&*STATES.lock().unwrap()) {
+        Ok(content) => content,
+        Err(_) => return,
+    };
+    if let Err(err) = write_atomic(&path, &content) {
+        tracing::warn!(error = %err, "保存窗口状态失败");
+    }
+}
+
+// 连续的移动与缩放只写入最后一次
+fn schedule_save() {
+    let generation = SAVE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SAVE_DELAY).await;
+        if SAVE_GENERATION.load(Ordering::SeqCst) == generation {
+            save_states();
+        }
+    });
+}
+
+// 各类窗口对应的前端路由
+fn route(kind: &str) -> Result<&'static str, String> {
+    match kind {
+        "chat" => Ok("/chat"),
+        "editor" => Ok("/plugin-editor"),
+        _ => Err(format!("未知的窗口类型: {}", kind)),
+    }
+}
+
+// 窗口标签只能包含字母、数字与 - / : _
+fn window_label(kind: &str, context_id: &str) -> String {
+    let id: String = context_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}-{}", kind, id)
+}
+
+fn update_state(label: &str, f: impl FnOnce(&mut WindowState)) {
+    let mut states = STATES.lock().unwrap();
+    if let Some(state) = states.iter_mut().find(|s| s.label == label) {
+        f(state);
+    }
+}
+
+fn track(window: &WebviewWindow) {
+    let handle = window.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::Moved(position) => {
+            let scale = handle.scale_factor().unwrap_or(1.0);
+            let position = position.to_logical::<f64>(scale);
+            update_state(handle.label(), |state| {
+                state.x = Some(position.x);
+                state.y = Some(position.y);
+            });
+            schedule_save();
+        }
+        WindowEvent::Resized(size) => {
+            let scale = handle.scale_factor().unwrap_or(1.0);
+            let size = size.to_logical::<f64>(scale);
+            if size.width > 0.0 && size.height > 0.0 {
+                update_state(handle.label(), |state| {
+                    state.width = size.width;
+                    state.height = size.height;
+                });
+                schedule_save();
+            }
+        }
+        // 用户关闭的窗口不再恢复, 但保留位置与大小
+        WindowEvent::CloseRequested { .. } => {
+            update_state(handle.label(), |state| state.open = false);
+            save_states();
+        }
+        _ => {}
+    });
+}
+
+fn build(app: &AppHandle, state: &WindowState) -> Result<WebviewWindow, String> {
+    let url = format!(
+        "{}?id={}",
+        route(&state.kind)?,
+        urlencoding::encode(&state.context_id)
+    );
+    let mut builder = WebviewWindowBuilder::new(app, &state.label, WebviewUrl::App(url.into()))
+        .title("ghostie")
+        .inner_size(state.width, state.height)
+        .min_inner_size(400.0, 300.0)
+        .decorations(false);
+    builder = match (state.x, state.y) {
+        (Some(x), Some(y)) => builder.position(x, y),
+        _ => builder.center(),
+    };
+    let window = builder
+        .build()
+        .map_err(|e| format!("Failed to create window: {}", e))?;
+    track(&window);
+    Ok(window)
+}
+
+/// 在独立窗口中打开对话或插件编辑器, 已打开时切换到该窗口
+///
+/// # 参数
+/// * `kind` - chat 或 editor
+/// * `context_id` - 对话 id 或插件 id
+#[tauri::command]
+pub async fn window_open(
+    app: AppHandle,
+    kind: String,
+    context_id: String,
+) -> Result<WindowState, String> {
+    route(&kind)?;
+    let label = window_label(&kind, &context_id);
+    let state = {
+        let mut states = STATES.lock().unwrap();
+        let index = match states.iter().position(|s| s.label == label) {
+            Some(index) => index,
+            None => {
+                states.push(WindowState {
+                    label: label.clone(),
+                    kind,
+                    context_id,
+                    x: None,
+                    y: None,
+                    width: DEFAULT_WIDTH,
+                    height: DEFAULT_HEIGHT,
+                    open: false,
+                });
+                states.len() - 1
+            }
+        };
+        // 最近打开的排在最后, 超出上限时删除最早关闭的
+        let mut state = states.remove(index);
+        state.open = true;
+        states.push(state.clone());
+        while states.len() > MAX_WINDOW_STATES {
+            match states.iter().position(|s| !s.open) {
+                Some(index) => states.remove(index),
+                None => break,
+            };
+        }
+        state
+    };
+    save_states();
+
+    let window = match app.get_webview_window(&label) {
+        Some(window) => window,
+        None => build(&app, &state)?,
+    };
+    let _ = window.show();
+    window
+        .set_focus()
+        .map_err(|e| format!("Failed to focus window: {}", e))?;
+    Ok(state)
+}
+
+/// 列出保存的独立窗口状态
+#[tauri::command]
+pub async fn window_states() -> Result<Vec<WindowState>, String> {
+    Ok(STATES.lock().unwrap().clone())
+}
+
+/// 恢复上次退出时打开的独立窗口
+pub fn restore_windows(app: &AppHandle) {
+    let states: Vec<WindowState> = STATES
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|s| s.open)
+        .cloned()
+        .collect();
+    for state in states {
+        if let Err(err) = build(app, &state) {
+            tracing::warn!(window = %state.label, error = %err, "恢复窗口失败");
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use super::file::{get_config_dir, write_atomic};
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use tauri::AppHandle;
+
+/// 默认工作区, 数据直接保存在配置目录下, 与旧版本目录结构一致
+pub const DEFAULT_WORKSPACE: &str = "default";
+const MAX_NAME_LEN: usize = 64;
+
+/// 工作区信息
+#[derive(Debug, Serialize, Clone)]
+pub struct WorkspaceInfo {
+    pub name: String,
+    pub path: String,
+    pub active: bool,
+    pub created_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct WorkspaceState {
+    active: Option<String>,
+    #[serde(default)]
+    created: Vec<(String, i64)>,
+}
+
+// 当前工作区名称, 启动时从 workspaces.json 读取
+static ACTIVE: Lazy<RwLock<String>> = Lazy::new(|| {
+    let name = load_state()
+        .active
+        .filter(|name| validate(name).is_ok() && root_of(name).is_some_and(|dir| dir.exists()))
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string());
+    RwLock::new(name)
+});
+
+fn state_path() -> Option<PathBuf> {
+    Some(get_config_dir()?.join("workspaces.json"))
+}
+
+fn load_state() -> WorkspaceState {
+    state_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &WorkspaceState) -> Result<(), String> {
+    let path = state_path().ok_or("无法获取配置目录")?;
+    let content =
+        serde_json::to_string_pretty(state).map_err(|e| format!("序列化工作区失败: {}", e))?;
+    write_atomic(&path, content.as_bytes()).map_err(|e| format!("保存工作区失败: {}", e))
+}
+
+fn validate(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(format!("工作区名称长度需在 1 到 {} 之间", MAX_NAME_LEN));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("工作区名称只能包含字母、数字、- 和 _".to_string());
+    }
+    Ok(())
+}
+
+fn root_of(name: &str) -> Option<PathBuf> {
+    let config_dir = get_config_dir()?;
+    if name == DEFAULT_WORKSPACE {
+        Some(config_dir)
+    } else {
+        Some(config_dir.join("workspaces").join(name))
+    }
+}
+
+/// 当前工作区名称
+pub fn current() -> String {
+    ACTIVE.read().unwrap().clone()
+}
+
+/// 当前工作区的根目录, 保存设置、同步状态与知识库
+pub fn dir() -> Option<PathBuf> {
+    root_of(&current())
+}
+
+/// 当前工作区的插件目录, 保存数据库、插件与插件数据
+pub fn plugins_dir() -> PathBuf {
+    let dir = dir().expect("无法获取配置目录").join("plugins");
+    if !dir.exists() {
+        fs::create_dir_all(&dir).expect("无法创建插件目录");
+    }
+    dir
+}
+
+/// 创建工作区, 新工作区不包含任何插件、环境变量、历史或设置
+///
+/// # 参数
+/// * `name` - 工作区名称, 只能包含字母、数字、- 和 _
+#[tauri::command]
+pub async fn workspace_create(name: String) -> Result<WorkspaceInfo, String> {
+    validate(&name)?;
+    let root = root_of(&name).ok_or("无法获取配置目录")?;
+    if name == DEFAULT_WORKSPACE || root.exists() {
+        return Err(format!("工作区已存在: {}", name));
+    }
+    fs::create_dir_all(root.join("plugins")).map_err(|e| format!("创建工作区失败: {}", e))?;
+
+    let created_at = Utc::now().timestamp_millis();
+    let mut state = load_state();
+    state.created.retain(|(existing, _)| existing != &name);
+    state.created.push((name.clone(), created_at));
+    save_state(&state)?;
+
+    Ok(WorkspaceInfo {
+        path: root.to_string_lossy().to_string(),
+        active: false,
+        created_at: Some(created_at),
+        name,
+    })
+}
+
+/// 列出所有工作区, 默认工作区排在最前
+#[tauri::command]
+pub async fn workspace_list() -> Result<Vec<WorkspaceInfo>, String> {
+    let config_dir = get_config_dir().ok_or("无法获取配置目录")?;
+    let state = load_state();
+    let active = current();
+    let created_at = |name: &str| {
+        state
+            .created
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, at)| *at)
+    };
+
+    let mut names = Vec::new();
+    if let Ok(entries) = fs::read_dir(config_dir.join("workspaces")) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.path().is_dir() && validate(&name).is_ok() && name != DEFAULT_WORKSPACE {
+                names.push(name);
+            }
+        }
+    }
+    names.sort();
+    names.insert(0, DEFAULT_WORKSPACE.to_string());
+
+    Ok(names
+        .into_iter()
+        .filter_map(|name| {
+            Some(WorkspaceInfo {
+                path: root_of(&name)?.to_string_lossy().to_string(),
+                active: name == active,
+                created_at: created_at(&name),
+                name,
+            })
+        })
+        .collect())
+}
+
+/// 切换工作区并重启应用, 后台服务、定时任务与数据库连接都会在新工作区中重新启动
+///
+/// # 参数
+/// * `name` - 工作区名称, default 为默认工作区
+#[tauri::command]
+pub async fn workspace_switch(app: AppHandle, name: String) -> Result<(), String> {
+    validate(&name)?;
+    let root = root_of(&name).ok_or("无法获取配置目录")?;
+    if !root.exists() {
+        return Err(format!("工作区不存在: {}", name));
+    }
+    if name == current() {
+        return Ok(());
+    }
+
+    let mut state = load_state();
+    state.active = Some(name.clone());
+    save_state(&state)?;
+    *ACTIVE.write().unwrap() = name;
+    app.restart();
+}
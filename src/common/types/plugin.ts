@@ -61,10 +61,78 @@ export interface PluginProps {
   name: string;
   /* 插件描述 */
   description: string;
+  /* 各语言的名称与描述, 键为语言代码 */
+  translations?: Record<string, { name?: string; description?: string }>;
+  /* 用户修改的显示信息, 优先于脚本声明的名称与描述 */
+  overrides?: { name?: string; description?: string; icon?: string };
   /* 插件版本 */
   version: string;
   /* 插件作者 */
   author?: string;
+  /* 是否启用, 禁用的插件不能执行 */
+  enabled: boolean;
+  /* 插件声明需要的全局环境变量 */
+  env?: string[];
+  /* 标签 */
+  tags: string[];
+  /* 分类 */
+  category?: string;
+  /* 用户对 Deno 权限请求的决定 */
+  grants?: { kind: string; target?: string; allowed: boolean; time: number }[];
+  /* 源码中使用的可执行命令或删除文件的接口, 非空时执行需要确认 */
+  dangerous_apis?: string[];
+  /* 安装来源, MCP 服务添加的插件为 { type: "mcp", transport } */
+  source?: { type: string; transport?: string; url?: string; repo?: string };
   /* 工具列表 */
   tools: ToolProps[];
 }
+
+/**
+ * 工具生成的文件
+ */
+export interface Artifact {
+  /* 相对产物目录的文件名 */
+  name: string;
+  /* 文件的绝对路径 */
+  path: string;
+  /* MIME 类型 */
+  mime: string;
+  /* 文件大小 (字节) */
+  size: number;
+}
+
+/**
+ * 工具执行结果
+ */
+export interface ExecutionResult {
+  /* 执行id, 对应产物目录 */
+  execution_id: string;
+  /* 工具返回值 */
+  result: unknown;
+  /* 实际尝试次数 */
+  attempts: number;
+  /* 执行耗时 (毫秒) */
+  duration_ms: number;
+  /* 是否来自缓存 */
+  cached: boolean;
+  /* 工具生成的文件 */
+  artifacts: Artifact[];
+  /* 仅生成执行计划, 此时 result 为执行计划 */
+  dry_run?: boolean;
+}
+
+/**
+ * 目录插件中的文件
+ */
+export interface PluginFile {
+  /* 文件名 */
+  name: string;
+  /* 相对插件目录的路径 */
+  path: string;
+  /* 是否为目录 */
+  is_dir: boolean;
+  /* 文件大小 (字节) */
+  size: number;
+  /* 子文件, 仅目录存在 */
+  children?: PluginFile[];
+}
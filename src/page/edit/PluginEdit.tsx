@@ -1,4 +1,4 @@
-import { PluginProps, ToolProperty } from "@/common/types/plugin";
+import { ExecutionResult, PluginProps, ToolProperty } from "@/common/types/plugin";
 import { Header } from "@/components/custom/Header";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
@@ -167,7 +167,17 @@ export function PluginEdit() {
                 // 如果是创建新插件，使用 plugin_import
                 const result = await cmd.invoke<PluginProps>("plugin_import", {
                     content: content
+                }).catch(async (err) => {
+                    // 未通过签名校验时, 询问是否仍然保存
+                    if (!(err as { Unsigned?: unknown })?.Unsigned) throw err;
+                    const allow = await cmd.confirm("插件未签名或签名无效, 是否仍然保存?");
+                    if (!allow) return undefined;
+                    return cmd.invoke<PluginProps>("plugin_import", {
+                        content: content,
+                        allowUnsigned: true
+                    });
                 });
+                if (!result) return;
                 PluginsStore.set({
                     [result.id]: result
                 });
@@ -206,12 +216,17 @@ export function PluginEdit() {
             });
 
             // 直接使用当前编辑的脚本和依赖进行测试
-            const result = await cmd.invoke("plugin_execute", {
+            const { result, attempts } = await cmd.invoke<ExecutionResult>("plugin_execute", {
                 id: plugin.id,
                 tool: tool,
                 args: testArgs,
+            }).catch((err) => {
+                // 测试由用户手动发起, 需要确认的工具直接确认执行
+                const pending = (err as { ApprovalRequired?: { approval_id: string } })?.ApprovalRequired;
+                if (!pending) throw err;
+                return cmd.invoke<ExecutionResult>("execution_approve", { id: pending.approval_id });
             });
-            console.log(result);
+            console.log(result, attempts);
             cmd.message(JSON.stringify(result).slice(0, 200), "测试成功");
         } catch (error) {
             console.log(error);
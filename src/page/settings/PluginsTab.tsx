@@ -36,6 +36,20 @@ export function PluginsTab() {
         loadPlugins();
     }, [loadPlugins]);
 
+    // 插件被外部修改或通过链接安装后刷新
+    useEffect(() => {
+        const unlisteners = [
+            cmd.listen("plugins://changed", loadPlugins),
+            cmd.listen("plugins://installed", loadPlugins),
+            cmd.listen("plugins://install-failed", (event) => {
+                cmd.message(`安装插件失败: ${event.payload}`, "error");
+            }),
+        ];
+        return () => {
+            unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()));
+        };
+    }, [loadPlugins]);
+
     const handleRemovePlugin = async (id: string) => {
         try {
             const confirm = await cmd.confirm("确定删除插件吗？");
@@ -61,10 +75,29 @@ export function PluginsTab() {
 
             if (!result?.content) return;
 
-            const pluginInfo = await cmd.invoke<PluginProps>("plugin_import", {
-                content: result.content.trim()
-            });
+            const content = result.content.trim();
+            let pluginInfo: PluginProps | undefined;
+            try {
+                pluginInfo = await cmd.invoke<PluginProps>("plugin_import", { content }).catch(async (err) => {
+                    // 未通过签名校验时, 询问是否仍然安装
+                    if (!(err as { Unsigned?: unknown })?.Unsigned) throw err;
+                    const allow = await cmd.confirm("插件未签名或签名无效, 是否仍然安装?");
+                    if (!allow) return undefined;
+                    return cmd.invoke<PluginProps>("plugin_import", { content, allowUnsigned: true });
+                });
+            } catch (err) {
+                // 已安装相同内容的插件时, 询问是否更新已有插件
+                const duplicate = (err as { Duplicate?: { id: string; name: string } })?.Duplicate;
+                if (!duplicate) throw err;
+                const update = await cmd.confirm(`已安装相同的插件 ${duplicate.name}, 是否更新该插件?`);
+                if (!update) return;
+                pluginInfo = await cmd.invoke<PluginProps>("plugin_update", {
+                    id: duplicate.id,
+                    content
+                });
+            }
 
+            if (!pluginInfo) return;
             await loadPlugins();
             cmd.message(`成功导入插件: ${pluginInfo.name}`, "success");
         } catch (err) {
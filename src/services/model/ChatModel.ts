@@ -1,7 +1,7 @@
 /** Chat模型
  * 该模型依赖于工具模块。
  */
-import { ToolProps } from "@/common/types/plugin";
+import { ExecutionResult, ToolProps } from "@/common/types/plugin";
 import { gen } from "@/utils/generator";
 import { cmd } from "@/utils/shell";
 import { ModelInfo } from "@common/types/agent";
@@ -119,11 +119,27 @@ export class ChatModel {
     const toolArgs = JSON.parse(tool_call.function.arguments || "{}");
     try {
       /** 执行工具 */
-      const toolResultPromise = cmd.invoke("plugin_execute", {
-        id: tool_call.function.name.split(TOOL_NAME_SPLIT)[1],
-        tool: tool_call.function.name.split(TOOL_NAME_SPLIT)[0],
-        args: toolArgs,
-      });
+      const toolResultPromise = cmd
+        .invoke<ExecutionResult>("plugin_execute", {
+          id: tool_call.function.name.split(TOOL_NAME_SPLIT)[1],
+          tool: tool_call.function.name.split(TOOL_NAME_SPLIT)[0],
+          args: toolArgs,
+        })
+        .catch(async (err) => {
+          // 危险工具需要用户确认后才会执行
+          const pending = (
+            err as { ApprovalRequired?: { approval_id: string; plugin_id: string; tool: string } }
+          )?.ApprovalRequired;
+          if (!pending) throw err;
+          const approved = await cmd.confirm(
+            `助手请求执行 ${pending.plugin_id}/${pending.tool}, 该工具可能删除文件或执行命令, 是否允许?`
+          );
+          if (!approved) {
+            await cmd.invoke("execution_reject", { id: pending.approval_id });
+            throw new Error("用户拒绝执行该工具");
+          }
+          return cmd.invoke<ExecutionResult>("execution_approve", { id: pending.approval_id });
+        });
 
       /* 等待工具执行完成 */
       const toolResult = await toolResultPromise;
@@ -134,7 +150,7 @@ export class ChatModel {
       return {
         name: tool_call.function.name,
         arguments: toolArgs,
-        result: toolResult,
+        result: toolResult.result,
       };
     } catch (error) {
       return {